@@ -14,3 +14,28 @@ impl From<&wysiwyg::ActionState> for ActionState {
         }
     }
 }
+
+#[derive(Debug, PartialEq, Eq, uniffi::Enum)]
+pub enum DisabledReason {
+    HistoryEmpty,
+    CannotIndent,
+    CannotUnindent,
+    ImmutableLink,
+    InInlineCode,
+    InCodeBlock,
+    NotApplicable,
+}
+
+impl From<&wysiwyg::DisabledReason> for DisabledReason {
+    fn from(inner: &wysiwyg::DisabledReason) -> Self {
+        match inner {
+            wysiwyg::DisabledReason::HistoryEmpty => Self::HistoryEmpty,
+            wysiwyg::DisabledReason::CannotIndent => Self::CannotIndent,
+            wysiwyg::DisabledReason::CannotUnindent => Self::CannotUnindent,
+            wysiwyg::DisabledReason::ImmutableLink => Self::ImmutableLink,
+            wysiwyg::DisabledReason::InInlineCode => Self::InInlineCode,
+            wysiwyg::DisabledReason::InCodeBlock => Self::InCodeBlock,
+            wysiwyg::DisabledReason::NotApplicable => Self::NotApplicable,
+        }
+    }
+}
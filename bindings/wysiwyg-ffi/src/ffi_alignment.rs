@@ -0,0 +1,58 @@
+// Copyright 2023 The Matrix.org Foundation C.I.C.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, uniffi::Enum)]
+pub enum Alignment {
+    Left,
+    Center,
+    Right,
+    Justify,
+}
+
+impl From<Alignment> for wysiwyg::Alignment {
+    fn from(alignment: Alignment) -> Self {
+        match alignment {
+            Alignment::Left => Self::Left,
+            Alignment::Center => Self::Center,
+            Alignment::Right => Self::Right,
+            Alignment::Justify => Self::Justify,
+        }
+    }
+}
+
+impl From<wysiwyg::Alignment> for Alignment {
+    fn from(alignment: wysiwyg::Alignment) -> Self {
+        match alignment {
+            wysiwyg::Alignment::Left => Self::Left,
+            wysiwyg::Alignment::Center => Self::Center,
+            wysiwyg::Alignment::Right => Self::Right,
+            wysiwyg::Alignment::Justify => Self::Justify,
+        }
+    }
+}
+
+#[derive(Debug, PartialEq, Eq, uniffi::Enum)]
+pub enum AlignmentAttribute {
+    Style,
+    Data,
+}
+
+impl From<AlignmentAttribute> for wysiwyg::AlignmentAttribute {
+    fn from(attribute: AlignmentAttribute) -> Self {
+        match attribute {
+            AlignmentAttribute::Style => Self::Style,
+            AlignmentAttribute::Data => Self::Data,
+        }
+    }
+}
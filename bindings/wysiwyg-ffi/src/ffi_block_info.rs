@@ -0,0 +1,34 @@
+// Copyright 2023 The Matrix.org Foundation C.I.C.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use widestring::Utf16String;
+
+#[derive(Debug, PartialEq, Eq, uniffi::Record)]
+pub struct BlockInfo {
+    pub start: u32,
+    pub end: u32,
+    pub html: String,
+    pub revision: u64,
+}
+
+impl From<wysiwyg::BlockInfo<Utf16String>> for BlockInfo {
+    fn from(inner: wysiwyg::BlockInfo<Utf16String>) -> Self {
+        Self {
+            start: u32::try_from(inner.start).unwrap(),
+            end: u32::try_from(inner.end).unwrap(),
+            html: inner.html.to_string(),
+            revision: inner.revision,
+        }
+    }
+}
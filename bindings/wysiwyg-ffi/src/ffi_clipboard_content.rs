@@ -0,0 +1,40 @@
+// Copyright 2023 The Matrix.org Foundation C.I.C.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use std::sync::Arc;
+
+use widestring::Utf16String;
+
+use crate::ffi_composer_update::ComposerUpdate;
+
+#[derive(Debug, PartialEq, Eq, uniffi::Record)]
+pub struct ClipboardContent {
+    pub html: String,
+    pub plain_text: String,
+}
+
+impl From<wysiwyg::ClipboardContent<Utf16String>> for ClipboardContent {
+    fn from(inner: wysiwyg::ClipboardContent<Utf16String>) -> Self {
+        Self {
+            html: inner.html.to_string(),
+            plain_text: inner.plain_text.to_string(),
+        }
+    }
+}
+
+#[derive(uniffi::Record)]
+pub struct CutResult {
+    pub content: ClipboardContent,
+    pub update: Arc<ComposerUpdate>,
+}
@@ -1,4 +1,4 @@
-// Copyright 2022 The Matrix.org Foundation C.I.C.
+// Copyright 2023 The Matrix.org Foundation C.I.C.
 //
 // Licensed under the Apache License, Version 2.0 (the "License");
 // you may not use this file except in compliance with the License.
@@ -12,10 +12,10 @@
 // See the License for the specific language governing permissions and
 // limitations under the License.
 
-use crate::dom::range::DomLocation;
-
-#[derive(Clone, Debug, PartialEq, Eq)]
-pub enum FindResult {
-    Found(Vec<DomLocation>),
-    NotFound,
+/// A command to run with `ComposerModel::execute_batch`, with its arguments
+/// as a JSON object.
+#[derive(Debug, PartialEq, Eq, uniffi::Record)]
+pub struct Command {
+    pub name: String,
+    pub args: String,
 }
@@ -0,0 +1,54 @@
+// Copyright 2023 The Matrix.org Foundation C.I.C.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use std::{error::Error, fmt::Display};
+
+#[derive(Debug, uniffi::Error)]
+pub enum CommandError {
+    UnknownCommand { name: String },
+    InvalidArguments { message: String },
+    Failed { message: String },
+}
+
+impl Display for CommandError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            CommandError::UnknownCommand { name } => {
+                write!(f, "unknown command: {name}")
+            }
+            CommandError::InvalidArguments { message } => {
+                write!(f, "invalid arguments: {message}")
+            }
+            CommandError::Failed { message } => {
+                write!(f, "command failed: {message}")
+            }
+        }
+    }
+}
+
+impl From<wysiwyg::CommandError> for CommandError {
+    fn from(error: wysiwyg::CommandError) -> Self {
+        match error {
+            wysiwyg::CommandError::UnknownCommand(name) => {
+                Self::UnknownCommand { name }
+            }
+            wysiwyg::CommandError::InvalidArguments(message) => {
+                Self::InvalidArguments { message }
+            }
+            wysiwyg::CommandError::Failed(message) => Self::Failed { message },
+        }
+    }
+}
+
+impl Error for CommandError {}
@@ -6,6 +6,8 @@ pub enum ComposerAction {
     Underline,
     InlineCode,
     Link,
+    CreateLinkWithText,
+    EditLink,
     Undo,
     Redo,
     OrderedList,
@@ -14,6 +16,12 @@ pub enum ComposerAction {
     Unindent,
     CodeBlock,
     Quote,
+    Heading,
+    Highlight,
+    AlignLeft,
+    AlignCenter,
+    AlignRight,
+    AlignJustify,
 }
 
 impl From<&ComposerAction> for wysiwyg::ComposerAction {
@@ -25,6 +33,8 @@ impl From<&ComposerAction> for wysiwyg::ComposerAction {
             ComposerAction::Underline => Self::Underline,
             ComposerAction::InlineCode => Self::InlineCode,
             ComposerAction::Link => Self::Link,
+            ComposerAction::CreateLinkWithText => Self::CreateLinkWithText,
+            ComposerAction::EditLink => Self::EditLink,
             ComposerAction::Undo => Self::Undo,
             ComposerAction::Redo => Self::Redo,
             ComposerAction::OrderedList => Self::OrderedList,
@@ -33,6 +43,12 @@ impl From<&ComposerAction> for wysiwyg::ComposerAction {
             ComposerAction::Unindent => Self::Unindent,
             ComposerAction::CodeBlock => Self::CodeBlock,
             ComposerAction::Quote => Self::Quote,
+            ComposerAction::Heading => Self::Heading,
+            ComposerAction::Highlight => Self::Highlight,
+            ComposerAction::AlignLeft => Self::AlignLeft,
+            ComposerAction::AlignCenter => Self::AlignCenter,
+            ComposerAction::AlignRight => Self::AlignRight,
+            ComposerAction::AlignJustify => Self::AlignJustify,
         }
     }
 }
@@ -46,6 +62,10 @@ impl From<&wysiwyg::ComposerAction> for ComposerAction {
             wysiwyg::ComposerAction::Underline => Self::Underline,
             wysiwyg::ComposerAction::InlineCode => Self::InlineCode,
             wysiwyg::ComposerAction::Link => Self::Link,
+            wysiwyg::ComposerAction::CreateLinkWithText => {
+                Self::CreateLinkWithText
+            }
+            wysiwyg::ComposerAction::EditLink => Self::EditLink,
             wysiwyg::ComposerAction::Undo => Self::Undo,
             wysiwyg::ComposerAction::Redo => Self::Redo,
             wysiwyg::ComposerAction::OrderedList => Self::OrderedList,
@@ -54,6 +74,12 @@ impl From<&wysiwyg::ComposerAction> for ComposerAction {
             wysiwyg::ComposerAction::Unindent => Self::Unindent,
             wysiwyg::ComposerAction::CodeBlock => Self::CodeBlock,
             wysiwyg::ComposerAction::Quote => Self::Quote,
+            wysiwyg::ComposerAction::Heading => Self::Heading,
+            wysiwyg::ComposerAction::Highlight => Self::Highlight,
+            wysiwyg::ComposerAction::AlignLeft => Self::AlignLeft,
+            wysiwyg::ComposerAction::AlignCenter => Self::AlignCenter,
+            wysiwyg::ComposerAction::AlignRight => Self::AlignRight,
+            wysiwyg::ComposerAction::AlignJustify => Self::AlignJustify,
         }
     }
 }
@@ -1,16 +1,25 @@
 use std::collections::HashMap;
-use std::sync::{Arc, Mutex};
+use std::ops::{Deref, DerefMut};
+use std::sync::{Arc, Mutex, MutexGuard};
 use std::vec;
 
 use widestring::Utf16String;
 
+use crate::ffi_command_error::CommandError;
 use crate::ffi_composer_state::ComposerState;
 use crate::ffi_composer_update::ComposerUpdate;
 use crate::ffi_dom_creation_error::DomCreationError;
+use crate::ffi_error_listener::{ErrorListener, FfiErrorListener};
 use crate::ffi_link_actions::LinkAction;
 use crate::ffi_mentions_state::MentionsState;
+use crate::ffi_metrics_listener::{FfiMetricsListener, MetricsListener};
 use crate::into_ffi::IntoFfi;
-use crate::{ActionState, ComposerAction, SuggestionPattern};
+use crate::{
+    ActionState, Alignment, AlignmentAttribute, BlockInfo, ClipboardContent,
+    Command, ComposerAction, CutResult, Direction, DisabledReason, Granularity,
+    InlineFormatType, OutlineEntry, PatternKey, QuoteAttribution,
+    SelectionStats, SuggestionPattern, TextRange,
+};
 
 #[derive(Default, uniffi::Object)]
 pub struct ComposerModel {
@@ -23,6 +32,53 @@ impl ComposerModel {
             inner: Mutex::new(wysiwyg::ComposerModel::new()),
         }
     }
+
+    /// Lock the model for a call, which is timed as `operation` for the
+    /// metrics listener until the returned guard is dropped.
+    fn lock_measured(&self, operation: &'static str) -> MeasuredModel<'_> {
+        let model = self.inner.lock().unwrap();
+        MeasuredModel {
+            timer: Some(model.measure(operation)),
+            model: Some(model),
+        }
+    }
+}
+
+/// The locked model, along with the timer for the call being made on it.
+/// When dropped, it stops the timer and unlocks the model before telling
+/// the listeners about the call and any errors it recovered from, so that
+/// the listeners can call the model too.
+struct MeasuredModel<'a> {
+    timer: Option<wysiwyg::OperationTimer>,
+    model: Option<MutexGuard<'a, wysiwyg::ComposerModel<Utf16String>>>,
+}
+
+impl Deref for MeasuredModel<'_> {
+    type Target = wysiwyg::ComposerModel<Utf16String>;
+
+    fn deref(&self) -> &Self::Target {
+        self.model.as_deref().unwrap()
+    }
+}
+
+impl DerefMut for MeasuredModel<'_> {
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        self.model.as_deref_mut().unwrap()
+    }
+}
+
+impl Drop for MeasuredModel<'_> {
+    fn drop(&mut self) {
+        let metrics = self.timer.take().map(wysiwyg::OperationTimer::stop);
+        // Taking the guard out unlocks the model
+        let errors = self.model.take().map(|m| m.take_unreported_errors());
+        if let Some(errors) = errors {
+            errors.report();
+        }
+        if let Some(metrics) = metrics {
+            metrics.report();
+        }
+    }
 }
 
 #[uniffi::export]
@@ -31,8 +87,9 @@ impl ComposerModel {
         self: &Arc<Self>,
         html: String,
     ) -> Result<Arc<ComposerUpdate>, DomCreationError> {
+        let mut model = self.lock_measured("set_content_from_html");
         let html = Utf16String::from_str(&html);
-        let update = self.inner.lock().unwrap().set_content_from_html(&html)?;
+        let update = model.set_content_from_html(&html)?;
         Ok(Arc::new(ComposerUpdate::from(update)))
     }
 
@@ -40,63 +97,239 @@ impl ComposerModel {
         self: &Arc<Self>,
         markdown: String,
     ) -> Result<Arc<ComposerUpdate>, DomCreationError> {
+        let mut model = self.lock_measured("set_content_from_markdown");
         let markdown = Utf16String::from_str(&markdown);
-        let update = self
-            .inner
-            .lock()
-            .unwrap()
-            .set_content_from_markdown(&markdown)?;
+        let update = model.set_content_from_markdown(&markdown)?;
         Ok(Arc::new(ComposerUpdate::from(update)))
     }
 
+    /// Run a command by name, with its arguments as a JSON object. See
+    /// `get_commands_as_json` for the commands and their arguments.
+    pub fn execute(
+        self: &Arc<Self>,
+        name: String,
+        args: String,
+    ) -> Result<Arc<ComposerUpdate>, CommandError> {
+        let mut model = self.lock_measured("execute");
+        let update = model.execute(&name, &args)?;
+        Ok(Arc::new(ComposerUpdate::from(update)))
+    }
+
+    /// Run several commands one after the other, returning a single update
+    /// for all of them, e.g. to select a word and then bold it.
+    pub fn execute_batch(
+        self: &Arc<Self>,
+        commands: Vec<Command>,
+    ) -> Result<Arc<ComposerUpdate>, CommandError> {
+        let mut model = self.lock_measured("execute_batch");
+        let commands: Vec<(&str, &str)> = commands
+            .iter()
+            .map(|c| (c.name.as_str(), c.args.as_str()))
+            .collect();
+        let update = model.execute_batch(&commands)?;
+        Ok(Arc::new(ComposerUpdate::from(update)))
+    }
+
+    pub fn get_commands_as_json(self: &Arc<Self>) -> String {
+        let model = self.lock_measured("get_commands_as_json");
+        model.get_commands_as_json()
+    }
+
     pub fn set_custom_suggestion_patterns(
         self: &Arc<Self>,
         custom_suggestion_patterns: Vec<String>,
+    ) {
+        let mut model = self.lock_measured("set_custom_suggestion_patterns");
+        model.set_custom_suggestion_patterns(custom_suggestion_patterns)
+    }
+
+    /// Allow suggestions for the given keys to continue across whitespace,
+    /// e.g. "@john smith". Only the At, Hash and Slash keys are supported.
+    pub fn set_multi_word_suggestion_patterns(
+        self: &Arc<Self>,
+        keys: Vec<PatternKey>,
+    ) {
+        let mut model =
+            self.lock_measured("set_multi_word_suggestion_patterns");
+        model.set_multi_word_suggestion_patterns(
+            keys.into_iter().map(wysiwyg::PatternKey::from).collect(),
+        )
+    }
+
+    /// Stop or start reporting suggestions for the given key. All keys are
+    /// enabled by default.
+    pub fn set_suggestion_pattern_enabled(
+        self: &Arc<Self>,
+        key: PatternKey,
+        enabled: bool,
+    ) {
+        let mut model = self.lock_measured("set_suggestion_pattern_enabled");
+        model.set_suggestion_pattern_enabled(
+            wysiwyg::PatternKey::from(key),
+            enabled,
+        )
+    }
+
+    pub fn set_keep_suggestion_punctuation(self: &Arc<Self>, keep: bool) {
+        let mut model = self.lock_measured("set_keep_suggestion_punctuation");
+        model.set_keep_suggestion_punctuation(keep)
+    }
+
+    pub fn set_custom_inline_tags(
+        self: &Arc<Self>,
+        custom_inline_tags: Vec<String>,
+    ) {
+        let mut model = self.lock_measured("set_custom_inline_tags");
+        model.set_custom_inline_tags(custom_inline_tags)
+    }
+
+    pub fn set_locale(self: &Arc<Self>, tag: String) {
+        let mut model = self.lock_measured("set_locale");
+        model.set_locale(&tag)
+    }
+
+    /// Report the name and duration of each call on this model to
+    /// `listener`, e.g. to track latency.
+    pub fn set_metrics_listener(
+        self: &Arc<Self>,
+        listener: Box<dyn MetricsListener>,
     ) {
         self.inner
             .lock()
             .unwrap()
-            .set_custom_suggestion_patterns(custom_suggestion_patterns)
+            .set_metrics_listener(Some(Arc::new(FfiMetricsListener(listener))))
     }
 
-    pub fn get_content_as_html(self: &Arc<Self>) -> String {
-        self.inner.lock().unwrap().get_content_as_html().to_string()
+    /// Stop reporting calls to the listener set with `set_metrics_listener`.
+    pub fn remove_metrics_listener(self: &Arc<Self>) {
+        self.inner.lock().unwrap().set_metrics_listener(None)
     }
 
-    pub fn get_content_as_message_html(self: &Arc<Self>) -> String {
+    /// Report each internal error the model recovers from to `listener`.
+    /// The call that hit the error leaves the content as it was.
+    pub fn set_error_listener(
+        self: &Arc<Self>,
+        listener: Box<dyn ErrorListener>,
+    ) {
         self.inner
             .lock()
             .unwrap()
-            .get_content_as_message_html()
-            .to_string()
+            .set_error_listener(Some(Arc::new(FfiErrorListener(listener))))
+    }
+
+    /// Stop reporting errors to the listener set with `set_error_listener`.
+    pub fn remove_error_listener(self: &Arc<Self>) {
+        self.inner.lock().unwrap().set_error_listener(None)
+    }
+
+    /// Rebuild the content from its own HTML if it has become inconsistent
+    /// because of a bug, reporting the problem to the error listener.
+    pub fn recover(self: &Arc<Self>) -> Arc<ComposerUpdate> {
+        let mut model = self.lock_measured("recover");
+        Arc::new(ComposerUpdate::from(model.recover()))
+    }
+
+    /// Format a number, e.g. of an ordered list item, with the digits of
+    /// the locale set with `set_locale`.
+    pub fn format_number(self: &Arc<Self>, number: u32) -> String {
+        let model = self.lock_measured("format_number");
+        model
+            .locale()
+            .format_number(usize::try_from(number).unwrap())
+    }
+
+    /// The revision of the current state, as sent in the latest update.
+    pub fn revision(self: &Arc<Self>) -> u64 {
+        let model = self.lock_measured("revision");
+        model.revision()
+    }
+
+    pub fn get_content_as_html(self: &Arc<Self>) -> String {
+        let model = self.lock_measured("get_content_as_html");
+        model.get_content_as_html().to_string()
+    }
+
+    pub fn get_content_as_message_html(self: &Arc<Self>) -> String {
+        let model = self.lock_measured("get_content_as_message_html");
+        model.get_content_as_message_html().to_string()
     }
 
     pub fn get_content_as_markdown(self: &Arc<Self>) -> String {
-        self.inner
-            .lock()
-            .unwrap()
-            .get_content_as_markdown()
-            .to_string()
+        let model = self.lock_measured("get_content_as_markdown");
+        model.get_content_as_markdown().to_string()
     }
 
     pub fn get_content_as_message_markdown(self: &Arc<Self>) -> String {
-        self.inner
-            .lock()
-            .unwrap()
-            .get_content_as_message_markdown()
-            .to_string()
+        let model = self.lock_measured("get_content_as_message_markdown");
+        model.get_content_as_message_markdown().to_string()
+    }
+
+    pub fn get_selection_as_markdown(self: &Arc<Self>) -> String {
+        let model = self.lock_measured("get_selection_as_markdown");
+        model.get_selection_as_markdown().to_string()
+    }
+
+    pub fn get_selection_as_plain_text(self: &Arc<Self>) -> String {
+        let model = self.lock_measured("get_selection_as_plain_text");
+        model.get_selection_as_plain_text().to_string()
+    }
+
+    pub fn selection_stats(self: &Arc<Self>) -> SelectionStats {
+        let model = self.lock_measured("selection_stats");
+        SelectionStats::from(model.selection_stats())
+    }
+
+    pub fn get_blocks(self: &Arc<Self>) -> Vec<BlockInfo> {
+        let model = self.lock_measured("get_blocks");
+        model
+            .get_blocks()
+            .into_iter()
+            .map(BlockInfo::from)
+            .collect()
+    }
+
+    pub fn get_block(self: &Arc<Self>, index: u32) -> Option<BlockInfo> {
+        let model = self.lock_measured("get_block");
+        model
+            .get_block(usize::try_from(index).unwrap())
+            .map(BlockInfo::from)
+    }
+
+    pub fn get_block_revisions(self: &Arc<Self>) -> Vec<u64> {
+        let model = self.lock_measured("get_block_revisions");
+        model.get_block_revisions()
     }
 
     pub fn get_content_as_plain_text(self: &Arc<Self>) -> String {
-        self.inner
-            .lock()
-            .unwrap()
-            .get_content_as_plain_text()
+        let model = self.lock_measured("get_content_as_plain_text");
+        model.get_content_as_plain_text().to_string()
+    }
+
+    pub fn get_preview(self: &Arc<Self>, max_chars: u32) -> String {
+        let model = self.lock_measured("get_preview");
+        model
+            .get_preview(usize::try_from(max_chars).unwrap())
             .to_string()
     }
 
+    pub fn content_hash(self: &Arc<Self>) -> u64 {
+        let model = self.lock_measured("content_hash");
+        model.content_hash()
+    }
+
+    pub fn is_content_empty(self: &Arc<Self>) -> bool {
+        let model = self.lock_measured("is_content_empty");
+        model.is_content_empty()
+    }
+
+    pub fn get_content_as_fallback_text(self: &Arc<Self>) -> String {
+        let model = self.lock_measured("get_content_as_fallback_text");
+        model.get_content_as_fallback_text().to_string()
+    }
+
     pub fn clear(self: &Arc<Self>) -> Arc<ComposerUpdate> {
-        Arc::new(ComposerUpdate::from(self.inner.lock().unwrap().clear()))
+        let mut model = self.lock_measured("clear");
+        Arc::new(ComposerUpdate::from(model.clear()))
     }
 
     pub fn select(
@@ -104,6 +337,57 @@ impl ComposerModel {
         start_utf16_codeunit: u32,
         end_utf16_codeunit: u32,
     ) -> Arc<ComposerUpdate> {
+        let mut model = self.lock_measured("select");
+        let start = wysiwyg::Location::from(
+            usize::try_from(start_utf16_codeunit).unwrap(),
+        );
+        let end = wysiwyg::Location::from(
+            usize::try_from(end_utf16_codeunit).unwrap(),
+        );
+
+        Arc::new(ComposerUpdate::from(model.select(start, end)))
+    }
+
+    pub fn move_cursor(
+        self: &Arc<Self>,
+        direction: Direction,
+        granularity: Granularity,
+    ) -> Arc<ComposerUpdate> {
+        let mut model = self.lock_measured("move_cursor");
+        Arc::new(ComposerUpdate::from(
+            model.move_cursor(direction.into(), granularity.into()),
+        ))
+    }
+
+    pub fn line_start_offset(self: &Arc<Self>, utf16_codeunit: u32) -> u32 {
+        let model = self.lock_measured("line_start_offset");
+        let position =
+            wysiwyg::Location::from(usize::try_from(utf16_codeunit).unwrap());
+        let start: usize = model.line_start_offset(position).into();
+        u32::try_from(start).unwrap()
+    }
+
+    pub fn line_end_offset(self: &Arc<Self>, utf16_codeunit: u32) -> u32 {
+        let model = self.lock_measured("line_end_offset");
+        let position =
+            wysiwyg::Location::from(usize::try_from(utf16_codeunit).unwrap());
+        let end: usize = model.line_end_offset(position).into();
+        u32::try_from(end).unwrap()
+    }
+
+    pub fn word_range_at(self: &Arc<Self>, utf16_codeunit: u32) -> TextRange {
+        let model = self.lock_measured("word_range_at");
+        let offset =
+            wysiwyg::Location::from(usize::try_from(utf16_codeunit).unwrap());
+        TextRange::from(model.word_range_at(offset))
+    }
+
+    pub fn get_clipboard_content(
+        self: &Arc<Self>,
+        start_utf16_codeunit: u32,
+        end_utf16_codeunit: u32,
+    ) -> ClipboardContent {
+        let model = self.lock_measured("get_clipboard_content");
         let start = wysiwyg::Location::from(
             usize::try_from(start_utf16_codeunit).unwrap(),
         );
@@ -111,20 +395,69 @@ impl ComposerModel {
             usize::try_from(end_utf16_codeunit).unwrap(),
         );
 
+        model.get_clipboard_content(start, end).into()
+    }
+
+    pub fn move_range(
+        self: &Arc<Self>,
+        src_start_utf16_codeunit: u32,
+        src_end_utf16_codeunit: u32,
+        dest_utf16_codeunit: u32,
+    ) -> Arc<ComposerUpdate> {
+        let mut model = self.lock_measured("move_range");
+        let src_start = wysiwyg::Location::from(
+            usize::try_from(src_start_utf16_codeunit).unwrap(),
+        );
+        let src_end = wysiwyg::Location::from(
+            usize::try_from(src_end_utf16_codeunit).unwrap(),
+        );
+        let dest = wysiwyg::Location::from(
+            usize::try_from(dest_utf16_codeunit).unwrap(),
+        );
+
         Arc::new(ComposerUpdate::from(
-            self.inner.lock().unwrap().select(start, end),
+            model.move_range(src_start, src_end, dest),
         ))
     }
 
+    pub fn cut_selection(self: &Arc<Self>) -> CutResult {
+        let mut model = self.lock_measured("cut_selection");
+        let (content, update) = model.cut_selection();
+        CutResult {
+            content: content.into(),
+            update: Arc::new(ComposerUpdate::from(update)),
+        }
+    }
+
     pub fn replace_text(
         self: &Arc<Self>,
         new_text: String,
     ) -> Arc<ComposerUpdate> {
+        let mut model = self.lock_measured("replace_text");
         Arc::new(ComposerUpdate::from(
-            self.inner
-                .lock()
-                .unwrap()
-                .replace_text(Utf16String::from_str(&new_text)),
+            model.replace_text(Utf16String::from_str(&new_text)),
+        ))
+    }
+
+    pub fn insert_emoji(
+        self: &Arc<Self>,
+        emoji: String,
+    ) -> Arc<ComposerUpdate> {
+        let mut model = self.lock_measured("insert_emoji");
+        Arc::new(ComposerUpdate::from(
+            model.insert_emoji(Utf16String::from_str(&emoji)),
+        ))
+    }
+
+    pub fn insert_text_without_formatting(
+        self: &Arc<Self>,
+        content: String,
+    ) -> Arc<ComposerUpdate> {
+        let mut model = self.lock_measured("insert_text_without_formatting");
+        Arc::new(ComposerUpdate::from(
+            model.insert_text_without_formatting(Utf16String::from_str(
+                &content,
+            )),
         ))
     }
 
@@ -134,15 +467,14 @@ impl ComposerModel {
         start: u32,
         end: u32,
     ) -> Arc<ComposerUpdate> {
+        let mut model = self.lock_measured("replace_text_in");
         let start = usize::try_from(start).unwrap();
         let end = usize::try_from(end).unwrap();
-        Arc::new(ComposerUpdate::from(
-            self.inner.lock().unwrap().replace_text_in(
-                Utf16String::from_str(&new_text),
-                start,
-                end,
-            ),
-        ))
+        Arc::new(ComposerUpdate::from(model.replace_text_in(
+            Utf16String::from_str(&new_text),
+            start,
+            end,
+        )))
     }
 
     pub fn replace_text_suggestion(
@@ -151,21 +483,22 @@ impl ComposerModel {
         suggestion: SuggestionPattern,
         append_space: bool,
     ) -> Arc<ComposerUpdate> {
-        Arc::new(ComposerUpdate::from(
-            self.inner.lock().unwrap().replace_text_suggestion(
-                Utf16String::from_str(&new_text),
-                wysiwyg::SuggestionPattern::from(suggestion),
-                append_space,
-            ),
-        ))
+        let mut model = self.lock_measured("replace_text_suggestion");
+        Arc::new(ComposerUpdate::from(model.replace_text_suggestion(
+            Utf16String::from_str(&new_text),
+            wysiwyg::SuggestionPattern::from(suggestion),
+            append_space,
+        )))
     }
 
     pub fn backspace(self: &Arc<Self>) -> Arc<ComposerUpdate> {
-        Arc::new(ComposerUpdate::from(self.inner.lock().unwrap().backspace()))
+        let mut model = self.lock_measured("backspace");
+        Arc::new(ComposerUpdate::from(model.backspace()))
     }
 
     pub fn delete(self: &Arc<Self>) -> Arc<ComposerUpdate> {
-        Arc::new(ComposerUpdate::from(self.inner.lock().unwrap().delete()))
+        let mut model = self.lock_measured("delete");
+        Arc::new(ComposerUpdate::from(model.delete()))
     }
 
     pub fn delete_in(
@@ -173,69 +506,270 @@ impl ComposerModel {
         start: u32,
         end: u32,
     ) -> Arc<ComposerUpdate> {
+        let mut model = self.lock_measured("delete_in");
         let start = usize::try_from(start).unwrap();
         let end = usize::try_from(end).unwrap();
-        Arc::new(ComposerUpdate::from(
-            self.inner.lock().unwrap().delete_in(start, end),
-        ))
+        Arc::new(ComposerUpdate::from(model.delete_in(start, end)))
+    }
+
+    /// Apply a format to the text between `start` and `end`, without
+    /// changing the selection. Custom tags which weren't allowed with
+    /// `set_custom_inline_tags` and invalid colours are ignored.
+    pub fn format_in(
+        self: &Arc<Self>,
+        start: u32,
+        end: u32,
+        format: InlineFormatType,
+    ) -> Arc<ComposerUpdate> {
+        let mut model = self.lock_measured("format_in");
+        let start = wysiwyg::Location::from(usize::try_from(start).unwrap());
+        let end = wysiwyg::Location::from(usize::try_from(end).unwrap());
+        Arc::new(ComposerUpdate::from(model.format_in(
+            start,
+            end,
+            wysiwyg::InlineFormatType::from(format),
+        )))
+    }
+
+    /// Remove a format from the text between `start` and `end`, without
+    /// changing the selection.
+    pub fn remove_format_in(
+        self: &Arc<Self>,
+        start: u32,
+        end: u32,
+        format: InlineFormatType,
+    ) -> Arc<ComposerUpdate> {
+        let mut model = self.lock_measured("remove_format_in");
+        let start = wysiwyg::Location::from(usize::try_from(start).unwrap());
+        let end = wysiwyg::Location::from(usize::try_from(end).unwrap());
+        Arc::new(ComposerUpdate::from(model.remove_format_in(
+            start,
+            end,
+            wysiwyg::InlineFormatType::from(format),
+        )))
     }
 
     pub fn enter(self: &Arc<Self>) -> Arc<ComposerUpdate> {
-        Arc::new(ComposerUpdate::from(self.inner.lock().unwrap().enter()))
+        let mut model = self.lock_measured("enter");
+        Arc::new(ComposerUpdate::from(model.enter()))
+    }
+
+    pub fn insert_line_break(self: &Arc<Self>) -> Arc<ComposerUpdate> {
+        let mut model = self.lock_measured("insert_line_break");
+        Arc::new(ComposerUpdate::from(model.insert_line_break()))
     }
 
     pub fn bold(self: &Arc<Self>) -> Arc<ComposerUpdate> {
-        Arc::new(ComposerUpdate::from(self.inner.lock().unwrap().bold()))
+        let mut model = self.lock_measured("bold");
+        Arc::new(ComposerUpdate::from(model.bold()))
     }
 
     pub fn italic(self: &Arc<Self>) -> Arc<ComposerUpdate> {
-        Arc::new(ComposerUpdate::from(self.inner.lock().unwrap().italic()))
+        let mut model = self.lock_measured("italic");
+        Arc::new(ComposerUpdate::from(model.italic()))
     }
 
     pub fn strike_through(self: &Arc<Self>) -> Arc<ComposerUpdate> {
-        Arc::new(ComposerUpdate::from(
-            self.inner.lock().unwrap().strike_through(),
-        ))
+        let mut model = self.lock_measured("strike_through");
+        Arc::new(ComposerUpdate::from(model.strike_through()))
     }
 
     pub fn underline(self: &Arc<Self>) -> Arc<ComposerUpdate> {
-        Arc::new(ComposerUpdate::from(self.inner.lock().unwrap().underline()))
+        let mut model = self.lock_measured("underline");
+        Arc::new(ComposerUpdate::from(model.underline()))
     }
 
     pub fn inline_code(self: &Arc<Self>) -> Arc<ComposerUpdate> {
-        Arc::new(ComposerUpdate::from(
-            self.inner.lock().unwrap().inline_code(),
-        ))
+        let mut model = self.lock_measured("inline_code");
+        Arc::new(ComposerUpdate::from(model.inline_code()))
+    }
+
+    pub fn custom_format(self: &Arc<Self>, tag: String) -> Arc<ComposerUpdate> {
+        let mut model = self.lock_measured("custom_format");
+        Arc::new(ComposerUpdate::from(model.custom_format(&tag)))
+    }
+
+    pub fn set_text_color(
+        self: &Arc<Self>,
+        color: String,
+    ) -> Arc<ComposerUpdate> {
+        let mut model = self.lock_measured("set_text_color");
+        Arc::new(ComposerUpdate::from(model.set_text_color(&color)))
+    }
+
+    pub fn remove_text_color(self: &Arc<Self>) -> Arc<ComposerUpdate> {
+        let mut model = self.lock_measured("remove_text_color");
+        Arc::new(ComposerUpdate::from(model.remove_text_color()))
+    }
+
+    pub fn set_highlight(
+        self: &Arc<Self>,
+        color: String,
+    ) -> Arc<ComposerUpdate> {
+        let mut model = self.lock_measured("set_highlight");
+        Arc::new(ComposerUpdate::from(model.set_highlight(&color)))
+    }
+
+    pub fn remove_highlight(self: &Arc<Self>) -> Arc<ComposerUpdate> {
+        let mut model = self.lock_measured("remove_highlight");
+        Arc::new(ComposerUpdate::from(model.remove_highlight()))
     }
 
     pub fn code_block(self: &Arc<Self>) -> Arc<ComposerUpdate> {
+        let mut model = self.lock_measured("code_block");
+        Arc::new(ComposerUpdate::from(model.code_block()))
+    }
+
+    pub fn insert_code_block_with_text(
+        self: &Arc<Self>,
+        text: String,
+        language: Option<String>,
+    ) -> Arc<ComposerUpdate> {
+        let mut model = self.lock_measured("insert_code_block_with_text");
+        Arc::new(ComposerUpdate::from(model.insert_code_block_with_text(
+            Utf16String::from_str(&text),
+            language.as_deref(),
+        )))
+    }
+
+    pub fn quote(self: &Arc<Self>) -> Arc<ComposerUpdate> {
+        let mut model = self.lock_measured("quote");
+        Arc::new(ComposerUpdate::from(model.quote()))
+    }
+
+    pub fn insert_quote(
+        self: &Arc<Self>,
+        html: String,
+    ) -> Result<Arc<ComposerUpdate>, DomCreationError> {
+        let mut model = self.lock_measured("insert_quote");
+        let html = Utf16String::from_str(&html);
+        let update = model.insert_quote(&html)?;
+        Ok(Arc::new(ComposerUpdate::from(update)))
+    }
+
+    /// Toggle a heading of `level`, from 1 to 6. Other levels do nothing.
+    pub fn heading(self: &Arc<Self>, level: u8) -> Arc<ComposerUpdate> {
+        let mut model = self.lock_measured("heading");
         Arc::new(ComposerUpdate::from(
-            self.inner.lock().unwrap().code_block(),
+            match wysiwyg::HeadingLevel::from_number(level) {
+                Some(level) => model.heading(level),
+                None => wysiwyg::ComposerUpdate::keep_at(model.revision()),
+            },
         ))
     }
 
-    pub fn quote(self: &Arc<Self>) -> Arc<ComposerUpdate> {
-        Arc::new(ComposerUpdate::from(self.inner.lock().unwrap().quote()))
+    /// The level, from 1 to 6, of the heading at the selection, if any.
+    pub fn get_heading_level(self: &Arc<Self>) -> Option<u8> {
+        let model = self.lock_measured("get_heading_level");
+        model.get_heading_level().map(|level| level.number())
     }
 
-    pub fn ordered_list(self: &Arc<Self>) -> Arc<ComposerUpdate> {
+    pub fn get_outline(self: &Arc<Self>) -> Vec<OutlineEntry> {
+        let model = self.lock_measured("get_outline");
+        model
+            .get_outline()
+            .into_iter()
+            .map(OutlineEntry::from)
+            .collect()
+    }
+
+    /// Align the selected paragraphs and headings, or remove their
+    /// alignment if `alignment` is None.
+    pub fn set_alignment(
+        self: &Arc<Self>,
+        alignment: Option<Alignment>,
+    ) -> Arc<ComposerUpdate> {
+        let mut model = self.lock_measured("set_alignment");
         Arc::new(ComposerUpdate::from(
-            self.inner.lock().unwrap().ordered_list(),
+            model.set_alignment(alignment.map(Into::into)),
         ))
     }
 
-    pub fn unordered_list(self: &Arc<Self>) -> Arc<ComposerUpdate> {
+    /// The alignment of the paragraph or heading at the selection, if set.
+    pub fn get_alignment(self: &Arc<Self>) -> Option<Alignment> {
+        let model = self.lock_measured("get_alignment");
+        model.get_alignment().map(Into::into)
+    }
+
+    /// Write alignments as a `style` or a `data-mx-align` attribute.
+    pub fn set_alignment_attribute(
+        self: &Arc<Self>,
+        attribute: AlignmentAttribute,
+    ) {
+        let mut model = self.lock_measured("set_alignment_attribute");
+        model.set_alignment_attribute(attribute.into())
+    }
+
+    pub fn set_safe_link_attributes(self: &Arc<Self>, enabled: bool) {
+        let mut model = self.lock_measured("set_safe_link_attributes");
+        model.set_safe_link_attributes(enabled)
+    }
+
+    pub fn insert_reply_quote(
+        self: &Arc<Self>,
+        text: String,
+        attribution: QuoteAttribution,
+    ) -> Arc<ComposerUpdate> {
+        let mut model = self.lock_measured("insert_reply_quote");
+        Arc::new(ComposerUpdate::from(model.insert_reply_quote(
+            Utf16String::from_str(&text),
+            attribution.into(),
+        )))
+    }
+
+    pub fn increase_quote_level(self: &Arc<Self>) -> Arc<ComposerUpdate> {
+        let mut model = self.lock_measured("increase_quote_level");
+        Arc::new(ComposerUpdate::from(model.increase_quote_level()))
+    }
+
+    pub fn decrease_quote_level(self: &Arc<Self>) -> Arc<ComposerUpdate> {
+        let mut model = self.lock_measured("decrease_quote_level");
+        Arc::new(ComposerUpdate::from(model.decrease_quote_level()))
+    }
+
+    pub fn get_quote_level(self: &Arc<Self>) -> u32 {
+        let model = self.lock_measured("get_quote_level");
+        u32::try_from(model.get_quote_level()).unwrap()
+    }
+
+    pub fn get_quote_attribution(self: &Arc<Self>) -> Option<QuoteAttribution> {
+        let model = self.lock_measured("get_quote_attribution");
+        model.get_quote_attribution().map(QuoteAttribution::from)
+    }
+
+    pub fn set_quote_attribution(
+        self: &Arc<Self>,
+        attribution: Option<QuoteAttribution>,
+    ) -> Arc<ComposerUpdate> {
+        let mut model = self.lock_measured("set_quote_attribution");
         Arc::new(ComposerUpdate::from(
-            self.inner.lock().unwrap().unordered_list(),
+            model.set_quote_attribution(attribution.map(Into::into)),
         ))
     }
 
+    pub fn ordered_list(self: &Arc<Self>) -> Arc<ComposerUpdate> {
+        let mut model = self.lock_measured("ordered_list");
+        Arc::new(ComposerUpdate::from(model.ordered_list()))
+    }
+
+    pub fn unordered_list(self: &Arc<Self>) -> Arc<ComposerUpdate> {
+        let mut model = self.lock_measured("unordered_list");
+        Arc::new(ComposerUpdate::from(model.unordered_list()))
+    }
+
     pub fn undo(self: &Arc<Self>) -> Arc<ComposerUpdate> {
-        Arc::new(ComposerUpdate::from(self.inner.lock().unwrap().undo()))
+        let mut model = self.lock_measured("undo");
+        Arc::new(ComposerUpdate::from(model.undo()))
     }
 
     pub fn redo(self: &Arc<Self>) -> Arc<ComposerUpdate> {
-        Arc::new(ComposerUpdate::from(self.inner.lock().unwrap().redo()))
+        let mut model = self.lock_measured("redo");
+        Arc::new(ComposerUpdate::from(model.redo()))
+    }
+
+    pub fn undo_last_formatting(self: &Arc<Self>) -> Arc<ComposerUpdate> {
+        let mut model = self.lock_measured("undo_last_formatting");
+        Arc::new(ComposerUpdate::from(model.undo_last_formatting()))
     }
 
     pub fn set_link(
@@ -243,6 +777,7 @@ impl ComposerModel {
         url: String,
         attributes: Vec<Attribute>,
     ) -> Arc<ComposerUpdate> {
+        let mut model = self.lock_measured("set_link");
         let url = Utf16String::from_str(&url);
         let attrs = attributes
             .iter()
@@ -253,9 +788,7 @@ impl ComposerModel {
                 )
             })
             .collect();
-        Arc::new(ComposerUpdate::from(
-            self.inner.lock().unwrap().set_link(url, attrs),
-        ))
+        Arc::new(ComposerUpdate::from(model.set_link(url, attrs)))
     }
 
     pub fn set_link_with_text(
@@ -264,6 +797,7 @@ impl ComposerModel {
         text: String,
         attributes: Vec<Attribute>,
     ) -> Arc<ComposerUpdate> {
+        let mut model = self.lock_measured("set_link_with_text");
         let url = Utf16String::from_str(&url);
         let text = Utf16String::from_str(&html_escape::encode_safe(&text));
         let attrs = attributes
@@ -276,20 +810,65 @@ impl ComposerModel {
             })
             .collect();
         Arc::new(ComposerUpdate::from(
-            self.inner
-                .lock()
-                .unwrap()
-                .set_link_with_text(url, text, attrs),
+            model.set_link_with_text(url, text, attrs),
         ))
     }
 
-    /// Creates an at-room mention node and inserts it into the composer at the current selection
-    pub fn insert_at_room_mention(self: &Arc<Self>) -> Arc<ComposerUpdate> {
+    /// Inserts a horizontal rule at the current selection, splitting the block it is in
+    pub fn insert_horizontal_rule(self: &Arc<Self>) -> Arc<ComposerUpdate> {
+        let mut model = self.lock_measured("insert_horizontal_rule");
+        Arc::new(ComposerUpdate::from(model.insert_horizontal_rule()))
+    }
+
+    /// Inserts maths written in LaTeX at the current selection, as its own
+    /// block if `display` is true, or inline otherwise
+    pub fn insert_math(
+        self: &Arc<Self>,
+        latex: String,
+        display: bool,
+    ) -> Arc<ComposerUpdate> {
+        let mut model = self.lock_measured("insert_math");
+        let latex = Utf16String::from_str(&latex);
+        Arc::new(ComposerUpdate::from(model.insert_math(latex, display)))
+    }
+
+    /// Creates an inline image node and inserts it into the composer at the current selection
+    pub fn insert_image(
+        self: &Arc<Self>,
+        src: String,
+        alt: String,
+        width: Option<u32>,
+        height: Option<u32>,
+    ) -> Arc<ComposerUpdate> {
+        let mut model = self.lock_measured("insert_image");
+        let src = Utf16String::from_str(&src);
+        let alt = Utf16String::from_str(&alt);
         Arc::new(ComposerUpdate::from(
-            self.inner.lock().unwrap().insert_at_room_mention(vec![]),
+            model.insert_image(src, alt, width, height),
         ))
     }
 
+    /// Creates a custom emoji with `shortcode` from the image at `mxc_url`
+    /// and inserts it into the composer at the current selection
+    pub fn insert_custom_emoji(
+        self: &Arc<Self>,
+        shortcode: String,
+        mxc_url: String,
+    ) -> Arc<ComposerUpdate> {
+        let mut model = self.lock_measured("insert_custom_emoji");
+        let shortcode = Utf16String::from_str(&shortcode);
+        let mxc_url = Utf16String::from_str(&mxc_url);
+        Arc::new(ComposerUpdate::from(
+            model.insert_custom_emoji(shortcode, mxc_url),
+        ))
+    }
+
+    /// Creates an at-room mention node and inserts it into the composer at the current selection
+    pub fn insert_at_room_mention(self: &Arc<Self>) -> Arc<ComposerUpdate> {
+        let mut model = self.lock_measured("insert_at_room_mention");
+        Arc::new(ComposerUpdate::from(model.insert_at_room_mention(vec![])))
+    }
+
     /// Creates a mention node and inserts it into the composer at the current selection
     pub fn insert_mention(
         self: &Arc<Self>,
@@ -297,12 +876,11 @@ impl ComposerModel {
         text: String,
         _attributes: Vec<Attribute>, // TODO remove attributes
     ) -> Arc<ComposerUpdate> {
+        let mut model = self.lock_measured("insert_mention");
         let url = Utf16String::from_str(&url);
         let text = Utf16String::from_str(&html_escape::encode_safe(&text));
         let attrs = vec![];
-        Arc::new(ComposerUpdate::from(
-            self.inner.lock().unwrap().insert_mention(url, text, attrs),
-        ))
+        Arc::new(ComposerUpdate::from(model.insert_mention(url, text, attrs)))
     }
 
     /// Creates an at-room mention node and inserts it into the composer, replacing the
@@ -311,13 +889,12 @@ impl ComposerModel {
         self: &Arc<Self>,
         suggestion: SuggestionPattern,
     ) -> Arc<ComposerUpdate> {
+        let mut model =
+            self.lock_measured("insert_at_room_mention_at_suggestion");
         let suggestion = wysiwyg::SuggestionPattern::from(suggestion);
         let attrs = vec![];
         Arc::new(ComposerUpdate::from(
-            self.inner
-                .lock()
-                .unwrap()
-                .insert_at_room_mention_at_suggestion(suggestion, attrs),
+            model.insert_at_room_mention_at_suggestion(suggestion, attrs),
         ))
     }
 
@@ -330,61 +907,97 @@ impl ComposerModel {
         suggestion: SuggestionPattern,
         _attributes: Vec<Attribute>, // TODO remove attributes
     ) -> Arc<ComposerUpdate> {
+        let mut model = self.lock_measured("insert_mention_at_suggestion");
         let url = Utf16String::from_str(&url);
         let text = Utf16String::from_str(&html_escape::encode_safe(&text));
         let suggestion = wysiwyg::SuggestionPattern::from(suggestion);
         let attrs = vec![];
         Arc::new(ComposerUpdate::from(
-            self.inner
-                .lock()
-                .unwrap()
-                .insert_mention_at_suggestion(url, text, suggestion, attrs),
+            model.insert_mention_at_suggestion(url, text, suggestion, attrs),
         ))
     }
 
     pub fn remove_links(self: &Arc<Self>) -> Arc<ComposerUpdate> {
-        Arc::new(ComposerUpdate::from(
-            self.inner.lock().unwrap().remove_links(),
-        ))
+        let mut model = self.lock_measured("remove_links");
+        Arc::new(ComposerUpdate::from(model.remove_links()))
     }
 
     pub fn indent(self: &Arc<Self>) -> Arc<ComposerUpdate> {
-        Arc::new(ComposerUpdate::from(self.inner.lock().unwrap().indent()))
+        let mut model = self.lock_measured("indent");
+        Arc::new(ComposerUpdate::from(model.indent()))
     }
 
     pub fn unindent(self: &Arc<Self>) -> Arc<ComposerUpdate> {
-        Arc::new(ComposerUpdate::from(self.inner.lock().unwrap().unindent()))
+        let mut model = self.lock_measured("unindent");
+        Arc::new(ComposerUpdate::from(model.unindent()))
+    }
+
+    pub fn handle_tab(self: &Arc<Self>) -> Arc<ComposerUpdate> {
+        let mut model = self.lock_measured("handle_tab");
+        Arc::new(ComposerUpdate::from(model.handle_tab()))
+    }
+
+    pub fn handle_shift_tab(self: &Arc<Self>) -> Arc<ComposerUpdate> {
+        let mut model = self.lock_measured("handle_shift_tab");
+        Arc::new(ComposerUpdate::from(model.handle_shift_tab()))
     }
 
     pub fn to_example_format(self: &Arc<Self>) -> String {
-        self.inner.lock().unwrap().to_example_format()
+        let model = self.lock_measured("to_example_format");
+        model.to_example_format()
     }
 
     pub fn to_tree(self: &Arc<Self>) -> String {
-        self.inner.lock().unwrap().to_tree().to_string()
+        let model = self.lock_measured("to_tree");
+        model.to_tree().to_string()
     }
 
     pub fn get_current_dom_state(self: &Arc<Self>) -> ComposerState {
-        self.inner
-            .lock()
-            .unwrap()
-            .get_current_state()
-            .clone()
-            .into()
+        let model = self.lock_measured("get_current_dom_state");
+        model.get_current_state().clone().into()
     }
 
     pub fn action_states(
         self: &Arc<Self>,
     ) -> HashMap<ComposerAction, ActionState> {
-        self.inner.lock().unwrap().action_states().into_ffi()
+        let model = self.lock_measured("action_states");
+        model.action_states().into_ffi()
+    }
+
+    pub fn undo_depth(self: &Arc<Self>) -> u32 {
+        let model = self.lock_measured("undo_depth");
+        u32::try_from(model.undo_depth()).unwrap()
+    }
+
+    pub fn redo_depth(self: &Arc<Self>) -> u32 {
+        let model = self.lock_measured("redo_depth");
+        u32::try_from(model.redo_depth()).unwrap()
+    }
+
+    pub fn disabled_reasons(
+        self: &Arc<Self>,
+    ) -> HashMap<ComposerAction, DisabledReason> {
+        let model = self.lock_measured("disabled_reasons");
+        model.disabled_reasons().into_ffi()
     }
 
     pub fn get_link_action(self: &Arc<Self>) -> LinkAction {
-        self.inner.lock().unwrap().get_link_action().into()
+        let model = self.lock_measured("get_link_action");
+        model.get_link_action().into()
+    }
+
+    pub fn get_selected_link_urls(self: &Arc<Self>) -> Vec<String> {
+        let model = self.lock_measured("get_selected_link_urls");
+        model
+            .get_selected_link_urls()
+            .iter()
+            .map(|url| url.to_string())
+            .collect()
     }
 
     pub fn get_mentions_state(self: &Arc<Self>) -> MentionsState {
-        self.inner.lock().unwrap().get_mentions_state().into()
+        let model = self.lock_measured("get_mentions_state");
+        model.get_mentions_state().into()
     }
 
     /// Force a panic for test purposes
@@ -399,3 +1012,42 @@ pub struct Attribute {
     pub key: String,
     pub value: String,
 }
+
+#[cfg(test)]
+mod test {
+    use std::sync::{Arc, Mutex};
+
+    use crate::{ComposerModel, MetricsListener};
+
+    /// Reads the composer whenever it is told about a call on it, as a
+    /// Kotlin or Swift listener might.
+    struct ReadingListener {
+        model: Arc<ComposerModel>,
+        reports: Arc<Mutex<Vec<String>>>,
+    }
+
+    impl MetricsListener for ReadingListener {
+        fn on_operation(&self, operation: String, _duration_ms: f64) {
+            let html = self.model.get_content_as_html();
+            self.reports
+                .lock()
+                .unwrap()
+                .push(format!("{operation}: {html}"));
+        }
+    }
+
+    #[test]
+    fn the_metrics_listener_can_call_the_composer() {
+        let model = Arc::new(ComposerModel::new());
+        let reports = Arc::new(Mutex::new(Vec::new()));
+        model.set_metrics_listener(Box::new(ReadingListener {
+            model: model.clone(),
+            reports: reports.clone(),
+        }));
+
+        model.replace_text(String::from("a"));
+
+        // The listener's own call isn't reported to it
+        assert_eq!(*reports.lock().unwrap(), vec!["replace_text: a"]);
+    }
+}
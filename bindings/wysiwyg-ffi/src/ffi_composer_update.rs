@@ -33,6 +33,12 @@ impl ComposerUpdate {
     pub fn link_action(&self) -> LinkActionUpdate {
         LinkActionUpdate::from(self.inner.link_action.clone())
     }
+
+    /// The revision of the model once this update is applied. Updates with
+    /// a lower revision than one already applied are out of date.
+    pub fn revision(&self) -> u64 {
+        self.inner.revision
+    }
 }
 
 #[cfg(test)]
@@ -40,8 +46,8 @@ mod test {
     use std::{collections::HashMap, sync::Arc};
 
     use crate::{
-        ActionState, ComposerAction, ComposerModel, MenuAction, MenuState,
-        SuggestionPattern,
+        ActionState, ComposerAction, ComposerModel, DisabledReason, MenuAction,
+        MenuState, SuggestionPattern,
     };
 
     #[test]
@@ -53,7 +59,11 @@ mod test {
         assert_eq!(
             update.menu_state(),
             MenuState::Update {
-                action_states: redo_indent_unindent_disabled()
+                action_states: redo_indent_unindent_disabled(),
+                disabled_reasons: redo_indent_unindent_disabled_reasons(),
+                undo_depth: 1,
+                redo_depth: 0,
+                quote_depth: 0,
             }
         );
     }
@@ -67,7 +77,11 @@ mod test {
         assert_eq!(
             update.menu_state(),
             MenuState::Update {
-                action_states: undo_redo_indent_unindent_disabled()
+                action_states: undo_redo_indent_unindent_disabled(),
+                disabled_reasons: undo_redo_indent_unindent_disabled_reasons(),
+                undo_depth: 0,
+                redo_depth: 0,
+                quote_depth: 0,
             }
         );
     }
@@ -84,7 +98,11 @@ mod test {
         assert_eq!(
             update.menu_state(),
             MenuState::Update {
-                action_states: undo_redo_indent_unindent_disabled()
+                action_states: undo_redo_indent_unindent_disabled(),
+                disabled_reasons: undo_redo_indent_unindent_disabled_reasons(),
+                undo_depth: 0,
+                redo_depth: 0,
+                quote_depth: 0,
             }
         );
     }
@@ -98,7 +116,11 @@ mod test {
         assert_eq!(
             update.menu_state(),
             MenuState::Update {
-                action_states: undo_redo_indent_unindent_disabled()
+                action_states: undo_redo_indent_unindent_disabled(),
+                disabled_reasons: undo_redo_indent_unindent_disabled_reasons(),
+                undo_depth: 0,
+                redo_depth: 0,
+                quote_depth: 0,
             }
         );
     }
@@ -124,6 +146,7 @@ mod test {
                     text: "alic".into(),
                     start: 0,
                     end: 5,
+                    revision: update.revision(),
                 }
             },
         )
@@ -143,6 +166,7 @@ mod test {
                     text: ":)".into(),
                     start: 14,
                     end: 16,
+                    revision: update.revision(),
                 }
             },
         )
@@ -294,6 +318,8 @@ mod test {
             (ComposerAction::InlineCode, ActionState::Enabled),
             (ComposerAction::Italic, ActionState::Enabled),
             (ComposerAction::Link, ActionState::Enabled),
+            (ComposerAction::CreateLinkWithText, ActionState::Enabled),
+            (ComposerAction::EditLink, ActionState::Disabled),
             (ComposerAction::OrderedList, ActionState::Enabled),
             (ComposerAction::Redo, ActionState::Disabled),
             (ComposerAction::StrikeThrough, ActionState::Enabled),
@@ -303,6 +329,12 @@ mod test {
             (ComposerAction::UnorderedList, ActionState::Enabled),
             (ComposerAction::CodeBlock, ActionState::Enabled),
             (ComposerAction::Quote, ActionState::Enabled),
+            (ComposerAction::Heading, ActionState::Enabled),
+            (ComposerAction::Highlight, ActionState::Enabled),
+            (ComposerAction::AlignLeft, ActionState::Enabled),
+            (ComposerAction::AlignCenter, ActionState::Enabled),
+            (ComposerAction::AlignRight, ActionState::Enabled),
+            (ComposerAction::AlignJustify, ActionState::Enabled),
         ])
     }
 
@@ -314,6 +346,8 @@ mod test {
             (ComposerAction::InlineCode, ActionState::Enabled),
             (ComposerAction::Italic, ActionState::Enabled),
             (ComposerAction::Link, ActionState::Enabled),
+            (ComposerAction::CreateLinkWithText, ActionState::Enabled),
+            (ComposerAction::EditLink, ActionState::Disabled),
             (ComposerAction::OrderedList, ActionState::Enabled),
             (ComposerAction::Redo, ActionState::Disabled),
             (ComposerAction::StrikeThrough, ActionState::Enabled),
@@ -323,6 +357,33 @@ mod test {
             (ComposerAction::UnorderedList, ActionState::Enabled),
             (ComposerAction::CodeBlock, ActionState::Enabled),
             (ComposerAction::Quote, ActionState::Enabled),
+            (ComposerAction::Heading, ActionState::Enabled),
+            (ComposerAction::Highlight, ActionState::Enabled),
+            (ComposerAction::AlignLeft, ActionState::Enabled),
+            (ComposerAction::AlignCenter, ActionState::Enabled),
+            (ComposerAction::AlignRight, ActionState::Enabled),
+            (ComposerAction::AlignJustify, ActionState::Enabled),
+        ])
+    }
+
+    fn redo_indent_unindent_disabled_reasons(
+    ) -> HashMap<ComposerAction, DisabledReason> {
+        HashMap::from([
+            (ComposerAction::EditLink, DisabledReason::NotApplicable),
+            (ComposerAction::Indent, DisabledReason::CannotIndent),
+            (ComposerAction::Redo, DisabledReason::HistoryEmpty),
+            (ComposerAction::Unindent, DisabledReason::CannotUnindent),
+        ])
+    }
+
+    fn undo_redo_indent_unindent_disabled_reasons(
+    ) -> HashMap<ComposerAction, DisabledReason> {
+        HashMap::from([
+            (ComposerAction::EditLink, DisabledReason::NotApplicable),
+            (ComposerAction::Indent, DisabledReason::CannotIndent),
+            (ComposerAction::Redo, DisabledReason::HistoryEmpty),
+            (ComposerAction::Unindent, DisabledReason::CannotUnindent),
+            (ComposerAction::Undo, DisabledReason::HistoryEmpty),
         ])
     }
 }
@@ -16,31 +16,43 @@ use std::{error::Error, fmt::Display};
 
 #[derive(Debug, uniffi::Error)]
 pub enum DomCreationError {
-    HtmlParseError,
-    MarkdownParseError,
+    /// `recovered_html` is what could be made of the HTML in spite of the
+    /// errors, and can be set instead, e.g. to restore a malformed draft.
+    HtmlParseError {
+        message: String,
+        recovered_html: Option<String>,
+    },
+    MarkdownParseError {
+        message: String,
+    },
 }
 
 impl Display for DomCreationError {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        f.write_str(match self {
-            DomCreationError::HtmlParseError => {
-                "could not create dom from html"
+        match self {
+            DomCreationError::HtmlParseError { message, .. } => {
+                write!(f, "could not create dom from html: {message}")
             }
-            DomCreationError::MarkdownParseError => {
-                "could not create dom from markdown"
+            DomCreationError::MarkdownParseError { message } => {
+                write!(f, "could not create dom from markdown: {message}")
             }
-        })
+        }
     }
 }
 
 impl From<wysiwyg::DomCreationError> for DomCreationError {
     fn from(error: wysiwyg::DomCreationError) -> Self {
         match error {
-            wysiwyg::DomCreationError::HtmlParseError(_) => {
-                Self::HtmlParseError
+            wysiwyg::DomCreationError::HtmlParseError(e) => {
+                Self::HtmlParseError {
+                    message: e.parse_errors.join(", "),
+                    recovered_html: e.recovered_html,
+                }
             }
-            wysiwyg::DomCreationError::MarkdownParseError(_) => {
-                Self::MarkdownParseError
+            wysiwyg::DomCreationError::MarkdownParseError(e) => {
+                Self::MarkdownParseError {
+                    message: e.to_string(),
+                }
             }
         }
     }
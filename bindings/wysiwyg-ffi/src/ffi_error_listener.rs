@@ -0,0 +1,14 @@
+/// Told about each internal error a composer model recovered from by
+/// leaving the content as it was, e.g. to report it.
+#[uniffi::export(callback_interface)]
+pub trait ErrorListener: Send + Sync {
+    fn on_error(&self, error: String);
+}
+
+pub(crate) struct FfiErrorListener(pub(crate) Box<dyn ErrorListener>);
+
+impl wysiwyg::ErrorListener for FfiErrorListener {
+    fn on_error(&self, error: &wysiwyg::ComposerError) {
+        self.0.on_error(error.to_string())
+    }
+}
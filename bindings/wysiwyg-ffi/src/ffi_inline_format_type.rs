@@ -0,0 +1,43 @@
+// Copyright 2023 The Matrix.org Foundation C.I.C.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+#[derive(Debug, PartialEq, Eq, uniffi::Enum)]
+pub enum InlineFormatType {
+    Bold,
+    Italic,
+    StrikeThrough,
+    Underline,
+    InlineCode,
+    /// A tag allowed with `set_custom_inline_tags`. Other tags are ignored.
+    Custom(String),
+    /// A colour in the form `#rrggbb`. Other colours are ignored.
+    TextColor(String),
+    /// A colour in the form `#rrggbb`. Other colours are ignored.
+    Highlight(String),
+}
+
+impl From<InlineFormatType> for wysiwyg::InlineFormatType {
+    fn from(format: InlineFormatType) -> Self {
+        match format {
+            InlineFormatType::Bold => Self::Bold,
+            InlineFormatType::Italic => Self::Italic,
+            InlineFormatType::StrikeThrough => Self::StrikeThrough,
+            InlineFormatType::Underline => Self::Underline,
+            InlineFormatType::InlineCode => Self::InlineCode,
+            InlineFormatType::Custom(tag) => Self::Custom(tag),
+            InlineFormatType::TextColor(color) => Self::TextColor(color),
+            InlineFormatType::Highlight(color) => Self::Highlight(color),
+        }
+    }
+}
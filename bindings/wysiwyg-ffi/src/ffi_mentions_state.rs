@@ -1,18 +1,27 @@
-#[derive(uniffi::Record)]
+/// The mentions in the content, to build the `m.mentions` of a message.
+/// Each list is sorted.
+#[derive(Debug, PartialEq, Eq, uniffi::Record)]
 pub struct MentionsState {
     pub user_ids: Vec<String>,
     pub room_ids: Vec<String>,
     pub room_aliases: Vec<String>,
+    /// Whether the content mentions `@room`.
     pub has_at_room_mention: bool,
 }
 
 impl From<wysiwyg::MentionsState> for MentionsState {
     fn from(value: wysiwyg::MentionsState) -> Self {
         Self {
-            user_ids: value.user_ids.into_iter().collect(),
-            room_ids: value.room_ids.into_iter().collect(),
-            room_aliases: value.room_aliases.into_iter().collect(),
+            user_ids: sorted(value.user_ids),
+            room_ids: sorted(value.room_ids),
+            room_aliases: sorted(value.room_aliases),
             has_at_room_mention: value.has_at_room_mention,
         }
     }
 }
+
+fn sorted(ids: impl IntoIterator<Item = String>) -> Vec<String> {
+    let mut ids: Vec<String> = ids.into_iter().collect();
+    ids.sort();
+    ids
+}
@@ -1,13 +1,17 @@
 use std::collections::HashMap;
 
 use crate::into_ffi::IntoFfi;
-use crate::{ActionState, ComposerAction};
+use crate::{ActionState, ComposerAction, DisabledReason};
 
 #[derive(Debug, PartialEq, Eq, uniffi::Enum)]
 pub enum MenuState {
     Keep,
     Update {
         action_states: HashMap<ComposerAction, ActionState>,
+        disabled_reasons: HashMap<ComposerAction, DisabledReason>,
+        undo_depth: u32,
+        redo_depth: u32,
+        quote_depth: u32,
     },
 }
 
@@ -17,6 +21,10 @@ impl MenuState {
             wysiwyg::MenuState::Keep => Self::Keep,
             wysiwyg::MenuState::Update(menu_update) => Self::Update {
                 action_states: menu_update.action_states.into_ffi(),
+                disabled_reasons: menu_update.disabled_reasons.into_ffi(),
+                undo_depth: u32::try_from(menu_update.undo_depth).unwrap(),
+                redo_depth: u32::try_from(menu_update.redo_depth).unwrap(),
+                quote_depth: u32::try_from(menu_update.quote_depth).unwrap(),
             },
         }
     }
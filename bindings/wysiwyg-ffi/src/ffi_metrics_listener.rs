@@ -0,0 +1,17 @@
+use std::time::Duration;
+
+/// Told how long each call on a composer model took, e.g. to track
+/// latency in production.
+#[uniffi::export(callback_interface)]
+pub trait MetricsListener: Send + Sync {
+    fn on_operation(&self, operation: String, duration_ms: f64);
+}
+
+pub(crate) struct FfiMetricsListener(pub(crate) Box<dyn MetricsListener>);
+
+impl wysiwyg::MetricsListener for FfiMetricsListener {
+    fn on_operation(&self, operation: &str, duration: Duration) {
+        self.0
+            .on_operation(operation.to_owned(), duration.as_secs_f64() * 1000.0)
+    }
+}
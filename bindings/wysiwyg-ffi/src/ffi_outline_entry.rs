@@ -0,0 +1,35 @@
+// Copyright 2023 The Matrix.org Foundation C.I.C.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use widestring::Utf16String;
+
+/// A heading of the content, with its level from 1 to 6.
+#[derive(Debug, PartialEq, Eq, uniffi::Record)]
+pub struct OutlineEntry {
+    pub level: u8,
+    pub text: String,
+    pub start: u32,
+    pub end: u32,
+}
+
+impl From<wysiwyg::OutlineEntry<Utf16String>> for OutlineEntry {
+    fn from(inner: wysiwyg::OutlineEntry<Utf16String>) -> Self {
+        Self {
+            level: inner.level.number(),
+            text: inner.text.to_string(),
+            start: u32::try_from(inner.start).unwrap(),
+            end: u32::try_from(inner.end).unwrap(),
+        }
+    }
+}
@@ -0,0 +1,37 @@
+// Copyright 2023 The Matrix.org Foundation C.I.C.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+#[derive(Debug, PartialEq, Eq, uniffi::Record)]
+pub struct QuoteAttribution {
+    pub author: String,
+    pub event_link: Option<String>,
+}
+
+impl From<wysiwyg::QuoteAttribution> for QuoteAttribution {
+    fn from(inner: wysiwyg::QuoteAttribution) -> Self {
+        Self {
+            author: inner.author,
+            event_link: inner.event_link,
+        }
+    }
+}
+
+impl From<QuoteAttribution> for wysiwyg::QuoteAttribution {
+    fn from(attribution: QuoteAttribution) -> Self {
+        Self {
+            author: attribution.author,
+            event_link: attribution.event_link,
+        }
+    }
+}
@@ -0,0 +1,30 @@
+// Copyright 2023 The Matrix.org Foundation C.I.C.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+#[derive(Debug, PartialEq, Eq, uniffi::Record)]
+pub struct SelectionStats {
+    pub character_count: u32,
+    pub word_count: u32,
+    pub spans_multiple_blocks: bool,
+}
+
+impl From<wysiwyg::SelectionStats> for SelectionStats {
+    fn from(inner: wysiwyg::SelectionStats) -> Self {
+        Self {
+            character_count: u32::try_from(inner.character_count).unwrap(),
+            word_count: u32::try_from(inner.word_count).unwrap(),
+            spans_multiple_blocks: inner.spans_multiple_blocks,
+        }
+    }
+}
@@ -20,6 +20,7 @@ pub struct SuggestionPattern {
     pub text: String,
     pub start: u32,
     pub end: u32,
+    pub revision: u64,
 }
 
 impl From<wysiwyg::SuggestionPattern> for SuggestionPattern {
@@ -29,6 +30,7 @@ impl From<wysiwyg::SuggestionPattern> for SuggestionPattern {
             text: inner.text,
             start: u32::try_from(inner.start).unwrap(),
             end: u32::try_from(inner.end).unwrap(),
+            revision: inner.revision,
         }
     }
 }
@@ -40,6 +42,7 @@ impl From<SuggestionPattern> for wysiwyg::SuggestionPattern {
             text: pattern.text,
             start: usize::try_from(pattern.start).unwrap(),
             end: usize::try_from(pattern.end).unwrap(),
+            revision: pattern.revision,
         }
     }
 }
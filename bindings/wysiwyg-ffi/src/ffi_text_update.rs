@@ -7,6 +7,8 @@ pub enum TextUpdate {
         replacement_html: Vec<u16>,
         start_utf16_codeunit: u32,
         end_utf16_codeunit: u32,
+        changed_start_utf16_codeunit: u32,
+        changed_end_utf16_codeunit: u32,
     },
     Select {
         start_utf16_codeunit: u32,
@@ -21,12 +23,27 @@ impl TextUpdate {
             wysiwyg::TextUpdate::ReplaceAll(replace_all) => {
                 let start_utf16_codeunit: usize = replace_all.start.into();
                 let end_utf16_codeunit: usize = replace_all.end.into();
+                let changed_start_utf16_codeunit: usize =
+                    replace_all.changed_start.into();
+                let changed_end_utf16_codeunit: usize =
+                    replace_all.changed_end.into();
                 Self::ReplaceAll {
-                    replacement_html: replace_all.replacement_html.into_vec(),
+                    replacement_html: replace_all
+                        .replacement_html
+                        .into_html()
+                        .into_vec(),
                     start_utf16_codeunit: u32::try_from(start_utf16_codeunit)
                         .unwrap(),
                     end_utf16_codeunit: u32::try_from(end_utf16_codeunit)
                         .unwrap(),
+                    changed_start_utf16_codeunit: u32::try_from(
+                        changed_start_utf16_codeunit,
+                    )
+                    .unwrap(),
+                    changed_end_utf16_codeunit: u32::try_from(
+                        changed_end_utf16_codeunit,
+                    )
+                    .unwrap(),
                 }
             }
             wysiwyg::TextUpdate::Select(selection) => {
@@ -1,13 +1,23 @@
 use std::collections::HashMap;
 
-use crate::{ActionState, ComposerAction};
+use crate::{ActionState, ComposerAction, DisabledReason};
 
-pub trait IntoFfi {
-    fn into_ffi(self) -> HashMap<ComposerAction, ActionState>;
+pub trait IntoFfi<T> {
+    fn into_ffi(self) -> T;
 }
 
-impl IntoFfi for &HashMap<wysiwyg::ComposerAction, wysiwyg::ActionState> {
+impl IntoFfi<HashMap<ComposerAction, ActionState>>
+    for &HashMap<wysiwyg::ComposerAction, wysiwyg::ActionState>
+{
     fn into_ffi(self) -> HashMap<ComposerAction, ActionState> {
         self.iter().map(|(a, s)| (a.into(), s.into())).collect()
     }
 }
+
+impl IntoFfi<HashMap<ComposerAction, DisabledReason>>
+    for &HashMap<wysiwyg::ComposerAction, wysiwyg::DisabledReason>
+{
+    fn into_ffi(self) -> HashMap<ComposerAction, DisabledReason> {
+        self.iter().map(|(a, r)| (a.into(), r.into())).collect()
+    }
+}
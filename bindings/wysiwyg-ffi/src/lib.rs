@@ -15,37 +15,65 @@
 uniffi_macros::include_scaffolding!("wysiwyg_composer");
 
 mod ffi_action_state;
+mod ffi_alignment;
+mod ffi_block_info;
+mod ffi_clipboard_content;
+mod ffi_command;
+mod ffi_command_error;
 mod ffi_composer_action;
 mod ffi_composer_model;
 mod ffi_composer_state;
 mod ffi_composer_update;
+mod ffi_direction;
 mod ffi_dom_creation_error;
+mod ffi_error_listener;
+mod ffi_granularity;
+mod ffi_inline_format_type;
 mod ffi_link_actions;
 mod ffi_mention_detector;
 mod ffi_mentions_state;
 mod ffi_menu_action;
 mod ffi_menu_state;
+mod ffi_metrics_listener;
+mod ffi_outline_entry;
 mod ffi_pattern_key;
+mod ffi_quote_attribution;
+mod ffi_selection_stats;
 mod ffi_suggestion_pattern;
+mod ffi_text_range;
 mod ffi_text_update;
 mod into_ffi;
 
 use std::sync::Arc;
 
-pub use crate::ffi_action_state::ActionState;
+pub use crate::ffi_action_state::{ActionState, DisabledReason};
+pub use crate::ffi_alignment::{Alignment, AlignmentAttribute};
+pub use crate::ffi_block_info::BlockInfo;
+pub use crate::ffi_clipboard_content::{ClipboardContent, CutResult};
+pub use crate::ffi_command::Command;
+pub use crate::ffi_command_error::CommandError;
 pub use crate::ffi_composer_action::ComposerAction;
 pub use crate::ffi_composer_model::Attribute;
 pub use crate::ffi_composer_model::ComposerModel;
 pub use crate::ffi_composer_state::ComposerState;
 pub use crate::ffi_composer_update::ComposerUpdate;
+pub use crate::ffi_direction::Direction;
 pub use crate::ffi_dom_creation_error::DomCreationError;
+pub use crate::ffi_error_listener::ErrorListener;
+pub use crate::ffi_granularity::Granularity;
+pub use crate::ffi_inline_format_type::InlineFormatType;
 pub use crate::ffi_link_actions::LinkAction;
 use crate::ffi_mention_detector::MentionDetector;
 pub use crate::ffi_mentions_state::MentionsState;
 pub use crate::ffi_menu_action::MenuAction;
 pub use crate::ffi_menu_state::MenuState;
+pub use crate::ffi_metrics_listener::MetricsListener;
+pub use crate::ffi_outline_entry::OutlineEntry;
 pub use crate::ffi_pattern_key::PatternKey;
+pub use crate::ffi_quote_attribution::QuoteAttribution;
+pub use crate::ffi_selection_stats::SelectionStats;
 pub use crate::ffi_suggestion_pattern::SuggestionPattern;
+pub use crate::ffi_text_range::TextRange;
 pub use crate::ffi_text_update::TextUpdate;
 
 #[uniffi::export]
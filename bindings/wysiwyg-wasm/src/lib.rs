@@ -15,6 +15,8 @@
 use std::{
     collections::{HashMap, VecDeque},
     fmt::Display,
+    sync::Arc,
+    time::Duration,
 };
 
 use wasm_bindgen::prelude::*;
@@ -48,6 +50,7 @@ pub fn new_composer_model_from_html(
 }
 
 #[wasm_bindgen]
+#[derive(Clone)]
 pub enum ActionState {
     /// The button can be clicked, and will perform its normal action
     /// e.g. make something bold
@@ -61,6 +64,16 @@ pub enum ActionState {
     Disabled,
 }
 
+impl From<&wysiwyg::ActionState> for ActionState {
+    fn from(inner: &wysiwyg::ActionState) -> Self {
+        match inner {
+            wysiwyg::ActionState::Enabled => Self::Enabled,
+            wysiwyg::ActionState::Reversed => Self::Reversed,
+            wysiwyg::ActionState::Disabled => Self::Disabled,
+        }
+    }
+}
+
 trait IntoFfi {
     fn into_ffi(self) -> js_sys::Map;
 }
@@ -75,6 +88,16 @@ impl IntoFfi for &HashMap<wysiwyg::ComposerAction, wysiwyg::ActionState> {
     }
 }
 
+impl IntoFfi for &HashMap<wysiwyg::ComposerAction, wysiwyg::DisabledReason> {
+    fn into_ffi(self) -> js_sys::Map {
+        let ret = js_sys::Map::new();
+        for (k, v) in self.iter() {
+            ret.set(&k.as_ref().into(), &v.as_ref().into());
+        }
+        ret
+    }
+}
+
 trait ToUtf16TupleVec {
     fn into_vec(self) -> Vec<(Utf16String, Utf16String)>;
 }
@@ -112,6 +135,48 @@ pub struct ComposerModel {
     inner: wysiwyg::ComposerModel<Utf16String>,
 }
 
+impl ComposerModel {
+    /// Run `call` on the model, timed as `operation` for the metrics
+    /// listener. The listeners are only told about the call and any errors
+    /// it recovered from once this call from JavaScript has returned,
+    /// because they can't use the model while it is borrowed.
+    fn measured<T>(
+        &self,
+        operation: &'static str,
+        call: impl FnOnce(&wysiwyg::ComposerModel<Utf16String>) -> T,
+    ) -> T {
+        let timer = self.inner.measure(operation);
+        let result = call(&self.inner);
+        report_later(self.inner.take_unreported_errors(), timer.stop());
+        result
+    }
+
+    /// Like [Self::measured], for calls which change the model.
+    fn measured_mut<T>(
+        &mut self,
+        operation: &'static str,
+        call: impl FnOnce(&mut wysiwyg::ComposerModel<Utf16String>) -> T,
+    ) -> T {
+        let timer = self.inner.measure(operation);
+        let result = call(&mut self.inner);
+        report_later(self.inner.take_unreported_errors(), timer.stop());
+        result
+    }
+}
+
+/// Tell the listeners about a call once it has returned to JavaScript.
+fn report_later(
+    errors: wysiwyg::UnreportedErrors,
+    metrics: wysiwyg::OperationMetrics,
+) {
+    if !errors.is_empty() || !metrics.is_empty() {
+        wasm_bindgen_futures::spawn_local(async move {
+            errors.report();
+            metrics.report();
+        });
+    }
+}
+
 #[wasm_bindgen]
 impl ComposerModel {
     pub fn new() -> Self {
@@ -127,37 +192,204 @@ impl ComposerModel {
     }
 
     pub fn to_example_format(&self) -> String {
-        self.inner.to_example_format()
+        self.measured("to_example_format", |model| model.to_example_format())
+    }
+
+    /// The revision of the current state, as sent in the latest update.
+    pub fn revision(&self) -> u64 {
+        self.measured("revision", |model| model.revision())
     }
 
     pub fn get_content_as_html(&self) -> String {
-        self.inner.get_content_as_html().to_string()
+        self.measured("get_content_as_html", |model| {
+            model.get_content_as_html().to_string()
+        })
     }
 
     pub fn get_content_as_message_html(&self) -> String {
-        self.inner.get_content_as_message_html().to_string()
+        self.measured("get_content_as_message_html", |model| {
+            model.get_content_as_message_html().to_string()
+        })
     }
 
     pub fn get_content_as_markdown(&self) -> String {
-        self.inner.get_content_as_markdown().to_string()
+        self.measured("get_content_as_markdown", |model| {
+            model.get_content_as_markdown().to_string()
+        })
     }
 
     pub fn get_content_as_message_markdown(&self) -> String {
-        self.inner.get_content_as_message_markdown().to_string()
+        self.measured("get_content_as_message_markdown", |model| {
+            model.get_content_as_message_markdown().to_string()
+        })
+    }
+
+    /// The composer HTML, along with where each node of the model ended up
+    /// in it, to map clicks and selections in the browser back to model
+    /// positions.
+    pub fn get_content_as_html_with_source_map(&self) -> HtmlSourceMap {
+        self.measured("get_content_as_html_with_source_map", |model| {
+            let (html, entries) = model.get_content_as_html_with_source_map();
+            HtmlSourceMap {
+                html: html.to_string(),
+                entries: entries
+                    .into_iter()
+                    .map(|inner| HtmlSourceMapEntry { inner })
+                    .collect(),
+            }
+        })
+    }
+
+    pub fn get_selection_as_markdown(&self) -> String {
+        self.measured("get_selection_as_markdown", |model| {
+            model.get_selection_as_markdown().to_string()
+        })
+    }
+
+    pub fn get_selection_as_plain_text(&self) -> String {
+        self.measured("get_selection_as_plain_text", |model| {
+            model.get_selection_as_plain_text().to_string()
+        })
+    }
+
+    pub fn selection_stats(&self) -> SelectionStats {
+        self.measured("selection_stats", |model| {
+            SelectionStats::from(model.selection_stats())
+        })
+    }
+
+    /// Whether indent and unindent are possible at the selection, and how
+    /// deeply the selected list items are nested.
+    pub fn get_list_indentation(&self) -> ListIndentation {
+        self.measured("get_list_indentation", |model| {
+            ListIndentation::from(model.get_list_indentation())
+        })
+    }
+
+    /// The top-level blocks of the content, to split a long message
+    /// between several events without cutting through a block.
+    pub fn get_blocks(&self) -> Blocks {
+        self.measured("get_blocks", |model| Blocks {
+            inner: model
+                .get_blocks()
+                .into_iter()
+                .map(BlockInfo::from)
+                .collect(),
+        })
+    }
+
+    pub fn get_block(&self, index: u32) -> Option<BlockInfo> {
+        self.measured("get_block", |model| {
+            model.get_block(index as usize).map(BlockInfo::from)
+        })
+    }
+
+    pub fn get_block_revisions(&self) -> Vec<u64> {
+        self.measured("get_block_revisions", |model| {
+            model.get_block_revisions()
+        })
     }
 
     pub fn get_content_as_plain_text(&self) -> String {
-        self.inner.get_content_as_plain_text().to_string()
+        self.measured("get_content_as_plain_text", |model| {
+            model.get_content_as_plain_text().to_string()
+        })
+    }
+
+    pub fn get_content_as_plain_text_with_options(
+        &self,
+        collapse_whitespace: bool,
+        trim: bool,
+    ) -> String {
+        let _timer =
+            self.inner.measure("get_content_as_plain_text_with_options");
+        let options = wysiwyg::PlainTextOptions {
+            collapse_whitespace,
+            trim,
+        };
+        self.inner
+            .get_content_as_plain_text_with_options(options)
+            .to_string()
+    }
+
+    /// The number of characters in the plain text content, counting each
+    /// grapheme as one, e.g. for a character counter.
+    pub fn get_character_count(&self) -> u32 {
+        self.measured("get_character_count", |model| {
+            u32::try_from(model.get_character_count()).unwrap()
+        })
+    }
+
+    pub fn get_word_count(&self) -> u32 {
+        self.measured("get_word_count", |model| {
+            u32::try_from(model.get_word_count()).unwrap()
+        })
+    }
+
+    pub fn content_hash(&self) -> u64 {
+        self.measured("content_hash", |model| model.content_hash())
+    }
+
+    pub fn is_content_empty(&self) -> bool {
+        self.measured("is_content_empty", |model| model.is_content_empty())
+    }
+
+    pub fn get_preview(&self, max_chars: u32) -> String {
+        self.measured("get_preview", |model| {
+            model.get_preview(max_chars as usize).to_string()
+        })
+    }
+
+    pub fn get_content_as_fallback_text(&self) -> String {
+        self.measured("get_content_as_fallback_text", |model| {
+            model.get_content_as_fallback_text().to_string()
+        })
     }
 
     pub fn document(&self) -> DomHandle {
-        DomHandle {
-            inner: self.inner.state.dom.document().handle(),
-        }
+        self.measured("document", |model| DomHandle {
+            inner: model.state.dom.document().handle(),
+        })
     }
 
     pub fn action_states(&self) -> js_sys::Map {
-        self.inner.action_states().into_ffi()
+        self.measured("action_states", |model| model.action_states().into_ffi())
+    }
+
+    pub fn undo_depth(&self) -> u32 {
+        self.measured("undo_depth", |model| {
+            u32::try_from(model.undo_depth()).unwrap()
+        })
+    }
+
+    pub fn redo_depth(&self) -> u32 {
+        self.measured("redo_depth", |model| {
+            u32::try_from(model.redo_depth()).unwrap()
+        })
+    }
+
+    pub fn disabled_reasons(&self) -> js_sys::Map {
+        self.measured("disabled_reasons", |model| {
+            model.disabled_reasons().into_ffi()
+        })
+    }
+
+    /// Perform `action` the way its toolbar button would, e.g. toggle bold.
+    pub fn apply_action(&mut self, action: ComposerAction) -> ComposerUpdate {
+        self.measured_mut("apply_action", |model| {
+            ComposerUpdate::from(model.apply_action((&action).into()))
+        })
+    }
+
+    /// Every action with its current state, to build a toolbar from.
+    pub fn available_actions(&self) -> Actions {
+        self.measured("available_actions", |model| Actions {
+            inner: model
+                .available_actions()
+                .iter()
+                .map(ActionInfo::from)
+                .collect(),
+        })
     }
 
     pub fn select(
@@ -165,147 +397,495 @@ impl ComposerModel {
         start_utf16_codeunit: u32,
         end_utf16_codeunit: u32,
     ) -> ComposerUpdate {
-        ComposerUpdate::from(self.inner.select(
-            wysiwyg::Location::from(
-                usize::try_from(start_utf16_codeunit).unwrap(),
-            ),
-            wysiwyg::Location::from(
-                usize::try_from(end_utf16_codeunit).unwrap(),
-            ),
-        ))
+        self.measured_mut("select", |model| {
+            ComposerUpdate::from(model.select(
+                wysiwyg::Location::from(
+                    usize::try_from(start_utf16_codeunit).unwrap(),
+                ),
+                wysiwyg::Location::from(
+                    usize::try_from(end_utf16_codeunit).unwrap(),
+                ),
+            ))
+        })
+    }
+
+    pub fn move_cursor(
+        &mut self,
+        direction: Direction,
+        granularity: Granularity,
+    ) -> ComposerUpdate {
+        self.measured_mut("move_cursor", |model| {
+            ComposerUpdate::from(
+                model.move_cursor(direction.into(), granularity.into()),
+            )
+        })
+    }
+
+    pub fn line_start_offset(&self, utf16_codeunit: u32) -> u32 {
+        self.measured("line_start_offset", |model| {
+            let start: usize = model
+                .line_start_offset(wysiwyg::Location::from(
+                    usize::try_from(utf16_codeunit).unwrap(),
+                ))
+                .into();
+            u32::try_from(start).unwrap()
+        })
+    }
+
+    pub fn line_end_offset(&self, utf16_codeunit: u32) -> u32 {
+        self.measured("line_end_offset", |model| {
+            let end: usize = model
+                .line_end_offset(wysiwyg::Location::from(
+                    usize::try_from(utf16_codeunit).unwrap(),
+                ))
+                .into();
+            u32::try_from(end).unwrap()
+        })
+    }
+
+    pub fn word_range_at(&self, utf16_codeunit: u32) -> Selection {
+        self.measured("word_range_at", |model| {
+            let (start, end) = model.word_range_at(wysiwyg::Location::from(
+                usize::try_from(utf16_codeunit).unwrap(),
+            ));
+            let start: usize = start.into();
+            let end: usize = end.into();
+            Selection {
+                start_utf16_codeunit: start as u32,
+                end_utf16_codeunit: end as u32,
+            }
+        })
+    }
+
+    pub fn get_clipboard_content(
+        &self,
+        start_utf16_codeunit: u32,
+        end_utf16_codeunit: u32,
+    ) -> ClipboardContent {
+        self.measured("get_clipboard_content", |model| {
+            let content = model.get_clipboard_content(
+                wysiwyg::Location::from(
+                    usize::try_from(start_utf16_codeunit).unwrap(),
+                ),
+                wysiwyg::Location::from(
+                    usize::try_from(end_utf16_codeunit).unwrap(),
+                ),
+            );
+            ClipboardContent {
+                html: content.html.to_string(),
+                plain_text: content.plain_text.to_string(),
+            }
+        })
+    }
+
+    pub fn cut_selection(&mut self) -> CutResult {
+        self.measured_mut("cut_selection", |model| {
+            let (content, update) = model.cut_selection();
+            CutResult {
+                content: ClipboardContent {
+                    html: content.html.to_string(),
+                    plain_text: content.plain_text.to_string(),
+                },
+                update: ComposerUpdate::from(update),
+            }
+        })
+    }
+
+    pub fn move_range(
+        &mut self,
+        src_start_utf16_codeunit: u32,
+        src_end_utf16_codeunit: u32,
+        dest_utf16_codeunit: u32,
+    ) -> ComposerUpdate {
+        self.measured_mut("move_range", |model| {
+            ComposerUpdate::from(model.move_range(
+                wysiwyg::Location::from(
+                    usize::try_from(src_start_utf16_codeunit).unwrap(),
+                ),
+                wysiwyg::Location::from(
+                    usize::try_from(src_end_utf16_codeunit).unwrap(),
+                ),
+                wysiwyg::Location::from(
+                    usize::try_from(dest_utf16_codeunit).unwrap(),
+                ),
+            ))
+        })
     }
 
     pub fn selection_start(&self) -> u32 {
-        let ret: usize = self.inner.state.start.into();
-        ret as u32
+        self.measured("selection_start", |model| {
+            let ret: usize = model.state.start.into();
+            ret as u32
+        })
     }
 
     pub fn selection_end(&self) -> u32 {
-        let ret: usize = self.inner.state.end.into();
-        ret as u32
+        self.measured("selection_end", |model| {
+            let ret: usize = model.state.end.into();
+            ret as u32
+        })
     }
 
     pub fn replace_text(&mut self, new_text: &str) -> ComposerUpdate {
-        // Conversion here to UTF-16, which has presumably just been
+        self.measured_mut(
+            "replace_text",
+            |model| // Conversion here to UTF-16, which has presumably just been
         // converted to UTF-8 in the FFI bindings layer.
         // If the performance is a problem, we could fix this.
         // Internal task to track this: PSU-739
         ComposerUpdate::from(
-            self.inner.replace_text(Utf16String::from_str(new_text)),
+            model.replace_text(Utf16String::from_str(new_text)),
+        ),
         )
     }
 
+    pub fn insert_emoji(&mut self, emoji: &str) -> ComposerUpdate {
+        self.measured_mut("insert_emoji", |model| {
+            ComposerUpdate::from(
+                model.insert_emoji(Utf16String::from_str(emoji)),
+            )
+        })
+    }
+
+    pub fn insert_text_without_formatting(
+        &mut self,
+        content: &str,
+    ) -> ComposerUpdate {
+        self.measured_mut("insert_text_without_formatting", |model| {
+            ComposerUpdate::from(
+                model.insert_text_without_formatting(Utf16String::from_str(
+                    content,
+                )),
+            )
+        })
+    }
+
     pub fn replace_text_suggestion(
         &mut self,
         new_text: &str,
         suggestion: &SuggestionPattern,
         append_space: bool,
     ) -> ComposerUpdate {
-        ComposerUpdate::from(self.inner.replace_text_suggestion(
-            Utf16String::from_str(new_text),
-            wysiwyg::SuggestionPattern::from(suggestion.clone()),
-            append_space,
-        ))
+        self.measured_mut("replace_text_suggestion", |model| {
+            ComposerUpdate::from(model.replace_text_suggestion(
+                Utf16String::from_str(new_text),
+                wysiwyg::SuggestionPattern::from(suggestion.clone()),
+                append_space,
+            ))
+        })
     }
 
     pub fn set_content_from_html(
         &mut self,
         text: &str,
     ) -> Result<ComposerUpdate, DomCreationError> {
-        let update = self
-            .inner
-            .set_content_from_html(&Utf16String::from_str(text))?;
-        Ok(ComposerUpdate::from(update))
+        self.measured_mut("set_content_from_html", |model| {
+            let update =
+                model.set_content_from_html(&Utf16String::from_str(text))?;
+            Ok(ComposerUpdate::from(update))
+        })
     }
 
     pub fn set_content_from_markdown(
         &mut self,
         text: &str,
     ) -> Result<ComposerUpdate, DomCreationError> {
-        let markdown = self
-            .inner
-            .set_content_from_markdown(&Utf16String::from_str(text))?;
-        Ok(ComposerUpdate::from(markdown))
+        self.measured_mut("set_content_from_markdown", |model| {
+            let markdown = model
+                .set_content_from_markdown(&Utf16String::from_str(text))?;
+            Ok(ComposerUpdate::from(markdown))
+        })
+    }
+
+    /// Run a command by name, with its arguments as a JSON object. See
+    /// `get_commands_as_json` for the commands and their arguments.
+    pub fn execute(
+        &mut self,
+        name: &str,
+        args: &str,
+    ) -> Result<ComposerUpdate, String> {
+        self.measured_mut("execute", |model| {
+            let update =
+                model.execute(name, args).map_err(|e| e.to_string())?;
+            Ok(ComposerUpdate::from(update))
+        })
+    }
+
+    pub fn get_commands_as_json(&self) -> String {
+        self.measured("get_commands_as_json", |model| {
+            model.get_commands_as_json()
+        })
+    }
+
+    /// The content, selection and undo history as an ArrayBuffer, which can
+    /// be transferred to a worker and passed to `import_state` there.
+    pub fn export_state(&self) -> js_sys::ArrayBuffer {
+        self.measured("export_state", |model| {
+            js_sys::Uint8Array::from(model.export_state().as_slice()).buffer()
+        })
+    }
+
+    /// Replace the content, selection and undo history with those from
+    /// `export_state`, keeping the settings of this model.
+    pub fn import_state(
+        &mut self,
+        state: &js_sys::ArrayBuffer,
+    ) -> Result<ComposerUpdate, String> {
+        self.measured_mut("import_state", |model| {
+            let bytes = js_sys::Uint8Array::new(state).to_vec();
+            let update =
+                model.import_state(&bytes).map_err(|e| e.to_string())?;
+            Ok(ComposerUpdate::from(update))
+        })
     }
 
     pub fn clear(&mut self) -> ComposerUpdate {
-        ComposerUpdate::from(self.inner.clear())
+        self.measured_mut("clear", |model| ComposerUpdate::from(model.clear()))
     }
 
     pub fn enter(&mut self) -> ComposerUpdate {
-        ComposerUpdate::from(self.inner.enter())
+        self.measured_mut("enter", |model| ComposerUpdate::from(model.enter()))
+    }
+
+    pub fn insert_line_break(&mut self) -> ComposerUpdate {
+        self.measured_mut("insert_line_break", |model| {
+            ComposerUpdate::from(model.insert_line_break())
+        })
     }
 
     pub fn backspace(&mut self) -> ComposerUpdate {
-        ComposerUpdate::from(self.inner.backspace())
+        self.measured_mut("backspace", |model| {
+            ComposerUpdate::from(model.backspace())
+        })
     }
 
     pub fn backspace_word(&mut self) -> ComposerUpdate {
-        ComposerUpdate::from(self.inner.backspace_word())
+        self.measured_mut("backspace_word", |model| {
+            ComposerUpdate::from(model.backspace_word())
+        })
     }
 
     pub fn delete(&mut self) -> ComposerUpdate {
-        ComposerUpdate::from(self.inner.delete())
+        self.measured_mut("delete", |model| {
+            ComposerUpdate::from(model.delete())
+        })
     }
 
     pub fn delete_word(&mut self) -> ComposerUpdate {
-        ComposerUpdate::from(self.inner.delete_word())
+        self.measured_mut("delete_word", |model| {
+            ComposerUpdate::from(model.delete_word())
+        })
     }
 
     pub fn bold(&mut self) -> ComposerUpdate {
-        ComposerUpdate::from(self.inner.bold())
+        self.measured_mut("bold", |model| ComposerUpdate::from(model.bold()))
     }
 
     pub fn italic(&mut self) -> ComposerUpdate {
-        ComposerUpdate::from(self.inner.italic())
+        self.measured_mut("italic", |model| {
+            ComposerUpdate::from(model.italic())
+        })
     }
 
     pub fn strike_through(&mut self) -> ComposerUpdate {
-        ComposerUpdate::from(self.inner.strike_through())
+        self.measured_mut("strike_through", |model| {
+            ComposerUpdate::from(model.strike_through())
+        })
     }
 
     pub fn underline(&mut self) -> ComposerUpdate {
-        ComposerUpdate::from(self.inner.underline())
+        self.measured_mut("underline", |model| {
+            ComposerUpdate::from(model.underline())
+        })
     }
 
     pub fn quote(&mut self) -> ComposerUpdate {
-        ComposerUpdate::from(self.inner.quote())
+        self.measured_mut("quote", |model| ComposerUpdate::from(model.quote()))
+    }
+
+    pub fn insert_quote(
+        &mut self,
+        html: &str,
+    ) -> Result<ComposerUpdate, DomCreationError> {
+        self.measured_mut("insert_quote", |model| {
+            let update = model.insert_quote(&Utf16String::from_str(html))?;
+            Ok(ComposerUpdate::from(update))
+        })
+    }
+
+    pub fn insert_reply_quote(
+        &mut self,
+        text: &str,
+        author: String,
+        event_link: Option<String>,
+    ) -> ComposerUpdate {
+        self.measured_mut("insert_reply_quote", |model| {
+            ComposerUpdate::from(model.insert_reply_quote(
+                Utf16String::from_str(text),
+                wysiwyg::QuoteAttribution { author, event_link },
+            ))
+        })
+    }
+
+    /// Nest the selected blocks one quote deeper, or quote them if they
+    /// are not in a quote.
+    pub fn increase_quote_level(&mut self) -> ComposerUpdate {
+        self.measured_mut("increase_quote_level", |model| {
+            ComposerUpdate::from(model.increase_quote_level())
+        })
+    }
+
+    /// Remove the innermost quote around the selection.
+    pub fn decrease_quote_level(&mut self) -> ComposerUpdate {
+        self.measured_mut("decrease_quote_level", |model| {
+            ComposerUpdate::from(model.decrease_quote_level())
+        })
+    }
+
+    /// How many quotes the selection is inside.
+    pub fn get_quote_level(&self) -> u32 {
+        self.measured("get_quote_level", |model| {
+            u32::try_from(model.get_quote_level()).unwrap()
+        })
+    }
+
+    pub fn get_quote_attribution(&self) -> Option<QuoteAttribution> {
+        self.measured("get_quote_attribution", |model| {
+            model.get_quote_attribution().map(QuoteAttribution::from)
+        })
+    }
+
+    /// Set the attribution of the quote at the selection, or remove it if
+    /// `author` is missing.
+    pub fn set_quote_attribution(
+        &mut self,
+        author: Option<String>,
+        event_link: Option<String>,
+    ) -> ComposerUpdate {
+        self.measured_mut("set_quote_attribution", |model| {
+            ComposerUpdate::from(model.set_quote_attribution(author.map(
+                |author| wysiwyg::QuoteAttribution { author, event_link },
+            )))
+        })
     }
 
     pub fn inline_code(&mut self) -> ComposerUpdate {
-        ComposerUpdate::from(self.inner.inline_code())
+        self.measured_mut("inline_code", |model| {
+            ComposerUpdate::from(model.inline_code())
+        })
+    }
+
+    pub fn custom_format(&mut self, tag: &str) -> ComposerUpdate {
+        self.measured_mut("custom_format", |model| {
+            ComposerUpdate::from(model.custom_format(tag))
+        })
+    }
+
+    pub fn set_text_color(&mut self, color: &str) -> ComposerUpdate {
+        self.measured_mut("set_text_color", |model| {
+            ComposerUpdate::from(model.set_text_color(color))
+        })
+    }
+
+    pub fn remove_text_color(&mut self) -> ComposerUpdate {
+        self.measured_mut("remove_text_color", |model| {
+            ComposerUpdate::from(model.remove_text_color())
+        })
+    }
+
+    pub fn set_highlight(&mut self, color: &str) -> ComposerUpdate {
+        self.measured_mut("set_highlight", |model| {
+            ComposerUpdate::from(model.set_highlight(color))
+        })
+    }
+
+    pub fn remove_highlight(&mut self) -> ComposerUpdate {
+        self.measured_mut("remove_highlight", |model| {
+            ComposerUpdate::from(model.remove_highlight())
+        })
     }
 
     pub fn code_block(&mut self) -> ComposerUpdate {
-        ComposerUpdate::from(self.inner.code_block())
+        self.measured_mut("code_block", |model| {
+            ComposerUpdate::from(model.code_block())
+        })
+    }
+
+    pub fn insert_code_block_with_text(
+        &mut self,
+        text: &str,
+        language: Option<String>,
+    ) -> ComposerUpdate {
+        self.measured_mut("insert_code_block_with_text", |model| {
+            ComposerUpdate::from(model.insert_code_block_with_text(
+                Utf16String::from_str(text),
+                language.as_deref(),
+            ))
+        })
     }
 
     pub fn undo(&mut self) -> ComposerUpdate {
-        ComposerUpdate::from(self.inner.undo())
+        self.measured_mut("undo", |model| ComposerUpdate::from(model.undo()))
     }
 
     pub fn redo(&mut self) -> ComposerUpdate {
-        ComposerUpdate::from(self.inner.redo())
+        self.measured_mut("redo", |model| ComposerUpdate::from(model.redo()))
+    }
+
+    pub fn undo_last_formatting(&mut self) -> ComposerUpdate {
+        self.measured_mut("undo_last_formatting", |model| {
+            ComposerUpdate::from(model.undo_last_formatting())
+        })
     }
 
     pub fn ordered_list(&mut self) -> ComposerUpdate {
-        ComposerUpdate::from(self.inner.ordered_list())
+        self.measured_mut("ordered_list", |model| {
+            ComposerUpdate::from(model.ordered_list())
+        })
     }
 
     pub fn unordered_list(&mut self) -> ComposerUpdate {
-        ComposerUpdate::from(self.inner.unordered_list())
+        self.measured_mut("unordered_list", |model| {
+            ComposerUpdate::from(model.unordered_list())
+        })
     }
 
     pub fn indent(&mut self) -> ComposerUpdate {
-        ComposerUpdate::from(self.inner.indent())
+        self.measured_mut("indent", |model| {
+            ComposerUpdate::from(model.indent())
+        })
     }
 
     pub fn unindent(&mut self) -> ComposerUpdate {
-        ComposerUpdate::from(self.inner.unindent())
+        self.measured_mut("unindent", |model| {
+            ComposerUpdate::from(model.unindent())
+        })
+    }
+
+    pub fn handle_tab(&mut self) -> ComposerUpdate {
+        self.measured_mut("handle_tab", |model| {
+            ComposerUpdate::from(model.handle_tab())
+        })
+    }
+
+    pub fn handle_shift_tab(&mut self) -> ComposerUpdate {
+        self.measured_mut("handle_shift_tab", |model| {
+            ComposerUpdate::from(model.handle_shift_tab())
+        })
     }
 
     pub fn get_link_action(&self) -> LinkAction {
-        self.inner.get_link_action().into()
+        self.measured("get_link_action", |model| model.get_link_action().into())
+    }
+
+    pub fn get_selected_link_urls(&self) -> js_sys::Array {
+        self.measured("get_selected_link_urls", |model| {
+            model
+                .get_selected_link_urls()
+                .iter()
+                .map(|url| JsValue::from_str(&url.to_string()))
+                .collect()
+        })
     }
 
     pub fn set_link(
@@ -313,10 +893,14 @@ impl ComposerModel {
         url: &str,
         attributes: js_sys::Map,
     ) -> ComposerUpdate {
-        ComposerUpdate::from(
-            self.inner
-                .set_link(Utf16String::from_str(url), attributes.into_vec()),
-        )
+        self.measured_mut("set_link", |model| {
+            ComposerUpdate::from(
+                model.set_link(
+                    Utf16String::from_str(url),
+                    attributes.into_vec(),
+                ),
+            )
+        })
     }
 
     pub fn set_link_with_text(
@@ -325,20 +909,206 @@ impl ComposerModel {
         text: &str,
         attributes: js_sys::Map,
     ) -> ComposerUpdate {
-        ComposerUpdate::from(self.inner.set_link_with_text(
-            Utf16String::from_str(url),
-            Utf16String::from_str(&html_escape::encode_safe(&text)),
-            attributes.into_vec(),
-        ))
+        self.measured_mut("set_link_with_text", |model| {
+            ComposerUpdate::from(model.set_link_with_text(
+                Utf16String::from_str(url),
+                Utf16String::from_str(&html_escape::encode_safe(&text)),
+                attributes.into_vec(),
+            ))
+        })
     }
 
     pub fn set_custom_suggestion_patterns(
         &mut self,
         custom_suggestion_patterns: js_sys::Array,
     ) {
-        self.inner.set_custom_suggestion_patterns(
-            custom_suggestion_patterns.into_vec(),
-        );
+        self.measured_mut("set_custom_suggestion_patterns", |model| {
+            model.set_custom_suggestion_patterns(
+                custom_suggestion_patterns.into_vec(),
+            );
+        })
+    }
+
+    /// Align the selected paragraphs and headings, or remove their
+    /// alignment if `alignment` is undefined.
+    pub fn set_alignment(
+        &mut self,
+        alignment: Option<Alignment>,
+    ) -> ComposerUpdate {
+        self.measured_mut("set_alignment", |model| {
+            ComposerUpdate::from(model.set_alignment(alignment.map(Into::into)))
+        })
+    }
+
+    /// The alignment of the paragraph or heading at the selection, if set.
+    pub fn get_alignment(&self) -> Option<Alignment> {
+        self.measured("get_alignment", |model| {
+            model.get_alignment().map(Into::into)
+        })
+    }
+
+    /// Write alignments as a `style` or a `data-mx-align` attribute.
+    pub fn set_alignment_attribute(&mut self, attribute: AlignmentAttribute) {
+        self.measured_mut("set_alignment_attribute", |model| {
+            model.set_alignment_attribute(attribute.into())
+        });
+    }
+
+    pub fn set_safe_link_attributes(&mut self, enabled: bool) {
+        self.measured_mut("set_safe_link_attributes", |model| {
+            model.set_safe_link_attributes(enabled)
+        });
+    }
+
+    pub fn set_custom_inline_tags(
+        &mut self,
+        custom_inline_tags: js_sys::Array,
+    ) {
+        self.measured_mut("set_custom_inline_tags", |model| {
+            model.set_custom_inline_tags(custom_inline_tags.into_vec());
+        })
+    }
+
+    pub fn set_locale(&mut self, tag: &str) {
+        self.measured_mut("set_locale", |model| model.set_locale(tag));
+    }
+
+    /// Call `listener` with the name and duration in milliseconds of each
+    /// call on this model, e.g. to track latency. Pass `undefined` to stop.
+    pub fn set_metrics_listener(&mut self, listener: Option<js_sys::Function>) {
+        self.inner.set_metrics_listener(listener.map(|f| {
+            Arc::new(JsMetricsListener(f)) as Arc<dyn wysiwyg::MetricsListener>
+        }));
+    }
+
+    /// Call `listener` with a description of each internal error the model
+    /// recovers from. The call that hit the error leaves the content as it
+    /// was. Pass `undefined` to stop.
+    pub fn set_error_listener(&mut self, listener: Option<js_sys::Function>) {
+        self.inner.set_error_listener(listener.map(|f| {
+            Arc::new(JsErrorListener(f)) as Arc<dyn wysiwyg::ErrorListener>
+        }));
+    }
+
+    /// Rebuild the content from its own HTML if it has become inconsistent
+    /// because of a bug, reporting the problem to the error listener.
+    pub fn recover(&mut self) -> ComposerUpdate {
+        self.measured_mut("recover", |model| {
+            ComposerUpdate::from(model.recover())
+        })
+    }
+
+    /// Format a number, e.g. of an ordered list item, with the digits of
+    /// the locale set with `set_locale`.
+    pub fn format_number(&self, number: u32) -> String {
+        self.measured("format_number", |model| {
+            model
+                .locale()
+                .format_number(usize::try_from(number).unwrap())
+        })
+    }
+
+    /// Inserts a horizontal rule at the current selection, splitting the block it is in
+    pub fn insert_horizontal_rule(&mut self) -> ComposerUpdate {
+        self.measured_mut("insert_horizontal_rule", |model| {
+            ComposerUpdate::from(model.insert_horizontal_rule())
+        })
+    }
+
+    /// Toggle a heading of `level`, from 1 to 6. Other levels do nothing.
+    pub fn heading(&mut self, level: u8) -> ComposerUpdate {
+        self.measured_mut("heading", |model| {
+            ComposerUpdate::from(
+                match wysiwyg::HeadingLevel::from_number(level) {
+                    Some(level) => model.heading(level),
+                    None => wysiwyg::ComposerUpdate::keep_at(model.revision()),
+                },
+            )
+        })
+    }
+
+    /// Turn the selected paragraphs into headings of `level`, from 1 to 6,
+    /// or back into paragraphs if `level` is missing. Other levels do
+    /// nothing.
+    pub fn set_heading(&mut self, level: Option<u8>) -> ComposerUpdate {
+        self.measured_mut("set_heading", |model| {
+            ComposerUpdate::from(match level {
+                None => model.set_heading(None),
+                Some(level) => {
+                    match wysiwyg::HeadingLevel::from_number(level) {
+                        Some(level) => model.set_heading(Some(level)),
+                        None => {
+                            wysiwyg::ComposerUpdate::keep_at(model.revision())
+                        }
+                    }
+                }
+            })
+        })
+    }
+
+    /// The level, from 1 to 6, of the heading at the selection, if any.
+    pub fn get_heading_level(&self) -> Option<u8> {
+        self.measured("get_heading_level", |model| {
+            model.get_heading_level().map(|level| level.number())
+        })
+    }
+
+    /// The headings of the content, e.g. for a pane to navigate it.
+    pub fn get_outline(&self) -> Outline {
+        self.measured("get_outline", |model| Outline {
+            inner: model
+                .get_outline()
+                .into_iter()
+                .map(OutlineEntry::from)
+                .collect(),
+        })
+    }
+
+    /// Inserts maths written in LaTeX at the current selection, as its own
+    /// block if `display` is true, or inline otherwise
+    pub fn insert_math(
+        &mut self,
+        latex: &str,
+        display: bool,
+    ) -> ComposerUpdate {
+        self.measured_mut("insert_math", |model| {
+            ComposerUpdate::from(
+                model.insert_math(Utf16String::from_str(latex), display),
+            )
+        })
+    }
+
+    /// Creates an inline image node and inserts it into the composer at the current selection
+    pub fn insert_image(
+        &mut self,
+        src: &str,
+        alt: &str,
+        width: Option<u32>,
+        height: Option<u32>,
+    ) -> ComposerUpdate {
+        self.measured_mut("insert_image", |model| {
+            ComposerUpdate::from(model.insert_image(
+                Utf16String::from_str(src),
+                Utf16String::from_str(alt),
+                width,
+                height,
+            ))
+        })
+    }
+
+    /// Creates a custom emoji with `shortcode` from the image at `mxc_url`
+    /// and inserts it into the composer at the current selection
+    pub fn insert_custom_emoji(
+        &mut self,
+        shortcode: &str,
+        mxc_url: &str,
+    ) -> ComposerUpdate {
+        self.measured_mut("insert_custom_emoji", |model| {
+            ComposerUpdate::from(model.insert_custom_emoji(
+                Utf16String::from_str(shortcode),
+                Utf16String::from_str(mxc_url),
+            ))
+        })
     }
 
     /// Creates an at-room mention node and inserts it into the composer at the current selection
@@ -346,9 +1116,11 @@ impl ComposerModel {
         &mut self,
         attributes: js_sys::Map,
     ) -> ComposerUpdate {
-        ComposerUpdate::from(
-            self.inner.insert_at_room_mention(attributes.into_vec()),
-        )
+        self.measured_mut("insert_at_room_mention", |model| {
+            ComposerUpdate::from(
+                model.insert_at_room_mention(attributes.into_vec()),
+            )
+        })
     }
 
     /// Creates a mention node and inserts it into the composer at the current selection
@@ -358,11 +1130,13 @@ impl ComposerModel {
         text: &str,
         attributes: js_sys::Map,
     ) -> ComposerUpdate {
-        ComposerUpdate::from(self.inner.insert_mention(
-            Utf16String::from_str(url),
-            Utf16String::from_str(&html_escape::encode_safe(&text)),
-            attributes.into_vec(),
-        ))
+        self.measured_mut("insert_mention", |model| {
+            ComposerUpdate::from(model.insert_mention(
+                Utf16String::from_str(url),
+                Utf16String::from_str(&html_escape::encode_safe(&text)),
+                attributes.into_vec(),
+            ))
+        })
     }
 
     /// Creates an at-room mention node and inserts it into the composer, replacing the
@@ -372,10 +1146,12 @@ impl ComposerModel {
         suggestion: &SuggestionPattern,
         attributes: js_sys::Map,
     ) -> ComposerUpdate {
-        ComposerUpdate::from(self.inner.insert_at_room_mention_at_suggestion(
-            wysiwyg::SuggestionPattern::from(suggestion.clone()),
-            attributes.into_vec(),
-        ))
+        self.measured_mut("insert_at_room_mention_at_suggestion", |model| {
+            ComposerUpdate::from(model.insert_at_room_mention_at_suggestion(
+                wysiwyg::SuggestionPattern::from(suggestion.clone()),
+                attributes.into_vec(),
+            ))
+        })
     }
 
     /// Creates a mention node and inserts it into the composer, replacing the
@@ -387,19 +1163,58 @@ impl ComposerModel {
         suggestion: &SuggestionPattern,
         attributes: js_sys::Map,
     ) -> ComposerUpdate {
-        ComposerUpdate::from(self.inner.insert_mention_at_suggestion(
-            Utf16String::from_str(url),
-            Utf16String::from_str(&html_escape::encode_safe(&text)),
-            wysiwyg::SuggestionPattern::from(suggestion.clone()),
-            attributes.into_vec(),
-        ))
+        self.measured_mut("insert_mention_at_suggestion", |model| {
+            ComposerUpdate::from(model.insert_mention_at_suggestion(
+                Utf16String::from_str(url),
+                Utf16String::from_str(&html_escape::encode_safe(&text)),
+                wysiwyg::SuggestionPattern::from(suggestion.clone()),
+                attributes.into_vec(),
+            ))
+        })
     }
 
     pub fn remove_links(&mut self) -> ComposerUpdate {
-        ComposerUpdate::from(self.inner.remove_links())
+        self.measured_mut("remove_links", |model| {
+            ComposerUpdate::from(model.remove_links())
+        })
+    }
+}
+
+#[derive(Clone)]
+struct JsMetricsListener(js_sys::Function);
+
+// The model is only used on the main thread, which is the only one the
+// function can be called from.
+unsafe impl Send for JsMetricsListener {}
+unsafe impl Sync for JsMetricsListener {}
+
+impl wysiwyg::MetricsListener for JsMetricsListener {
+    fn on_operation(&self, operation: &str, duration: Duration) {
+        // Errors thrown by the listener are not the composer's concern
+        let _ = self.0.call2(
+            &JsValue::NULL,
+            &JsValue::from_str(operation),
+            &JsValue::from_f64(duration.as_secs_f64() * 1000.0),
+        );
+    }
+}
+
+#[derive(Clone)]
+struct JsErrorListener(js_sys::Function);
+
+// See JsMetricsListener
+unsafe impl Send for JsErrorListener {}
+unsafe impl Sync for JsErrorListener {}
+
+impl wysiwyg::ErrorListener for JsErrorListener {
+    fn on_error(&self, error: &wysiwyg::ComposerError) {
+        let _ = self
+            .0
+            .call1(&JsValue::NULL, &JsValue::from_str(&error.to_string()));
     }
 }
 
+#[derive(Clone)]
 #[wasm_bindgen]
 pub struct ComposerUpdate {
     inner: wysiwyg::ComposerUpdate<Utf16String>,
@@ -424,6 +1239,12 @@ impl ComposerUpdate {
     pub fn menu_action(&self) -> MenuAction {
         MenuAction::from(self.inner.menu_action.clone())
     }
+
+    /// The revision of the model once this update is applied. Updates with
+    /// a lower revision than one already applied are out of date.
+    pub fn revision(&self) -> u64 {
+        self.inner.revision
+    }
 }
 
 #[derive(Clone, Debug)]
@@ -483,6 +1304,9 @@ impl TextUpdate {
             wysiwyg::TextUpdate::ReplaceAll(r) => {
                 let start_utf16_codeunit: usize = r.start.into();
                 let end_utf16_codeunit: usize = r.end.into();
+                let changed_start_utf16_codeunit: usize =
+                    r.changed_start.into();
+                let changed_end_utf16_codeunit: usize = r.changed_end.into();
                 Self {
                     keep: None,
                     replace_all: Some(ReplaceAll {
@@ -493,6 +1317,14 @@ impl TextUpdate {
                         .unwrap(),
                         end_utf16_codeunit: u32::try_from(end_utf16_codeunit)
                             .unwrap(),
+                        changed_start_utf16_codeunit: u32::try_from(
+                            changed_start_utf16_codeunit,
+                        )
+                        .unwrap(),
+                        changed_end_utf16_codeunit: u32::try_from(
+                            changed_end_utf16_codeunit,
+                        )
+                        .unwrap(),
                     }),
                     select: None,
                 }
@@ -527,6 +1359,73 @@ pub struct ReplaceAll {
     pub replacement_html: String,
     pub start_utf16_codeunit: u32,
     pub end_utf16_codeunit: u32,
+    pub changed_start_utf16_codeunit: u32,
+    pub changed_end_utf16_codeunit: u32,
+}
+
+#[derive(Clone)]
+#[wasm_bindgen(getter_with_clone)]
+pub struct ClipboardContent {
+    pub html: String,
+    pub plain_text: String,
+}
+
+#[wasm_bindgen(getter_with_clone)]
+pub struct CutResult {
+    pub content: ClipboardContent,
+    pub update: ComposerUpdate,
+}
+
+#[derive(Clone)]
+#[wasm_bindgen(getter_with_clone)]
+pub struct ListIndentation {
+    pub can_indent: bool,
+    pub can_unindent: bool,
+    pub depth: u32,
+}
+
+impl From<wysiwyg::ListIndentation> for ListIndentation {
+    fn from(inner: wysiwyg::ListIndentation) -> Self {
+        Self {
+            can_indent: inner.can_indent,
+            can_unindent: inner.can_unindent,
+            depth: u32::try_from(inner.depth).unwrap(),
+        }
+    }
+}
+
+#[derive(Clone)]
+#[wasm_bindgen(getter_with_clone)]
+pub struct QuoteAttribution {
+    pub author: String,
+    pub event_link: Option<String>,
+}
+
+impl From<wysiwyg::QuoteAttribution> for QuoteAttribution {
+    fn from(inner: wysiwyg::QuoteAttribution) -> Self {
+        Self {
+            author: inner.author,
+            event_link: inner.event_link,
+        }
+    }
+}
+
+#[derive(Clone)]
+#[wasm_bindgen(getter_with_clone)]
+pub struct SelectionStats {
+    pub character_count: u32,
+    pub word_count: u32,
+    pub spans_multiple_blocks: bool,
+}
+
+impl From<wysiwyg::SelectionStats> for SelectionStats {
+    fn from(inner: wysiwyg::SelectionStats) -> Self {
+        Self {
+            character_count: u32::try_from(inner.character_count).unwrap(),
+            word_count: u32::try_from(inner.word_count).unwrap(),
+            spans_multiple_blocks: inner.spans_multiple_blocks,
+        }
+    }
 }
 
 #[derive(Clone)]
@@ -567,12 +1466,20 @@ impl MenuState {
 #[derive(Debug)]
 pub struct MenuStateUpdate {
     pub action_states: js_sys::Map,
+    pub disabled_reasons: js_sys::Map,
+    pub undo_depth: u32,
+    pub redo_depth: u32,
+    pub quote_depth: u32,
 }
 
 impl MenuStateUpdate {
     pub fn from(inner: &wysiwyg::MenuStateUpdate) -> Self {
         Self {
             action_states: inner.action_states.into_ffi(),
+            disabled_reasons: inner.disabled_reasons.into_ffi(),
+            undo_depth: u32::try_from(inner.undo_depth).unwrap(),
+            redo_depth: u32::try_from(inner.redo_depth).unwrap(),
+            quote_depth: u32::try_from(inner.quote_depth).unwrap(),
         }
     }
 }
@@ -627,6 +1534,8 @@ pub enum ComposerAction {
     Underline,
     InlineCode,
     Link,
+    CreateLinkWithText,
+    EditLink,
     Undo,
     Redo,
     OrderedList,
@@ -635,6 +1544,12 @@ pub enum ComposerAction {
     Unindent,
     CodeBlock,
     Quote,
+    Heading,
+    Highlight,
+    AlignLeft,
+    AlignCenter,
+    AlignRight,
+    AlignJustify,
 }
 
 impl ComposerAction {
@@ -646,6 +1561,10 @@ impl ComposerAction {
             wysiwyg::ComposerAction::Underline => Self::Underline,
             wysiwyg::ComposerAction::InlineCode => Self::InlineCode,
             wysiwyg::ComposerAction::Link => Self::Link,
+            wysiwyg::ComposerAction::CreateLinkWithText => {
+                Self::CreateLinkWithText
+            }
+            wysiwyg::ComposerAction::EditLink => Self::EditLink,
             wysiwyg::ComposerAction::Undo => Self::Undo,
             wysiwyg::ComposerAction::Redo => Self::Redo,
             wysiwyg::ComposerAction::OrderedList => Self::OrderedList,
@@ -654,6 +1573,12 @@ impl ComposerAction {
             wysiwyg::ComposerAction::Unindent => Self::Unindent,
             wysiwyg::ComposerAction::CodeBlock => Self::CodeBlock,
             wysiwyg::ComposerAction::Quote => Self::Quote,
+            wysiwyg::ComposerAction::Heading => Self::Heading,
+            wysiwyg::ComposerAction::Highlight => Self::Highlight,
+            wysiwyg::ComposerAction::AlignLeft => Self::AlignLeft,
+            wysiwyg::ComposerAction::AlignCenter => Self::AlignCenter,
+            wysiwyg::ComposerAction::AlignRight => Self::AlignRight,
+            wysiwyg::ComposerAction::AlignJustify => Self::AlignJustify,
         }
     }
 }
@@ -667,6 +1592,8 @@ impl From<&ComposerAction> for wysiwyg::ComposerAction {
             ComposerAction::Underline => Self::Underline,
             ComposerAction::InlineCode => Self::InlineCode,
             ComposerAction::Link => Self::Link,
+            ComposerAction::CreateLinkWithText => Self::CreateLinkWithText,
+            ComposerAction::EditLink => Self::EditLink,
             ComposerAction::Undo => Self::Undo,
             ComposerAction::Redo => Self::Redo,
             ComposerAction::OrderedList => Self::OrderedList,
@@ -675,6 +1602,12 @@ impl From<&ComposerAction> for wysiwyg::ComposerAction {
             ComposerAction::Unindent => Self::Unindent,
             ComposerAction::CodeBlock => Self::CodeBlock,
             ComposerAction::Quote => Self::Quote,
+            ComposerAction::Heading => Self::Heading,
+            ComposerAction::Highlight => Self::Highlight,
+            ComposerAction::AlignLeft => Self::AlignLeft,
+            ComposerAction::AlignCenter => Self::AlignCenter,
+            ComposerAction::AlignRight => Self::AlignRight,
+            ComposerAction::AlignJustify => Self::AlignJustify,
         }
     }
 }
@@ -686,6 +1619,7 @@ pub struct SuggestionPattern {
     pub text: String,
     pub start: u32,
     pub end: u32,
+    pub revision: u64,
 }
 
 impl From<wysiwyg::SuggestionPattern> for SuggestionPattern {
@@ -695,6 +1629,7 @@ impl From<wysiwyg::SuggestionPattern> for SuggestionPattern {
             text: inner.text,
             start: u32::try_from(inner.start).unwrap(),
             end: u32::try_from(inner.end).unwrap(),
+            revision: inner.revision,
         }
     }
 }
@@ -706,6 +1641,86 @@ impl From<SuggestionPattern> for wysiwyg::SuggestionPattern {
             text: pattern.text,
             start: usize::try_from(pattern.start).unwrap(),
             end: usize::try_from(pattern.end).unwrap(),
+            revision: pattern.revision,
+        }
+    }
+}
+
+#[wasm_bindgen]
+#[derive(Clone)]
+pub enum Direction {
+    Forwards,
+    Backwards,
+}
+
+impl From<Direction> for wysiwyg::Direction {
+    fn from(direction: Direction) -> Self {
+        match direction {
+            Direction::Forwards => Self::Forwards,
+            Direction::Backwards => Self::Backwards,
+        }
+    }
+}
+
+#[wasm_bindgen]
+#[derive(Clone)]
+pub enum Granularity {
+    Character,
+    Word,
+}
+
+impl From<Granularity> for wysiwyg::Granularity {
+    fn from(granularity: Granularity) -> Self {
+        match granularity {
+            Granularity::Character => Self::Character,
+            Granularity::Word => Self::Word,
+        }
+    }
+}
+
+#[wasm_bindgen]
+#[derive(Clone, Copy)]
+pub enum Alignment {
+    Left,
+    Center,
+    Right,
+    Justify,
+}
+
+impl From<Alignment> for wysiwyg::Alignment {
+    fn from(alignment: Alignment) -> Self {
+        match alignment {
+            Alignment::Left => Self::Left,
+            Alignment::Center => Self::Center,
+            Alignment::Right => Self::Right,
+            Alignment::Justify => Self::Justify,
+        }
+    }
+}
+
+impl From<wysiwyg::Alignment> for Alignment {
+    fn from(alignment: wysiwyg::Alignment) -> Self {
+        match alignment {
+            wysiwyg::Alignment::Left => Self::Left,
+            wysiwyg::Alignment::Center => Self::Center,
+            wysiwyg::Alignment::Right => Self::Right,
+            wysiwyg::Alignment::Justify => Self::Justify,
+        }
+    }
+}
+
+#[wasm_bindgen]
+#[derive(Clone)]
+pub enum AlignmentAttribute {
+    Style,
+    Data,
+}
+
+impl From<AlignmentAttribute> for wysiwyg::AlignmentAttribute {
+    fn from(attribute: AlignmentAttribute) -> Self {
+        match attribute {
+            AlignmentAttribute::Style => Self::Style,
+            AlignmentAttribute::Data => Self::Data,
         }
     }
 }
@@ -790,6 +1805,160 @@ impl FromIterator<DomHandle> for DomChildren {
     }
 }
 
+/// HTML and its source map entries, which are returned one by one to work
+/// around the lack of support for returning Vec<T> in wasm_bindgen.
+#[wasm_bindgen]
+pub struct HtmlSourceMap {
+    html: String,
+    entries: VecDeque<HtmlSourceMapEntry>,
+}
+
+#[wasm_bindgen]
+impl HtmlSourceMap {
+    pub fn html(&self) -> String {
+        self.html.clone()
+    }
+
+    pub fn next_entry(&mut self) -> Option<HtmlSourceMapEntry> {
+        self.entries.pop_front()
+    }
+}
+
+#[wasm_bindgen]
+/// Where a node of the model is in the HTML, and which model positions its
+/// content covers.
+pub struct HtmlSourceMapEntry {
+    inner: wysiwyg::HtmlSourceMapEntry,
+}
+
+#[wasm_bindgen]
+impl HtmlSourceMapEntry {
+    pub fn handle(&self) -> DomHandle {
+        DomHandle {
+            inner: self.inner.handle.clone(),
+        }
+    }
+
+    pub fn html_start(&self) -> u32 {
+        self.inner.html_start as u32
+    }
+
+    pub fn html_end(&self) -> u32 {
+        self.inner.html_end as u32
+    }
+
+    pub fn start(&self) -> u32 {
+        self.inner.start as u32
+    }
+
+    pub fn end(&self) -> u32 {
+        self.inner.end as u32
+    }
+}
+
+/// The actions and their states, which are returned one by one to work
+/// around the lack of support for returning Vec<T> in wasm_bindgen.
+#[wasm_bindgen]
+pub struct Actions {
+    inner: VecDeque<ActionInfo>,
+}
+
+#[wasm_bindgen]
+impl Actions {
+    pub fn next_action(&mut self) -> Option<ActionInfo> {
+        self.inner.pop_front()
+    }
+}
+
+#[derive(Clone)]
+#[wasm_bindgen(getter_with_clone)]
+pub struct ActionInfo {
+    pub action: ComposerAction,
+    pub state: ActionState,
+    /// Why the action is disabled, if it is.
+    pub disabled_reason: Option<String>,
+}
+
+impl ActionInfo {
+    fn from(inner: &wysiwyg::ActionInfo) -> Self {
+        Self {
+            action: ComposerAction::from(&inner.action),
+            state: ActionState::from(&inner.state),
+            disabled_reason: inner
+                .disabled_reason
+                .map(|reason| reason.as_ref().to_owned()),
+        }
+    }
+}
+
+/// The blocks of the content, which are returned one by one to work around
+/// the lack of support for returning Vec<T> in wasm_bindgen.
+#[wasm_bindgen]
+pub struct Blocks {
+    inner: VecDeque<BlockInfo>,
+}
+
+#[wasm_bindgen]
+impl Blocks {
+    pub fn next_block(&mut self) -> Option<BlockInfo> {
+        self.inner.pop_front()
+    }
+}
+
+#[derive(Clone)]
+#[wasm_bindgen(getter_with_clone)]
+pub struct BlockInfo {
+    pub start: u32,
+    pub end: u32,
+    pub html: String,
+    pub revision: u64,
+}
+
+impl BlockInfo {
+    fn from(inner: wysiwyg::BlockInfo<Utf16String>) -> Self {
+        Self {
+            start: inner.start as u32,
+            end: inner.end as u32,
+            html: inner.html.to_string(),
+            revision: inner.revision,
+        }
+    }
+}
+
+/// The headings of the content, which are returned one by one to work
+/// around the lack of support for returning Vec<T> in wasm_bindgen.
+#[wasm_bindgen]
+pub struct Outline {
+    inner: VecDeque<OutlineEntry>,
+}
+
+#[wasm_bindgen]
+impl Outline {
+    pub fn next_entry(&mut self) -> Option<OutlineEntry> {
+        self.inner.pop_front()
+    }
+}
+
+#[derive(Clone)]
+#[wasm_bindgen(getter_with_clone)]
+pub struct OutlineEntry {
+    pub level: u8,
+    pub text: String,
+    pub start: u32,
+    pub end: u32,
+}
+
+impl OutlineEntry {
+    fn from(inner: wysiwyg::OutlineEntry<Utf16String>) -> Self {
+        Self {
+            level: inner.level.number(),
+            text: inner.text.to_string(),
+            start: inner.start as u32,
+            end: inner.end as u32,
+        }
+    }
+}
+
 #[wasm_bindgen]
 /// Refers to a node in the composer model.
 pub struct DomHandle {
@@ -809,6 +1978,8 @@ impl DomHandle {
             wysiwyg::DomNode::Container(_) => "container",
             wysiwyg::DomNode::LineBreak(_) => "line_break",
             wysiwyg::DomNode::Mention(_) => "mention",
+            wysiwyg::DomNode::Image(_) => "image",
+            wysiwyg::DomNode::HorizontalRule(_) => "horizontal_rule",
             wysiwyg::DomNode::Text(_) => "text",
         })
     }
@@ -843,6 +2014,8 @@ impl DomHandle {
             wysiwyg::DomNode::Container(_) => String::from(""),
             wysiwyg::DomNode::LineBreak(_) => String::from(""),
             wysiwyg::DomNode::Mention(node) => node.display_text().to_string(),
+            wysiwyg::DomNode::Image(node) => node.alt().to_string(),
+            wysiwyg::DomNode::HorizontalRule(_) => String::from(""),
             wysiwyg::DomNode::Text(node) => node.data().to_string(),
         }
     }
@@ -857,6 +2030,8 @@ impl DomHandle {
             wysiwyg::DomNode::Container(node) => node.name().to_string(),
             wysiwyg::DomNode::LineBreak(node) => node.name().to_string(),
             wysiwyg::DomNode::Mention(node) => node.name().to_string(),
+            wysiwyg::DomNode::Image(node) => node.name().to_string(),
+            wysiwyg::DomNode::HorizontalRule(node) => node.name().to_string(),
             wysiwyg::DomNode::Text(_) => String::from("-text-"),
         }
     }
@@ -966,4 +2141,18 @@ mod test {
         assert_eq!(children[2].node_type(&model), "text");
         assert_eq!(children[2].text(&model), "4");
     }
+
+    #[test]
+    fn cut_selection_returns_the_content_and_the_update() {
+        let mut model = ComposerModel::new();
+        model.replace_text("foo bar");
+        model.select(4, 7);
+
+        let cut = model.cut_selection();
+
+        assert_eq!(cut.content.plain_text, "bar");
+        assert_eq!(cut.content.html, "bar");
+        assert_eq!(cut.update.revision(), model.revision());
+        assert_eq!(model.get_content_as_html(), "foo\u{a0}");
+    }
 }
@@ -0,0 +1,69 @@
+// Copyright 2023 The Matrix.org Foundation C.I.C.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Benchmarks for typing in the middle of one very long text node, which
+//! moves the whole rest of the node with a contiguous string like
+//! [Utf16String], but only changes the chunk being typed into with a
+//! [RopeString]. Run them with:
+//!
+//! ```text
+//! cargo bench -p wysiwyg --features rope --bench insert_text
+//! ```
+//!
+//! `RopeString` should take roughly the same time at every length, while
+//! `Utf16String` grows with the length of the node.
+
+use criterion::{criterion_group, criterion_main, BenchmarkId, Criterion};
+use widestring::Utf16String;
+use wysiwyg::{Dom, DomNode, RopeString, UnicodeString};
+
+/// The lengths of text node to type into, in code units.
+const LENGTHS: [usize; 3] = [10_000, 100_000, 1_000_000];
+
+/// A Dom holding one paragraph with `len` code units of text.
+fn dom<S: UnicodeString>(len: usize) -> Dom<S> {
+    let text: String = "abcdefghij".chars().cycle().take(len).collect();
+    Dom::new(vec![DomNode::new_paragraph(vec![DomNode::new_text(
+        S::from(text.as_str()),
+    )])])
+}
+
+fn insert_in_text_node(c: &mut Criterion) {
+    let mut group = c.benchmark_group("insert_in_text_node");
+    for len in LENGTHS {
+        let mut utf16 = dom::<Utf16String>(len);
+        group.bench_function(BenchmarkId::new("Utf16String", len), |b| {
+            b.iter(|| {
+                utf16
+                    .replace_text_in(
+                        Utf16String::from_str("a"),
+                        len / 2,
+                        len / 2,
+                    )
+                    .unwrap()
+            })
+        });
+        let mut rope = dom::<RopeString>(len);
+        group.bench_function(BenchmarkId::new("RopeString", len), |b| {
+            b.iter(|| {
+                rope.replace_text_in(RopeString::from("a"), len / 2, len / 2)
+                    .unwrap()
+            })
+        });
+    }
+    group.finish();
+}
+
+criterion_group!(benches, insert_in_text_node);
+criterion_main!(benches);
@@ -0,0 +1,140 @@
+// Copyright 2023 The Matrix.org Foundation C.I.C.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Benchmarks for the work done on every keystroke or selection change:
+//! finding the range of the selection, computing the menu state and
+//! replacing text. Run them with:
+//!
+//! ```text
+//! cargo bench -p wysiwyg --bench menu_state
+//! ```
+//!
+//! # Performance budget
+//!
+//! All of this runs on the UI thread, so it has to fit inside one frame
+//! (16ms) even on a low-end phone. The documents below are much longer
+//! than a typical message (a few hundred blocks), so on a developer laptop
+//! we aim for:
+//!
+//! | Benchmark              | Budget |
+//! |------------------------|--------|
+//! | `find_range/*`         | < 1ms  |
+//! | `compute_menu_state/*` | < 5ms  |
+//! | `replace_text/*`       | < 16ms |
+//!
+//! Benchmarks build with the release profile, which optimises for size
+//! rather than speed, so compare numbers from the same machine and
+//! profile. A change that pushes any of these over budget needs a good
+//! reason.
+
+use criterion::{
+    criterion_group, criterion_main, BatchSize, BenchmarkId, Criterion,
+};
+use widestring::Utf16String;
+use wysiwyg::{ComposerModel, Location};
+
+/// Large documents, in the shapes users actually write.
+fn documents() -> Vec<(&'static str, String)> {
+    let formatted_paragraph = "<p>Some <strong>bold</strong>, some \
+        <em>italic <a href=\"https://matrix.org\">linked</a></em> and some \
+        <code>code</code> text.</p>";
+    let mut nested_lists = String::new();
+    for i in 0..50 {
+        nested_lists.push_str(&format!(
+            "<blockquote><ol><li><p>item {i}</p><ul><li><p>a <del>b</del> \
+            c</p><ol><li>deep <u>item</u></li></ol></li></ul></li></ol>\
+            </blockquote>"
+        ));
+    }
+    vec![
+        ("formatted_paragraphs", formatted_paragraph.repeat(200)),
+        ("nested_lists", nested_lists),
+    ]
+}
+
+fn model(html: &str) -> ComposerModel<Utf16String> {
+    let mut model = ComposerModel::new();
+    model
+        .set_content_from_html(&Utf16String::from_str(html))
+        .unwrap();
+    model
+}
+
+fn find_range(c: &mut Criterion) {
+    let mut group = c.benchmark_group("find_range");
+    for (name, html) in documents() {
+        let model = model(&html);
+        let len = model.state.dom.text_len();
+        group.bench_function(BenchmarkId::new("cursor", name), |b| {
+            b.iter(|| model.state.dom.find_range(len / 2, len / 2))
+        });
+        group.bench_function(BenchmarkId::new("select_all", name), |b| {
+            b.iter(|| model.state.dom.find_range(0, len))
+        });
+    }
+    group.finish();
+}
+
+/// The menu state is computed whenever the selection changes, so select
+/// back and forth between two places to compute it on every iteration.
+fn compute_menu_state(c: &mut Criterion) {
+    let mut group = c.benchmark_group("compute_menu_state");
+    for (name, html) in documents() {
+        let mut model = model(&html);
+        let len = model.state.dom.text_len();
+        group.bench_function(BenchmarkId::new("cursor", name), |b| {
+            let mut pos = len / 2;
+            b.iter(|| {
+                pos = if pos == len / 2 { len / 2 + 1 } else { len / 2 };
+                model.select(Location::from(pos), Location::from(pos))
+            })
+        });
+        group.bench_function(BenchmarkId::new("select_all", name), |b| {
+            let mut start = 0;
+            b.iter(|| {
+                start = 1 - start;
+                model.select(Location::from(start), Location::from(len))
+            })
+        });
+    }
+    group.finish();
+}
+
+fn replace_text(c: &mut Criterion) {
+    let mut group = c.benchmark_group("replace_text");
+    for (name, html) in documents() {
+        let mut model = model(&html);
+        let len = model.state.dom.text_len();
+        model.select(Location::from(len / 2), Location::from(len / 2));
+        group.bench_function(BenchmarkId::new("cursor", name), |b| {
+            b.iter_batched_ref(
+                || model.clone(),
+                |model| model.replace_text(Utf16String::from_str("a")),
+                BatchSize::SmallInput,
+            )
+        });
+        model.select(Location::from(len / 3), Location::from(2 * len / 3));
+        group.bench_function(BenchmarkId::new("selection", name), |b| {
+            b.iter_batched_ref(
+                || model.clone(),
+                |model| model.replace_text(Utf16String::from_str("a")),
+                BatchSize::SmallInput,
+            )
+        });
+    }
+    group.finish();
+}
+
+criterion_group!(benches, find_range, compute_menu_state, replace_text);
+criterion_main!(benches);
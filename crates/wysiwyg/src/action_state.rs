@@ -14,9 +14,42 @@
 
 use strum_macros::AsRefStr;
 
+use crate::ComposerAction;
+
 #[derive(AsRefStr, Clone, Debug, PartialEq, Eq)]
 pub enum ActionState {
     Enabled,
     Reversed,
     Disabled,
 }
+
+/// Why an action is [ActionState::Disabled], e.g. to explain it in a
+/// tooltip.
+#[derive(AsRefStr, Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum DisabledReason {
+    /// There is nothing to undo, or nothing to redo.
+    HistoryEmpty,
+    /// The selection isn't in a list item which can be indented.
+    CannotIndent,
+    /// The selection isn't in a list item which can be unindented.
+    CannotUnindent,
+    /// The selection contains a link which can't be changed, e.g. a
+    /// mention.
+    ImmutableLink,
+    /// The selection is in inline code, which can't have other formatting.
+    InInlineCode,
+    /// The selection is in a code block.
+    InCodeBlock,
+    /// The action doesn't apply to the selection, e.g. editing a link when
+    /// no link is selected.
+    NotApplicable,
+}
+
+/// An action with its state, e.g. to build a toolbar button for it.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ActionInfo {
+    pub action: ComposerAction,
+    pub state: ActionState,
+    /// Why the action is disabled, if it is.
+    pub disabled_reason: Option<DisabledReason>,
+}
@@ -0,0 +1,181 @@
+// Copyright 2023 The Matrix.org Foundation C.I.C.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use crate::UnicodeString;
+
+const STYLE_ATTRIBUTE: &str = "style";
+const DATA_ATTRIBUTE: &str = "data-mx-align";
+const STYLE_PROPERTY: &str = "text-align";
+
+/// How the lines of a paragraph or heading are lined up.
+#[derive(Clone, Copy, Debug, Eq, PartialEq, Hash)]
+pub enum Alignment {
+    Left,
+    Center,
+    Right,
+    Justify,
+}
+
+impl Alignment {
+    /// All the alignments.
+    pub const ALL: [Alignment; 4] = [
+        Alignment::Left,
+        Alignment::Center,
+        Alignment::Right,
+        Alignment::Justify,
+    ];
+
+    /// The alignment as it is written in HTML, e.g. `center`.
+    pub fn name(&self) -> &'static str {
+        match self {
+            Alignment::Left => "left",
+            Alignment::Center => "center",
+            Alignment::Right => "right",
+            Alignment::Justify => "justify",
+        }
+    }
+
+    /// The alignment for a name like `center`, ignoring case and
+    /// surrounding whitespace, or None if it isn't one.
+    pub fn from_name(name: &str) -> Option<Self> {
+        let name = name.trim();
+        Self::ALL
+            .into_iter()
+            .find(|alignment| alignment.name().eq_ignore_ascii_case(name))
+    }
+
+    /// The alignment in the attributes of a paragraph or heading, and the
+    /// attribute it was found in. A `data-mx-align` attribute wins over a
+    /// `text-align` in a `style` attribute.
+    pub(crate) fn from_attributes<'a>(
+        attributes: impl IntoIterator<Item = (&'a str, &'a str)>,
+    ) -> Option<(Self, AlignmentAttribute)> {
+        let mut from_style = None;
+        for (name, value) in attributes {
+            match name {
+                DATA_ATTRIBUTE => {
+                    if let Some(alignment) = Self::from_name(value) {
+                        return Some((alignment, AlignmentAttribute::Data));
+                    }
+                }
+                STYLE_ATTRIBUTE => {
+                    from_style = Self::from_style(value)
+                        .map(|a| (a, AlignmentAttribute::Style));
+                }
+                _ => {}
+            }
+        }
+        from_style
+    }
+
+    /// The alignment given by the last `text-align` declaration in a
+    /// `style` attribute, if it's a valid one.
+    fn from_style(style: &str) -> Option<Self> {
+        style
+            .rsplit(';')
+            .filter_map(|declaration| declaration.split_once(':'))
+            .find(|(property, _)| {
+                property.trim().eq_ignore_ascii_case(STYLE_PROPERTY)
+            })
+            .and_then(|(_, value)| Self::from_name(value))
+    }
+}
+
+/// How the alignment of a paragraph or heading is written in its HTML, see
+/// [crate::ComposerModel::set_alignment_attribute].
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq, Hash)]
+pub enum AlignmentAttribute {
+    /// `style="text-align: center"`, which any HTML renderer understands
+    #[default]
+    Style,
+    /// `data-mx-align="center"`, for hosts which don't allow `style`
+    /// attributes and apply the alignment themselves
+    Data,
+}
+
+impl AlignmentAttribute {
+    /// The attribute storing `alignment` in this way.
+    pub(crate) fn to_attribute<S: UnicodeString>(
+        self,
+        alignment: Alignment,
+    ) -> (S, S) {
+        match self {
+            AlignmentAttribute::Style => (
+                STYLE_ATTRIBUTE.into(),
+                format!("{STYLE_PROPERTY}: {}", alignment.name()).into(),
+            ),
+            AlignmentAttribute::Data => {
+                (DATA_ATTRIBUTE.into(), alignment.name().into())
+            }
+        }
+    }
+
+    /// Whether an attribute called `name` can store an alignment.
+    pub(crate) fn is_alignment_attribute(name: &str) -> bool {
+        name == STYLE_ATTRIBUTE || name == DATA_ATTRIBUTE
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::{Alignment, AlignmentAttribute};
+
+    #[test]
+    fn alignments_round_trip_through_names() {
+        for alignment in Alignment::ALL {
+            assert_eq!(Alignment::from_name(alignment.name()), Some(alignment));
+        }
+        assert_eq!(Alignment::from_name(" CENTER "), Some(Alignment::Center));
+        assert_eq!(Alignment::from_name("middle"), None);
+    }
+
+    #[test]
+    fn alignment_is_read_from_either_attribute() {
+        assert_eq!(
+            Alignment::from_attributes([("style", "text-align: right")]),
+            Some((Alignment::Right, AlignmentAttribute::Style))
+        );
+        assert_eq!(
+            Alignment::from_attributes([("data-mx-align", "justify")]),
+            Some((Alignment::Justify, AlignmentAttribute::Data))
+        );
+    }
+
+    #[test]
+    fn data_attribute_wins_over_style() {
+        assert_eq!(
+            Alignment::from_attributes([
+                ("style", "text-align: right"),
+                ("data-mx-align", "left"),
+            ]),
+            Some((Alignment::Left, AlignmentAttribute::Data))
+        );
+    }
+
+    #[test]
+    fn other_style_declarations_are_ignored() {
+        assert_eq!(
+            Alignment::from_attributes([(
+                "style",
+                "color: red; Text-Align:center;"
+            )]),
+            Some((Alignment::Center, AlignmentAttribute::Style))
+        );
+        assert_eq!(Alignment::from_attributes([("style", "color: red")]), None);
+        assert_eq!(
+            Alignment::from_attributes([("style", "text-align: start")]),
+            None
+        );
+    }
+}
@@ -0,0 +1,32 @@
+// Copyright 2023 The Matrix.org Foundation C.I.C.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use crate::dom::UnicodeString;
+
+/// One of the top-level blocks of the content, e.g. a paragraph, a list or
+/// a quote, with where it is in the content and its message HTML. A long
+/// message can be split between two of these without breaking a block.
+///
+/// `revision` changes whenever the block does, so a renderer can skip
+/// blocks it has already drawn.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct BlockInfo<S>
+where
+    S: UnicodeString,
+{
+    pub start: usize,
+    pub end: usize,
+    pub html: S,
+    pub revision: u64,
+}
@@ -0,0 +1,78 @@
+// Copyright 2023 The Matrix.org Foundation C.I.C.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use core::fmt;
+
+/// A command which can be run by name with `ComposerModel::execute`, e.g.
+/// from a scripting layer or a test harness.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CommandInfo {
+    pub name: &'static str,
+    pub description: &'static str,
+    pub args: &'static [CommandArg],
+}
+
+/// An argument of a [CommandInfo], passed as a member of the JSON object
+/// given to `ComposerModel::execute`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CommandArg {
+    pub name: &'static str,
+    pub arg_type: CommandArgType,
+    pub required: bool,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CommandArgType {
+    /// A JSON string.
+    String,
+    /// A location in the content, as a JSON number of code units.
+    Location,
+    /// HTML attributes, as a JSON object with string values.
+    Attributes,
+}
+
+impl CommandArgType {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::String => "string",
+            Self::Location => "location",
+            Self::Attributes => "attributes",
+        }
+    }
+}
+
+/// Why `ComposerModel::execute` couldn't run a command.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum CommandError {
+    /// No command has this name.
+    UnknownCommand(String),
+    /// The arguments were not valid JSON, or didn't match those of the
+    /// command.
+    InvalidArguments(String),
+    /// The command ran but failed, e.g. because its HTML couldn't be
+    /// parsed.
+    Failed(String),
+}
+
+impl fmt::Display for CommandError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Self::UnknownCommand(name) => write!(f, "unknown command: {name}"),
+            Self::InvalidArguments(message) => {
+                write!(f, "invalid arguments: {message}")
+            }
+            Self::Failed(message) => write!(f, "command failed: {message}"),
+        }
+    }
+}
@@ -22,6 +22,14 @@ pub enum ComposerAction {
     Underline,
     InlineCode,
     Link,
+    /// Whether a new link with its own text can be inserted, i.e. when
+    /// [crate::LinkAction::CreateWithText] would be returned by
+    /// `get_link_action`. Enabled or disabled, never reversed.
+    CreateLinkWithText,
+    /// Whether an existing link can be edited, i.e. when
+    /// [crate::LinkAction::Edit] would be returned by `get_link_action`.
+    /// Enabled or disabled, never reversed.
+    EditLink,
     Undo,
     Redo,
     OrderedList,
@@ -30,4 +38,19 @@ pub enum ComposerAction {
     Unindent,
     CodeBlock,
     Quote,
+    /// Reversed when the selection is inside a heading of any level, see
+    /// `get_heading_level` for which one.
+    Heading,
+    /// Reversed when the selection is highlighted in any colour. Set and
+    /// remove highlights with `set_highlight` and `remove_highlight`.
+    Highlight,
+    /// Reversed when every selected paragraph or heading is explicitly
+    /// aligned left. Set alignments with `set_alignment`.
+    AlignLeft,
+    /// Like [ComposerAction::AlignLeft], for centred text.
+    AlignCenter,
+    /// Like [ComposerAction::AlignLeft], for text aligned right.
+    AlignRight,
+    /// Like [ComposerAction::AlignLeft], for justified text.
+    AlignJustify,
 }
@@ -0,0 +1,271 @@
+// Copyright 2023 The Matrix.org Foundation C.I.C.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use core::fmt;
+use std::sync::{Arc, Mutex};
+
+use crate::metrics::while_reporting;
+use crate::{ComposerModel, ComposerUpdate, DomHandle, UnicodeString};
+
+/// Something that went wrong inside a [ComposerModel] while it was changing
+/// the content, e.g. because a node moved earlier in the same operation.
+/// The operation is abandoned, the content is left as it was before it, and
+/// the error is reported to the listener set with
+/// [ComposerModel::set_error_listener].
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub enum ComposerError {
+    /// The handle doesn't point to a node in the Dom.
+    InvalidHandle(DomHandle),
+    /// The node at the handle should be a container, but it isn't.
+    NotAContainer(DomHandle),
+    /// There is no leaf node at this position, but there should be one.
+    MissingLeaf(usize),
+    /// The Dom broke one of its invariants, as described, and had to be
+    /// rebuilt by [ComposerModel::recover].
+    InvariantViolation(String),
+}
+
+impl fmt::Display for ComposerError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Self::InvalidHandle(handle) if !handle.is_set() => {
+                write!(f, "handle is unset")
+            }
+            Self::InvalidHandle(handle) => {
+                write!(f, "no node at handle {:?}", handle.raw())
+            }
+            Self::NotAContainer(handle) => {
+                write!(
+                    f,
+                    "node at handle {:?} is not a container",
+                    handle.raw()
+                )
+            }
+            Self::MissingLeaf(pos) => write!(f, "no leaf at position {pos}"),
+            Self::InvariantViolation(violation) => {
+                write!(f, "invariant violated: {violation}")
+            }
+        }
+    }
+}
+
+impl std::error::Error for ComposerError {}
+
+/// Told about each [ComposerError] a [ComposerModel] recovered from, e.g. so
+/// that clients can report them. Set with
+/// [ComposerModel::set_error_listener].
+pub trait ErrorListener: Send + Sync {
+    fn on_error(&self, error: &ComposerError);
+}
+
+impl<S> ComposerModel<S>
+where
+    S: UnicodeString,
+{
+    /// Report each error the model recovers from to `listener`, or stop
+    /// reporting if it is None. Errors are kept until they are taken with
+    /// [Self::take_unreported_errors], so that they can be reported once
+    /// the model is no longer locked or borrowed and the listener can use
+    /// it too.
+    pub fn set_error_listener(
+        &mut self,
+        listener: Option<Arc<dyn ErrorListener>>,
+    ) {
+        self.error_listener = listener;
+    }
+
+    /// Give up on the operation which failed with `error`: report it, and
+    /// go back to the state from when the last update was made, dropping
+    /// any undo steps added since.
+    pub(crate) fn recover_from(
+        &mut self,
+        error: ComposerError,
+    ) -> ComposerUpdate<S> {
+        if let Some((before, history_len)) = self.recovery_state.clone() {
+            self.restore_state(before);
+            self.previous_states.truncate(history_len);
+        }
+        self.report_error(&error);
+        ComposerUpdate::keep_at(self.state.revision)
+    }
+
+    /// Remember the current state as the one to go back to if an operation
+    /// fails. The Dom is shared rather than copied, so this is cheap.
+    pub(crate) fn remember_recovery_state(&mut self) {
+        self.recovery_state =
+            Some((self.state.clone(), self.previous_states.len()));
+    }
+
+    /// Keep `error` to tell the error listener about, if there is one.
+    pub(crate) fn report_error(&self, error: &ComposerError) {
+        if self.error_listener.is_some() {
+            self.unreported_errors.0.lock().unwrap().push(error.clone());
+        }
+    }
+
+    /// Take the errors the model recovered from since this was last
+    /// called, to report them to the error listener.
+    pub fn take_unreported_errors(&self) -> UnreportedErrors {
+        UnreportedErrors {
+            listener: self.error_listener.clone(),
+            errors: std::mem::take(
+                &mut self.unreported_errors.0.lock().unwrap(),
+            ),
+        }
+    }
+}
+
+/// The errors a [ComposerModel] recovered from which haven't been taken
+/// yet. Behind a lock, as reading the model can recover from errors too.
+#[derive(Default)]
+pub(crate) struct ErrorQueue(Mutex<Vec<ComposerError>>);
+
+impl Clone for ErrorQueue {
+    fn clone(&self) -> Self {
+        Self(Mutex::new(self.0.lock().unwrap().clone()))
+    }
+}
+
+/// Errors taken from a [ComposerModel] with
+/// [ComposerModel::take_unreported_errors], waiting to be reported to the
+/// error listener.
+#[must_use]
+pub struct UnreportedErrors {
+    listener: Option<Arc<dyn ErrorListener>>,
+    errors: Vec<ComposerError>,
+}
+
+impl UnreportedErrors {
+    /// True if there is nothing to report.
+    pub fn is_empty(&self) -> bool {
+        self.listener.is_none() || self.errors.is_empty()
+    }
+
+    /// Tell the error listener about each error.
+    pub fn report(self) {
+        if let Some(listener) = self.listener {
+            while_reporting(|| {
+                for error in &self.errors {
+                    listener.on_error(error);
+                }
+            });
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::sync::{Arc, Mutex};
+
+    use super::{ComposerError, ErrorListener};
+    use crate::tests::testutils_composer_model::{cm, tx};
+    use crate::tests::testutils_conversion::utf16;
+    use crate::{ComposerUpdate, DomHandle};
+
+    #[derive(Default)]
+    struct Recorder(Mutex<Vec<ComposerError>>);
+
+    impl ErrorListener for Recorder {
+        fn on_error(&self, error: &ComposerError) {
+            self.0.lock().unwrap().push(error.clone());
+        }
+    }
+
+    #[test]
+    fn recovering_restores_the_content_and_reports_the_error() {
+        let recorder = Arc::new(Recorder::default());
+        let mut model = cm("abc|");
+        model.set_error_listener(Some(recorder.clone()));
+        model.push_state_to_history();
+        model.state.dom.replace_text_in(utf16("d"), 0, 1).unwrap();
+
+        let error = ComposerError::InvalidHandle(DomHandle::from_raw(vec![3]));
+        let update = model.recover_from(error.clone());
+        model.take_unreported_errors().report();
+
+        assert_eq!(update, ComposerUpdate::keep_at(model.revision()));
+        assert_eq!(tx(&model), "abc|");
+        assert_eq!(*recorder.0.lock().unwrap(), vec![error]);
+    }
+
+    #[test]
+    fn errors_are_not_reported_until_they_are_taken() {
+        let recorder = Arc::new(Recorder::default());
+        let mut model = cm("|");
+        model.set_error_listener(Some(recorder.clone()));
+        model.push_state_to_history();
+        model.recover_from(ComposerError::MissingLeaf(0));
+        assert!(recorder.0.lock().unwrap().is_empty());
+
+        let errors = model.take_unreported_errors();
+        assert!(!errors.is_empty());
+        errors.report();
+        assert!(model.take_unreported_errors().is_empty());
+        assert_eq!(
+            *recorder.0.lock().unwrap(),
+            vec![ComposerError::MissingLeaf(0)]
+        );
+    }
+
+    #[test]
+    fn recovering_drops_the_undo_step_of_the_failed_operation() {
+        let mut model = cm("|");
+        model.replace_text(utf16("a"));
+        model.push_state_to_history();
+        model.recover_from(ComposerError::MissingLeaf(0));
+        model.undo();
+        assert_eq!(tx(&model), "|");
+    }
+
+    #[test]
+    fn recovering_without_an_undo_step_keeps_the_history() {
+        let mut model = cm("|");
+        model.replace_text(utf16("a"));
+        model.replace_text(utf16("b"));
+        model.state.dom.replace_text_in(utf16("c"), 0, 1).unwrap();
+        model.recover_from(ComposerError::MissingLeaf(0));
+        assert_eq!(tx(&model), "ab|");
+        model.undo();
+        assert_eq!(tx(&model), "a|");
+    }
+
+    #[test]
+    fn errors_are_not_reported_after_the_listener_is_removed() {
+        let recorder = Arc::new(Recorder::default());
+        let mut model = cm("|");
+        model.set_error_listener(Some(recorder.clone()));
+        model.set_error_listener(None);
+        model.push_state_to_history();
+        model.recover_from(ComposerError::MissingLeaf(0));
+        model.take_unreported_errors().report();
+        assert!(recorder.0.lock().unwrap().is_empty());
+    }
+
+    #[test]
+    fn errors_describe_what_went_wrong() {
+        let handle = DomHandle::from_raw(vec![0, 2]);
+        assert_eq!(
+            ComposerError::InvalidHandle(handle.clone()).to_string(),
+            "no node at handle [0, 2]"
+        );
+        assert_eq!(
+            ComposerError::NotAContainer(handle).to_string(),
+            "node at handle [0, 2] is not a container"
+        );
+        assert_eq!(
+            ComposerError::InvalidHandle(DomHandle::new_unset()).to_string(),
+            "handle is unset"
+        );
+    }
+}
@@ -12,21 +12,41 @@
 // See the License for the specific language governing permissions and
 // limitations under the License.
 
+pub(crate) mod action_registry;
+pub mod actions;
+pub mod alignment;
+mod automatic_actions;
 pub mod base;
+pub mod blocks;
+mod changed_range;
+pub mod clipboard;
 pub mod code_block;
+pub mod commands;
+pub mod cursor_movement;
 pub mod delete_text;
 pub mod example_format;
 pub mod format;
 mod format_inline_code;
+pub mod headings;
+pub mod horizontal_rules;
 pub mod hyperlinks;
+pub mod images;
+mod insert_block;
+pub mod linkify;
 pub mod lists;
+pub mod maths;
 pub mod mentions;
 pub mod menu_action;
 pub mod menu_state;
+pub mod move_range;
 pub mod new_lines;
 pub mod quotes;
+pub mod recovery;
 pub mod replace_text;
 pub mod selection;
+pub mod state_export;
+pub mod tab;
 pub mod undo_redo;
+pub mod wrap;
 
 pub use base::ComposerModel;
@@ -0,0 +1,299 @@
+// Copyright 2023 The Matrix.org Foundation C.I.C.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! How the [ActionState] of each [ComposerAction] is worked out.
+//!
+//! Every action has one [ActionRule] in [ACTION_RULES], saying which node
+//! makes it reversed and what disables it. The menu state is computed from
+//! these rules, so a new action only needs a new rule.
+//!
+//! [ActionState]: crate::ActionState
+
+use crate::dom::nodes::{ContainerNode, ContainerNodeKind};
+use crate::{
+    Alignment, ComposerAction, DisabledReason, InlineFormatType, ListType,
+    UnicodeString,
+};
+use DisabledWhen::*;
+
+/// A kind of node which makes an action reversed when the selection is
+/// inside it.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub(crate) enum ReversedBy {
+    Format(InlineFormatType),
+    Link,
+    List(ListType),
+    CodeBlock,
+    Quote,
+    Heading,
+    /// A highlight of any colour
+    Highlight,
+    /// A paragraph or heading with this alignment
+    Alignment(Alignment),
+}
+
+impl ReversedBy {
+    pub(crate) fn matches<S: UnicodeString>(
+        &self,
+        container: &ContainerNode<S>,
+    ) -> bool {
+        match (self, container.kind()) {
+            (Self::Format(format), ContainerNodeKind::Formatting(kind)) => {
+                format == kind
+            }
+            (Self::Link, ContainerNodeKind::Link(_)) => true,
+            (Self::List(list_type), ContainerNodeKind::List(kind)) => {
+                list_type == kind
+            }
+            (Self::CodeBlock, ContainerNodeKind::CodeBlock) => true,
+            (Self::Quote, ContainerNodeKind::Quote) => true,
+            (Self::Heading, ContainerNodeKind::Heading(_)) => true,
+            (
+                Self::Highlight,
+                ContainerNodeKind::Formatting(InlineFormatType::Highlight(_)),
+            ) => true,
+            (Self::Alignment(alignment), _) => {
+                container.alignment() == Some(*alignment)
+            }
+            _ => false,
+        }
+    }
+}
+
+/// Something which disables an action while it's true.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub(crate) enum DisabledWhen {
+    NothingToUndo,
+    NothingToRedo,
+    CannotIndent,
+    CannotUnindent,
+    /// `get_link_action` returns [crate::LinkAction::Disabled].
+    LinkActionDisabled,
+    /// `get_link_action` doesn't return [crate::LinkAction::CreateWithText].
+    NotCreatingLinkWithText,
+    /// `get_link_action` doesn't return [crate::LinkAction::Edit].
+    NotEditingLink,
+    /// The selection contains inline code, or inline code was toggled for
+    /// the next text typed.
+    InInlineCode,
+    /// The selection contains a code block. Ignored when
+    /// [DisabledWhen::InInlineCode] is also true.
+    InCodeBlock,
+}
+
+impl DisabledWhen {
+    pub(crate) fn reason(&self) -> DisabledReason {
+        match self {
+            NothingToUndo | NothingToRedo => DisabledReason::HistoryEmpty,
+            CannotIndent => DisabledReason::CannotIndent,
+            CannotUnindent => DisabledReason::CannotUnindent,
+            LinkActionDisabled => DisabledReason::ImmutableLink,
+            NotCreatingLinkWithText | NotEditingLink => {
+                DisabledReason::NotApplicable
+            }
+            InInlineCode => DisabledReason::InInlineCode,
+            InCodeBlock => DisabledReason::InCodeBlock,
+        }
+    }
+}
+
+pub(crate) struct ActionRule {
+    pub(crate) action: ComposerAction,
+    pub(crate) reversed_by: Option<ReversedBy>,
+    /// The first of these which is true is reported as the reason why the
+    /// action is disabled, so more helpful ones come first.
+    pub(crate) disabled_when: &'static [DisabledWhen],
+}
+
+pub(crate) static ACTION_RULES: [ActionRule; 22] = [
+    ActionRule {
+        action: ComposerAction::Bold,
+        reversed_by: Some(ReversedBy::Format(InlineFormatType::Bold)),
+        disabled_when: &[InInlineCode],
+    },
+    ActionRule {
+        action: ComposerAction::Italic,
+        reversed_by: Some(ReversedBy::Format(InlineFormatType::Italic)),
+        disabled_when: &[InInlineCode],
+    },
+    ActionRule {
+        action: ComposerAction::StrikeThrough,
+        reversed_by: Some(ReversedBy::Format(InlineFormatType::StrikeThrough)),
+        disabled_when: &[InInlineCode],
+    },
+    ActionRule {
+        action: ComposerAction::Underline,
+        reversed_by: Some(ReversedBy::Format(InlineFormatType::Underline)),
+        disabled_when: &[InInlineCode],
+    },
+    ActionRule {
+        action: ComposerAction::InlineCode,
+        reversed_by: Some(ReversedBy::Format(InlineFormatType::InlineCode)),
+        disabled_when: &[InCodeBlock],
+    },
+    ActionRule {
+        action: ComposerAction::Link,
+        reversed_by: Some(ReversedBy::Link),
+        disabled_when: &[LinkActionDisabled, InInlineCode, InCodeBlock],
+    },
+    ActionRule {
+        action: ComposerAction::CreateLinkWithText,
+        reversed_by: None,
+        disabled_when: &[
+            LinkActionDisabled,
+            InInlineCode,
+            InCodeBlock,
+            NotCreatingLinkWithText,
+        ],
+    },
+    ActionRule {
+        action: ComposerAction::EditLink,
+        reversed_by: None,
+        disabled_when: &[
+            LinkActionDisabled,
+            InInlineCode,
+            InCodeBlock,
+            NotEditingLink,
+        ],
+    },
+    ActionRule {
+        action: ComposerAction::Undo,
+        reversed_by: None,
+        disabled_when: &[NothingToUndo],
+    },
+    ActionRule {
+        action: ComposerAction::Redo,
+        reversed_by: None,
+        disabled_when: &[NothingToRedo],
+    },
+    ActionRule {
+        action: ComposerAction::OrderedList,
+        reversed_by: Some(ReversedBy::List(ListType::Ordered)),
+        disabled_when: &[InCodeBlock],
+    },
+    ActionRule {
+        action: ComposerAction::UnorderedList,
+        reversed_by: Some(ReversedBy::List(ListType::Unordered)),
+        disabled_when: &[InCodeBlock],
+    },
+    ActionRule {
+        action: ComposerAction::Indent,
+        reversed_by: None,
+        disabled_when: &[CannotIndent],
+    },
+    ActionRule {
+        action: ComposerAction::Unindent,
+        reversed_by: None,
+        disabled_when: &[CannotUnindent],
+    },
+    ActionRule {
+        action: ComposerAction::CodeBlock,
+        reversed_by: Some(ReversedBy::CodeBlock),
+        disabled_when: &[],
+    },
+    ActionRule {
+        action: ComposerAction::Quote,
+        reversed_by: Some(ReversedBy::Quote),
+        disabled_when: &[InCodeBlock],
+    },
+    ActionRule {
+        action: ComposerAction::Heading,
+        reversed_by: Some(ReversedBy::Heading),
+        disabled_when: &[InCodeBlock],
+    },
+    ActionRule {
+        action: ComposerAction::Highlight,
+        reversed_by: Some(ReversedBy::Highlight),
+        disabled_when: &[InInlineCode],
+    },
+    ActionRule {
+        action: ComposerAction::AlignLeft,
+        reversed_by: Some(ReversedBy::Alignment(Alignment::Left)),
+        disabled_when: &[InCodeBlock],
+    },
+    ActionRule {
+        action: ComposerAction::AlignCenter,
+        reversed_by: Some(ReversedBy::Alignment(Alignment::Center)),
+        disabled_when: &[InCodeBlock],
+    },
+    ActionRule {
+        action: ComposerAction::AlignRight,
+        reversed_by: Some(ReversedBy::Alignment(Alignment::Right)),
+        disabled_when: &[InCodeBlock],
+    },
+    ActionRule {
+        action: ComposerAction::AlignJustify,
+        reversed_by: Some(ReversedBy::Alignment(Alignment::Justify)),
+        disabled_when: &[InCodeBlock],
+    },
+];
+
+/// The actions reversed by `container`, e.g. both
+/// [ComposerAction::Heading] and [ComposerAction::AlignCenter] for a centred
+/// heading.
+pub(crate) fn actions_reversed_by<S: UnicodeString>(
+    container: &ContainerNode<S>,
+) -> impl Iterator<Item = ComposerAction> + '_ {
+    ACTION_RULES
+        .iter()
+        .filter(|rule| {
+            rule.reversed_by
+                .as_ref()
+                .map_or(false, |reversed_by| reversed_by.matches(container))
+        })
+        .map(|rule| rule.action.clone())
+}
+
+/// The action toggling the given format, if any.
+pub(crate) fn action_for_format(
+    format: &InlineFormatType,
+) -> Option<ComposerAction> {
+    ACTION_RULES
+        .iter()
+        .find(|rule| {
+            matches!(&rule.reversed_by, Some(ReversedBy::Format(f)) if f == format)
+        })
+        .map(|rule| rule.action.clone())
+}
+
+#[cfg(test)]
+mod test {
+    use std::collections::HashSet;
+
+    use strum::IntoEnumIterator;
+
+    use super::*;
+
+    #[test]
+    fn every_action_has_exactly_one_rule() {
+        for action in ComposerAction::iter() {
+            let count =
+                ACTION_RULES.iter().filter(|r| r.action == action).count();
+            assert_eq!(count, 1, "{action:?} has {count} rules");
+        }
+    }
+
+    #[test]
+    fn no_two_actions_are_reversed_by_the_same_node() {
+        let mut seen = HashSet::new();
+        for reversed_by in
+            ACTION_RULES.iter().filter_map(|r| r.reversed_by.as_ref())
+        {
+            assert!(
+                seen.insert(format!("{reversed_by:?}")),
+                "{reversed_by:?} reverses more than one action"
+            );
+        }
+    }
+}
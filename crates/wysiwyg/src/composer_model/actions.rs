@@ -0,0 +1,178 @@
+// Copyright 2023 The Matrix.org Foundation C.I.C.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use strum::IntoEnumIterator;
+
+use crate::{
+    ActionInfo, Alignment, ComposerAction, ComposerModel, ComposerUpdate,
+    UnicodeString,
+};
+
+impl<S> ComposerModel<S>
+where
+    S: UnicodeString,
+{
+    /// Perform `action` the way its toolbar button would, e.g. toggle bold
+    /// or remove the quote around the selection if Quote is reversed.
+    ///
+    /// Actions which need more input only do something when they are
+    /// reversed, i.e. links, headings and highlights are removed but can't
+    /// be added this way. Disabled actions do nothing.
+    pub fn apply_action(
+        &mut self,
+        action: ComposerAction,
+    ) -> ComposerUpdate<S> {
+        if self.action_is_disabled(action.clone()) {
+            return ComposerUpdate::keep_at(self.state.revision);
+        }
+        let reversed = self.action_is_reversed(action.clone());
+        match action {
+            ComposerAction::Bold => self.bold(),
+            ComposerAction::Italic => self.italic(),
+            ComposerAction::StrikeThrough => self.strike_through(),
+            ComposerAction::Underline => self.underline(),
+            ComposerAction::InlineCode => self.inline_code(),
+            ComposerAction::Undo => self.undo(),
+            ComposerAction::Redo => self.redo(),
+            ComposerAction::OrderedList => self.ordered_list(),
+            ComposerAction::UnorderedList => self.unordered_list(),
+            ComposerAction::Indent => self.indent(),
+            ComposerAction::Unindent => self.unindent(),
+            ComposerAction::CodeBlock => self.code_block(),
+            ComposerAction::Quote => self.quote(),
+            ComposerAction::AlignLeft => {
+                self.set_alignment((!reversed).then_some(Alignment::Left))
+            }
+            ComposerAction::AlignCenter => {
+                self.set_alignment((!reversed).then_some(Alignment::Center))
+            }
+            ComposerAction::AlignRight => {
+                self.set_alignment((!reversed).then_some(Alignment::Right))
+            }
+            ComposerAction::AlignJustify => {
+                self.set_alignment((!reversed).then_some(Alignment::Justify))
+            }
+            ComposerAction::Link if reversed => self.remove_links(),
+            ComposerAction::Heading if reversed => self.set_heading(None),
+            ComposerAction::Highlight if reversed => self.remove_highlight(),
+            ComposerAction::Link
+            | ComposerAction::CreateLinkWithText
+            | ComposerAction::EditLink
+            | ComposerAction::Heading
+            | ComposerAction::Highlight => {
+                ComposerUpdate::keep_at(self.state.revision)
+            }
+        }
+    }
+
+    /// Every action with its state as last reported, in the order they are
+    /// declared in [ComposerAction].
+    pub fn available_actions(&self) -> Vec<ActionInfo> {
+        ComposerAction::iter()
+            .filter_map(|action| {
+                let state = self.action_states.get(&action)?.clone();
+                let disabled_reason =
+                    self.disabled_reasons.get(&action).copied();
+                Some(ActionInfo {
+                    action,
+                    state,
+                    disabled_reason,
+                })
+            })
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::tests::testutils_composer_model::{cm, tx};
+    use crate::{
+        ActionInfo, ActionState, ComposerAction, ComposerUpdate, DisabledReason,
+    };
+
+    #[test]
+    fn apply_action_toggles_formatting() {
+        let mut model = cm("{abc}|");
+        model.apply_action(ComposerAction::Bold);
+        assert_eq!(tx(&model), "<strong>{abc}|</strong>");
+        model.apply_action(ComposerAction::Bold);
+        assert_eq!(tx(&model), "{abc}|");
+    }
+
+    #[test]
+    fn apply_action_toggles_blocks() {
+        let mut model = cm("abc|");
+        model.apply_action(ComposerAction::Quote);
+        assert_eq!(tx(&model), "<blockquote><p>abc|</p></blockquote>");
+        model.apply_action(ComposerAction::Quote);
+        assert_eq!(tx(&model), "<p>abc|</p>");
+    }
+
+    #[test]
+    fn apply_action_toggles_alignment() {
+        let mut model = cm("<p>abc|</p>");
+        model.apply_action(ComposerAction::AlignCenter);
+        assert_eq!(tx(&model), "<p style=\"text-align: center\">abc|</p>");
+        model.apply_action(ComposerAction::AlignCenter);
+        assert_eq!(tx(&model), "<p>abc|</p>");
+    }
+
+    #[test]
+    fn apply_action_removes_a_reversed_link() {
+        let mut model = cm("<a href=\"https://matrix.org\">{abc}|</a>");
+        model.apply_action(ComposerAction::Link);
+        assert_eq!(tx(&model), "{abc}|");
+    }
+
+    #[test]
+    fn apply_action_can_not_add_a_link() {
+        let mut model = cm("{abc}|");
+        let update = model.apply_action(ComposerAction::Link);
+        assert_eq!(update, ComposerUpdate::keep_at(model.revision()));
+        assert_eq!(tx(&model), "{abc}|");
+    }
+
+    #[test]
+    fn apply_action_does_nothing_when_disabled() {
+        let mut model = cm("abc|");
+        let update = model.apply_action(ComposerAction::Redo);
+        assert_eq!(update, ComposerUpdate::keep_at(model.revision()));
+        let update = model.apply_action(ComposerAction::Indent);
+        assert_eq!(update, ComposerUpdate::keep_at(model.revision()));
+    }
+
+    #[test]
+    fn available_actions_lists_every_action_in_order() {
+        let mut model = cm("abc|");
+        model.bold();
+        let actions = model.available_actions();
+        assert_eq!(actions.len(), 22);
+        assert_eq!(
+            actions[0],
+            ActionInfo {
+                action: ComposerAction::Bold,
+                state: ActionState::Reversed,
+                disabled_reason: None,
+            }
+        );
+        assert_eq!(
+            actions[9],
+            ActionInfo {
+                action: ComposerAction::Redo,
+                state: ActionState::Disabled,
+                disabled_reason: Some(DisabledReason::HistoryEmpty),
+            }
+        );
+    }
+}
@@ -0,0 +1,90 @@
+// Copyright 2023 The Matrix.org Foundation C.I.C.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use crate::dom::nodes::DomNode;
+use crate::{
+    Alignment, AlignmentAttribute, ComposerModel, ComposerUpdate, UnicodeString,
+};
+
+impl<S> ComposerModel<S>
+where
+    S: UnicodeString,
+{
+    /// Align the selected paragraphs and headings, or remove their
+    /// alignment if `alignment` is None. Blocks inside code blocks are left
+    /// alone.
+    pub fn set_alignment(
+        &mut self,
+        alignment: Option<Alignment>,
+    ) -> ComposerUpdate<S> {
+        let document = self.state.dom.document();
+        let only_inline_nodes =
+            document.children().iter().all(|n| !n.is_block_node());
+        if only_inline_nodes && alignment.is_none() {
+            return ComposerUpdate::keep_at(self.state.revision);
+        }
+
+        self.push_state_to_history();
+        if only_inline_nodes {
+            // The content isn't in any block yet, so it becomes one
+            // paragraph to hold the alignment
+            let children = self.state.dom.document_mut().remove_children();
+            self.state
+                .dom
+                .append_at_end_of_document(DomNode::new_paragraph(children));
+        }
+        let attribute = self.alignment_attribute;
+        for handle in self.text_block_targets() {
+            if let Ok(DomNode::Container(block)) =
+                self.state.dom.try_lookup_node_mut(&handle)
+            {
+                block.set_alignment(alignment, attribute);
+            }
+        }
+        self.create_update_replace_all()
+    }
+
+    /// The alignment of the paragraph or heading containing the start of
+    /// the selection, if it was set.
+    pub fn get_alignment(&self) -> Option<Alignment> {
+        let (s, _) = self.safe_selection();
+        let handle = self.block_at(s)?;
+        self.state.dom.lookup_container(&handle).alignment()
+    }
+
+    /// Write alignments as `attribute` from now on. Blocks which are
+    /// already aligned are rewritten, so the HTML uses one attribute
+    /// throughout, but aligned blocks in HTML set later keep the attribute
+    /// they were given with until they are aligned again.
+    pub fn set_alignment_attribute(&mut self, attribute: AlignmentAttribute) {
+        self.alignment_attribute = attribute;
+        let handles: Vec<_> = self
+            .state
+            .dom
+            .iter_containers()
+            .filter(|c| {
+                c.alignment_and_attribute()
+                    .is_some_and(|(_, current)| current != attribute)
+            })
+            .map(|c| c.handle())
+            .collect();
+        for handle in handles {
+            if let Ok(DomNode::Container(block)) =
+                self.state.dom.try_lookup_node_mut(&handle)
+            {
+                block.set_alignment(block.alignment(), attribute);
+            }
+        }
+    }
+}
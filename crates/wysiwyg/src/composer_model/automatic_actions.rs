@@ -0,0 +1,51 @@
+// Copyright 2023 The Matrix.org Foundation C.I.C.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use crate::composer_state::ComposerState;
+use crate::{ComposerModel, UnicodeString};
+
+/// A change the model made by itself (e.g. linking a URL as it was typed),
+/// remembered so that the text as the user typed it can be restored.
+#[derive(Clone)]
+pub(crate) struct AutomaticAction<S>
+where
+    S: UnicodeString,
+{
+    /// The state just before the change was made
+    before: ComposerState<S>,
+    /// The state just after the change was made
+    after: ComposerState<S>,
+}
+
+impl<S> ComposerModel<S>
+where
+    S: UnicodeString,
+{
+    /// Remember that the model just changed itself from the `before` state
+    /// to the current one, so that [ComposerModel::undo] or
+    /// [ComposerModel::backspace] straight afterwards only reverts that.
+    pub(crate) fn record_automatic_action(&mut self, before: ComposerState<S>) {
+        self.last_automatic_action = Some(AutomaticAction {
+            before,
+            after: self.state.clone(),
+        });
+    }
+
+    /// If nothing has changed since the last automatic action, forget it
+    /// and return the state before it.
+    pub(crate) fn take_automatic_action(&mut self) -> Option<ComposerState<S>> {
+        let action = self.last_automatic_action.take()?;
+        (action.after == self.state).then_some(action.before)
+    }
+}
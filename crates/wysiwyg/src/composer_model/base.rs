@@ -12,19 +12,34 @@
 // See the License for the specific language governing permissions and
 // limitations under the License.
 
-use crate::action_state::ActionState;
-use crate::composer_model::menu_state::MenuStateComputeType;
+use crate::action_state::{ActionState, DisabledReason};
+use crate::composer_error::ErrorQueue;
+use crate::composer_model::automatic_actions::AutomaticAction;
+use crate::composer_model::blocks::BlockRevisions;
+use crate::composer_model::changed_range::ContentMap;
+use crate::composer_model::mentions::FoundMention;
+use crate::composer_model::menu_state::{MenuStateCache, MenuStateComputeType};
 use crate::composer_state::ComposerState;
+use crate::dom::nodes::{ContainerNode, ContainerNodeKind, DomNode};
 use crate::dom::parser::markdown::markdown_html_parser::MarkdownHTMLParser;
-use crate::dom::parser::parse;
-use crate::dom::to_plain_text::ToPlainText;
-use crate::dom::{Dom, DomCreationError, UnicodeString};
+use crate::dom::parser::{
+    parse, parse_with_options, HtmlParseOptions, WhitespacePolicy,
+};
+use crate::dom::to_html::ToHtmlState;
+use crate::dom::to_plain_text::{PlainTextOptions, ToPlainText};
+use crate::dom::unicode_string::{UnicodeStr, UnicodeStrExt};
+use crate::dom::{Dom, DomCreationError, MarkdownOptions, UnicodeString};
+use crate::format_type::allowed_custom_tag;
 use crate::link_action::LinkActionUpdate;
 use crate::{
-    ComposerAction, ComposerUpdate, DomHandle, Location, ToHtml, ToMarkdown,
-    ToTree,
+    AlignmentAttribute, ComposerAction, ComposerUpdate, CustomTagSerializer,
+    CustomTagSerializers, DomHandle, ErrorListener, HtmlSourceMapEntry, Locale,
+    Location, MetricsListener, PatternKey, SuggestionPattern, ToHtml,
+    ToMarkdown, ToTree,
 };
 use std::collections::{HashMap, HashSet};
+use std::sync::Arc;
+use unicode_segmentation::UnicodeSegmentation;
 
 #[derive(Clone, Default)]
 pub struct ComposerModel<S>
@@ -40,11 +55,100 @@ where
     /// States after the current one that may be restored by calling redo()
     pub(crate) next_states: Vec<ComposerState<S>>,
 
+    /// The last change made automatically, e.g. by linking a URL as it
+    /// was typed
+    pub(crate) last_automatic_action: Option<AutomaticAction<S>>,
+
     /// The states of the buttons for each action e.g. bold, undo
     pub(crate) action_states: HashMap<ComposerAction, ActionState>,
+    pub(crate) disabled_reasons: HashMap<ComposerAction, DisabledReason>,
+    /// The undo and redo depths in the last menu state update
+    pub(crate) reported_history_depths: (usize, usize),
+    /// The quote depth last reported in a menu state update
+    pub(crate) reported_quote_depth: usize,
+    /// The action states last computed, whether or not they were reported
+    pub(crate) menu_state_cache: MenuStateCache,
 
     /// Suggestion patterns provided by the client at runtime
     pub(crate) custom_suggestion_patterns: HashSet<String>,
+
+    /// Pattern keys whose suggestions may continue across whitespace
+    pub(crate) multi_word_suggestion_keys: Vec<PatternKey>,
+
+    /// Pattern keys whose suggestions are never reported
+    pub(crate) disabled_suggestion_keys: Vec<PatternKey>,
+
+    /// The suggestion the client dismissed, if any
+    pub(crate) dismissed_suggestion: Option<SuggestionPattern>,
+
+    /// The revision of the last update which replaced the content
+    pub(crate) content_revision: u64,
+
+    /// The mentions in the content when it was last reported and their
+    /// positions, with the revision of the Dom they were found in
+    pub(crate) found_mentions: Option<(u64, Vec<(usize, FoundMention)>)>,
+
+    /// Whether punctuation typed right after a suggestion is kept when the
+    /// suggestion is replaced
+    pub(crate) keep_suggestion_punctuation: bool,
+
+    /// Whether URLs and email addresses are turned into links when
+    /// whitespace is typed after them
+    pub(crate) link_as_you_type: bool,
+
+    /// Whether email addresses are turned into `mailto:` links when linking
+    /// text automatically
+    pub(crate) link_emails: bool,
+
+    /// Whether text typed at the edge of a link becomes part of it
+    pub(crate) extend_links_at_edges: bool,
+
+    /// Whether each line of multi-line text inserted into a list item
+    /// becomes a list item of its own, rather than a paragraph inside it
+    pub(crate) split_lines_into_list_items: bool,
+
+    /// Whether lines of inserted text starting with `- `, `* ` or `1. `
+    /// become list items
+    pub(crate) convert_pasted_bullets_to_lists: bool,
+
+    /// Whether enter on an empty line leaves a list, quote or code block,
+    /// for each of those block types
+    pub(crate) exit_list_on_empty_line: bool,
+    pub(crate) exit_quote_on_empty_line: bool,
+    pub(crate) exit_code_block_on_empty_line: bool,
+
+    /// Extra inline tags, e.g. `kbd`, which are kept when setting content
+    pub(crate) custom_inline_tags: Vec<String>,
+
+    /// How some of the extra inline tags are written in message HTML
+    pub(crate) custom_tag_serializers: CustomTagSerializers,
+
+    /// Whether links in message HTML open in a new tab without passing on
+    /// the referrer or a handle to the page
+    pub(crate) safe_link_attributes: bool,
+
+    /// How alignments set with `set_alignment` are written in the HTML
+    pub(crate) alignment_attribute: AlignmentAttribute,
+
+    /// The locale used e.g. to find where words end
+    pub(crate) locale: Locale,
+
+    /// Told how long measured operations take, if set
+    pub(crate) metrics_listener: Option<Arc<dyn MetricsListener>>,
+
+    /// Told about errors the model recovered from, if set
+    pub(crate) error_listener: Option<Arc<dyn ErrorListener>>,
+
+    /// Errors recovered from which the listener hasn't been told about yet
+    pub(crate) unreported_errors: ErrorQueue,
+
+    /// The state when the last update was made, with how many undo steps
+    /// there were then, to go back to if an operation fails
+    pub(crate) recovery_state: Option<(ComposerState<S>, usize)>,
+
+    /// The content as it was in the last update, to find what changed
+    pub(crate) reported_content: ContentMap<S>,
+    pub(crate) block_revisions: BlockRevisions,
 }
 
 impl<S> ComposerModel<S>
@@ -56,8 +160,38 @@ where
             state: ComposerState::default(),
             previous_states: Vec::new(),
             next_states: Vec::new(),
+            last_automatic_action: None,
             action_states: HashMap::new(), // TODO: Calculate state based on ComposerState
+            disabled_reasons: HashMap::new(),
+            reported_history_depths: (0, 0),
+            reported_quote_depth: 0,
+            menu_state_cache: MenuStateCache::default(),
             custom_suggestion_patterns: HashSet::new(),
+            multi_word_suggestion_keys: Vec::new(),
+            disabled_suggestion_keys: Vec::new(),
+            dismissed_suggestion: None,
+            content_revision: 0,
+            found_mentions: None,
+            keep_suggestion_punctuation: false,
+            link_as_you_type: false,
+            link_emails: true,
+            extend_links_at_edges: false,
+            split_lines_into_list_items: true,
+            convert_pasted_bullets_to_lists: false,
+            exit_list_on_empty_line: true,
+            exit_quote_on_empty_line: true,
+            exit_code_block_on_empty_line: true,
+            custom_inline_tags: Vec::new(),
+            custom_tag_serializers: CustomTagSerializers::new(),
+            safe_link_attributes: false,
+            alignment_attribute: AlignmentAttribute::default(),
+            locale: Locale::default(),
+            metrics_listener: None,
+            error_listener: None,
+            unreported_errors: ErrorQueue::default(),
+            recovery_state: None,
+            reported_content: ContentMap::default(),
+            block_revisions: BlockRevisions::default(),
         };
         instance.compute_menu_state(MenuStateComputeType::AlwaysUpdate);
         instance
@@ -68,8 +202,38 @@ where
             state,
             previous_states: Vec::new(),
             next_states: Vec::new(),
+            last_automatic_action: None,
             action_states: HashMap::new(), // TODO: Calculate state based on ComposerState
+            disabled_reasons: HashMap::new(),
+            reported_history_depths: (0, 0),
+            reported_quote_depth: 0,
+            menu_state_cache: MenuStateCache::default(),
             custom_suggestion_patterns: HashSet::new(),
+            multi_word_suggestion_keys: Vec::new(),
+            disabled_suggestion_keys: Vec::new(),
+            dismissed_suggestion: None,
+            content_revision: 0,
+            found_mentions: None,
+            keep_suggestion_punctuation: false,
+            link_as_you_type: false,
+            link_emails: true,
+            extend_links_at_edges: false,
+            split_lines_into_list_items: true,
+            convert_pasted_bullets_to_lists: false,
+            exit_list_on_empty_line: true,
+            exit_quote_on_empty_line: true,
+            exit_code_block_on_empty_line: true,
+            custom_inline_tags: Vec::new(),
+            custom_tag_serializers: CustomTagSerializers::new(),
+            safe_link_attributes: false,
+            alignment_attribute: AlignmentAttribute::default(),
+            locale: Locale::default(),
+            metrics_listener: None,
+            error_listener: None,
+            unreported_errors: ErrorQueue::default(),
+            recovery_state: None,
+            reported_content: ContentMap::default(),
+            block_revisions: BlockRevisions::default(),
         }
     }
 
@@ -86,14 +250,46 @@ where
                 start: Location::from(start_codeunit),
                 end: Location::from(end_codeunit),
                 toggled_format_types: Vec::new(),
+                revision: 0,
             },
             previous_states: Vec::new(),
             next_states: Vec::new(),
+            last_automatic_action: None,
             action_states: HashMap::new(), // TODO: Calculate state based on ComposerState
+            disabled_reasons: HashMap::new(),
+            reported_history_depths: (0, 0),
+            reported_quote_depth: 0,
+            menu_state_cache: MenuStateCache::default(),
             custom_suggestion_patterns: HashSet::new(),
+            multi_word_suggestion_keys: Vec::new(),
+            disabled_suggestion_keys: Vec::new(),
+            dismissed_suggestion: None,
+            content_revision: 0,
+            found_mentions: None,
+            keep_suggestion_punctuation: false,
+            link_as_you_type: false,
+            link_emails: true,
+            extend_links_at_edges: false,
+            split_lines_into_list_items: true,
+            convert_pasted_bullets_to_lists: false,
+            exit_list_on_empty_line: true,
+            exit_quote_on_empty_line: true,
+            exit_code_block_on_empty_line: true,
+            custom_inline_tags: Vec::new(),
+            custom_tag_serializers: CustomTagSerializers::new(),
+            safe_link_attributes: false,
+            alignment_attribute: AlignmentAttribute::default(),
+            locale: Locale::default(),
+            metrics_listener: None,
+            error_listener: None,
+            unreported_errors: ErrorQueue::default(),
+            recovery_state: None,
+            reported_content: ContentMap::default(),
+            block_revisions: BlockRevisions::default(),
         };
         model.compute_menu_state(MenuStateComputeType::AlwaysUpdate);
         Self::post_process_dom(&mut model.state.dom);
+        model.remember_reported_content();
         model
     }
 
@@ -104,19 +300,37 @@ where
         &mut self,
         html: &S,
     ) -> Result<ComposerUpdate<S>, DomCreationError> {
-        let dom = parse(&html.to_string())
+        self.set_content_from_html_with_whitespace_policy(
+            html,
+            WhitespacePolicy::default(),
+        )
+    }
+
+    /// Like `set_content_from_html`, choosing how whitespace in the HTML's
+    /// text is handled, e.g. to keep the layout of pasted pre-formatted text.
+    pub fn set_content_from_html_with_whitespace_policy(
+        &mut self,
+        html: &S,
+        whitespace_policy: WhitespacePolicy,
+    ) -> Result<ComposerUpdate<S>, DomCreationError> {
+        let options = HtmlParseOptions {
+            whitespace_policy,
+            custom_inline_tags: self.custom_inline_tags.clone(),
+        };
+        let dom = parse_with_options(&html.to_string(), &options)
             .map_err(DomCreationError::HtmlParseError)?;
 
         self.state.dom = dom;
         self.previous_states.clear();
         self.next_states.clear();
+        self.dismissed_suggestion = None;
         Self::post_process_dom(&mut self.state.dom);
         self.state.start = Location::from(self.state.dom.text_len());
         self.state.end = self.state.start;
         Ok(self.create_update_replace_all_with_menu_state())
     }
 
-    fn post_process_dom(dom: &mut Dom<S>) {
+    pub(crate) fn post_process_dom(dom: &mut Dom<S>) {
         dom.wrap_inline_nodes_into_paragraphs_if_needed(&DomHandle::root());
         dom.explicitly_assert_invariants();
     }
@@ -139,10 +353,146 @@ where
             HashSet::from_iter(custom_suggestion_patterns)
     }
 
+    /// Allow suggestions for the given pattern keys to continue across
+    /// whitespace (e.g. "@john smith") until they are dismissed with
+    /// [ComposerModel::dismiss_suggestion]. Only the static At, Hash and
+    /// Slash keys are supported.
+    pub fn set_multi_word_suggestion_patterns(
+        &mut self,
+        keys: Vec<PatternKey>,
+    ) {
+        self.multi_word_suggestion_keys =
+            keys.into_iter().filter(|k| k.is_static_pattern()).collect();
+    }
+
+    /// Stop or start reporting suggestions for the given pattern key, e.g.
+    /// to turn off `/` commands in a client which doesn't support them.
+    /// All keys are enabled by default.
+    pub fn set_suggestion_pattern_enabled(
+        &mut self,
+        key: PatternKey,
+        enabled: bool,
+    ) {
+        self.disabled_suggestion_keys.retain(|k| *k != key);
+        if !enabled {
+            self.disabled_suggestion_keys.push(key);
+        }
+    }
+
+    /// When enabled, punctuation at the end of a suggestion (e.g. the comma
+    /// in "@alice,") is kept after the text or mention replacing it, instead
+    /// of being replaced along with the suggestion.
+    pub fn set_keep_suggestion_punctuation(&mut self, keep: bool) {
+        self.keep_suggestion_punctuation = keep;
+    }
+
+    /// When enabled, a URL or email address is turned into a link as soon
+    /// as whitespace is typed after it.
+    pub fn set_link_as_you_type(&mut self, enabled: bool) {
+        self.link_as_you_type = enabled;
+    }
+
+    /// Whether email addresses should be turned into `mailto:` links, both
+    /// as you type and by `linkify_content`. Enabled by default.
+    pub fn set_link_emails(&mut self, enabled: bool) {
+        self.link_emails = enabled;
+    }
+
+    /// Whether text typed at the end of a link, or at its start when
+    /// nothing comes before it in the block, becomes part of the link.
+    /// Otherwise it is added next to the link. Disabled by default.
+    pub fn set_extend_links_at_edges(&mut self, enabled: bool) {
+        self.extend_links_at_edges = enabled;
+    }
+
+    /// Whether each line of multi-line text inserted into a list item, e.g.
+    /// when pasting, becomes a sibling list item. Otherwise the lines are
+    /// kept inside the list item as paragraphs. Enabled by default.
+    pub fn set_split_lines_into_list_items(&mut self, enabled: bool) {
+        self.split_lines_into_list_items = enabled;
+    }
+
+    /// Whether lines of multi-line text inserted outside a list, e.g. when
+    /// pasting notes, that start with `- `, `* ` or a number and `. ` become
+    /// items of an unordered or ordered list, without those markers.
+    /// Disabled by default.
+    pub fn set_convert_pasted_bullets_to_lists(&mut self, enabled: bool) {
+        self.convert_pasted_bullets_to_lists = enabled;
+    }
+
+    /// Whether enter on an empty list item leaves the list. Otherwise it
+    /// adds another list item. Enabled by default.
+    pub fn set_exit_list_on_empty_line(&mut self, enabled: bool) {
+        self.exit_list_on_empty_line = enabled;
+    }
+
+    /// Whether enter on an empty line in a quote leaves the quote.
+    /// Otherwise it adds another line to it. Enabled by default.
+    pub fn set_exit_quote_on_empty_line(&mut self, enabled: bool) {
+        self.exit_quote_on_empty_line = enabled;
+    }
+
+    /// Whether enter on an empty line in a code block leaves the code
+    /// block. Otherwise it adds another line to it. Enabled by default.
+    pub fn set_exit_code_block_on_empty_line(&mut self, enabled: bool) {
+        self.exit_code_block_on_empty_line = enabled;
+    }
+
+    /// Set the locale from a BCP 47 language tag, e.g. `en-GB`. It decides
+    /// where words end for `backspace_word` and `delete_word`.
+    pub fn set_locale(&mut self, tag: &str) {
+        self.locale = Locale::from_tag(tag);
+    }
+
+    pub fn locale(&self) -> &Locale {
+        &self.locale
+    }
+
+    /// Keep the given inline tags (e.g. `abbr`, `kbd` or `cite`) when
+    /// setting content from HTML or Markdown, instead of dropping them, and
+    /// allow toggling them with `custom_format`. Only inline tags which hold
+    /// formatted text and have no meaning in the editor already, such as
+    /// `abbr`, `cite`, `kbd`, `mark`, `sub` or `sup`, can be registered.
+    /// Others are ignored, including all block tags such as `div` or
+    /// `section`, which aren't supported.
+    pub fn set_custom_inline_tags(&mut self, tags: Vec<String>) {
+        self.custom_inline_tags = tags
+            .into_iter()
+            .map(|tag| tag.to_ascii_lowercase())
+            .filter(|tag| allowed_custom_tag(tag).is_some())
+            .collect();
+    }
+
+    /// Write the given custom inline tag (see [Self::set_custom_inline_tags])
+    /// with `serializer` in message HTML, instead of as the tag and its
+    /// attributes. Replaces any serializer set before for the tag.
+    pub fn set_custom_tag_serializer(
+        &mut self,
+        tag: &str,
+        serializer: Arc<dyn CustomTagSerializer>,
+    ) {
+        self.custom_tag_serializers
+            .insert(tag.to_ascii_lowercase(), serializer);
+    }
+
+    /// Whether links in message HTML get `target="_blank"` and
+    /// `rel="noreferrer noopener"`, replacing any target or rel they had,
+    /// so that clients rendering the message follow them safely. The
+    /// content itself is not changed. Disabled by default.
+    pub fn set_safe_link_attributes(&mut self, enabled: bool) {
+        self.safe_link_attributes = enabled;
+    }
+
     pub fn action_states(&self) -> &HashMap<ComposerAction, ActionState> {
         &self.action_states
     }
 
+    /// Why each of the disabled actions in [Self::action_states] is
+    /// disabled.
+    pub fn disabled_reasons(&self) -> &HashMap<ComposerAction, DisabledReason> {
+        &self.disabled_reasons
+    }
+
     #[cfg(test)]
     pub(crate) fn action_is_enabled(&self, action: ComposerAction) -> bool {
         self.action_states.get(&action) == Some(&ActionState::Enabled)
@@ -152,7 +502,6 @@ where
         self.action_states.get(&action) == Some(&ActionState::Reversed)
     }
 
-    #[cfg(test)]
     pub(crate) fn action_is_disabled(&self, action: ComposerAction) -> bool {
         self.action_states.get(&action) == Some(&ActionState::Disabled)
     }
@@ -165,6 +514,8 @@ where
 
         let menu_state =
             self.compute_menu_state(MenuStateComputeType::KeepIfUnchanged);
+        let revision = self.next_revision();
+        self.remember_recovery_state();
 
         ComposerUpdate::update_selection(
             self.state.start,
@@ -172,6 +523,7 @@ where
             menu_state,
             self.compute_menu_action(),
             LinkActionUpdate::Update(self.get_link_action()),
+            revision,
         )
     }
 
@@ -179,13 +531,21 @@ where
         #[cfg(any(test, feature = "assert-invariants"))]
         self.state.dom.assert_transaction_not_in_progress();
 
-        ComposerUpdate::replace_all(
-            self.state.dom.to_html(),
+        let changed = self.changed_range();
+        let menu_state =
+            self.compute_menu_state(MenuStateComputeType::KeepIfUnchanged);
+        // Suggestions are sent with the revision of the new content
+        let revision = self.next_revision();
+        self.content_revision = revision;
+        ComposerUpdate::replace_all_with_dom(
+            self.state.dom.clone(),
             self.state.start,
             self.state.end,
-            self.compute_menu_state(MenuStateComputeType::KeepIfUnchanged),
+            changed,
+            menu_state,
             self.compute_menu_action(),
             LinkActionUpdate::Update(self.get_link_action()),
+            revision,
         )
     }
 
@@ -195,16 +555,41 @@ where
         #[cfg(any(test, feature = "assert-invariants"))]
         self.state.dom.assert_transaction_not_in_progress();
 
-        ComposerUpdate::replace_all(
-            self.state.dom.to_html(),
+        let changed = self.changed_range();
+        let menu_state =
+            self.compute_menu_state(MenuStateComputeType::AlwaysUpdate);
+        let revision = self.next_revision();
+        self.content_revision = revision;
+        ComposerUpdate::replace_all_with_dom(
+            self.state.dom.clone(),
             self.state.start,
             self.state.end,
-            self.compute_menu_state(MenuStateComputeType::AlwaysUpdate),
+            changed,
+            menu_state,
             self.compute_menu_action(),
             LinkActionUpdate::Update(self.get_link_action()),
+            revision,
         )
     }
 
+    pub(crate) fn next_revision(&mut self) -> u64 {
+        self.state.revision += 1;
+        self.state.revision
+    }
+
+    /// Replace the state with `state`, e.g. one from the undo history,
+    /// keeping the current revision so that it only ever goes up.
+    pub(crate) fn restore_state(&mut self, state: ComposerState<S>) {
+        let revision = self.state.revision;
+        self.state = state;
+        self.state.revision = revision;
+    }
+
+    /// The revision of the current state, as sent in the latest update.
+    pub fn revision(&self) -> u64 {
+        self.state.revision
+    }
+
     pub fn get_selection(&self) -> (Location, Location) {
         (self.state.start, self.state.end)
     }
@@ -213,8 +598,24 @@ where
         self.state.dom.to_html()
     }
 
+    /// The same HTML as `get_content_as_html`, along with where each node of
+    /// the model is in it, so that clicks and selections in the rendered
+    /// HTML can be mapped back to positions in the model.
+    pub fn get_content_as_html_with_source_map(
+        &self,
+    ) -> (S, Vec<HtmlSourceMapEntry>) {
+        self.state.dom.to_html_with_source_map()
+    }
+
     pub fn get_content_as_message_html(&self) -> S {
-        self.state.dom.to_message_html()
+        let state = ToHtmlState {
+            custom_tag_serializers: Some(&self.custom_tag_serializers),
+            safe_link_attributes: self.safe_link_attributes,
+            ..ToHtmlState::default()
+        };
+        let mut buf = S::default();
+        self.state.dom.fmt_html(&mut buf, None, &state, true);
+        buf
     }
 
     pub fn get_content_as_markdown(&self) -> S {
@@ -225,10 +626,95 @@ where
         self.state.dom.to_message_markdown().unwrap()
     }
 
+    /// The content as a plain text `body` to send alongside the HTML
+    /// `formatted_body` of a Matrix message, e.g. `*bold*`, `> quote` and
+    /// the display names of mentions. Unlike `get_content_as_plain_text`, it
+    /// keeps a readable trace of the formatting.
+    pub fn get_content_as_fallback_text(&self) -> S {
+        self.state
+            .dom
+            .to_message_markdown_with_options(MarkdownOptions::FALLBACK_TEXT)
+            .unwrap()
+    }
+
     pub fn get_content_as_plain_text(&self) -> S {
         self.state.dom.to_plain_text()
     }
 
+    pub fn get_content_as_plain_text_with_options(
+        &self,
+        options: PlainTextOptions,
+    ) -> S {
+        let text = self.state.dom.to_plain_text().to_string();
+        S::from(options.apply(&text))
+    }
+
+    /// The number of characters in `get_content_as_plain_text`, counting
+    /// each grapheme as one character, e.g. for a character counter.
+    pub fn get_character_count(&self) -> usize {
+        self.state.dom.to_plain_text().to_string().grapheme_count()
+    }
+
+    /// The number of words in `get_content_as_plain_text`, found using the
+    /// Unicode word boundary rules.
+    pub fn get_word_count(&self) -> usize {
+        self.state
+            .dom
+            .to_plain_text()
+            .to_string()
+            .unicode_words()
+            .count()
+    }
+
+    /// A one-line plain text snippet of the content, e.g. to preview a draft
+    /// in a room list. Formatting is removed, mentions show their display
+    /// names and whitespace is collapsed. If it is longer than `max_chars`
+    /// graphemes, it is cut and ends with `…`, still within `max_chars`.
+    pub fn get_preview(&self, max_chars: usize) -> S {
+        let text = self.state.dom.to_plain_text().to_string();
+        let text = PlainTextOptions {
+            collapse_whitespace: true,
+            trim: true,
+        }
+        .apply(&text);
+        if text.grapheme_count() <= max_chars {
+            return S::from(text);
+        }
+        if max_chars == 0 {
+            return S::default();
+        }
+        let end = text.nth_grapheme_boundary(max_chars - 1).unwrap();
+        let mut preview = text[..end].to_owned();
+        preview.truncate(preview.trim_end().len());
+        preview.push('…');
+        S::from(preview)
+    }
+
+    /// A hash of the content, e.g. to tell whether a draft changed since it
+    /// was last saved without fetching its HTML. Equal content always has
+    /// the same hash, across runs and platforms, and the selection doesn't
+    /// change it.
+    pub fn content_hash(&self) -> u64 {
+        let mut hasher = ContentHasher::new();
+        hasher.write_node(self.state.dom.document_node());
+        hasher.0
+    }
+
+    /// Whether there is nothing worth sending, i.e. the content has no text
+    /// other than whitespace, and no mentions, images or horizontal rules.
+    /// Empty paragraphs, list items and line breaks don't count.
+    pub fn is_content_empty(&self) -> bool {
+        self.state.dom.iter().all(|node| match node {
+            DomNode::Container(_) | DomNode::LineBreak(_) => true,
+            DomNode::Text(text) => {
+                text.data().to_string().chars().all(char::is_whitespace)
+            }
+            DomNode::Mention(_)
+            | DomNode::Image(_)
+            | DomNode::HorizontalRule(_) => false,
+        })
+    }
+
     pub fn get_current_state(&self) -> &ComposerState<S> {
         &self.state
     }
@@ -243,9 +729,91 @@ where
     }
 }
 
+/// Builds the 64-bit FNV-1a hash of a node tree, which unlike the std
+/// hashers is the same in every build. Inline nodes directly inside a block
+/// other than a paragraph are hashed as if they were wrapped in one, so
+/// `a` and `<p>a</p>` hash the same.
+struct ContentHasher(u64);
+
+impl ContentHasher {
+    fn new() -> Self {
+        Self(0xcbf29ce484222325)
+    }
+
+    fn write(&mut self, bytes: &[u8]) {
+        for byte in bytes {
+            self.0 = (self.0 ^ u64::from(*byte)).wrapping_mul(0x100000001b3);
+        }
+    }
+
+    fn write_str<S: UnicodeString>(&mut self, text: &S::Str) {
+        let mut buf = [0; 4];
+        for c in text.chars() {
+            self.write(c.encode_utf8(&mut buf).as_bytes());
+        }
+    }
+
+    fn write_node<S: UnicodeString>(&mut self, node: &DomNode<S>) {
+        match node {
+            DomNode::Container(container) => {
+                self.write(b"<");
+                self.write_str::<S>(container.name());
+                if let ContainerNodeKind::Link(url) = container.kind() {
+                    self.write(b" href=");
+                    self.write_str::<S>(url);
+                }
+                for (name, value) in
+                    container.attributes().into_iter().flatten()
+                {
+                    self.write(b" ");
+                    self.write_str::<S>(name);
+                    self.write(b"=");
+                    self.write_str::<S>(value);
+                }
+                self.write(b">");
+                self.write_children(container);
+                self.write(b"</>");
+            }
+            DomNode::Text(text) => self.write_str::<S>(text.data()),
+            DomNode::LineBreak(_) => self.write(b"<br>"),
+            DomNode::HorizontalRule(_) => self.write(b"<hr>"),
+            DomNode::Mention(_) | DomNode::Image(_) => {
+                self.write_str::<S>(&node.to_html())
+            }
+        }
+    }
+
+    fn write_children<S: UnicodeString>(
+        &mut self,
+        container: &ContainerNode<S>,
+    ) {
+        let implicit_paragraphs = container.is_block_node()
+            && !matches!(container.kind(), ContainerNodeKind::Paragraph)
+            && container
+                .children()
+                .iter()
+                .any(|child| !child.is_block_node());
+        let mut in_paragraph = false;
+        for child in container.children() {
+            if implicit_paragraphs && child.is_block_node() == in_paragraph {
+                if in_paragraph {
+                    self.write(b"</>");
+                } else {
+                    self.write(b"<p>");
+                }
+                in_paragraph = !in_paragraph;
+            }
+            self.write_node(child);
+        }
+        if in_paragraph {
+            self.write(b"</>");
+        }
+    }
+}
+
 #[cfg(test)]
 mod test {
-    use widestring::Utf16String;
+    use crate::tests::testutils_composer_model::TestString;
 
     use crate::tests::testutils_composer_model::{cm, tx};
     use crate::tests::testutils_conversion::utf16;
@@ -258,7 +826,7 @@ mod test {
     fn completely_replacing_html_works() {
         let mut model = cm("{hello}| world");
         model
-            .set_content_from_html(&Utf16String::from_str("foo <b>bar</b>"))
+            .set_content_from_html(&TestString::from_str("foo <b>bar</b>"))
             .unwrap();
         assert_eq!(model.state.dom.to_string(), "foo <b>bar</b>")
     }
@@ -266,7 +834,7 @@ mod test {
     #[test]
     fn action_states_are_reported() {
         let mut model = ComposerModel::new();
-        model.replace_text(Utf16String::from("a"));
+        model.replace_text(TestString::from("a"));
         model.select(Location::from(0), Location::from(1));
         model.bold();
 
@@ -0,0 +1,162 @@
+// Copyright 2023 The Matrix.org Foundation C.I.C.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use std::ops::Range;
+
+use crate::dom::Dom;
+use crate::{BlockInfo, ComposerModel, ToHtml, UnicodeString};
+
+/// A revision number for each top-level block, which changes whenever the
+/// block does, so a renderer can skip blocks it has already drawn.
+#[derive(Clone, Debug, Default)]
+pub(crate) struct BlockRevisions {
+    revisions: Vec<u64>,
+    last_revision: u64,
+}
+
+/// Where a top-level block is in the content, and which children of the
+/// document it is made of.
+struct BlockSpan {
+    start: usize,
+    end: usize,
+    children: Range<usize>,
+}
+
+impl<S> ComposerModel<S>
+where
+    S: UnicodeString,
+{
+    /// The top-level blocks of the content in order, so that a long message
+    /// can be sent as several events without cutting through a paragraph.
+    /// Consecutive inline nodes outside any block count as one block.
+    pub fn get_blocks(&self) -> Vec<BlockInfo<S>> {
+        block_spans(&self.state.dom)
+            .into_iter()
+            .enumerate()
+            .map(|(index, span)| self.block_info(index, span))
+            .collect()
+    }
+
+    /// The top-level block at `index`, as in `get_blocks`.
+    pub fn get_block(&self, index: usize) -> Option<BlockInfo<S>> {
+        block_spans(&self.state.dom)
+            .into_iter()
+            .nth(index)
+            .map(|span| self.block_info(index, span))
+    }
+
+    /// The revision of each top-level block, as in `get_blocks`. A block's
+    /// revision changes when an update changes its content, so a renderer
+    /// only needs to call `get_block` for blocks whose revision it hasn't
+    /// seen yet.
+    pub fn get_block_revisions(&self) -> Vec<u64> {
+        let count = block_spans(&self.state.dom).len();
+        (0..count).map(|i| self.block_revision(i)).collect()
+    }
+
+    /// Give a new revision to each block touching the `changed` range of
+    /// the content, and keep the revisions of the blocks before and after
+    /// it. `None` means nothing changed.
+    pub(crate) fn update_block_revisions(
+        &mut self,
+        changed: Option<(usize, usize)>,
+    ) {
+        let spans = block_spans(&self.state.dom);
+        let state = &mut self.block_revisions;
+        let old = std::mem::take(&mut state.revisions);
+        let (changed_start, changed_end) = match changed {
+            None if old.len() == spans.len() => {
+                state.revisions = old;
+                return;
+            }
+            None => (0, usize::MAX),
+            Some(changed) => changed,
+        };
+        let added = spans.len() as isize - old.len() as isize;
+        for (i, span) in spans.iter().enumerate() {
+            let kept = if span.end < changed_start {
+                old.get(i)
+            } else if span.start > changed_end {
+                usize::try_from(i as isize - added)
+                    .ok()
+                    .and_then(|j| old.get(j))
+            } else {
+                None
+            };
+            let revision = kept.copied().unwrap_or_else(|| {
+                state.last_revision += 1;
+                state.last_revision
+            });
+            state.revisions.push(revision);
+        }
+    }
+
+    fn block_revision(&self, index: usize) -> u64 {
+        self.block_revisions
+            .revisions
+            .get(index)
+            .copied()
+            .unwrap_or_default()
+    }
+
+    fn block_info(&self, index: usize, span: BlockSpan) -> BlockInfo<S> {
+        let html: String = self.state.dom.children()[span.children]
+            .iter()
+            .map(|node| node.to_message_html().to_string())
+            .collect();
+        BlockInfo {
+            start: span.start,
+            end: span.end,
+            html: S::from(html),
+            revision: self.block_revision(index),
+        }
+    }
+}
+
+fn block_spans<S: UnicodeString>(dom: &Dom<S>) -> Vec<BlockSpan> {
+    let children = dom.children();
+    // Each block node, or run of inline nodes, with whether it is a block
+    let mut groups: Vec<(Range<usize>, bool)> = Vec::new();
+    for (i, child) in children.iter().enumerate() {
+        let is_block = child.is_block_node();
+        match groups.last_mut() {
+            Some((range, false)) if !is_block => range.end = i + 1,
+            _ => groups.push((i..i + 1, is_block)),
+        }
+    }
+
+    let mut spans = Vec::new();
+    let mut pos = 0;
+    let mut seen_block = false;
+    for (range, is_block) in groups {
+        if is_block {
+            // Blocks after the first are separated by a new line
+            if seen_block {
+                pos += 1;
+            }
+            seen_block = true;
+        }
+        let start = pos;
+        pos += children[range.clone()]
+            .iter()
+            .map(|n| n.text_len())
+            .sum::<usize>();
+        spans.push(BlockSpan {
+            start,
+            end: pos,
+            children: range,
+        });
+    }
+    spans
+}
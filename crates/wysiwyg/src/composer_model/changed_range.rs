@@ -0,0 +1,159 @@
+// Copyright 2023 The Matrix.org Foundation C.I.C.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use std::sync::Arc;
+
+use crate::dom::nodes::dom_node::DomNodeKind;
+use crate::dom::nodes::{ContainerNodeKind, DomNode, MentionNodeKind};
+use crate::dom::Dom;
+use crate::{ComposerModel, Location, UnicodeString};
+
+/// What is at each code unit of the content: the kind of leaf, the code
+/// unit itself for text, what a mention refers to, and the containers
+/// around it. The separators
+/// between blocks are `None`.
+#[derive(Clone, Default)]
+pub(crate) struct ContentMap<S>
+where
+    S: UnicodeString,
+{
+    units: Vec<Option<UnitContent<S>>>,
+}
+
+#[derive(Clone)]
+struct UnitContent<S>
+where
+    S: UnicodeString,
+{
+    kind: DomNodeKind,
+    unit: Option<S::CodeUnit>,
+    mention: Option<(MentionNodeKind, S)>,
+    // Shared by all the code units of a leaf
+    containers: Arc<Vec<ContainerNodeKind<S>>>,
+}
+
+impl<S> PartialEq for UnitContent<S>
+where
+    S: UnicodeString,
+{
+    fn eq(&self, other: &Self) -> bool {
+        self.kind == other.kind
+            && self.unit == other.unit
+            && self.mention == other.mention
+            && (Arc::ptr_eq(&self.containers, &other.containers)
+                || self.containers == other.containers)
+    }
+}
+
+impl<S> ContentMap<S>
+where
+    S: UnicodeString,
+{
+    pub(crate) fn of(dom: &Dom<S>) -> Self {
+        let len = dom.text_len();
+        let mut units = vec![None; len];
+        for leaf in dom.find_range(0, len).leaves() {
+            let mut containers = Vec::new();
+            let mut handle = leaf.node_handle.clone();
+            while handle.has_parent() {
+                handle = handle.parent_handle();
+                if let DomNode::Container(container) = dom.lookup_node(&handle)
+                {
+                    containers.push(container.kind().clone());
+                }
+            }
+            let containers = Arc::new(containers);
+            let node = dom.lookup_node(&leaf.node_handle);
+            let code_units: Vec<Option<S::CodeUnit>> = match node {
+                DomNode::Text(text) => {
+                    text.data().as_ref().iter().map(|u| Some(*u)).collect()
+                }
+                _ => vec![None; leaf.length],
+            };
+            let mention = match node {
+                DomNode::Mention(mention) => {
+                    Some((mention.kind().clone(), mention.display_text()))
+                }
+                _ => None,
+            };
+            for (i, unit) in code_units.into_iter().enumerate() {
+                if let Some(entry) = units.get_mut(leaf.position + i) {
+                    *entry = Some(UnitContent {
+                        kind: node.kind(),
+                        unit,
+                        mention: mention.clone(),
+                        containers: Arc::clone(&containers),
+                    });
+                }
+            }
+        }
+        Self { units }
+    }
+
+    /// The start and end in `after` of what differs from `self`, found by
+    /// skipping what is the same at the start and at the end, or None if
+    /// they are the same.
+    fn changed_range(&self, after: &Self) -> Option<(usize, usize)> {
+        let (before, after) = (&self.units, &after.units);
+        let prefix = before
+            .iter()
+            .zip(after.iter())
+            .take_while(|(b, a)| b == a)
+            .count();
+        if prefix == before.len() && prefix == after.len() {
+            return None;
+        }
+        let max_suffix = before.len().min(after.len()) - prefix;
+        let suffix = before
+            .iter()
+            .rev()
+            .zip(after.iter().rev())
+            .take(max_suffix)
+            .take_while(|(b, a)| b == a)
+            .count();
+        Some((prefix, after.len() - suffix))
+    }
+}
+
+impl<S> ComposerModel<S>
+where
+    S: UnicodeString,
+{
+    /// The start and end of the part of the content which changed since
+    /// the last update, or an empty range at the cursor if nothing changed.
+    /// Remembers the content for the next update.
+    pub(crate) fn changed_range(&mut self) -> (Location, Location) {
+        let content = ContentMap::of(&self.state.dom);
+        let changed = self.reported_content.changed_range(&content);
+        let reported_len = self.reported_content.units.len();
+        self.reported_content = content;
+        self.update_block_revisions(changed);
+        self.update_found_mentions(changed, reported_len);
+        self.remember_recovery_state();
+        match changed {
+            Some((start, end)) => (Location::from(start), Location::from(end)),
+            None => (self.state.end, self.state.end),
+        }
+    }
+
+    /// Treat the current content as already reported, e.g. when a model is
+    /// created with some content.
+    pub(crate) fn remember_reported_content(&mut self) {
+        self.reported_content = ContentMap::of(&self.state.dom);
+        self.update_block_revisions(None);
+        self.found_mentions = None;
+        self.update_found_mentions(None, 0);
+        self.remember_recovery_state();
+    }
+}
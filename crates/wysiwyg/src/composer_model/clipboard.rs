@@ -0,0 +1,95 @@
+// Copyright 2023 The Matrix.org Foundation C.I.C.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use crate::dom::to_plain_text::ToPlainText;
+use crate::dom::Dom;
+use crate::{
+    ClipboardContent, ComposerError, ComposerModel, ComposerUpdate, Location,
+    ToHtml, UnicodeString,
+};
+
+impl<S> ComposerModel<S>
+where
+    S: UnicodeString,
+{
+    /// The content between `start` and `end` as HTML and as plain text,
+    /// to put on the clipboard when it is copied. Formatting around the
+    /// range is kept, e.g. copying part of a bold word gives bold HTML.
+    pub fn get_clipboard_content(
+        &self,
+        start: Location,
+        end: Location,
+    ) -> ClipboardContent<S> {
+        let (s, e) = self.safe_locations_from(start, end);
+        if s == e {
+            return ClipboardContent {
+                html: S::default(),
+                plain_text: S::default(),
+            };
+        }
+        let dom = match self.dom_between(s, e) {
+            Ok(dom) => dom,
+            Err(error) => {
+                self.report_error(&error);
+                return ClipboardContent {
+                    html: S::default(),
+                    plain_text: S::default(),
+                };
+            }
+        };
+        let plain_text = dom.to_plain_text().to_string();
+        ClipboardContent {
+            html: without_zwsp(&dom.to_html().to_string()),
+            plain_text: without_zwsp(plain_text.trim_end_matches('\n')),
+        }
+    }
+
+    /// A copy of the document with everything outside `start` and `end`
+    /// removed.
+    pub(crate) fn dom_between(
+        &self,
+        start: usize,
+        end: usize,
+    ) -> Result<Dom<S>, ComposerError> {
+        let mut dom = self.state.dom.clone();
+        let len = dom.text_len();
+        if end < len {
+            dom.replace_text_in(S::default(), end, len)?;
+        }
+        if start > 0 {
+            dom.replace_text_in(S::default(), 0, start)?;
+        }
+        Ok(dom)
+    }
+
+    /// Remove the selected content and return it as it would be copied by
+    /// [ComposerModel::get_clipboard_content]. The removal is a single undo
+    /// step. If nothing is selected, nothing is removed and the returned
+    /// content is empty.
+    pub fn cut_selection(
+        &mut self,
+    ) -> (ClipboardContent<S>, ComposerUpdate<S>) {
+        let (s, e) = self.safe_selection();
+        let content =
+            self.get_clipboard_content(Location::from(s), Location::from(e));
+        if s == e {
+            return (content, ComposerUpdate::keep_at(self.state.revision));
+        }
+        (content, self.delete())
+    }
+}
+
+fn without_zwsp<S: UnicodeString>(text: &str) -> S {
+    S::from(text.replace('\u{200B}', ""))
+}
@@ -15,7 +15,9 @@
 use crate::dom::nodes::dom_node::DomNodeKind::*;
 use crate::dom::nodes::{ContainerNode, ContainerNodeKind, DomNode};
 use crate::dom::{DomHandle, DomLocation, Range};
-use crate::{ComposerAction, ComposerModel, ComposerUpdate, UnicodeString};
+use crate::{
+    ComposerAction, ComposerModel, ComposerUpdate, Location, UnicodeString,
+};
 
 impl<S> ComposerModel<S>
 where
@@ -29,7 +31,49 @@ where
         }
     }
 
-    fn add_code_block(&mut self) -> ComposerUpdate<S> {
+    /// Insert a code block containing `text` at the cursor, replacing any
+    /// selection, e.g. when pasting text flagged as code. Each line of
+    /// `text` becomes a line of the code block, but a trailing newline does
+    /// not add an empty line at the end. If `text` is fenced with ```, the
+    /// fences are dropped and the language after the opening one is used
+    /// unless `language` is given. The cursor ends up at the end of the
+    /// code block, and the insertion is a single undo step.
+    pub fn insert_code_block_with_text(
+        &mut self,
+        text: S,
+        language: Option<&str>,
+    ) -> ComposerUpdate<S> {
+        self.push_state_to_history();
+        let text = text.to_string().replace("\r\n", "\n");
+        let (body, fence_language) = unfence(&text);
+        let body = body.strip_suffix('\n').unwrap_or(body);
+        let lines = body
+            .split('\n')
+            .map(|line| {
+                let children = if line.is_empty() {
+                    Vec::new()
+                } else {
+                    vec![DomNode::new_text(S::from(line))]
+                };
+                DomNode::new_paragraph(children)
+            })
+            .collect();
+        let mut code_block = ContainerNode::new_code_block(lines);
+        if let Some(language) = language.or(fence_language) {
+            code_block.set_code_block_language(language);
+        }
+
+        let handle =
+            match self.insert_block_at_cursor(DomNode::Container(code_block)) {
+                Ok(handle) => handle,
+                Err(error) => return self.recover_from(error),
+            };
+        self.state.start = Location::from(self.end_of_block(&handle));
+        self.state.end = self.state.start;
+        self.create_update_replace_all()
+    }
+
+    pub(crate) fn add_code_block(&mut self) -> ComposerUpdate<S> {
         let (s, e) = self.safe_selection();
         let Some(wrap_result) =
             self.state.dom.find_nodes_to_wrap_in_block(s, e)
@@ -41,8 +85,9 @@ where
                 Vec::new(),
             )]);
             if leaves.is_empty() {
-                if let Some(deepest_block_location) =
-                    range.deepest_block_node(None)
+                if let Some(deepest_block_location) = range
+                    .deepest_block_node(None)
+                    .filter(|l| !l.node_handle.is_root())
                 {
                     let mut block_node = self
                         .state
@@ -177,7 +222,7 @@ where
         let Some(block_location) =
             range.locations.iter().find(|l| l.kind == CodeBlock)
         else {
-            return ComposerUpdate::keep();
+            return ComposerUpdate::keep_at(self.state.revision);
         };
 
         self.state
@@ -187,6 +232,27 @@ where
         self.create_update_replace_all()
     }
 
+    /// Move the first line of the code block at `handle` out in front of
+    /// it as a paragraph, e.g. when backspacing at the start of the
+    /// document, where there is nothing to merge it into.
+    pub(crate) fn do_backspace_at_start_of_code_block(
+        &mut self,
+        handle: &DomHandle,
+    ) -> ComposerUpdate<S> {
+        let code_block = self.state.dom.lookup_container(handle);
+        let first_line_is_paragraph = code_block
+            .children()
+            .first()
+            .map_or(false, |c| c.kind() == Paragraph);
+        if code_block.children().len() > 1 && first_line_is_paragraph {
+            let first_line = self.state.dom.remove(&handle.child_handle(0));
+            self.state.dom.insert_at(handle, first_line);
+        } else {
+            self.state.dom.remove_and_keep_children(handle);
+        }
+        self.create_update_replace_all()
+    }
+
     /// Converts any nodes to be added to a code block to the right format, recursively.
     /// Line breaks get turned into `\n` chars.
     /// Text nodes are just cloned.
@@ -228,7 +294,9 @@ where
 
         if matches!(
             container.kind(),
-            ContainerNodeKind::Paragraph | ContainerNodeKind::ListItem
+            ContainerNodeKind::Paragraph
+                | ContainerNodeKind::Heading(_)
+                | ContainerNodeKind::ListItem
         ) {
             vec![DomNode::new_paragraph(children)]
         } else if container.is_block_node() {
@@ -241,9 +309,31 @@ where
     }
 }
 
+/// The code inside ``` fences around `text` and the language after the
+/// opening fence, if any, or `text` itself if it isn't fenced.
+fn unfence(text: &str) -> (&str, Option<&str>) {
+    let trimmed = text.trim_end();
+    let Some((opening, rest)) = trimmed.split_once('\n') else {
+        return (text, None);
+    };
+    let Some(info) = opening.trim().strip_prefix("```") else {
+        return (text, None);
+    };
+    let Some(code) = rest.strip_suffix("```") else {
+        return (text, None);
+    };
+    let Some(code) = code.strip_suffix('\n').or(code.is_empty().then_some(""))
+    else {
+        return (text, None);
+    };
+    let language = info.split_whitespace().next();
+    (code, language)
+}
+
 #[cfg(test)]
 mod test {
     use crate::tests::testutils_composer_model::{cm, tx};
+    use crate::tests::testutils_conversion::utf16;
     use indoc::indoc;
 
     #[test]
@@ -537,4 +627,93 @@ mod test {
         <pre><code>|C</code></pre>"
         );
     }
+
+    #[test]
+    fn insert_code_block_with_text_into_empty_dom() {
+        let mut model = cm("|");
+        model.insert_code_block_with_text(utf16("a\n\nb\n"), None);
+        assert_eq!(tx(&model), "<pre><code>a\n\nb|</code></pre>");
+    }
+
+    #[test]
+    fn insert_code_block_with_text_keeps_a_second_trailing_newline() {
+        let mut model = cm("|");
+        model.insert_code_block_with_text(utf16("a\r\n\r\n"), None);
+        assert_eq!(tx(&model), "<pre><code>a\n&nbsp;|</code></pre>");
+    }
+
+    #[test]
+    fn insert_code_block_with_text_sets_the_language() {
+        let mut model = cm("|");
+        model.insert_code_block_with_text(utf16("a"), Some("rust"));
+        assert_eq!(
+            tx(&model),
+            "<pre><code class=\"language-rust\">a|</code></pre>"
+        );
+    }
+
+    #[test]
+    fn insert_code_block_with_fenced_text_drops_the_fences() {
+        let mut model = cm("|");
+        model.insert_code_block_with_text(
+            utf16("```rust\nlet a = 1;\n```\n"),
+            None,
+        );
+        assert_eq!(
+            tx(&model),
+            "<pre><code class=\"language-rust\">let a = 1;|</code></pre>"
+        );
+    }
+
+    #[test]
+    fn insert_code_block_with_text_prefers_the_given_language() {
+        let mut model = cm("|");
+        model.insert_code_block_with_text(utf16("```rust\na\n```"), Some("c"));
+        assert_eq!(
+            tx(&model),
+            "<pre><code class=\"language-c\">a|</code></pre>"
+        );
+    }
+
+    #[test]
+    fn insert_code_block_with_text_splits_the_paragraph() {
+        let mut model = cm("<p>ab|cd</p>");
+        model.insert_code_block_with_text(utf16("x\ny"), None);
+        assert_eq!(
+            tx(&model),
+            "<p>ab</p><pre><code>x\ny|</code></pre><p>cd</p>"
+        );
+    }
+
+    #[test]
+    fn insert_code_block_with_text_after_inline_text() {
+        let mut model = cm("ab|");
+        model.insert_code_block_with_text(utf16("x"), None);
+        assert_eq!(tx(&model), "<p>ab</p><pre><code>x|</code></pre>");
+    }
+
+    #[test]
+    fn insert_code_block_with_text_replaces_the_selection() {
+        let mut model = cm("<p>a{bc}|d</p><p>e</p>");
+        model.insert_code_block_with_text(utf16("x"), None);
+        assert_eq!(
+            tx(&model),
+            "<p>a</p><pre><code>x|</code></pre><p>d</p><p>e</p>"
+        );
+    }
+
+    #[test]
+    fn insert_code_block_with_text_replaces_an_empty_paragraph() {
+        let mut model = cm("<p>a</p><p>|</p><p>b</p>");
+        model.insert_code_block_with_text(utf16("x"), None);
+        assert_eq!(tx(&model), "<p>a</p><pre><code>x|</code></pre><p>b</p>");
+    }
+
+    #[test]
+    fn insert_code_block_with_text_is_one_undo_step() {
+        let mut model = cm("ab|");
+        model.insert_code_block_with_text(utf16("x\ny"), None);
+        model.undo();
+        assert_eq!(tx(&model), "ab|");
+    }
 }
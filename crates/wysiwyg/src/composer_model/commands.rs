@@ -0,0 +1,452 @@
+// Copyright 2023 The Matrix.org Foundation C.I.C.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Running commands by name, e.g. `execute("set_link", r#"{"url": ".."}"#)`,
+//! so scripts and tests can drive the model without a method per command.
+//!
+//! Every command has one [CommandInfo] in [COMMANDS], which is what
+//! [ComposerModel::get_commands] lists, and one arm in
+//! [ComposerModel::execute].
+
+use serde_json::{json, Map, Value};
+
+use crate::composer_model::menu_state::MenuStateComputeType;
+use crate::dom::DomCreationError;
+use crate::CommandArgType::{self, Attributes, Location as Loc, String as Str};
+use crate::{
+    CommandArg, CommandError, CommandInfo, ComposerModel, ComposerUpdate,
+    Location, TextUpdate, UnicodeString,
+};
+
+const fn arg(
+    name: &'static str,
+    arg_type: CommandArgType,
+    required: bool,
+) -> CommandArg {
+    CommandArg {
+        name,
+        arg_type,
+        required,
+    }
+}
+
+const fn command(
+    name: &'static str,
+    description: &'static str,
+    args: &'static [CommandArg],
+) -> CommandInfo {
+    CommandInfo {
+        name,
+        description,
+        args,
+    }
+}
+
+pub(crate) static COMMANDS: [CommandInfo; 32] = [
+    command("bold", "Toggle bold on the selection", &[]),
+    command("italic", "Toggle italic on the selection", &[]),
+    command(
+        "strike_through",
+        "Toggle strikethrough on the selection",
+        &[],
+    ),
+    command("underline", "Toggle underline on the selection", &[]),
+    command("inline_code", "Toggle inline code on the selection", &[]),
+    command("ordered_list", "Toggle an ordered list", &[]),
+    command("unordered_list", "Toggle an unordered list", &[]),
+    command("indent", "Indent the selected list items", &[]),
+    command("unindent", "Unindent the selected list items", &[]),
+    command("code_block", "Toggle a code block", &[]),
+    command("quote", "Toggle a quote", &[]),
+    command(
+        "increase_quote_level",
+        "Nest the selected blocks one quote deeper",
+        &[],
+    ),
+    command(
+        "decrease_quote_level",
+        "Remove the innermost quote around the selection",
+        &[],
+    ),
+    command("undo", "Undo the last change", &[]),
+    command("redo", "Redo the last undone change", &[]),
+    command(
+        "replace_text",
+        "Replace the selection with text",
+        &[arg("text", Str, true)],
+    ),
+    command(
+        "select",
+        "Select between two locations",
+        &[arg("start", Loc, true), arg("end", Loc, true)],
+    ),
+    command("backspace", "Delete backwards", &[]),
+    command("delete", "Delete forwards", &[]),
+    command("backspace_word", "Delete the word before the cursor", &[]),
+    command("delete_word", "Delete the word after the cursor", &[]),
+    command("enter", "Start a new paragraph or list item", &[]),
+    command("tab", "Indent in a list or code block", &[]),
+    command("shift_tab", "Unindent in a list or code block", &[]),
+    command("clear", "Remove all content", &[]),
+    command(
+        "set_link",
+        "Make the selection a link",
+        &[arg("url", Str, true), arg("attributes", Attributes, false)],
+    ),
+    command(
+        "set_link_with_text",
+        "Replace the selection with a link",
+        &[
+            arg("url", Str, true),
+            arg("text", Str, true),
+            arg("attributes", Attributes, false),
+        ],
+    ),
+    command("remove_links", "Remove links from the selection", &[]),
+    command(
+        "insert_mention",
+        "Replace the selection with a mention",
+        &[
+            arg("url", Str, true),
+            arg("text", Str, true),
+            arg("attributes", Attributes, false),
+        ],
+    ),
+    command(
+        "insert_at_room_mention",
+        "Replace the selection with an @room mention",
+        &[arg("attributes", Attributes, false)],
+    ),
+    command(
+        "set_content_from_html",
+        "Replace all content with HTML",
+        &[arg("html", Str, true)],
+    ),
+    command(
+        "set_content_from_markdown",
+        "Replace all content with Markdown",
+        &[arg("markdown", Str, true)],
+    ),
+];
+
+impl<S> ComposerModel<S>
+where
+    S: UnicodeString,
+{
+    /// Every command which can be run with [Self::execute].
+    pub fn get_commands(&self) -> &'static [CommandInfo] {
+        &COMMANDS
+    }
+
+    /// [Self::get_commands] as a JSON array, with one object per command
+    /// giving its name, description and arguments.
+    pub fn get_commands_as_json(&self) -> String {
+        let commands: Vec<Value> = COMMANDS
+            .iter()
+            .map(|command| {
+                let args: Vec<Value> = command
+                    .args
+                    .iter()
+                    .map(|arg| {
+                        json!({
+                            "name": arg.name,
+                            "type": arg.arg_type.as_str(),
+                            "required": arg.required,
+                        })
+                    })
+                    .collect();
+                json!({
+                    "name": command.name,
+                    "description": command.description,
+                    "args": args,
+                })
+            })
+            .collect();
+        Value::Array(commands).to_string()
+    }
+
+    /// Run the command called `name`, as listed by [Self::get_commands].
+    /// `args` is a JSON object holding the command's arguments, and may be
+    /// empty for commands without any.
+    pub fn execute(
+        &mut self,
+        name: &str,
+        args: &str,
+    ) -> Result<ComposerUpdate<S>, CommandError> {
+        let args = CommandArgs::parse(name, args)?;
+        self.run_command(name, &args)
+    }
+
+    /// Run several commands, as `(name, args)` pairs, one after the other,
+    /// returning a single update for all of them, e.g. to select a word and
+    /// then bold it in one call.
+    ///
+    /// The arguments of every command are checked before any of them runs,
+    /// so these errors don't change the content. A command which fails while
+    /// running leaves the commands before it applied.
+    pub fn execute_batch(
+        &mut self,
+        commands: &[(&str, &str)],
+    ) -> Result<ComposerUpdate<S>, CommandError> {
+        let args = commands
+            .iter()
+            .map(|(name, args)| CommandArgs::parse(name, args))
+            .collect::<Result<Vec<_>, _>>()?;
+
+        // Updates are worked out against what was last reported, so put
+        // that back before making the one update for the whole batch.
+        let reported = (
+            self.action_states.clone(),
+            self.disabled_reasons.clone(),
+            self.reported_history_depths,
+            self.reported_quote_depth,
+            self.reported_content.clone(),
+            self.found_mentions.clone(),
+        );
+        let mut text_changed = false;
+        let mut selection_changed = false;
+        let mut result = Ok(());
+        for ((name, _), args) in commands.iter().zip(&args) {
+            match self.run_command(name, args) {
+                Ok(update) => match update.text_update {
+                    TextUpdate::ReplaceAll(_) => text_changed = true,
+                    TextUpdate::Select(_) => selection_changed = true,
+                    TextUpdate::Keep => {}
+                },
+                Err(e) => {
+                    result = Err(e);
+                    break;
+                }
+            }
+        }
+        (
+            self.action_states,
+            self.disabled_reasons,
+            self.reported_history_depths,
+            self.reported_quote_depth,
+            self.reported_content,
+            self.found_mentions,
+        ) = reported;
+        result?;
+
+        Ok(if text_changed {
+            self.create_update_replace_all()
+        } else if selection_changed {
+            self.create_update_update_selection()
+        } else {
+            let menu_state =
+                self.compute_menu_state(MenuStateComputeType::KeepIfUnchanged);
+            let revision = self.next_revision();
+            ComposerUpdate::update_menu_state(
+                menu_state,
+                self.compute_menu_action(),
+                revision,
+            )
+        })
+    }
+
+    fn run_command(
+        &mut self,
+        name: &str,
+        args: &CommandArgs,
+    ) -> Result<ComposerUpdate<S>, CommandError> {
+        let update = match name {
+            "bold" => self.bold(),
+            "italic" => self.italic(),
+            "strike_through" => self.strike_through(),
+            "underline" => self.underline(),
+            "inline_code" => self.inline_code(),
+            "ordered_list" => self.ordered_list(),
+            "unordered_list" => self.unordered_list(),
+            "indent" => self.indent(),
+            "unindent" => self.unindent(),
+            "code_block" => self.code_block(),
+            "quote" => self.quote(),
+            "increase_quote_level" => self.increase_quote_level(),
+            "decrease_quote_level" => self.decrease_quote_level(),
+            "undo" => self.undo(),
+            "redo" => self.redo(),
+            "replace_text" => self.replace_text(args.string("text")),
+            "select" => {
+                self.select(args.location("start"), args.location("end"))
+            }
+            "backspace" => self.backspace(),
+            "delete" => self.delete(),
+            "backspace_word" => self.backspace_word(),
+            "delete_word" => self.delete_word(),
+            "enter" => self.enter(),
+            "tab" => self.handle_tab(),
+            "shift_tab" => self.handle_shift_tab(),
+            "clear" => self.clear(),
+            "set_link" => self.set_link(args.string("url"), args.attributes()),
+            "set_link_with_text" => self.set_link_with_text(
+                args.string("url"),
+                args.string("text"),
+                args.attributes(),
+            ),
+            "remove_links" => self.remove_links(),
+            "insert_mention" => self.insert_mention(
+                args.string("url"),
+                args.string("text"),
+                args.attributes(),
+            ),
+            "insert_at_room_mention" => {
+                self.insert_at_room_mention(args.attributes())
+            }
+            "set_content_from_html" => self
+                .set_content_from_html(&args.string("html"))
+                .map_err(command_failed)?,
+            "set_content_from_markdown" => self
+                .set_content_from_markdown(&args.string("markdown"))
+                .map_err(command_failed)?,
+            _ => unreachable!("{name} is in COMMANDS but can't be executed"),
+        };
+        Ok(update)
+    }
+}
+
+fn command_failed(error: DomCreationError) -> CommandError {
+    let message = match error {
+        DomCreationError::HtmlParseError(e) => e.parse_errors.join(", "),
+        DomCreationError::MarkdownParseError(e) => e.to_string(),
+    };
+    CommandError::Failed(message)
+}
+
+/// The arguments of a command, checked against its [CommandInfo] so that
+/// the getters below can't fail.
+struct CommandArgs {
+    args: Map<String, Value>,
+}
+
+impl CommandArgs {
+    fn parse(name: &str, json: &str) -> Result<Self, CommandError> {
+        let Some(command) = COMMANDS.iter().find(|c| c.name == name) else {
+            return Err(CommandError::UnknownCommand(name.to_owned()));
+        };
+        let invalid = |message: String| {
+            CommandError::InvalidArguments(format!(
+                "{}: {message}",
+                command.name
+            ))
+        };
+        let args = if json.trim().is_empty() {
+            Map::new()
+        } else {
+            match serde_json::from_str(json) {
+                Ok(Value::Object(args)) => args,
+                Ok(Value::Null) => Map::new(),
+                Ok(_) => return Err(invalid("expected an object".to_owned())),
+                Err(e) => return Err(invalid(e.to_string())),
+            }
+        };
+
+        if let Some(name) = args
+            .keys()
+            .find(|k| !command.args.iter().any(|a| &a.name == k))
+        {
+            return Err(invalid(format!("unexpected argument {name}")));
+        }
+        for arg in command.args {
+            let Some(value) = args.get(arg.name) else {
+                if arg.required {
+                    return Err(invalid(format!(
+                        "missing argument {}",
+                        arg.name
+                    )));
+                }
+                continue;
+            };
+            let valid = match arg.arg_type {
+                Str => value.is_string(),
+                Loc => value.is_u64(),
+                Attributes => value
+                    .as_object()
+                    .map_or(false, |o| o.values().all(Value::is_string)),
+            };
+            if !valid {
+                return Err(invalid(format!(
+                    "{} should be a {}",
+                    arg.name,
+                    arg.arg_type.as_str()
+                )));
+            }
+        }
+        Ok(Self { args })
+    }
+
+    fn string<S: UnicodeString>(&self, name: &str) -> S {
+        S::from(self.args[name].as_str().unwrap())
+    }
+
+    fn location(&self, name: &str) -> Location {
+        Location::from(self.args[name].as_u64().unwrap() as usize)
+    }
+
+    /// The `attributes` argument, or none if it wasn't given.
+    fn attributes<S: UnicodeString>(&self) -> Vec<(S, S)> {
+        let Some(Value::Object(attributes)) = self.args.get("attributes")
+        else {
+            return Vec::new();
+        };
+        attributes
+            .iter()
+            .map(|(k, v)| (S::from(k.as_str()), S::from(v.as_str().unwrap())))
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::collections::HashSet;
+
+    use crate::tests::testutils_composer_model::cm;
+    use crate::{CommandArgType, CommandError};
+
+    use super::COMMANDS;
+
+    #[test]
+    fn every_command_has_a_different_name() {
+        let mut seen = HashSet::new();
+        for command in COMMANDS.iter() {
+            assert!(
+                seen.insert(command.name),
+                "{} is listed twice",
+                command.name
+            );
+        }
+    }
+
+    #[test]
+    fn every_command_can_be_executed() {
+        for command in COMMANDS.iter() {
+            let args: Vec<String> = command
+                .args
+                .iter()
+                .filter(|a| a.required)
+                .map(|a| match a.arg_type {
+                    CommandArgType::Location => format!("\"{}\": 0", a.name),
+                    _ => format!("\"{}\": \"\"", a.name),
+                })
+                .collect();
+            let args = format!("{{{}}}", args.join(", "));
+            let result = cm("a{bc}|").execute(command.name, &args);
+            assert!(
+                !matches!(result, Err(CommandError::UnknownCommand(_))),
+                "{} can't be executed",
+                command.name
+            );
+        }
+    }
+}
@@ -0,0 +1,243 @@
+// Copyright 2023 The Matrix.org Foundation C.I.C.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use crate::composer_model::delete_text::Direction;
+use crate::dom::nodes::dom_node::DomNodeKind;
+use crate::dom::unicode_string::UnicodeStrExt;
+use crate::dom::{DomHandle, DomLocation};
+use crate::{
+    ComposerModel, ComposerUpdate, Granularity, Location, UnicodeString,
+};
+
+/// What the cursor moves over in one step.
+pub(crate) enum Step {
+    /// A grapheme of text, starting with this character.
+    Text(char),
+    /// A node the cursor can't be put inside, e.g. a mention.
+    Atomic,
+    /// A line break or the boundary between two blocks.
+    Boundary,
+}
+
+impl<S> ComposerModel<S>
+where
+    S: UnicodeString,
+{
+    /// Move the cursor as the arrow keys do, so hosts don't have to work
+    /// out where it goes. Mentions and immutable links are stepped over as
+    /// one character, and the cursor never ends up inside a grapheme.
+    ///
+    /// If there is a selection, moving by character collapses it to its
+    /// start or end, and moving by word starts from there.
+    pub fn move_cursor(
+        &mut self,
+        direction: Direction,
+        granularity: Granularity,
+    ) -> ComposerUpdate<S> {
+        let (s, e) = self.safe_selection();
+        let from = match direction {
+            Direction::Forwards => e,
+            Direction::Backwards => s,
+        };
+        let to = match granularity {
+            Granularity::Character if s != e => from,
+            Granularity::Character => self
+                .cursor_step(from, &direction)
+                .map_or(from, |(to, _)| to),
+            Granularity::Word => self.word_step(from, &direction),
+        };
+        self.select(Location::from(to), Location::from(to))
+    }
+
+    /// The start of the line containing `position`, e.g. where Home moves
+    /// the cursor. Lines end at line breaks and at the edges of blocks, not
+    /// where the host wraps text.
+    pub fn line_start_offset(&self, position: Location) -> Location {
+        Location::from(self.line_range_at(position).0)
+    }
+
+    /// The end of the line containing `position`, e.g. where End moves the
+    /// cursor. See [ComposerModel::line_start_offset].
+    pub fn line_end_offset(&self, position: Location) -> Location {
+        Location::from(self.line_range_at(position).1)
+    }
+
+    /// The (start, end) of the line containing `position`. Each line is a
+    /// run of adjoining leaves in one block without a line break between
+    /// them, so an empty block is a line of its own.
+    fn line_range_at(&self, position: Location) -> (usize, usize) {
+        let (pos, _) = self.safe_locations_from(position, position);
+        let range = self.state.dom.find_range(0, self.state.dom.text_len());
+        let mut lines = Vec::new();
+        let mut current: Option<(DomHandle, usize, usize)> = None;
+        for leaf in range.leaves() {
+            let block = self.block_containing(&leaf.node_handle);
+            let (start, end) = match current.take() {
+                Some((b, start, end)) if b == block && end == leaf.position => {
+                    (start, end)
+                }
+                Some((_, start, end)) => {
+                    lines.push((start, end));
+                    (leaf.position, leaf.position)
+                }
+                None => (leaf.position, leaf.position),
+            };
+            let leaf_end = leaf.position + leaf.length;
+            if leaf.kind == DomNodeKind::LineBreak {
+                lines.push((start, end));
+                current = Some((block, leaf_end, leaf_end));
+            } else {
+                current = Some((block, start, leaf_end));
+            }
+        }
+        lines.extend(current.map(|(_, start, end)| (start, end)));
+        lines
+            .into_iter()
+            .find(|(start, end)| (*start..=*end).contains(&pos))
+            .unwrap_or((pos, pos))
+    }
+
+    /// The closest block node containing the node at `handle`.
+    pub(crate) fn block_containing(&self, handle: &DomHandle) -> DomHandle {
+        let mut block = handle.parent_handle();
+        while block.has_parent()
+            && !self.state.dom.lookup_node(&block).kind().is_block_kind()
+        {
+            block = block.parent_handle();
+        }
+        block
+    }
+
+    /// Where moving one word from `pos` puts the cursor: past any
+    /// whitespace and punctuation, then past the word after it. If there
+    /// is no word, past one atomic node or block boundary instead.
+    fn word_step(&self, mut pos: usize, direction: &Direction) -> usize {
+        let mut moved = false;
+        while let Some((next, Step::Text(c))) = self.cursor_step(pos, direction)
+        {
+            if is_word_char(c) {
+                break;
+            }
+            pos = next;
+            moved = true;
+        }
+        match self.cursor_step(pos, direction) {
+            Some((_, Step::Text(_))) => {}
+            Some((next, _)) if !moved => return next,
+            _ => return pos,
+        }
+        while let Some((next, Step::Text(c))) = self.cursor_step(pos, direction)
+        {
+            let joins_word = (c == '-' || self.locale.joins_words(c))
+                && matches!(
+                    self.cursor_step(next, direction),
+                    Some((_, Step::Text(c))) if c.is_alphanumeric()
+                );
+            if !is_word_char(c) && !joins_word {
+                break;
+            }
+            pos = next;
+        }
+        pos
+    }
+
+    /// The position one step from `pos` in `direction` and what was
+    /// stepped over, or None at the start or end of the document.
+    pub(crate) fn cursor_step(
+        &self,
+        pos: usize,
+        direction: &Direction,
+    ) -> Option<(usize, Step)> {
+        let at_edge = match direction {
+            Direction::Forwards => pos >= self.state.dom.text_len(),
+            Direction::Backwards => pos == 0,
+        };
+        if at_edge {
+            return None;
+        }
+        let range = self.state.dom.find_range(pos, pos);
+        let ahead = |l: &&DomLocation| match direction {
+            Direction::Forwards => l.start_offset < l.length,
+            Direction::Backwards => l.start_offset > 0,
+        };
+        let edge = |l: &DomLocation| match direction {
+            Direction::Forwards => l.position + l.length,
+            Direction::Backwards => l.position,
+        };
+
+        if let Some(atomic) = range
+            .locations
+            .iter()
+            .filter(ahead)
+            .find(|l| self.is_atomic(l))
+        {
+            return Some((edge(atomic), Step::Atomic));
+        }
+        let Some(leaf) = range.leaves().find(ahead) else {
+            return Some((direction.increment(pos), Step::Boundary));
+        };
+        match leaf.kind {
+            DomNodeKind::Text => {
+                let text = self
+                    .state
+                    .dom
+                    .lookup_node(&leaf.node_handle)
+                    .as_text()
+                    .unwrap()
+                    .data();
+                let offset = leaf.start_offset;
+                let (start, end) = match direction {
+                    Direction::Forwards => (
+                        offset,
+                        text.next_grapheme_boundary(offset)
+                            .unwrap_or(offset + 1),
+                    ),
+                    Direction::Backwards => (
+                        text.previous_grapheme_boundary(offset)
+                            .unwrap_or(offset - 1),
+                        offset,
+                    ),
+                };
+                // Offsets are in code units, so find the character through
+                // the grapheme starting there rather than by char index.
+                let c = text
+                    .grapheme_clusters()
+                    .take_while(|(g_start, _)| *g_start <= start)
+                    .last()
+                    .and_then(|(_, g)| g.chars().next())?;
+                let next = match direction {
+                    Direction::Forwards => end,
+                    Direction::Backwards => start,
+                };
+                Some((leaf.position + next, Step::Text(c)))
+            }
+            DomNodeKind::LineBreak => Some((edge(leaf), Step::Boundary)),
+            _ => Some((edge(leaf), Step::Atomic)),
+        }
+    }
+
+    /// Whether the cursor must step over the node at `location` in one go.
+    fn is_atomic(&self, location: &DomLocation) -> bool {
+        location.kind == DomNodeKind::Link
+            && self
+                .state
+                .dom
+                .lookup_container(&location.node_handle)
+                .is_immutable_link()
+    }
+}
+
+fn is_word_char(c: char) -> bool {
+    c.is_alphanumeric() || c == '_'
+}
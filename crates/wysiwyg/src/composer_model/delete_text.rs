@@ -12,14 +12,17 @@
 // See the License for the specific language governing permissions and
 // limitations under the License.
 
-use crate::dom::nodes::dom_node::DomNodeKind::{Link, ListItem};
-use crate::dom::nodes::text_node::CharType;
+use crate::composer_model::cursor_movement::Step;
+use crate::dom::nodes::dom_node::DomNodeKind::{
+    CodeBlock, Link, ListItem, Quote,
+};
+use crate::dom::nodes::text_node::{get_char_type, CharType};
 use crate::dom::nodes::{DomNode, TextNode};
 use crate::dom::unicode_string::UnicodeStrExt;
 use crate::dom::{DomHandle, DomLocation, Range};
 use crate::{ComposerModel, ComposerUpdate, Location, UnicodeString};
 
-#[derive(PartialEq, Eq, Debug)]
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
 pub enum Direction {
     Forwards,
     Backwards,
@@ -45,14 +48,21 @@ where
     S: UnicodeString,
 {
     pub fn backspace(&mut self) -> ComposerUpdate<S> {
+        if let Some(before) = self.take_automatic_action() {
+            // Restore the text as it was typed
+            self.push_state_to_history();
+            self.restore_state(before);
+            return self.create_update_replace_all();
+        }
         self.push_state_to_history();
         self.handle_non_editable_selection(&Direction::Backwards);
+        if self.remove_horizontal_rule_next_to_cursor(&Direction::Backwards) {
+            return self.create_update_replace_all();
+        }
 
         let (s, e) = self.safe_selection();
         if s == e {
             // We have no selection - check for special list behaviour
-            // TODO: should probably also get inside here if our selection
-            // only contains a zero-width space.
             let range = self.state.dom.find_range(s, e);
             self.backspace_single_cursor(range)
         } else {
@@ -64,7 +74,7 @@ where
     /// we don't have a selection
     fn delete_selection(&mut self) -> ComposerUpdate<S> {
         if self.has_cursor() {
-            return ComposerUpdate::keep();
+            return ComposerUpdate::keep_at(self.state.revision);
         }
 
         let (s, e) = self.safe_selection();
@@ -134,7 +144,7 @@ where
                     .lookup_container(&link.node_handle)
                     .is_immutable_link()
                 {
-                    self.select(
+                    self.select_without_update(
                         Location::from(link.position),
                         Location::from(link.position + link.length),
                     );
@@ -151,6 +161,9 @@ where
 
     pub fn do_delete(&mut self) -> ComposerUpdate<S> {
         self.handle_non_editable_selection(&Direction::Forwards);
+        if self.remove_horizontal_rule_next_to_cursor(&Direction::Forwards) {
+            return self.create_update_replace_all();
+        }
 
         if self.state.start == self.state.end {
             let (s, _) = self.safe_selection();
@@ -182,8 +195,11 @@ where
         self.remove_word_in_direction(Direction::Backwards)
     }
 
-    /// Given a direction will get the remove word arguments and then run 'remove_word'
-    /// if arguments can be generated
+    /// Remove the whitespace before or after the cursor, then the run of
+    /// punctuation or other characters next to it, even if it crosses
+    /// formatting or links. If the whitespace is followed by a line break,
+    /// a boundary between blocks or a mention, only that is removed after
+    /// it.
     fn remove_word_in_direction(
         &mut self,
         direction: Direction,
@@ -192,178 +208,62 @@ where
         if self.has_selection() {
             return self.delete_selection();
         }
-
-        let args = self.get_remove_word_arguments(&direction);
-        match args {
-            None => ComposerUpdate::keep(),
-            Some(arguments) => {
-                // here we have a non-split cursor, a single location, and a textlike node
-                let (location, start_type) = arguments;
-                self.remove_word(start_type, direction, location)
-            }
-        }
-    }
-
-    /// Remove the word runs from the dom and can recursively call itself
-    fn remove_word(
-        &mut self,
-        start_type: CharType,
-        direction: Direction,
-        location: DomLocation,
-    ) -> ComposerUpdate<S> {
-        // we could have entered a non-editable node during this run, if this is the
-        // case, we handle it by calling the relecant method once which will adjust the
-        // selection to cover that node and then remove it, ending the recursive calls
+        // Non-editable nodes are removed whole, like with delete/backspace
         if self.cursor_is_inside_non_editable_text_node() {
-            // TODO fix the divergence in behaviour between delete and backspace.
-            // `do_delete` was recently added and there's some work needed to make
-            // backspace and delete be equivalent, as well as the do_* functions
             return match direction {
-                Direction::Forwards => self.do_delete(),
+                Direction::Forwards => self.delete(),
                 Direction::Backwards => self.backspace(),
             };
         }
-        match self.state.dom.lookup_node_mut(&location.node_handle) {
-            DomNode::Container(_) | DomNode::LineBreak(_) => match start_type {
-                CharType::Whitespace => self.delete_to_cursor(
-                    direction.increment(location.index_in_dom()),
-                ),
-                _ => ComposerUpdate::keep(),
-            },
-            DomNode::Mention(_) => self
-                .delete_to_cursor(direction.increment(location.index_in_dom())),
-            DomNode::Text(node) => {
-                // we are guaranteed to get valid chars here, so can use unwrap
-                let mut current_offset = location.start_offset;
-                let mut current_type = node
-                    .char_type_at_offset(current_offset, &direction)
-                    .unwrap();
-
-                while node.offset_is_inside_node(current_offset, &direction)
-                    && current_type == start_type
-                {
-                    let next_offset = direction.increment(current_offset);
-                    let next_type = node
-                        .char_type_at_offset(current_offset, &direction)
-                        .unwrap();
 
-                    if next_type != current_type {
-                        break;
-                    }
-
-                    current_offset = next_offset;
-                    current_type = next_type;
-                }
-
-                // determine our current position in the dom
-                let current_position = location.position + current_offset;
-                let offset_is_inside_node =
-                    node.offset_is_inside_node(current_offset, &direction);
-
-                // delete to the cursor
-                let update = self.delete_to_cursor(current_position);
-
-                // if we have stopped inside the node and we didn't start at whitespace, stop
-                if offset_is_inside_node && start_type != CharType::Whitespace {
-                    return update;
-                }
-
-                // otherwise make a recursive call
-                let next_args = self.get_remove_word_arguments(&direction);
-                match next_args {
-                    None => update,
-                    Some(args) => {
-                        let (location, next_type) = args;
-                        let type_argument = if offset_is_inside_node {
-                            // where we finished inside the node, get the next type when we
-                            // are making a recursive call after having removed whitespace
-                            next_type
-                        } else {
-                            // if we hit the edge of the node, we need to make the next call
-                            // with the same initial starting type
-                            start_type
-                        };
-
-                        self.remove_word(type_argument, direction, location)
-                    }
-                }
-            }
+        let (s, _) = self.safe_selection();
+        let end = self.end_of_word_to_remove(s, &direction);
+        if end == s {
+            ComposerUpdate::keep_at(self.state.revision)
+        } else {
+            self.delete_to_cursor(end)
         }
     }
 
-    /// In order for the recursive calls to work we need quite a few details
-    /// from the cursor location, this gets those details and returns them
-    /// as a tuple.
-    fn get_remove_word_arguments(
+    /// Where removing a word from `start` in `direction` stops, walking
+    /// across node boundaries.
+    fn end_of_word_to_remove(
         &self,
+        start: usize,
         direction: &Direction,
-    ) -> Option<(DomLocation, CharType)> {
-        let (s, e) = self.safe_selection();
-        let range = self.state.dom.find_range(s, e);
-        let dom_length = self.state.dom.text_len();
-
-        if self.has_selection() {
-            return None;
-        }
-
-        let selected_location = range
-            .locations
-            .iter()
-            .find(|loc| {
-                // do not include the location if it is at the wrong end of the dom
-                let exclude_due_to_end_of_dom = match direction {
-                    Direction::Forwards => e == dom_length,
-                    Direction::Backwards => s == 0,
-                };
-
-                let inside_location = loc.start_offset < loc.length;
-
-                let is_correct_boundary_location = match direction {
-                    Direction::Forwards => loc.start_offset == 0,
-                    Direction::Backwards => loc.start_offset == loc.length,
-                };
-
-                // find the first location that we are inside, or if we're at a boundary,
-                // choose based on direction
-                !exclude_due_to_end_of_dom
-                    && (inside_location || is_correct_boundary_location)
-            })
-            .cloned();
-        match selected_location {
-            None => None,
-            Some(location) => {
-                let char_type =
-                    self.get_char_type_from_location(&location, direction);
-                char_type.map(|char_type| (location, char_type))
+    ) -> usize {
+        let mut pos = start;
+        while let Some((next, Step::Text(c))) = self.cursor_step(pos, direction)
+        {
+            if !c.is_whitespace() {
+                break;
             }
+            pos = next;
         }
-    }
 
-    /// Given a location, return the type of character adjacent to the cursor offset position
-    /// bearing in mind the direction
-    fn get_char_type_from_location(
-        &self,
-        location: &DomLocation,
-        direction: &Direction,
-    ) -> Option<CharType> {
-        let node = self.state.dom.lookup_node(&location.node_handle);
-        match node {
-            DomNode::Container(node) => {
-                if node.is_empty() {
-                    Some(CharType::Whitespace)
-                } else {
-                    None
-                }
-            }
-            DomNode::LineBreak(_) => {
-                // we have to treat linebreaks as chars, this type fits best
-                Some(CharType::Whitespace)
+        let run_type = match self.cursor_step(pos, direction) {
+            None => return pos,
+            Some((next, Step::Text(c))) => {
+                pos = next;
+                get_char_type(c)
             }
-            DomNode::Mention(_) => Some(CharType::Other),
-            DomNode::Text(text_node) => {
-                text_node.char_type_at_offset(location.start_offset, direction)
+            Some((next, Step::Atomic | Step::Boundary)) => return next,
+        };
+        while let Some((next, Step::Text(c))) = self.cursor_step(pos, direction)
+        {
+            let joins_word = run_type == CharType::Other
+                && self.locale.joins_words(c)
+                && matches!(
+                    self.cursor_step(next, direction),
+                    Some((_, Step::Text(after)))
+                        if get_char_type(after) == CharType::Other
+                );
+            if get_char_type(c) != run_type && !joins_word {
+                break;
             }
+            pos = next;
         }
+        pos
     }
 
     fn backspace_single_cursor(&mut self, range: Range) -> ComposerUpdate<S> {
@@ -379,12 +279,36 @@ where
             // we are at the start of a list item.
             let parent_list_item_loc =
                 range.deepest_node_of_kind(ListItem, Some(&leaf.node_handle));
-            if let Some(list_item_loc) = parent_list_item_loc {
+            let parent_code_block_loc =
+                range.deepest_node_of_kind(CodeBlock, Some(&leaf.node_handle));
+            // The quote the cursor is at the start of, unless a list item
+            // or code block inside it is handled instead
+            let parent_quote_loc = range
+                .deepest_node_of_kind(Quote, Some(&leaf.node_handle))
+                .filter(|q| {
+                    q.start_offset == 0
+                        && ![parent_list_item_loc, parent_code_block_loc]
+                            .into_iter()
+                            .flatten()
+                            .any(|l| {
+                                q.node_handle.is_ancestor_of(&l.node_handle)
+                            })
+                });
+            if let Some(quote_loc) = parent_quote_loc {
+                self.do_backspace_at_start_of_quote(&quote_loc.node_handle)
+            } else if let Some(list_item_loc) = parent_list_item_loc {
                 if list_item_loc.start_offset == 0 {
                     self.do_backspace_in_list(&list_item_loc.node_handle)
                 } else {
                     self.do_backspace()
                 }
+            } else if let Some(code_block_loc) = parent_code_block_loc.filter(
+                // Anywhere else, backspace merges into the block before
+                |l| l.position == 0 && l.start_offset == 0,
+            ) {
+                self.do_backspace_at_start_of_code_block(
+                    &code_block_loc.node_handle,
+                )
             } else {
                 self.do_backspace()
             }
@@ -451,25 +375,13 @@ where
 
     /// Returns the length of the [char] for the current [S] string encoding before the given [pos].
     fn find_previous_char_len(pos: usize, str: &S::Str) -> usize {
-        let graphemes = str.find_graphemes_at(pos);
-        // Take the grapheme before the position
-        if let Some(last_grapheme) = graphemes.0 {
-            last_grapheme.len()
-        } else {
-            // Default length for characters
-            1
-        }
+        // Default length for characters
+        str.previous_grapheme_boundary(pos).map_or(1, |b| pos - b)
     }
 
     /// Returns the length of the [char] for the current [S] string encoding after the given [pos].
     fn find_next_char_len(pos: usize, str: &S::Str) -> usize {
-        let graphemes = str.find_graphemes_at(pos);
-        // Take the grapheme after the position
-        if let Some(first_grapheme) = graphemes.1 {
-            first_grapheme.len()
-        } else {
-            // Default length for characters
-            1
-        }
+        // Default length for characters
+        str.next_grapheme_boundary(pos).map_or(1, |b| b - pos)
     }
 }
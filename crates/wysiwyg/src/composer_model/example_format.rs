@@ -15,20 +15,24 @@
 use std::collections::HashMap;
 use std::ops::Not;
 
-use widestring::{Utf16Str, Utf16String};
-
 use crate::char::CharExt;
 use crate::composer_model::menu_state::MenuStateComputeType;
-use crate::dom::nodes::{ContainerNode, LineBreakNode, MentionNode, TextNode};
+use crate::dom::nodes::{
+    ContainerNode, HorizontalRuleNode, ImageNode, LineBreakNode, MentionNode,
+    TextNode,
+};
 use crate::dom::parser::parse;
 use crate::dom::to_html::ToHtmlState;
-use crate::dom::unicode_string::{UnicodeStr, UnicodeStrExt};
+use crate::dom::unicode_string::UnicodeStr;
 use crate::dom::{Dom, DomLocation};
 use crate::{
     ComposerModel, DomHandle, DomNode, Location, ToHtml, UnicodeString,
 };
 
-impl ComposerModel<Utf16String> {
+impl<S> ComposerModel<S>
+where
+    S: UnicodeString,
+{
     /// Convenience function to allow working with ComposerModel instances
     /// based on ASCII-art-style representions.
     ///
@@ -54,20 +58,31 @@ impl ComposerModel<Utf16String> {
     /// selection starting at 4 and ending at 2 (i.e. the user dragged from
     /// right to left to select).
     ///
-    /// The characters `{`, `}` or `|` must not appear anywhere else in the
-    /// text.
+    /// To use `{`, `}` or `|` as text, escape them with a backslash, so
+    /// `a\{b|` means the text `a{b`, with the cursor at 3. Elsewhere these
+    /// characters must not appear in the text.
     ///
     /// HTML works, so `AA<b>B|B</b>CC` means a text node containing `AA`,
     /// followed by a bold node containing a text node containing `BB`,
     /// followed by a text node containing `CC`, with a selection starting and
-    /// ending at 3.
+    /// ending at 3. Any node the HTML parser understands can be written this
+    /// way, including blocks like `<p>`, `<blockquote>` and headings, and
+    /// alignment as `style="text-align: center"`. Atomic nodes, i.e.
+    /// mentions (the pills), images and horizontal rules, are written as
+    /// their HTML too, and the selection can go before or after them but not
+    /// inside. The selection inside maths goes in its `data-mx-maths`
+    /// attribute, which holds the source, e.g.
+    /// `<span data-mx-maths="x|^2"><code>x^2</code></span>`. Tables are not
+    /// supported, because the model has no table nodes.
     ///
     /// ## Examples
     ///
     /// ```
+    /// use widestring::Utf16String;
     /// use wysiwyg::{ComposerModel, Location, ToHtml, UnicodeString};
     ///
-    /// let mut model = ComposerModel::from_example_format("aa{bb}|cc");
+    /// let mut model =
+    ///     ComposerModel::<Utf16String>::from_example_format("aa{bb}|cc");
     /// assert_eq!(model.state.dom.to_html().to_string(), "aabbcc");
     /// assert_eq!(model.state.start, 2);
     /// assert_eq!(model.state.end, 4);
@@ -76,7 +91,7 @@ impl ComposerModel<Utf16String> {
     /// ```
     pub fn from_example_format(text: &str) -> Self {
         let mut model = ComposerModel::new();
-        model.state.dom = parse(text).unwrap();
+        model.state.dom = parse(&hide_escaped_markers(text)).unwrap();
 
         let mut offset = 0;
         let (start, end, curs) = Self::find_selection_in(
@@ -88,8 +103,8 @@ impl ComposerModel<Utf16String> {
             panic!("Selection not found");
         };
 
-        fn delete_range(
-            model: &mut ComposerModel<Utf16String>,
+        fn delete_range<S: UnicodeString>(
+            model: &mut ComposerModel<S>,
             loc: &SelectionLocation,
             len: usize,
         ) {
@@ -97,11 +112,11 @@ impl ComposerModel<Utf16String> {
             if let DomNode::Text(text_node) =
                 model.state.dom.lookup_node_mut(&loc.handle)
             {
-                if text_node.data().len() == len {
+                if text_node.text_len() == len {
                     needs_deletion = true;
                 } else {
                     text_node.replace_range(
-                        Utf16String::new(),
+                        S::default(),
                         loc.offset,
                         loc.offset + len,
                     );
@@ -139,19 +154,26 @@ impl ComposerModel<Utf16String> {
             model.state.start = Location::from(curs.index_in_dom());
             model.state.end = Location::from(curs.index_in_dom());
         }
+        replace_text_chars(&mut model.state.dom, |c| {
+            LITERAL_MARKERS
+                .iter()
+                .find(|(_, placeholder)| *placeholder == c)
+                .map_or(c, |(marker, _)| *marker)
+        });
         model.compute_menu_state(MenuStateComputeType::KeepIfUnchanged);
         model
             .state
             .dom
             .wrap_inline_nodes_into_paragraphs_if_needed(&DomHandle::root());
         model.state.dom.explicitly_assert_invariants();
+        model.remember_reported_content();
 
         model
     }
 
     fn find_selection_in(
-        model: &Dom<Utf16String>,
-        node: &DomNode<Utf16String>,
+        model: &Dom<S>,
+        node: &DomNode<S>,
         offset: &mut usize,
     ) -> (
         Option<SelectionLocation>,
@@ -188,7 +210,7 @@ impl ComposerModel<Utf16String> {
             }
             DomNode::Text(text_node) => {
                 let start_pos = *offset;
-                let data: &Utf16Str = text_node.data();
+                let data: &S::Str = text_node.data();
                 for ch in data.chars() {
                     if ch == '{' {
                         start = Some(SelectionLocation::new(
@@ -227,11 +249,18 @@ impl ComposerModel<Utf16String> {
     ///
     /// See [ComposerModel::from_example_format] for the format used.
     pub fn to_example_format(&self) -> String {
-        // Clone the model because we will modify it to add selection markers
         let state = &self.state;
-        let dom = &state.dom;
-
-        let mut buf = Utf16String::new();
+        // Literal markers in the text are swapped for placeholders of the
+        // same length, so they can be told apart from the selection
+        let mut dom = state.dom.clone();
+        replace_text_chars(&mut dom, |c| {
+            LITERAL_MARKERS
+                .iter()
+                .find(|(marker, _)| *marker == c)
+                .map_or(c, |(_, placeholder)| *placeholder)
+        });
+
+        let mut buf = S::default();
 
         // Find out which nodes are involved in the selection
         let range = dom.find_range(state.start.into(), state.end.into());
@@ -275,7 +304,53 @@ impl ComposerModel<Utf16String> {
         }
 
         // Replace characters with visible ones
-        html.replace(char::nbsp(), "&nbsp;")
+        let mut html = html.replace(char::nbsp(), "&nbsp;");
+        for (marker, placeholder) in LITERAL_MARKERS {
+            html = html.replace(placeholder, &format!("\\{marker}"));
+        }
+        html
+    }
+}
+
+/// The characters with a special meaning in the example format, with the
+/// placeholders standing in for them while they are used as text.
+const LITERAL_MARKERS: [(char, char); 3] =
+    [('{', '\u{E000}'), ('}', '\u{E001}'), ('|', '\u{E002}')];
+
+/// `text` with each escaped marker, e.g. `\{`, replaced by its placeholder.
+fn hide_escaped_markers(text: &str) -> String {
+    let mut hidden = String::with_capacity(text.len());
+    let mut chars = text.chars().peekable();
+    while let Some(c) = chars.next() {
+        let placeholder = chars.peek().and_then(|next| {
+            LITERAL_MARKERS
+                .iter()
+                .find(|(marker, _)| c == '\\' && marker == next)
+                .map(|(_, placeholder)| *placeholder)
+        });
+        if let Some(placeholder) = placeholder {
+            chars.next();
+            hidden.push(placeholder);
+        } else {
+            hidden.push(c);
+        }
+    }
+    hidden
+}
+
+/// Map every character of every text node in `dom` with `f`, which must
+/// keep the length of the text the same.
+fn replace_text_chars<S: UnicodeString>(
+    dom: &mut Dom<S>,
+    f: impl Fn(char) -> char,
+) {
+    let handles: Vec<DomHandle> =
+        dom.iter_text().map(|text| text.handle()).collect();
+    for handle in handles {
+        if let DomNode::Text(text) = dom.lookup_node_mut(&handle) {
+            let data: String = text.data().chars().map(&f).collect();
+            text.set_data(S::from(data.as_str()));
+        }
     }
 }
 
@@ -318,7 +393,7 @@ impl SelectionWriter {
         node: &TextNode<S>,
     ) {
         if let Some(loc) = self.locations.get(&node.handle()) {
-            let strings_to_add = self.state.advance(loc, node.data().len());
+            let strings_to_add = self.state.advance(loc, node.text_len());
             for (string, i) in strings_to_add.into_iter().rev() {
                 buf.insert(start_pos + i, &S::from(string));
             }
@@ -366,6 +441,43 @@ impl SelectionWriter {
         }
     }
 
+    /// Write special selection (`{` and `}`) and cursor (`|`) characters
+    /// before or after an image node
+    ///
+    /// * `buf` - the output buffer up to and including the given node
+    /// * `start_pos` - the buffer position immediately before the node
+    pub fn write_selection_image_node<S: UnicodeString>(
+        &mut self,
+        buf: &mut S,
+        start_pos: usize,
+        node: &ImageNode<S>,
+    ) {
+        if let Some(loc) = self.locations.get(&node.handle()) {
+            let strings_to_add = self.state.advance(loc, 1);
+            for (str, i) in strings_to_add.into_iter().rev() {
+                let insert_pos = if i == 0 { start_pos } else { buf.len() };
+                buf.insert(insert_pos, &S::from(str));
+            }
+        }
+    }
+
+    /// Write special selection (`{` and `}`) and cursor (`|`) characters
+    /// before or after a horizontal rule
+    pub fn write_selection_horizontal_rule_node<S: UnicodeString>(
+        &mut self,
+        buf: &mut S,
+        start_pos: usize,
+        node: &HorizontalRuleNode<S>,
+    ) {
+        if let Some(loc) = self.locations.get(&node.handle()) {
+            let strings_to_add = self.state.advance(loc, 1);
+            for (str, i) in strings_to_add.into_iter().rev() {
+                let insert_pos = if i == 0 { start_pos } else { buf.len() };
+                buf.insert(insert_pos, &S::from(str));
+            }
+        }
+    }
+
     /// Write special selection (`{` and `}`) and cursor (`|`) characters
     /// after an empty container node
     ///
@@ -530,8 +642,8 @@ impl SelectionWritingState {
 
 #[cfg(all(test, not(target_arch = "wasm32")))]
 mod test {
+    use crate::tests::testutils_composer_model::TestString;
     use speculoos::{prelude::*, AssertionFailure, Spec};
-    use widestring::Utf16String;
 
     use crate::dom::nodes::dom_node::DomNodeKind;
     use crate::dom::{parser, Dom, DomLocation};
@@ -683,6 +795,7 @@ mod test {
     }
 
     #[test]
+    #[cfg_attr(feature = "test-utf32", ignore = "uses UTF-16 offsets")]
     fn cm_creates_correct_component_model_multi_code_unit_characters() {
         let t1 = cm("foo|\u{1F4A9}bar");
         assert_eq!(t1.state.start, 3);
@@ -749,6 +862,7 @@ mod test {
     }
 
     #[test]
+    #[cfg_attr(feature = "test-utf32", ignore = "uses UTF-16 offsets")]
     fn cm_creates_correct_model_selection_multi_code_units_selection() {
         let t3 = cm("\u{1F4A9}{def}|ghi");
         assert_eq!(t3.state.start, 2);
@@ -799,36 +913,39 @@ mod test {
 
     #[test]
     fn tx_formats_selection_spanning_outwards_from_tag_forwards() {
-        let model: ComposerModel<Utf16String> =
+        let model: ComposerModel<TestString> =
             ComposerModel::from_state(ComposerState {
                 dom: parser::parse("AAA<b>BBB</b>CCC").unwrap(),
                 start: Location::from(4),
                 end: Location::from(7),
                 toggled_format_types: Vec::new(),
+                revision: 0,
             });
         assert_eq!(tx(&model), "AAA<b>B{BB</b>C}|CC");
     }
 
     #[test]
     fn tx_formats_selection_spanning_outwards_from_tag_backwards() {
-        let model: ComposerModel<Utf16String> =
+        let model: ComposerModel<TestString> =
             ComposerModel::from_state(ComposerState {
                 dom: parser::parse("AAA<b>BBB</b>CCC").unwrap(),
                 start: Location::from(7),
                 end: Location::from(4),
                 toggled_format_types: Vec::new(),
+                revision: 0,
             });
         assert_eq!(tx(&model), "AAA<b>B|{BB</b>C}CC");
     }
 
     #[test]
     fn tx_formats_empty_model() {
-        let model: ComposerModel<Utf16String> =
+        let model: ComposerModel<TestString> =
             ComposerModel::from_state(ComposerState {
                 dom: Dom::default(),
                 start: Location::from(1),
                 end: Location::from(1),
                 toggled_format_types: Vec::new(),
+                revision: 0,
             });
         assert_eq!(tx(&model), "|");
     }
@@ -948,6 +1065,70 @@ mod test {
         .roundtrips();
     }
 
+    #[test]
+    fn cm_reads_escaped_markers_as_text() {
+        let model = cm("a\\{b\\}c\\|d|");
+        assert_eq!(model.get_content_as_html(), utf16("a{b}c|d"));
+        assert_eq!(model.state.start, 7);
+        assert_eq!(model.state.end, 7);
+    }
+
+    #[test]
+    fn tx_escapes_markers_in_text() {
+        let mut model = cm("|");
+        model.replace_text(utf16("{|}"));
+        assert_eq!(tx(&model), "\\{\\|\\}|");
+    }
+
+    #[test]
+    fn escaped_markers_next_to_a_selection_roundtrip() {
+        assert_that!("<b>a\\{</b>{b}|\\}").roundtrips();
+        assert_that!("<p>\\|</p><p>|{b\\}}</p>").roundtrips();
+    }
+
+    #[test]
+    fn selection_around_a_mention_roundtrips() {
+        assert_that!(
+            "a{<a data-mention-type=\"user\" href=\"https://matrix.to/#/@alice:matrix.org\" contenteditable=\"false\">Alice</a>}|b"
+        )
+        .roundtrips();
+    }
+
+    #[test]
+    fn headings_and_alignment_roundtrip() {
+        assert_that!("<h1>a|</h1><p>b</p>").roundtrips();
+        assert_that!("<h2>{a</h2><h3>b}|</h3>").roundtrips();
+        assert_that!("<p style=\"text-align: center\">a|</p>").roundtrips();
+        assert_that!("<h1 style=\"text-align: right\">|{a}</h1>").roundtrips();
+    }
+
+    #[test]
+    fn selection_around_an_image_roundtrips() {
+        assert_that!("a<img src=\"mxc://a/b\" alt=\"x\" />|b").roundtrips();
+        assert_that!("|<img src=\"mxc://a/b\" alt=\"x\" />").roundtrips();
+        assert_that!("{a<img src=\"mxc://a/b\" alt=\"x\" />}|").roundtrips();
+    }
+
+    #[test]
+    fn selection_around_a_horizontal_rule_roundtrips() {
+        assert_that!("<p>a</p>|<hr /><p>b</p>").roundtrips();
+        assert_that!("<p>{a</p><hr /><p>b}|</p>").roundtrips();
+    }
+
+    #[test]
+    fn selection_inside_math_roundtrips() {
+        assert_that!("a<span data-mx-maths=\"x|^2\"><code>x^2</code></span>")
+            .roundtrips();
+        assert_that!(
+            "<div data-mx-maths=\"{x}|^2\"><code>x^2</code></div><p>a</p>"
+        )
+        .roundtrips();
+        assert_that!(
+            "{a<span data-mx-maths=\"x &lt; 2}|\"><code>x &lt; 2</code></span>"
+        )
+        .roundtrips();
+    }
+
     trait Roundtrips<T> {
         fn roundtrips(&self);
     }
@@ -16,11 +16,14 @@ use std::collections::HashMap;
 
 use crate::composer_model::menu_state::MenuStateComputeType;
 use crate::dom::action_list::DomActionList;
+use crate::dom::nodes::dom_node::DomNodeKind;
 use crate::dom::nodes::{ContainerNodeKind, DomNode};
-use crate::dom::unicode_string::UnicodeStrExt;
+use crate::dom::range::DomLocationPosition;
 use crate::dom::{Dom, DomHandle, DomLocation, Range};
+use crate::format_type::{has_valid_color, is_valid_color};
 use crate::{
-    ComposerModel, ComposerUpdate, InlineFormatType, MenuAction, UnicodeString,
+    ComposerModel, ComposerUpdate, InlineFormatType, Location, MenuAction,
+    UnicodeString,
 };
 
 #[derive(Eq, PartialEq, Debug)]
@@ -56,13 +59,223 @@ where
     pub fn inline_code(&mut self) -> ComposerUpdate<S> {
         self.push_state_to_history();
         let format_type = InlineFormatType::InlineCode;
-        if self.action_is_reversed(format_type.action()) {
+        if self.format_is_reversed(&format_type) {
             self.unformat(format_type)
         } else {
             self.add_inline_code()
         }
     }
 
+    /// Toggle one of the tags allowed with `set_custom_inline_tags` (e.g.
+    /// `kbd`) on the selection, like [ComposerModel::bold] does for bold.
+    /// Does nothing if the tag is not allowed.
+    pub fn custom_format(&mut self, tag: &str) -> ComposerUpdate<S> {
+        let format = InlineFormatType::Custom(tag.to_ascii_lowercase());
+        if !self.is_allowed_format(&format) {
+            return ComposerUpdate::keep_at(self.state.revision);
+        }
+        self.push_state_to_history();
+        self.format_or_unformat(format)
+    }
+
+    /// Whether `format` may be applied: custom tags must have been allowed
+    /// with `set_custom_inline_tags` and colours must be valid.
+    pub(crate) fn is_allowed_format(&self, format: &InlineFormatType) -> bool {
+        match format {
+            InlineFormatType::Custom(tag) => {
+                self.custom_inline_tags.contains(tag)
+            }
+            format => has_valid_color(format),
+        }
+    }
+
+    /// Colour the selected text with `color` (e.g. `#ff0000`), replacing
+    /// any other colour it has. With nothing selected, text typed next gets
+    /// the colour. Does nothing unless `color` is in the form `#rrggbb`.
+    pub fn set_text_color(&mut self, color: &str) -> ComposerUpdate<S> {
+        if !is_valid_color(color) {
+            return ComposerUpdate::keep_at(self.state.revision);
+        }
+        self.push_state_to_history();
+        self.replace_format_of_kind(
+            is_text_color,
+            Some(InlineFormatType::TextColor(color.to_owned())),
+        )
+    }
+
+    /// Remove any colour from the selected text. With nothing selected,
+    /// text typed next gets no colour.
+    pub fn remove_text_color(&mut self) -> ComposerUpdate<S> {
+        self.push_state_to_history();
+        self.replace_format_of_kind(is_text_color, None)
+    }
+
+    /// Highlight the selected text with the background colour `color`
+    /// (e.g. `#ffff00`), replacing any other highlight it has. With nothing
+    /// selected, text typed next gets the highlight. Does nothing unless
+    /// `color` is in the form `#rrggbb`.
+    pub fn set_highlight(&mut self, color: &str) -> ComposerUpdate<S> {
+        if !is_valid_color(color) {
+            return ComposerUpdate::keep_at(self.state.revision);
+        }
+        self.push_state_to_history();
+        self.replace_format_of_kind(
+            is_highlight,
+            Some(InlineFormatType::Highlight(color.to_owned())),
+        )
+    }
+
+    /// Remove any highlight from the selected text. With nothing selected,
+    /// text typed next gets no highlight.
+    pub fn remove_highlight(&mut self) -> ComposerUpdate<S> {
+        self.push_state_to_history();
+        self.replace_format_of_kind(is_highlight, None)
+    }
+
+    /// Remove the formats matching `is_kind` from the selection and apply
+    /// `format` instead, if given. Used for colours, where only one of each
+    /// kind applies at a time.
+    fn replace_format_of_kind(
+        &mut self,
+        is_kind: fn(&InlineFormatType) -> bool,
+        format: Option<InlineFormatType>,
+    ) -> ComposerUpdate<S> {
+        let (s, e) = self.safe_selection();
+        let old_formats = self.formats_of_kind_in(s, e, is_kind);
+        if s == e {
+            self.state.toggled_format_types.retain(|f| !is_kind(f));
+            for old in old_formats {
+                if Some(&old) != format.as_ref()
+                    && self.format_is_reversed(&old)
+                {
+                    self.toggle_zero_length_format(&old);
+                }
+            }
+            if let Some(format) = format {
+                if !self.format_is_reversed(&format) {
+                    self.toggle_zero_length_format(&format);
+                }
+            }
+            ComposerUpdate::update_menu_state(
+                self.compute_menu_state(MenuStateComputeType::KeepIfUnchanged),
+                MenuAction::Keep,
+                self.next_revision(),
+            )
+        } else {
+            for old in old_formats {
+                self.unformat_range(s, e, &old);
+            }
+            if let Some(format) = format {
+                self.format_range(s, e, &format);
+            }
+            self.create_update_replace_all()
+        }
+    }
+
+    /// The formats matching `is_kind` on the text between `start` and
+    /// `end`.
+    fn formats_of_kind_in(
+        &self,
+        start: usize,
+        end: usize,
+        is_kind: fn(&InlineFormatType) -> bool,
+    ) -> Vec<InlineFormatType> {
+        let range = self.state.dom.find_range(start, end);
+        let mut formats = Vec::new();
+        for loc in range.locations.iter() {
+            if let DomNode::Container(container) =
+                self.state.dom.lookup_node(&loc.node_handle)
+            {
+                if let ContainerNodeKind::Formatting(format) = container.kind()
+                {
+                    if is_kind(format) && !formats.contains(format) {
+                        formats.push(format.clone());
+                    }
+                }
+            }
+        }
+        formats
+    }
+
+    /// Apply all the given formats to the selection as a single undo step,
+    /// e.g. for a "bold and italic" toolbar preset. Formats already
+    /// applied to the whole selection are kept rather than toggled off, and
+    /// custom tags which aren't allowed and invalid colours are ignored.
+    pub fn apply_formats(
+        &mut self,
+        formats: &[InlineFormatType],
+    ) -> ComposerUpdate<S> {
+        let mut to_apply: Vec<InlineFormatType> = Vec::new();
+        for format in formats {
+            if self.is_allowed_format(format)
+                && !to_apply.contains(format)
+                && !self.format_is_reversed(format)
+            {
+                to_apply.push(format.clone());
+            }
+        }
+        if to_apply.is_empty() {
+            return ComposerUpdate::keep_at(self.state.revision);
+        }
+        self.push_state_to_history();
+        let (s, e) = self.safe_selection();
+        if s == e {
+            for format in to_apply.iter() {
+                self.toggle_zero_length_format(format);
+            }
+            ComposerUpdate::update_menu_state(
+                self.compute_menu_state(MenuStateComputeType::KeepIfUnchanged),
+                MenuAction::Keep,
+                self.next_revision(),
+            )
+        } else {
+            for format in to_apply.iter() {
+                self.format_range(s, e, format);
+            }
+            self.create_update_replace_all()
+        }
+    }
+
+    /// Apply a format to the text between `start` and `end`, without
+    /// touching the selection, e.g. to bold every match of a search.
+    /// Unlike [ComposerModel::bold], this never removes the format. Custom
+    /// formats must have been allowed with `set_custom_inline_tags`, as for
+    /// [ComposerModel::custom_format].
+    pub fn format_in(
+        &mut self,
+        start: Location,
+        end: Location,
+        format: InlineFormatType,
+    ) -> ComposerUpdate<S> {
+        let (s, e) = self.safe_locations_from(start, end);
+        if s == e
+            || !self.is_allowed_format(&format)
+            || self.is_formatted_in(s, e, &format)
+        {
+            return ComposerUpdate::keep_at(self.state.revision);
+        }
+        self.push_state_to_history();
+        self.format_range(s, e, &format);
+        self.create_update_replace_all()
+    }
+
+    /// Remove a format from the text between `start` and `end`, without
+    /// touching the selection.
+    pub fn remove_format_in(
+        &mut self,
+        start: Location,
+        end: Location,
+        format: InlineFormatType,
+    ) -> ComposerUpdate<S> {
+        let (s, e) = self.safe_locations_from(start, end);
+        if s == e {
+            return ComposerUpdate::keep_at(self.state.revision);
+        }
+        self.push_state_to_history();
+        self.unformat_range(s, e, &format);
+        self.create_update_replace_all()
+    }
+
     /// Finds the closest structure node ancestor for each leaf node handle and groups it with other
     /// leaves that share it as the common closest structure node ancestor. If none is found,
     /// the root/document node is used instead.
@@ -89,18 +302,48 @@ where
         &mut self,
         format_type: InlineFormatType,
     ) -> ComposerUpdate<S> {
-        if self.action_is_reversed(format_type.action()) {
+        if self.format_is_reversed(&format_type) {
             self.unformat(format_type)
         } else {
             self.format(format_type)
         }
     }
 
+    /// Whether the format is applied to the selection, taking into account
+    /// formats toggled while nothing was selected.
+    pub(crate) fn format_is_reversed(&self, format: &InlineFormatType) -> bool {
+        if let Some(action) = format.toggle_action() {
+            return self.action_is_reversed(action);
+        }
+        // Custom formats have no action, so aren't part of the menu state
+        let (s, e) = self.safe_selection();
+        let range = self.state.dom.find_range(s, e);
+        let mut leaves = range
+            .leaves()
+            .filter(|l| l.relative_position() != DomLocationPosition::After)
+            .peekable();
+        let is_formatted = leaves.peek().is_some()
+            && leaves.all(|l| {
+                Self::path_contains_format_node(
+                    &self.state.dom,
+                    &l.node_handle,
+                    format,
+                )
+                .is_some()
+            });
+        is_formatted != self.state.toggled_format_types.contains(format)
+    }
+
     pub(crate) fn apply_pending_formats(&mut self, start: usize, end: usize) {
         // Reverse to pop and apply in expected order.
         self.state.toggled_format_types.reverse();
-        while let Some(format) = self.state.toggled_format_types.pop() {
-            if self.action_is_reversed(format.action()) {
+        while let Some(format) = self.state.toggled_format_types.last() {
+            let format = format.clone();
+            // Check before popping, as custom formats take the toggled
+            // formats into account when they are checked.
+            let is_reversed = self.format_is_reversed(&format);
+            self.state.toggled_format_types.pop();
+            if is_reversed {
                 self.format_range(start, end, &format);
             } else {
                 self.unformat_range(start, end, &format);
@@ -116,6 +359,7 @@ where
             ComposerUpdate::update_menu_state(
                 self.compute_menu_state(MenuStateComputeType::KeepIfUnchanged),
                 MenuAction::Keep,
+                self.next_revision(),
             )
         } else {
             self.format_range(s, e, &format);
@@ -123,7 +367,7 @@ where
         }
     }
 
-    fn format_range(
+    pub(crate) fn format_range(
         &mut self,
         start: usize,
         end: usize,
@@ -134,7 +378,7 @@ where
             self.add_inline_code_in(start, end);
         } else {
             let range = self.state.dom.find_range(start, end);
-            self.format_several_nodes(&range, format);
+            self.format_several_nodes(start, end, &range, format);
         }
     }
 
@@ -146,6 +390,7 @@ where
             ComposerUpdate::update_menu_state(
                 self.compute_menu_state(MenuStateComputeType::KeepIfUnchanged),
                 MenuAction::Keep,
+                self.next_revision(),
             )
         } else {
             self.unformat_range(s, e, &format);
@@ -153,7 +398,7 @@ where
         }
     }
 
-    fn unformat_range(
+    pub(crate) fn unformat_range(
         &mut self,
         start: usize,
         end: usize,
@@ -215,20 +460,59 @@ where
         }
     }
 
+    /// Whether all the text between `start` and `end` already has the
+    /// format, so that formatting it would remove the format instead.
+    fn is_formatted_in(
+        &self,
+        start: usize,
+        end: usize,
+        format: &InlineFormatType,
+    ) -> bool {
+        let range = self.state.dom.find_range(start, end);
+        self.check_format_selection_type(&range.locations, format)
+            == FormatSelectionType::Remove
+    }
+
     fn format_several_nodes(
         &mut self,
+        start: usize,
+        end: usize,
         range: &Range,
         format: &InlineFormatType,
     ) {
         let selection_type =
             self.check_format_selection_type(&range.locations, format);
         match selection_type {
-            FormatSelectionType::Remove => {} // TODO: actually implement this
-            FormatSelectionType::Extend => self
-                .extend_format_in_multiple_nodes(
-                    range.leaves().collect(),
+            // Formatting containers are split so that only the selected
+            // part of them loses the format
+            FormatSelectionType::Remove => {
+                self.unformat_several_nodes(start, end, range, format)
+            }
+            // Maths is never formatted, as that would change its source
+            FormatSelectionType::Extend => {
+                let maths: Vec<&DomHandle> = range
+                    .locations
+                    .iter()
+                    .filter(|l| {
+                        matches!(
+                            l.kind,
+                            DomNodeKind::Math | DomNodeKind::MathBlock
+                        )
+                    })
+                    .map(|l| &l.node_handle)
+                    .collect();
+                self.extend_format_in_multiple_nodes(
+                    range
+                        .leaves()
+                        .filter(|l| {
+                            !maths
+                                .iter()
+                                .any(|m| m.is_ancestor_of(&l.node_handle))
+                        })
+                        .collect(),
                     format,
-                ),
+                )
+            }
         }
     }
 
@@ -405,7 +689,7 @@ where
     ) -> (Option<DomNode<S>>, Option<DomNode<S>>) {
         if let Some(node) = node {
             if let DomNode::Text(text_node) = node {
-                if text_node.data().is_empty() {
+                if text_node.text_len() == 0 {
                     (None, None)
                 } else {
                     let split_data_orig =
@@ -456,6 +740,14 @@ where
     }
 }
 
+fn is_text_color(format: &InlineFormatType) -> bool {
+    matches!(format, InlineFormatType::TextColor(_))
+}
+
+fn is_highlight(format: &InlineFormatType) -> bool {
+    matches!(format, InlineFormatType::Highlight(_))
+}
+
 #[cfg(test)]
 mod test {
     use crate::tests::testutils_composer_model::{cm, tx};
@@ -544,6 +836,23 @@ mod test {
         assert_eq!(selection_type, FormatSelectionType::Remove);
     }
 
+    #[test]
+    fn formatting_formatted_text_removes_the_format_from_the_middle() {
+        let mut model = cm("<b>a{b}|c</b>");
+        model.format(InlineFormatType::Bold);
+        assert_eq!(tx(&model), "<strong>a</strong>{b}|<strong>c</strong>");
+    }
+
+    #[test]
+    fn formatting_formatted_text_across_nodes_removes_the_format() {
+        let mut model = cm("<b>hel{lo</b><i><b>wor}|ld</b></i>");
+        model.format(InlineFormatType::Bold);
+        assert_eq!(
+            tx(&model),
+            "<strong>hel</strong>{lo<i>wor}|<strong>ld</strong></i>"
+        );
+    }
+
     #[test]
     fn formatting_several_nodes_works_with_different_format() {
         let mut model = cm("{hello <i>wor}|ld</i>");
@@ -14,7 +14,6 @@
 
 use crate::composer_model::menu_state::MenuStateComputeType;
 use crate::dom::nodes::{DomNode, TextNode};
-use crate::dom::unicode_string::UnicodeStrExt;
 use crate::dom::{DomHandle, DomLocation};
 use crate::{ComposerModel, ComposerUpdate, InlineFormatType, UnicodeString};
 
@@ -29,9 +28,13 @@ where
         if s == e {
             self.state.toggled_format_types =
                 vec![InlineFormatType::InlineCode];
+            let menu_state =
+                self.compute_menu_state(MenuStateComputeType::KeepIfUnchanged);
+            let revision = self.next_revision();
             ComposerUpdate::update_menu_state(
-                self.compute_menu_state(MenuStateComputeType::KeepIfUnchanged),
+                menu_state,
                 self.compute_menu_action(),
+                revision,
             )
         } else {
             self.add_inline_code_in(s, e);
@@ -40,6 +43,10 @@ where
     }
 
     pub(crate) fn add_inline_code_in(&mut self, start: usize, end: usize) {
+        // Maths is already shown as code, and can't be split
+        if self.is_selection_inside_math(start, end) {
+            return;
+        }
         let range = self.state.dom.find_range(start, end);
         let leaves: Vec<&DomLocation> = range.leaves().collect();
         // We'll iterate through the leaves finding their closest structural node ancestor and
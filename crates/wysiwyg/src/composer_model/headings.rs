@@ -0,0 +1,129 @@
+// Copyright 2023 The Matrix.org Foundation C.I.C.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use crate::dom::nodes::dom_node::DomNodeKind::{CodeBlock, Heading, Paragraph};
+use crate::dom::nodes::DomNode;
+use crate::{
+    ComposerModel, ComposerUpdate, DomHandle, HeadingLevel, OutlineEntry,
+    ToRawText, UnicodeString,
+};
+
+impl<S> ComposerModel<S>
+where
+    S: UnicodeString,
+{
+    /// Turn the selected paragraphs into headings of `level`, or remove the
+    /// heading if every selected block is already a heading of that level.
+    pub fn heading(&mut self, level: HeadingLevel) -> ComposerUpdate<S> {
+        let handles = self.text_block_targets();
+        let all_at_level = !handles.is_empty()
+            && handles.iter().all(|h| {
+                self.state.dom.lookup_container(h).heading_level()
+                    == Some(level)
+            });
+        if all_at_level {
+            self.set_heading(None)
+        } else {
+            self.set_heading(Some(level))
+        }
+    }
+
+    /// Turn the selected paragraphs and headings into headings of `level`,
+    /// or back into paragraphs if `level` is None. Blocks inside code
+    /// blocks and list items without paragraphs are left alone.
+    pub fn set_heading(
+        &mut self,
+        level: Option<HeadingLevel>,
+    ) -> ComposerUpdate<S> {
+        let document = self.state.dom.document();
+        let only_inline_nodes =
+            document.children().iter().all(|n| !n.is_block_node());
+        if only_inline_nodes && level.is_none() {
+            return ComposerUpdate::keep_at(self.state.revision);
+        }
+
+        self.push_state_to_history();
+        if only_inline_nodes {
+            // The content isn't in any block yet, so it becomes one heading
+            let children = self.state.dom.document_mut().remove_children();
+            self.state
+                .dom
+                .append_at_end_of_document(DomNode::new_paragraph(children));
+        }
+        for handle in self.text_block_targets() {
+            if let Ok(DomNode::Container(block)) =
+                self.state.dom.try_lookup_node_mut(&handle)
+            {
+                block.set_heading_level(level);
+            }
+        }
+        self.create_update_replace_all()
+    }
+
+    /// The level of the heading containing the start of the selection, if
+    /// it is in one.
+    pub fn get_heading_level(&self) -> Option<HeadingLevel> {
+        let (s, _) = self.safe_selection();
+        let handle = self.block_at(s)?;
+        self.state.dom.lookup_container(&handle).heading_level()
+    }
+
+    /// The headings of the content in order, with their levels, text and
+    /// where they are, e.g. for a pane to navigate a long message.
+    pub fn get_outline(&self) -> Vec<OutlineEntry<S>> {
+        let len = self.state.dom.text_len();
+        self.state
+            .dom
+            .find_range(0, len)
+            .locations
+            .into_iter()
+            .filter(|l| l.kind == Heading)
+            .filter_map(|l| {
+                let heading = self.state.dom.lookup_container(&l.node_handle);
+                // The length of the block counts the boundary after it
+                let text_len: usize =
+                    heading.children().iter().map(|c| c.text_len()).sum();
+                Some(OutlineEntry {
+                    level: heading.heading_level()?,
+                    text: heading.to_raw_text(),
+                    start: l.position,
+                    end: l.position + text_len,
+                })
+            })
+            .collect()
+    }
+
+    /// The paragraphs and headings touched by the selection, outside code
+    /// blocks.
+    pub(crate) fn text_block_targets(&self) -> Vec<DomHandle> {
+        let (s, e) = self.safe_selection();
+        let mut handles: Vec<DomHandle> = if s == e {
+            self.block_at(s).into_iter().collect()
+        } else {
+            self.state
+                .dom
+                .find_range(s, e)
+                .locations
+                .iter()
+                .filter(|l| l.start_offset < l.end_offset)
+                .map(|l| l.node_handle.clone())
+                .collect()
+        };
+        handles.retain(|h| {
+            matches!(self.state.dom.lookup_node(h).kind(), Paragraph | Heading)
+                && self.find_closest_ancestor_of_kind(h, CodeBlock).is_none()
+        });
+        handles
+    }
+}
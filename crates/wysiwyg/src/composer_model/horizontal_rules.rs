@@ -0,0 +1,123 @@
+// Copyright 2023 The Matrix.org Foundation C.I.C.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use crate::composer_model::delete_text::Direction;
+use crate::dom::nodes::dom_node::DomNodeKind;
+use crate::dom::nodes::DomNode;
+use crate::{
+    ComposerModel, ComposerUpdate, DomHandle, Location, UnicodeString,
+};
+
+impl<S> ComposerModel<S>
+where
+    S: UnicodeString,
+{
+    /// Insert a horizontal rule at the cursor, replacing any selection. The
+    /// block the cursor is in is split around it, and the cursor moves to
+    /// the start of the block after it, which is a new empty paragraph if
+    /// there was nothing to type into after the rule.
+    pub fn insert_horizontal_rule(&mut self) -> ComposerUpdate<S> {
+        self.push_state_to_history();
+        self.insert_block_and_move_after(DomNode::new_horizontal_rule())
+    }
+
+    /// Insert `block` at the cursor, splitting the block the cursor is in
+    /// around it, and move the cursor to the start of the block after it,
+    /// adding an empty paragraph if there is nothing to type into there.
+    pub(crate) fn insert_block_and_move_after(
+        &mut self,
+        block: DomNode<S>,
+    ) -> ComposerUpdate<S> {
+        let block = match self.insert_block_at_cursor(block) {
+            Ok(block) => block,
+            Err(error) => return self.recover_from(error),
+        };
+        let next = block.next_sibling();
+        let has_block_to_type_into = self.state.dom.contains(&next)
+            && matches!(
+                self.state.dom.lookup_node(&next).kind(),
+                DomNodeKind::Paragraph | DomNodeKind::Heading
+            );
+        if !has_block_to_type_into {
+            self.state
+                .dom
+                .insert_at(&next, DomNode::new_paragraph(Vec::new()));
+        }
+        self.state.start = Location::from(self.end_of_block(&block) + 1);
+        self.state.end = self.state.start;
+        self.create_update_replace_all()
+    }
+
+    /// The horizontal rule the cursor is on when it is at `pos`, if any.
+    pub(crate) fn horizontal_rule_at(&self, pos: usize) -> Option<DomHandle> {
+        self.state
+            .dom
+            .find_range(pos, pos)
+            .locations
+            .into_iter()
+            .find(|l| {
+                l.kind == DomNodeKind::HorizontalRule && l.position == pos
+            })
+            .map(|l| l.node_handle)
+    }
+
+    /// If the cursor is on a horizontal rule at `pos`, add an empty
+    /// paragraph after it and move the cursor there, so there is
+    /// somewhere to type. Returns false if there is no rule at `pos`.
+    pub(crate) fn move_off_horizontal_rule_at(&mut self, pos: usize) -> bool {
+        let Some(rule) = self.horizontal_rule_at(pos) else {
+            return false;
+        };
+        self.state.dom.insert_at(
+            &rule.next_sibling(),
+            DomNode::new_paragraph(Vec::new()),
+        );
+        self.state.start = Location::from(pos + 1);
+        self.state.end = self.state.start;
+        true
+    }
+
+    /// Remove the horizontal rule that backspace or delete in `direction`
+    /// would reach from the cursor: the one the cursor is on, or the one
+    /// just before the start of the block it is in (for backspace) or just
+    /// after the end of it (for delete). Returns false if there is none.
+    pub(crate) fn remove_horizontal_rule_next_to_cursor(
+        &mut self,
+        direction: &Direction,
+    ) -> bool {
+        let (s, e) = self.safe_selection();
+        if s != e {
+            return false;
+        }
+        let next_to = match direction {
+            Direction::Backwards => s.checked_sub(1),
+            Direction::Forwards => Some(s + 1),
+        };
+        let Some(rule) = self
+            .horizontal_rule_at(s)
+            .or_else(|| next_to.and_then(|p| self.horizontal_rule_at(p)))
+        else {
+            return false;
+        };
+
+        // Any blocks left empty by removing the rule go with it
+        let top_level = DomHandle::from_raw(vec![rule.raw()[0]]);
+        self.remove_and_clean_up_empty_nodes_until(&rule, &top_level);
+        if *direction == Direction::Backwards {
+            self.state.start = Location::from(s.saturating_sub(1));
+            self.state.end = self.state.start;
+        }
+        true
+    }
+}
@@ -15,11 +15,10 @@
 use std::cmp::{max, min};
 
 use crate::dom::nodes::dom_node::DomNodeKind;
-use crate::dom::nodes::dom_node::DomNodeKind::{Link, List};
+use crate::dom::nodes::dom_node::DomNodeKind::Link;
 use crate::dom::nodes::ContainerNodeKind;
 use crate::dom::nodes::DomNode;
-use crate::dom::unicode_string::UnicodeStrExt;
-use crate::dom::Range;
+use crate::dom::{DomLocation, Range};
 use crate::{
     ComposerModel, ComposerUpdate, DomHandle, LinkAction, UnicodeString,
 };
@@ -62,6 +61,29 @@ where
         }
     }
 
+    /// The URLs of the different links in the selection, in the order they
+    /// appear. [Self::get_link_action] only reports the first one, so this
+    /// lets the UI tell when e.g. removing all of them makes more sense than
+    /// editing the first. Links which can't be edited are left out.
+    pub fn get_selected_link_urls(&self) -> Vec<S> {
+        let (s, e) = self.safe_selection();
+        let mut urls: Vec<S> = Vec::new();
+        for loc in self.state.dom.find_range(s, e).locations {
+            if loc.kind != DomNodeKind::Link {
+                continue;
+            }
+            let link = self.state.dom.lookup_container(&loc.node_handle);
+            if let Some(url) =
+                link.get_link_url().filter(|_| !link.is_immutable())
+            {
+                if !urls.contains(&url) {
+                    urls.push(url);
+                }
+            }
+        }
+        urls
+    }
+
     fn is_blank_selection(&self, range: Range) -> bool {
         for leaf in range.leaves() {
             match leaf.kind {
@@ -77,8 +99,10 @@ where
                         return false;
                     }
                 }
-                DomNodeKind::LineBreak => continue,
-                DomNodeKind::Mention => return false,
+                DomNodeKind::LineBreak | DomNodeKind::HorizontalRule => {
+                    continue
+                }
+                DomNodeKind::Mention | DomNodeKind::Image => return false,
                 DomNodeKind::Formatting(_)
                 | DomNodeKind::Link
                 | DomNodeKind::ListItem
@@ -86,7 +110,10 @@ where
                 | DomNodeKind::CodeBlock
                 | DomNodeKind::Quote
                 | DomNodeKind::Generic
-                | DomNodeKind::Paragraph => {
+                | DomNodeKind::Paragraph
+                | DomNodeKind::Heading
+                | DomNodeKind::Math
+                | DomNodeKind::MathBlock => {
                     unreachable!("Inside leaf iterator and found a non-leaf")
                 }
             }
@@ -102,7 +129,9 @@ where
     ) -> ComposerUpdate<S> {
         let (s, _) = self.safe_selection();
         self.push_state_to_history();
-        self.do_replace_text(text.clone());
+        if let Err(error) = self.replace_text_without_update(text.clone()) {
+            return self.recover_from(error);
+        }
         let e = s + text.len();
         let range = self.state.dom.find_range(s, e);
         self.set_link_in_range(url, range, attributes)
@@ -125,7 +154,7 @@ where
         &mut self,
         mut url: S,
         range: Range,
-        attributes: Vec<(S, S)>,
+        mut attributes: Vec<(S, S)>,
     ) -> ComposerUpdate<S> {
         self.add_http_scheme(&mut url);
 
@@ -133,50 +162,33 @@ where
         // Find container link that completely covers the range
         if let Some(link) = self.find_closest_ancestor_link(&range) {
             // If found, update the range to the container link bounds
-            let range = self.state.dom.find_range_by_node(&link);
+            let range = match self.state.dom.try_find_range_by_node(&link) {
+                Ok(range) => range,
+                Err(error) => return self.recover_from(error),
+            };
             (s, e) = (range.start(), range.end());
         }
 
         if s == e {
-            return ComposerUpdate::keep();
+            return ComposerUpdate::keep_at(self.state.revision);
         }
 
-        let mut split_points: Vec<(DomHandle, usize, usize)> = Vec::new();
-
-        for location in range.locations.iter() {
-            // Look for block nodes
-            if (location.kind.is_block_kind()
-                || location.kind.is_structure_kind())
-                && location.kind != List
-            {
-                let start = location.position + location.start_offset;
-                let end = if location.end_offset == location.length
-                    && !location.node_handle.is_root()
-                {
-                    // The end of the block node is covered (end_offset == length), don't include it
-                    location.position + location.end_offset - 1
-                } else {
-                    location.position + location.end_offset
-                };
-                // If there was a child block node added as a split point, don't add this one
-                if !split_points
-                    .iter()
-                    .any(|(h, _, _)| location.node_handle.is_ancestor_of(h))
-                    // Only include split points which actually have some text in the range.
-                    && end > start
-                {
-                    split_points.push((
-                        location.node_handle.clone(),
-                        start,
-                        end,
-                    ));
-                }
-            }
-        }
+        let mut split_points = range.inline_ranges_in_blocks();
 
         for location in range.locations.iter() {
             // Now look for previous links inside the selection
             if location.kind == Link {
+                // Keep the replaced link's data-* attributes, unless new
+                // values are given for them
+                if let DomNode::Container(link) =
+                    self.state.dom.lookup_node(&location.node_handle)
+                {
+                    for (name, value) in link.data_attributes() {
+                        if !attributes.iter().any(|(n, _)| n == &name) {
+                            attributes.push((name, value));
+                        }
+                    }
+                }
                 let start = location.position;
                 let end = location.position + location.length;
                 let idx = split_points.iter().position(|(h, s, e)| {
@@ -304,6 +316,62 @@ where
         None
     }
 
+    /// Insert `text` at `pos` inside the link ending there, or the one
+    /// starting there if nothing comes before it in the block, like text
+    /// typed after formatting picks it up. Returns false, changing nothing,
+    /// if there is no such link or it can't be extended.
+    pub(crate) fn insert_text_into_link_at_edge(
+        &mut self,
+        text: &S,
+        pos: usize,
+    ) -> bool {
+        let range = self.state.dom.find_range(pos, pos);
+        let ends_here =
+            |l: &DomLocation| l.length > 0 && l.start_offset == l.length;
+        let links: Vec<&DomLocation> = range
+            .locations
+            .iter()
+            .filter(|l| {
+                l.kind == Link
+                    && !self
+                        .state
+                        .dom
+                        .lookup_container(&l.node_handle)
+                        .is_immutable_link()
+            })
+            .collect();
+        let in_link = |link: &DomLocation, leaf: &DomLocation| {
+            link.node_handle.is_ancestor_of(&leaf.node_handle)
+        };
+        let leaf = if let Some(link) = links.iter().find(|l| ends_here(l)) {
+            range
+                .leaves()
+                .find(|leaf| in_link(link, leaf) && ends_here(leaf))
+        } else if let Some(link) = links.iter().find(|l| {
+            l.start_offset == 0
+                && !range
+                    .leaves()
+                    .any(|leaf| ends_here(leaf) && !in_link(l, leaf))
+        }) {
+            range
+                .leaves()
+                .find(|leaf| in_link(link, leaf) && leaf.start_offset == 0)
+        } else {
+            None
+        };
+        let Some(leaf) = leaf else {
+            return false;
+        };
+        let offset = leaf.start_offset;
+        let Ok(DomNode::Text(text_node)) =
+            self.state.dom.try_lookup_node_mut(&leaf.node_handle)
+        else {
+            return false;
+        };
+        text_node.replace_range(text.clone(), offset, offset);
+        true
+    }
+
     pub fn remove_links(&mut self) -> ComposerUpdate<S> {
         let mut has_found_link = false;
         let (s, e) = self.safe_selection();
@@ -321,7 +389,7 @@ where
             }
         }
         if !has_found_link {
-            return ComposerUpdate::keep();
+            return ComposerUpdate::keep_at(self.state.revision);
         }
         self.create_update_replace_all()
     }
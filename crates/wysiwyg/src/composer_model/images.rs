@@ -0,0 +1,78 @@
+// Copyright 2023 The Matrix.org Foundation C.I.C.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use crate::dom::nodes::image_node::is_mxc_uri;
+use crate::{ComposerModel, ComposerUpdate, DomNode, Location, UnicodeString};
+
+impl<S> ComposerModel<S>
+where
+    S: UnicodeString,
+{
+    /// Insert an inline image at the cursor, replacing any selection, and
+    /// move the cursor after it. Nothing happens inside inline code or a
+    /// code block, which can only hold text, or if `src` isn't an `mxc://`
+    /// URI.
+    pub fn insert_image(
+        &mut self,
+        src: S,
+        alt: S,
+        width: Option<u32>,
+        height: Option<u32>,
+    ) -> ComposerUpdate<S> {
+        if !is_mxc_uri(&src.to_string()) {
+            return ComposerUpdate::keep_at(self.state.revision);
+        }
+        self.insert_image_node(DomNode::new_image(src, alt, width, height))
+    }
+
+    /// Insert a custom emoji with `shortcode`, e.g. `party` or `:party:`,
+    /// from the image at `mxc_url`. Like [Self::insert_image], it replaces
+    /// any selection and the cursor moves after it, and it is deleted and
+    /// selected as a single character. Nothing happens if `mxc_url` isn't
+    /// an `mxc://` URI.
+    pub fn insert_custom_emoji(
+        &mut self,
+        shortcode: S,
+        mxc_url: S,
+    ) -> ComposerUpdate<S> {
+        if !is_mxc_uri(&mxc_url.to_string()) {
+            return ComposerUpdate::keep_at(self.state.revision);
+        }
+        self.insert_image_node(DomNode::new_custom_emoji(mxc_url, shortcode))
+    }
+
+    fn insert_image_node(&mut self, image: DomNode<S>) -> ComposerUpdate<S> {
+        let (s, e) = self.safe_selection();
+        let range = self.state.dom.find_range(s, e);
+        if range.locations.iter().any(|l| l.kind.is_code_kind()) {
+            return ComposerUpdate::keep_at(self.state.revision);
+        }
+
+        self.push_state_to_history();
+        if self.has_selection() {
+            if let Err(error) = self.replace_text_without_update(S::default()) {
+                return self.recover_from(error);
+            }
+        }
+
+        let (s, _) = self.safe_selection();
+        let range = self.state.dom.find_range(s, s);
+        let new_cursor_index = s + image.text_len();
+        self.state.dom.insert_node_at_cursor(&range, image);
+
+        self.state.start = Location::from(new_cursor_index);
+        self.state.end = self.state.start;
+        self.create_update_replace_all()
+    }
+}
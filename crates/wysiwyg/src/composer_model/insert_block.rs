@@ -0,0 +1,126 @@
+// Copyright 2023 The Matrix.org Foundation C.I.C.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use crate::dom::nodes::dom_node::DomNodeKind::{self, CodeBlock};
+use crate::dom::nodes::DomNode;
+use crate::{ComposerError, ComposerModel, DomHandle, Location, UnicodeString};
+
+impl<S> ComposerModel<S>
+where
+    S: UnicodeString,
+{
+    /// Insert `block` at the cursor, replacing any selection. The block the
+    /// cursor is in is split around it, or replaced if it is an empty
+    /// paragraph. A code block is split rather than the line inside it, as
+    /// it can't contain other blocks. The caller places the selection.
+    ///
+    /// Returns the handle of the inserted block.
+    pub(crate) fn insert_block_at_cursor(
+        &mut self,
+        block: DomNode<S>,
+    ) -> Result<DomHandle, ComposerError> {
+        let (s, e) = self.safe_selection();
+        if s != e {
+            self.state.dom.replace_text_in(S::default(), s, e)?;
+            self.state.start = Location::from(s);
+            self.state.end = self.state.start;
+        }
+        let Some(target) = self.block_to_split_at(s) else {
+            return Ok(self.state.dom.append_at_end_of_document(block));
+        };
+        let target_node = self.state.dom.try_lookup_node(&target)?;
+        if target_node.kind() == DomNodeKind::Paragraph
+            && target_node.text_len() == 0
+        {
+            self.state.dom.replace(&target, vec![block]);
+            return Ok(target);
+        }
+
+        let range = self.state.dom.find_range(s, s);
+        let at_start = range
+            .locations
+            .iter()
+            .any(|l| l.node_handle == target && l.start_offset == 0);
+        let split_at = range
+            .leaves()
+            .find(|l| {
+                target.is_ancestor_of(&l.node_handle)
+                    && l.start_offset < l.length
+            })
+            .map(|l| (l.node_handle.clone(), l.start_offset));
+        Ok(match split_at {
+            _ if at_start => self.state.dom.insert_at(&target, block),
+            Some((leaf, offset)) => {
+                let after = self
+                    .state
+                    .dom
+                    .split_sub_tree_from(&leaf, offset, target.depth())
+                    .take_document();
+                let handle =
+                    self.state.dom.insert_at(&target.next_sibling(), block);
+                self.state.dom.insert_at(&handle.next_sibling(), after);
+                handle
+            }
+            None => self.state.dom.insert_at(&target.next_sibling(), block),
+        })
+    }
+
+    /// The position at the end of the content of the block at `handle`,
+    /// e.g. to put the cursor there after inserting it.
+    pub(crate) fn end_of_block(&self, handle: &DomHandle) -> usize {
+        let dom = &self.state.dom;
+        dom.find_range(0, dom.text_len())
+            .locations
+            .iter()
+            .find(|l| l.node_handle == *handle)
+            .map_or(dom.text_len(), |l| {
+                l.position + dom.lookup_node(handle).text_len()
+            })
+    }
+
+    /// The block to split when inserting a block at `pos`. Inline nodes
+    /// directly inside a list item, quote or the document are wrapped in a
+    /// paragraph first, so that only they are split.
+    fn block_to_split_at(&mut self, pos: usize) -> Option<DomHandle> {
+        let handle = self.block_at(pos)?;
+        let handle = self
+            .find_closest_ancestor_of_kind(&handle, CodeBlock)
+            .unwrap_or(handle);
+        let kind = self.state.dom.lookup_node(&handle).kind();
+        if matches!(
+            kind,
+            DomNodeKind::Paragraph
+                | DomNodeKind::Heading
+                | DomNodeKind::HorizontalRule
+                | CodeBlock
+        ) {
+            return Some(handle);
+        }
+        let container = self
+            .state
+            .dom
+            .try_lookup_node_mut(&handle)
+            .ok()?
+            .as_container_mut()?;
+        if container.children().iter().any(|c| c.is_block_node()) {
+            self.state
+                .dom
+                .wrap_inline_nodes_into_paragraphs_if_needed(&handle);
+            return self.block_at(pos).filter(|h| *h != handle);
+        }
+        let children = container.remove_children();
+        container.append_child(DomNode::new_paragraph(children));
+        Some(handle.child_handle(0))
+    }
+}
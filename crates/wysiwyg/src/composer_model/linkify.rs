@@ -0,0 +1,247 @@
+// Copyright 2023 The Matrix.org Foundation C.I.C.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use email_address::EmailAddress;
+use url::Url;
+
+use crate::dom::nodes::dom_node::DomNodeKind;
+use crate::dom::nodes::DomNode;
+use crate::dom::unicode_string::UnicodeStr;
+use crate::dom::Range;
+use crate::{ComposerModel, ComposerUpdate, DomHandle, UnicodeString};
+
+impl<S> ComposerModel<S>
+where
+    S: UnicodeString,
+{
+    /// Wrap every URL (e.g. `https://matrix.org` or `www.matrix.org`) and
+    /// email address found in the text of the document in a link. Text
+    /// inside code blocks, inline code and existing links is left alone.
+    /// Email addresses are skipped if `set_link_emails(false)` was called.
+    ///
+    /// Useful after importing a plain text draft, or pasting a large amount
+    /// of unformatted text.
+    pub fn linkify_content(&mut self) -> ComposerUpdate<S> {
+        let links = self.find_linkable_text();
+        if links.is_empty() {
+            return ComposerUpdate::keep_at(self.state.revision);
+        }
+
+        self.push_state_to_history();
+        // Wrapping text in a link doesn't change any text positions, but
+        // going backwards keeps the handles we haven't visited yet valid.
+        for (start, end, url) in links.into_iter().rev() {
+            let range = self.state.dom.find_range(start, end);
+            self.state.dom.insert_parent(
+                &range,
+                DomNode::new_link(S::from(url), vec![], vec![]),
+            );
+        }
+        self.create_update_replace_all()
+    }
+
+    /// If `set_link_as_you_type` is enabled and whitespace was just typed
+    /// after a URL or email address, wrap it in a link. An undo or
+    /// backspace straight afterwards removes the link but keeps the
+    /// whitespace.
+    pub(crate) fn link_word_before_cursor(&mut self) {
+        if !self.link_as_you_type {
+            return;
+        }
+        let (s, e) = self.safe_selection();
+        if s != e {
+            return;
+        }
+        let Some((start, end, url)) = self.find_linkable_word_before(s) else {
+            return;
+        };
+        let before = self.state.clone();
+        let range = self.state.dom.find_range(start, end);
+        self.state.dom.insert_parent(
+            &range,
+            DomNode::new_link(S::from(url), vec![], vec![]),
+        );
+        self.record_automatic_action(before);
+    }
+
+    /// Find the (start, end, url) of a URL or email address which is the
+    /// last word before `pos`, if `pos` comes straight after whitespace.
+    fn find_linkable_word_before(
+        &self,
+        pos: usize,
+    ) -> Option<(usize, usize, String)> {
+        let range = self.state.dom.find_range(pos, pos);
+        let excluded = excluded_handles(&range);
+        let leaf = range.leaves().find(|leaf| {
+            leaf.kind == DomNodeKind::Text
+                && leaf.start_offset > 0
+                && !excluded.iter().any(|h| h.is_ancestor_of(&leaf.node_handle))
+        })?;
+        let text = self
+            .state
+            .dom
+            .lookup_node(&leaf.node_handle)
+            .as_text()
+            .unwrap()
+            .data();
+        let before = &text[..leaf.start_offset];
+        if !before.chars().last().map_or(false, char::is_whitespace) {
+            return None;
+        }
+        let (start, end, url) =
+            find_links_in_text(before, self.link_emails).pop()?;
+        // Only link the word that was just finished, not an earlier one
+        if before[end..]
+            .chars()
+            .skip_while(|c| !c.is_whitespace())
+            .any(|c| !c.is_whitespace())
+        {
+            return None;
+        }
+        Some((leaf.position + start, leaf.position + end, url))
+    }
+
+    /// Find the (start, end, url) of each URL or email address in the text
+    /// nodes of the document which are not inside code or links.
+    fn find_linkable_text(&self) -> Vec<(usize, usize, String)> {
+        let range = self.state.dom.find_range(0, self.state.dom.text_len());
+        let excluded = excluded_handles(&range);
+
+        range
+            .leaves()
+            .filter(|leaf| leaf.kind == DomNodeKind::Text)
+            .filter(|leaf| {
+                !excluded.iter().any(|h| h.is_ancestor_of(&leaf.node_handle))
+            })
+            .flat_map(|leaf| {
+                let text = self
+                    .state
+                    .dom
+                    .lookup_node(&leaf.node_handle)
+                    .as_text()
+                    .unwrap()
+                    .data();
+                find_links_in_text(text, self.link_emails).into_iter().map(
+                    |(s, e, url)| (leaf.position + s, leaf.position + e, url),
+                )
+            })
+            .collect()
+    }
+}
+
+/// The handles of the nodes in the range whose text must never be linked,
+/// i.e. code and existing links.
+fn excluded_handles(range: &Range) -> Vec<&DomHandle> {
+    range
+        .locations
+        .iter()
+        .filter(|l| l.kind.is_code_kind() || l.kind.is_link_kind())
+        .map(|l| &l.node_handle)
+        .collect()
+}
+
+/// Split the text into whitespace separated words and return the
+/// (start, end, url) of the ones which are URLs or email addresses, in code
+/// units of the text's encoding. Punctuation around a word (e.g. brackets,
+/// or a full stop ending a sentence) is not part of the link.
+pub(crate) fn find_links_in_text<T>(
+    text: &T,
+    link_emails: bool,
+) -> Vec<(usize, usize, String)>
+where
+    T: UnicodeStr + ?Sized,
+{
+    let mut links = Vec::new();
+    let mut words: Vec<(usize, Vec<char>)> = Vec::new();
+    let mut offset = 0;
+    let mut in_word = false;
+    for c in text.chars() {
+        if c.is_whitespace() {
+            in_word = false;
+        } else if in_word {
+            words.last_mut().unwrap().1.push(c);
+        } else {
+            words.push((offset, vec![c]));
+            in_word = true;
+        }
+        offset += text.char_len(&c);
+    }
+
+    for (mut start, mut chars) in words {
+        while chars
+            .first()
+            .map_or(false, |c| is_punctuation_before_url(*c))
+        {
+            start += text.char_len(&chars.remove(0));
+        }
+        while chars.last().map_or(false, |c| is_punctuation_after_url(*c)) {
+            chars.pop();
+        }
+        let word: String = chars.iter().collect();
+        if let Some(url) = url_for_word(&word, link_emails) {
+            let end =
+                start + chars.iter().map(|c| text.char_len(c)).sum::<usize>();
+            links.push((start, end, url));
+        }
+    }
+    links
+}
+
+/// The URL to link the given word to, if it looks like a web address or
+/// (when `link_emails` is set) an email address.
+fn url_for_word(word: &str, link_emails: bool) -> Option<String> {
+    let lowercase = word.to_lowercase();
+    let url = if lowercase.starts_with("https://")
+        || lowercase.starts_with("http://")
+    {
+        word.to_owned()
+    } else if lowercase.starts_with("www.") {
+        format!("https://{word}")
+    } else if link_emails && is_email_address(word) {
+        return Some(format!("mailto:{word}"));
+    } else {
+        return None;
+    };
+
+    match Url::parse(&url) {
+        Ok(parsed) if parsed.host_str().map_or(false, |h| h.contains('.')) => {
+            Some(url)
+        }
+        _ => None,
+    }
+}
+
+/// Email addresses need a domain with a dot to be linked, so that e.g. a
+/// `user@localhost` in a sentence isn't turned into a link.
+fn is_email_address(word: &str) -> bool {
+    EmailAddress::is_valid(word)
+        && word
+            .rsplit_once('@')
+            .map_or(false, |(_, domain)| domain.contains('.'))
+}
+
+/// Punctuation that commonly comes before a link in a sentence, e.g.
+/// "(example.com" or "<example.com", and isn't part of it.
+fn is_punctuation_before_url(c: char) -> bool {
+    matches!(c, '(' | '[' | '{' | '<' | '"' | '\'')
+}
+
+/// Punctuation that commonly follows a link in a sentence, e.g.
+/// "see example.com." or "<example.com>", and isn't part of it.
+fn is_punctuation_after_url(c: char) -> bool {
+    matches!(
+        c,
+        '.' | ',' | ';' | ':' | '!' | '?' | ')' | ']' | '}' | '>' | '"' | '\''
+    )
+}
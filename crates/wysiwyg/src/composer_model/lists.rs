@@ -18,7 +18,9 @@ use crate::dom::nodes::DomNode;
 use crate::dom::range::DomLocationPosition;
 use crate::dom::range::DomLocationPosition::Before;
 use crate::dom::{DomHandle, DomLocation, Range};
-use crate::{ComposerModel, ComposerUpdate, ListType, UnicodeString};
+use crate::{
+    ComposerModel, ComposerUpdate, ListIndentation, ListType, UnicodeString,
+};
 
 impl<S> ComposerModel<S>
 where
@@ -34,6 +36,35 @@ where
         self.toggle_list(ListType::Unordered)
     }
 
+    /// Change the list holding the selection to `list_type`, along with the
+    /// lists it is nested in and the ones nested in it, keeping the items
+    /// and the selection where they are. Does nothing outside a list.
+    pub fn convert_list_type(
+        &mut self,
+        list_type: ListType,
+    ) -> ComposerUpdate<S> {
+        let (s, e) = self.safe_selection();
+        let range = self.state.dom.find_extended_range(s, e);
+        let Some(list_handle) = range
+            .deepest_block_node(None)
+            .and_then(|l| self.outermost_list_of(&l.node_handle))
+        else {
+            return ComposerUpdate::keep_at(self.state.revision);
+        };
+        let has_type = self
+            .state
+            .dom
+            .lookup_node(&list_handle)
+            .iter_subtree()
+            .filter_map(|n| n.as_container()?.get_list_type())
+            .all(|t| *t == list_type);
+        if has_type {
+            return ComposerUpdate::keep_at(self.state.revision);
+        }
+        self.push_state_to_history();
+        self.convert_list_structure(&list_handle, list_type)
+    }
+
     pub fn indent(&mut self) -> ComposerUpdate<S> {
         // push_state_to_history is called if we can indent
         let (s, e) = self.safe_selection();
@@ -47,7 +78,7 @@ where
             self.indent_locations(&top_most_locations);
             self.create_update_replace_all()
         } else {
-            ComposerUpdate::keep()
+            ComposerUpdate::keep_at(self.state.revision)
         }
     }
 
@@ -62,7 +93,32 @@ where
             self.unindent_locations(&top_most_locations);
             self.create_update_replace_all()
         } else {
-            ComposerUpdate::keep()
+            ComposerUpdate::keep_at(self.state.revision)
+        }
+    }
+
+    /// Whether [Self::indent] and [Self::unindent] would do anything at the
+    /// current selection, and how deeply the selected list items are nested.
+    pub fn get_list_indentation(&self) -> ListIndentation {
+        let (s, e) = self.safe_selection();
+        let range = self.state.dom.find_range(s, e);
+        let top_most_locations =
+            self.find_top_most_list_item_locations(&range.locations);
+        let depth = top_most_locations.first().map_or(0, |location| {
+            let mut handle = location.node_handle.clone();
+            let mut depth = 0;
+            while handle.has_parent() {
+                handle = handle.parent_handle();
+                if self.state.dom.lookup_node(&handle).is_list() {
+                    depth += 1;
+                }
+            }
+            depth
+        });
+        ListIndentation {
+            can_indent: self.can_indent(&top_most_locations),
+            can_unindent: self.can_unindent(&top_most_locations),
+            depth,
         }
     }
 
@@ -178,6 +234,18 @@ where
         self.create_update_replace_all()
     }
 
+    /// Put the blocks between `start` and `end` into a list of
+    /// `list_type`, like selecting them and choosing that list would.
+    pub(crate) fn wrap_range_in_list(
+        &mut self,
+        list_type: ListType,
+        start: usize,
+        end: usize,
+    ) {
+        let range = self.state.dom.find_extended_range(start, end);
+        self.toggle_list_range(list_type, range);
+    }
+
     fn toggle_list(&mut self, list_type: ListType) -> ComposerUpdate<S> {
         let (s, e) = self.safe_selection();
         let range = self.state.dom.find_extended_range(s, e);
@@ -235,8 +303,12 @@ where
                 );
                 self.create_update_replace_all()
             } else {
-                let list_node_handle = list.handle();
-                self.update_list_type(&list_node_handle, list_type)
+                // The whole list is converted, rather than splitting off a
+                // list of the new type
+                let list_handle = list.handle();
+                let outermost =
+                    self.outermost_list_of(&list_handle).unwrap_or(list_handle);
+                self.convert_list_structure(&outermost, list_type)
             }
         } else {
             unreachable!("No list in range. Should have been catched by toggle_list_range")
@@ -262,14 +334,39 @@ where
         self.create_update_replace_all()
     }
 
-    fn update_list_type(
+    /// The list at or around `handle` which isn't inside another list.
+    fn outermost_list_of(&self, handle: &DomHandle) -> Option<DomHandle> {
+        let mut list = self
+            .find_closest_ancestor_of_kind_or_self(handle, DomNodeKind::List)?;
+        while let Some(parent_list) =
+            self.find_closest_ancestor_of_kind(&list, DomNodeKind::List)
+        {
+            list = parent_list;
+        }
+        Some(list)
+    }
+
+    /// Set the type of the list at `list_handle` and of every list nested
+    /// in it to `list_type`.
+    fn convert_list_structure(
         &mut self,
         list_handle: &DomHandle,
         list_type: ListType,
     ) -> ComposerUpdate<S> {
-        let list_node = self.state.dom.lookup_node_mut(list_handle);
-        if let DomNode::Container(list) = list_node {
-            list.set_list_type(list_type);
+        let lists: Vec<DomHandle> = self
+            .state
+            .dom
+            .lookup_node(list_handle)
+            .iter_subtree()
+            .filter(|n| n.kind() == DomNodeKind::List)
+            .map(|n| n.handle())
+            .collect();
+        for handle in lists {
+            if let Ok(DomNode::Container(list)) =
+                self.state.dom.try_lookup_node_mut(&handle)
+            {
+                list.set_list_type(list_type.clone());
+            }
         }
         self.create_update_replace_all()
     }
@@ -346,8 +443,8 @@ where
             removed_list_items.insert(0, self.state.dom.remove(handle));
         }
 
-        if let DomNode::Container(dest_list_item) =
-            &mut self.state.dom.lookup_node_mut(&insert_into_handle)
+        if let Ok(DomNode::Container(dest_list_item)) =
+            self.state.dom.try_lookup_node_mut(&insert_into_handle)
         {
             // Wrap any existing inline nodes inside the destination ListItem into a paragraph
             if dest_list_item.children().len() == 1
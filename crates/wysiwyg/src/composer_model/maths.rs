@@ -0,0 +1,87 @@
+// Copyright 2023 The Matrix.org Foundation C.I.C.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use crate::dom::nodes::dom_node::DomNodeKind;
+use crate::dom::nodes::DomNode;
+use crate::{ComposerModel, ComposerUpdate, Location, UnicodeString};
+
+impl<S> ComposerModel<S>
+where
+    S: UnicodeString,
+{
+    /// Insert maths written as the LaTeX source `latex` at the cursor,
+    /// replacing any selection. If `display` is true it is a block of its
+    /// own, and the cursor moves to the start of the block after it, like
+    /// [Self::insert_horizontal_rule]. Otherwise it is inline, and the
+    /// cursor ends up after it. Nothing happens if `latex` is empty or the
+    /// cursor is in code.
+    pub fn insert_math(
+        &mut self,
+        latex: S,
+        display: bool,
+    ) -> ComposerUpdate<S> {
+        let math = DomNode::new_math(latex, display);
+        let (s, e) = self.safe_selection();
+        let range = self.state.dom.find_range(s, e);
+        if math.text_len() == 0
+            || range.locations.iter().any(|l| l.kind.is_code_kind())
+        {
+            return ComposerUpdate::keep_at(self.state.revision);
+        }
+
+        self.push_state_to_history();
+        if display {
+            self.insert_math_block(math)
+        } else {
+            self.insert_inline_math(math)
+        }
+    }
+
+    fn insert_inline_math(&mut self, math: DomNode<S>) -> ComposerUpdate<S> {
+        if self.has_selection() {
+            if let Err(error) = self.replace_text_without_update(S::default()) {
+                return self.recover_from(error);
+            }
+        }
+        let (s, _) = self.safe_selection();
+        let range = self.state.dom.find_range(s, s);
+        let new_cursor_index = s + math.text_len();
+        self.state.dom.insert_node_at_cursor(&range, math);
+
+        self.state.start = Location::from(new_cursor_index);
+        self.state.end = self.state.start;
+        self.create_update_replace_all()
+    }
+
+    fn insert_math_block(&mut self, math: DomNode<S>) -> ComposerUpdate<S> {
+        self.insert_block_and_move_after(math)
+    }
+
+    /// Whether either end of the selection from `start` to `end` is inside
+    /// maths, where splitting it or adding formatting would break its
+    /// LaTeX source.
+    pub(crate) fn is_selection_inside_math(
+        &self,
+        start: usize,
+        end: usize,
+    ) -> bool {
+        let range = self.state.dom.find_range(start, end);
+        range.locations.iter().any(|l| {
+            matches!(l.kind, DomNodeKind::Math | DomNodeKind::MathBlock)
+                && [l.start_offset, l.end_offset]
+                    .iter()
+                    .any(|offset| *offset > 0 && *offset < l.length)
+        })
+    }
+}
@@ -12,13 +12,16 @@
 // See the License for the specific language governing permissions and
 // limitations under the License.
 
+use std::cmp::min;
+
 use crate::{
     dom::{
-        nodes::{MentionNode, MentionNodeKind},
+        nodes::{dom_node::DomNodeKind, MentionNode, MentionNodeKind},
+        unicode_string::UnicodeStr,
         DomLocation,
     },
-    ComposerModel, ComposerUpdate, DomNode, Location, MentionsState,
-    SuggestionPattern, UnicodeString,
+    ComposerError, ComposerModel, ComposerUpdate, DomHandle, DomNode, Location,
+    MentionsState, SuggestionPattern, UnicodeString,
 };
 
 impl<S> ComposerModel<S>
@@ -27,40 +30,87 @@ where
 {
     /// Returns the current mentions state of the content of the RTE editor.
     pub fn get_mentions_state(&self) -> MentionsState {
-        let mut mentions_state = MentionsState::default();
-        for node in self.state.dom.iter_mentions() {
-            match node.kind() {
-                MentionNodeKind::AtRoom => {
-                    mentions_state.has_at_room_mention = true
-                }
-                MentionNodeKind::MatrixUri { mention } => match mention.kind() {
-                    matrix_mentions::MentionKind::Room(id_type) => {
-                        match id_type {
-                            matrix_mentions::RoomIdentificationType::Id => {
-                                mentions_state
-                                    .room_ids
-                                    .insert(mention.mx_id().to_string());
-                            }
-                            matrix_mentions::RoomIdentificationType::Alias => {
-                                mentions_state
-                                    .room_aliases
-                                    .insert(mention.mx_id().to_string());
-                            }
-                        }
-                    }
-                    matrix_mentions::MentionKind::User => {
-                        mentions_state
-                            .user_ids
-                            .insert(mention.mx_id().to_string());
-                    }
-                },
+        match &self.found_mentions {
+            Some((revision, mentions))
+                if *revision == self.state.dom.revision() =>
+            {
+                mentions_state(mentions.iter().map(|(_, m)| m))
             }
+            _ => mentions_state(
+                self.mentions_in(0, self.state.dom.text_len())
+                    .iter()
+                    .map(|(_, m)| m),
+            ),
         }
-        mentions_state
+    }
+
+    /// Update the mentions found in the content after it changed between
+    /// `changed`, where it used to be `reported_len` long. Only that part
+    /// is searched again; the mentions before it are kept, and the ones
+    /// after it are moved. Searches everything if no mentions were found
+    /// before.
+    pub(crate) fn update_found_mentions(
+        &mut self,
+        changed: Option<(usize, usize)>,
+        reported_len: usize,
+    ) {
+        let len = self.state.dom.text_len();
+        let mentions = match (self.found_mentions.take(), changed) {
+            (Some((_, mentions)), None) => mentions,
+            (Some((_, old)), Some((start, end))) => {
+                let old_end = end + reported_len - len;
+                let mut mentions: Vec<(usize, FoundMention)> = old
+                    .iter()
+                    .filter(|(pos, _)| *pos < start)
+                    .cloned()
+                    .collect();
+                mentions.extend(self.mentions_in(start, end));
+                mentions.extend(
+                    old.into_iter()
+                        .filter(|(pos, _)| *pos >= old_end)
+                        .map(|(pos, m)| (pos + len - reported_len, m)),
+                );
+                mentions
+            }
+            (None, _) => self.mentions_in(0, len),
+        };
+        self.found_mentions = Some((self.state.dom.revision(), mentions));
+    }
+
+    /// The mentions starting between `start` and `end`, with their
+    /// positions.
+    fn mentions_in(
+        &self,
+        start: usize,
+        end: usize,
+    ) -> Vec<(usize, FoundMention)> {
+        self.state
+            .dom
+            .find_range(start, end)
+            .leaves()
+            .filter(|l| {
+                l.kind == DomNodeKind::Mention
+                    && l.position >= start
+                    && l.position < end
+            })
+            .filter_map(|l| match self.state.dom.lookup_node(&l.node_handle) {
+                DomNode::Mention(mention) => {
+                    Some((l.position, FoundMention::of(mention)))
+                }
+                _ => None,
+            })
+            .collect()
     }
 
     /// Checks to see if the mention should be inserted and also if the mention can be created.
-    /// If both of these checks are passed it will remove the suggestion and then insert a mention.
+    /// If both of these checks are passed it will replace the suggestion with a mention.
+    ///
+    /// The mention is placed inside the same containers as the suggestion text, so a
+    /// suggestion typed inside e.g. bold text or a list item is replaced by a mention
+    /// that is still bold or inside that list item. The cursor is left right after the
+    /// mention (and any trailing space added after it).
+    ///
+    /// Nothing happens if the content changed after the suggestion was sent.
     pub fn insert_mention_at_suggestion(
         &mut self,
         url: S,
@@ -68,22 +118,17 @@ where
         suggestion: SuggestionPattern,
         attributes: Vec<(S, S)>,
     ) -> ComposerUpdate<S> {
-        if self.range_contains_link_or_code_leaves() {
-            return ComposerUpdate::keep();
+        if self.range_contains_link_or_code_leaves()
+            || self.suggestion_is_out_of_date(&suggestion)
+        {
+            return ComposerUpdate::keep_at(self.state.revision);
         }
 
         if let Ok(mention_node) = DomNode::new_mention(url, text, attributes) {
             self.push_state_to_history();
-            self.do_replace_text_in(
-                S::default(),
-                suggestion.start,
-                suggestion.end,
-            );
-            self.state.start = Location::from(suggestion.start);
-            self.state.end = self.state.start;
-            self.do_insert_mention(mention_node)
+            self.do_replace_suggestion_with_mention(mention_node, &suggestion)
         } else {
-            ComposerUpdate::keep()
+            ComposerUpdate::keep_at(self.state.revision)
         }
     }
 
@@ -96,38 +141,41 @@ where
         attributes: Vec<(S, S)>,
     ) -> ComposerUpdate<S> {
         if self.range_contains_link_or_code_leaves() {
-            return ComposerUpdate::keep();
+            return ComposerUpdate::keep_at(self.state.revision);
         }
 
         if let Ok(mention_node) = DomNode::new_mention(url, text, attributes) {
             self.push_state_to_history();
             if self.has_selection() {
-                self.do_replace_text(S::default());
+                if let Err(error) =
+                    self.replace_text_without_update(S::default())
+                {
+                    return self.recover_from(error);
+                }
             }
             self.do_insert_mention(mention_node)
         } else {
-            ComposerUpdate::keep()
+            ComposerUpdate::keep_at(self.state.revision)
         }
     }
 
     /// Checks to see if the at-room mention should be inserted.
-    /// If so it will remove the suggestion and then insert an at-room mention.
+    /// If so it will replace the suggestion with an at-room mention, keeping it inside
+    /// the containers of the suggestion text like [Self::insert_mention_at_suggestion].
     pub fn insert_at_room_mention_at_suggestion(
         &mut self,
         suggestion: SuggestionPattern,
         attributes: Vec<(S, S)>,
     ) -> ComposerUpdate<S> {
-        if self.range_contains_link_or_code_leaves() {
-            return ComposerUpdate::keep();
+        if self.range_contains_link_or_code_leaves()
+            || self.suggestion_is_out_of_date(&suggestion)
+        {
+            return ComposerUpdate::keep_at(self.state.revision);
         }
 
         self.push_state_to_history();
-        self.do_replace_text_in(S::default(), suggestion.start, suggestion.end);
-        self.state.start = Location::from(suggestion.start);
-        self.state.end = self.state.start;
-
         let mention_node = DomNode::new_at_room_mention(attributes);
-        self.do_insert_mention(mention_node)
+        self.do_replace_suggestion_with_mention(mention_node, &suggestion)
     }
 
     /// Checks to see if the at-room mention should be inserted.
@@ -137,12 +185,14 @@ where
         attributes: Vec<(S, S)>,
     ) -> ComposerUpdate<S> {
         if self.range_contains_link_or_code_leaves() {
-            return ComposerUpdate::keep();
+            return ComposerUpdate::keep_at(self.state.revision);
         }
 
         self.push_state_to_history();
         if self.has_selection() {
-            self.do_replace_text(S::default());
+            if let Err(error) = self.replace_text_without_update(S::default()) {
+                return self.recover_from(error);
+            }
         }
 
         let mention_node = DomNode::new_at_room_mention(attributes);
@@ -165,18 +215,144 @@ where
             .dom
             .insert_node_at_cursor(&range, DomNode::Mention(mention_node));
 
+        self.finish_mention_insertion(&handle, new_cursor_index, 0)
+    }
+
+    /// Replaces the text of the suggestion with the mention node.
+    ///
+    /// The mention is inserted right after the suggestion's first character, i.e. inside
+    /// the text node holding the suggestion, and only then is the suggestion text around
+    /// it removed. Removing the text first would also remove any container left empty
+    /// (e.g. `<strong>@alic</strong>`), and the mention would end up outside of it.
+    fn do_replace_suggestion_with_mention(
+        &mut self,
+        mention_node: MentionNode<S>,
+        suggestion: &SuggestionPattern,
+    ) -> ComposerUpdate<S> {
+        let Some(first_char) = suggestion
+            .key
+            .trigger_char()
+            .or_else(|| suggestion.text.chars().next())
+        else {
+            return ComposerUpdate::keep_at(self.state.revision);
+        };
+        let kept_len = self.kept_suggestion_suffix_len(suggestion);
+        let start = suggestion.start - self.placeholder_len_before(suggestion);
+        let end = suggestion.end - kept_len;
+        let after_first_char =
+            min(suggestion.start + S::default().char_len(&first_char), end);
+        let mention_len = mention_node.text_len();
+
+        let range = self
+            .state
+            .dom
+            .find_range(after_first_char, after_first_char);
+        self.state
+            .dom
+            .insert_node_at_cursor(&range, DomNode::Mention(mention_node));
+
+        // Remove the rest of the suggestion after the mention, then its first character
+        let mut result = Ok(());
+        if end > after_first_char {
+            result = self.replace_text_in_without_update(
+                S::default(),
+                after_first_char + mention_len,
+                end + mention_len,
+            );
+        }
+        if let Err(error) = result.and_then(|()| {
+            self.replace_text_in_without_update(
+                S::default(),
+                start,
+                after_first_char,
+            )
+        }) {
+            return self.recover_from(error);
+        }
+
+        let new_cursor_index = start + mention_len;
+        let range = self.state.dom.find_range(start, new_cursor_index);
+        let Some(handle) = range
+            .leaves()
+            .find(|l| l.kind == DomNodeKind::Mention && l.position == start)
+            .map(|l| l.node_handle.clone())
+        else {
+            // The inserted mention could not be found
+            return self.recover_from(ComposerError::MissingLeaf(start));
+        };
+        self.finish_mention_insertion(&handle, new_cursor_index, kept_len)
+    }
+
+    /// The length of a placeholder (see [DomNode::is_placeholder]) at the start of the
+    /// suggestion's text node and directly before it. It stood for the empty paragraph
+    /// the suggestion was typed in, so it is removed along with the suggestion.
+    fn placeholder_len_before(&self, suggestion: &SuggestionPattern) -> usize {
+        let range = self
+            .state
+            .dom
+            .find_range(suggestion.start, suggestion.start);
+        let placeholder_len = range
+            .leaves()
+            .find_map(|loc| {
+                let text_node =
+                    self.state.dom.lookup_node(&loc.node_handle).as_text()?;
+                let before = &text_node.data()[..loc.start_offset];
+                (loc.start_offset > 0 && before == "\u{A0}")
+                    .then_some(loc.start_offset)
+            })
+            .unwrap_or(0);
+        placeholder_len
+    }
+
+    /// Moves the cursor after an inserted mention and any kept punctuation following it,
+    /// then adds a trailing space when nothing else follows them in their parent.
+    fn finish_mention_insertion(
+        &mut self,
+        handle: &DomHandle,
+        new_cursor_index: usize,
+        kept_suffix_len: usize,
+    ) -> ComposerUpdate<S> {
         // manually move the cursor to the end of the mention
-        self.state.start = Location::from(new_cursor_index);
+        self.state.start = Location::from(new_cursor_index + kept_suffix_len);
         self.state.end = self.state.start;
 
-        // add a trailing space in cases when we do not have a next sibling
-        if self.state.dom.is_last_in_parent(&handle) {
+        // add a trailing space in cases when we do not have a next sibling,
+        // or when the kept punctuation is the only thing after the mention,
+        // unless there is already whitespace after it, e.g. after the end
+        // of the formatting the mention is in
+        let last_handle = if kept_suffix_len > 0 {
+            handle.next_sibling()
+        } else {
+            handle.clone()
+        };
+        let is_followed_by_more_text = kept_suffix_len > 0
+            && self.state.dom.lookup_node(&last_handle).text_len()
+                > kept_suffix_len;
+        if self.state.dom.is_last_in_parent(&last_handle)
+            && !is_followed_by_more_text
+            && !self.is_whitespace_at(new_cursor_index + kept_suffix_len)
+        {
             self.do_replace_text(" ".into())
         } else {
             self.create_update_replace_all()
         }
     }
 
+    /// Whether the character at `pos` is whitespace.
+    fn is_whitespace_at(&self, pos: usize) -> bool {
+        self.state.dom.find_range(pos, pos + 1).leaves().any(|l| {
+            l.start_offset < l.end_offset
+                && matches!(
+                    self.state.dom.lookup_node(&l.node_handle),
+                    DomNode::Text(text)
+                        if text.data()[l.start_offset..]
+                            .chars()
+                            .next()
+                            .is_some_and(char::is_whitespace)
+                )
+        })
+    }
+
     /// We should not insert a mention if the uri is invalid or the range contains link
     /// or code leaves. See issue https://github.com/matrix-org/matrix-rich-text-editor/issues/702.
     fn range_contains_link_or_code_leaves(&self) -> bool {
@@ -188,3 +364,54 @@ where
         })
     }
 }
+
+/// What a mention in the content refers to, as reported in the
+/// [MentionsState].
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub(crate) enum FoundMention {
+    User(String),
+    RoomId(String),
+    RoomAlias(String),
+    AtRoom,
+}
+
+impl FoundMention {
+    fn of<S: UnicodeString>(node: &MentionNode<S>) -> Self {
+        match node.kind() {
+            MentionNodeKind::AtRoom => Self::AtRoom,
+            MentionNodeKind::MatrixUri { mention } => {
+                let id = mention.mx_id().to_string();
+                match mention.kind() {
+                    matrix_mentions::MentionKind::Room(
+                        matrix_mentions::RoomIdentificationType::Id,
+                    ) => Self::RoomId(id),
+                    matrix_mentions::MentionKind::Room(
+                        matrix_mentions::RoomIdentificationType::Alias,
+                    ) => Self::RoomAlias(id),
+                    matrix_mentions::MentionKind::User => Self::User(id),
+                }
+            }
+        }
+    }
+}
+
+fn mentions_state<'a>(
+    mentions: impl Iterator<Item = &'a FoundMention>,
+) -> MentionsState {
+    let mut mentions_state = MentionsState::default();
+    for mention in mentions {
+        match mention {
+            FoundMention::User(id) => {
+                mentions_state.user_ids.insert(id.clone());
+            }
+            FoundMention::RoomId(id) => {
+                mentions_state.room_ids.insert(id.clone());
+            }
+            FoundMention::RoomAlias(id) => {
+                mentions_state.room_aliases.insert(id.clone());
+            }
+            FoundMention::AtRoom => mentions_state.has_at_room_mention = true,
+        }
+    }
+    mentions_state
+}
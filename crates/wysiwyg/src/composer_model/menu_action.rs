@@ -16,10 +16,12 @@ use std::collections::HashSet;
 
 use crate::{
     dom::{
-        unicode_string::{UnicodeStr, UnicodeStringExt},
+        nodes::dom_node::DomNodeKind,
+        unicode_string::{UnicodeStr, UnicodeStrExt, UnicodeStringExt},
         Range,
     },
-    ComposerModel, MenuAction, PatternKey, SuggestionPattern, UnicodeString,
+    ComposerModel, ComposerUpdate, MenuAction, PatternKey, SuggestionPattern,
+    UnicodeString,
 };
 
 impl<S> ComposerModel<S>
@@ -38,21 +40,131 @@ where
         {
             return MenuAction::None;
         }
-        let (raw_text, start, end) = self.extended_text(range);
+        let (raw_text, start, end) = self.extended_text(range.clone());
 
-        if let Some((key, text)) = Self::pattern_for_text(
+        let suggestion = if let Some((key, text)) = Self::pattern_for_text(
             raw_text,
             start,
             &self.custom_suggestion_patterns,
         ) {
-            MenuAction::Suggestion(SuggestionPattern {
+            Some(SuggestionPattern {
                 key,
                 text,
                 start,
                 end,
+                revision: self.state.revision,
             })
         } else {
-            MenuAction::None
+            self.multi_word_pattern_for_range(&range)
+        };
+
+        match suggestion {
+            Some(suggestion)
+                if !self.is_dismissed(&suggestion)
+                    && !self
+                        .disabled_suggestion_keys
+                        .contains(&suggestion.key) =>
+            {
+                MenuAction::Suggestion(suggestion)
+            }
+            _ => MenuAction::None,
+        }
+    }
+
+    /// Stop reporting the current suggestion. Typing more text into the
+    /// same pattern won't bring it back, but editing the text before it
+    /// or starting a new pattern will.
+    pub fn dismiss_suggestion(&mut self) -> ComposerUpdate<S> {
+        if let MenuAction::Suggestion(suggestion) = self.compute_menu_action() {
+            self.dismissed_suggestion = Some(suggestion);
+        }
+        self.create_update_update_selection()
+    }
+
+    /// A suggestion stays dismissed while it is the dismissed pattern with
+    /// more text typed after it.
+    fn is_dismissed(&self, suggestion: &SuggestionPattern) -> bool {
+        self.dismissed_suggestion
+            .as_ref()
+            .map_or(false, |dismissed| {
+                dismissed.key == suggestion.key
+                    && dismissed.start == suggestion.start
+                    && suggestion.text.starts_with(&dismissed.text)
+            })
+    }
+
+    /// Whether the content changed after `suggestion` was sent, so that its
+    /// offsets can't be trusted any more, e.g. when a slow lookup for a
+    /// mention finishes after the user typed more.
+    pub(crate) fn suggestion_is_out_of_date(
+        &self,
+        suggestion: &SuggestionPattern,
+    ) -> bool {
+        suggestion.revision < self.content_revision
+    }
+
+    /// Compute a pattern that may contain whitespace, for the keys set with
+    /// `set_multi_word_suggestion_patterns`. The pattern starts at the
+    /// closest trigger character before the cursor which is at the start
+    /// of its text node or follows a whitespace, and ends at the end of the
+    /// word containing the cursor.
+    fn multi_word_pattern_for_range(
+        &self,
+        range: &Range,
+    ) -> Option<SuggestionPattern> {
+        if self.multi_word_suggestion_keys.is_empty()
+            || range.start() != range.end()
+        {
+            return None;
+        }
+        let location = range.leaves().find(|loc| {
+            loc.kind == DomNodeKind::Text && loc.start_offset > 0
+        })?;
+        let data = self
+            .state
+            .dom
+            .lookup_node(&location.node_handle)
+            .as_text()?
+            .data();
+
+        let before_cursor = &data[..location.start_offset];
+        let mut trigger = None;
+        let mut offset = 0;
+        let mut prev_char: Option<char> = None;
+        for c in before_cursor.chars() {
+            if prev_char.map_or(true, |p| p.is_whitespace()) {
+                if let Some(key) =
+                    self.multi_word_suggestion_keys.iter().find(|k| {
+                        k.trigger_char() == Some(c)
+                            && (**k != PatternKey::Slash
+                                || location.position + offset == 0)
+                    })
+                {
+                    let text_start = offset + data.char_len(&c);
+                    trigger = Some((key.clone(), offset, text_start));
+                }
+            }
+            offset += data.char_len(&c);
+            prev_char = Some(c);
+        }
+        let (key, trigger_start, text_start) = trigger?;
+
+        let end_offset = location.start_offset
+            + data.next_whitespace_offset(location.start_offset);
+        let text = data[text_start..end_offset].to_string();
+        let start = location.position + trigger_start;
+        let end = location.position + end_offset;
+
+        if text.starts_with(char::is_whitespace) || text.contains('\n') {
+            None
+        } else {
+            Some(SuggestionPattern {
+                key,
+                text,
+                start,
+                end,
+                revision: self.state.revision,
+            })
         }
     }
 
@@ -12,20 +12,23 @@
 // See the License for the specific language governing permissions and
 // limitations under the License.
 
-use strum::IntoEnumIterator;
-
-use crate::action_state::ActionState;
+use crate::action_state::{ActionState, DisabledReason};
+use crate::composer_model::action_registry::DisabledWhen::{
+    self, CannotIndent, CannotUnindent, InCodeBlock, InInlineCode,
+    LinkActionDisabled, NotCreatingLinkWithText, NotEditingLink, NothingToRedo,
+    NothingToUndo,
+};
+use crate::composer_model::action_registry::{
+    actions_reversed_by, ACTION_RULES,
+};
 use crate::dom::nodes::dom_node::DomNodeKind;
-use crate::dom::nodes::{ContainerNode, ContainerNodeKind};
 use crate::dom::range::DomLocationPosition::{After, Before};
 use crate::dom::{DomLocation, Range};
 use crate::menu_state::MenuStateUpdate;
-use crate::ComposerAction::{
-    Indent, Link, OrderedList, Unindent, UnorderedList,
-};
+use crate::ComposerAction::{OrderedList, UnorderedList};
 use crate::{
     ComposerAction, ComposerModel, DomHandle, DomNode, InlineFormatType,
-    LinkAction, ListType, MenuState, UnicodeString,
+    LinkAction, Location, MenuState, UnicodeString,
 };
 use std::collections::{HashMap, HashSet};
 
@@ -34,6 +37,27 @@ pub(crate) enum MenuStateComputeType {
     KeepIfUnchanged,
 }
 
+/// The action states last computed, and what they were computed from, so
+/// that they are only computed again once one of those changes.
+#[derive(Clone, Default)]
+pub(crate) struct MenuStateCache {
+    inputs: Option<MenuStateInputs>,
+    action_states: HashMap<ComposerAction, ActionState>,
+    disabled_reasons: HashMap<ComposerAction, DisabledReason>,
+    quote_depth: usize,
+}
+
+/// Everything the action states depend on.
+#[derive(Clone, PartialEq)]
+struct MenuStateInputs {
+    dom_revision: u64,
+    start: Location,
+    end: Location,
+    toggled_format_types: Vec<InlineFormatType>,
+    can_undo: bool,
+    can_redo: bool,
+}
+
 impl<S> ComposerModel<S>
 where
     S: UnicodeString,
@@ -42,67 +66,110 @@ where
         &mut self,
         compute_type: MenuStateComputeType,
     ) -> MenuState {
-        let (s, e) = self.safe_selection();
-        let range = self.state.dom.find_range(s, e);
-
-        let action_states = self.compute_action_states(&range);
+        let (action_states, disabled_reasons, quote_depth) =
+            self.cached_action_states();
+        let history_depths = (self.undo_depth(), self.redo_depth());
 
         if action_states == self.action_states
+            && disabled_reasons == self.disabled_reasons
+            && history_depths == self.reported_history_depths
+            && quote_depth == self.reported_quote_depth
             && matches!(compute_type, MenuStateComputeType::KeepIfUnchanged)
         {
             MenuState::Keep
         } else {
             self.action_states = action_states.clone();
-            MenuState::Update(MenuStateUpdate { action_states })
+            self.disabled_reasons = disabled_reasons.clone();
+            self.reported_history_depths = history_depths;
+            self.reported_quote_depth = quote_depth;
+            MenuState::Update(MenuStateUpdate {
+                action_states,
+                disabled_reasons,
+                undo_depth: history_depths.0,
+                redo_depth: history_depths.1,
+                quote_depth,
+            })
+        }
+    }
+
+    /// The action states and quote depth for the current state of the
+    /// model, only computed again if the content, selection, toggled formats
+    /// or history changed since they were last computed.
+    fn cached_action_states(
+        &mut self,
+    ) -> (
+        HashMap<ComposerAction, ActionState>,
+        HashMap<ComposerAction, DisabledReason>,
+        usize,
+    ) {
+        let inputs = MenuStateInputs {
+            dom_revision: self.state.dom.revision(),
+            start: self.state.start,
+            end: self.state.end,
+            toggled_format_types: self.state.toggled_format_types.clone(),
+            can_undo: !self.previous_states.is_empty(),
+            can_redo: !self.next_states.is_empty(),
+        };
+        if self.menu_state_cache.inputs.as_ref() != Some(&inputs) {
+            let (s, e) = self.safe_selection();
+            let range = self.state.dom.find_range(s, e);
+            let (action_states, disabled_reasons) =
+                self.compute_action_states(&range);
+            self.menu_state_cache = MenuStateCache {
+                inputs: Some(inputs),
+                action_states,
+                disabled_reasons,
+                quote_depth: self.get_quote_level(),
+            };
         }
+        (
+            self.menu_state_cache.action_states.clone(),
+            self.menu_state_cache.disabled_reasons.clone(),
+            self.menu_state_cache.quote_depth,
+        )
     }
 
     fn compute_action_states(
         &self,
         range: &Range,
-    ) -> HashMap<ComposerAction, ActionState> {
-        let mut action_states = HashMap::new();
+    ) -> (
+        HashMap<ComposerAction, ActionState>,
+        HashMap<ComposerAction, DisabledReason>,
+    ) {
+        let mut action_states = HashMap::with_capacity(ACTION_RULES.len());
+        let mut disabled_reasons = HashMap::new();
 
         let reversed = self.compute_reversed_actions_from_range(range);
-        let disabled = self.compute_disabled_actions();
+        let disabled = self.compute_disabled_conditions(range);
 
-        for action in ComposerAction::iter() {
-            let state = if disabled.contains(&action) {
+        for rule in ACTION_RULES.iter() {
+            let disabled_by = rule
+                .disabled_when
+                .iter()
+                .find(|condition| disabled.contains(condition));
+            let state = if let Some(condition) = disabled_by {
+                disabled_reasons
+                    .insert(rule.action.clone(), condition.reason());
                 ActionState::Disabled
-            } else if reversed.contains(&action) {
+            } else if reversed.contains(&rule.action) {
                 ActionState::Reversed
             } else {
                 ActionState::Enabled
             };
-            action_states.insert(action, state);
+            action_states.insert(rule.action.clone(), state);
         }
 
-        action_states
+        (action_states, disabled_reasons)
     }
 
     fn compute_reversed_actions_from_range(
         &self,
         range: &Range,
     ) -> HashSet<ComposerAction> {
-        let toggled_format_actions = self
-            .state
-            .toggled_format_types
-            .iter()
-            .map(|format| format.action())
-            .collect();
-
-        let reversed_actions = if let Some(first_leaf) = range.leaves().next() {
-            range
-                .leaves()
-                // do not need locations after the cursor for next logic
-                .filter(|loc| !(loc.relative_position() == After))
-                .map(|loc| self.compute_reversed_actions(&loc.node_handle))
-                .fold(
-                    // Init with reversed_actions from the first leave.
-                    self.compute_reversed_actions(&first_leaf.node_handle),
-                    // And intersect with the reversed_actions of all subsequent leaves.
-                    |i, set| i.intersection(&set).cloned().collect(),
-                )
+        let mut reversed_actions = if let Some(first_leaf) =
+            range.leaves().next()
+        {
+            self.compute_reversed_actions_for_leaves(range, first_leaf)
         } else if self.state.dom.document().children().is_empty() {
             HashSet::new()
         } else if let Some(container_loc) = range.deepest_container_node(None) {
@@ -115,10 +182,65 @@ where
             HashSet::new()
         };
 
+        // Toggled formats flip whether their action is reversed.
+        for action in self
+            .state
+            .toggled_format_types
+            .iter()
+            .filter_map(|format| format.toggle_action())
+        {
+            if !reversed_actions.remove(&action) {
+                reversed_actions.insert(action);
+            }
+        }
+        // Highlights of different colours can be toggled at once, e.g. one
+        // off and another on, so Highlight is reversed if one is turned on.
+        let mut toggled_highlights = self
+            .state
+            .toggled_format_types
+            .iter()
+            .filter(|f| matches!(f, InlineFormatType::Highlight(_)))
+            .peekable();
+        if toggled_highlights.peek().is_some() {
+            if toggled_highlights.any(|f| self.format_is_reversed(f)) {
+                reversed_actions.insert(ComposerAction::Highlight);
+            } else {
+                reversed_actions.remove(&ComposerAction::Highlight);
+            }
+        }
         reversed_actions
-            .symmetric_difference(&toggled_format_actions)
-            .cloned()
-            .collect()
+    }
+
+    /// The actions reversed for every leaf in `range`, ignoring those after
+    /// the cursor. This is the slowest part of computing the menu state for
+    /// large selections, so it narrows down one set in place, only looks at
+    /// each parent once and stops as soon as nothing is left.
+    fn compute_reversed_actions_for_leaves(
+        &self,
+        range: &Range,
+        first_leaf: &DomLocation,
+    ) -> HashSet<ComposerAction> {
+        let mut reversed =
+            self.compute_reversed_actions(&first_leaf.node_handle);
+        // Leaves are never containers, so leaves sharing a parent reverse
+        // the same actions.
+        let mut checked_parent: Option<DomHandle> = None;
+        for loc in range
+            .leaves()
+            .filter(|loc| loc.relative_position() != After)
+        {
+            if reversed.is_empty() {
+                break;
+            }
+            let parent = loc.node_handle.parent_handle();
+            if checked_parent.as_ref() == Some(&parent) {
+                continue;
+            }
+            let leaf_reversed = self.compute_reversed_actions(&loc.node_handle);
+            reversed.retain(|action| leaf_reversed.contains(action));
+            checked_parent = Some(parent);
+        }
+        reversed
     }
 
     fn compute_reversed_actions(
@@ -132,7 +254,7 @@ where
         handle.with_ancestors().iter().rev().fold(
             HashSet::new(),
             |mut set, handle| {
-                if let Some(action) = self.reversed_action_for_handle(handle) {
+                for action in self.reversed_actions_for_handle(handle) {
                     match action {
                         // If there is multiple list types in the hierarchy we
                         // only keep the deepest list type.
@@ -147,104 +269,65 @@ where
         )
     }
 
-    fn reversed_action_for_handle(
+    fn reversed_actions_for_handle(
         &self,
         handle: &DomHandle,
-    ) -> Option<ComposerAction> {
+    ) -> Vec<ComposerAction> {
         let node = self.state.dom.lookup_node(handle);
         if let DomNode::Container(container) = node {
-            Self::reversed_action_for_container(container)
+            actions_reversed_by(container).collect()
         } else {
-            None
+            Vec::new()
         }
     }
 
-    fn reversed_action_for_container(
-        container: &ContainerNode<S>,
-    ) -> Option<ComposerAction> {
-        match container.kind() {
-            ContainerNodeKind::Formatting(format) => match format {
-                InlineFormatType::Bold => Some(ComposerAction::Bold),
-                InlineFormatType::Italic => Some(ComposerAction::Italic),
-                InlineFormatType::StrikeThrough => {
-                    Some(ComposerAction::StrikeThrough)
-                }
-                InlineFormatType::Underline => Some(ComposerAction::Underline),
-                InlineFormatType::InlineCode => {
-                    Some(ComposerAction::InlineCode)
-                }
-            },
-            ContainerNodeKind::Link(_) => Some(ComposerAction::Link),
-            ContainerNodeKind::List(list_type) => match list_type {
-                ListType::Ordered => Some(ComposerAction::OrderedList),
-                ListType::Unordered => Some(ComposerAction::UnorderedList),
-            },
-            ContainerNodeKind::CodeBlock => Some(ComposerAction::CodeBlock),
-            ContainerNodeKind::Quote => Some(ComposerAction::Quote),
-            _ => None,
-        }
-    }
-
-    fn compute_disabled_actions(&self) -> HashSet<ComposerAction> {
-        let mut disabled_actions = HashSet::new();
+    /// The conditions from [DisabledWhen] which are currently true.
+    fn compute_disabled_conditions(
+        &self,
+        range: &Range,
+    ) -> HashSet<DisabledWhen> {
+        let mut conditions = HashSet::new();
         if self.previous_states.is_empty() {
-            disabled_actions.insert(ComposerAction::Undo);
+            conditions.insert(NothingToUndo);
         }
         if self.next_states.is_empty() {
-            disabled_actions.insert(ComposerAction::Redo);
+            conditions.insert(NothingToRedo);
         }
 
-        let (s, e) = self.safe_selection();
-        let range = self.state.dom.find_range(s, e);
-        disabled_actions.extend(
-            self.compute_disabled_actions_for_locations(&range.locations),
-        );
-        disabled_actions
-    }
-
-    fn compute_disabled_actions_for_locations(
-        &self,
-        locations: &[DomLocation],
-    ) -> HashSet<ComposerAction> {
-        let mut disabled_actions = HashSet::new();
         let top_most_list_locations =
-            self.find_top_most_list_item_locations(locations);
+            self.find_top_most_list_item_locations(&range.locations);
         if !self.can_indent(&top_most_list_locations) {
-            disabled_actions.insert(Indent);
+            conditions.insert(CannotIndent);
         }
         if !self.can_unindent(&top_most_list_locations) {
-            disabled_actions.insert(Unindent);
+            conditions.insert(CannotUnindent);
         }
-        if self.get_link_action() == LinkAction::Disabled {
-            disabled_actions.insert(Link);
+
+        let link_action = self.get_link_action();
+        if link_action == LinkAction::Disabled {
+            conditions.insert(LinkActionDisabled);
         }
+        if link_action != LinkAction::CreateWithText {
+            conditions.insert(NotCreatingLinkWithText);
+        }
+        if !matches!(link_action, LinkAction::Edit(_)) {
+            conditions.insert(NotEditingLink);
+        }
+
         // XOR on inline code in selection & toggled format types.
         // If selection is not a cursor, toggled format types is always
         // empty, which makes `contains_inline_code` the only condition.
-        if contains_inline_code(locations)
+        if contains_inline_code(&range.locations)
             ^ self
                 .state
                 .toggled_format_types
                 .contains(&InlineFormatType::InlineCode)
         {
-            // Remove the rest of inline formatting options
-            disabled_actions.extend(vec![
-                ComposerAction::Bold,
-                ComposerAction::Italic,
-                ComposerAction::Underline,
-                ComposerAction::StrikeThrough,
-                ComposerAction::Link,
-            ])
-        } else if contains_code_block(locations) {
-            disabled_actions.extend(vec![
-                ComposerAction::InlineCode,
-                ComposerAction::OrderedList,
-                ComposerAction::UnorderedList,
-                ComposerAction::Quote,
-                ComposerAction::Link,
-            ])
+            conditions.insert(InInlineCode);
+        } else if contains_code_block(&range.locations) {
+            conditions.insert(InCodeBlock);
         }
-        disabled_actions
+        conditions
     }
 }
 
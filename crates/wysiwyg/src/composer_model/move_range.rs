@@ -0,0 +1,117 @@
+// Copyright 2023 The Matrix.org Foundation C.I.C.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use crate::dom::nodes::DomNode;
+use crate::dom::Dom;
+use crate::{
+    ComposerModel, ComposerUpdate, DomHandle, Location, UnicodeString,
+};
+
+impl<S> ComposerModel<S>
+where
+    S: UnicodeString,
+{
+    /// Move the content between `src_start` and `src_end` to `dest`, e.g.
+    /// when it is dragged and dropped. `dest` is a location in the document
+    /// as it is before the move. Formatting, links and mentions inside the
+    /// moved content are kept, and the moved content is selected afterwards.
+    /// The move is a single undo step.
+    ///
+    /// Only content inside one block can be moved, and it can't be dropped
+    /// inside itself. Otherwise nothing happens.
+    pub fn move_range(
+        &mut self,
+        src_start: Location,
+        src_end: Location,
+        dest: Location,
+    ) -> ComposerUpdate<S> {
+        let (s, e) = self.safe_locations_from(src_start, src_end);
+        let dest: usize = dest.into();
+        if s == e || dest > self.state.dom.text_len() || (s..=e).contains(&dest)
+        {
+            return ComposerUpdate::keep_at(self.state.revision);
+        }
+        let dom = match self.dom_between(s, e) {
+            Ok(dom) => dom,
+            Err(error) => {
+                self.report_error(&error);
+                return ComposerUpdate::keep_at(self.state.revision);
+            }
+        };
+        let Some(nodes) = inline_children(dom) else {
+            return ComposerUpdate::keep_at(self.state.revision);
+        };
+
+        self.push_state_to_history();
+        if let Err(error) = self.state.dom.replace_text_in(S::default(), s, e) {
+            return self.recover_from(error);
+        }
+        let dest = if dest > e { dest - (e - s) } else { dest };
+        let len: usize = nodes.iter().map(|n| n.text_len()).sum();
+        self.insert_nodes_at(dest, nodes);
+
+        self.state.start = Location::from(dest);
+        self.state.end = Location::from(dest + len);
+        self.create_update_replace_all()
+    }
+
+    /// Insert `nodes` next to each other at `pos`. Nodes are inserted before
+    /// a leaf starting at `pos` rather than after one ending there, so they
+    /// don't pick up the formatting of the text before them.
+    fn insert_nodes_at(&mut self, pos: usize, mut nodes: Vec<DomNode<S>>) {
+        if nodes.is_empty() {
+            return;
+        }
+        let first = nodes.remove(0);
+        let range = self.state.dom.find_range(pos, pos);
+        let leaf = range
+            .leaves()
+            .find(|l| l.start_offset < l.length)
+            .or_else(|| range.leaves().last())
+            .map(|l| (l.node_handle.clone(), l.start_offset));
+        let first_handle = if let Some((handle, offset)) = leaf {
+            self.state.dom.insert_into_text(&handle, offset, first)
+        } else {
+            self.state.dom.insert_node_at_cursor(&range, first)
+        };
+
+        let parent_handle = first_handle.parent_handle();
+        self.state
+            .dom
+            .parent_mut(&first_handle)
+            .insert_children(first_handle.index_in_parent() + 1, nodes);
+        self.state.dom.join_nodes_in_container(&parent_handle);
+    }
+}
+
+/// The inline nodes inside the only block of `dom`, or None if it contains
+/// more than one block.
+fn inline_children<S: UnicodeString>(dom: Dom<S>) -> Option<Vec<DomNode<S>>> {
+    let mut handle = DomHandle::root();
+    loop {
+        let DomNode::Container(container) = dom.lookup_node(&handle) else {
+            return None;
+        };
+        let children = container.children();
+        match children.as_slice() {
+            [child] if child.kind().is_block_kind() => {
+                handle = child.handle();
+            }
+            _ if children.iter().any(|c| c.kind().is_block_kind()) => {
+                return None;
+            }
+            _ => return Some(children.clone()),
+        }
+    }
+}
@@ -2,45 +2,125 @@ use crate::dom::nodes::dom_node::DomNodeKind;
 use crate::dom::nodes::dom_node::DomNodeKind::{
     Generic, Link, List, ListItem, Paragraph,
 };
+use crate::dom::nodes::ContainerNodeKind;
 use crate::dom::{Dom, DomLocation};
-use crate::{ComposerModel, ComposerUpdate, DomNode, UnicodeString};
+use crate::{
+    ComposerError, ComposerModel, ComposerUpdate, DomNode, Location,
+    UnicodeString,
+};
 
 impl<S> ComposerModel<S>
 where
     S: UnicodeString,
 {
     /// Adds a new line break by creating a paragraph.
+    /// Does nothing inside maths, which can't be split.
     pub fn enter(&mut self) -> ComposerUpdate<S> {
+        let (s, e) = self.safe_selection();
+        if self.is_selection_inside_math(s, e) {
+            return ComposerUpdate::keep_at(self.state.revision);
+        }
         self.push_state_to_history();
         self.do_enter()
     }
 
+    /// Adds a line break (`<br />`) at the cursor, replacing any selection,
+    /// e.g. for shift+enter. Unlike [Self::enter], the block the cursor is
+    /// in is not split. In a code block, this is the same as enter, and
+    /// inside maths it does nothing.
+    pub fn insert_line_break(&mut self) -> ComposerUpdate<S> {
+        let (s, e) = self.safe_selection();
+        if self.is_selection_inside_math(s, e) {
+            return ComposerUpdate::keep_at(self.state.revision);
+        }
+        let range = self.state.dom.find_range(s, e);
+        if range
+            .locations
+            .iter()
+            .any(|l| l.kind == DomNodeKind::CodeBlock)
+        {
+            return self.enter();
+        }
+
+        self.push_state_to_history();
+        if s != e {
+            if let Err(error) = self.replace_text_without_update(S::default()) {
+                return self.recover_from(error);
+            }
+        }
+        let (s, _) = self.safe_selection();
+        let range = self.state.dom.find_range(s, s);
+        self.state
+            .dom
+            .insert_node_at_cursor(&range, DomNode::new_line_break());
+        self.state.start = Location::from(s + 1);
+        self.state.end = self.state.start;
+        self.create_update_replace_all()
+    }
+
     pub(crate) fn do_enter(&mut self) -> ComposerUpdate<S> {
+        match self.enter_without_update() {
+            Ok(true) => self.create_update_replace_all(),
+            Ok(false) => ComposerUpdate::keep_at(self.state.revision),
+            Err(error) => self.recover_from(error),
+        }
+    }
+
+    /// Like [Self::do_enter], but without creating an update, for when a
+    /// new line is only one step of an operation. Returns false if nothing
+    /// changed.
+    pub(crate) fn enter_without_update(
+        &mut self,
+    ) -> Result<bool, ComposerError> {
         let (s, e) = self.safe_selection();
+        if self.is_selection_inside_math(s, e) {
+            return Ok(false);
+        }
+        // Enter on a horizontal rule starts a new line after it
+        if s == e && self.move_off_horizontal_rule_at(s) {
+            return Ok(true);
+        }
         let range = self.state.dom.find_range(s, e);
 
         // No selection, add two paragraphs to the Dom and exit:
         // 1st paragraph was the one we were in (although it was a 'virtual' one).
         // 2nd one is the one we just created.
-        if range.locations.is_empty() {
+        if range.is_empty() {
             self.state.dom.document_mut().append_children(vec![
                 DomNode::new_paragraph(Vec::new()),
                 DomNode::new_paragraph(Vec::new()),
             ]);
             self.state.advance_selection();
-            return self.create_update_replace_all();
+            return Ok(true);
         }
 
         // If the selection covered several characters, remove them first
         if range.is_selection() {
-            self.do_replace_text(S::default());
+            self.replace_text_without_update(S::default())?;
         }
 
-        let block_location = range.deepest_block_node(None).expect(
-            "No block node selected (at least the root one should be here)",
-        );
+        // The root is always found, so there is always a block
+        let Some(block_location) = range.deepest_block_node(None) else {
+            return Ok(false);
+        };
         let block_handle = block_location.node_handle.clone();
 
+        // A list with no items, e.g. from setting the HTML, gets an empty
+        // one, and then enter works as it does in any empty list item
+        if block_location.kind == List
+            && self
+                .state
+                .dom
+                .try_lookup_container(&block_handle)?
+                .is_empty()
+        {
+            self.state.dom.insert_at(
+                &block_handle.child_handle(0),
+                DomNode::new_list_item(Vec::new()),
+            );
+            return self.enter_without_update();
+        }
+
         // TODO: what if a block node was removed and the next one has the same type?
         // If we the block node was removed, just insert an empty paragraph
         if !self.state.dom.contains(&block_handle)
@@ -68,39 +148,41 @@ where
             } else {
                 self.state.dom.insert_at(&block_handle, paragraph);
             }
-            return self.create_update_replace_all();
+            return Ok(true);
         }
 
         let first_leaf = range.leaves().next();
         match block_location.kind {
-            Paragraph | DomNodeKind::Quote => {
+            Paragraph | DomNodeKind::Heading | DomNodeKind::Quote => {
                 let ancestor_block_location =
                     range.deepest_block_node(Some(&block_handle));
                 if let Some(ancestor_block_location) = ancestor_block_location {
-                    if ancestor_block_location.kind != Generic
-                        && block_location.is_empty()
+                    if block_location.is_empty()
+                        && self
+                            .exits_on_empty_line(&ancestor_block_location.kind)
                     {
                         self.do_new_line_in_block_node(
                             block_location,
                             ancestor_block_location,
-                        );
+                        )?;
                     } else {
                         self.do_new_line_in_paragraph(
                             first_leaf,
                             block_location,
-                        );
+                        )?;
                     }
                 } else {
-                    self.do_new_line_in_paragraph(first_leaf, block_location);
+                    self.do_new_line_in_paragraph(first_leaf, block_location)?;
                 }
             }
             ListItem => {
                 let list_item_has_no_text = self
                     .state
                     .dom
-                    .lookup_node(&block_location.node_handle)
+                    .try_lookup_node(&block_location.node_handle)?
                     .has_no_text();
-                if list_item_has_no_text {
+                if list_item_has_no_text && self.exits_on_empty_line(&ListItem)
+                {
                     let list_handle =
                         block_location.node_handle.parent_handle();
                     // Remove the current list item
@@ -120,7 +202,9 @@ where
                     } else {
                         // Otherwise, add new paragraph after the current list
                         let DomNode::Container(list_item) = li else {
-                            panic!("List item is not a container")
+                            return Err(ComposerError::NotAContainer(
+                                block_location.node_handle.clone(),
+                            ));
                         };
                         // A list item without text might still contain some formatting nodes that
                         // should be transferred to the new paragraph.
@@ -130,14 +214,18 @@ where
                         );
                     }
                     // If list becomes empty, remove it too
-                    if self.state.dom.lookup_container(&list_handle).is_empty()
+                    if self
+                        .state
+                        .dom
+                        .try_lookup_container(&list_handle)?
+                        .is_empty()
                     {
                         self.state.dom.remove(&list_handle);
 
                         // Then remove extra paragraphs from siblings if needed
                         let remove_extra_initial_paragraph = {
                             let parent_list_item_container =
-                                self.state.dom.parent(&list_handle);
+                                self.state.dom.try_parent(&list_handle)?;
                             parent_list_item_container.is_list_item()
                                 && parent_list_item_container.children().len()
                                     == 1
@@ -151,13 +239,21 @@ where
                             );
                         }
                     }
+                } else if list_item_has_no_text {
+                    self.state.dom.insert_at(
+                        &block_location.node_handle.next_sibling(),
+                        DomNode::new_list_item(Vec::new()),
+                    );
+                    self.state.advance_selection();
                 } else if block_location.start_offset == 0 {
                     self.state.dom.insert_at(
                         &block_location.node_handle,
                         DomNode::new_list_item(Vec::new()),
                     );
                 } else {
-                    let first_leaf = first_leaf.unwrap();
+                    let Some(first_leaf) = first_leaf else {
+                        return Err(ComposerError::MissingLeaf(s));
+                    };
                     let mut sub_tree = self.state.dom.split_sub_tree_from(
                         &first_leaf.node_handle,
                         first_leaf.start_offset,
@@ -172,35 +268,49 @@ where
                     self.state.advance_selection();
                 }
             }
-            Generic => {
-                self.do_new_line_in_paragraph(first_leaf, block_location);
+            // Anything else, e.g. the root when the document only has
+            // inline nodes, is split like a paragraph
+            _ => {
+                self.do_new_line_in_paragraph(first_leaf, block_location)?;
             }
-            _ => panic!(
-                "Unexpected kind {:?} with inline contents",
-                block_location.kind
-            ),
         }
-        self.create_update_replace_all()
+        Ok(true)
+    }
+
+    /// Whether enter on an empty line directly inside a block of `kind`
+    /// leaves that block rather than adding another line to it.
+    fn exits_on_empty_line(&self, kind: &DomNodeKind) -> bool {
+        match kind {
+            ListItem => self.exit_list_on_empty_line,
+            DomNodeKind::Quote => self.exit_quote_on_empty_line,
+            DomNodeKind::CodeBlock => self.exit_code_block_on_empty_line,
+            Generic => false,
+            _ => true,
+        }
     }
 
     fn do_new_line_in_paragraph(
         &mut self,
         first_leaf: Option<&DomLocation>,
         paragraph_location: &DomLocation,
-    ) {
+    ) -> Result<(), ComposerError> {
         if let Some(first_leaf) = first_leaf {
             let block_node_handle = paragraph_location.node_handle.clone();
-            let block_node_is_paragraph =
-                self.state.dom.lookup_node(&block_node_handle).kind()
-                    == Paragraph;
-            let child_count = self
-                .state
-                .dom
-                .lookup_container(&block_node_handle)
-                .children()
-                .len();
+            let block_node_is_paragraph = matches!(
+                self.state.dom.try_lookup_node(&block_node_handle)?.kind(),
+                Paragraph | DomNodeKind::Heading
+            );
+            let block_container =
+                self.state.dom.try_lookup_container(&block_node_handle)?;
+            let heading_level = block_container.heading_level();
+            let alignment = block_container.alignment_and_attribute();
+            let Some(last_child_index) =
+                block_container.children().len().checked_sub(1)
+            else {
+                return Err(ComposerError::MissingLeaf(first_leaf.position));
+            };
             let last_child_handle =
-                block_node_handle.child_handle(child_count - 1);
+                block_node_handle.child_handle(last_child_index);
 
             // Wrap the contents of the "right" sub tree into a paragraph and insert it
             let mut sub_tree = self.state.dom.split_sub_tree_between(
@@ -217,12 +327,34 @@ where
                 !self.state.dom.contains(&block_node_handle);
 
             let mut children = sub_tree_container.remove_children();
-            let new_paragraph =
+            let mut new_paragraph =
                 if children.first().map_or(false, |n| n.kind() == Paragraph) {
                     children.remove(0)
                 } else {
                     DomNode::new_paragraph(children)
                 };
+            // The second half of a split heading is a heading too, but
+            // splitting one at its end starts a normal paragraph after it
+            if let (Some(level), DomNode::Container(c)) =
+                (heading_level, &mut new_paragraph)
+            {
+                if c.kind() == &ContainerNodeKind::Paragraph && c.text_len() > 0
+                {
+                    c.set_heading_level(Some(level));
+                }
+            }
+            // Both halves keep the alignment
+            if let (Some((alignment, attribute)), DomNode::Container(c)) =
+                (alignment, &mut new_paragraph)
+            {
+                if matches!(
+                    c.kind(),
+                    ContainerNodeKind::Paragraph
+                        | ContainerNodeKind::Heading(_)
+                ) {
+                    c.set_alignment(Some(alignment), attribute);
+                }
+            }
             let depth = if block_node_is_paragraph {
                 block_node_handle.depth()
             } else {
@@ -241,9 +373,11 @@ where
             // Now do the same for any children remaining in the tree
             if !block_node_is_paragraph {
                 let DomNode::Container(block_container) =
-                    self.state.dom.lookup_node_mut(&block_node_handle)
+                    self.state.dom.try_lookup_node_mut(&block_node_handle)?
                 else {
-                    panic!("Block container must be a container node");
+                    return Err(ComposerError::NotAContainer(
+                        block_node_handle,
+                    ));
                 };
                 let mut children = Vec::new();
                 for _ in 0..new_paragraph_handle.index_in_parent() {
@@ -270,13 +404,14 @@ where
             );
             self.state.advance_selection();
         }
+        Ok(())
     }
 
     fn do_new_line_in_block_node(
         &mut self,
         empty_paragraph_location: &DomLocation,
         ancestor_block_location: &DomLocation,
-    ) {
+    ) -> Result<(), ComposerError> {
         let block_handle = &ancestor_block_location.node_handle;
         // Remove existing empty paragraph
         self.state.dom.remove(&empty_paragraph_location.node_handle);
@@ -292,7 +427,7 @@ where
         let block_node_became_empty = if block_node_was_removed {
             false
         } else {
-            self.state.dom.lookup_node(block_handle).is_empty()
+            self.state.dom.try_lookup_node(block_handle)?.is_empty()
         };
         if block_node_became_empty {
             self.state.dom.remove(block_handle);
@@ -315,6 +450,7 @@ where
         self.state
             .dom
             .insert_at(&insert_at, DomNode::new_paragraph(Vec::new()));
+        Ok(())
     }
 }
 
@@ -506,4 +642,43 @@ mod test {
             r#"<p><a data-mention-type="user" href="https://matrix.to/#/@carol:matrix.org" contenteditable="false">@carol</a></p><p>&nbsp;|</p>"#
         )
     }
+
+    #[test]
+    fn insert_line_break_keeps_the_paragraph() {
+        let mut model = cm("<p>ab|cd</p><p>ef</p>");
+        model.insert_line_break();
+        assert_eq!(tx(&model), "<p>ab<br />|cd</p><p>ef</p>");
+        model.undo();
+        assert_eq!(tx(&model), "<p>ab|cd</p><p>ef</p>");
+    }
+
+    #[test]
+    fn insert_line_break_replaces_the_selection() {
+        let mut model = cm("a{bc}|d");
+        model.insert_line_break();
+        assert_eq!(tx(&model), "a<br />|d");
+    }
+
+    #[test]
+    fn insert_line_break_in_formatting_and_list_items() {
+        let mut model = cm("<ul><li><b>ab|</b></li></ul>");
+        model.insert_line_break();
+        assert_eq!(tx(&model), "<ul><li><b>ab<br />|</b></li></ul>");
+    }
+
+    #[test]
+    fn insert_line_break_in_code_block_adds_a_new_line() {
+        let mut model = cm("<pre><code>ab|</code></pre>");
+        let mut expected = model.clone();
+        model.insert_line_break();
+        expected.enter();
+        assert_eq!(tx(&model), tx(&expected));
+    }
+
+    #[test]
+    fn insert_line_break_in_inline_code_adds_a_line_break() {
+        let mut model = cm("<p><code>ab|cd</code></p>");
+        model.insert_line_break();
+        assert_eq!(tx(&model), "<p><code>ab<br />|cd</code></p>");
+    }
 }
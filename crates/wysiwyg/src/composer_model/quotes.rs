@@ -13,9 +13,13 @@
 // limitations under the License.
 
 use crate::dom::nodes::dom_node::DomNodeKind::{Generic, ListItem, Quote};
-use crate::dom::DomLocation;
+use crate::dom::parser::{
+    parse_with_options, HtmlParseOptions, WhitespacePolicy,
+};
+use crate::dom::{DomCreationError, DomHandle, DomLocation};
 use crate::{
-    ComposerAction, ComposerModel, ComposerUpdate, DomNode, UnicodeString,
+    ComposerAction, ComposerModel, ComposerUpdate, DomNode, Location,
+    QuoteAttribution, UnicodeString,
 };
 
 impl<S> ComposerModel<S>
@@ -30,7 +34,218 @@ where
         }
     }
 
-    fn add_quote(&mut self) -> ComposerUpdate<S> {
+    /// Insert a quote of the message being replied to at the start of the
+    /// content, with one paragraph per line of `text` and an attribution
+    /// saying who wrote it. The cursor is put after the quote.
+    pub fn insert_reply_quote(
+        &mut self,
+        text: S,
+        attribution: QuoteAttribution,
+    ) -> ComposerUpdate<S> {
+        self.push_state_to_history();
+        let paragraphs = text
+            .to_string()
+            .split('\n')
+            .map(|line| {
+                let children = if line.is_empty() {
+                    Vec::new()
+                } else {
+                    vec![DomNode::new_text(S::from(line))]
+                };
+                DomNode::new_paragraph(children)
+            })
+            .collect();
+        let mut quote = DomNode::new_quote(paragraphs);
+        if let DomNode::Container(container) = &mut quote {
+            container.set_quote_attribution(Some(&attribution));
+        }
+
+        let dom = &mut self.state.dom;
+        if dom.document().children().is_empty() {
+            dom.append_at_end_of_document(DomNode::new_paragraph(Vec::new()));
+        }
+        let quote_handle = dom.insert_at(&DomHandle::from_raw(vec![0]), quote);
+        dom.wrap_inline_nodes_into_paragraphs_if_needed(&DomHandle::root());
+
+        let after_quote = dom
+            .find_range(0, dom.text_len())
+            .locations
+            .iter()
+            .find(|l| l.node_handle == quote_handle)
+            .map_or(0, |l| l.position + l.length);
+        self.state.start = Location::from(after_quote);
+        self.state.end = self.state.start;
+        self.create_update_replace_all()
+    }
+
+    /// Insert a quote containing `html` at the cursor, replacing any
+    /// selection, e.g. to quote a message. The block the cursor is in is
+    /// split around the quote, and the cursor ends up at the end of the
+    /// quoted content, as it does after [ComposerModel::quote]. The
+    /// insertion is a single undo step. `html` is parsed like any other
+    /// content, so e.g. images whose source isn't an `mxc://` URI are
+    /// dropped.
+    pub fn insert_quote(
+        &mut self,
+        html: &S,
+    ) -> Result<ComposerUpdate<S>, DomCreationError> {
+        let options = HtmlParseOptions {
+            whitespace_policy: WhitespacePolicy::default(),
+            custom_inline_tags: self.custom_inline_tags.clone(),
+        };
+        let mut content = parse_with_options(&html.to_string(), &options)
+            .map_err(DomCreationError::HtmlParseError)?;
+        Self::post_process_dom(&mut content);
+
+        self.push_state_to_history();
+        let quote =
+            DomNode::new_quote(content.into_container().take_children());
+        let handle = match self.insert_block_at_cursor(quote) {
+            Ok(handle) => handle,
+            Err(error) => return Ok(self.recover_from(error)),
+        };
+        self.state.start = Location::from(self.end_of_block(&handle));
+        self.state.end = self.state.start;
+        Ok(self.create_update_replace_all())
+    }
+
+    /// The attribution of the innermost quote containing the selection.
+    pub fn get_quote_attribution(&self) -> Option<QuoteAttribution> {
+        let handle = self.quote_at_selection()?;
+        self.state.dom.lookup_container(&handle).quote_attribution()
+    }
+
+    /// Set or remove the attribution of the innermost quote containing the
+    /// selection. Does nothing outside a quote.
+    pub fn set_quote_attribution(
+        &mut self,
+        attribution: Option<QuoteAttribution>,
+    ) -> ComposerUpdate<S> {
+        let Some(handle) = self.quote_at_selection() else {
+            return ComposerUpdate::keep_at(self.state.revision);
+        };
+        self.push_state_to_history();
+        if let Ok(DomNode::Container(quote)) =
+            self.state.dom.try_lookup_node_mut(&handle)
+        {
+            quote.set_quote_attribution(attribution.as_ref());
+        }
+        self.create_update_replace_all()
+    }
+
+    /// Nest the blocks in the selection one quote deeper, e.g. to quote
+    /// part of a quote. Outside a quote this adds one, like
+    /// [ComposerModel::quote].
+    pub fn increase_quote_level(&mut self) -> ComposerUpdate<S> {
+        let Some(quote_handle) = self.quote_at_selection() else {
+            self.push_state_to_history();
+            return self.add_quote();
+        };
+        let (s, e) = self.safe_selection();
+        let quote_len = self
+            .state
+            .dom
+            .lookup_container(&quote_handle)
+            .children()
+            .len();
+        if quote_len == 0 {
+            return ComposerUpdate::keep_at(self.state.revision);
+        }
+        let child_of_quote = |pos| {
+            self.block_at(pos)
+                .filter(|block| quote_handle.is_ancestor_of(block))
+                .map(|block| block.raw()[quote_handle.depth()])
+        };
+        let first = child_of_quote(s).unwrap_or(0);
+        let last = child_of_quote(e).unwrap_or(quote_len - 1).max(first);
+
+        self.push_state_to_history();
+        let mut nested = Vec::new();
+        for index in (first..=last).rev() {
+            nested.insert(
+                0,
+                self.state.dom.remove(&quote_handle.child_handle(index)),
+            );
+        }
+        self.state.dom.insert_at(
+            &quote_handle.child_handle(first),
+            DomNode::new_quote(nested),
+        );
+        self.create_update_replace_all()
+    }
+
+    /// Remove the innermost quote containing the selection, keeping its
+    /// content inside any quotes around it. Does nothing outside a quote.
+    pub fn decrease_quote_level(&mut self) -> ComposerUpdate<S> {
+        let Some(quote_handle) = self.quote_at_selection() else {
+            return ComposerUpdate::keep_at(self.state.revision);
+        };
+        self.push_state_to_history();
+        self.state.dom.remove_and_keep_children(&quote_handle);
+        self.create_update_replace_all()
+    }
+
+    /// How many quotes the selection is inside, 0 outside any quote.
+    pub fn get_quote_level(&self) -> usize {
+        let Some(mut handle) = self.quote_at_selection() else {
+            return 0;
+        };
+        let mut level = 1;
+        while handle.has_parent() {
+            handle = handle.parent_handle();
+            if self.state.dom.lookup_node(&handle).kind() == Quote {
+                level += 1;
+            }
+        }
+        level
+    }
+
+    fn quote_at_selection(&self) -> Option<DomHandle> {
+        let (s, _) = self.safe_selection();
+        let mut handle = self.block_at(s)?;
+        while self.state.dom.lookup_node(&handle).kind() != Quote {
+            if !handle.has_parent() {
+                return None;
+            }
+            handle = handle.parent_handle();
+        }
+        Some(handle)
+    }
+
+    /// Move the first block of the quote at `handle` out in front of it,
+    /// e.g. when backspacing at the start of the quote, removing the quote
+    /// if nothing else is left in it.
+    pub(crate) fn do_backspace_at_start_of_quote(
+        &mut self,
+        handle: &DomHandle,
+    ) -> ComposerUpdate<S> {
+        // Wrap any inline children in a paragraph first, so a block is
+        // lifted out and the quote's parent isn't left with inline nodes
+        if let Ok(DomNode::Container(quote)) =
+            self.state.dom.try_lookup_node_mut(handle)
+        {
+            let inline_count = quote
+                .children()
+                .iter()
+                .take_while(|c| !c.is_block_node())
+                .count();
+            if inline_count > 0 {
+                let inline_children =
+                    (0..inline_count).map(|_| quote.remove_child(0)).collect();
+                quote.insert_child(0, DomNode::new_paragraph(inline_children));
+            }
+        }
+        let quote = self.state.dom.lookup_container(handle);
+        if quote.children().len() > 1 {
+            let first_block = self.state.dom.remove(&handle.child_handle(0));
+            self.state.dom.insert_at(handle, first_block);
+        } else {
+            self.state.dom.remove_and_keep_children(handle);
+        }
+        self.create_update_replace_all()
+    }
+
+    pub(crate) fn add_quote(&mut self) -> ComposerUpdate<S> {
         let (s, e) = self.safe_selection();
         let Some(wrap_result) =
             self.state.dom.find_nodes_to_wrap_in_block(s, e)
@@ -42,8 +257,9 @@ where
             let node =
                 DomNode::new_quote(vec![DomNode::new_paragraph(Vec::new())]);
             if leaves.is_empty() {
-                if let Some(deepest_block_location) =
-                    range.deepest_block_node(None)
+                if let Some(deepest_block_location) = range
+                    .deepest_block_node(None)
+                    .filter(|l| !l.node_handle.is_root())
                 {
                     let mut block_node = self
                         .state
@@ -135,7 +351,7 @@ where
         let Some(quote_location) =
             range.locations.iter().find(|l| l.kind == Quote)
         else {
-            return ComposerUpdate::keep();
+            return ComposerUpdate::keep_at(self.state.revision);
         };
 
         self.state
@@ -149,6 +365,8 @@ where
 #[cfg(test)]
 mod test {
     use crate::tests::testutils_composer_model::{cm, tx};
+    use crate::tests::testutils_conversion::utf16;
+    use crate::{ComposerUpdate, Location, MenuState, QuoteAttribution};
 
     #[test]
     fn apply_quote_to_empty_dom() {
@@ -342,4 +560,297 @@ mod test {
             "<ul><li><blockquote><p>&nbsp;|</p></blockquote></li></ul>"
         )
     }
+
+    fn alice() -> QuoteAttribution {
+        QuoteAttribution {
+            author: "@alice:matrix.org".into(),
+            event_link: Some(
+                "https://matrix.to/#/!room:matrix.org/$event".into(),
+            ),
+        }
+    }
+
+    #[test]
+    fn quote_attribution_is_parsed_and_written_back() {
+        let model = cm(
+            "<blockquote data-mx-quote-author=\"@alice:matrix.org\"><p>a|</p></blockquote>",
+        );
+        assert_eq!(
+            model.get_quote_attribution(),
+            Some(QuoteAttribution {
+                author: "@alice:matrix.org".into(),
+                event_link: None,
+            })
+        );
+        assert_eq!(
+            model.get_content_as_message_html(),
+            "<blockquote data-mx-quote-author=\"@alice:matrix.org\">a</blockquote>"
+        );
+    }
+
+    #[test]
+    fn quote_attribution_is_escaped_in_html() {
+        let mut model = cm("<blockquote><p>a|</p></blockquote>");
+        model.set_quote_attribution(Some(QuoteAttribution {
+            author: "\"><b>x".into(),
+            event_link: None,
+        }));
+        assert_eq!(
+            tx(&model),
+            "<blockquote data-mx-quote-author=\"&quot;&gt;&lt;b&gt;x\"><p>a|</p></blockquote>"
+        );
+    }
+
+    #[test]
+    fn quote_attribution_can_be_removed() {
+        let mut model = cm(
+            "<blockquote data-mx-quote-author=\"@alice:matrix.org\"><p>a|</p></blockquote>",
+        );
+        model.set_quote_attribution(None);
+        assert_eq!(tx(&model), "<blockquote><p>a|</p></blockquote>");
+        assert_eq!(model.get_quote_attribution(), None);
+    }
+
+    #[test]
+    fn setting_quote_attribution_outside_a_quote_does_nothing() {
+        let mut model = cm("a|");
+        model.set_quote_attribution(Some(alice()));
+        assert_eq!(tx(&model), "a|");
+        assert_eq!(model.get_quote_attribution(), None);
+    }
+
+    #[test]
+    fn reply_quote_is_inserted_before_the_content() {
+        let mut model = cm("my re|ply");
+        model.insert_reply_quote(utf16("first\nsecond"), alice());
+        assert_eq!(
+            tx(&model),
+            "<blockquote data-mx-quote-author=\"@alice:matrix.org\" data-mx-quote-event=\"https://matrix.to/#/!room:matrix.org/$event\"><p>first</p><p>second</p></blockquote><p>|my reply</p>"
+        );
+        model.undo();
+        assert_eq!(tx(&model), "my re|ply");
+    }
+
+    #[test]
+    fn reply_quote_in_empty_model_is_followed_by_a_paragraph() {
+        let mut model = cm("|");
+        model.insert_reply_quote(utf16("quoted"), alice());
+        assert_eq!(model.get_quote_attribution(), None);
+        model.replace_text(utf16("reply"));
+        assert_eq!(
+            tx(&model),
+            "<blockquote data-mx-quote-author=\"@alice:matrix.org\" data-mx-quote-event=\"https://matrix.to/#/!room:matrix.org/$event\"><p>quoted</p></blockquote><p>reply|</p>"
+        );
+    }
+
+    #[test]
+    fn quote_attribution_is_kept_when_the_quote_is_split() {
+        let mut model = cm(
+            "<blockquote data-mx-quote-author=\"@alice:matrix.org\"><p>a</p><p>|</p><p>b</p></blockquote>",
+        );
+        model.enter();
+        assert_eq!(
+            tx(&model),
+            "<blockquote data-mx-quote-author=\"@alice:matrix.org\"><p>a</p></blockquote><p>&nbsp;|</p><blockquote data-mx-quote-author=\"@alice:matrix.org\"><p>b</p></blockquote>"
+        );
+    }
+
+    #[test]
+    fn quotes_with_different_attributions_are_not_merged() {
+        let mut model = cm(
+            "<blockquote data-mx-quote-author=\"@alice:matrix.org\"><p>a</p></blockquote><p>|</p><blockquote data-mx-quote-author=\"@bob:matrix.org\"><p>b</p></blockquote>",
+        );
+        model.backspace();
+        assert_eq!(
+            model.get_quote_attribution().unwrap().author,
+            "@alice:matrix.org"
+        );
+        assert!(tx(&model).contains("data-mx-quote-author=\"@bob:matrix.org\""));
+    }
+
+    #[test]
+    fn insert_quote_into_empty_model() {
+        let mut model = cm("|");
+        model.insert_quote(&utf16("<b>a</b> b")).unwrap();
+        assert_eq!(tx(&model), "<blockquote><p><b>a</b> b|</p></blockquote>");
+    }
+
+    #[test]
+    fn insert_quote_keeps_paragraphs() {
+        let mut model = cm("|");
+        model.insert_quote(&utf16("<p>a</p><p>b</p>")).unwrap();
+        assert_eq!(tx(&model), "<blockquote><p>a</p><p>b|</p></blockquote>");
+    }
+
+    #[test]
+    fn insert_quote_splits_the_paragraph_at_the_cursor() {
+        let mut model = cm("<p>ab|cd</p>");
+        model.insert_quote(&utf16("x")).unwrap();
+        assert_eq!(
+            tx(&model),
+            "<p>ab</p><blockquote><p>x|</p></blockquote><p>cd</p>"
+        );
+    }
+
+    #[test]
+    fn insert_quote_at_the_start_of_a_paragraph_goes_before_it() {
+        let mut model = cm("<p>|ab</p>");
+        model.insert_quote(&utf16("x")).unwrap();
+        assert_eq!(tx(&model), "<blockquote><p>x|</p></blockquote><p>ab</p>");
+    }
+
+    #[test]
+    fn insert_quote_replaces_the_selection() {
+        let mut model = cm("<p>a{b</p><p>c}|d</p>");
+        model.insert_quote(&utf16("x")).unwrap();
+        assert_eq!(
+            tx(&model),
+            "<p>a</p><blockquote><p>x|</p></blockquote><p>d</p>"
+        );
+    }
+
+    #[test]
+    fn insert_quote_in_a_list_item_stays_in_it() {
+        let mut model = cm("<ul><li>ab|</li></ul>");
+        model.insert_quote(&utf16("x")).unwrap();
+        assert_eq!(
+            tx(&model),
+            "<ul><li><p>ab</p><blockquote><p>x|</p></blockquote></li></ul>"
+        );
+    }
+
+    #[test]
+    fn insert_quote_is_one_undo_step() {
+        let mut model = cm("ab|");
+        model.insert_quote(&utf16("x")).unwrap();
+        model.undo();
+        assert_eq!(tx(&model), "ab|");
+    }
+
+    #[test]
+    fn insert_quote_drops_images_from_anywhere_but_mxc() {
+        let mut model = cm("|");
+        model
+            .insert_quote(&utf16(
+                "a<img data-mx-emoticon src=\"javascript:alert(1)\" alt=\":x:\" />\
+                <img src=\"data:image/png;base64,\" alt=\"cat\" />\
+                <img src=\"mxc://a/b\" alt=\"dog\" />",
+            ))
+            .unwrap();
+        assert_eq!(
+            tx(&model),
+            "<blockquote><p>a<img src=\"mxc://a/b\" alt=\"dog\" />|</p></blockquote>"
+        );
+    }
+
+    #[test]
+    fn increase_quote_level_outside_a_quote_adds_one() {
+        let mut model = cm("<p>ab|</p>");
+        model.increase_quote_level();
+        assert_eq!(tx(&model), "<blockquote><p>ab|</p></blockquote>");
+        assert_eq!(model.get_quote_level(), 1);
+    }
+
+    #[test]
+    fn increase_quote_level_nests_the_quote() {
+        let mut model = cm("<blockquote><p>ab|</p></blockquote>");
+        model.increase_quote_level();
+        assert_eq!(
+            tx(&model),
+            "<blockquote><blockquote><p>ab|</p></blockquote></blockquote>"
+        );
+        assert_eq!(model.get_quote_level(), 2);
+    }
+
+    #[test]
+    fn increase_quote_level_only_nests_the_selected_blocks() {
+        let mut model =
+            cm("<blockquote><p>a</p><p>{b</p><p>c}|</p><p>d</p></blockquote>");
+        model.increase_quote_level();
+        assert_eq!(
+            tx(&model),
+            "<blockquote><p>a</p><blockquote><p>{b</p><p>c}|</p></blockquote><p>d</p></blockquote>"
+        );
+    }
+
+    #[test]
+    fn increase_quote_level_nests_the_block_at_the_cursor() {
+        let mut model = cm("<blockquote><p>a</p><p>b|</p></blockquote>");
+        model.increase_quote_level();
+        assert_eq!(
+            tx(&model),
+            "<blockquote><p>a</p><blockquote><p>b|</p></blockquote></blockquote>"
+        );
+    }
+
+    #[test]
+    fn increase_quote_level_nests_a_whole_list() {
+        let mut model = cm("<blockquote><ul><li>a|</li></ul></blockquote>");
+        model.increase_quote_level();
+        assert_eq!(
+            tx(&model),
+            "<blockquote><blockquote><ul><li>a|</li></ul></blockquote></blockquote>"
+        );
+    }
+
+    #[test]
+    fn decrease_quote_level_removes_the_innermost_quote() {
+        let mut model = cm(
+            "<blockquote><p>a</p><blockquote><p>b|</p></blockquote></blockquote>",
+        );
+        model.decrease_quote_level();
+        assert_eq!(tx(&model), "<blockquote><p>a</p><p>b|</p></blockquote>");
+        assert_eq!(model.get_quote_level(), 1);
+        model.decrease_quote_level();
+        assert_eq!(tx(&model), "<p>a</p><p>b|</p>");
+        assert_eq!(model.get_quote_level(), 0);
+    }
+
+    #[test]
+    fn decrease_quote_level_outside_a_quote_does_nothing() {
+        let mut model = cm("<p>ab|</p>");
+        let update = model.decrease_quote_level();
+        assert_eq!(update, ComposerUpdate::keep_at(model.revision()));
+        assert_eq!(tx(&model), "<p>ab|</p>");
+    }
+
+    #[test]
+    fn changing_quote_level_can_be_undone() {
+        let mut model = cm("<blockquote><p>ab|</p></blockquote>");
+        model.increase_quote_level();
+        model.undo();
+        assert_eq!(tx(&model), "<blockquote><p>ab|</p></blockquote>");
+        model.decrease_quote_level();
+        model.undo();
+        assert_eq!(tx(&model), "<blockquote><p>ab|</p></blockquote>");
+    }
+
+    #[test]
+    fn menu_state_reports_the_quote_depth() {
+        let mut model = cm("<p>ab|</p>");
+        let update = model.increase_quote_level();
+        let MenuState::Update(menu_state) = update.menu_state else {
+            panic!("Expected a menu state update");
+        };
+        assert_eq!(menu_state.quote_depth, 1);
+
+        let update = model.increase_quote_level();
+        let MenuState::Update(menu_state) = update.menu_state else {
+            panic!("Expected a menu state update");
+        };
+        assert_eq!(menu_state.quote_depth, 2);
+    }
+
+    #[test]
+    fn menu_state_is_updated_when_moving_to_a_different_quote_depth() {
+        let mut model = cm(
+            "<blockquote><p>a|</p><blockquote><p>b</p></blockquote></blockquote>",
+        );
+        model.select(Location::from(0), Location::from(0));
+        let update = model.select(Location::from(3), Location::from(3));
+        let MenuState::Update(menu_state) = update.menu_state else {
+            panic!("Expected a menu state update");
+        };
+        assert_eq!(menu_state.quote_depth, 2);
+    }
 }
@@ -0,0 +1,76 @@
+// Copyright 2023 The Matrix.org Foundation C.I.C.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use std::cmp::min;
+
+use crate::dom::parser::{
+    parse_with_options, HtmlParseOptions, WhitespacePolicy,
+};
+use crate::dom::to_plain_text::ToPlainText;
+use crate::dom::Dom;
+use crate::{
+    ComposerError, ComposerModel, ComposerUpdate, DomNode, Location, ToHtml,
+    UnicodeString,
+};
+
+impl<S> ComposerModel<S>
+where
+    S: UnicodeString,
+{
+    /// If the content breaks one of the rules the model relies on, e.g.
+    /// because a bug left two text nodes next to each other, rebuild it
+    /// from its own HTML so that editing can carry on. The text is kept,
+    /// and the selection stays at the same positions as far as possible.
+    /// The violation is reported to the error listener.
+    ///
+    /// Does nothing if the content is consistent.
+    pub fn recover(&mut self) -> ComposerUpdate<S> {
+        let Some(violation) = self.state.dom.invariant_violation() else {
+            return ComposerUpdate::keep_at(self.state.revision);
+        };
+        self.report_error(&ComposerError::InvariantViolation(violation));
+
+        let options = HtmlParseOptions {
+            whitespace_policy: WhitespacePolicy::default(),
+            custom_inline_tags: self.custom_inline_tags.clone(),
+        };
+        let html = self.state.dom.to_html().to_string();
+        let parsed = parse_with_options(&html, &options).map(|mut dom| {
+            Self::post_process_dom(&mut dom);
+            dom
+        });
+        let dom = match parsed {
+            Ok(dom) if dom.invariant_violation().is_none() => dom,
+            // Fall back to the text alone
+            _ => {
+                let text = self.state.dom.to_plain_text();
+                let text = text.to_string();
+                let text = text.trim_end_matches('\n');
+                if text.is_empty() {
+                    Dom::new(Vec::new())
+                } else {
+                    Dom::new(vec![DomNode::new_text(S::from(text))])
+                }
+            }
+        };
+        self.state.dom = dom;
+
+        let len = self.state.dom.text_len();
+        let start: usize = self.state.start.into();
+        let end: usize = self.state.end.into();
+        self.state.start = Location::from(min(start, len));
+        self.state.end = Location::from(min(end, len));
+        self.create_update_replace_all_with_menu_state()
+    }
+}
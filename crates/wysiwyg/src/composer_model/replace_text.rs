@@ -12,16 +12,30 @@
 // See the License for the specific language governing permissions and
 // limitations under the License.
 
+use crate::composer_model::cursor_movement::Step;
+use crate::composer_model::delete_text::Direction;
 use crate::dom::nodes::dom_node::DomNodeKind;
 use crate::dom::nodes::DomNode;
-use crate::dom::unicode_string::UnicodeStrExt;
+use crate::dom::parser::parse;
+use crate::dom::to_plain_text::ToPlainText;
+use crate::dom::unicode_string::{UnicodeStr, UnicodeStrExt};
 use crate::dom::{DomLocation, Range};
+use crate::emoji::normalize_emoji;
 use crate::{
-    ComposerModel, ComposerUpdate, DomHandle, Location, SuggestionPattern,
-    UnicodeString,
+    ComposerError, ComposerModel, ComposerUpdate, DomHandle, ListType,
+    Location, SuggestionPattern, UnicodeString,
 };
+use once_cell::sync::Lazy;
+use regex::Regex;
+use std::borrow::Cow;
 use std::cmp::min;
 
+/// A complete opening, closing or self-closing HTML tag, e.g. `<b>`,
+/// `</p>` or `<br />`.
+static HTML_TAG: Lazy<Regex> = Lazy::new(|| {
+    Regex::new(r#"</?[a-zA-Z][a-zA-Z0-9-]*(\s[^<>]*)?/?>"#).unwrap()
+});
+
 impl<S> ComposerModel<S>
 where
     S: UnicodeString,
@@ -31,7 +45,35 @@ where
     /// the document (i.e. it will be escaped).
     pub fn replace_text(&mut self, new_text: S) -> ComposerUpdate<S> {
         self.push_state_to_history();
-        self.do_replace_text(new_text)
+        if let Err(error) = self.replace_text_without_update(new_text) {
+            return self.recover_from(error);
+        }
+        self.link_word_before_cursor();
+        self.create_update_replace_all()
+    }
+
+    /// Replaces the selection with the text of `content`, dropping any
+    /// formatting, like pasting with Ctrl+Shift+V. `content` may be HTML
+    /// (e.g. from a clipboard) or plain text. Paragraphs and line breaks
+    /// in HTML become new lines.
+    pub fn insert_text_without_formatting(
+        &mut self,
+        content: S,
+    ) -> ComposerUpdate<S> {
+        self.replace_text(strip_markup(content))
+    }
+
+    /// Replaces the selection with `emoji`, e.g. one picked from an emoji
+    /// picker. Pending formats are applied to it, it is one undo step and
+    /// the cursor ends up after it. Nothing happens unless `emoji` is a
+    /// single grapheme, so it can never be split by the cursor.
+    pub fn insert_emoji(&mut self, emoji: S) -> ComposerUpdate<S> {
+        let emoji = normalize_emoji(&emoji.to_string()).into_owned();
+        if emoji.grapheme_count() != 1 {
+            return ComposerUpdate::keep_at(self.state.revision);
+        }
+        self.push_state_to_history();
+        self.do_replace_text(S::from(emoji))
     }
 
     /// Replaces text in the an arbitrary start..end range with new_text.
@@ -42,25 +84,68 @@ where
         end: usize,
     ) -> ComposerUpdate<S> {
         self.push_state_to_history();
-        self.do_replace_text_in(new_text, start, end)
+        if let Err(error) =
+            self.replace_text_in_without_update(new_text, start, end)
+        {
+            return self.recover_from(error);
+        }
+        self.link_word_before_cursor();
+        self.create_update_replace_all()
     }
 
+    /// Replace the text of `suggestion` with `new_text`, adding a space
+    /// after it if `append_space`. Nothing happens if the content changed
+    /// after the suggestion was sent.
     pub fn replace_text_suggestion(
         &mut self,
         new_text: S,
         suggestion: SuggestionPattern,
         append_space: bool,
     ) -> ComposerUpdate<S> {
+        if self.suggestion_is_out_of_date(&suggestion) {
+            return ComposerUpdate::keep_at(self.state.revision);
+        }
         self.push_state_to_history();
-        let replace_suggestion_update =
-            self.do_replace_text_in(new_text, suggestion.start, suggestion.end);
-        if append_space {
-            self.do_replace_text(" ".into())
-        } else {
-            replace_suggestion_update
+        let kept_len = self.kept_suggestion_suffix_len(&suggestion);
+        let mut result = self.replace_text_in_without_update(
+            new_text,
+            suggestion.start,
+            suggestion.end - kept_len,
+        );
+        if kept_len > 0 {
+            // Move the cursor after the kept punctuation
+            self.state.start += kept_len as isize;
+            self.state.end = self.state.start;
+        }
+        if append_space && result.is_ok() {
+            result = self.replace_text_without_update(" ".into());
+        }
+        match result {
+            Ok(()) => self.create_update_replace_all(),
+            Err(error) => self.recover_from(error),
         }
     }
 
+    /// The length of the punctuation at the end of the suggestion that
+    /// should survive its replacement, in code units. Always 0 unless
+    /// `set_keep_suggestion_punctuation` was enabled.
+    pub(crate) fn kept_suggestion_suffix_len(
+        &self,
+        suggestion: &SuggestionPattern,
+    ) -> usize {
+        if !self.keep_suggestion_punctuation {
+            return 0;
+        }
+        let text = S::from(suggestion.text.as_str());
+        let chars: Vec<char> = text.chars().collect();
+        chars
+            .iter()
+            .rev()
+            .take_while(|c| is_punctuation_after_name(**c))
+            .map(|c| text.char_len(c))
+            .sum()
+    }
+
     #[deprecated(since = "0.20.0")]
     pub fn add_line_break(&mut self) -> ComposerUpdate<S> {
         self.push_state_to_history();
@@ -75,7 +160,11 @@ where
             self.enter_with_zero_length_selection(range)
         } else {
             // Clear selection then enter.
-            self.do_replace_text_in("".into(), s, e);
+            if let Err(error) =
+                self.replace_text_in_without_update("".into(), s, e)
+            {
+                return self.recover_from(error);
+            }
             self.do_add_line_break()
         }
     }
@@ -123,7 +212,6 @@ where
         self.do_find_closest_ancestor_of_kind(handle, kind, false)
     }
 
-    #[allow(dead_code)]
     pub(crate) fn find_closest_ancestor_of_kind_or_self(
         &self,
         handle: &DomHandle,
@@ -170,7 +258,7 @@ where
                 if let DomNode::Text(t) =
                     self.state.dom.lookup_node(&l.node_handle)
                 {
-                    t.data().is_empty()
+                    t.text_len() == 0
                 } else {
                     false
                 }
@@ -202,7 +290,55 @@ where
         start: usize,
         end: usize,
     ) -> ComposerUpdate<S> {
+        match self.replace_text_in_without_update(new_text, start, end) {
+            // TODO: for now, we replace every time, to check ourselves, but
+            // at least some of the time we should not
+            Ok(()) => self.create_update_replace_all(),
+            Err(error) => self.recover_from(error),
+        }
+    }
+
+    /// Like [Self::do_replace_text], but without creating an update, for
+    /// edits which are only one step of an operation. The operation
+    /// creates one update at the end, so the menu state is only computed
+    /// once.
+    pub(crate) fn replace_text_without_update(
+        &mut self,
+        new_text: S,
+    ) -> Result<(), ComposerError> {
+        let (s, e) = self.safe_selection();
+        self.replace_text_in_without_update(new_text, s, e)
+    }
+
+    /// Like [Self::do_replace_text_in], but without creating an update. If
+    /// this fails, the content may be left half changed, so the caller
+    /// should recover with [Self::recover_from].
+    pub(crate) fn replace_text_in_without_update(
+        &mut self,
+        new_text: S,
+        start: usize,
+        end: usize,
+    ) -> Result<(), ComposerError> {
         let text_string = new_text.to_string();
+        let normalized = match normalize_emoji(&text_string) {
+            Cow::Owned(normalized) => Some(normalized),
+            Cow::Borrowed(_) => None,
+        };
+        let (new_text, text_string) = match normalized {
+            Some(normalized) => (S::from(normalized.as_str()), normalized),
+            None => (new_text, text_string),
+        };
+        // Text typed on a horizontal rule goes on a new line after it
+        if start == end
+            && !new_text.is_empty()
+            && self.move_off_horizontal_rule_at(start)
+        {
+            return self.replace_text_in_without_update(
+                new_text,
+                start + 1,
+                start + 1,
+            );
+        }
         // If passed start, end don't match the model's state, we can't fix them
         let (s, e) = self.safe_selection();
         let needs_to_recalculate_selection = s == start && e == end;
@@ -210,19 +346,88 @@ where
         // insert each slice while simulating calls to the
         // enter function in betweeen.
         if text_string.contains('\n') {
-            let mut slices = text_string.split('\n').peekable();
+            let is_blank =
+                |slice: &&str| slice.is_empty() || *slice == "\u{A0}";
+            let mut slices: Vec<&str> = text_string.split('\n').collect();
+            let list_item = self.block_at(s).and_then(|b| {
+                self.state.dom.find_ancestor_list_item_or_self(&b)
+            });
+            if list_item.is_some() {
+                // Pressing enter in an empty list item leaves the list, so
+                // blank lines are dropped. A trailing newline still starts
+                // a new list item.
+                let ends_with_newline = slices.last().is_some_and(is_blank);
+                slices.retain(|slice| !is_blank(slice));
+                if ends_with_newline {
+                    if slices.is_empty() {
+                        slices.push("");
+                    }
+                    slices.push("");
+                }
+            }
+            let list_len = |model: &Self| {
+                list_item.as_ref().map_or(0, |h| {
+                    model
+                        .state
+                        .dom
+                        .try_parent(h)
+                        .map_or(0, |l| l.children().len())
+                })
+            };
+            let convert_bullets = self.convert_pasted_bullets_to_lists
+                && list_item.is_none()
+                && !self
+                    .state
+                    .dom
+                    .find_range(s, e)
+                    .locations
+                    .iter()
+                    .any(|l| l.kind == DomNodeKind::CodeBlock);
+            let mut at_line_start = convert_bullets
+                && matches!(
+                    self.cursor_step(s, &Direction::Backwards),
+                    None | Some((_, Step::Boundary))
+                );
+            // The list type, start and end of each line that had a bullet
+            let mut bullet_lines = Vec::new();
+            let mut added_items = 0;
+            let mut slices = slices.into_iter().peekable();
             while let Some(slice) = slices.next() {
                 let (s, e) = self.safe_selection();
-                if !slice.is_empty() && slice != "\u{A0}" {
-                    self.do_replace_text_in(S::from(slice), s, e);
+                let bullet = bullet_list_type(slice)
+                    .filter(|_| convert_bullets && at_line_start);
+                let slice =
+                    bullet.as_ref().map_or(slice, |(_, len)| &slice[*len..]);
+                if !is_blank(&slice) {
+                    self.replace_text_in_without_update(S::from(slice), s, e)?;
+                }
+                if let Some((list_type, _)) = bullet {
+                    let (end, _) = self.safe_selection();
+                    bullet_lines.push((list_type, s, end));
                 }
                 if slices.peek().is_some() {
-                    self.do_enter();
+                    let len_before = list_len(self);
+                    self.enter_without_update()?;
+                    added_items += list_len(self).saturating_sub(len_before);
+                }
+                at_line_start = true;
+            }
+            self.wrap_bullet_lines_in_lists(bullet_lines);
+            if let Some(list_item) = list_item {
+                if !self.split_lines_into_list_items && added_items > 0 {
+                    self.state
+                        .dom
+                        .join_list_items_as_paragraphs(&list_item, added_items);
                 }
             }
         } else {
             let len = new_text.len();
-            self.state.dom.replace_text_in(new_text, start, end);
+            let extended_link = self.extend_links_at_edges
+                && start == end
+                && self.insert_text_into_link_at_edge(&new_text, start);
+            if !extended_link {
+                self.state.dom.replace_text_in(new_text, start, end)?;
+            }
             self.apply_pending_formats(start, start + len);
             let start = if needs_to_recalculate_selection {
                 let (new_start, _) = self.safe_selection();
@@ -234,10 +439,75 @@ where
                 Location::from(min(start + len, self.state.dom.text_len()));
             self.state.end = self.state.start;
         }
+        Ok(())
+    }
+}
 
-        // TODO: for now, we replace every time, to check ourselves, but
-        // at least some of the time we should not
-        self.create_update_replace_all()
+impl<S> ComposerModel<S>
+where
+    S: UnicodeString,
+{
+    /// Put each run of consecutive lines with the same type of bullet into
+    /// a list. Lines are given as their list type, start and end.
+    fn wrap_bullet_lines_in_lists(
+        &mut self,
+        bullet_lines: Vec<(ListType, usize, usize)>,
+    ) {
+        let mut runs: Vec<(ListType, usize, usize)> = Vec::new();
+        for (list_type, start, end) in bullet_lines {
+            match runs.last_mut() {
+                // Lines in the same run are only a block boundary apart
+                Some((run_type, _, run_end))
+                    if *run_type == list_type && *run_end + 1 == start =>
+                {
+                    *run_end = end;
+                }
+                _ => runs.push((list_type, start, end)),
+            }
+        }
+        // Wrap the last run first, so the positions of the others stay valid
+        for (list_type, start, end) in runs.into_iter().rev() {
+            self.wrap_range_in_list(list_type, start, end);
+        }
+    }
+}
+
+/// The list type a line of plain text starts with and the length of its
+/// bullet, e.g. 2 for `- ` or 4 for `12. `, or None if it has no bullet.
+fn bullet_list_type(line: &str) -> Option<(ListType, usize)> {
+    if line.starts_with("- ") || line.starts_with("* ") {
+        return Some((ListType::Unordered, 2));
+    }
+    let digits = line.chars().take_while(char::is_ascii_digit).count();
+    if digits > 0 && line[digits..].starts_with(". ") {
+        Some((ListType::Ordered, digits + 2))
+    } else {
+        None
+    }
+}
+
+/// Punctuation that commonly follows a name in a sentence, e.g.
+/// "@alice, hi" or "ask #room!".
+fn is_punctuation_after_name(c: char) -> bool {
+    matches!(
+        c,
+        ',' | '.' | ';' | ':' | '!' | '?' | ')' | ']' | '}' | '"' | '\'' | '…'
+    )
+}
+
+/// The text of `content` if it contains HTML tags, or `content` itself if
+/// it is plain text.
+fn strip_markup<S: UnicodeString>(content: S) -> S {
+    let html = content.to_string();
+    if !HTML_TAG.is_match(&html) {
+        return content;
+    }
+    match parse::<S>(&html) {
+        Ok(dom) => {
+            let text = dom.to_plain_text().to_string();
+            S::from(text.trim_end_matches('\n'))
+        }
+        Err(_) => content,
     }
 }
 
@@ -245,9 +515,9 @@ where
 mod test {
     use std::collections::HashMap;
 
-    use widestring::Utf16String;
+    use crate::tests::testutils_composer_model::TestString;
 
-    use crate::action_state::ActionState;
+    use crate::action_state::{ActionState, DisabledReason};
     use crate::link_action::LinkActionUpdate;
     use crate::menu_state::MenuStateUpdate;
     use crate::tests::testutils_composer_model::cm;
@@ -261,18 +531,35 @@ mod test {
     #[test]
     fn composer_update_contains_escaped_html() {
         let mut model = cm("|");
-        let update = model.replace_text(Utf16String::from_str("<"));
+        let update = model.replace_text(TestString::from_str("<"));
         assert_eq!(
             update,
             ComposerUpdate::replace_all(
                 utf16("&lt;"),
                 Location::from(1),
                 Location::from(1),
+                (Location::from(0), Location::from(1)),
                 MenuState::Update(MenuStateUpdate {
-                    action_states: indent_unindent_redo_disabled()
+                    action_states: indent_unindent_redo_disabled(),
+                    disabled_reasons: HashMap::from([
+                        (ComposerAction::Redo, DisabledReason::HistoryEmpty),
+                        (ComposerAction::Indent, DisabledReason::CannotIndent),
+                        (
+                            ComposerAction::Unindent,
+                            DisabledReason::CannotUnindent
+                        ),
+                        (
+                            ComposerAction::EditLink,
+                            DisabledReason::NotApplicable
+                        ),
+                    ]),
+                    undo_depth: 1,
+                    redo_depth: 0,
+                    quote_depth: 0,
                 }),
                 MenuAction::None,
                 LinkActionUpdate::Update(LinkAction::CreateWithText),
+                1,
             ),
         );
     }
@@ -284,6 +571,7 @@ mod test {
                 ComposerAction::Redo
                     | ComposerAction::Indent
                     | ComposerAction::Unindent
+                    | ComposerAction::EditLink
             ) {
                 (action, ActionState::Disabled)
             } else {
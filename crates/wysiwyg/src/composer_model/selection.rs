@@ -12,7 +12,15 @@
 // See the License for the specific language governing permissions and
 // limitations under the License.
 
-use crate::{ComposerModel, ComposerUpdate, Location, UnicodeString};
+use crate::composer_model::linkify::find_links_in_text;
+use crate::dom::nodes::dom_node::DomNodeKind;
+use crate::dom::unicode_string::{UnicodeStr, UnicodeStrExt};
+use crate::dom::DomHandle;
+use crate::{
+    ComposerModel, ComposerUpdate, Locale, Location, SelectionStats,
+    ToMarkdown, UnicodeString,
+};
+use unicode_segmentation::UnicodeSegmentation;
 
 impl<S> ComposerModel<S>
 where
@@ -25,14 +33,28 @@ where
         start: Location,
         end: Location,
     ) -> ComposerUpdate<S> {
+        if self.select_without_update(start, end) {
+            self.create_update_update_selection()
+        } else {
+            ComposerUpdate::keep_at(self.state.revision)
+        }
+    }
+
+    /// Like [Self::select], but without creating an update, for when the
+    /// selection changes as one step of an operation. Returns false if the
+    /// selection was already there.
+    pub(crate) fn select_without_update(
+        &mut self,
+        start: Location,
+        end: Location,
+    ) -> bool {
         if self.state.start == start && self.state.end == end {
-            return ComposerUpdate::keep();
+            return false;
         }
         self.state.toggled_format_types.clear();
         self.state.start = start;
         self.state.end = end;
-
-        self.create_update_update_selection()
+        true
     }
 
     /// Return the start and end of the selection, ensuring the first number
@@ -71,6 +93,165 @@ where
         let (s, e) = self.safe_selection();
         s == e
     }
+
+    /// The range to select when the user double-clicks at `offset`. This is
+    /// usually the word there, which may contain hyphens or (depending on
+    /// the locale) apostrophes. A link, a URL or a mention is selected
+    /// whole, and a run of whitespace or punctuation is selected on its own.
+    pub fn word_range_at(&self, offset: Location) -> (Location, Location) {
+        let (offset, _) = self.safe_locations_from(offset, offset);
+        let range = self.state.dom.find_range(offset, offset);
+        let whole = |position: usize, length: usize| {
+            (Location::from(position), Location::from(position + length))
+        };
+        if let Some(link) = range
+            .locations
+            .iter()
+            .find(|l| l.kind == DomNodeKind::Link && l.start_offset < l.length)
+        {
+            return whole(link.position, link.length);
+        }
+        let Some(leaf) = range
+            .leaves()
+            .find(|l| l.start_offset < l.length)
+            .or_else(|| range.leaves().last())
+        else {
+            return whole(offset, 0);
+        };
+        if leaf.kind != DomNodeKind::Text {
+            return whole(leaf.position, leaf.length);
+        }
+        let text = self
+            .state
+            .dom
+            .lookup_node(&leaf.node_handle)
+            .as_text()
+            .unwrap()
+            .data();
+        if let Some((start, end, _)) = find_links_in_text(text, false)
+            .into_iter()
+            .find(|(s, e, _)| (*s..*e).contains(&leaf.start_offset))
+        {
+            return whole(leaf.position + start, end - start);
+        }
+        let (start, end) = word_range_in(text, leaf.start_offset, &self.locale);
+        whole(leaf.position + start, end - start)
+    }
+
+    /// The selected content as Markdown, e.g. to quote it in a reply.
+    /// Empty if nothing is selected.
+    pub fn get_selection_as_markdown(&self) -> S {
+        let (s, e) = self.safe_selection();
+        if s == e {
+            return S::default();
+        }
+        match self.dom_between(s, e) {
+            Ok(dom) => dom.to_markdown().unwrap(),
+            Err(error) => {
+                self.report_error(&error);
+                S::default()
+            }
+        }
+    }
+
+    /// The selected content as plain text, without zero-width spaces.
+    /// Empty if nothing is selected.
+    pub fn get_selection_as_plain_text(&self) -> S {
+        self.get_clipboard_content(self.state.start, self.state.end)
+            .plain_text
+    }
+
+    /// How many characters and words are selected, and whether the
+    /// selection is in more than one block.
+    pub fn selection_stats(&self) -> SelectionStats {
+        let (s, e) = self.safe_selection();
+        if s == e {
+            return SelectionStats::default();
+        }
+        let text = self.get_selection_as_plain_text().to_string();
+        SelectionStats {
+            character_count: text.grapheme_count(),
+            word_count: text.unicode_words().count(),
+            spans_multiple_blocks: self.block_at(s) != self.block_at(e),
+        }
+    }
+
+    /// The closest block node containing the leaf at `pos`, preferring the
+    /// one starting there, or the deepest block there if it is empty.
+    pub(crate) fn block_at(&self, pos: usize) -> Option<DomHandle> {
+        let range = self.state.dom.find_range(pos, pos);
+        let leaf = range
+            .leaves()
+            .find(|l| l.start_offset < l.length)
+            .or_else(|| range.leaves().last());
+        if let Some(leaf) = leaf {
+            return Some(self.block_containing(&leaf.node_handle));
+        }
+        range
+            .locations
+            .iter()
+            .filter(|l| {
+                l.kind.is_block_kind()
+                    && (l.start_offset < l.length || l.position == pos)
+            })
+            .max_by_key(|l| l.node_handle.depth())
+            .map(|l| l.node_handle.clone())
+    }
+}
+
+/// The (start, end) in code units of the word, whitespace or punctuation
+/// containing the character at `offset` in `text`, or the last character if
+/// `offset` is at the end.
+fn word_range_in<T>(text: &T, offset: usize, locale: &Locale) -> (usize, usize)
+where
+    T: UnicodeStr + ?Sized,
+{
+    let mut chars = Vec::new();
+    let mut pos = 0;
+    for c in text.chars() {
+        chars.push((pos, c));
+        pos += text.char_len(&c);
+    }
+    let Some(index) = chars.iter().rposition(|(start, _)| *start <= offset)
+    else {
+        return (offset, offset);
+    };
+
+    let is_alphanumeric =
+        |i: usize| chars.get(i).map_or(false, |(_, c)| c.is_alphanumeric());
+    let is_word_char = |i: usize| {
+        let c = chars[i].1;
+        if c.is_alphanumeric() || c == '_' {
+            true
+        } else if c == '-' || locale.joins_words(c) {
+            i > 0 && is_alphanumeric(i - 1) && is_alphanumeric(i + 1)
+        } else {
+            false
+        }
+    };
+    let class = |i: usize| {
+        if is_word_char(i) {
+            0
+        } else if chars[i].1.is_whitespace() {
+            1
+        } else {
+            2
+        }
+    };
+
+    let target = class(index);
+    let mut first = index;
+    while first > 0 && class(first - 1) == target {
+        first -= 1;
+    }
+    let mut last = index;
+    while last + 1 < chars.len() && class(last + 1) == target {
+        last += 1;
+    }
+    (
+        chars[first].0,
+        chars[last].0 + text.char_len(&chars[last].1),
+    )
 }
 
 #[cfg(test)]
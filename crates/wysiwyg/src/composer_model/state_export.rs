@@ -0,0 +1,194 @@
+// Copyright 2023 The Matrix.org Foundation C.I.C.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Exporting the content, selection and history of a model as bytes, and
+//! importing them into another model, e.g. one in a web worker.
+
+use serde_json::{json, Value};
+
+use crate::dom::parser::{
+    parse_with_options, HtmlParseOptions, WhitespacePolicy,
+};
+use crate::{
+    ComposerModel, ComposerState, ComposerUpdate, DomCreationError,
+    InlineFormatType, Location, StateImportError, ToHtml, UnicodeString,
+};
+
+/// Bumped whenever the exported format changes in a way older versions
+/// can't read.
+const STATE_VERSION: u64 = 1;
+
+impl<S> ComposerModel<S>
+where
+    S: UnicodeString,
+{
+    /// The content, selection and undo history of the model as bytes, to be
+    /// passed to [Self::import_state]. Settings such as custom suggestion
+    /// patterns and the locale are not included.
+    pub fn export_state(&self) -> Vec<u8> {
+        let state_to_json = |state: &ComposerState<S>| {
+            let toggled_formats: Vec<String> = state
+                .toggled_format_types
+                .iter()
+                .map(tag_for_format)
+                .collect();
+            json!({
+                "html": state.dom.to_html().to_string(),
+                "start": usize::from(state.start),
+                "end": usize::from(state.end),
+                "toggled_formats": toggled_formats,
+            })
+        };
+        json!({
+            "version": STATE_VERSION,
+            "state": state_to_json(&self.state),
+            "previous_states": self
+                .previous_states
+                .iter()
+                .map(state_to_json)
+                .collect::<Vec<_>>(),
+            "next_states": self
+                .next_states
+                .iter()
+                .map(state_to_json)
+                .collect::<Vec<_>>(),
+        })
+        .to_string()
+        .into_bytes()
+    }
+
+    /// Replace the content, selection and undo history with those exported
+    /// by [Self::export_state], keeping the settings of this model. Toggled
+    /// formats this model doesn't allow are dropped, and a selection past
+    /// the end of the content is moved to the end. Nothing changes if the
+    /// state can't be imported.
+    pub fn import_state(
+        &mut self,
+        bytes: &[u8],
+    ) -> Result<ComposerUpdate<S>, StateImportError> {
+        let exported: Value = serde_json::from_slice(bytes)
+            .map_err(|e| StateImportError::InvalidFormat(e.to_string()))?;
+        if exported["version"].as_u64() != Some(STATE_VERSION) {
+            return Err(StateImportError::InvalidFormat(
+                "unsupported version".to_owned(),
+            ));
+        }
+        let state = self.state_from_json(&exported["state"])?;
+        let previous_states =
+            self.states_from_json(&exported["previous_states"])?;
+        let next_states = self.states_from_json(&exported["next_states"])?;
+
+        self.restore_state(state);
+        self.previous_states = previous_states;
+        self.next_states = next_states;
+        self.last_automatic_action = None;
+        self.dismissed_suggestion = None;
+        Ok(self.create_update_replace_all_with_menu_state())
+    }
+
+    fn states_from_json(
+        &self,
+        json: &Value,
+    ) -> Result<Vec<ComposerState<S>>, StateImportError> {
+        json.as_array()
+            .ok_or_else(|| invalid_format("expected a list of states"))?
+            .iter()
+            .map(|state| self.state_from_json(state))
+            .collect()
+    }
+
+    fn state_from_json(
+        &self,
+        json: &Value,
+    ) -> Result<ComposerState<S>, StateImportError> {
+        let (Some(html), Some(start), Some(end), Some(toggled_formats)) = (
+            json["html"].as_str(),
+            json["start"].as_u64(),
+            json["end"].as_u64(),
+            json["toggled_formats"].as_array(),
+        ) else {
+            return Err(invalid_format("expected a state"));
+        };
+        let options = HtmlParseOptions {
+            whitespace_policy: WhitespacePolicy::default(),
+            custom_inline_tags: self.custom_inline_tags.clone(),
+        };
+        let mut dom = parse_with_options(html, &options).map_err(|e| {
+            StateImportError::InvalidContent(DomCreationError::HtmlParseError(
+                e,
+            ))
+        })?;
+        Self::post_process_dom(&mut dom);
+        let mut toggled_format_types = Vec::new();
+        for tag in toggled_formats {
+            let format = tag
+                .as_str()
+                .map(format_for_tag)
+                .ok_or_else(|| invalid_format("expected a format tag"))?;
+            // Dropped like any other format this model wouldn't apply, e.g.
+            // a custom tag it doesn't allow or a colour that isn't valid
+            if self.is_allowed_format(&format) {
+                toggled_format_types.push(format);
+            }
+        }
+        let len = dom.text_len();
+        Ok(ComposerState {
+            dom,
+            start: Location::from((start as usize).min(len)),
+            end: Location::from((end as usize).min(len)),
+            toggled_format_types,
+            revision: 0,
+        })
+    }
+}
+
+fn invalid_format(message: &str) -> StateImportError {
+    StateImportError::InvalidFormat(message.to_owned())
+}
+
+/// How a toggled format is exported: its tag as returned by
+/// [InlineFormatType::tag], or the name of its attribute, `:` and the
+/// colour for a colour, which has no tag of its own.
+fn tag_for_format(format: &InlineFormatType) -> String {
+    match format {
+        InlineFormatType::TextColor(color) => {
+            format!("{TEXT_COLOR_PREFIX}{color}")
+        }
+        InlineFormatType::Highlight(color) => {
+            format!("{HIGHLIGHT_PREFIX}{color}")
+        }
+        format => format.tag().to_owned(),
+    }
+}
+
+const TEXT_COLOR_PREFIX: &str = "data-mx-color:";
+const HIGHLIGHT_PREFIX: &str = "data-mx-bg-color:";
+
+/// The format with the given tag, as returned by [tag_for_format].
+fn format_for_tag(tag: &str) -> InlineFormatType {
+    if let Some(color) = tag.strip_prefix(TEXT_COLOR_PREFIX) {
+        return InlineFormatType::TextColor(color.to_owned());
+    }
+    if let Some(color) = tag.strip_prefix(HIGHLIGHT_PREFIX) {
+        return InlineFormatType::Highlight(color.to_owned());
+    }
+    match tag {
+        "strong" => InlineFormatType::Bold,
+        "em" => InlineFormatType::Italic,
+        "del" => InlineFormatType::StrikeThrough,
+        "u" => InlineFormatType::Underline,
+        "code" => InlineFormatType::InlineCode,
+        tag => InlineFormatType::Custom(tag.to_owned()),
+    }
+}
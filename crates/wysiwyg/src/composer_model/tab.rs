@@ -0,0 +1,195 @@
+// Copyright 2023 The Matrix.org Foundation C.I.C.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use crate::dom::nodes::dom_node::DomNodeKind::{CodeBlock, ListItem};
+use crate::dom::nodes::DomNode;
+use crate::{
+    ComposerModel, ComposerUpdate, DomHandle, Location, UnicodeString,
+};
+
+/// How many spaces Shift+Tab removes from the start of a line in a code
+/// block when it doesn't start with a tab.
+const UNINDENT_SPACES: usize = 4;
+
+impl<S> ComposerModel<S>
+where
+    S: UnicodeString,
+{
+    /// Handle the Tab key. Inside a list this indents the selected list
+    /// items, and inside a code block it inserts a tab, or indents every
+    /// selected line if the selection covers several. Does nothing anywhere else,
+    /// so hosts can forward the key without checking where the cursor is.
+    pub fn handle_tab(&mut self) -> ComposerUpdate<S> {
+        match self.tab_target() {
+            Some(TabTarget::CodeBlock(code_block)) => {
+                let (s, e) = self.safe_selection();
+                if self.block_at(s) == self.block_at(e) {
+                    self.replace_text(S::from("\t"))
+                } else {
+                    self.indent_code_lines(&code_block)
+                }
+            }
+            Some(TabTarget::List) => self.indent(),
+            None => ComposerUpdate::keep_at(self.state.revision),
+        }
+    }
+
+    /// Handle Shift+Tab. Inside a list this unindents the selected list
+    /// items, and inside a code block it removes a tab, or up to four
+    /// spaces, from the start of every selected line. Does nothing anywhere
+    /// else.
+    pub fn handle_shift_tab(&mut self) -> ComposerUpdate<S> {
+        match self.tab_target() {
+            Some(TabTarget::CodeBlock(code_block)) => {
+                self.unindent_code_lines(&code_block)
+            }
+            Some(TabTarget::List) => self.unindent(),
+            None => ComposerUpdate::keep_at(self.state.revision),
+        }
+    }
+
+    /// The closest code block or list item around the start of the
+    /// selection.
+    fn tab_target(&self) -> Option<TabTarget> {
+        let (s, _) = self.safe_selection();
+        let block = self.block_at(s)?;
+        block.with_ancestors().into_iter().rev().find_map(|handle| {
+            match self.state.dom.lookup_node(&handle).kind() {
+                CodeBlock => Some(TabTarget::CodeBlock(handle)),
+                ListItem => Some(TabTarget::List),
+                _ => None,
+            }
+        })
+    }
+
+    /// The handles of the lines of `code_block` touched by the selection,
+    /// with the positions where the first and last of them start.
+    fn selected_code_lines(
+        &self,
+        code_block: &DomHandle,
+    ) -> (Vec<DomHandle>, usize, usize) {
+        let (s, e) = self.safe_selection();
+        let line_index = |pos: usize| {
+            self.block_at(pos)
+                .filter(|b| b.parent_handle() == *code_block)
+                .map(|b| b.index_in_parent())
+        };
+        let count =
+            self.state.dom.lookup_container(code_block).children().len();
+        let first = line_index(s).unwrap_or(0);
+        let last = line_index(e).unwrap_or(count - 1);
+        let lines: Vec<DomHandle> =
+            (first..=last).map(|i| code_block.child_handle(i)).collect();
+        let range = self.state.dom.find_range(s, e);
+        let line_start = |handle: &DomHandle, default: usize| {
+            range
+                .locations
+                .iter()
+                .find(|l| l.node_handle == *handle)
+                .map_or(default, |l| l.position)
+        };
+        let first_start = line_start(&lines[0], s);
+        let last_start = line_start(&lines[lines.len() - 1], e);
+        (lines, first_start, last_start)
+    }
+
+    fn indent_code_lines(
+        &mut self,
+        code_block: &DomHandle,
+    ) -> ComposerUpdate<S> {
+        let (lines, _, _) = self.selected_code_lines(code_block);
+        let (s, e) = self.safe_selection();
+        self.push_state_to_history();
+        for line in &lines {
+            let Ok(DomNode::Container(paragraph)) =
+                self.state.dom.try_lookup_node_mut(line)
+            else {
+                continue;
+            };
+            if let Some(DomNode::Text(text)) = paragraph.get_child_mut(0) {
+                text.replace_range(S::from("\t"), 0, 0);
+            } else {
+                paragraph.insert_child(0, DomNode::new_text(S::from("\t")));
+            }
+        }
+        self.state.start = Location::from(s + 1);
+        self.state.end = Location::from(e + lines.len());
+        self.create_update_replace_all()
+    }
+
+    fn unindent_code_lines(
+        &mut self,
+        code_block: &DomHandle,
+    ) -> ComposerUpdate<S> {
+        let (lines, first_start, last_start) =
+            self.selected_code_lines(code_block);
+        let removed: Vec<usize> = lines
+            .iter()
+            .map(|line| self.leading_indentation(line))
+            .collect();
+        if removed.iter().all(|r| *r == 0) {
+            return ComposerUpdate::keep_at(self.state.revision);
+        }
+        let (s, e) = self.safe_selection();
+        self.push_state_to_history();
+        for (line, len) in lines.iter().zip(&removed) {
+            if *len == 0 {
+                continue;
+            }
+            let text_handle = line.child_handle(0);
+            let Ok(DomNode::Text(text)) =
+                self.state.dom.try_lookup_node_mut(&text_handle)
+            else {
+                continue;
+            };
+            text.replace_range(S::default(), 0, *len);
+            if text.text_len() == 0 {
+                self.state.dom.remove(&text_handle);
+            }
+        }
+        let first_removed = removed[0];
+        let total_removed: usize = removed.iter().sum();
+        let last_removed = removed[removed.len() - 1];
+        let s = s - first_removed.min(s - first_start);
+        let e = e
+            - (total_removed - last_removed)
+            - last_removed.min(e - last_start);
+        self.state.start = Location::from(s);
+        self.state.end = Location::from(e);
+        self.create_update_replace_all()
+    }
+
+    /// How much of the start of `line` Shift+Tab would remove: one tab, or
+    /// up to [UNINDENT_SPACES] spaces.
+    fn leading_indentation(&self, line: &DomHandle) -> usize {
+        let paragraph = self.state.dom.lookup_container(line);
+        let Some(DomNode::Text(text)) = paragraph.children().first() else {
+            return 0;
+        };
+        let data = text.data().to_string();
+        if data.starts_with('\t') {
+            1
+        } else {
+            data.chars()
+                .take(UNINDENT_SPACES)
+                .take_while(|c| *c == ' ')
+                .count()
+        }
+    }
+}
+
+enum TabTarget {
+    CodeBlock(DomHandle),
+    List,
+}
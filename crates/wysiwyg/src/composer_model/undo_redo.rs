@@ -12,30 +12,140 @@
 // See the License for the specific language governing permissions and
 // limitations under the License.
 
-use crate::{ComposerModel, ComposerUpdate, UnicodeString};
+use crate::dom::nodes::dom_node::DomNodeKind;
+use crate::dom::nodes::{ContainerNodeKind, DomNode};
+use crate::dom::Dom;
+use crate::{ComposerModel, ComposerUpdate, InlineFormatType, UnicodeString};
+
+/// The code unit at each position of a document, if it is text, and the
+/// inline formats applied to it.
+type FormatMap<U> = Vec<(Option<U>, Vec<InlineFormatType>)>;
 
 impl<S> ComposerModel<S>
 where
     S: UnicodeString,
 {
     pub fn undo(&mut self) -> ComposerUpdate<S> {
+        if let Some(before) = self.take_automatic_action() {
+            // Only undo the automatic change, not the typing which caused it
+            self.next_states.push(self.state.clone());
+            self.restore_state(before);
+            return self.create_update_replace_all();
+        }
         if let Some(prev) = self.previous_states.pop() {
             self.next_states.push(self.state.clone());
-            self.state = prev;
+            self.restore_state(prev);
             self.create_update_replace_all()
         } else {
-            ComposerUpdate::keep()
+            ComposerUpdate::keep_at(self.state.revision)
         }
     }
 
     pub fn redo(&mut self) -> ComposerUpdate<S> {
         if let Some(next) = self.next_states.pop() {
             self.previous_states.push(self.state.clone());
-            self.state = next;
+            self.restore_state(next);
             self.create_update_replace_all()
         } else {
-            ComposerUpdate::keep()
+            ComposerUpdate::keep_at(self.state.revision)
+        }
+    }
+
+    /// Revert the most recent change which only altered inline formatting
+    /// (e.g. bold), keeping any text typed since. The revert is added to
+    /// the history as a new step. Does nothing if there is no such change,
+    /// or if the text it formatted has been edited since.
+    pub fn undo_last_formatting(&mut self) -> ComposerUpdate<S> {
+        let current = format_map(&self.state.dom);
+        let mut after = current.clone();
+        for previous in self.previous_states.iter().rev() {
+            let before = format_map(&previous.dom);
+            if is_formatting_only_change(&before, &after) {
+                return self
+                    .revert_formatting_change(&before, &after, &current);
+            }
+            after = before;
+        }
+        ComposerUpdate::keep_at(self.state.revision)
+    }
+
+    /// Restore the formats changed between `before` and `after` in the
+    /// current document, which is `after` with later edits applied.
+    fn revert_formatting_change(
+        &mut self,
+        before: &FormatMap<S::CodeUnit>,
+        after: &FormatMap<S::CodeUnit>,
+        current: &FormatMap<S::CodeUnit>,
+    ) -> ComposerUpdate<S> {
+        let changed: Vec<usize> = (0..after.len())
+            .filter(|&i| !same_formats(&before[i].1, &after[i].1))
+            .collect();
+        let (first, last) = (changed[0], changed[changed.len() - 1]);
+
+        // Later edits must not touch the formatted text, so it is either in
+        // the unchanged start or the unchanged end of the document.
+        let prefix = after
+            .iter()
+            .zip(current.iter())
+            .take_while(|(a, c)| a.0 == c.0)
+            .count();
+        let suffix = after
+            .iter()
+            .rev()
+            .zip(current.iter().rev())
+            .take(after.len().min(current.len()) - prefix)
+            .take_while(|(a, c)| a.0 == c.0)
+            .count();
+        let offset = if last < prefix {
+            0
+        } else if first >= after.len() - suffix {
+            current.len() as isize - after.len() as isize
+        } else {
+            return ComposerUpdate::keep_at(self.state.revision);
+        };
+
+        self.push_state_to_history();
+        let mut formats: Vec<&InlineFormatType> = Vec::new();
+        for i in changed.iter() {
+            for format in before[*i].1.iter().chain(after[*i].1.iter()) {
+                if !formats.contains(&format) {
+                    formats.push(format);
+                }
+            }
         }
+        for format in formats {
+            let added = |i: usize| {
+                after[i].1.contains(format) && !before[i].1.contains(format)
+            };
+            let removed = |i: usize| {
+                before[i].1.contains(format) && !after[i].1.contains(format)
+            };
+            for (start, end) in runs(&changed, added) {
+                self.unformat_range(
+                    start.saturating_add_signed(offset),
+                    end.saturating_add_signed(offset),
+                    format,
+                );
+            }
+            for (start, end) in runs(&changed, removed) {
+                self.format_range(
+                    start.saturating_add_signed(offset),
+                    end.saturating_add_signed(offset),
+                    format,
+                );
+            }
+        }
+        self.create_update_replace_all()
+    }
+
+    /// How many times [ComposerModel::undo] can be called.
+    pub fn undo_depth(&self) -> usize {
+        self.previous_states.len()
+    }
+
+    /// How many times [ComposerModel::redo] can be called.
+    pub fn redo_depth(&self) -> usize {
+        self.next_states.len()
     }
 
     pub(crate) fn push_state_to_history(&mut self) {
@@ -45,3 +155,65 @@ where
         self.previous_states.push(self.state.clone());
     }
 }
+
+fn format_map<S: UnicodeString>(dom: &Dom<S>) -> FormatMap<S::CodeUnit> {
+    let len = dom.text_len();
+    let mut map = vec![(None, Vec::new()); len];
+    for leaf in dom.find_range(0, len).leaves() {
+        if leaf.kind != DomNodeKind::Text {
+            continue;
+        }
+        let mut formats = Vec::new();
+        let mut handle = leaf.node_handle.clone();
+        while handle.has_parent() {
+            handle = handle.parent_handle();
+            if let DomNode::Container(container) = dom.lookup_node(&handle) {
+                if let ContainerNodeKind::Formatting(format) = container.kind()
+                {
+                    formats.push(format.clone());
+                }
+            }
+        }
+        let data = dom.lookup_node(&leaf.node_handle).as_text().unwrap().data();
+        for (i, unit) in data.as_ref().iter().enumerate() {
+            if let Some(entry) = map.get_mut(leaf.position + i) {
+                *entry = (Some(*unit), formats.clone());
+            }
+        }
+    }
+    map
+}
+
+/// Whether the text is the same in both maps, but the formats aren't.
+fn is_formatting_only_change<U: PartialEq>(
+    before: &FormatMap<U>,
+    after: &FormatMap<U>,
+) -> bool {
+    before.len() == after.len()
+        && before.iter().zip(after.iter()).all(|(b, a)| b.0 == a.0)
+        && before
+            .iter()
+            .zip(after.iter())
+            .any(|(b, a)| !same_formats(&b.1, &a.1))
+}
+
+/// Whether the same formats are applied, in any nesting order.
+fn same_formats(a: &[InlineFormatType], b: &[InlineFormatType]) -> bool {
+    a.len() == b.len() && a.iter().all(|f| b.contains(f))
+}
+
+/// The (start, end) of each run of consecutive positions for which
+/// `matches` is true.
+fn runs(
+    positions: &[usize],
+    matches: impl Fn(usize) -> bool,
+) -> Vec<(usize, usize)> {
+    let mut runs: Vec<(usize, usize)> = Vec::new();
+    for &i in positions.iter().filter(|&&i| matches(i)) {
+        match runs.last_mut() {
+            Some((_, end)) if *end == i => *end = i + 1,
+            _ => runs.push((i, i + 1)),
+        }
+    }
+    runs
+}
@@ -0,0 +1,275 @@
+// Copyright 2023 The Matrix.org Foundation C.I.C.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use crate::dom::nodes::container_node::{
+    is_data_attribute, CUSTOM_TAG_ATTRIBUTES,
+};
+use crate::dom::nodes::dom_node::DomNodeKind;
+use crate::dom::nodes::{ContainerNode, ContainerNodeKind, DomNode};
+use crate::{ComposerModel, ComposerUpdate, InlineFormatType, UnicodeString};
+
+impl<S> ComposerModel<S>
+where
+    S: UnicodeString,
+{
+    /// Wrap the selection in a new container of the given kind with the
+    /// given attributes, e.g. a `span` with a `data-mx-spoiler` attribute
+    /// to hide the selected text as a spoiler.
+    ///
+    /// Formatting and links are inline, so a selection over several blocks
+    /// gets one of them in each block. Quotes and code blocks wrap every
+    /// block the selection touches, like [ComposerModel::quote] and
+    /// [ComposerModel::code_block]. Other kinds of container can't wrap a
+    /// selection, and nothing happens.
+    ///
+    /// Only `data-*` attributes and the attributes kept on custom tags (e.g.
+    /// `title`) are set. Formatting must be allowed as for
+    /// [ComposerModel::format_in], except for a `span` with at least one
+    /// `data-*` attribute, which can always wrap the selection.
+    pub fn wrap_selection_in(
+        &mut self,
+        kind: ContainerNodeKind<S>,
+        attrs: Vec<(S, S)>,
+    ) -> ComposerUpdate<S> {
+        let attrs: Vec<(S, S)> = attrs
+            .into_iter()
+            .filter(|(name, _)| is_allowed_attribute(&name.to_string()))
+            .collect();
+        if let ContainerNodeKind::Formatting(format) = &kind {
+            let allowed = match format {
+                InlineFormatType::Custom(tag) if tag == "span" => attrs
+                    .iter()
+                    .any(|(name, _)| is_data_attribute(&name.to_string())),
+                format => self.is_allowed_format(format),
+            };
+            if !allowed {
+                return ComposerUpdate::keep_at(self.state.revision);
+            }
+        }
+        match kind {
+            ContainerNodeKind::Formatting(_) | ContainerNodeKind::Link(_) => {
+                self.wrap_selection_in_inline_node(kind, attrs)
+            }
+            ContainerNodeKind::Quote | ContainerNodeKind::CodeBlock => {
+                self.wrap_selection_in_block_node(kind, attrs)
+            }
+            ContainerNodeKind::Generic
+            | ContainerNodeKind::List(_)
+            | ContainerNodeKind::ListItem
+            | ContainerNodeKind::Paragraph
+            | ContainerNodeKind::Heading(_)
+            | ContainerNodeKind::Math
+            | ContainerNodeKind::MathBlock => {
+                ComposerUpdate::keep_at(self.state.revision)
+            }
+        }
+    }
+
+    fn wrap_selection_in_inline_node(
+        &mut self,
+        kind: ContainerNodeKind<S>,
+        attrs: Vec<(S, S)>,
+    ) -> ComposerUpdate<S> {
+        let (s, e) = self.safe_selection();
+        if s == e {
+            return ComposerUpdate::keep_at(self.state.revision);
+        }
+        self.push_state_to_history();
+        let range = self.state.dom.find_range(s, e);
+        for (_, s, e) in range.inline_ranges_in_blocks() {
+            let range = self.state.dom.find_range(s, e);
+            self.state
+                .dom
+                .insert_parent(&range, new_inline_node(kind.clone(), &attrs));
+        }
+        self.create_update_replace_all()
+    }
+
+    fn wrap_selection_in_block_node(
+        &mut self,
+        kind: ContainerNodeKind<S>,
+        attrs: Vec<(S, S)>,
+    ) -> ComposerUpdate<S> {
+        self.push_state_to_history();
+        let node_kind = DomNodeKind::from_container_kind(&kind);
+        if node_kind == DomNodeKind::Quote {
+            self.add_quote();
+        } else {
+            self.add_code_block();
+        }
+        let (s, _) = self.safe_selection();
+        let range = self.state.dom.find_range(s, s);
+        if let Some(block) = range.deepest_node_of_kind(node_kind, None) {
+            let handle = block.node_handle.clone();
+            if let Ok(DomNode::Container(block)) =
+                self.state.dom.try_lookup_node_mut(&handle)
+            {
+                block.set_attributes(attrs);
+            }
+        }
+        self.create_update_replace_all()
+    }
+}
+
+/// Whether an attribute may be set by [ComposerModel::wrap_selection_in].
+fn is_allowed_attribute(name: &str) -> bool {
+    is_data_attribute(name) || CUSTOM_TAG_ATTRIBUTES.contains(&name)
+}
+
+fn new_inline_node<S: UnicodeString>(
+    kind: ContainerNodeKind<S>,
+    attrs: &[(S, S)],
+) -> DomNode<S> {
+    match kind {
+        ContainerNodeKind::Link(url) => {
+            DomNode::new_link(url, Vec::new(), attrs.to_vec())
+        }
+        ContainerNodeKind::Formatting(format) => {
+            DomNode::Container(ContainerNode::new(
+                format.tag().into(),
+                ContainerNodeKind::Formatting(format),
+                (!attrs.is_empty()).then(|| attrs.to_vec()),
+                Vec::new(),
+            ))
+        }
+        _ => unreachable!("Only formatting and links are inline"),
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::dom::nodes::ContainerNodeKind;
+    use crate::tests::testutils_composer_model::{cm, tx, TestString};
+    use crate::tests::testutils_conversion::utf16;
+    use crate::InlineFormatType;
+
+    fn spoiler() -> ContainerNodeKind<TestString> {
+        ContainerNodeKind::Formatting(InlineFormatType::Custom("span".into()))
+    }
+
+    #[test]
+    fn wrapping_selection_in_custom_span_adds_attributes() {
+        let mut model = cm("a{bc}|d");
+        model.wrap_selection_in(
+            spoiler(),
+            vec![(utf16("data-mx-spoiler"), utf16(""))],
+        );
+        assert_eq!(tx(&model), "a<span data-mx-spoiler=\"\">{bc}|</span>d");
+    }
+
+    #[test]
+    fn wrapping_selection_drops_attributes_which_are_not_allowed() {
+        let mut model = cm("a{bc}|d");
+        model.wrap_selection_in(
+            spoiler(),
+            vec![
+                (utf16("onclick"), utf16("alert(1)")),
+                (utf16("data-mx-spoiler"), utf16("")),
+                (utf16("style"), utf16("color: red")),
+            ],
+        );
+        assert_eq!(tx(&model), "a<span data-mx-spoiler=\"\">{bc}|</span>d");
+    }
+
+    #[test]
+    fn wrapping_selection_in_span_without_data_attributes_does_nothing() {
+        let mut model = cm("a{bc}|d");
+        model.wrap_selection_in(
+            spoiler(),
+            vec![(utf16("onclick"), utf16("alert(1)"))],
+        );
+        assert_eq!(tx(&model), "a{bc}|d");
+    }
+
+    #[test]
+    fn wrapping_selection_in_tag_which_is_not_allowed_does_nothing() {
+        let mut model = cm("a{bc}|d");
+        model.wrap_selection_in(
+            ContainerNodeKind::Formatting(InlineFormatType::Custom(
+                "style".into(),
+            )),
+            vec![(utf16("data-x"), utf16(""))],
+        );
+        assert_eq!(tx(&model), "a{bc}|d");
+    }
+
+    #[test]
+    fn wrapping_selection_across_paragraphs_wraps_each_one() {
+        let mut model = cm("<p>a{b</p><p>c}|d</p>");
+        model.wrap_selection_in(
+            spoiler(),
+            vec![(utf16("data-mx-spoiler"), utf16(""))],
+        );
+        assert_eq!(
+            tx(&model),
+            "<p>a<span data-mx-spoiler=\"\">{b</span></p>\
+             <p><span data-mx-spoiler=\"\">c}|</span>d</p>"
+        );
+    }
+
+    #[test]
+    fn wrapping_selection_in_formatting_without_attributes() {
+        let mut model = cm("a{bc}|d");
+        model.wrap_selection_in(
+            ContainerNodeKind::Formatting(InlineFormatType::Bold),
+            Vec::new(),
+        );
+        assert_eq!(tx(&model), "a<strong>{bc}|</strong>d");
+    }
+
+    #[test]
+    fn wrapping_selection_in_link_keeps_url() {
+        let mut model = cm("a{bc}|d");
+        model.wrap_selection_in(
+            ContainerNodeKind::Link(utf16("https://matrix.org")),
+            Vec::new(),
+        );
+        assert_eq!(tx(&model), "a<a href=\"https://matrix.org\">{bc}|</a>d");
+    }
+
+    #[test]
+    fn wrapping_selection_in_quote_sets_attributes() {
+        let mut model = cm("ab|c");
+        model.wrap_selection_in(
+            ContainerNodeKind::Quote,
+            vec![(utf16("data-mx-quote-author"), utf16("@alice:matrix.org"))],
+        );
+        assert_eq!(
+            tx(&model),
+            "<blockquote data-mx-quote-author=\"@alice:matrix.org\"><p>ab|c</p></blockquote>"
+        );
+    }
+
+    #[test]
+    fn wrapping_cursor_in_inline_node_does_nothing() {
+        let mut model = cm("ab|c");
+        model.wrap_selection_in(spoiler(), Vec::new());
+        assert_eq!(tx(&model), "ab|c");
+    }
+
+    #[test]
+    fn wrapping_selection_in_paragraph_does_nothing() {
+        let mut model = cm("a{bc}|d");
+        model.wrap_selection_in(ContainerNodeKind::Paragraph, Vec::new());
+        assert_eq!(tx(&model), "a{bc}|d");
+    }
+
+    #[test]
+    fn wrapping_selection_can_be_undone() {
+        let mut model = cm("a{bc}|d");
+        model.wrap_selection_in(spoiler(), Vec::new());
+        model.undo();
+        assert_eq!(tx(&model), "a{bc}|d");
+    }
+}
@@ -15,7 +15,7 @@
 use crate::dom::{Dom, UnicodeString};
 use crate::{InlineFormatType, Location};
 
-#[derive(Clone, Debug, PartialEq, Default)]
+#[derive(Clone, Debug, Default)]
 pub struct ComposerState<S>
 where
     S: UnicodeString,
@@ -24,6 +24,24 @@ where
     pub start: Location,
     pub end: Location,
     pub toggled_format_types: Vec<InlineFormatType>,
+    /// Goes up by one with every update the model sends, so that updates
+    /// computed from an older state can be recognised. It keeps going up
+    /// when an earlier state is restored, e.g. by undo. Not compared when
+    /// comparing states, so states with the same content are equal.
+    pub revision: u64,
+}
+
+/// States are compared by what they contain, whatever their revision.
+impl<S> PartialEq for ComposerState<S>
+where
+    S: UnicodeString,
+{
+    fn eq(&self, other: &Self) -> bool {
+        self.dom == other.dom
+            && self.start == other.start
+            && self.end == other.end
+            && self.toggled_format_types == other.toggled_format_types
+    }
 }
 
 impl<S> ComposerState<S>
@@ -36,6 +54,7 @@ where
             start: Location::default(),
             end: Location::default(),
             toggled_format_types: Vec::new(),
+            revision: 0,
         }
     }
 
@@ -12,10 +12,11 @@
 // See the License for the specific language governing permissions and
 // limitations under the License.
 
-use crate::dom::UnicodeString;
+use crate::dom::{Dom, UnicodeString};
 use crate::link_action::LinkActionUpdate;
 use crate::{
-    Location, MenuAction, MenuState, ReplaceAll, Selection, TextUpdate,
+    Location, MenuAction, MenuState, ReplaceAll, ReplacementHtml, Selection,
+    TextUpdate,
 };
 
 #[derive(Debug, Clone, PartialEq, Eq)]
@@ -27,30 +28,45 @@ where
     pub menu_state: MenuState,
     pub menu_action: MenuAction,
     pub link_action: LinkActionUpdate<S>,
+    /// The revision of the model's state once this update is applied. Hosts
+    /// applying updates asynchronously can drop any with a lower revision
+    /// than one they have already applied.
+    pub revision: u64,
 }
 
 impl<S> ComposerUpdate<S>
 where
     S: UnicodeString,
 {
+    /// An update which changes nothing, with a revision of 0. Use
+    /// [ComposerUpdate::keep_at] to give the revision of the model.
     pub fn keep() -> Self {
+        Self::keep_at(0)
+    }
+
+    /// An update which changes nothing, sent when the model is at
+    /// `revision`.
+    pub fn keep_at(revision: u64) -> Self {
         Self {
             text_update: TextUpdate::<S>::Keep,
             menu_state: MenuState::Keep,
             menu_action: MenuAction::Keep,
             link_action: LinkActionUpdate::Keep,
+            revision,
         }
     }
 
     pub fn update_menu_state(
         menu_state: MenuState,
         menu_action: MenuAction,
+        revision: u64,
     ) -> Self {
         Self {
             text_update: TextUpdate::<S>::Keep,
             menu_state,
             menu_action,
             link_action: LinkActionUpdate::Keep,
+            revision,
         }
     }
 
@@ -60,32 +76,91 @@ where
         menu_state: MenuState,
         menu_action: MenuAction,
         link_action: LinkActionUpdate<S>,
+        revision: u64,
     ) -> Self {
         Self {
             text_update: TextUpdate::<S>::Select(Selection { start, end }),
             menu_state,
             menu_action,
             link_action,
+            revision,
         }
     }
 
+    /// An update replacing all the content with `replacement_html`.
+    #[allow(clippy::too_many_arguments)]
     pub fn replace_all(
         replacement_html: S,
         start: Location,
         end: Location,
+        changed: (Location, Location),
+        menu_state: MenuState,
+        menu_action: MenuAction,
+        link_action: LinkActionUpdate<S>,
+        revision: u64,
+    ) -> Self {
+        Self::replace_all_with_html(
+            ReplacementHtml::from_html(replacement_html),
+            start,
+            end,
+            changed,
+            menu_state,
+            menu_action,
+            link_action,
+            revision,
+        )
+    }
+
+    /// An update replacing all the content with `dom`, which is only
+    /// serialized to HTML if the consumer reads it. Taking the Dom is cheap,
+    /// because it shares its nodes with the model's until either changes.
+    #[allow(clippy::too_many_arguments)]
+    pub fn replace_all_with_dom(
+        dom: Dom<S>,
+        start: Location,
+        end: Location,
+        changed: (Location, Location),
+        menu_state: MenuState,
+        menu_action: MenuAction,
+        link_action: LinkActionUpdate<S>,
+        revision: u64,
+    ) -> Self {
+        Self::replace_all_with_html(
+            ReplacementHtml::new(dom),
+            start,
+            end,
+            changed,
+            menu_state,
+            menu_action,
+            link_action,
+            revision,
+        )
+    }
+
+    #[allow(clippy::too_many_arguments)]
+    fn replace_all_with_html(
+        replacement_html: ReplacementHtml<S>,
+        start: Location,
+        end: Location,
+        changed: (Location, Location),
         menu_state: MenuState,
         menu_action: MenuAction,
         link_action: LinkActionUpdate<S>,
+        revision: u64,
     ) -> Self {
+        let (changed_start, changed_end) = changed;
         Self {
             text_update: TextUpdate::ReplaceAll(ReplaceAll {
                 replacement_html,
                 start,
                 end,
+                changed_start,
+                changed_end,
             }),
             menu_state,
             menu_action,
             link_action,
+            revision,
         }
     }
 }
@@ -14,6 +14,7 @@
 
 pub mod action_list;
 pub mod dom_block_nodes;
+pub mod dom_builder;
 pub mod dom_creation_error;
 pub mod dom_handle;
 pub mod dom_invariants;
@@ -22,7 +23,7 @@ pub mod dom_methods;
 pub mod dom_struct;
 pub mod find_extended_range;
 pub mod find_range;
-pub mod find_result;
+pub mod html_source_map;
 pub mod insert_node_at_cursor;
 pub mod insert_parent;
 pub mod iter;
@@ -30,23 +31,31 @@ pub mod join_nodes;
 pub mod nodes;
 pub mod parser;
 pub mod range;
+#[cfg(feature = "rope")]
+pub mod rope_string;
 pub mod to_html;
 pub mod to_markdown;
 pub mod to_plain_text;
 pub mod to_raw_text;
 pub mod to_tree;
 pub mod unicode_string;
+pub mod visitor;
 
+pub use dom_builder::DomBuilder;
 pub use dom_creation_error::DomCreationError;
 pub use dom_creation_error::HtmlParseError;
 pub use dom_creation_error::MarkdownParseError;
 pub use dom_handle::DomHandle;
 pub use dom_struct::Dom;
-pub use find_result::FindResult;
+pub use html_source_map::HtmlSourceMapEntry;
 pub use range::DomLocation;
 pub use range::Range;
-pub use to_html::ToHtml;
-pub use to_markdown::{MarkdownError, ToMarkdown};
+#[cfg(feature = "rope")]
+pub use rope_string::RopeString;
+pub use to_html::{CustomTagSerializer, CustomTagSerializers, ToHtml};
+pub use to_markdown::{MarkdownError, MarkdownOptions, ToMarkdown};
+pub use to_plain_text::PlainTextOptions;
 pub use to_raw_text::ToRawText;
 pub use to_tree::ToTree;
 pub use unicode_string::UnicodeString;
+pub use visitor::{walk_children, walk_node, DomVisitor};
@@ -0,0 +1,343 @@
+// Copyright 2023 The Matrix.org Foundation C.I.C.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use crate::dom::nodes::DomNode;
+use crate::dom::Dom;
+use crate::{HeadingLevel, InlineFormatType, ListType, UnicodeString};
+
+/// Builds a [Dom] from code, e.g. in tests or bots, without creating each
+/// node by hand. Handles are given to the nodes when the Dom is built, so
+/// none of them is ever left unset.
+///
+/// Each builder makes one node. Inline content is added with methods like
+/// [DomBuilder::text] and [DomBuilder::bold], and blocks are added to a
+/// [DomBuilder::document] or a quote with [DomBuilder::child].
+///
+/// ## Examples
+///
+/// ```
+/// use widestring::Utf16String;
+/// use wysiwyg::{DomBuilder, HeadingLevel, ToHtml};
+///
+/// let dom = DomBuilder::<Utf16String>::document()
+///     .child(DomBuilder::heading(HeadingLevel::H1).text("Title"))
+///     .child(
+///         DomBuilder::paragraph()
+///             .text("hi ")
+///             .bold(|b| b.text("there")),
+///     )
+///     .build();
+/// assert_eq!(
+///     dom.to_html().to_string(),
+///     "<h1>Title</h1><p>hi <strong>there</strong></p>"
+/// );
+/// ```
+#[derive(Clone, Debug)]
+pub struct DomBuilder<S>
+where
+    S: UnicodeString,
+{
+    kind: BuilderKind<S>,
+    children: Vec<DomNode<S>>,
+}
+
+#[derive(Clone, Debug)]
+enum BuilderKind<S>
+where
+    S: UnicodeString,
+{
+    Document,
+    Paragraph,
+    Heading(HeadingLevel),
+    Quote,
+    CodeBlock,
+    List(ListType),
+    ListItem,
+    Formatting(InlineFormatType),
+    Link(S),
+}
+
+impl<S> DomBuilder<S>
+where
+    S: UnicodeString,
+{
+    fn new(kind: BuilderKind<S>) -> Self {
+        Self {
+            kind,
+            children: Vec::new(),
+        }
+    }
+
+    /// A whole document, holding the blocks added with [Self::child].
+    pub fn document() -> Self {
+        Self::new(BuilderKind::Document)
+    }
+
+    pub fn paragraph() -> Self {
+        Self::new(BuilderKind::Paragraph)
+    }
+
+    pub fn heading(level: HeadingLevel) -> Self {
+        Self::new(BuilderKind::Heading(level))
+    }
+
+    /// A quote. Inline content added to it is wrapped in a paragraph.
+    pub fn quote() -> Self {
+        Self::new(BuilderKind::Quote)
+    }
+
+    /// A code block. Inline content added to it is wrapped in a paragraph.
+    pub fn code_block() -> Self {
+        Self::new(BuilderKind::CodeBlock)
+    }
+
+    /// A list, holding the items added with [Self::item].
+    pub fn list(list_type: ListType) -> Self {
+        Self::new(BuilderKind::List(list_type))
+    }
+
+    pub fn list_item() -> Self {
+        Self::new(BuilderKind::ListItem)
+    }
+
+    /// Add a text node. Empty text is ignored.
+    pub fn text(mut self, text: &str) -> Self {
+        if !text.is_empty() {
+            self.children.push(DomNode::new_text(text.into()));
+        }
+        self
+    }
+
+    pub fn line_break(mut self) -> Self {
+        self.children.push(DomNode::new_line_break());
+        self
+    }
+
+    /// Add a mention of the user, room or event at `url`, showing
+    /// `display_text`. If `url` isn't a Matrix URI, a link is added
+    /// instead, as when parsing HTML.
+    pub fn mention(mut self, url: &str, display_text: &str) -> Self {
+        let node = match DomNode::new_mention(
+            url.into(),
+            display_text.into(),
+            Vec::new(),
+        ) {
+            Ok(mention) => DomNode::Mention(mention),
+            Err(_) => DomNode::new_link(
+                url.into(),
+                vec![DomNode::new_text(display_text.into())],
+                Vec::new(),
+            ),
+        };
+        self.children.push(node);
+        self
+    }
+
+    /// Add an image of `src` described by `alt`, with no size.
+    pub fn image(mut self, src: &str, alt: &str) -> Self {
+        self.children.push(DomNode::new_image(
+            src.into(),
+            alt.into(),
+            None,
+            None,
+        ));
+        self
+    }
+
+    /// Add a node of the given format, containing what `build` adds to it.
+    pub fn format(
+        self,
+        format: InlineFormatType,
+        build: impl FnOnce(Self) -> Self,
+    ) -> Self {
+        self.nested(BuilderKind::Formatting(format), build)
+    }
+
+    pub fn bold(self, build: impl FnOnce(Self) -> Self) -> Self {
+        self.format(InlineFormatType::Bold, build)
+    }
+
+    pub fn italic(self, build: impl FnOnce(Self) -> Self) -> Self {
+        self.format(InlineFormatType::Italic, build)
+    }
+
+    pub fn strike_through(self, build: impl FnOnce(Self) -> Self) -> Self {
+        self.format(InlineFormatType::StrikeThrough, build)
+    }
+
+    pub fn underline(self, build: impl FnOnce(Self) -> Self) -> Self {
+        self.format(InlineFormatType::Underline, build)
+    }
+
+    pub fn inline_code(self, build: impl FnOnce(Self) -> Self) -> Self {
+        self.format(InlineFormatType::InlineCode, build)
+    }
+
+    /// Add a link to `url`, containing what `build` adds to it.
+    pub fn link(self, url: &str, build: impl FnOnce(Self) -> Self) -> Self {
+        self.nested(BuilderKind::Link(url.into()), build)
+    }
+
+    /// Add a list item containing what `build` adds to it.
+    pub fn item(self, build: impl FnOnce(Self) -> Self) -> Self {
+        self.nested(BuilderKind::ListItem, build)
+    }
+
+    /// Add the node made by another builder, e.g. a paragraph to a document.
+    pub fn child(mut self, child: DomBuilder<S>) -> Self {
+        self.children.extend(child.into_nodes());
+        self
+    }
+
+    /// Add a node which was made some other way.
+    pub fn node(mut self, node: DomNode<S>) -> Self {
+        self.children.push(node);
+        self
+    }
+
+    /// Make the [Dom]. Anything but a [Self::document] becomes the only
+    /// node in the document.
+    pub fn build(self) -> Dom<S> {
+        Dom::new(self.into_nodes())
+    }
+
+    fn nested(
+        mut self,
+        kind: BuilderKind<S>,
+        build: impl FnOnce(Self) -> Self,
+    ) -> Self {
+        self.children.extend(build(Self::new(kind)).into_nodes());
+        self
+    }
+
+    /// The node this builder makes, or the nodes in it for a document.
+    fn into_nodes(self) -> Vec<DomNode<S>> {
+        let children = self.children;
+        let node = match self.kind {
+            BuilderKind::Document => return children,
+            BuilderKind::Paragraph => DomNode::new_paragraph(children),
+            BuilderKind::Heading(level) => {
+                DomNode::new_heading(level, children)
+            }
+            BuilderKind::Quote => DomNode::new_quote(children),
+            BuilderKind::CodeBlock => DomNode::new_code_block(children),
+            BuilderKind::List(list_type) => {
+                DomNode::new_list(list_type, children)
+            }
+            BuilderKind::ListItem => DomNode::new_list_item(children),
+            BuilderKind::Formatting(format) => {
+                DomNode::new_formatting(format, children)
+            }
+            BuilderKind::Link(url) => {
+                DomNode::new_link(url, children, Vec::new())
+            }
+        };
+        vec![node]
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::DomBuilder;
+    use crate::tests::testutils_composer_model::{cm, TestString};
+    use crate::{HeadingLevel, ListType, ToHtml};
+
+    type Builder = DomBuilder<TestString>;
+
+    fn html(builder: Builder) -> String {
+        builder.build().to_html().to_string()
+    }
+
+    #[test]
+    fn builds_a_paragraph_with_formatting() {
+        let builder = Builder::paragraph()
+            .text("a ")
+            .bold(|b| b.text("b").italic(|i| i.text("c")))
+            .text(" ")
+            .inline_code(|c| c.text("d"));
+        assert_eq!(
+            html(builder),
+            "<p>a <strong>b<em>c</em></strong> <code>d</code></p>"
+        );
+    }
+
+    #[test]
+    fn builds_the_same_dom_as_parsing_html() {
+        let built = Builder::document()
+            .child(Builder::heading(HeadingLevel::H2).text("Title"))
+            .child(
+                Builder::list(ListType::Unordered)
+                    .item(|li| li.text("one"))
+                    .item(|li| li.text("two")),
+            )
+            .child(Builder::quote().child(Builder::paragraph().text("q")))
+            .build();
+        let parsed = cm("<h2>Title</h2><ul><li>one</li><li>two</li></ul>\
+            <blockquote><p>q|</p></blockquote>")
+        .state
+        .dom;
+        assert_eq!(built, parsed);
+    }
+
+    #[test]
+    fn inline_content_in_quotes_and_code_blocks_is_in_a_paragraph() {
+        assert_eq!(
+            html(Builder::quote().text("q")),
+            "<blockquote><p>q</p></blockquote>"
+        );
+        assert_eq!(
+            html(Builder::code_block().text("c")),
+            "<pre><code>c</code></pre>"
+        );
+    }
+
+    #[test]
+    fn builds_links_mentions_and_line_breaks() {
+        let builder = Builder::paragraph()
+            .link("https://matrix.org", |a| a.text("matrix"))
+            .line_break()
+            .mention("https://matrix.to/#/@alice:matrix.org", "Alice");
+        assert_eq!(
+            html(builder),
+            "<p><a href=\"https://matrix.org\">matrix</a><br />\
+            <a data-mention-type=\"user\" href=\"https://matrix.to/#/@alice:matrix.org\" contenteditable=\"false\">Alice</a></p>"
+        );
+    }
+
+    #[test]
+    fn a_mention_of_something_else_is_a_link() {
+        let builder = Builder::paragraph().mention("https://example.com", "x");
+        assert_eq!(
+            html(builder),
+            "<p><a href=\"https://example.com\">x</a></p>"
+        );
+    }
+
+    #[test]
+    fn builds_images() {
+        let builder = Builder::paragraph().text("a").image("mxc://a/b", "cat");
+        assert_eq!(
+            html(builder),
+            "<p>a<img src=\"mxc://a/b\" alt=\"cat\" /></p>"
+        );
+    }
+
+    #[test]
+    fn every_node_has_its_handle() {
+        let dom = Builder::paragraph().bold(|b| b.text("a")).build();
+        for node in dom.iter() {
+            assert!(node.handle().is_set());
+        }
+    }
+}
@@ -23,11 +23,23 @@ pub enum DomCreationError {
 #[derive(Debug, Eq, PartialEq)]
 pub struct HtmlParseError {
     pub parse_errors: Vec<String>,
+    /// What could be made of the HTML in spite of the errors, as HTML which
+    /// can be set without errors, e.g. to restore a malformed draft.
+    pub recovered_html: Option<String>,
 }
 
 impl HtmlParseError {
     pub fn new(parse_errors: Vec<String>) -> Self {
-        Self { parse_errors }
+        Self {
+            parse_errors,
+            recovered_html: None,
+        }
+    }
+}
+
+impl fmt::Display for HtmlParseError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "unable to parse html: {}", self.parse_errors.join(", "))
     }
 }
 
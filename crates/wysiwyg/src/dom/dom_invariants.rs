@@ -15,7 +15,7 @@
 //! Assertions that guarantee the Dom is in a known state.
 //!
 //! To see the full list of the invariants we enforce, look at the
-//! assert_invariants method.
+//! invariant_violation method.
 //!
 //! In future, every public method on Dom should call assert_invariants at the
 //! beginning and the end of the method. This will allow us to find places
@@ -29,14 +29,13 @@
 //! "assert-invariants".
 //!
 //! TODO: build the demo app with these assertions enabled
-//! TODO: add more assertions - see the code of assert_invariants for ideas
+//! TODO: add more assertions - see the code of invariant_violation for ideas
 
-#[cfg(any(test, feature = "assert-invariants"))]
-use crate::dom::unicode_string::UnicodeStrExt;
+use crate::dom::nodes::dom_node::DomNodeKind;
 use crate::dom::Dom;
-use crate::UnicodeString;
 #[cfg(any(test, feature = "assert-invariants"))]
-use crate::{DomNode, ToTree};
+use crate::ToTree;
+use crate::{DomNode, UnicodeString};
 
 impl<S> Dom<S>
 where
@@ -58,36 +57,43 @@ where
             // as the DOM is known to be in an inconsistent state
             return;
         }
-        self.assert_no_empty_text_nodes();
-        self.assert_no_adjacent_text_nodes();
-        self.assert_exactly_one_generic_container();
-        self.assert_all_nodes_in_containers_are_block_or_inline();
-
-        // We probably want some more asserts like these:
-        // self.assert_document_node_is_a_container();
-        // self.assert_no_empty_containers_except_at_root();
-        // self.assert_inline_code_contains_no_tags_except_line_breaks
-        // self.assert_code_blocks_do_not_contain_structure_tags
-        // self.assert_links_do_not_contain_structure_tags
-        // self.assert_links_do_not_contain_links
-        // self.assert_zero_width_spaces_are_only_in_empty_list_item_tags
+        if let Some(violation) = self.invariant_violation() {
+            panic!("{violation}\n{}", self.to_tree());
+        }
     }
 
-    #[cfg(any(test, feature = "assert-invariants"))]
-    fn assert_no_empty_text_nodes(&self) {
-        for text in self.iter_text() {
-            if text.data().is_empty() {
-                panic!(
-                    "Empty text node found! handle: {:?}\n{}",
-                    text.handle(),
-                    self.to_tree(),
-                );
-            }
-        }
+    /// A description of the first invariant this Dom breaks, or None if it
+    /// keeps all of them. Unlike the assertions, this is always available,
+    /// so that a broken Dom can be detected and rebuilt outside tests too.
+    /// It only names node kinds and handles, never the content, so it is
+    /// safe to report.
+    pub fn invariant_violation(&self) -> Option<String> {
+        self.empty_text_node_violation()
+            .or_else(|| self.adjacent_text_nodes_violation())
+            .or_else(|| self.generic_container_violation())
+            .or_else(|| self.mixed_block_and_inline_violation())
+
+        // We probably want some more checks like these:
+        // self.document_node_is_not_a_container_violation()
+        // self.empty_containers_except_at_root_violation()
+        // self.inline_code_contains_tags_except_line_breaks_violation()
+        // self.code_blocks_contain_structure_tags_violation()
+        // self.links_contain_structure_tags_violation()
+        // self.links_contain_links_violation()
     }
 
-    #[cfg(any(test, feature = "assert-invariants"))]
-    fn assert_no_adjacent_text_nodes(&self) {
+    fn empty_text_node_violation(&self) -> Option<String> {
+        self.iter_text()
+            .find(|text| text.text_len() == 0)
+            .map(|text| {
+                format!(
+                    "Empty text node found! handle: {:?}",
+                    text.handle().raw()
+                )
+            })
+    }
+
+    fn adjacent_text_nodes_violation(&self) -> Option<String> {
         for node in self.iter_containers() {
             let mut prev_node: Option<&DomNode<S>> = None;
             for child in node.children() {
@@ -95,21 +101,20 @@ where
                     if let (DomNode::Text(_), DomNode::Text(_)) =
                         (prev_node, child)
                     {
-                        panic!(
-                            "Adjacent text nodes found! handle: {:?}\n{}",
-                            prev_node.handle(),
-                            self.to_tree()
-                        );
+                        return Some(format!(
+                            "Adjacent text nodes found! handle: {:?}",
+                            prev_node.handle().raw()
+                        ));
                     }
                 }
                 prev_node = Some(child);
             }
         }
+        None
     }
 
     /// Check there is only one generic container and that it is the root node
-    #[cfg(any(test, feature = "assert-invariants"))]
-    fn assert_exactly_one_generic_container(&self) {
+    fn generic_container_violation(&self) -> Option<String> {
         use super::nodes::ContainerNodeKind;
 
         let generic_nodes = self
@@ -119,25 +124,32 @@ where
 
         if handles.len() > 1 {
             let first = handles.into_iter().find(|h| !h.is_root());
-            panic!(
-                "More than one generic container node found. Handle: {:?}\n{}",
-                first.unwrap().raw(),
-                self.to_tree()
-            );
+            Some(format!(
+                "More than one generic container node found. Handle: {:?}",
+                first.unwrap().raw()
+            ))
+        } else {
+            None
         }
     }
 
-    #[cfg(any(test, feature = "assert-invariants"))]
-    fn assert_all_nodes_in_containers_are_block_or_inline(&self) {
+    fn mixed_block_and_inline_violation(&self) -> Option<String> {
         for container in self.iter_containers() {
             let all_nodes_are_inline =
                 container.children().iter().all(|n| !n.is_block_node());
             let all_nodes_are_block =
                 container.children().iter().all(|n| n.is_block_node());
             if !all_nodes_are_inline && !all_nodes_are_block {
-                panic!("All child nodes of handle {:?} must be either inline nodes or block nodes:\n{}", container.handle(), container.to_tree());
+                let kinds: Vec<DomNodeKind> =
+                    container.children().iter().map(|n| n.kind()).collect();
+                return Some(format!(
+                    "All child nodes of handle {:?} must be either inline nodes or block nodes, found: {:?}",
+                    container.handle().raw(),
+                    kinds
+                ));
             }
         }
+        None
     }
 }
 
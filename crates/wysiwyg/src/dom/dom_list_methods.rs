@@ -204,11 +204,51 @@ where
         list.insert_child(handle.index_in_parent() + 1, slice);
         self.join_nodes_in_container(&handle.parent_handle());
     }
+
+    /// Move the contents of the `count` list items after the one at `handle`
+    /// into it, so each list item becomes a paragraph inside it.
+    pub(crate) fn join_list_items_as_paragraphs(
+        &mut self,
+        handle: &DomHandle,
+        count: usize,
+    ) {
+        let index = handle.index_in_parent();
+        let DomNode::Container(list) =
+            self.lookup_node_mut(&handle.parent_handle())
+        else {
+            panic!("List node is not a container")
+        };
+        let mut children = Vec::new();
+        for _ in 0..=count {
+            let DomNode::Container(item) = list.remove_child(index) else {
+                panic!("List item is not a container")
+            };
+            // Inline content is wrapped in paragraphs, e.g. next to a
+            // nested list
+            let first_child = children.len();
+            let mut inline = Vec::new();
+            for child in item.take_children() {
+                if child.is_block_node() {
+                    if !inline.is_empty() {
+                        children.push(DomNode::new_paragraph(inline));
+                        inline = Vec::new();
+                    }
+                    children.push(child);
+                } else {
+                    inline.push(child);
+                }
+            }
+            if !inline.is_empty() || children.len() == first_child {
+                children.push(DomNode::new_paragraph(inline));
+            }
+        }
+        list.insert_child(index, DomNode::new_list_item(children));
+    }
 }
 
 #[cfg(test)]
 mod test {
-    use widestring::Utf16String;
+    use crate::tests::testutils_composer_model::TestString;
 
     use crate::dom::Dom;
     use crate::tests::testutils_composer_model::{cm, tx};
@@ -420,7 +460,7 @@ mod test {
     }
 
     // TODO: move this to a more globally usable location if needed
-    fn ds(dom: &Dom<Utf16String>) -> String {
+    fn ds(dom: &Dom<TestString>) -> String {
         dom.to_string().replace(char::nbsp(), "&nbsp;")
     }
 }
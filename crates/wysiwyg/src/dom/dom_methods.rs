@@ -18,12 +18,12 @@
 use crate::dom::nodes::dom_node::DomNodeKind::{Generic, ListItem, Paragraph};
 use crate::dom::range::DomLocationPosition::After;
 use crate::dom::DomLocation;
-use crate::{DomHandle, DomNode, UnicodeString};
+use crate::{ComposerError, DomHandle, DomNode, UnicodeString};
 
 use super::action_list::{DomAction, DomActionList};
 use super::nodes::dom_node::DomNodeKind;
 use super::nodes::{ContainerNode, TextNode};
-use super::unicode_string::{UnicodeStrExt, UnicodeStringExt};
+use super::unicode_string::UnicodeStringExt;
 use super::{Dom, Range};
 
 impl<S> Dom<S>
@@ -60,7 +60,14 @@ where
         self.assert_invariants();
     }
 
-    pub fn replace_text_in(&mut self, new_text: S, start: usize, end: usize) {
+    /// Replace the text between `start` and `end` with `new_text`. If this
+    /// fails, the Dom may be left half changed.
+    pub fn replace_text_in(
+        &mut self,
+        new_text: S,
+        start: usize,
+        end: usize,
+    ) -> Result<(), ComposerError> {
         #[cfg(any(test, feature = "assert-invariants"))]
         self.assert_invariants();
 
@@ -74,13 +81,13 @@ where
             }
             (Vec::new(), Vec::new())
         // We check for the first starting_link_handle if any
-        // Because for links we always add the text to the previous or next sibling
+        // Because for links and inline maths we always add the text to the previous or next sibling
         } else if let Some(starting_link) =
             first_shrinkable_link_node_handle(&range)
         {
             // We replace and delete as normal with an empty string on the current range
             let deleted_handles =
-                self.replace_multiple_nodes(&range, "".into());
+                self.replace_multiple_nodes(&range, "".into())?;
             let mut moved_handles = Vec::new();
             // Then we set the new text value in the previous/next sibling node (or create a new one if none exists)
             if starting_link.is_start() {
@@ -99,7 +106,7 @@ where
 
             (deleted_handles, moved_handles)
         } else {
-            (self.replace_multiple_nodes(&range, new_text), Vec::new())
+            (self.replace_multiple_nodes(&range, new_text)?, Vec::new())
         };
 
         // If text was replaced, not inserted
@@ -131,22 +138,28 @@ where
                 if start_block_loc != end_block_loc {
                     let end_block = self.remove(&end_block_loc.node_handle);
                     let DomNode::Container(end_block) = end_block else {
-                        panic!("Ending block node must be a container node")
+                        return Err(ComposerError::NotAContainer(
+                            end_block_loc.node_handle,
+                        ));
                     };
                     let removed_items = end_block.take_children();
-                    if let DomNode::Container(start_block) =
-                        self.lookup_node_mut(&start_block_loc.node_handle)
-                    {
-                        // Merge contents in `start_block`
-                        start_block.append_children(removed_items);
-                    } else {
-                        panic!("Starting block node must be a container node");
-                    }
+                    let DomNode::Container(start_block) =
+                        self.try_lookup_node_mut(&start_block_loc.node_handle)?
+                    else {
+                        return Err(ComposerError::NotAContainer(
+                            start_block_loc.node_handle,
+                        ));
+                    };
+                    // Merge contents in `start_block`
+                    start_block.append_children(removed_items);
 
                     if end_block_loc.node_handle.has_parent() {
-                        self.remove_empty_nodes_recursively(
-                            &end_block_loc.node_handle.parent_handle(),
+                        let parent_handle =
+                            end_block_loc.node_handle.parent_handle();
+                        self.lift_items_out_of_list_only_list_item(
+                            &parent_handle,
                         );
+                        self.remove_empty_nodes_recursively(&parent_handle);
                     }
 
                     self.join_nodes_in_container(&start_block_loc.node_handle);
@@ -157,9 +170,14 @@ where
         if let Some(leaf_handle) = self.first_leaf_handle_at_location(start) {
             self.remove_list_item_child_paragraph_if_needed(&leaf_handle);
         }
+        // Links to the same URL may now be next to each other
+        if let Some(leaf_handle) = self.first_leaf_handle_at_location(start) {
+            self.join_nodes_up_to_block(&leaf_handle.parent_handle());
+        }
 
         #[cfg(any(test, feature = "assert-invariants"))]
         self.assert_invariants();
+        Ok(())
     }
 
     /// Removes paragraph from the closest list item ancestor, if
@@ -182,6 +200,38 @@ where
         }
     }
 
+    /// If the node at `handle` is a list item which only contains nested
+    /// lists, e.g. after its paragraph was merged into the block before
+    /// it, replace it with the items of those lists so no item is left
+    /// without a bullet of its own.
+    fn lift_items_out_of_list_only_list_item(&mut self, handle: &DomHandle) {
+        let node = self.lookup_node(handle);
+        let is_list_only_item = node.kind() == ListItem
+            && node.as_container().map_or(false, |li| {
+                !li.children().is_empty()
+                    && li
+                        .children()
+                        .iter()
+                        .all(|c| c.kind() == DomNodeKind::List)
+            });
+        if !is_list_only_item {
+            return;
+        }
+        let DomNode::Container(list_item) = self.remove(handle) else {
+            unreachable!("List items are always containers");
+        };
+        let items = list_item
+            .take_children()
+            .into_iter()
+            .filter_map(|list| match list {
+                DomNode::Container(list) => Some(list.take_children()),
+                _ => None,
+            })
+            .flatten()
+            .collect();
+        self.insert(handle, items);
+    }
+
     fn first_leaf_handle_at_location(&self, pos: usize) -> Option<DomHandle> {
         let range = self.find_range(pos, pos);
         let mut leaves = range.leaves();
@@ -296,7 +346,7 @@ where
     }
 
     /// Return the previous text node belonging to a sibling of given handle were we could write text.
-    /// This excludes text nodes that are inside a link container.
+    /// This excludes text nodes that are inside a link or maths container.
     fn prev_sibling_writable_text_node_mut(
         &mut self,
         node_handle: &DomHandle,
@@ -309,7 +359,7 @@ where
         {
             match node {
                 DomNode::Container(c) => {
-                    if c.is_link() {
+                    if c.is_link() || c.is_math() {
                         None
                     } else if let Some(last_child) = c.last_child_mut() {
                         last_text_node_in(last_child)
@@ -318,7 +368,10 @@ where
                     }
                 }
                 DomNode::Text(t) => Some(t),
-                DomNode::LineBreak(_) | DomNode::Mention(_) => None,
+                DomNode::LineBreak(_)
+                | DomNode::Mention(_)
+                | DomNode::Image(_)
+                | DomNode::HorizontalRule(_) => None,
             }
         }
 
@@ -351,7 +404,7 @@ where
     }
 
     /// Return the next text node belonging to a sibling of given handle were we could write text.
-    /// This excludes text nodes that are inside a link container.
+    /// This excludes text nodes that are inside a link or maths container.
     fn next_sibling_writable_text_node_mut(
         &mut self,
         node_handle: &DomHandle,
@@ -364,7 +417,7 @@ where
         {
             match node {
                 DomNode::Container(c) => {
-                    if c.is_link() {
+                    if c.is_link() || c.is_math() {
                         None
                     } else if let Some(first_child) = c.first_child_mut() {
                         first_text_node_in(first_child)
@@ -373,7 +426,10 @@ where
                     }
                 }
                 DomNode::Text(t) => Some(t),
-                DomNode::LineBreak(_) | DomNode::Mention(_) => None,
+                DomNode::LineBreak(_)
+                | DomNode::Mention(_)
+                | DomNode::Image(_)
+                | DomNode::HorizontalRule(_) => None,
             }
         }
 
@@ -392,7 +448,7 @@ where
         &mut self,
         range: &Range,
         new_text: S,
-    ) -> Vec<DomHandle> {
+    ) -> Result<Vec<DomHandle>, ComposerError> {
         let len = new_text.len();
         let action_list = self.replace_in_text_nodes(range.clone(), new_text);
 
@@ -406,12 +462,14 @@ where
         // invalidated by the add we do here.
         for add_action in to_add.into_iter().rev() {
             let parent_handle = &add_action.parent_handle;
-            let parent = self.lookup_node_mut(parent_handle);
-            if let DomNode::Container(parent) = parent {
-                parent.insert_child(add_action.index, add_action.node);
-            } else {
-                panic!("Parent was not a container!");
-            }
+            let DomNode::Container(parent) =
+                self.try_lookup_node_mut(parent_handle)?
+            else {
+                return Err(ComposerError::NotAContainer(
+                    parent_handle.clone(),
+                ));
+            };
+            parent.insert_child(add_action.index, add_action.node);
         }
 
         // Delete the nodes marked for deletion
@@ -446,7 +504,7 @@ where
             }
         }
 
-        deleted_handles
+        Ok(deleted_handles)
     }
 
     fn find_first_non_matching_ancestor_in(
@@ -472,6 +530,22 @@ where
         parent_handle_in_list(list, node_handle)
     }
 
+    /// Join nodes in the container at `handle` and in each of its
+    /// ancestors up to the closest block node, so e.g. links to the same
+    /// URL which end up next to each other become one link.
+    fn join_nodes_up_to_block(&mut self, handle: &DomHandle) {
+        let mut handle = handle.clone();
+        loop {
+            self.join_nodes_in_container(&handle);
+            if !handle.has_parent()
+                || self.lookup_node(&handle).kind().is_block_kind()
+            {
+                break;
+            }
+            handle = handle.parent_handle();
+        }
+    }
+
     pub(crate) fn join_nodes_in_container(
         &mut self,
         container_handle: &DomHandle,
@@ -528,7 +602,7 @@ where
                                 first_text_node = false;
                             } else if first_text_node {
                                 match loc.kind {
-                                    Paragraph | ListItem => {
+                                    Paragraph | DomNodeKind::Heading | ListItem => {
                                         let text_node = DomNode::new_text(new_text.clone());
                                         action_list.push(DomAction::add_node(
                                             loc.node_handle.clone(),
@@ -537,6 +611,18 @@ where
                                         ));
                                         first_text_node = false;
                                     },
+                                    DomNodeKind::List => {
+                                        // A list with no items gets one holding the text
+                                        let list_item = DomNode::new_list_item(vec![
+                                            DomNode::new_text(new_text.clone()),
+                                        ]);
+                                        action_list.push(DomAction::add_node(
+                                            loc.node_handle.clone(),
+                                            0,
+                                            list_item,
+                                        ));
+                                        first_text_node = false;
+                                    },
                                     _ => panic!("A block node that can't contain inline nodes was selected, text can't be added to it."),
                                 }
                             }
@@ -546,7 +632,10 @@ where
                             ));
                             first_text_node = false;
                         } else if !first_text_node
-                            && matches!(loc.kind, Paragraph)
+                            && matches!(
+                                loc.kind,
+                                Paragraph | DomNodeKind::Heading
+                            )
                         {
                             // FIXME: Workaround for empty paragraphs. This is a hack, but trying to fix the root cause
                             // breaks lots of other behaviours resulting in dozens of failing tests.
@@ -575,16 +664,30 @@ where
                         first_text_node = false;
                     }
                 }
-                DomNode::LineBreak(_) | DomNode::Mention(_) => {
+                DomNode::HorizontalRule(_) => {
+                    // Like an empty paragraph, the rule goes when the
+                    // selection covers it or text is deleted up to it
+                    let covered = (loc.start_offset, loc.end_offset) == (0, 1);
+                    let deleted_up_to =
+                        loc.relative_position() == After && new_text.is_empty();
+                    if covered || deleted_up_to {
+                        action_list.push(DomAction::remove_node(
+                            loc.node_handle.clone(),
+                        ));
+                    }
+                }
+                DomNode::LineBreak(_)
+                | DomNode::Mention(_)
+                | DomNode::Image(_) => {
                     match (loc.start_offset, loc.end_offset) {
                         (0, 1) => {
-                            // Whole line break or mention is selected, delete it
+                            // Whole line break, mention or image is selected, delete it
                             action_list.push(DomAction::remove_node(
                                 loc.node_handle.clone(),
                             ));
                         }
                         (1, 1) => {
-                            // Cursor is after the line break or mention, no need to delete
+                            // Cursor is after the line break, mention or image, no need to delete
                         }
                         (0, 0) => {
                             if first_text_node && !new_text.is_empty() {
@@ -597,7 +700,7 @@ where
                             }
                         }
                         _ => panic!(
-                            "Tried to insert text into a line break or mention with offset != 0 or 1. \
+                            "Tried to insert text into a line break, mention or image with offset != 0 or 1. \
                             Start offset: {}, end offset: {}",
                             loc.start_offset,
                             loc.end_offset,
@@ -605,34 +708,32 @@ where
                     }
                 }
                 DomNode::Text(node) => {
-                    let old_data = node.data();
-
                     // If this is not the first node, and the selections spans
                     // it, delete it.
                     if loc.start_offset == 0
-                        && loc.end_offset == old_data.len()
+                        && loc.end_offset == node.text_len()
                         && !first_text_node
                     {
                         action_list.push(DomAction::remove_node(
                             loc.node_handle.clone(),
                         ));
                     } else {
-                        // Otherwise, delete the selected text
-                        let mut new_data =
-                            old_data[..loc.start_offset].to_owned();
-
-                        // and replace with the new content
-                        if first_text_node {
-                            new_data.push(new_text.deref());
-                        }
-
-                        new_data.push(&old_data[loc.end_offset..]);
-                        if new_data.is_empty() {
+                        // Otherwise, replace the selected text in place, with
+                        // the new content if this is the first node
+                        let new_data = if first_text_node {
+                            new_text.clone()
+                        } else {
+                            S::default()
+                        };
+                        node.replace_range(
+                            new_data,
+                            loc.start_offset,
+                            loc.end_offset,
+                        );
+                        if node.text_len() == 0 {
                             action_list.push(DomAction::remove_node(
                                 loc.node_handle.clone(),
                             ));
-                        } else {
-                            node.set_data(new_data);
                         }
                     }
 
@@ -676,7 +777,11 @@ where
         inserted_length: usize,
     ) {
         // If we've ended up with adjacent text nodes, merge them
-        if let Some(first_location) = replaced_range.locations.first() {
+        if let Some(first_location) = replaced_range
+            .locations
+            .first()
+            .filter(|l| !l.node_handle.is_root())
+        {
             let first_handle = &first_location.node_handle;
             if deleted_handles.contains(first_handle) {
                 // If we deleted the first node in the range ...
@@ -730,7 +835,8 @@ where
         &mut self,
         handle: &DomHandle,
     ) {
-        if !self.lookup_node(handle).is_block_node() {
+        let node = self.lookup_node(handle);
+        if !node.is_container_node() || !node.is_block_node() {
             return;
         }
         self.wrap_inline_nodes_into_paragraphs_at_container(handle);
@@ -1014,9 +1120,13 @@ where
             {
                 needs_to_remove_container = true;
             }
-            result = vec![DomNode::Container(
-                container.clone_with_new_children(child_nodes),
-            )]
+            // If the container is left empty, it's moved rather than split
+            let container = if needs_to_remove_container {
+                container.clone_with_new_children(child_nodes)
+            } else {
+                container.clone_for_split(child_nodes)
+            };
+            result = vec![DomNode::Container(container)]
         } else {
             result = Vec::new();
         }
@@ -1043,7 +1153,7 @@ where
         if (cur_handle == *from_handle
             || (from_handle.is_ancestor_of(&cur_handle)
                 && cur_handle.index_in_parent() == 0))
-            && (1..=text_node.data().len()).contains(&start_offset)
+            && (1..=text_node.text_len()).contains(&start_offset)
         {
             let left_data = text_node.data()[..start_offset].to_owned();
             let right_data = text_node.data()[start_offset..].to_owned();
@@ -1053,7 +1163,7 @@ where
             }
         } else if to_handle.is_some()
             && cur_handle == to_handle.unwrap()
-            && (1..=text_node.data().len()).contains(&end_offset)
+            && (1..=text_node.text_len()).contains(&end_offset)
         {
             let left_data = text_node.data()[..end_offset].to_owned();
             let right_data = text_node.data()[end_offset..].to_owned();
@@ -1130,7 +1240,7 @@ where
 
 fn first_shrinkable_link_node_handle(range: &Range) -> Option<&DomLocation> {
     let Some(link_loc) = range.locations.iter().find(|loc| {
-        loc.kind == DomNodeKind::Link
+        matches!(loc.kind, DomNodeKind::Link | DomNodeKind::Math)
             && !loc.is_covered()
             && (loc.is_start() || loc.leading_is_end())
     }) else {
@@ -1192,6 +1302,7 @@ mod test {
     }
 
     #[test]
+    #[cfg_attr(feature = "test-utf32", ignore = "uses UTF-16 offsets")]
     fn split_dom_with_emojis() {
         let mut model = cm("👍👍|<b>👍👍</b><i>👍👍</i>");
         let ret = model.state.dom.split_sub_tree_from(
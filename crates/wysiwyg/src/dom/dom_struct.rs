@@ -13,30 +13,53 @@
 // limitations under the License.
 
 use std::fmt::Display;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Arc;
 
 use crate::composer_model::example_format::SelectionWriter;
 use crate::dom::nodes::{ContainerNode, DomNode};
 use crate::dom::to_html::ToHtmlState;
 use crate::dom::to_markdown::{MarkdownError, MarkdownOptions, ToMarkdown};
-use crate::dom::unicode_string::UnicodeStrExt;
 use crate::dom::{
     find_range, to_raw_text::ToRawText, DomHandle, Range, ToTree, UnicodeString,
 };
-use crate::ToHtml;
+use crate::{ComposerError, ToHtml};
 
 use super::to_plain_text::ToPlainText;
-use super::FindResult;
 
-#[derive(Clone, Debug, PartialEq, Default)]
+/// The next revision given to a changed [Dom], shared by all of them so
+/// that two different Doms never have the same revision.
+static NEXT_REVISION: AtomicU64 = AtomicU64::new(1);
+
+#[derive(Clone, Debug, Default)]
 pub struct Dom<S>
 where
     S: UnicodeString,
 {
-    document: DomNode<S>,
+    /// Shared between clones until one of them is modified, so that
+    /// snapshots of the Dom, e.g. in updates, are cheap to take.
+    document: Arc<DomNode<S>>,
+    /// Changes whenever the document might have been modified, so that
+    /// anything worked out from it can be cached until it does.
+    revision: u64,
     #[cfg(any(test, feature = "assert-invariants"))]
     is_transaction_in_progress: bool,
 }
 
+fn next_revision() -> u64 {
+    NEXT_REVISION.fetch_add(1, Ordering::Relaxed)
+}
+
+impl<S> PartialEq for Dom<S>
+where
+    S: UnicodeString,
+{
+    /// Doms are equal if their documents are, whatever their revisions.
+    fn eq(&self, other: &Self) -> bool {
+        self.document == other.document
+    }
+}
+
 impl<S> Dom<S>
 where
     S: UnicodeString,
@@ -47,7 +70,8 @@ where
         document.set_handle(DomHandle::root());
 
         Self {
-            document: DomNode::Container(document),
+            document: Arc::new(DomNode::Container(document)),
+            revision: next_revision(),
             #[cfg(any(test, feature = "assert-invariants"))]
             is_transaction_in_progress: false,
         }
@@ -65,7 +89,8 @@ where
         root_node.set_handle(DomHandle::root());
 
         Self {
-            document: root_node,
+            document: Arc::new(root_node),
+            revision: next_revision(),
             #[cfg(any(test, feature = "assert-invariants"))]
             is_transaction_in_progress: false,
         }
@@ -74,7 +99,7 @@ where
     pub fn document(&self) -> &ContainerNode<S> {
         // Would be nice if we could avoid this, but it is really convenient
         // in several places to be able to treat document as a DomNode.
-        if let DomNode::Container(ret) = &self.document {
+        if let DomNode::Container(ret) = self.document.as_ref() {
             ret
         } else {
             panic!("Document should always be a Container!")
@@ -82,7 +107,7 @@ where
     }
 
     pub fn into_container(self) -> ContainerNode<S> {
-        if let DomNode::Container(ret) = self.document {
+        if let DomNode::Container(ret) = self.into_document_node() {
             ret
         } else {
             panic!("Document should always be a Container!")
@@ -90,9 +115,10 @@ where
     }
 
     pub fn document_mut(&mut self) -> &mut ContainerNode<S> {
+        self.revision = next_revision();
         // Would be nice if we could avoid this, but it is really convenient
         // in several places to be able to treat document as a DomNode.
-        if let DomNode::Container(ret) = &mut self.document {
+        if let DomNode::Container(ret) = Arc::make_mut(&mut self.document) {
             ret
         } else {
             panic!("Document should always be a Container!")
@@ -104,7 +130,7 @@ where
     }
 
     pub fn into_document_node(self) -> DomNode<S> {
-        self.document
+        Arc::unwrap_or_clone(self.document)
     }
 
     pub fn into_node(mut self, handle: &DomHandle) -> DomNode<S> {
@@ -115,6 +141,12 @@ where
         }
     }
 
+    /// A number which changes whenever this Dom might have been modified.
+    /// Clones share a revision until one of them is modified.
+    pub(crate) fn revision(&self) -> u64 {
+        self.revision
+    }
+
     pub fn children(&self) -> &Vec<DomNode<S>> {
         self.document().children()
     }
@@ -199,7 +231,7 @@ where
 
     #[cfg(all(feature = "js", target_arch = "wasm32"))]
     pub(crate) fn take_children(self) -> Vec<DomNode<S>> {
-        if let DomNode::Container(container) = self.document {
+        if let DomNode::Container(container) = self.into_document_node() {
             container.take_children()
         } else {
             panic!("Document should always be a Container!")
@@ -294,7 +326,7 @@ where
     ///
     /// **Note**: this call moves the Dom, so it becomes unusable.
     pub fn take_document(self) -> DomNode<S> {
-        self.document
+        self.into_document_node()
     }
 
     /// Given the start and end code units, find which nodes of this Dom are
@@ -304,19 +336,31 @@ where
     }
 
     pub fn find_range_by_node(&self, node_handle: &DomHandle) -> Range {
-        let result = find_range::find_pos(self, node_handle, 0, usize::MAX);
-
-        let locations = match result {
-            FindResult::Found(locations) => locations,
-            _ => panic!("Node does not exist"),
-        };
-
-        let leaves = locations.iter().filter(|l| l.is_leaf());
-
-        let s = leaves.clone().map(|l| l.position).min().unwrap();
-        let e = leaves.map(|l| l.position + l.length).max().unwrap();
+        self.try_find_range_by_node(node_handle)
+            .expect("Node does not exist")
+    }
 
-        self.find_range(s, e)
+    /// Like [Self::find_range_by_node], but returns an error instead of
+    /// panicking if there is no node with leaves at the handle.
+    pub fn try_find_range_by_node(
+        &self,
+        node_handle: &DomHandle,
+    ) -> Result<Range, ComposerError> {
+        self.try_lookup_node(node_handle)?;
+        let range = find_range::find_pos(self, node_handle, 0, usize::MAX);
+        let invalid = || ComposerError::InvalidHandle(node_handle.clone());
+        let s = range
+            .leaves()
+            .map(|l| l.position)
+            .min()
+            .ok_or_else(invalid)?;
+        let e = range
+            .leaves()
+            .map(|l| l.position + l.length)
+            .max()
+            .ok_or_else(invalid)?;
+
+        Ok(self.find_range(s, e))
     }
 
     pub(crate) fn document_handle(&self) -> DomHandle {
@@ -369,6 +413,58 @@ where
         self.lookup_node(node_handle).as_container().unwrap()
     }
 
+    /// Find the node based on its handle, like [Self::lookup_node], but
+    /// returns an error instead of panicking if there is no node there,
+    /// e.g. because the handle went stale when the Dom changed.
+    pub fn try_lookup_node(
+        &self,
+        node_handle: &DomHandle,
+    ) -> Result<&DomNode<S>, ComposerError> {
+        let invalid = || ComposerError::InvalidHandle(node_handle.clone());
+        if !node_handle.is_set() {
+            return Err(invalid());
+        }
+        let mut node = self.document_node();
+        for idx in node_handle.raw() {
+            let DomNode::Container(container) = node else {
+                return Err(invalid());
+            };
+            node = container.children().get(*idx).ok_or_else(invalid)?;
+        }
+        Ok(node)
+    }
+
+    /// Like [Self::try_lookup_node], but returns a mutable reference.
+    pub fn try_lookup_node_mut(
+        &mut self,
+        node_handle: &DomHandle,
+    ) -> Result<&mut DomNode<S>, ComposerError> {
+        self.try_lookup_node(node_handle)?;
+        Ok(self.lookup_node_mut(node_handle))
+    }
+
+    /// Like [Self::lookup_container], but returns an error if there is no
+    /// node at the handle or it is not a container.
+    pub fn try_lookup_container(
+        &self,
+        node_handle: &DomHandle,
+    ) -> Result<&ContainerNode<S>, ComposerError> {
+        self.try_lookup_node(node_handle)?
+            .as_container()
+            .ok_or_else(|| ComposerError::NotAContainer(node_handle.clone()))
+    }
+
+    /// Like [Self::parent], but returns an error instead of panicking.
+    pub fn try_parent(
+        &self,
+        handle: &DomHandle,
+    ) -> Result<&ContainerNode<S>, ComposerError> {
+        if !handle.is_set() || !handle.has_parent() {
+            return Err(ComposerError::InvalidHandle(handle.clone()));
+        }
+        self.try_lookup_container(&handle.parent_handle())
+    }
+
     /// Find the node based on its handle and returns a mutable reference.
     /// Panics if the handle is invalid or unset
     pub fn lookup_node_mut(
@@ -388,7 +484,8 @@ where
             )
         }
 
-        let mut node = &mut self.document;
+        self.revision = next_revision();
+        let mut node = Arc::make_mut(&mut self.document);
         if !node_handle.is_set() {
             panic!(
                 "Attempting to lookup a node using an unset DomHandle ({:?})",
@@ -410,6 +507,14 @@ where
                     "Handle is invalid: refers to the child of a mention node, \
                     but mention nodes cannot have children."
                 ),
+                DomNode::Image(_) => panic!(
+                    "Handle is invalid: refers to the child of an image node, \
+                    but image nodes cannot have children."
+                ),
+                DomNode::HorizontalRule(_) => panic!(
+                    "Handle is invalid: refers to the child of a horizontal \
+                    rule, but horizontal rules cannot have children."
+                ),
             }
         }
 
@@ -446,7 +551,10 @@ where
             DomNode::Container(_) => {
                 panic!("Can't insert into a non-text node!")
             }
-            DomNode::LineBreak(_) | DomNode::Mention(_) => {
+            DomNode::LineBreak(_)
+            | DomNode::Mention(_)
+            | DomNode::Image(_)
+            | DomNode::HorizontalRule(_) => {
                 if offset == 0 {
                     Where::Before
                 } else if offset == 1 {
@@ -462,7 +570,7 @@ where
             DomNode::Text(n) => {
                 if offset == 0 {
                     Where::Before
-                } else if offset == n.data().len() {
+                } else if offset == n.text_len() {
                     Where::After
                 } else {
                     Where::During
@@ -697,7 +805,7 @@ impl Display for ItemNode {
 
 #[cfg(test)]
 mod test {
-    use widestring::Utf16String;
+    use crate::tests::testutils_composer_model::TestString;
 
     use crate::dom::nodes::dom_node::DomNode;
     use crate::dom::nodes::TextNode;
@@ -761,6 +869,44 @@ mod test {
         assert_eq!(dom.lookup_node(&handle), nested_node);
     }
 
+    #[test]
+    fn looking_up_a_stale_handle_is_an_error() {
+        let mut dom = dom(&[tn("foo"), b(&[tn("bar")])]);
+        let handle = DomHandle::from_raw(vec![1, 0]);
+        assert_eq!(dom.try_lookup_node(&handle).unwrap().to_html(), "bar");
+
+        dom.remove(&DomHandle::from_raw(vec![1]));
+
+        assert_eq!(
+            dom.try_lookup_node(&handle),
+            Err(ComposerError::InvalidHandle(handle.clone()))
+        );
+        assert!(dom.try_lookup_node_mut(&handle).is_err());
+    }
+
+    #[test]
+    fn looking_up_a_container_which_is_not_one_is_an_error() {
+        let dom = dom(&[tn("foo")]);
+        let handle = DomHandle::from_raw(vec![0]);
+        assert_eq!(
+            dom.try_lookup_container(&handle),
+            Err(ComposerError::NotAContainer(handle))
+        );
+        assert_eq!(
+            dom.try_parent(&DomHandle::from_raw(vec![0, 0])),
+            Err(ComposerError::NotAContainer(DomHandle::from_raw(vec![0])))
+        );
+    }
+
+    #[test]
+    fn the_root_has_no_parent() {
+        let dom = dom(&[tn("foo")]);
+        assert_eq!(
+            dom.try_parent(&DomHandle::root()),
+            Err(ComposerError::InvalidHandle(DomHandle::root()))
+        );
+    }
+
     #[test]
     fn can_replace_toplevel_node_with_multiple_nodes() {
         let mut dom = dom(&[tn("foo"), tn("bar")]);
@@ -896,6 +1042,39 @@ mod test {
         assert_eq!("<code><b>some</b> code</code>", d.to_html().to_string());
     }
 
+    #[test]
+    fn modifying_a_dom_changes_its_revision() {
+        let mut d = cm("aa|").state.dom;
+        let clone = d.clone();
+        assert_eq!(d.revision(), clone.revision());
+
+        d.document_mut().append_child(DomNode::new_text(utf16("b")));
+        assert_ne!(d.revision(), clone.revision());
+        let revision = d.revision();
+        d.lookup_node_mut(&DomHandle::from_raw(vec![0]));
+        assert_ne!(d.revision(), revision);
+    }
+
+    #[test]
+    fn doms_with_different_revisions_can_be_equal() {
+        let mut d = cm("aa|").state.dom;
+        let clone = d.clone();
+        d.lookup_node_mut(&DomHandle::from_raw(vec![0]));
+        assert_ne!(d.revision(), clone.revision());
+        assert_eq!(d, clone);
+    }
+
+    #[test]
+    fn clones_share_their_nodes_until_one_is_modified() {
+        let mut d = cm("aa|").state.dom;
+        let clone = d.clone();
+        assert!(Arc::ptr_eq(&d.document, &clone.document));
+
+        d.document_mut().append_child(DomNode::new_text(utf16("b")));
+        assert!(!Arc::ptr_eq(&d.document, &clone.document));
+        assert_eq!(clone.to_html().to_string(), "aa");
+    }
+
     #[test]
     fn text_len_ignores_html_tags() {
         assert_eq!(0, cm("|").state.dom.text_len());
@@ -982,6 +1161,16 @@ mod test {
         assert_eq!(range_by_node, actual_range);
     }
 
+    #[test]
+    fn try_find_range_by_node_fails_for_a_missing_node() {
+        let d = cm("<b>Hello|</b>").state.dom;
+        let handle = DomHandle::from_raw(vec![0, 3]);
+        assert_eq!(
+            d.try_find_range_by_node(&handle),
+            Err(ComposerError::InvalidHandle(handle))
+        );
+    }
+
     #[test]
     fn find_range_by_node_root() {
         let d = cm("<b><u>Hello, <i>world|</i></u></b>").state.dom;
@@ -1028,7 +1217,7 @@ mod test {
         let mut dom = dom(vec![]);
         dom.start_transaction();
         let container =
-            DomNode::<Utf16String>::Container(ContainerNode::default());
+            DomNode::<TestString>::Container(ContainerNode::default());
         dom.insert(&DomHandle::root().child_handle(0), vec![container]);
         dom.end_transaction();
     }
@@ -1052,13 +1241,16 @@ mod test {
         d.end_transaction();
     }
 
-    const NO_CHILDREN: &Vec<DomNode<Utf16String>> = &Vec::new();
+    const NO_CHILDREN: &Vec<DomNode<TestString>> = &Vec::new();
 
     /// If this node is an element, return its children - otherwise panic
-    fn kids(node: &DomNode<Utf16String>) -> &Vec<DomNode<Utf16String>> {
+    fn kids(node: &DomNode<TestString>) -> &Vec<DomNode<TestString>> {
         match node {
             DomNode::Container(n) => n.children(),
-            DomNode::LineBreak(_) | DomNode::Mention(_) => NO_CHILDREN,
+            DomNode::LineBreak(_)
+            | DomNode::Mention(_)
+            | DomNode::Image(_)
+            | DomNode::HorizontalRule(_) => NO_CHILDREN,
             DomNode::Text(_) => {
                 panic!("We expected an Element, but found Text")
             }
@@ -0,0 +1,182 @@
+// Copyright 2023 The Matrix.org Foundation C.I.C.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use std::cell::RefCell;
+use std::collections::HashMap;
+use std::rc::Rc;
+
+use crate::dom::find_range::find_pos;
+use crate::dom::to_html::ToHtmlState;
+use crate::dom::{Dom, DomHandle, DomLocation, ToHtml, UnicodeString};
+
+/// Where a node of the Dom ended up in the HTML returned by
+/// [Dom::to_html_with_source_map].
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct HtmlSourceMapEntry {
+    /// The node this entry describes
+    pub handle: DomHandle,
+    /// Where the node's HTML, including its tags, starts in the HTML
+    /// string. Measured in code units.
+    pub html_start: usize,
+    /// Where the node's HTML ends in the HTML string (exclusive).
+    pub html_end: usize,
+    /// The model position where the node's content starts, as used for
+    /// selections.
+    pub start: usize,
+    /// The model position where the node's content ends.
+    pub end: usize,
+}
+
+/// Records the HTML range of each node while it is written out. Shared by
+/// all the clones of a [ToHtmlState].
+pub(crate) type HtmlSourceMapRecorder =
+    Rc<RefCell<Vec<(DomHandle, usize, usize)>>>;
+
+impl<S> Dom<S>
+where
+    S: UnicodeString,
+{
+    /// Convert to HTML exactly like [ToHtml::to_html], also returning an
+    /// entry for every node saying where its HTML and its content are, so
+    /// that positions in the rendered HTML can be mapped back to the model.
+    ///
+    /// Entries are in document order, with each container before its
+    /// children.
+    pub fn to_html_with_source_map(&self) -> (S, Vec<HtmlSourceMapEntry>) {
+        let recorder = HtmlSourceMapRecorder::default();
+        let state = ToHtmlState {
+            source_map: Some(recorder.clone()),
+            ..ToHtmlState::default()
+        };
+        let mut buf = S::default();
+        self.fmt_html(&mut buf, None, &state, false);
+
+        let locations =
+            find_pos(self, &self.document_handle(), 0, usize::MAX).locations;
+        let leaves: Vec<&DomLocation> =
+            locations.iter().filter(|l| l.is_leaf()).collect();
+        let positions: HashMap<&DomHandle, (usize, usize)> = locations
+            .iter()
+            .map(|l| (&l.node_handle, (l.position, l.position + l.length)))
+            .collect();
+
+        let mut entries: Vec<HtmlSourceMapEntry> = recorder
+            .take()
+            .into_iter()
+            .map(|(handle, html_start, html_end)| {
+                // Like `find_range_by_node`, a container covers its leaves,
+                // not the separators between blocks.
+                let descendants = leaves
+                    .iter()
+                    .filter(|l| handle.is_ancestor_of(&l.node_handle));
+                let start = descendants.clone().map(|l| l.position).min();
+                let end = descendants.map(|l| l.position + l.length).max();
+                let (start, end) = match (start, end) {
+                    (Some(start), Some(end)) => (start, end),
+                    _ => positions.get(&handle).copied().unwrap_or_default(),
+                };
+                HtmlSourceMapEntry {
+                    handle,
+                    html_start,
+                    html_end,
+                    start,
+                    end,
+                }
+            })
+            .collect();
+        entries.sort_by(|a, b| a.handle.cmp(&b.handle));
+
+        (buf, entries)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::tests::testutils_composer_model::TestString;
+
+    use crate::tests::testutils_composer_model::cm;
+    use crate::tests::testutils_conversion::utf16;
+    use crate::DomHandle;
+
+    use super::HtmlSourceMapEntry;
+
+    fn entry(
+        path: Vec<usize>,
+        html: (usize, usize),
+        content: (usize, usize),
+    ) -> HtmlSourceMapEntry {
+        HtmlSourceMapEntry {
+            handle: DomHandle::from_raw(path),
+            html_start: html.0,
+            html_end: html.1,
+            start: content.0,
+            end: content.1,
+        }
+    }
+
+    fn html_of(html: &TestString, entry: &HtmlSourceMapEntry) -> String {
+        html[entry.html_start..entry.html_end].to_string()
+    }
+
+    #[test]
+    fn source_map_of_formatted_text() {
+        let model = cm("a<strong>bc</strong>d|");
+        let (html, entries) = model.get_content_as_html_with_source_map();
+        assert_eq!(html, utf16("a<strong>bc</strong>d"));
+        assert_eq!(
+            entries,
+            vec![
+                entry(vec![], (0, 21), (0, 4)),
+                entry(vec![0], (0, 1), (0, 1)),
+                entry(vec![1], (1, 20), (1, 3)),
+                entry(vec![1, 0], (9, 11), (1, 3)),
+                entry(vec![2], (20, 21), (3, 4)),
+            ]
+        );
+    }
+
+    #[test]
+    fn source_map_html_ranges_contain_each_node() {
+        let model = cm("<p>a <em>b</em></p><p>&nbsp;</p><ol><li>c|</li></ol>");
+        let (html, entries) = model.get_content_as_html_with_source_map();
+        let html_for = |path: Vec<usize>| {
+            let e = entries
+                .iter()
+                .find(|e| e.handle == DomHandle::from_raw(path.clone()))
+                .unwrap();
+            html_of(&html, e)
+        };
+        assert_eq!(html_for(vec![0]), "<p>a <em>b</em></p>");
+        assert_eq!(html_for(vec![0, 1]), "<em>b</em>");
+        assert_eq!(html_for(vec![1]), "<p>\u{A0}</p>");
+        assert_eq!(html_for(vec![2, 0]), "<li>c</li>");
+    }
+
+    #[test]
+    fn source_map_content_positions_follow_paragraphs() {
+        let model = cm("<p>ab</p><p>cd|</p>");
+        let (_, entries) = model.get_content_as_html_with_source_map();
+        let positions: Vec<_> =
+            entries.iter().map(|e| (e.start, e.end)).collect();
+        assert_eq!(positions, vec![(0, 5), (0, 2), (0, 2), (3, 5), (3, 5)]);
+    }
+
+    #[test]
+    fn source_map_of_empty_model() {
+        let model = cm("|");
+        let (html, entries) = model.get_content_as_html_with_source_map();
+        assert_eq!(html, utf16(""));
+        assert_eq!(entries, vec![entry(vec![], (0, 0), (0, 0))]);
+    }
+}
@@ -415,7 +415,7 @@ where
 
 #[cfg(test)]
 mod test {
-    use widestring::Utf16String;
+    use crate::tests::testutils_composer_model::TestString;
 
     use crate::tests::testutils_composer_model::cm;
     use crate::{DomHandle, DomNode};
@@ -703,12 +703,14 @@ mod test {
         );
     }
 
-    fn node_txt(node: &DomNode<Utf16String>) -> String {
+    fn node_txt(node: &DomNode<TestString>) -> String {
         match node {
             DomNode::Container(c) => c.name().to_string(),
             DomNode::Text(t) => format!("'{}'", t.data()),
             DomNode::LineBreak(_) => String::from("br"),
             DomNode::Mention(_) => String::from("mention"),
+            DomNode::Image(_) => String::from("img"),
+            DomNode::HorizontalRule(_) => String::from("hr"),
         }
     }
 }
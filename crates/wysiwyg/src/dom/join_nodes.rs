@@ -124,8 +124,10 @@ where
                 ContainerNodeKind::Formatting(next_format),
             ) = (prev_node.kind(), next_node.kind())
             {
-                // Found a matching sibling node with the same format
-                return prev_format == next_format;
+                // Found a matching sibling node with the same format. Their
+                // attributes must match too, so none are lost by merging.
+                return prev_format == next_format
+                    && prev_node.attributes() == next_node.attributes();
             }
         }
         false
@@ -18,14 +18,19 @@ use crate::char::CharExt;
 use crate::composer_model::example_format::SelectionWriter;
 use crate::dom::dom_handle::DomHandle;
 use crate::dom::nodes::dom_node::{DomNode, DomNodeKind};
-use crate::dom::to_html::{ToHtml, ToHtmlExt, ToHtmlState};
+use crate::dom::to_html::{
+    CustomTagSerializer, ToHtml, ToHtmlExt, ToHtmlState,
+};
 use crate::dom::to_markdown::{MarkdownError, MarkdownOptions, ToMarkdown};
 use crate::dom::to_plain_text::ToPlainText;
 use crate::dom::to_raw_text::ToRawText;
 use crate::dom::to_tree::ToTree;
-use crate::dom::unicode_string::{UnicodeStr, UnicodeStrExt, UnicodeStringExt};
+use crate::dom::unicode_string::{UnicodeStrExt, UnicodeStringExt};
 use crate::dom::{self, UnicodeString};
-use crate::{InlineFormatType, ListType};
+use crate::{
+    Alignment, AlignmentAttribute, HeadingLevel, InlineFormatType, ListType,
+    QuoteAttribution,
+};
 
 #[derive(Clone, Debug, PartialEq)]
 pub struct ContainerNode<S>
@@ -52,6 +57,11 @@ where
     CodeBlock,
     Quote,
     Paragraph,
+    Heading(HeadingLevel),
+    /// Inline maths, holding its LaTeX source as text
+    Math,
+    /// Maths displayed as a block, holding its LaTeX source as text
+    MathBlock,
 }
 
 impl<S: dom::unicode_string::UnicodeString> Default for ContainerNode<S> {
@@ -102,6 +112,16 @@ where
         }
     }
 
+    pub fn new_heading(level: HeadingLevel, children: Vec<DomNode<S>>) -> Self {
+        Self {
+            name: level.tag().into(),
+            kind: ContainerNodeKind::Heading(level),
+            attrs: None,
+            children,
+            handle: DomHandle::new_unset(),
+        }
+    }
+
     pub fn new_formatting_from_tag(
         format: S,
         children: Vec<DomNode<S>>,
@@ -120,10 +140,19 @@ where
         format: InlineFormatType,
         children: Vec<DomNode<S>>,
     ) -> Self {
+        let attrs = match &format {
+            InlineFormatType::TextColor(color) => {
+                Some(vec![("data-mx-color".into(), color.as_str().into())])
+            }
+            InlineFormatType::Highlight(color) => {
+                Some(vec![("data-mx-bg-color".into(), color.as_str().into())])
+            }
+            _ => None,
+        };
         Self {
             name: format.tag().into(),
             kind: ContainerNodeKind::Formatting(format),
-            attrs: None,
+            attrs,
             children,
             handle: DomHandle::new_unset(),
         }
@@ -169,6 +198,24 @@ where
         }
     }
 
+    /// Maths written as the LaTeX source `latex`, which is shown as its own
+    /// block if `display` is true, or inline otherwise. Serialized with the
+    /// source in `data-mx-maths`, as described in the Matrix spec.
+    pub fn new_math(latex: S, display: bool) -> Self {
+        let (name, kind) = if display {
+            ("div", ContainerNodeKind::MathBlock)
+        } else {
+            ("span", ContainerNodeKind::Math)
+        };
+        Self {
+            name: name.into(),
+            kind,
+            attrs: None,
+            children: vec![DomNode::new_text(latex)],
+            handle: DomHandle::new_unset(),
+        }
+    }
+
     pub fn append_child(&mut self, mut child: DomNode<S>) -> DomHandle {
         assert!(self.handle.is_set());
 
@@ -291,6 +338,21 @@ where
         self.attrs.as_ref()
     }
 
+    pub(crate) fn set_attributes(&mut self, attrs: Vec<(S, S)>) {
+        self.attrs = (!attrs.is_empty()).then_some(attrs);
+    }
+
+    /// The `data-*` attributes of this node that the editor keeps, see
+    /// [is_data_attribute].
+    pub(crate) fn data_attributes(&self) -> Vec<(S, S)> {
+        self.attrs
+            .iter()
+            .flatten()
+            .filter(|(name, _)| is_data_attribute(&name.to_string()))
+            .cloned()
+            .collect()
+    }
+
     pub fn children(&self) -> &Vec<DomNode<S>> {
         &self.children
     }
@@ -358,6 +420,14 @@ where
         matches!(&self.kind, ContainerNodeKind::Formatting(f) if f == format_type)
     }
 
+    /// Whether this is inline or block maths.
+    pub fn is_math(&self) -> bool {
+        matches!(
+            self.kind,
+            ContainerNodeKind::Math | ContainerNodeKind::MathBlock
+        )
+    }
+
     pub(crate) fn is_block_node(&self) -> bool {
         DomNodeKind::from_container_kind(&self.kind).is_block_kind()
     }
@@ -416,6 +486,91 @@ where
         }
     }
 
+    pub(crate) fn heading_level(&self) -> Option<HeadingLevel> {
+        match self.kind {
+            ContainerNodeKind::Heading(level) => Some(level),
+            _ => None,
+        }
+    }
+
+    /// Turn a paragraph or heading into a heading of the given level, or
+    /// into a paragraph if `level` is None.
+    pub(crate) fn set_heading_level(&mut self, level: Option<HeadingLevel>) {
+        match self.kind {
+            ContainerNodeKind::Paragraph | ContainerNodeKind::Heading(_) => {
+                let (name, kind) = match level {
+                    Some(level) => {
+                        (level.tag(), ContainerNodeKind::Heading(level))
+                    }
+                    None => ("p", ContainerNodeKind::Paragraph),
+                };
+                self.name = name.into();
+                self.kind = kind;
+            }
+            _ => panic!(
+                "Setting heading level on a non-paragraph container is not allowed"
+            ),
+        }
+    }
+
+    /// How the lines of this paragraph or heading are aligned, if that was
+    /// set explicitly.
+    pub fn alignment(&self) -> Option<Alignment> {
+        self.alignment_and_attribute()
+            .map(|(alignment, _)| alignment)
+    }
+
+    /// The alignment of this paragraph or heading, and the attribute it is
+    /// stored in.
+    pub(crate) fn alignment_and_attribute(
+        &self,
+    ) -> Option<(Alignment, AlignmentAttribute)> {
+        if !matches!(
+            self.kind,
+            ContainerNodeKind::Paragraph | ContainerNodeKind::Heading(_)
+        ) {
+            return None;
+        }
+        let attrs: Vec<(String, String)> = self
+            .attrs
+            .iter()
+            .flatten()
+            .map(|(name, value)| (name.to_string(), value.to_string()))
+            .collect();
+        Alignment::from_attributes(
+            attrs.iter().map(|(n, v)| (n.as_str(), v.as_str())),
+        )
+    }
+
+    /// Align this paragraph or heading, storing the alignment in
+    /// `attribute`, or remove its alignment if `alignment` is None.
+    pub(crate) fn set_alignment(
+        &mut self,
+        alignment: Option<Alignment>,
+        attribute: AlignmentAttribute,
+    ) {
+        match self.kind {
+            ContainerNodeKind::Paragraph | ContainerNodeKind::Heading(_) => {
+                let mut attrs: Vec<(S, S)> = self
+                    .attrs
+                    .take()
+                    .into_iter()
+                    .flatten()
+                    .filter(|(name, _)| {
+                        !AlignmentAttribute::is_alignment_attribute(
+                            &name.to_string(),
+                        )
+                    })
+                    .collect();
+                attrs.extend(alignment.map(|a| attribute.to_attribute(a)));
+                self.set_attributes(attrs);
+            }
+            _ => panic!(
+                "Setting an alignment on a non-paragraph container is not allowed"
+            ),
+        }
+    }
+
     pub(crate) fn get_link_url(&self) -> Option<S> {
         let ContainerNodeKind::Link(url) = self.kind.clone() else {
             return None;
@@ -423,6 +578,80 @@ where
         Some(url)
     }
 
+    /// The language of a code block, as given by a `language-*` class on its
+    /// `<code>` tag, e.g. `rust` for `<pre><code class="language-rust">`.
+    pub fn code_block_language(&self) -> Option<String> {
+        if !matches!(self.kind, ContainerNodeKind::CodeBlock) {
+            return None;
+        }
+        let (_, class) = self
+            .attrs
+            .as_ref()?
+            .iter()
+            .find(|(name, _)| name.to_string() == "class")?;
+        Self::language_from_class(&class.to_string()).map(String::from)
+    }
+
+    pub(crate) fn set_code_block_language(&mut self, language: &str) {
+        match self.kind {
+            ContainerNodeKind::CodeBlock => {
+                self.attrs = Some(vec![(
+                    "class".into(),
+                    format!("language-{language}").into(),
+                )]);
+            }
+            _ => panic!(
+                "Setting a language on a non-code block container is not allowed"
+            ),
+        }
+    }
+
+    /// Who wrote the text in this quote, if it was given.
+    pub fn quote_attribution(&self) -> Option<QuoteAttribution> {
+        if !matches!(self.kind, ContainerNodeKind::Quote) {
+            return None;
+        }
+        let attrs: Vec<(String, String)> = self
+            .attrs
+            .iter()
+            .flatten()
+            .map(|(name, value)| (name.to_string(), value.to_string()))
+            .collect();
+        QuoteAttribution::from_attributes(
+            attrs.iter().map(|(n, v)| (n.as_str(), v.as_str())),
+        )
+    }
+
+    pub(crate) fn set_quote_attribution(
+        &mut self,
+        attribution: Option<&QuoteAttribution>,
+    ) {
+        match self.kind {
+            ContainerNodeKind::Quote => {
+                self.attrs = attribution.map(QuoteAttribution::to_attributes);
+            }
+            _ => panic!(
+                "Setting an attribution on a non-quote container is not allowed"
+            ),
+        }
+    }
+
+    /// Find the language in the value of a `class` attribute, following the
+    /// `language-*` convention used by Matrix clients and Markdown parsers.
+    /// Languages containing unexpected characters are ignored.
+    pub(crate) fn language_from_class(class: &str) -> Option<&str> {
+        class
+            .split_whitespace()
+            .find_map(|c| c.strip_prefix("language-"))
+            .filter(|language| {
+                !language.is_empty()
+                    && language.chars().all(|c| {
+                        c.is_alphanumeric()
+                            || matches!(c, '-' | '_' | '+' | '#' | '.')
+                    })
+            })
+    }
+
     /// Creates a container with the same kind & attributes
     /// as self, with given children and an unset handle.
     pub(crate) fn clone_with_new_children(
@@ -438,6 +667,26 @@ where
         }
     }
 
+    /// Like [Self::clone_with_new_children], for the new node holding one
+    /// half of this node when it's split. Attributes are copied following
+    /// their [SplitAttributePolicy].
+    pub(crate) fn clone_for_split(&self, children: Vec<DomNode<S>>) -> Self {
+        let attrs = self.attrs.as_ref().map(|attrs| {
+            attrs
+                .iter()
+                .filter(|(name, _)| {
+                    SplitAttributePolicy::for_attribute(&name.to_string())
+                        == SplitAttributePolicy::Duplicate
+                })
+                .cloned()
+                .collect()
+        });
+        Self {
+            attrs,
+            ..self.clone_with_new_children(children)
+        }
+    }
+
     /// Remove leading Line break char from this container.
     /// Returns false if no updates were done.
     pub fn remove_leading_line_break(&mut self) -> bool {
@@ -512,7 +761,7 @@ where
                     removed_children
                         .push(self.children.remove(index_to_remove));
                 }
-                self.clone_with_new_children(removed_children)
+                self.clone_for_split(removed_children)
             }
         }
     }
@@ -539,7 +788,7 @@ where
                 for i in (0..child_index).rev() {
                     removed_children.insert(0, self.children.remove(i));
                 }
-                self.clone_with_new_children(removed_children)
+                self.clone_for_split(removed_children)
             }
         }
     }
@@ -616,12 +865,36 @@ where
                 state,
                 as_message,
             ),
+            ContainerNodeKind::Heading(_) => self.fmt_heading_html(
+                formatter,
+                selection_writer,
+                state,
+                as_message,
+            ),
             ContainerNodeKind::CodeBlock => self.fmt_code_block_html(
                 formatter,
                 selection_writer,
                 state,
                 as_message,
             ),
+            ContainerNodeKind::Math | ContainerNodeKind::MathBlock => self
+                .fmt_math_html(formatter, selection_writer, state, as_message),
+            ContainerNodeKind::Formatting(
+                InlineFormatType::Custom(_)
+                | InlineFormatType::TextColor(_)
+                | InlineFormatType::Highlight(_),
+            )
+            | ContainerNodeKind::Quote => self.fmt_custom_format_html(
+                formatter,
+                selection_writer,
+                state,
+                as_message,
+            ),
+            ContainerNodeKind::Link(_)
+                if as_message && state.safe_link_attributes =>
+            {
+                self.fmt_safe_link_html(formatter, selection_writer, state)
+            }
             _ => self.fmt_default_html(
                 formatter,
                 selection_writer,
@@ -653,6 +926,96 @@ impl<S: UnicodeString> ContainerNode<S> {
         }
     }
 
+    /// Like [Self::fmt_default_html], but escaping the attributes, as
+    /// custom tags can keep any attributes they were parsed with, and a
+    /// quote's attribution can contain any text. In a message, a custom tag
+    /// with a [crate::CustomTagSerializer] is written by it instead.
+    fn fmt_custom_format_html(
+        &self,
+        formatter: &mut S,
+        selection_writer: Option<&mut SelectionWriter>,
+        state: &ToHtmlState,
+        as_message: bool,
+    ) {
+        if let Some((tag, serializer)) = self.custom_tag_serializer(state) {
+            let attrs: Vec<(String, String)> = self
+                .escaped_attrs()
+                .iter()
+                .flatten()
+                .map(|(k, v)| (k.to_string(), v.to_string()))
+                .collect();
+            formatter.push(serializer.open(tag, &attrs).as_str());
+            self.fmt_children_html(
+                formatter,
+                selection_writer,
+                state,
+                as_message,
+            );
+            formatter.push(serializer.close(tag).as_str());
+            return;
+        }
+        let name = self.name();
+        self.fmt_tag_open(name, formatter, &self.escaped_attrs());
+        self.fmt_children_html(formatter, selection_writer, state, as_message);
+        self.fmt_tag_close(name, formatter);
+    }
+
+    /// The serializer for this custom tag in `state`, if any, along with
+    /// the tag.
+    fn custom_tag_serializer<'a>(
+        &'a self,
+        state: &'a ToHtmlState,
+    ) -> Option<(&'a str, &'a dyn CustomTagSerializer)> {
+        let ContainerNodeKind::Formatting(InlineFormatType::Custom(tag)) =
+            &self.kind
+        else {
+            return None;
+        };
+        let serializer = state.custom_tag_serializers.as_ref()?.get(tag)?;
+        Some((tag.as_str(), serializer.as_ref()))
+    }
+
+    /// Like [Self::fmt_default_html], but replacing any `target` and `rel`
+    /// of the link with ones that open it in a new tab without passing on
+    /// the referrer or a handle to the page.
+    fn fmt_safe_link_html(
+        &self,
+        formatter: &mut S,
+        selection_writer: Option<&mut SelectionWriter>,
+        state: &ToHtmlState,
+    ) {
+        let mut attrs: Vec<(S, S)> = self
+            .escaped_attrs()
+            .into_iter()
+            .flatten()
+            .filter(|(name, _)| {
+                let name = name.to_string();
+                !name.eq_ignore_ascii_case("target")
+                    && !name.eq_ignore_ascii_case("rel")
+            })
+            .collect();
+        attrs.push(("target".into(), "_blank".into()));
+        attrs.push(("rel".into(), "noreferrer noopener".into()));
+        let name = self.name();
+        self.fmt_tag_open(name, formatter, &Some(attrs));
+        self.fmt_children_html(formatter, selection_writer, state, true);
+        self.fmt_tag_close(name, formatter);
+    }
+
+    fn escaped_attrs(&self) -> Option<Vec<(S, S)>> {
+        self.attrs.as_ref().map(|attrs| {
+            attrs
+                .iter()
+                .map(|(name, value)| {
+                    let value = value.to_string();
+                    let escaped =
+                        html_escape::encode_double_quoted_attribute(&value);
+                    (name.clone(), S::from(escaped.as_ref()))
+                })
+                .collect()
+        })
+    }
+
     fn fmt_paragraph_html(
         &self,
         formatter: &mut S,
@@ -706,13 +1069,27 @@ impl<S: UnicodeString> ContainerNode<S> {
         let as_message = true;
         assert!(matches!(self.kind, ContainerNodeKind::Paragraph));
 
+        // Paragraphs are usually written as lines separated by line breaks,
+        // but an aligned one needs its tag to hold the alignment.
+        if self.alignment().is_some() {
+            let name = self.name();
+            self.fmt_tag_open(name, formatter, &self.attrs);
+            self.fmt_children_html(
+                formatter,
+                selection_writer,
+                state,
+                as_message,
+            );
+            self.fmt_tag_close(name, formatter);
+            return;
+        }
+
         // If the previous node was a paragraph, it expected this node to
         // be a block node and break onto a new line.
-        if state
-            .prev_sibling
-            .as_ref()
-            .is_some_and(|k| matches!(k, DomNodeKind::Paragraph))
-        {
+        if state.prev_sibling.as_ref().is_some_and(|k| {
+            matches!(k, DomNodeKind::Paragraph)
+                && !state.prev_sibling_is_aligned
+        }) {
             formatter.push("<br />");
         }
 
@@ -729,6 +1106,26 @@ impl<S: UnicodeString> ContainerNode<S> {
         }
     }
 
+    fn fmt_heading_html(
+        &self,
+        formatter: &mut S,
+        selection_writer: Option<&mut SelectionWriter>,
+        state: &ToHtmlState,
+        as_message: bool,
+    ) {
+        assert!(matches!(self.kind, ContainerNodeKind::Heading(_)));
+        let name = self.name();
+
+        self.fmt_tag_open(name, formatter, &self.attrs);
+        // Like an empty paragraph, an empty heading needs a placeholder to
+        // give it a height and the caret somewhere to go.
+        if !as_message && self.is_empty() {
+            formatter.push(char::nbsp());
+        }
+        self.fmt_children_html(formatter, selection_writer, state, as_message);
+        self.fmt_tag_close(name, formatter);
+    }
+
     fn fmt_code_paragraph_html(
         &self,
         formatter: &mut S,
@@ -737,7 +1134,10 @@ impl<S: UnicodeString> ContainerNode<S> {
         as_message: bool,
     ) {
         assert!(matches!(self.kind, ContainerNodeKind::Paragraph));
-        if self.is_empty()
+        // The placeholder only gives the caret somewhere to go, so messages
+        // keep the exact whitespace of the code block.
+        if !as_message
+            && self.is_empty()
             && (state.next_sibling.is_none() || state.prev_sibling.is_none())
         {
             formatter.push(char::nbsp());
@@ -756,11 +1156,12 @@ impl<S: UnicodeString> ContainerNode<S> {
         as_message: bool,
     ) {
         assert!(matches!(self.kind, ContainerNodeKind::CodeBlock));
-        self.fmt_tag_open(&S::from("pre"), formatter, &self.attrs);
+        self.fmt_tag_open(&S::from("pre"), formatter, &None::<Vec<(S, S)>>);
         let mut state = state.clone();
         state.is_inside_code_block = true;
 
-        self.fmt_tag_open(&S::from("code"), formatter, &None::<Vec<(S, S)>>);
+        // Any attributes (e.g. the language class) belong on the code tag
+        self.fmt_tag_open(&S::from("code"), formatter, &self.attrs);
 
         self.fmt_children_html(formatter, selection_writer, &state, as_message);
 
@@ -768,6 +1169,41 @@ impl<S: UnicodeString> ContainerNode<S> {
         self.fmt_tag_close(&S::from("pre"), formatter);
     }
 
+    /// The LaTeX source goes in `data-mx-maths`, and again in a code tag as
+    /// the fallback for clients which can't render maths.
+    fn fmt_math_html(
+        &self,
+        formatter: &mut S,
+        selection_writer: Option<&mut SelectionWriter>,
+        state: &ToHtmlState,
+        as_message: bool,
+    ) {
+        assert!(self.is_math());
+        let name = self.name();
+        // The source is read back from the attribute, so that is where the
+        // selection goes in the example format
+        let mut selection_writer = selection_writer;
+        let latex = match (selection_writer.take(), self.children.first()) {
+            (Some(w), Some(DomNode::Text(text))) => {
+                let mut latex = text.data().to_owned();
+                w.write_selection_text_node(&mut latex, 0, text);
+                latex.to_string()
+            }
+            (w, _) => {
+                selection_writer = w;
+                self.to_raw_text().to_string()
+            }
+        };
+        let latex = html_escape::encode_double_quoted_attribute(&latex);
+        let attrs =
+            Some(vec![(S::from("data-mx-maths"), S::from(latex.as_ref()))]);
+        self.fmt_tag_open(name, formatter, &attrs);
+        self.fmt_tag_open(&S::from("code"), formatter, &None::<Vec<(S, S)>>);
+        self.fmt_children_html(formatter, selection_writer, state, as_message);
+        self.fmt_tag_close(&S::from("code"), formatter);
+        self.fmt_tag_close(name, formatter);
+    }
+
     fn fmt_children_html(
         &self,
         formatter: &mut S,
@@ -794,19 +1230,22 @@ impl<S: UnicodeString> ContainerNode<S> {
             }
         }
     }
-    fn updated_state(
+    fn updated_state<'a>(
         &self,
-        initial_state: &ToHtmlState,
+        initial_state: &ToHtmlState<'a>,
         child_index: usize,
-    ) -> ToHtmlState {
+    ) -> ToHtmlState<'a> {
         let mut state = initial_state.clone();
         state.next_sibling =
             self.children().get(child_index + 1).map(|n| n.kind());
-        state.prev_sibling = if child_index == 0 {
-            None
-        } else {
-            self.children().get(child_index - 1).map(|n| n.kind())
-        };
+        let prev = child_index
+            .checked_sub(1)
+            .and_then(|i| self.children().get(i));
+        state.prev_sibling = prev.map(|n| n.kind());
+        state.prev_sibling_is_aligned = matches!(
+            prev,
+            Some(DomNode::Container(c)) if c.alignment().is_some()
+        );
         state
     }
 }
@@ -950,6 +1389,10 @@ where
                 fmt_inline_code(self, buffer, &mut options, as_message)?;
             }
 
+            Formatting(Custom(_) | TextColor(_) | Highlight(_)) => {
+                fmt_custom_format(self, buffer, &options, as_message)?;
+            }
+
             Link(url) => {
                 fmt_link(self, buffer, &options, url, as_message)?;
             }
@@ -973,6 +1416,18 @@ where
             Paragraph => {
                 fmt_paragraph(self, buffer, &options, as_message)?;
             }
+
+            Heading(level) => {
+                fmt_heading(self, buffer, &options, *level, as_message)?;
+            }
+
+            // Markdown has no maths, so it is written as HTML
+            Math | MathBlock => {
+                buffer.push(self.to_html());
+                if self.is_block_node() {
+                    buffer.push("\n");
+                }
+            }
         };
 
         return Ok(());
@@ -1017,9 +1472,15 @@ where
             // `foo_bar_baz`. We reckon it's good to follow this
             // trend to avoid unexpected behaviours for our users.
 
-            buffer.push("*");
+            let delimiter = if options.contains(MarkdownOptions::FALLBACK_TEXT)
+            {
+                "_"
+            } else {
+                "*"
+            };
+            buffer.push(delimiter);
             fmt_children(this, buffer, options, as_message)?;
-            buffer.push("*");
+            buffer.push(delimiter);
 
             Ok(())
         }
@@ -1044,9 +1505,16 @@ where
             // interpreted by various Markdown compilers out
             // there. Instead, it will produce `*__…__*`.
 
-            buffer.push("__");
+            // Plain text fallbacks conventionally use `*` for bold.
+            let delimiter = if options.contains(MarkdownOptions::FALLBACK_TEXT)
+            {
+                "*"
+            } else {
+                "__"
+            };
+            buffer.push(delimiter);
             fmt_children(this, buffer, options, as_message)?;
-            buffer.push("__");
+            buffer.push(delimiter);
 
             Ok(())
         }
@@ -1087,6 +1555,10 @@ where
             // Underline format is absent from Markdown. Let's
             // use raw HTML.
 
+            if options.contains(MarkdownOptions::FALLBACK_TEXT) {
+                return fmt_children(this, buffer, options, as_message);
+            }
+
             buffer.push("<u>");
             fmt_children(this, buffer, options, as_message)?;
             buffer.push("</u>");
@@ -1094,6 +1566,29 @@ where
             Ok(())
         }
 
+        #[inline(always)]
+        fn fmt_custom_format<S>(
+            this: &ContainerNode<S>,
+            buffer: &mut S,
+            options: &MarkdownOptions,
+            as_message: bool,
+        ) -> Result<(), MarkdownError<S>>
+        where
+            S: UnicodeString,
+        {
+            // Like underline, custom tags can only be kept as raw HTML.
+
+            if options.contains(MarkdownOptions::FALLBACK_TEXT) {
+                return fmt_children(this, buffer, options, as_message);
+            }
+
+            this.fmt_tag_open(this.name(), buffer, &this.escaped_attrs());
+            fmt_children(this, buffer, options, as_message)?;
+            this.fmt_tag_close(this.name(), buffer);
+
+            Ok(())
+        }
+
         #[inline(always)]
         fn fmt_inline_code<S>(
             this: &ContainerNode<S>,
@@ -1116,12 +1611,19 @@ where
             // backtick. Those spaces are removed during
             // normalization.
 
-            buffer.push("`` ");
+            let (open, close) =
+                if options.contains(MarkdownOptions::FALLBACK_TEXT) {
+                    ("`", "`")
+                } else {
+                    ("`` ", " ``")
+                };
+            buffer.push(open);
 
             options.insert(MarkdownOptions::IGNORE_LINE_BREAK);
+            options.insert(MarkdownOptions::IN_CODE);
             fmt_children(this, buffer, options, as_message)?;
 
-            buffer.push(" ``");
+            buffer.push(close);
 
             Ok(())
         }
@@ -1137,6 +1639,12 @@ where
         where
             S: UnicodeString,
         {
+            if options.contains(MarkdownOptions::FALLBACK_TEXT) {
+                return fmt_fallback_link(
+                    this, buffer, options, url, as_message,
+                );
+            }
+
             buffer.push('[');
 
             fmt_children(this, buffer, options, as_message)?;
@@ -1163,6 +1671,38 @@ where
             Ok(())
         }
 
+        /// In plain text, a link is its text followed by its URL in
+        /// brackets, unless the text already is the URL.
+        #[inline(always)]
+        fn fmt_fallback_link<S>(
+            this: &ContainerNode<S>,
+            buffer: &mut S,
+            options: &MarkdownOptions,
+            url: &S,
+            as_message: bool,
+        ) -> Result<(), MarkdownError<S>>
+        where
+            S: UnicodeString,
+        {
+            let mut text = S::default();
+            fmt_children(this, &mut text, options, as_message)?;
+
+            let url_string = url.to_string();
+            let text_string = text.to_string();
+            let text_is_url = text_string == url_string
+                || url_string.strip_prefix("mailto:") == Some(&text_string)
+                || url_string.strip_prefix("https://") == Some(&text_string);
+
+            buffer.push(text);
+            if !text_is_url {
+                buffer.push(" (");
+                buffer.push(url.clone());
+                buffer.push(")");
+            }
+
+            Ok(())
+        }
+
         #[inline(always)]
         fn fmt_list<S>(
             this: &ContainerNode<S>,
@@ -1221,6 +1761,18 @@ where
                             mention.name(),
                         )))
                     }
+
+                    DomNode::Image(image) => {
+                        return Err(MarkdownError::InvalidListItem(Some(
+                            image.name(),
+                        )))
+                    }
+
+                    DomNode::HorizontalRule(rule) => {
+                        return Err(MarkdownError::InvalidListItem(Some(
+                            rule.name(),
+                        )))
+                    }
                 };
 
                 // What's the current indentation, for this specific list only.
@@ -1322,8 +1874,14 @@ where
         where
             S: UnicodeString,
         {
-            buffer.push("```\n");
-            fmt_children(this, buffer, options, as_message)?;
+            buffer.push("```");
+            if let Some(language) = this.code_block_language() {
+                buffer.push(language.as_str());
+            }
+            buffer.push("\n");
+            let mut options = *options;
+            options.insert(MarkdownOptions::IN_CODE);
+            fmt_children(this, buffer, &options, as_message)?;
             buffer.push("\n```\n");
 
             Ok(())
@@ -1360,9 +1918,73 @@ where
 
             Ok(())
         }
+
+        #[inline(always)]
+        fn fmt_heading<S>(
+            this: &ContainerNode<S>,
+            buffer: &mut S,
+            options: &MarkdownOptions,
+            level: HeadingLevel,
+            as_message: bool,
+        ) -> Result<(), MarkdownError<S>>
+        where
+            S: UnicodeString,
+        {
+            buffer.push("#".repeat(level.number().into()).as_str());
+            buffer.push(" ");
+            fmt_children(this, buffer, options, as_message)?;
+
+            Ok(())
+        }
+    }
+}
+
+/// What happens to an attribute of a link, formatting or other container
+/// node when the node is split in two, e.g. by formatting part of a link or
+/// pressing enter in the middle of a span. Mentions are never split, so they
+/// always keep all of their attributes.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum SplitAttributePolicy {
+    /// Both halves keep the attribute, so that e.g. both halves of a link
+    /// keep its `href`, and both halves of a spoiler keep its
+    /// `data-mx-spoiler`. This is the case for most attributes.
+    Duplicate,
+    /// Only the node that was split keeps the attribute, and the new node
+    /// holding the other half doesn't get it. This is for the attributes
+    /// that must be unique in a document, i.e. `id` and `name`.
+    KeepOnOriginal,
+}
+
+impl SplitAttributePolicy {
+    pub fn for_attribute(name: &str) -> Self {
+        match name {
+            "id" | "name" => Self::KeepOnOriginal,
+            _ => Self::Duplicate,
+        }
     }
 }
 
+/// The attributes kept on custom tags, which are the ones that matter for
+/// the sort of inline tags that might be allowed, e.g. `<abbr title="..">`.
+pub(crate) const CUSTOM_TAG_ATTRIBUTES: [&str; 3] =
+    ["title", "datetime", "lang"];
+
+/// Whether an attribute is a `data-*` attribute that the editor keeps on
+/// links and spans, e.g. `data-mention-type`. Only lowercase names made of
+/// ASCII letters, digits and `-` are kept. Colours are formats of their own,
+/// so `data-mx-color` and `data-mx-bg-color` are not included.
+pub(crate) fn is_data_attribute(name: &str) -> bool {
+    if matches!(name, "data-mx-color" | "data-mx-bg-color") {
+        return false;
+    }
+    name.strip_prefix("data-").map_or(false, |suffix| {
+        !suffix.is_empty()
+            && suffix.chars().all(|c| {
+                c.is_ascii_lowercase() || c.is_ascii_digit() || c == '-'
+            })
+    })
+}
+
 #[cfg(test)]
 mod test {
     use widestring::Utf16String;
@@ -1580,6 +2202,40 @@ mod test {
         container.slice_after(42);
     }
 
+    #[test]
+    fn slicing_link_keeps_unique_attributes_on_original() {
+        let mut link = ContainerNode::new_link(
+            utf16("https://matrix.org"),
+            vec![text_node("abcdef")],
+            vec![(utf16("id"), utf16("top")), (utf16("title"), utf16("t"))],
+        );
+        let after = link.slice_after(3);
+        assert_eq!(
+            link.to_html(),
+            "<a id=\"top\" title=\"t\" href=\"https://matrix.org\">abc</a>"
+        );
+        assert_eq!(
+            after.to_html(),
+            "<a title=\"t\" href=\"https://matrix.org\">def</a>"
+        );
+    }
+
+    #[test]
+    fn split_attribute_policy_only_keeps_unique_attributes_on_original() {
+        for name in ["id", "name"] {
+            assert_eq!(
+                SplitAttributePolicy::for_attribute(name),
+                SplitAttributePolicy::KeepOnOriginal
+            );
+        }
+        for name in ["href", "title", "data-mx-spoiler", "data-mention-type"] {
+            assert_eq!(
+                SplitAttributePolicy::for_attribute(name),
+                SplitAttributePolicy::Duplicate
+            );
+        }
+    }
+
     #[test]
     fn paragraph_to_message_html() {
         let model = cm("<p>&nbsp;</p><p>&nbsp;</p><p>Hello!</p><p>&nbsp;</p>|");
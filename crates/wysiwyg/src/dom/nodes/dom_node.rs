@@ -22,12 +22,11 @@ use crate::dom::to_markdown::{MarkdownError, MarkdownOptions, ToMarkdown};
 use crate::dom::to_plain_text::ToPlainText;
 use crate::dom::to_raw_text::ToRawText;
 use crate::dom::to_tree::ToTree;
-use crate::dom::unicode_string::UnicodeStrExt;
 use crate::dom::{self, UnicodeString};
-use crate::{InlineFormatType, ListType};
+use crate::{HeadingLevel, InlineFormatType, ListType};
 
 use super::mention_node::UriParseError;
-use super::MentionNode;
+use super::{HorizontalRuleNode, ImageNode, MentionNode};
 
 #[derive(Clone, Debug, PartialEq)]
 pub enum DomNode<S>
@@ -38,6 +37,8 @@ where
     Text(TextNode<S>),
     LineBreak(LineBreakNode<S>),
     Mention(MentionNode<S>),
+    Image(ImageNode<S>),
+    HorizontalRule(HorizontalRuleNode<S>),
 }
 
 impl<S: dom::unicode_string::UnicodeString> Default for DomNode<S> {
@@ -104,12 +105,21 @@ where
         DomNode::Container(ContainerNode::new_paragraph(children))
     }
 
+    pub fn new_heading(
+        level: HeadingLevel,
+        children: Vec<DomNode<S>>,
+    ) -> DomNode<S> {
+        DomNode::Container(ContainerNode::new_heading(level, children))
+    }
+
     pub fn handle(&self) -> DomHandle {
         match self {
             DomNode::Container(n) => n.handle(),
             DomNode::LineBreak(n) => n.handle(),
             DomNode::Text(n) => n.handle(),
             DomNode::Mention(n) => n.handle(),
+            DomNode::Image(n) => n.handle(),
+            DomNode::HorizontalRule(n) => n.handle(),
         }
     }
 
@@ -119,15 +129,19 @@ where
             DomNode::LineBreak(n) => n.set_handle(handle),
             DomNode::Text(n) => n.set_handle(handle),
             DomNode::Mention(n) => n.set_handle(handle),
+            DomNode::Image(n) => n.set_handle(handle),
+            DomNode::HorizontalRule(n) => n.set_handle(handle),
         }
     }
 
     pub fn text_len(&self) -> usize {
         match self {
-            DomNode::Text(n) => n.data().len(),
+            DomNode::Text(n) => n.text_len(),
             DomNode::LineBreak(n) => n.text_len(),
             DomNode::Container(n) => n.text_len(),
             DomNode::Mention(n) => n.text_len(),
+            DomNode::Image(n) => n.text_len(),
+            DomNode::HorizontalRule(n) => n.text_len(),
         }
     }
 
@@ -159,6 +173,27 @@ where
         MentionNode::new_at_room(attributes)
     }
 
+    pub fn new_image(
+        src: S,
+        alt: S,
+        width: Option<u32>,
+        height: Option<u32>,
+    ) -> DomNode<S> {
+        DomNode::Image(ImageNode::new(src, alt, width, height))
+    }
+
+    pub fn new_custom_emoji(src: S, shortcode: S) -> DomNode<S> {
+        DomNode::Image(ImageNode::new_custom_emoji(src, shortcode))
+    }
+
+    pub fn new_math(latex: S, display: bool) -> DomNode<S> {
+        DomNode::Container(ContainerNode::new_math(latex, display))
+    }
+
+    pub fn new_horizontal_rule() -> DomNode<S> {
+        DomNode::HorizontalRule(HorizontalRuleNode::default())
+    }
+
     pub fn is_container_node(&self) -> bool {
         matches!(self, DomNode::Container(_))
     }
@@ -171,6 +206,14 @@ where
         matches!(self, DomNode::Mention(_))
     }
 
+    pub fn is_image_node(&self) -> bool {
+        matches!(self, DomNode::Image(_))
+    }
+
+    pub fn is_horizontal_rule(&self) -> bool {
+        matches!(self, DomNode::HorizontalRule(_))
+    }
+
     /// Returns `true` if the dom node is [`LineBreak`].
     ///
     /// [`LineBreak`]: DomNode::LineBreak
@@ -201,7 +244,11 @@ where
     }
 
     pub(crate) fn is_block_node(&self) -> bool {
-        matches!(self, Self::Container(container) if container.is_block_node())
+        match self {
+            Self::Container(container) => container.is_block_node(),
+            Self::HorizontalRule(_) => true,
+            _ => false,
+        }
     }
 
     pub(crate) fn is_list_item(&self) -> bool {
@@ -259,6 +306,8 @@ where
             DomNode::LineBreak(_) => DomNodeKind::LineBreak,
             DomNode::Container(n) => DomNodeKind::from_container_kind(n.kind()),
             DomNode::Mention(_) => DomNodeKind::Mention,
+            DomNode::Image(_) => DomNodeKind::Image,
+            DomNode::HorizontalRule(_) => DomNodeKind::HorizontalRule,
         }
     }
 
@@ -269,7 +318,9 @@ where
             DomNode::Container(c) => c.has_leading_line_break(),
             DomNode::Text(_) => false,
             DomNode::LineBreak(_) => true,
-            DomNode::Mention(_) => false,
+            DomNode::Mention(_)
+            | DomNode::Image(_)
+            | DomNode::HorizontalRule(_) => false,
         }
     }
 
@@ -286,8 +337,13 @@ where
         match (self, other_node) {
             (DomNode::Container(c1), DomNode::Container(c2)) => {
                 c1.kind() == c2.kind()
+                    && c1.attributes() == c2.attributes()
                     && !c1.is_list_item()
-                    && !matches!(c1.kind(), ContainerNodeKind::Paragraph)
+                    && !matches!(
+                        c1.kind(),
+                        ContainerNodeKind::Paragraph
+                            | ContainerNodeKind::Heading(_)
+                    )
             }
             (DomNode::Text(_), DomNode::Text(_)) => true,
             _ => false,
@@ -321,6 +377,10 @@ where
             DomNode::Text(t) => DomNode::Text(t.slice_after(position)),
             DomNode::LineBreak(_) => panic!("Can't slice a linebreak"),
             DomNode::Mention(_) => panic!("Can't slice a mention"),
+            DomNode::Image(_) => panic!("Can't slice an image"),
+            DomNode::HorizontalRule(_) => {
+                panic!("Can't slice a horizontal rule")
+            }
         }
     }
 
@@ -336,6 +396,10 @@ where
             DomNode::Text(t) => DomNode::Text(t.slice_before(position)),
             DomNode::LineBreak(_) => panic!("Can't slice a linebreak"),
             DomNode::Mention(_) => panic!("Can't slice a mention"),
+            DomNode::Image(_) => panic!("Can't slice an image"),
+            DomNode::HorizontalRule(_) => {
+                panic!("Can't slice a horizontal rule")
+            }
         }
     }
 
@@ -377,6 +441,14 @@ where
                     "Handle {:?} is invalid: refers to the child of a mention node, \
                     but text nodes cannot have children.", node_handle
                 ),
+                DomNode::Image(_) => panic!(
+                    "Handle {:?} is invalid: refers to the child of an image node, \
+                    but image nodes cannot have children.", node_handle
+                ),
+                DomNode::HorizontalRule(_) => panic!(
+                    "Handle {:?} is invalid: refers to the child of a horizontal \
+                    rule, but horizontal rules cannot have children.", node_handle
+                ),
             }
         }
         node
@@ -385,7 +457,7 @@ where
     pub fn is_empty(&self) -> bool {
         match self {
             DomNode::Container(container) => container.is_empty(),
-            DomNode::Text(text_node) => text_node.data().is_empty(),
+            DomNode::Text(text_node) => text_node.text_len() == 0,
             _ => false,
         }
     }
@@ -394,7 +466,7 @@ where
     pub fn has_no_text(&self) -> bool {
         match self {
             DomNode::Container(c) => c.has_no_text(),
-            DomNode::Text(t) => t.data().is_empty(),
+            DomNode::Text(t) => t.text_len() == 0,
             _ => false,
         }
     }
@@ -426,6 +498,7 @@ where
         state: &ToHtmlState,
         as_message: bool,
     ) {
+        let html_start = buf.len();
         match self {
             DomNode::Container(s) => {
                 s.fmt_html(buf, selection_writer, state, as_message)
@@ -439,6 +512,19 @@ where
             DomNode::Mention(s) => {
                 s.fmt_html(buf, selection_writer, state, as_message)
             }
+            DomNode::Image(s) => {
+                s.fmt_html(buf, selection_writer, state, as_message)
+            }
+            DomNode::HorizontalRule(s) => {
+                s.fmt_html(buf, selection_writer, state, as_message)
+            }
+        }
+        if let Some(source_map) = &state.source_map {
+            source_map.borrow_mut().push((
+                self.handle(),
+                html_start,
+                buf.len(),
+            ));
         }
     }
 }
@@ -453,6 +539,8 @@ where
             DomNode::LineBreak(n) => n.to_raw_text(),
             DomNode::Text(n) => n.to_raw_text(),
             DomNode::Mention(n) => n.to_raw_text(),
+            DomNode::Image(n) => n.to_raw_text(),
+            DomNode::HorizontalRule(n) => n.to_raw_text(),
         }
     }
 }
@@ -467,6 +555,8 @@ where
             DomNode::LineBreak(n) => n.to_plain_text(),
             DomNode::Text(n) => n.to_plain_text(),
             DomNode::Mention(n) => n.to_plain_text(),
+            DomNode::Image(n) => n.to_plain_text(),
+            DomNode::HorizontalRule(n) => n.to_plain_text(),
         }
     }
 }
@@ -481,6 +571,10 @@ where
             DomNode::LineBreak(n) => n.to_tree_display(continuous_positions),
             DomNode::Text(n) => n.to_tree_display(continuous_positions),
             DomNode::Mention(n) => n.to_tree_display(continuous_positions),
+            DomNode::Image(n) => n.to_tree_display(continuous_positions),
+            DomNode::HorizontalRule(n) => {
+                n.to_tree_display(continuous_positions)
+            }
         }
     }
 }
@@ -508,6 +602,12 @@ where
             DomNode::Mention(node) => {
                 node.fmt_markdown(buffer, options, as_message)
             }
+            DomNode::Image(node) => {
+                node.fmt_markdown(buffer, options, as_message)
+            }
+            DomNode::HorizontalRule(node) => {
+                node.fmt_markdown(buffer, options, as_message)
+            }
         }
     }
 }
@@ -518,6 +618,8 @@ pub enum DomNodeKind {
     Text,
     LineBreak,
     Mention,
+    Image,
+    HorizontalRule,
     Formatting(InlineFormatType),
     Link,
     ListItem,
@@ -525,6 +627,9 @@ pub enum DomNodeKind {
     CodeBlock,
     Quote,
     Paragraph,
+    Heading,
+    Math,
+    MathBlock,
 }
 
 impl DomNodeKind {
@@ -542,6 +647,9 @@ impl DomNodeKind {
             ContainerNodeKind::CodeBlock => DomNodeKind::CodeBlock,
             ContainerNodeKind::Quote => DomNodeKind::Quote,
             ContainerNodeKind::Paragraph => DomNodeKind::Paragraph,
+            ContainerNodeKind::Heading(_) => DomNodeKind::Heading,
+            ContainerNodeKind::Math => DomNodeKind::Math,
+            ContainerNodeKind::MathBlock => DomNodeKind::MathBlock,
         }
     }
 
@@ -558,27 +666,42 @@ impl DomNodeKind {
                 | Self::CodeBlock
                 | Self::Quote
                 | Self::Paragraph
+                | Self::Heading
+                | Self::HorizontalRule
+                | Self::MathBlock
         )
     }
 
     pub fn is_leaf_kind(&self) -> bool {
         match self {
-            Self::Text | Self::LineBreak | Self::Mention => true,
-            Self::Generic
+            Self::Text | Self::LineBreak | Self::Mention | Self::Image => true,
+            // A horizontal rule has no content, so it is treated like an
+            // empty block rather than a leaf
+            Self::HorizontalRule
+            | Self::Generic
             | Self::Formatting(_)
             | Self::Link
             | Self::ListItem
             | Self::List
             | Self::CodeBlock
             | Self::Quote
-            | Self::Paragraph => false,
+            | Self::Paragraph
+            | Self::Heading
+            | Self::Math
+            | Self::MathBlock => false,
         }
     }
 
+    /// Whether the text inside nodes of this kind is source code, which
+    /// can't contain e.g. links or mentions. The LaTeX source of maths
+    /// counts as code.
     pub fn is_code_kind(&self) -> bool {
         matches!(
             self,
-            Self::CodeBlock | Self::Formatting(InlineFormatType::InlineCode)
+            Self::CodeBlock
+                | Self::Formatting(InlineFormatType::InlineCode)
+                | Self::Math
+                | Self::MathBlock
         )
     }
 
@@ -589,7 +712,7 @@ impl DomNodeKind {
 
 #[cfg(test)]
 mod test {
-    use widestring::Utf16String;
+    use crate::tests::testutils_composer_model::TestString;
 
     use crate::{DomHandle, DomNode, InlineFormatType, ToHtml, UnicodeString};
 
@@ -683,7 +806,7 @@ mod test {
     }
 
     /// Result HTML is "<del><em>abc</em>def</del>".
-    fn format_container_with_nested_children() -> DomNode<Utf16String> {
+    fn format_container_with_nested_children() -> DomNode<TestString> {
         let italic = format_container_with_handle_and_children(
             InlineFormatType::Italic,
             vec![text_node("abc")],
@@ -698,9 +821,9 @@ mod test {
 
     fn format_container_with_handle_and_children<'a>(
         format: InlineFormatType,
-        children: Vec<DomNode<Utf16String>>,
+        children: Vec<DomNode<TestString>>,
         raw_handle: impl IntoIterator<Item = &'a usize>,
-    ) -> DomNode<Utf16String> {
+    ) -> DomNode<TestString> {
         let mut node = DomNode::new_formatting(format, children);
         let handle =
             DomHandle::from_raw(raw_handle.into_iter().cloned().collect());
@@ -710,7 +833,7 @@ mod test {
 
     fn list_item_with_handle<'a>(
         raw_handle: impl IntoIterator<Item = &'a usize>,
-    ) -> DomNode<Utf16String> {
+    ) -> DomNode<TestString> {
         let mut node = DomNode::new_list_item(vec![]);
         let handle =
             DomHandle::from_raw(raw_handle.into_iter().cloned().collect());
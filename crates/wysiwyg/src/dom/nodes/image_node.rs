@@ -0,0 +1,279 @@
+// Copyright 2023 The Matrix.org Foundation C.I.C.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use crate::composer_model::example_format::SelectionWriter;
+use crate::dom::dom_handle::DomHandle;
+use crate::dom::to_html::{ToHtml, ToHtmlState};
+use crate::dom::to_markdown::{
+    push_escaped_markdown, MarkdownError, MarkdownOptions, ToMarkdown,
+};
+use crate::dom::to_plain_text::ToPlainText;
+use crate::dom::to_raw_text::ToRawText;
+use crate::dom::to_tree::ToTree;
+use crate::dom::unicode_string::UnicodeStringExt;
+use crate::dom::UnicodeString;
+
+/// The height custom emoji are shown at, in pixels, so that they fit in
+/// with the text around them.
+const CUSTOM_EMOJI_HEIGHT: u32 = 32;
+
+/// Whether `uri` is an `mxc://` URI, the only kind of image source that
+/// may be inserted or kept when parsing, so that images can't load from
+/// anywhere else or run script.
+pub fn is_mxc_uri(uri: &str) -> bool {
+    uri.strip_prefix("mxc://").is_some_and(|rest| {
+        !rest.is_empty() && !rest.contains(char::is_whitespace)
+    })
+}
+
+/// An inline image, e.g. `<img src="mxc://example.org/abc" alt="cat" />`.
+/// Like a mention, it can't be edited and takes up a single position in the
+/// editor.
+///
+/// Custom emoji are images too, marked with `data-mx-emoticon` and with
+/// their shortcode as the alt text.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ImageNode<S>
+where
+    S: UnicodeString,
+{
+    src: S,
+    alt: S,
+    width: Option<u32>,
+    height: Option<u32>,
+    emoticon: bool,
+    handle: DomHandle,
+}
+
+impl<S> ImageNode<S>
+where
+    S: UnicodeString,
+{
+    /// Create a new ImageNode. Check `src` with [is_mxc_uri] first, as only
+    /// images from the media repository are allowed in the content.
+    ///
+    /// NOTE: Its handle() will be unset until it is added to a Dom, e.g. by
+    /// appending it to another node.
+    pub fn new(
+        src: S,
+        alt: S,
+        width: Option<u32>,
+        height: Option<u32>,
+    ) -> Self {
+        Self {
+            src,
+            alt,
+            width,
+            height,
+            emoticon: false,
+            handle: DomHandle::new_unset(),
+        }
+    }
+
+    /// Create a custom emoji, shown at the height of the text around it.
+    /// `shortcode` is used as the alt text, with colons added around it if
+    /// it doesn't have them, e.g. `:party:`.
+    pub fn new_custom_emoji(src: S, shortcode: S) -> Self {
+        let shortcode = shortcode.to_string();
+        let shortcode = shortcode.trim_matches(':');
+        Self {
+            emoticon: true,
+            ..Self::new(
+                src,
+                S::from(format!(":{shortcode}:").as_str()),
+                None,
+                Some(CUSTOM_EMOJI_HEIGHT),
+            )
+        }
+    }
+
+    /// Mark this image as a custom emoji, e.g. when it was parsed from an
+    /// `<img>` with `data-mx-emoticon`.
+    pub(crate) fn set_custom_emoji(&mut self, emoticon: bool) {
+        self.emoticon = emoticon;
+    }
+
+    pub fn name(&self) -> S {
+        S::from("img")
+    }
+
+    pub fn src(&self) -> &S {
+        &self.src
+    }
+
+    pub fn alt(&self) -> &S {
+        &self.alt
+    }
+
+    pub fn width(&self) -> Option<u32> {
+        self.width
+    }
+
+    pub fn height(&self) -> Option<u32> {
+        self.height
+    }
+
+    /// Whether this is a custom emoji rather than an ordinary image.
+    pub fn is_custom_emoji(&self) -> bool {
+        self.emoticon
+    }
+
+    pub fn set_handle(&mut self, handle: DomHandle) {
+        self.handle = handle;
+    }
+
+    pub fn handle(&self) -> DomHandle {
+        self.handle.clone()
+    }
+
+    pub fn text_len(&self) -> usize {
+        // An image can't be edited, so like a mention it takes up a
+        // single position.
+        1
+    }
+
+    /// The attributes of the `<img>` tag, escaped so they can be written
+    /// inside double quotes.
+    fn escaped_attrs(&self) -> Vec<(S, S)> {
+        let escape = |value: &S| {
+            let value = value.to_string();
+            S::from(
+                html_escape::encode_double_quoted_attribute(&value).as_ref(),
+            )
+        };
+        let mut attrs = vec![
+            ("src".into(), escape(&self.src)),
+            ("alt".into(), escape(&self.alt)),
+        ];
+        if self.emoticon {
+            attrs.push(("title".into(), escape(&self.alt)));
+        }
+        if let Some(width) = self.width {
+            attrs.push(("width".into(), width.to_string().as_str().into()));
+        }
+        if let Some(height) = self.height {
+            attrs.push(("height".into(), height.to_string().as_str().into()));
+        }
+        attrs
+    }
+
+    fn fmt_img_tag(&self, formatter: &mut S) {
+        formatter.push("<img");
+        if self.emoticon {
+            formatter.push(" data-mx-emoticon");
+        }
+        for (name, value) in self.escaped_attrs() {
+            formatter.push(' ');
+            formatter.push(name);
+            formatter.push("=\"");
+            formatter.push(value);
+            formatter.push('"');
+        }
+        formatter.push(" />");
+    }
+}
+
+impl<S> ToHtml<S> for ImageNode<S>
+where
+    S: UnicodeString,
+{
+    fn fmt_html(
+        &self,
+        formatter: &mut S,
+        selection_writer: Option<&mut SelectionWriter>,
+        _: &ToHtmlState,
+        _as_message: bool,
+    ) {
+        let cur_pos = formatter.len();
+        self.fmt_img_tag(formatter);
+        if let Some(sel_writer) = selection_writer {
+            sel_writer.write_selection_image_node(formatter, cur_pos, self);
+        }
+    }
+}
+
+impl<S> ToRawText<S> for ImageNode<S>
+where
+    S: UnicodeString,
+{
+    fn to_raw_text(&self) -> S {
+        self.alt.clone()
+    }
+}
+
+impl<S> ToPlainText<S> for ImageNode<S>
+where
+    S: UnicodeString,
+{
+    fn to_plain_text(&self) -> S {
+        self.alt.clone()
+    }
+}
+
+impl<S> ToTree<S> for ImageNode<S>
+where
+    S: UnicodeString,
+{
+    fn to_tree_display(&self, continuous_positions: Vec<usize>) -> S {
+        let mut description: S = self.name();
+
+        description.push(" \"");
+        description.push(self.alt.clone());
+        description.push("\", ");
+        description.push(self.src.clone());
+
+        self.tree_line(
+            description,
+            self.handle.raw().len(),
+            continuous_positions,
+        )
+    }
+}
+
+impl<S> ToMarkdown<S> for ImageNode<S>
+where
+    S: UnicodeString,
+{
+    fn fmt_markdown(
+        &self,
+        buffer: &mut S,
+        options: &MarkdownOptions,
+        _as_message: bool,
+    ) -> Result<(), MarkdownError<S>> {
+        if options.contains(MarkdownOptions::FALLBACK_TEXT) {
+            buffer.push(self.alt.clone());
+        } else if self.emoticon || self.width.is_some() || self.height.is_some()
+        {
+            // Markdown images have no size or other attributes, but HTML is
+            // valid markdown.
+            self.fmt_img_tag(buffer);
+        } else {
+            buffer.push("![");
+            push_escaped_markdown(buffer, &self.alt, options);
+            // See the link destination in `ContainerNode::fmt_markdown`.
+            buffer.push("](<");
+            buffer.push(
+                self.src
+                    .to_string()
+                    .replace('<', "\\<")
+                    .replace('>', "\\>")
+                    .replace('(', "\\(")
+                    .replace(')', "\\)")
+                    .as_str(),
+            );
+            buffer.push(">)");
+        }
+        Ok(())
+    }
+}
@@ -16,12 +16,15 @@ use crate::composer_model::delete_text::Direction;
 use crate::composer_model::example_format::SelectionWriter;
 use crate::dom::dom_handle::DomHandle;
 use crate::dom::to_html::{ToHtml, ToHtmlState};
-use crate::dom::to_markdown::{MarkdownError, MarkdownOptions, ToMarkdown};
+use crate::dom::to_markdown::{
+    push_escaped_markdown, MarkdownError, MarkdownOptions, ToMarkdown,
+};
 use crate::dom::to_plain_text::ToPlainText;
 use crate::dom::to_raw_text::ToRawText;
 use crate::dom::to_tree::ToTree;
 use crate::dom::unicode_string::{UnicodeStr, UnicodeStrExt, UnicodeStringExt};
 use crate::dom::UnicodeString;
+use crate::Locale;
 use html_escape;
 use std::ops::Range;
 
@@ -82,10 +85,12 @@ where
     }
 
     pub fn replace_range(&mut self, data: S, start: usize, end: usize) {
-        let mut new_data = self.data[..start].to_owned();
-        new_data.push(data);
-        new_data.push(self.data[end..].to_owned());
-        self.data = new_data;
+        self.data.replace_range(start..end, &data);
+    }
+
+    /// The length of the text, in code units of the current encoding.
+    pub fn text_len(&self) -> usize {
+        self.data.len()
     }
 
     pub fn handle(&self) -> DomHandle {
@@ -129,7 +134,20 @@ where
     ) -> Option<char> {
         self.data()
             .chars()
-            .nth(direction.get_index_from_cursor(offset))
+            .nth(direction.get_index_from_cursor(self.char_index(offset)))
+    }
+
+    /// The index in chars of the character starting at the given offset in
+    /// code units, which differ for characters outside the BMP in UTF-16.
+    fn char_index(&self, offset: usize) -> usize {
+        let data = self.data();
+        let mut units = 0;
+        data.chars()
+            .take_while(|c| {
+                units += data.char_len(c);
+                units <= offset
+            })
+            .count()
     }
 
     /// This gets the character type at the cursor offset, considering the
@@ -138,9 +156,22 @@ where
         &self,
         offset: usize,
         direction: &Direction,
+        locale: &Locale,
     ) -> Option<CharType> {
-        let char = self.char_at_offset(offset, direction);
-        char.map(get_char_type)
+        let char = self.char_at_offset(offset, direction)?;
+        let index = direction.get_index_from_cursor(self.char_index(offset));
+        if locale.joins_words(char) && index > 0 {
+            let is_word_char = |c: Option<char>| {
+                c.map_or(false, |c| get_char_type(c) == CharType::Other)
+            };
+            let mut chars = self.data().chars().skip(index - 1);
+            let before = chars.next();
+            let after = chars.nth(1);
+            if is_word_char(before) && is_word_char(after) {
+                return Some(CharType::Other);
+            }
+        }
+        Some(get_char_type(char))
     }
 
     /// When moving through a node, the cursor counts as inside the node
@@ -150,7 +181,7 @@ where
         current_offset: usize,
         direction: &Direction,
     ) -> bool {
-        let node_length = self.data().len();
+        let node_length = self.text_len();
         match direction {
             Direction::Forwards => current_offset < node_length,
             Direction::Backwards => current_offset > 0,
@@ -159,7 +190,7 @@ where
 
     /// Required due to zero length text node existence
     pub fn is_empty(&self) -> bool {
-        self.data().len() != 0
+        self.text_len() != 0
     }
 
     /// Push content of the given text node into self.
@@ -194,7 +225,7 @@ where
 }
 
 /// Given a character, determine its type
-fn get_char_type(c: char) -> CharType {
+pub(crate) fn get_char_type(c: char) -> CharType {
     // in order to determine where a ctrl/opt + delete type operation finishes
     // we need to distinguish between whitespace (nb no newline characters), punctuation
     // and then everything else is treated as the same type
@@ -228,9 +259,7 @@ where
         if !state.is_inside_code_block {
             escaped = escaped.replace("  ", "\u{A0}\u{A0}");
 
-            if state.next_sibling.is_none()
-                && escaped.chars().next_back().map_or(false, |c| c == ' ')
-            {
+            if state.next_sibling.is_none() && escaped.ends_with(' ') {
                 // If this is the last node and it ends in a space, replace that
                 // space with a non-breaking one.
                 escaped.replace_range(escaped.len() - 1.., "\u{A0}");
@@ -293,10 +322,10 @@ where
     fn fmt_markdown(
         &self,
         buffer: &mut S,
-        _options: &MarkdownOptions,
+        options: &MarkdownOptions,
         _as_message: bool,
     ) -> Result<(), MarkdownError<S>> {
-        buffer.push(self.data.to_owned());
+        push_escaped_markdown(buffer, &self.data, options);
 
         Ok(())
     }
@@ -308,6 +337,7 @@ mod test {
     use crate::composer_model::delete_text::Direction;
     use crate::dom::nodes::text_node::CharType;
     use crate::tests::testutils_conversion::utf16;
+    use crate::Locale;
 
     use super::{get_char_type, TextNode};
 
@@ -338,6 +368,25 @@ mod test {
         assert_eq!(get_char_type('z'), CharType::Other);
     }
 
+    #[test]
+    #[cfg_attr(feature = "test-utf32", ignore = "uses UTF-16 offsets")]
+    fn char_type_at_offset_after_characters_outside_the_bmp() {
+        let test_node = TextNode::from(utf16("😀 it's"));
+        let locale = Locale::from_tag("en");
+        assert_eq!(
+            test_node.char_type_at_offset(2, &Direction::Forwards, &locale),
+            Some(CharType::Whitespace)
+        );
+        assert_eq!(
+            test_node.char_type_at_offset(3, &Direction::Backwards, &locale),
+            Some(CharType::Whitespace)
+        );
+        assert_eq!(
+            test_node.char_type_at_offset(5, &Direction::Forwards, &locale),
+            Some(CharType::Other)
+        );
+    }
+
     #[test]
     fn offset_is_inside_node_end_of_node() {
         let test_node = TextNode::from(utf16("test"));
@@ -57,3 +57,7 @@ mod sys {
 use sys::*;
 
 pub use parse::parse;
+pub use parse::{
+    parse_with_options, parse_with_whitespace_policy, HtmlParseOptions,
+    WhitespacePolicy,
+};
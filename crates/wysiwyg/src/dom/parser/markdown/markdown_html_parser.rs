@@ -28,6 +28,9 @@ impl MarkdownHTMLParser {
 
         let mut options = Options::empty();
         options.insert(Options::ENABLE_STRIKETHROUGH);
+        // Options::ENABLE_TABLES is left off until the Dom has table nodes:
+        // until then, pipe tables are kept as plain text so that they
+        // round-trip through `ToMarkdown` unchanged.
 
         let markdown = markdown.to_string();
         let parser_events: Vec<_> = Parser::new_ext(&markdown, options)
@@ -14,6 +14,8 @@
 
 use std::fmt::Display;
 
+use html5ever::tendril::StrTendril;
+
 use super::{paqual_name, PaDomHandle, PaDomNode, PaNodeContainer, PaNodeText};
 
 #[derive(Clone, Debug, PartialEq)]
@@ -79,7 +81,7 @@ impl PaDom {
         // We ignore flags
         let node = match name.local.as_ref() {
             "" => PaDomNode::Text(PaNodeText {
-                content: String::from(""),
+                content: StrTendril::new(),
             }),
             _ => PaDomNode::Container(PaNodeContainer {
                 name,
@@ -35,8 +35,7 @@ impl PaDomCreator {
             paqual_name(""),
             vec![],
         )
-        .from_utf8()
-        .one(html.as_bytes())
+        .one(StrTendril::from_slice(html))
     }
 }
 
@@ -129,7 +128,7 @@ impl TreeSink for PaDomCreator {
                     if let PaDomNode::Text(p) =
                         self.state.dom.get_mut_node(&text_handle)
                     {
-                        p.content += tendril.as_ref();
+                        p.content.push_tendril(&tendril);
                     } else {
                         unreachable!(
                             "`text_handle` must map to a `PaDomNode::Text`"
@@ -138,7 +137,7 @@ impl TreeSink for PaDomCreator {
                 } else {
                     let new_handle =
                         self.state.dom.add_node(PaDomNode::Text(PaNodeText {
-                            content: tendril.as_ref().to_owned(),
+                            content: tendril,
                         }));
 
                     match self.state.dom.get_mut_node(parent) {
@@ -407,7 +406,7 @@ mod test {
     fn tx(content: &str) -> TestNode {
         TestNode {
             dom_node: PaDomNode::Text(PaNodeText {
-                content: content.to_owned(),
+                content: StrTendril::from_slice(content),
             }),
             children: Vec::new(),
         }
@@ -12,7 +12,11 @@
 // See the License for the specific language governing permissions and
 // limitations under the License.
 
+use html5ever::tendril::StrTendril;
+
+/// The text of a node, sharing its buffer with the HTML being parsed where
+/// possible, so that parsing doesn't need to copy every text node.
 #[derive(Clone, Debug, PartialEq)]
 pub(crate) struct PaNodeText {
-    pub(crate) content: String,
+    pub(crate) content: StrTendril,
 }
@@ -12,23 +12,98 @@
 // See the License for the specific language governing permissions and
 // limitations under the License.
 
+use once_cell::sync::Lazy;
 use regex::Regex;
 
 use crate::dom::dom_creation_error::HtmlParseError;
+use crate::dom::nodes::container_node::{
+    is_data_attribute, CUSTOM_TAG_ATTRIBUTES,
+};
 use crate::dom::nodes::dom_node::DomNodeKind::{self};
 use crate::dom::nodes::{ContainerNode, ContainerNodeKind};
 use crate::dom::Dom;
-use crate::{DomHandle, DomNode, UnicodeString};
+use crate::format_type::has_valid_color;
+use crate::{
+    Alignment, DomHandle, DomNode, HeadingLevel, QuoteAttribution,
+    UnicodeString,
+};
+
+/// Keep the alignment given in `attributes` on a parsed paragraph or
+/// heading, in the attribute it was given in.
+fn keep_alignment<S: UnicodeString>(
+    block: &mut ContainerNode<S>,
+    attributes: &[(String, String)],
+) {
+    if let Some((alignment, attribute)) = Alignment::from_attributes(
+        attributes.iter().map(|(k, v)| (k.as_str(), v.as_str())),
+    ) {
+        block.set_alignment(Some(alignment), attribute);
+    }
+}
+
+/// How whitespace in the text of parsed HTML is handled. Code blocks always
+/// keep their whitespace, whatever the policy.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum WhitespacePolicy {
+    /// Keep spaces as they are, but treat line breaks in the HTML source,
+    /// along with the indentation around them, as a single space.
+    #[default]
+    Preserve,
+    /// Like a browser would: every run of whitespace becomes a single space.
+    Collapse,
+    /// Like in a `<pre>` tag: keep all spaces, and turn line breaks into
+    /// line breaks in the content.
+    Pre,
+}
 
 pub fn parse<S>(html: &str) -> Result<Dom<S>, HtmlParseError>
+where
+    S: UnicodeString,
+{
+    parse_with_whitespace_policy(html, WhitespacePolicy::default())
+}
+
+/// Options for [parse_with_options].
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct HtmlParseOptions {
+    pub whitespace_policy: WhitespacePolicy,
+    /// Extra inline tags (e.g. `kbd`) to keep as
+    /// [crate::InlineFormatType::Custom] formatting, instead of dropping
+    /// them. Must be lowercase.
+    pub custom_inline_tags: Vec<String>,
+}
+
+/// Like [parse], but with control over what happens to whitespace in text.
+pub fn parse_with_whitespace_policy<S>(
+    html: &str,
+    whitespace_policy: WhitespacePolicy,
+) -> Result<Dom<S>, HtmlParseError>
+where
+    S: UnicodeString,
+{
+    parse_with_options(
+        html,
+        &HtmlParseOptions {
+            whitespace_policy,
+            ..Default::default()
+        },
+    )
+}
+
+/// Like [parse], but with control over whitespace and which extra tags are
+/// kept.
+pub fn parse_with_options<S>(
+    html: &str,
+    options: &HtmlParseOptions,
+) -> Result<Dom<S>, HtmlParseError>
 where
     S: UnicodeString,
 {
     cfg_if::cfg_if! {
         if #[cfg(feature = "sys")] {
-            sys::HtmlParser::default().parse(html)
+            sys::HtmlParser::new(options.clone()).parse(html)
         } else if #[cfg(all(feature = "js", target_arch = "wasm32"))] {
-            js::HtmlParser::default().parse(html)
+            js::HtmlParser::new(options.clone()).parse(html)
         } else {
             unreachable!("The `sys` or `js` are mutually exclusive, and one of them must be enabled.")
         }
@@ -45,17 +120,25 @@ mod sys {
     use super::*;
     use crate::dom::nodes::dom_node::DomNodeKind;
     use crate::dom::nodes::dom_node::DomNodeKind::CodeBlock;
-    use crate::dom::nodes::{ContainerNode, DomNode};
+    use crate::dom::nodes::image_node::is_mxc_uri;
+    use crate::dom::nodes::{ContainerNode, DomNode, ImageNode};
     use crate::dom::parser::sys::PaNodeText;
-    use crate::ListType;
+    use crate::{InlineFormatType, ListType, ToHtml};
 
     pub(super) struct HtmlParser {
         current_path: Vec<DomNodeKind>,
+        options: HtmlParseOptions,
     }
     impl HtmlParser {
+        #[cfg(test)]
         pub(super) fn default() -> Self {
+            Self::new(HtmlParseOptions::default())
+        }
+
+        pub(super) fn new(options: HtmlParseOptions) -> Self {
             Self {
                 current_path: Vec::new(),
+                options,
             }
         }
 
@@ -72,7 +155,7 @@ mod sys {
                     post_process_blocks(dom)
                 })
                 .map_err(|err| {
-                    self.padom_creation_error_to_html_parse_error(err)
+                    self.padom_creation_error_to_html_parse_error::<S>(err)
                 })
         }
 
@@ -132,6 +215,7 @@ mod sys {
                             node,
                             is_inside_code_block,
                             is_only_child_in_parent,
+                            self.options.whitespace_policy,
                         );
                     }
                 }
@@ -153,6 +237,17 @@ mod sys {
                 "b" | "code" | "del" | "em" | "i" | "strong" | "u" => {
                     let formatting_node = Self::new_formatting(tag);
                     if tag == "code" && self.current_path.contains(&CodeBlock) {
+                        if let Some(language) = child
+                            .get_attr("class")
+                            .and_then(ContainerNode::<S>::language_from_class)
+                        {
+                            if matches!(
+                                node.kind(),
+                                ContainerNodeKind::CodeBlock
+                            ) {
+                                node.set_code_block_language(language);
+                            }
+                        }
                         self.convert_children(padom, child, Some(node));
                     } else {
                         self.current_path.push(formatting_node.kind());
@@ -168,6 +263,14 @@ mod sys {
                 "br" => {
                     node.append_child(Self::new_line_break());
                 }
+                "hr" => {
+                    node.append_child(DomNode::new_horizontal_rule());
+                }
+                "img" => {
+                    if let Some(image) = Self::new_image(child) {
+                        node.append_child(image);
+                    }
+                }
                 "ol" | "ul" => {
                     self.current_path.push(DomNodeKind::List);
                     node.append_child(Self::new_list(tag));
@@ -229,7 +332,7 @@ mod sys {
                 }
                 "blockquote" => {
                     self.current_path.push(DomNodeKind::Quote);
-                    node.append_child(Self::new_quote());
+                    node.append_child(Self::new_quote(child));
                     self.convert_children(
                         padom,
                         child,
@@ -245,7 +348,68 @@ mod sys {
                 }
                 "p" => {
                     self.current_path.push(DomNodeKind::Paragraph);
-                    node.append_child(Self::new_paragraph());
+                    node.append_child(Self::new_paragraph(child));
+                    self.convert_children(
+                        padom,
+                        child,
+                        last_container_mut_in(node),
+                    );
+                    self.current_path.remove(cur_path_idx);
+                }
+                "h1" | "h2" | "h3" | "h4" | "h5" | "h6" => {
+                    let level = HeadingLevel::from_tag(tag).unwrap();
+                    self.current_path.push(DomNodeKind::Heading);
+                    let mut heading =
+                        ContainerNode::new_heading(level, Vec::new());
+                    keep_alignment(&mut heading, &child.attrs);
+                    node.append_child(DomNode::Container(heading));
+                    self.convert_children(
+                        padom,
+                        child,
+                        last_container_mut_in(node),
+                    );
+                    self.current_path.remove(cur_path_idx);
+                }
+                "span" | "div" if child.get_attr("data-mx-maths").is_some() => {
+                    // The children are only a fallback for the LaTeX source
+                    let latex = child.get_attr("data-mx-maths").unwrap();
+                    node.append_child(DomNode::new_math(
+                        S::from(latex),
+                        tag == "div",
+                    ));
+                }
+                "span" | "font" if !Self::color_formats(child).is_empty() => {
+                    // Nest a formatting node for each colour, text colour
+                    // outermost.
+                    let mut container = node;
+                    for format in Self::color_formats(child) {
+                        self.current_path
+                            .push(DomNodeKind::Formatting(format.clone()));
+                        container.append_child(DomNode::new_formatting(
+                            format,
+                            Vec::new(),
+                        ));
+                        container = last_container_mut_in(container)
+                            .expect("Container became non-container!");
+                    }
+                    self.convert(padom, child, container);
+                    self.current_path.truncate(cur_path_idx);
+                }
+                _ if self
+                    .options
+                    .custom_inline_tags
+                    .iter()
+                    .any(|t| t == tag)
+                    || (tag == "span"
+                        && child
+                            .attrs
+                            .iter()
+                            .any(|(k, _)| is_data_attribute(k))) =>
+                {
+                    let format = InlineFormatType::Custom(tag.to_owned());
+                    self.current_path
+                        .push(DomNodeKind::Formatting(format.clone()));
+                    node.append_child(Self::new_custom_format(child, format));
                     self.convert_children(
                         padom,
                         child,
@@ -295,6 +459,56 @@ mod sys {
             DomNode::new_line_break()
         }
 
+        /// The valid colours of a `span` or `font` tag: its text colour from
+        /// its `data-mx-color` attribute (or the older `color` attribute of a
+        /// `font`), then its highlight from its `data-mx-bg-color`
+        /// attribute.
+        fn color_formats(child: &PaNodeContainer) -> Vec<InlineFormatType> {
+            let text_color = child.get_attr("data-mx-color").or_else(|| {
+                if child.name.local.as_ref() == "font" {
+                    child.get_attr("color")
+                } else {
+                    None
+                }
+            });
+            let highlight = child.get_attr("data-mx-bg-color");
+            text_color
+                .map(|c| InlineFormatType::TextColor(c.to_owned()))
+                .into_iter()
+                .chain(
+                    highlight
+                        .map(|c| InlineFormatType::Highlight(c.to_owned())),
+                )
+                .filter(has_valid_color)
+                .collect()
+        }
+
+        /// Create a formatting node for a custom tag, or a span with `data-*`
+        /// attributes
+        fn new_custom_format<S>(
+            child: &PaNodeContainer,
+            format: InlineFormatType,
+        ) -> DomNode<S>
+        where
+            S: UnicodeString,
+        {
+            let attrs: Vec<(S, S)> = child
+                .attrs
+                .iter()
+                .filter(|(k, _)| {
+                    CUSTOM_TAG_ATTRIBUTES.contains(&k.as_str())
+                        || is_data_attribute(k)
+                })
+                .map(|(k, v)| (k.as_str().into(), v.as_str().into()))
+                .collect();
+            DomNode::Container(ContainerNode::new(
+                format.tag().into(),
+                ContainerNodeKind::Formatting(format),
+                (!attrs.is_empty()).then_some(attrs),
+                Vec::new(),
+            ))
+        }
+
         /// Create a link node
         fn new_link<S>(child: &PaNodeContainer) -> DomNode<S>
         where
@@ -313,6 +527,25 @@ mod sys {
             ))
         }
 
+        /// Create an image node, or None if the tag has no `src` or it isn't
+        /// an `mxc://` URI
+        fn new_image<S>(child: &PaNodeContainer) -> Option<DomNode<S>>
+        where
+            S: UnicodeString,
+        {
+            let src = child.get_attr("src").filter(|src| is_mxc_uri(src))?;
+            let size = |name| child.get_attr(name).and_then(|v| v.parse().ok());
+            let mut image = ImageNode::new(
+                src.into(),
+                child.get_attr("alt").unwrap_or("").into(),
+                size("width"),
+                size("height"),
+            );
+            image
+                .set_custom_emoji(child.get_attr("data-mx-emoticon").is_some());
+            Some(DomNode::Image(image))
+        }
+
         fn new_mention<S>(
             link: &PaNodeContainer,
             text: &PaNodeText,
@@ -325,7 +558,7 @@ mod sys {
             // creating a mention node could fail if the uri is invalid
             let creation_result = DomNode::new_mention(
                 link.get_attr("href").unwrap_or("").into(),
-                text.as_str().into(),
+                text.as_ref().into(),
                 // custom attributes are not required when cfg feature != "js"
                 vec![],
             );
@@ -363,28 +596,43 @@ mod sys {
             DomNode::Container(ContainerNode::new_code_block(Vec::new()))
         }
 
-        /// Create a quote node
-        fn new_quote<S>() -> DomNode<S>
+        /// Create a quote node, keeping its attribution
+        fn new_quote<S>(child: &PaNodeContainer) -> DomNode<S>
         where
             S: UnicodeString,
         {
-            DomNode::Container(ContainerNode::new_quote(Vec::new()))
+            let mut quote = ContainerNode::new_quote(Vec::new());
+            let attribution = QuoteAttribution::from_attributes(
+                child.attrs.iter().map(|(k, v)| (k.as_str(), v.as_str())),
+            );
+            quote.set_quote_attribution(attribution.as_ref());
+            DomNode::Container(quote)
         }
 
-        /// Create a paragraph
-        fn new_paragraph<S>() -> DomNode<S>
+        /// Create a paragraph, keeping its alignment
+        fn new_paragraph<S>(child: &PaNodeContainer) -> DomNode<S>
         where
             S: UnicodeString,
         {
-            DomNode::Container(ContainerNode::new_paragraph(Vec::new()))
+            let mut paragraph = ContainerNode::new_paragraph(Vec::new());
+            keep_alignment(&mut paragraph, &child.attrs);
+            DomNode::Container(paragraph)
         }
 
-        fn padom_creation_error_to_html_parse_error(
+        /// html5ever still builds a tree when it finds errors, so keep it
+        /// as HTML the caller can fall back to.
+        fn padom_creation_error_to_html_parse_error<S>(
             &mut self,
             e: PaDomCreationError,
-        ) -> HtmlParseError {
+        ) -> HtmlParseError
+        where
+            S: UnicodeString,
+        {
+            let recovered: Dom<S> =
+                post_process_blocks(self.padom_to_dom(e.dom));
             HtmlParseError {
                 parse_errors: e.parse_errors,
+                recovered_html: Some(recovered.to_html().to_string()),
             }
         }
     }
@@ -922,6 +1170,40 @@ mod sys {
                 "#}
             );
         }
+
+        #[test]
+        fn parse_text_split_around_entities_is_joined() {
+            let html = "a &amp; b &lt;c&gt; d";
+            let dom: Dom<Utf16String> =
+                HtmlParser::default().parse(html).unwrap();
+            let tree = dom.to_tree().to_string();
+            assert_eq!(
+                tree,
+                indoc! {
+                r#"
+
+                  └>"a & b <c> d"
+                "#}
+            );
+        }
+
+        #[test]
+        fn parse_text_without_line_breaks_keeps_all_spaces() {
+            let html = "<b>  a  b  </b>  c";
+            let dom: Dom<Utf16String> =
+                HtmlParser::default().parse(html).unwrap();
+            let tree = dom.to_tree().to_string();
+            assert_eq!(
+                tree,
+                indoc! {
+                r#"
+
+                  ├>b
+                  │ └>"  a  b  "
+                  └>"  c"
+                "#}
+            );
+        }
     }
 }
 
@@ -935,7 +1217,7 @@ fn post_process_blocks<S: UnicodeString>(mut dom: Dom<S>) -> Dom<S> {
 
 fn find_blocks<S: UnicodeString>(dom: &Dom<S>) -> Vec<DomHandle> {
     dom.iter()
-        .filter(|n| n.is_block_node())
+        .filter(|n| n.is_container_node() && n.is_block_node())
         .map(|n| n.handle())
         .collect::<Vec<_>>()
 }
@@ -988,8 +1270,9 @@ fn post_process_block_lines<S: UnicodeString>(
     }
 
     // Create a new node to hold the processed contents if necessary
+    let heading_level = container_node.heading_level();
     let new_node = match container_node.kind() {
-        ContainerNodeKind::Paragraph => None,
+        ContainerNodeKind::Paragraph | ContainerNodeKind::Heading(_) => None,
         _ => Some(container_node.clone_with_new_children(vec![])),
     };
 
@@ -1012,6 +1295,16 @@ fn post_process_block_lines<S: UnicodeString>(
                 .rev()
                 .for_each(|n| contents.insert(0, n.clone()));
         }
+        // Each line of a heading becomes a heading of the same level
+        if heading_level.is_some() {
+            for node in contents.iter_mut() {
+                if let DomNode::Container(c) = node {
+                    if c.kind() == &ContainerNodeKind::Paragraph {
+                        c.set_heading_level(heading_level);
+                    }
+                }
+            }
+        }
         contents
     };
 
@@ -1088,11 +1381,20 @@ fn last_container_mut_in<S: UnicodeString>(
     node.last_child_mut().and_then(|n| n.as_container_mut())
 }
 
+// Compiled once, rather than for every text node that is parsed.
+static SURROUNDING_INDENT: Lazy<Regex> =
+    Lazy::new(|| Regex::new(r"^(\s*\n\s*)+|(\s*\n\s*)+$").unwrap());
+static INTERNAL_INDENT: Lazy<Regex> =
+    Lazy::new(|| Regex::new(r"s*\n\s*").unwrap());
+static HTML_WHITESPACE: Lazy<Regex> =
+    Lazy::new(|| Regex::new(r"[ \t\n\r\x0C]+").unwrap());
+
 fn convert_text<S: UnicodeString>(
     text: &str,
     node: &mut ContainerNode<S>,
     is_inside_code_block: bool,
     is_only_child_in_parent: bool,
+    whitespace_policy: WhitespacePolicy,
 ) {
     if is_inside_code_block {
         let text_nodes: Vec<_> = text.split('\n').collect();
@@ -1114,35 +1416,73 @@ fn convert_text<S: UnicodeString>(
             return;
         }
 
-        // Trim any surrounding indentation
-        let surrounding_indent =
-            Regex::new(r"^(\s*\n\s*)+|(\s*\n\s*)+$").unwrap();
-        let contents = &surrounding_indent.replace_all(contents, "");
+        match whitespace_policy {
+            // Text without line breaks has no indentation to remove, so it
+            // can be turned into text nodes straight from the parsed HTML.
+            WhitespacePolicy::Preserve if !contents.contains('\n') => {
+                append_text_and_mentions(contents, node);
+            }
+            WhitespacePolicy::Preserve => {
+                // Trim any surrounding indentation
+                let contents = &SURROUNDING_INDENT.replace_all(contents, "");
+
+                // Replace any internal indentation with a single space
+                let contents = &INTERNAL_INDENT.replace_all(contents, " ");
+
+                append_text_and_mentions(contents, node);
+            }
+            WhitespacePolicy::Collapse => {
+                let contents = &SURROUNDING_INDENT.replace_all(contents, "");
 
-        // Replace any internal indentation with a single space
-        let internal_indent = Regex::new(r"s*\n\s*").unwrap();
-        let contents = &internal_indent.replace_all(contents, " ");
+                // Only HTML whitespace, so that non-breaking spaces are kept
+                let contents = &HTML_WHITESPACE.replace_all(contents, " ");
 
-        for (i, part) in contents.split("@room").enumerate() {
-            if i > 0 {
-                node.append_child(DomNode::Mention(
-                    DomNode::new_at_room_mention(vec![]),
-                ));
+                append_text_and_mentions(contents, node);
             }
-            if !part.is_empty() {
-                node.append_child(DomNode::new_text(part.into()));
+            WhitespacePolicy::Pre => {
+                // Whitespace-only text containing line breaks is just
+                // formatting between tags, e.g. between two paragraphs.
+                if contents.contains('\n') && contents.trim().is_empty() {
+                    return;
+                }
+                let lines: Vec<_> = contents.split('\n').collect();
+                let lines_len = lines.len();
+                for (i, line) in lines.into_iter().enumerate() {
+                    append_text_and_mentions(line, node);
+                    if i + 1 < lines_len {
+                        node.append_child(DomNode::new_line_break());
+                    }
+                }
             }
         }
     }
 }
 
+/// Append the text to the node, turning any `@room` into a mention.
+fn append_text_and_mentions<S: UnicodeString>(
+    contents: &str,
+    node: &mut ContainerNode<S>,
+) {
+    for (i, part) in contents.split("@room").enumerate() {
+        if i > 0 {
+            node.append_child(DomNode::Mention(DomNode::new_at_room_mention(
+                vec![],
+            )));
+        }
+        if !part.is_empty() {
+            node.append_child(DomNode::new_text(part.into()));
+        }
+    }
+}
+
 #[cfg(all(feature = "js", target_arch = "wasm32"))]
 mod js {
     use super::*;
     use crate::dom::nodes::dom_node::DomNodeKind;
     use crate::dom::nodes::dom_node::DomNodeKind::CodeBlock;
     use crate::{
-        dom::nodes::{ContainerNode, DomNode},
+        dom::nodes::image_node::is_mxc_uri,
+        dom::nodes::{ContainerNode, DomNode, ImageNode},
         InlineFormatType, ListType,
     };
     use matrix_mentions::Mention;
@@ -1152,11 +1492,18 @@ mod js {
 
     pub(super) struct HtmlParser {
         current_path: Vec<DomNodeKind>,
+        options: HtmlParseOptions,
     }
     impl HtmlParser {
+        #[cfg(test)]
         pub(super) fn default() -> Self {
+            Self::new(HtmlParseOptions::default())
+        }
+
+        pub(super) fn new(options: HtmlParseOptions) -> Self {
             Self {
                 current_path: Vec::new(),
+                options,
             }
         }
 
@@ -1228,6 +1575,37 @@ mod js {
                         dom.append_child(DomNode::new_line_break());
                     }
 
+                    "HR" => {
+                        dom.append_child(DomNode::new_horizontal_rule());
+                    }
+
+                    "IMG" => {
+                        let element = node.unchecked_ref::<Element>();
+                        if let Some(src) = element
+                            .get_attribute("src")
+                            .filter(|src| is_mxc_uri(src))
+                        {
+                            let size = |name| {
+                                element
+                                    .get_attribute(name)
+                                    .and_then(|v| v.parse().ok())
+                            };
+                            let mut image = ImageNode::new(
+                                src.into(),
+                                element
+                                    .get_attribute("alt")
+                                    .unwrap_or_default()
+                                    .into(),
+                                size("width"),
+                                size("height"),
+                            );
+                            image.set_custom_emoji(
+                                element.has_attribute("data-mx-emoticon"),
+                            );
+                            dom.append_child(DomNode::Image(image));
+                        }
+                    }
+
                     "#text" => match node.node_value() {
                         Some(value) => {
                             let is_inside_code_block =
@@ -1238,6 +1616,7 @@ mod js {
                                 dom,
                                 is_inside_code_block,
                                 is_only_child_in_parent,
+                                self.options.whitespace_policy,
                             );
                         }
                         _ => {}
@@ -1265,6 +1644,12 @@ mod js {
                             }
                         }
 
+                        attributes.extend(
+                            data_attributes(node.unchecked_ref::<Element>())
+                                .into_iter()
+                                .map(|(k, v)| (k.into(), v.into())),
+                        );
+
                         let url = node
                             .unchecked_ref::<Element>()
                             .get_attribute("href")
@@ -1345,45 +1730,115 @@ mod js {
                     "PRE" => {
                         self.current_path.push(DomNodeKind::CodeBlock);
                         let children = node.child_nodes();
+                        let mut language = None;
                         let children = if children.length() == 1
                             && children.get(0).unwrap().node_name().as_str()
                                 == "CODE"
                         {
                             let code_node = children.get(0).unwrap();
+                            language = code_node
+                                .unchecked_ref::<Element>()
+                                .get_attribute("class");
                             code_node.child_nodes()
                         } else {
                             children
                         };
-                        dom.append_child(DomNode::Container(
-                            ContainerNode::new_code_block(
-                                self.convert(children)?.take_children(),
-                            ),
-                        ));
+                        let mut code_block = ContainerNode::new_code_block(
+                            self.convert(children)?.take_children(),
+                        );
+                        if let Some(language) = language
+                            .as_deref()
+                            .and_then(ContainerNode::<S>::language_from_class)
+                        {
+                            code_block.set_code_block_language(language);
+                        }
+                        dom.append_child(DomNode::Container(code_block));
                         self.current_path.pop();
                     }
 
                     "BLOCKQUOTE" => {
                         self.current_path.push(DomNodeKind::Quote);
-                        dom.append_child(DomNode::Container(
-                            ContainerNode::new_quote(
-                                self.convert(node.child_nodes())?
-                                    .take_children(),
-                            ),
-                        ));
+                        let mut quote = ContainerNode::new_quote(
+                            self.convert(node.child_nodes())?.take_children(),
+                        );
+                        quote.set_quote_attribution(
+                            quote_attribution(node.unchecked_ref::<Element>())
+                                .as_ref(),
+                        );
+                        dom.append_child(DomNode::Container(quote));
                         self.current_path.pop();
                     }
 
                     "P" => {
                         self.current_path.push(DomNodeKind::Paragraph);
-                        dom.append_child(DomNode::Container(
-                            ContainerNode::new_paragraph(
-                                self.convert(node.child_nodes())?
-                                    .take_children(),
-                            ),
-                        ));
+                        let mut paragraph = ContainerNode::new_paragraph(
+                            self.convert(node.child_nodes())?.take_children(),
+                        );
+                        keep_alignment(
+                            &mut paragraph,
+                            &attributes(node.unchecked_ref::<Element>()),
+                        );
+                        dom.append_child(DomNode::Container(paragraph));
                         self.current_path.pop();
                     }
 
+                    tag @ ("H1" | "H2" | "H3" | "H4" | "H5" | "H6") => {
+                        let level = HeadingLevel::from_tag(tag).unwrap();
+                        self.current_path.push(DomNodeKind::Heading);
+                        let mut heading = ContainerNode::new_heading(
+                            level,
+                            self.convert(node.child_nodes())?.take_children(),
+                        );
+                        keep_alignment(
+                            &mut heading,
+                            &attributes(node.unchecked_ref::<Element>()),
+                        );
+                        dom.append_child(DomNode::Container(heading));
+                        self.current_path.pop();
+                    }
+
+                    "SPAN" | "DIV"
+                        if node
+                            .unchecked_ref::<Element>()
+                            .has_attribute("data-mx-maths") =>
+                    {
+                        // The children are only a fallback for the LaTeX
+                        // source
+                        let latex = node
+                            .unchecked_ref::<Element>()
+                            .get_attribute("data-mx-maths")
+                            .unwrap_or_default();
+                        dom.append_child(DomNode::new_math(
+                            latex.into(),
+                            node.node_name() == "DIV",
+                        ));
+                    }
+
+                    "SPAN" | "FONT"
+                        if !color_formats(node.unchecked_ref()).is_empty() =>
+                    {
+                        // Nest a formatting node for each colour, text
+                        // colour outermost.
+                        let formats = color_formats(node.unchecked_ref());
+                        for format in &formats {
+                            self.current_path
+                                .push(DomNodeKind::Formatting(format.clone()));
+                        }
+                        let mut formatted =
+                            self.convert(node.child_nodes())?.take_children();
+                        for format in formats.into_iter().rev() {
+                            formatted = vec![DomNode::Container(
+                                ContainerNode::new_formatting(
+                                    format, formatted,
+                                ),
+                            )];
+                            self.current_path.pop();
+                        }
+                        for child in formatted {
+                            dom.append_child(child);
+                        }
+                    }
+
                     node_name => {
                         let children_nodes =
                             self.convert(node.child_nodes())?.take_children();
@@ -1395,9 +1850,23 @@ mod js {
                             "U" => InlineFormatType::Underline,
                             "CODE" => InlineFormatType::InlineCode,
                             _ => {
-                                return Err(Error::UnknownNode(
-                                    node_name.to_owned(),
-                                ))
+                                let tag = node_name.to_lowercase();
+                                let is_data_span = tag == "span"
+                                    && !data_attributes(
+                                        node.unchecked_ref::<Element>(),
+                                    )
+                                    .is_empty();
+                                if !is_data_span
+                                    && !self
+                                        .options
+                                        .custom_inline_tags
+                                        .contains(&tag)
+                                {
+                                    return Err(Error::UnknownNode(
+                                        node_name.to_owned(),
+                                    ));
+                                }
+                                InlineFormatType::Custom(tag)
                             }
                         };
 
@@ -1405,12 +1874,35 @@ mod js {
                             formatting_kind.clone(),
                         ));
 
-                        dom.append_child(DomNode::Container(
+                        let container = if let InlineFormatType::Custom(tag) =
+                            &formatting_kind
+                        {
+                            let element = node.unchecked_ref::<Element>();
+                            let attrs = CUSTOM_TAG_ATTRIBUTES
+                                .iter()
+                                .filter_map(|name| {
+                                    element.get_attribute(name).map(|value| {
+                                        ((*name).to_owned(), value)
+                                    })
+                                })
+                                .chain(data_attributes(element))
+                                .map(|(k, v)| (k.into(), v.into()))
+                                .collect::<Vec<_>>();
+                            ContainerNode::new(
+                                tag.as_str().into(),
+                                ContainerNodeKind::Formatting(
+                                    formatting_kind.clone(),
+                                ),
+                                (!attrs.is_empty()).then_some(attrs),
+                                children_nodes,
+                            )
+                        } else {
                             ContainerNode::new_formatting(
                                 formatting_kind,
                                 children_nodes,
-                            ),
-                        ));
+                            )
+                        };
+                        dom.append_child(DomNode::Container(container));
                         self.current_path.pop();
                     }
                 }
@@ -1420,13 +1912,66 @@ mod js {
         }
     }
 
+    /// The valid colours of a `span` or `font` element: its text colour from
+    /// its `data-mx-color` attribute (or the older `color` attribute of a
+    /// `font`), then its highlight from its `data-mx-bg-color` attribute.
+    fn color_formats(element: &Element) -> Vec<InlineFormatType> {
+        let text_color = element.get_attribute("data-mx-color").or_else(|| {
+            if element.tag_name() == "FONT" {
+                element.get_attribute("color")
+            } else {
+                None
+            }
+        });
+        let highlight = element.get_attribute("data-mx-bg-color");
+        text_color
+            .map(InlineFormatType::TextColor)
+            .into_iter()
+            .chain(highlight.map(InlineFormatType::Highlight))
+            .filter(has_valid_color)
+            .collect()
+    }
+
+    /// The `data-*` attributes of an element that the editor keeps
+    fn data_attributes(element: &Element) -> Vec<(String, String)> {
+        element
+            .get_attribute_names()
+            .iter()
+            .filter_map(|name| name.as_string())
+            .filter(|name| is_data_attribute(name))
+            .filter_map(|name| {
+                let value = element.get_attribute(&name)?;
+                Some((name, value))
+            })
+            .collect()
+    }
+
+    /// The attribution in the attributes of a `<blockquote>`, if any
+    fn quote_attribution(element: &Element) -> Option<QuoteAttribution> {
+        let attributes = attributes(element);
+        QuoteAttribution::from_attributes(
+            attributes.iter().map(|(k, v)| (k.as_str(), v.as_str())),
+        )
+    }
+
+    /// All the attributes of `element`, with their values.
+    fn attributes(element: &Element) -> Vec<(String, String)> {
+        element
+            .get_attribute_names()
+            .iter()
+            .filter_map(|name| name.as_string())
+            .filter_map(|name| {
+                let value = element.get_attribute(&name)?;
+                Some((name, value))
+            })
+            .collect()
+    }
+
     fn to_dom_creation_error<E>(error: E) -> HtmlParseError
     where
         E: ToString,
     {
-        HtmlParseError {
-            parse_errors: vec![error.to_string()],
-        }
+        HtmlParseError::new(vec![error.to_string()])
     }
 
     enum Error {
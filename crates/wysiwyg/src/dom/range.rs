@@ -227,6 +227,12 @@ pub struct Range {
     pub locations: Vec<DomLocation>,
 }
 
+impl From<Vec<DomLocation>> for Range {
+    fn from(locations: Vec<DomLocation>) -> Self {
+        Self { locations }
+    }
+}
+
 impl Range {
     pub fn new<'a>(
         locations: impl IntoIterator<Item = &'a DomLocation>,
@@ -271,6 +277,47 @@ impl Range {
             .unwrap_or(0)
     }
 
+    /// Split this range at block boundaries, so each part can be wrapped
+    /// in an inline node like a link. Returns the innermost block (or the
+    /// root) holding each part, with the part's start and end positions.
+    /// Blocks where the range contains no text are skipped.
+    pub(crate) fn inline_ranges_in_blocks(
+        &self,
+    ) -> Vec<(DomHandle, usize, usize)> {
+        let mut split_points: Vec<(DomHandle, usize, usize)> = Vec::new();
+        for location in self.locations.iter() {
+            // Look for block nodes
+            if (location.kind.is_block_kind()
+                || location.kind.is_structure_kind())
+                && location.kind != DomNodeKind::List
+            {
+                let start = location.position + location.start_offset;
+                let end = if location.end_offset == location.length
+                    && !location.node_handle.is_root()
+                {
+                    // The end of the block node is covered (end_offset == length), don't include it
+                    location.position + location.end_offset - 1
+                } else {
+                    location.position + location.end_offset
+                };
+                // If there was a child block node added as a split point, don't add this one
+                if !split_points
+                    .iter()
+                    .any(|(h, _, _)| location.node_handle.is_ancestor_of(h))
+                    // Only include split points which actually have some text in the range.
+                    && end > start
+                {
+                    split_points.push((
+                        location.node_handle.clone(),
+                        start,
+                        end,
+                    ));
+                }
+            }
+        }
+        split_points
+    }
+
     pub fn has_leaves(&self) -> bool {
         self.locations.iter().any(|l| l.is_leaf())
     }
@@ -342,9 +389,10 @@ impl Range {
         self.start() != self.end()
     }
 
-    // TODO: remove all uses of this when we guarantee that Dom is never empty
+    /// Whether this range contains no nodes, apart from the root of an
+    /// empty Dom.
     pub fn is_empty(&self) -> bool {
-        self.locations.is_empty()
+        self.locations.iter().all(|l| l.node_handle.is_root())
     }
 
     pub fn contains(&self, handle: &DomHandle) -> bool {
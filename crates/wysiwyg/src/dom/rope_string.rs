@@ -0,0 +1,609 @@
+// Copyright 2023 The Matrix.org Foundation C.I.C.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use std::borrow::Borrow;
+use std::fmt;
+use std::ops::{Deref, Index, Range, RangeFrom, RangeTo};
+use std::sync::OnceLock;
+
+use widestring::{Utf16Str, Utf16String};
+
+use crate::dom::unicode_string::{UnicodeStr, UnicodeString};
+
+/// Chunks are split in half once they grow past this many code units.
+const MAX_CHUNK_LEN: usize = 1024;
+
+/// A UTF-16 [UnicodeString] for very long documents, stored as a list of
+/// chunks so that inserting or removing text in the middle of a huge text
+/// node only copies the chunk it lands in, rather than the whole string.
+///
+/// Lengths, characters and slices inside one chunk are read straight from
+/// the chunks. Reads which need all of the string in one piece, like
+/// writing it out as HTML, join the chunks into one string, which is kept
+/// until the next change.
+#[derive(Default)]
+pub struct RopeString {
+    chunks: Vec<Utf16String>,
+    joined: OnceLock<Utf16String>,
+}
+
+/// A slice of a [RopeString], as returned by indexing it.
+#[derive(PartialEq)]
+#[repr(transparent)]
+pub struct RopeStr(Utf16Str);
+
+impl RopeStr {
+    fn new(s: &Utf16Str) -> &Self {
+        // SAFETY: RopeStr is a repr(transparent) wrapper around Utf16Str, so
+        // the two references have the same layout.
+        unsafe { &*(s as *const Utf16Str as *const RopeStr) }
+    }
+}
+
+impl RopeString {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Like [Utf16String::from_str], so that this can be used in its place.
+    #[allow(clippy::should_implement_trait)]
+    pub fn from_str(s: &str) -> Self {
+        Self::from(s)
+    }
+
+    /// The whole string in one piece, which only needs joining up if there
+    /// is more than one chunk.
+    fn contiguous(&self) -> &Utf16Str {
+        match self.chunks.as_slice() {
+            [] => <&Utf16Str>::default(),
+            [chunk] => chunk,
+            _ => self.joined(),
+        }
+    }
+
+    fn joined(&self) -> &Utf16Str {
+        self.joined.get_or_init(|| {
+            let mut joined = Utf16String::with_capacity(self.len());
+            for chunk in &self.chunks {
+                joined.push_utfstr(chunk);
+            }
+            joined
+        })
+    }
+
+    /// The length in UTF-16 code units.
+    pub fn len(&self) -> usize {
+        self.chunks.iter().map(|c| c.len()).sum()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.chunks.is_empty()
+    }
+
+    /// The index of the chunk containing code unit `idx`, and the offset of
+    /// `idx` inside it. An `idx` at the end of a chunk is in that chunk, not
+    /// at the start of the next one, so that appending to text stays in the
+    /// same chunk.
+    fn find_chunk(&self, idx: usize) -> (usize, usize) {
+        let mut start = 0;
+        for (i, chunk) in self.chunks.iter().enumerate() {
+            if idx <= start + chunk.len() {
+                return (i, idx - start);
+            }
+            start += chunk.len();
+        }
+        panic!("Index {idx} is out of bounds for a string of length {start}");
+    }
+
+    /// The code units in `range`, borrowed from the chunk holding them if
+    /// they are all in one, so that the chunks only need joining for slices
+    /// which cross a chunk boundary.
+    fn slice(&self, range: Range<usize>) -> &Utf16Str {
+        if range.start == range.end {
+            assert!(range.end <= self.len(), "Index is out of bounds");
+            return Default::default();
+        }
+        if range.start < range.end {
+            let (mut i, mut offset) = self.find_chunk(range.start);
+            if offset == self.chunks[i].len() && i + 1 < self.chunks.len() {
+                i += 1;
+                offset = 0;
+            }
+            if let Some(slice) =
+                self.chunks[i].get(offset..offset + range.len())
+            {
+                return slice;
+            }
+        }
+        &self.contiguous()[range]
+    }
+
+    /// Remove the code units in `range`, only changing the chunks it covers.
+    fn remove_range(&mut self, range: Range<usize>) {
+        let mut chunk_start = 0;
+        let mut i = 0;
+        while i < self.chunks.len() && chunk_start < range.end {
+            let chunk_end = chunk_start + self.chunks[i].len();
+            if range.start < chunk_end {
+                let from = range.start.saturating_sub(chunk_start);
+                let to = range.end.min(chunk_end) - chunk_start;
+                self.chunks[i].replace_range(from..to, Default::default());
+            }
+            chunk_start = chunk_end;
+            if self.chunks[i].is_empty() {
+                self.chunks.remove(i);
+            } else {
+                i += 1;
+            }
+        }
+        assert!(range.end <= chunk_start, "Range is out of bounds");
+    }
+
+    /// Split the chunk at `i` in half until none of the pieces are too long,
+    /// making sure not to separate the two halves of a surrogate pair.
+    fn split_if_long(&mut self, i: usize) {
+        let chunk = &self.chunks[i];
+        if chunk.len() <= MAX_CHUNK_LEN {
+            return;
+        }
+        let mut mid = chunk.len() / 2;
+        if !chunk.is_char_boundary(mid) {
+            mid += 1;
+        }
+        let second = Utf16String::from(&chunk[mid..]);
+        self.chunks[i].truncate(mid);
+        self.chunks.insert(i + 1, second);
+        self.split_if_long(i + 1);
+        self.split_if_long(i);
+    }
+
+    fn push_utfstr(&mut self, s: &Utf16Str) {
+        if s.is_empty() {
+            return;
+        }
+        self.joined.take();
+        match self.chunks.last_mut() {
+            Some(last) => last.push_utfstr(s),
+            None => self.chunks.push(Utf16String::from(s)),
+        }
+        self.split_if_long(self.chunks.len() - 1);
+    }
+
+    fn remove_chunk_if_empty(&mut self, i: usize) {
+        if self.chunks[i].is_empty() {
+            self.chunks.remove(i);
+        }
+    }
+}
+
+impl UnicodeString for RopeString {
+    type CodeUnit = u16;
+    type Str = RopeStr;
+
+    fn insert(&mut self, idx: usize, s: &Self::Str) {
+        if s.0.is_empty() {
+            return;
+        }
+        if self.chunks.is_empty() {
+            assert_eq!(idx, 0, "Index is out of bounds");
+            self.push_utfstr(&s.0);
+            return;
+        }
+        self.joined.take();
+        let (i, offset) = self.find_chunk(idx);
+        self.chunks[i].insert_utfstr(offset, &s.0);
+        self.split_if_long(i);
+    }
+
+    fn remove_at(&mut self, idx: usize) -> char {
+        self.joined.take();
+        // The char is in the chunk starting at `idx` if `idx` is the end of
+        // the chunk found.
+        let (mut i, mut offset) = self.find_chunk(idx);
+        if offset == self.chunks[i].len() {
+            i += 1;
+            offset = 0;
+        }
+        let removed = self.chunks[i].remove(offset);
+        self.remove_chunk_if_empty(i);
+        removed
+    }
+
+    fn pop_first(&mut self) -> Option<char> {
+        if self.chunks.is_empty() {
+            return None;
+        }
+        self.joined.take();
+        let removed = self.chunks[0].remove(0);
+        self.remove_chunk_if_empty(0);
+        Some(removed)
+    }
+
+    fn pop_last(&mut self) -> Option<char> {
+        let last = self.chunks.len().checked_sub(1)?;
+        self.joined.take();
+        let removed = self.chunks[last].pop();
+        self.remove_chunk_if_empty(last);
+        removed
+    }
+
+    fn replace_range(&mut self, range: Range<usize>, s: &RopeStr) {
+        self.joined.take();
+        self.remove_range(range.clone());
+        self.insert(range.start, s);
+    }
+
+    fn len(&self) -> usize {
+        RopeString::len(self)
+    }
+
+    fn is_empty(&self) -> bool {
+        RopeString::is_empty(self)
+    }
+
+    fn chars(&self) -> Box<dyn Iterator<Item = char> + '_> {
+        // Chunks are never split inside a surrogate pair, so each one can be
+        // decoded on its own.
+        Box::new(self.chunks.iter().flat_map(|chunk| chunk.chars()))
+    }
+
+    fn char_at(&self, idx: usize) -> char {
+        UnicodeString::chars(self).nth(idx).unwrap()
+    }
+}
+
+impl UnicodeStr for RopeStr {
+    type CodeUnit = u16;
+    type StringType = RopeString;
+
+    fn chars(&self) -> Box<dyn Iterator<Item = char> + '_> {
+        Box::new(self.0.chars())
+    }
+
+    fn char_len(&self, char: &char) -> usize {
+        char.len_utf16()
+    }
+
+    fn char_at(&self, idx: usize) -> char {
+        self.0.chars().nth(idx).unwrap()
+    }
+}
+
+impl Clone for RopeString {
+    fn clone(&self) -> Self {
+        // Don't copy the joined string, which may never be read again, e.g.
+        // when this is a copy of the content kept for undo.
+        Self {
+            chunks: self.chunks.clone(),
+            joined: OnceLock::new(),
+        }
+    }
+}
+
+impl Deref for RopeString {
+    type Target = RopeStr;
+
+    fn deref(&self) -> &RopeStr {
+        RopeStr::new(self.contiguous())
+    }
+}
+
+impl Borrow<RopeStr> for RopeString {
+    fn borrow(&self) -> &RopeStr {
+        self
+    }
+}
+
+impl ToOwned for RopeStr {
+    type Owned = RopeString;
+
+    fn to_owned(&self) -> RopeString {
+        let mut s = RopeString::new();
+        s.push_utfstr(&self.0);
+        s
+    }
+}
+
+impl AsRef<[u16]> for RopeString {
+    fn as_ref(&self) -> &[u16] {
+        self.contiguous().as_slice()
+    }
+}
+
+impl AsRef<[u16]> for RopeStr {
+    fn as_ref(&self) -> &[u16] {
+        self.0.as_slice()
+    }
+}
+
+impl PartialEq for RopeString {
+    fn eq(&self, other: &Self) -> bool {
+        self.chunks
+            .iter()
+            .flat_map(|c| c.as_slice())
+            .eq(other.chunks.iter().flat_map(|c| c.as_slice()))
+    }
+}
+
+impl PartialEq<str> for RopeString {
+    fn eq(&self, other: &str) -> bool {
+        **self == *other
+    }
+}
+
+impl PartialEq<&str> for RopeString {
+    fn eq(&self, other: &&str) -> bool {
+        **self == **other
+    }
+}
+
+impl PartialEq<RopeString> for RopeStr {
+    fn eq(&self, other: &RopeString) -> bool {
+        *self == **other
+    }
+}
+
+impl PartialEq<Utf16String> for RopeString {
+    fn eq(&self, other: &Utf16String) -> bool {
+        self.contiguous() == other.as_utfstr()
+    }
+}
+
+impl PartialEq<RopeString> for Utf16String {
+    fn eq(&self, other: &RopeString) -> bool {
+        other == self
+    }
+}
+
+impl PartialEq<String> for RopeString {
+    fn eq(&self, other: &String) -> bool {
+        **self == **other
+    }
+}
+
+impl PartialEq<RopeString> for String {
+    fn eq(&self, other: &RopeString) -> bool {
+        other == self
+    }
+}
+
+impl PartialEq<str> for RopeStr {
+    fn eq(&self, other: &str) -> bool {
+        self.0.chars().eq(other.chars())
+    }
+}
+
+impl fmt::Debug for RopeString {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt::Debug::fmt(&**self, f)
+    }
+}
+
+impl fmt::Display for RopeString {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt::Display::fmt(&**self, f)
+    }
+}
+
+impl fmt::Debug for RopeStr {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt::Debug::fmt(&self.0, f)
+    }
+}
+
+impl fmt::Display for RopeStr {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt::Display::fmt(&self.0, f)
+    }
+}
+
+impl From<&str> for RopeString {
+    fn from(s: &str) -> Self {
+        let mut ret = Self::new();
+        ret.push_utfstr(&Utf16String::from_str(s));
+        ret
+    }
+}
+
+impl From<String> for RopeString {
+    fn from(s: String) -> Self {
+        Self::from(s.as_str())
+    }
+}
+
+impl<'a> Extend<&'a RopeStr> for RopeString {
+    fn extend<T: IntoIterator<Item = &'a RopeStr>>(&mut self, iter: T) {
+        for s in iter {
+            self.push_utfstr(&s.0);
+        }
+    }
+}
+
+impl Extend<RopeString> for RopeString {
+    fn extend<T: IntoIterator<Item = RopeString>>(&mut self, iter: T) {
+        for s in iter {
+            for chunk in &s.chunks {
+                self.push_utfstr(chunk);
+            }
+        }
+    }
+}
+
+impl Extend<char> for RopeString {
+    fn extend<T: IntoIterator<Item = char>>(&mut self, iter: T) {
+        let s: String = iter.into_iter().collect();
+        self.push_utfstr(&Utf16String::from_str(&s));
+    }
+}
+
+impl<'a> Extend<&'a str> for RopeString {
+    fn extend<T: IntoIterator<Item = &'a str>>(&mut self, iter: T) {
+        for s in iter {
+            self.push_utfstr(&Utf16String::from_str(s));
+        }
+    }
+}
+
+impl Index<Range<usize>> for RopeString {
+    type Output = RopeStr;
+
+    fn index(&self, index: Range<usize>) -> &RopeStr {
+        RopeStr::new(self.slice(index))
+    }
+}
+
+impl Index<RangeFrom<usize>> for RopeString {
+    type Output = RopeStr;
+
+    fn index(&self, index: RangeFrom<usize>) -> &RopeStr {
+        RopeStr::new(self.slice(index.start..self.len()))
+    }
+}
+
+impl Index<RangeTo<usize>> for RopeString {
+    type Output = RopeStr;
+
+    fn index(&self, index: RangeTo<usize>) -> &RopeStr {
+        RopeStr::new(self.slice(0..index.end))
+    }
+}
+
+impl Index<Range<usize>> for RopeStr {
+    type Output = RopeStr;
+
+    fn index(&self, index: Range<usize>) -> &RopeStr {
+        RopeStr::new(&self.0[index])
+    }
+}
+
+impl Index<RangeFrom<usize>> for RopeStr {
+    type Output = RopeStr;
+
+    fn index(&self, index: RangeFrom<usize>) -> &RopeStr {
+        RopeStr::new(&self.0[index])
+    }
+}
+
+impl Index<RangeTo<usize>> for RopeStr {
+    type Output = RopeStr;
+
+    fn index(&self, index: RangeTo<usize>) -> &RopeStr {
+        RopeStr::new(&self.0[index])
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::{RopeString, MAX_CHUNK_LEN};
+    use crate::dom::unicode_string::{UnicodeString, UnicodeStringExt};
+
+    fn long_text(len: usize) -> String {
+        "abcdefghij".chars().cycle().take(len).collect()
+    }
+
+    #[test]
+    fn inserting_in_the_middle_of_a_long_string_splits_its_chunk() {
+        let text = long_text(MAX_CHUNK_LEN * 3);
+        let mut s = RopeString::from(text.as_str());
+        let chunks_before = s.chunks.len();
+        s.insert(MAX_CHUNK_LEN, &RopeString::from(long_text(10).as_str()));
+        s.insert(MAX_CHUNK_LEN, &RopeString::from("😄"));
+
+        let mut expected = text.clone();
+        expected.insert_str(MAX_CHUNK_LEN, "😄abcdefghij");
+        assert_eq!(s.to_string(), expected);
+        assert!(s.chunks.len() >= chunks_before);
+        assert!(s.chunks.iter().all(|c| c.len() <= MAX_CHUNK_LEN));
+    }
+
+    #[test]
+    fn chunks_are_not_split_inside_a_surrogate_pair() {
+        let text = format!("a{}", "😄".repeat(MAX_CHUNK_LEN));
+        let s = RopeString::from(text.as_str());
+        assert!(s.chunks.len() > 1);
+        assert!(s.chunks.iter().all(|c| c.is_char_boundary(0)));
+        assert_eq!(s.to_string(), text);
+        assert_eq!(s.len(), MAX_CHUNK_LEN * 2 + 1);
+    }
+
+    #[test]
+    fn removing_across_chunk_boundaries() {
+        let text = long_text(MAX_CHUNK_LEN * 2);
+        let mut s = RopeString::from(text.as_str());
+        let first_chunk_len = s.chunks[0].len();
+        assert_eq!(s.remove_at(first_chunk_len), 'e');
+        assert_eq!(s.pop_first(), Some('a'));
+        assert_eq!(s.pop_last(), Some('h'));
+        assert_eq!(s.len(), MAX_CHUNK_LEN * 2 - 3);
+        assert_eq!(&s[..3], "bcd");
+    }
+
+    #[test]
+    fn reading_after_a_change_sees_the_change() {
+        let mut s = RopeString::from("ac");
+        assert_eq!(&s[1..], "c");
+        s.insert(1, &RopeString::from("b"));
+        assert_eq!(&s[1..], "bc");
+        s.push("d");
+        assert_eq!(s, RopeString::from("abcd"));
+        assert_eq!(s.to_string(), "abcd");
+    }
+
+    #[test]
+    fn replacing_a_range_across_chunks() {
+        let text = long_text(MAX_CHUNK_LEN * 3);
+        let mut s = RopeString::from(text.as_str());
+        let range = MAX_CHUNK_LEN - 2..MAX_CHUNK_LEN * 2 + 2;
+        s.replace_range(range.clone(), &RopeString::from("xyz"));
+
+        let mut expected = text.clone();
+        expected.replace_range(range, "xyz");
+        assert_eq!(s.to_string(), expected);
+        assert!(s.chunks.iter().all(|c| !c.is_empty()));
+    }
+
+    #[test]
+    fn reading_inside_one_chunk_does_not_join_the_chunks() {
+        let text = long_text(MAX_CHUNK_LEN * 4);
+        let mut s = RopeString::from(text.as_str());
+        let mid = MAX_CHUNK_LEN * 2;
+        s.insert(mid, &RopeString::from("x"));
+
+        assert_eq!(UnicodeString::len(&s), text.len() + 1);
+        assert_eq!(UnicodeString::char_at(&s, mid), 'x');
+        assert_eq!(UnicodeString::chars(&s).count(), text.len() + 1);
+        assert_eq!(
+            s[mid - 1..mid + 1].to_string(),
+            format!("{}x", &text[mid - 1..mid])
+        );
+        assert_eq!(&s[mid + 1..mid + 1], "");
+        assert!(s.joined.get().is_none());
+
+        assert_eq!(
+            s[1..].to_string(),
+            format!("{}x{}", &text[1..mid], &text[mid..])
+        );
+        assert!(s.joined.get().is_some());
+    }
+
+    #[test]
+    fn removing_everything_leaves_an_empty_string() {
+        let mut s = RopeString::from("ab");
+        s.pop_last();
+        s.pop_first();
+        assert!(s.is_empty());
+        assert_eq!(s.pop_last(), None);
+        assert_eq!(s, RopeString::new());
+    }
+}
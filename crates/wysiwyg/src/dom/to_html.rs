@@ -12,7 +12,11 @@
 // See the License for the specific language governing permissions and
 // limitations under the License.
 
+use std::collections::HashMap;
+use std::sync::Arc;
+
 use crate::composer_model::example_format::SelectionWriter;
+use crate::dom::html_source_map::HtmlSourceMapRecorder;
 
 use super::{
     nodes::dom_node::DomNodeKind, unicode_string::UnicodeStringExt,
@@ -43,6 +47,21 @@ where
         buf
     }
 
+    /// Like [ToHtml::to_message_html], but writing custom inline tags with
+    /// the given serializers, by tag.
+    fn to_message_html_with_serializers(
+        &self,
+        serializers: &CustomTagSerializers,
+    ) -> S {
+        let state = ToHtmlState {
+            custom_tag_serializers: Some(serializers),
+            ..ToHtmlState::default()
+        };
+        let mut buf = S::default();
+        self.fmt_html(&mut buf, None, &state, true);
+        buf
+    }
+
     /// Convert to a literal HTML represention of the source object
     fn to_html(&self) -> S {
         let mut buf = S::default();
@@ -99,8 +118,56 @@ where
 /// State of the HTML generation at every `fmt_html` call, usually used to pass info from ancestor
 /// nodes to their descendants.
 #[derive(Clone, Default)]
-pub struct ToHtmlState {
+pub struct ToHtmlState<'a> {
     pub is_inside_code_block: bool,
     pub prev_sibling: Option<DomNodeKind>,
     pub next_sibling: Option<DomNodeKind>,
+    /// Set when the previous sibling is an aligned paragraph, which keeps
+    /// its `<p>` tag in message HTML
+    pub(crate) prev_sibling_is_aligned: bool,
+    /// Set when the HTML range of each node should be recorded
+    pub(crate) source_map: Option<HtmlSourceMapRecorder>,
+    /// How custom inline tags are written in message HTML, by tag
+    pub(crate) custom_tag_serializers: Option<&'a CustomTagSerializers>,
+    /// Set when links in message HTML should get safe `target` and `rel`
+    /// attributes, see [crate::ComposerModel::set_safe_link_attributes]
+    pub(crate) safe_link_attributes: bool,
+}
+
+/// Writes a custom inline tag (see
+/// [crate::ComposerModel::set_custom_inline_tags]) in message HTML, for a
+/// host that needs something other than the tag and its attributes, e.g. to
+/// send `<kbd>` as a `<code>` that other clients understand. Its children
+/// are written as usual between what [Self::open] and [Self::close]
+/// return, which are not escaped.
+pub trait CustomTagSerializer: Send + Sync {
+    /// The HTML written before the children of the tag. The values in
+    /// `attrs` are already escaped, so they can be written between double
+    /// quotes as they are.
+    fn open(&self, tag: &str, attrs: &[(String, String)]) -> String;
+
+    /// The HTML written after the children of the tag.
+    fn close(&self, tag: &str) -> String;
+}
+
+/// Serializers for custom inline tags, by tag.
+pub type CustomTagSerializers = HashMap<String, Arc<dyn CustomTagSerializer>>;
+
+#[cfg(test)]
+mod test {
+    use crate::tests::testutils_composer_model::{cm, TestString};
+    use crate::{ToHtml, ToMarkdown, ToRawText, ToTree};
+
+    #[test]
+    fn serialization_traits_can_be_used_as_trait_objects() {
+        let dom = cm("<em>a</em>|").state.dom;
+        let html: &dyn ToHtml<TestString> = &dom;
+        let markdown: &dyn ToMarkdown<TestString> = dom.document();
+        let raw_text: &dyn ToRawText<TestString> = &dom;
+        let tree: &dyn ToTree<TestString> = &dom;
+        assert_eq!(html.to_html(), "<em>a</em>");
+        assert_eq!(markdown.to_markdown().unwrap(), "*a*");
+        assert_eq!(raw_text.to_raw_text(), "a");
+        assert!(!tree.to_tree().is_empty());
+    }
 }
@@ -12,6 +12,7 @@
 // See the License for the specific language governing permissions and
 // limitations under the License.
 
+use super::unicode_string::{UnicodeStr, UnicodeStringExt};
 use super::UnicodeString;
 use std::{error::Error, fmt};
 
@@ -50,14 +51,26 @@ where
     ) -> Result<(), MarkdownError<S>>;
 
     fn to_message_markdown(&self) -> Result<S, MarkdownError<S>> {
+        self.to_message_markdown_with_options(MarkdownOptions::empty())
+    }
+    fn to_markdown(&self) -> Result<S, MarkdownError<S>> {
+        self.to_markdown_with_options(MarkdownOptions::empty())
+    }
+    fn to_message_markdown_with_options(
+        &self,
+        options: MarkdownOptions,
+    ) -> Result<S, MarkdownError<S>> {
         let mut buffer = S::default();
-        self.fmt_markdown(&mut buffer, &MarkdownOptions::empty(), true)?;
+        self.fmt_markdown(&mut buffer, &options, true)?;
 
         Ok(buffer)
     }
-    fn to_markdown(&self) -> Result<S, MarkdownError<S>> {
+    fn to_markdown_with_options(
+        &self,
+        options: MarkdownOptions,
+    ) -> Result<S, MarkdownError<S>> {
         let mut buffer = S::default();
-        self.fmt_markdown(&mut buffer, &MarkdownOptions::empty(), false)?;
+        self.fmt_markdown(&mut buffer, &options, false)?;
 
         Ok(buffer)
     }
@@ -70,6 +83,18 @@ pub struct MarkdownOptions {
 
 impl MarkdownOptions {
     pub const IGNORE_LINE_BREAK: Self = Self { bits: 0b0001 };
+    /// Push text as-is, without escaping any Markdown-significant
+    /// characters.
+    pub const NO_ESCAPING: Self = Self { bits: 0b0010 };
+    /// Escape every ASCII punctuation character in text, not only the ones
+    /// which would change the meaning of the Markdown.
+    pub const ESCAPE_ALL_PUNCTUATION: Self = Self { bits: 0b0100 };
+    /// Set while formatting the content of inline code or a code block,
+    /// where text must never be escaped.
+    pub(crate) const IN_CODE: Self = Self { bits: 0b1000 };
+    /// Produce a human readable plain text fallback rather than Markdown:
+    /// lighter formatting markers, URLs after link text and no escaping.
+    pub(crate) const FALLBACK_TEXT: Self = Self { bits: 0b1_0000 };
 
     pub const fn empty() -> Self {
         Self { bits: 0 }
@@ -85,3 +110,106 @@ impl MarkdownOptions {
         self.bits |= other.bits;
     }
 }
+
+/// Push `text` into `buffer`, escaping the characters that would otherwise
+/// be interpreted as Markdown, according to the escaping `options`.
+///
+/// Characters that start blocks (e.g. `#` for headings, `-` for lists) are
+/// only escaped at the start of a line, i.e. when nothing but block markers
+/// precede them in `buffer`.
+pub(crate) fn push_escaped_markdown<S>(
+    buffer: &mut S,
+    text: &S::Str,
+    options: &MarkdownOptions,
+) where
+    S: UnicodeString,
+{
+    if options.contains(MarkdownOptions::NO_ESCAPING)
+        || options.contains(MarkdownOptions::IN_CODE)
+        || options.contains(MarkdownOptions::FALLBACK_TEXT)
+    {
+        buffer.push(text);
+        return;
+    }
+
+    let chars: Vec<char> = text.chars().collect();
+    let escape_all = options.contains(MarkdownOptions::ESCAPE_ALL_PUNCTUATION);
+    let mut at_line_start = !escape_all
+        && chars
+            .iter()
+            .find(|c| !c.is_whitespace())
+            .map_or(false, |c| is_block_marker(*c))
+        && is_at_line_start(buffer);
+    let mut escaped = String::with_capacity(chars.len());
+
+    for (i, &c) in chars.iter().enumerate() {
+        let next = chars.get(i + 1);
+        let needs_escape = if escape_all {
+            c.is_ascii_punctuation()
+        } else {
+            match c {
+                '\\' | '*' | '_' | '`' | '[' | ']' | '~' | '<' => true,
+                // Would be read as an entity, e.g. `&amp;`
+                '&' => next.map_or(false, |n| n.is_alphanumeric() || *n == '#'),
+                '#' | '>' | '-' | '+' | '=' => at_line_start,
+                // Ordered list marker, e.g. `1.`
+                '.' | ')' => {
+                    at_line_start && i > 0 && chars[i - 1].is_ascii_digit()
+                }
+                _ => false,
+            }
+        };
+        if needs_escape {
+            escaped.push('\\');
+        }
+        escaped.push(c);
+
+        if c == '\n' {
+            at_line_start = true;
+        } else if !c.is_whitespace() && !c.is_ascii_digit() {
+            at_line_start = false;
+        }
+    }
+
+    buffer.push(escaped.as_str());
+}
+
+fn is_block_marker(c: char) -> bool {
+    matches!(c, '#' | '>' | '-' | '+' | '=') || c.is_ascii_digit()
+}
+
+/// Whether only whitespace and the markers of quotes and lists come after
+/// the last line break in the buffer.
+fn is_at_line_start<S>(buffer: &S) -> bool
+where
+    S: UnicodeString,
+{
+    // Only look back as far as the last line break, not at the whole buffer
+    let newline = S::CodeUnit::from(b'\n');
+    let line_start = buffer
+        .as_ref()
+        .iter()
+        .rposition(|u| *u == newline)
+        .map_or(0, |i| i + 1);
+    let line = buffer[line_start..].to_string();
+    let mut rest = line.trim_start();
+    loop {
+        rest = rest.trim_start();
+        if let Some(r) = rest.strip_prefix('>') {
+            rest = r;
+        } else if let Some(r) = rest
+            .strip_prefix("- ")
+            .or_else(|| rest.strip_prefix("* "))
+            .or_else(|| rest.strip_prefix("+ "))
+        {
+            rest = r;
+        } else {
+            let digits = rest.trim_start_matches(|c: char| c.is_ascii_digit());
+            match digits.strip_prefix(". ") {
+                Some(r) if digits.len() < rest.len() => rest = r,
+                _ => break,
+            }
+        }
+    }
+    rest.is_empty()
+}
@@ -20,3 +20,37 @@ where
 {
     fn to_plain_text(&self) -> S;
 }
+
+/// How `ComposerModel::get_content_as_plain_text_with_options` tidies the
+/// plain text. The default keeps it as `get_content_as_plain_text` gives it.
+#[derive(Debug, Default, Copy, Clone, PartialEq, Eq)]
+pub struct PlainTextOptions {
+    /// Replace each run of whitespace, including line breaks, with a single
+    /// space, e.g. to show the text on one line.
+    pub collapse_whitespace: bool,
+    /// Remove whitespace from the start and end of the text.
+    pub trim: bool,
+}
+
+impl PlainTextOptions {
+    pub(crate) fn apply(&self, text: &str) -> String {
+        let text = if self.trim { text.trim() } else { text };
+        if !self.collapse_whitespace {
+            return text.to_owned();
+        }
+        let mut collapsed = String::with_capacity(text.len());
+        let mut in_whitespace = false;
+        for c in text.chars() {
+            if c.is_whitespace() {
+                if !in_whitespace {
+                    collapsed.push(' ');
+                }
+                in_whitespace = true;
+            } else {
+                collapsed.push(c);
+                in_whitespace = false;
+            }
+        }
+        collapsed
+    }
+}
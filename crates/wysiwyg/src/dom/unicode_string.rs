@@ -15,6 +15,7 @@
 use std::fmt;
 use std::iter;
 use std::ops::{Deref, Index, Range, RangeFrom, RangeTo};
+use std::vec;
 use unicode_segmentation::UnicodeSegmentation;
 
 use widestring::{Utf16Str, Utf16String, Utf32Str, Utf32String};
@@ -23,6 +24,14 @@ use widestring::{Utf16Str, Utf16String, Utf32Str, Utf32String};
 /// contain valid Unicode, and allow slicing by code unit positions.
 /// We implement this for String, Utf16String and Utf32String (from the
 /// widestring crate).
+/// With the `rope` feature, there is also [crate::RopeString] for very long
+/// documents.
+///
+/// `Deref`, `Index` and `AsRef<[CodeUnit]>` all hand out borrowed slices, so
+/// implementations which don't store their code units contiguously must
+/// join them up when they are read. Editing and reading through the methods
+/// of this trait, like [UnicodeString::replace_range] and
+/// [UnicodeString::len], lets them avoid that.
 pub trait UnicodeString:
     Clone
     + fmt::Debug
@@ -49,6 +58,37 @@ pub trait UnicodeString:
     fn remove_at(&mut self, idx: usize) -> char;
     fn pop_first(&mut self) -> Option<char>;
     fn pop_last(&mut self) -> Option<char>;
+
+    /// Replace the code units in `range` with `s`. By default this builds
+    /// a new string, so implementations which can change themselves in
+    /// place should do that instead.
+    fn replace_range(&mut self, range: Range<usize>, s: &Self::Str) {
+        let mut new_string = self[..range.start].to_owned();
+        new_string.extend(iter::once(s));
+        new_string.extend(iter::once(&self[range.end..]));
+        *self = new_string;
+    }
+
+    // The methods below read the string without going through `Deref`, so
+    // that implementations which aren't contiguous can avoid joining up
+    // their code units to answer them.
+
+    /// The length in code units of the current encoding.
+    fn len(&self) -> usize {
+        UnicodeStrExt::len(self.deref())
+    }
+
+    fn is_empty(&self) -> bool {
+        UnicodeStrExt::is_empty(self.deref())
+    }
+
+    fn chars(&self) -> Box<dyn Iterator<Item = char> + '_> {
+        UnicodeStr::chars(self.deref())
+    }
+
+    fn char_at(&self, idx: usize) -> char {
+        UnicodeStr::char_at(self.deref(), idx)
+    }
 }
 
 pub trait UnicodeStr:
@@ -94,6 +134,9 @@ impl UnicodeString for String {
     fn pop_last(&mut self) -> Option<char> {
         self.pop()
     }
+    fn replace_range(&mut self, range: Range<usize>, s: &Self::Str) {
+        String::replace_range(self, range, s);
+    }
 }
 
 impl UnicodeStr for str {
@@ -133,6 +176,9 @@ impl UnicodeString for Utf16String {
     fn pop_last(&mut self) -> Option<char> {
         self.pop()
     }
+    fn replace_range(&mut self, range: Range<usize>, s: &Self::Str) {
+        Utf16String::replace_range(self, range, s);
+    }
 }
 
 impl UnicodeStr for Utf16Str {
@@ -172,6 +218,9 @@ impl UnicodeString for Utf32String {
     fn pop_last(&mut self) -> Option<char> {
         self.pop()
     }
+    fn replace_range(&mut self, range: Range<usize>, s: &Self::Str) {
+        Utf32String::replace_range(self, range, s);
+    }
 }
 
 impl UnicodeStr for Utf32Str {
@@ -214,7 +263,26 @@ pub trait UnicodeStrExt: UnicodeStr {
         &self,
         index: usize,
     ) -> (Option<Self::StringType>, Option<Self::StringType>);
+    #[deprecated(
+        since = "2.38.0",
+        note = "use the grapheme helpers, which work in the string's own encoding"
+    )]
+    #[allow(dead_code)]
     fn u8_map_index(&self, pos: usize) -> usize;
+    /// The grapheme clusters (what a user sees as a single character, e.g. a
+    /// complex emoji) of the string, each with the offset it starts at in the
+    /// current character encoding.
+    fn grapheme_clusters(&self) -> vec::IntoIter<(usize, Self::StringType)>;
+    /// The number of grapheme clusters in the string.
+    fn grapheme_count(&self) -> usize;
+    /// The offset, in the current character encoding, just after the first
+    /// `n` grapheme clusters, so `0` for `n == 0` and `len()` for the number
+    /// of grapheme clusters. `None` if the string has fewer than `n`.
+    fn nth_grapheme_boundary(&self, n: usize) -> Option<usize>;
+    /// The offset of the closest grapheme boundary before [pos], if any.
+    fn previous_grapheme_boundary(&self, pos: usize) -> Option<usize>;
+    /// The offset of the closest grapheme boundary after [pos], if any.
+    fn next_grapheme_boundary(&self, pos: usize) -> Option<usize>;
     /// Iterate over the characters before given position, until reaching a whitespace
     /// or the start of the `UnicodeString`.
     /// Returns the offset (bytes) to the whitespace or to the start, with the current character encoding.
@@ -244,18 +312,16 @@ impl<S: UnicodeStr + ?Sized> UnicodeStrExt for S {
         &self,
         index: usize,
     ) -> (Option<Self::StringType>, Option<Self::StringType>) {
-        let u8_str = self.to_string();
-        let u8_index = self.u8_map_index(index);
-        let graphemes = u8_str.grapheme_indices(true);
+        if index > self.len() {
+            panic!("Index is out of bounds.");
+        }
         let mut prev = None;
         let mut next = None;
-        for g in graphemes {
-            if g.0 == u8_index {
-                next = Some(Self::StringType::from(g.1))
-            }
-            let length = g.1.len();
-            if g.0 + length == u8_index {
-                prev = Some(Self::StringType::from(g.1))
+        for (start, grapheme) in self.grapheme_clusters() {
+            if start == index {
+                next = Some(grapheme)
+            } else if start + grapheme.len() == index {
+                prev = Some(grapheme)
             }
         }
         (prev, next)
@@ -285,6 +351,46 @@ impl<S: UnicodeStr + ?Sized> UnicodeStrExt for S {
         pos_u8
     }
 
+    fn grapheme_clusters(&self) -> vec::IntoIter<(usize, Self::StringType)> {
+        let u8_str = self.to_string();
+        let mut offset = 0;
+        u8_str
+            .graphemes(true)
+            .map(|g| {
+                let start = offset;
+                offset += g.chars().map(|c| self.char_len(&c)).sum::<usize>();
+                (start, Self::StringType::from(g))
+            })
+            .collect::<Vec<_>>()
+            .into_iter()
+    }
+
+    fn grapheme_count(&self) -> usize {
+        self.to_string().graphemes(true).count()
+    }
+
+    fn nth_grapheme_boundary(&self, n: usize) -> Option<usize> {
+        if n == 0 {
+            return Some(0);
+        }
+        self.grapheme_clusters()
+            .nth(n - 1)
+            .map(|(start, grapheme)| start + grapheme.len())
+    }
+
+    fn previous_grapheme_boundary(&self, pos: usize) -> Option<usize> {
+        self.grapheme_clusters()
+            .map(|(start, _)| start)
+            .take_while(|start| *start < pos)
+            .last()
+    }
+
+    fn next_grapheme_boundary(&self, pos: usize) -> Option<usize> {
+        self.grapheme_clusters()
+            .map(|(start, grapheme)| start + grapheme.len())
+            .find(|end| *end > pos)
+    }
+
     fn previous_whitespace_offset(&self, pos: usize) -> usize {
         let mut offset = 0;
         while let Some(prev) = self.find_graphemes_at(pos - offset).0 {
@@ -439,4 +545,62 @@ mod test {
         assert_eq!(str.next_whitespace_offset(3), 4);
         assert_eq!(str.next_whitespace_offset(11), 4);
     }
+
+    #[test]
+    #[allow(deprecated)]
+    fn test_u8_map_index_utf16() {
+        let str = Utf16String::from_str("a😄b");
+        assert_eq!(str.u8_map_index(1), 1);
+        assert_eq!(str.u8_map_index(3), 5);
+        assert_eq!(str.u8_map_index(4), 6);
+    }
+
+    #[test]
+    fn test_grapheme_clusters_utf8() {
+        let str = "a😮‍💨b";
+        let clusters: Vec<_> = str.grapheme_clusters().collect();
+        assert_eq!(
+            clusters,
+            vec![
+                (0, "a".to_owned()),
+                (1, "😮‍💨".to_owned()),
+                (12, "b".to_owned())
+            ]
+        );
+    }
+
+    #[test]
+    fn test_grapheme_clusters_utf16() {
+        let str = Utf16String::from_str("a😮‍💨b");
+        let starts: Vec<_> =
+            str.grapheme_clusters().map(|(start, _)| start).collect();
+        assert_eq!(starts, vec![0, 1, 6]);
+    }
+
+    #[test]
+    fn test_grapheme_count() {
+        assert_eq!("".grapheme_count(), 0);
+        assert_eq!("a😮‍💨b".grapheme_count(), 3);
+        assert_eq!(Utf32String::from_str("a😮‍💨b").grapheme_count(), 3);
+    }
+
+    #[test]
+    fn test_nth_grapheme_boundary_utf16() {
+        let str = Utf16String::from_str("a😮‍💨b");
+        assert_eq!(str.nth_grapheme_boundary(0), Some(0));
+        assert_eq!(str.nth_grapheme_boundary(1), Some(1));
+        assert_eq!(str.nth_grapheme_boundary(2), Some(6));
+        assert_eq!(str.nth_grapheme_boundary(3), Some(7));
+        assert_eq!(str.nth_grapheme_boundary(4), None);
+    }
+
+    #[test]
+    fn test_previous_and_next_grapheme_boundary_utf32() {
+        let str = Utf32String::from_str("a😮‍💨b");
+        assert_eq!(str.previous_grapheme_boundary(0), None);
+        assert_eq!(str.previous_grapheme_boundary(4), Some(1));
+        assert_eq!(str.previous_grapheme_boundary(2), Some(1));
+        assert_eq!(str.next_grapheme_boundary(1), Some(4));
+        assert_eq!(str.next_grapheme_boundary(5), None);
+    }
 }
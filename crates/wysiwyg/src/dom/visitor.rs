@@ -0,0 +1,305 @@
+// Copyright 2023 The Matrix.org Foundation C.I.C.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! A [DomVisitor] walks a [Dom] with one method per kind of node, so that
+//! exporters and analyses don't each need their own traversal.
+//!
+//! Each `visit_*` method of a container visits its children by default, by
+//! calling [walk_children]. A visitor overriding one can do something
+//! before and after calling [walk_children] itself, e.g. write an opening
+//! and a closing tag, or not call it to skip the children.
+
+use crate::dom::nodes::{
+    ContainerNode, ContainerNodeKind, DomNode, HorizontalRuleNode, ImageNode,
+    LineBreakNode, MentionNode, TextNode,
+};
+use crate::dom::Dom;
+use crate::{HeadingLevel, InlineFormatType, ListType, UnicodeString};
+
+pub trait DomVisitor<S>
+where
+    S: UnicodeString,
+{
+    /// The root node of the document.
+    fn visit_document(&mut self, node: &ContainerNode<S>) {
+        walk_children(self, node);
+    }
+
+    fn visit_paragraph(&mut self, node: &ContainerNode<S>) {
+        walk_children(self, node);
+    }
+
+    fn visit_heading(&mut self, node: &ContainerNode<S>, _level: HeadingLevel) {
+        walk_children(self, node);
+    }
+
+    fn visit_quote(&mut self, node: &ContainerNode<S>) {
+        walk_children(self, node);
+    }
+
+    fn visit_code_block(&mut self, node: &ContainerNode<S>) {
+        walk_children(self, node);
+    }
+
+    fn visit_list(&mut self, node: &ContainerNode<S>, _list_type: &ListType) {
+        walk_children(self, node);
+    }
+
+    fn visit_list_item(&mut self, node: &ContainerNode<S>) {
+        walk_children(self, node);
+    }
+
+    /// Bold, italic, inline code and the other inline formats, including
+    /// custom tags.
+    fn visit_formatting(
+        &mut self,
+        node: &ContainerNode<S>,
+        _format: &InlineFormatType,
+    ) {
+        walk_children(self, node);
+    }
+
+    fn visit_link(&mut self, node: &ContainerNode<S>, _url: &S) {
+        walk_children(self, node);
+    }
+
+    /// Inline or, if `display` is true, block maths. Its only child is the
+    /// text of its LaTeX source.
+    fn visit_math(&mut self, node: &ContainerNode<S>, _display: bool) {
+        walk_children(self, node);
+    }
+
+    fn visit_text(&mut self, _node: &TextNode<S>) {}
+
+    fn visit_line_break(&mut self, _node: &LineBreakNode<S>) {}
+
+    fn visit_mention(&mut self, _node: &MentionNode<S>) {}
+
+    fn visit_image(&mut self, _node: &ImageNode<S>) {}
+
+    fn visit_horizontal_rule(&mut self, _node: &HorizontalRuleNode<S>) {}
+}
+
+/// Call the `visit_*` method of `visitor` for the kind of `node`.
+pub fn walk_node<S, V>(visitor: &mut V, node: &DomNode<S>)
+where
+    S: UnicodeString,
+    V: DomVisitor<S> + ?Sized,
+{
+    match node {
+        DomNode::Container(container) => match container.kind() {
+            ContainerNodeKind::Generic => visitor.visit_document(container),
+            ContainerNodeKind::Paragraph => visitor.visit_paragraph(container),
+            ContainerNodeKind::Heading(level) => {
+                visitor.visit_heading(container, *level)
+            }
+            ContainerNodeKind::Quote => visitor.visit_quote(container),
+            ContainerNodeKind::CodeBlock => visitor.visit_code_block(container),
+            ContainerNodeKind::List(list_type) => {
+                visitor.visit_list(container, list_type)
+            }
+            ContainerNodeKind::ListItem => visitor.visit_list_item(container),
+            ContainerNodeKind::Formatting(format) => {
+                visitor.visit_formatting(container, format)
+            }
+            ContainerNodeKind::Link(url) => visitor.visit_link(container, url),
+            ContainerNodeKind::Math => visitor.visit_math(container, false),
+            ContainerNodeKind::MathBlock => visitor.visit_math(container, true),
+        },
+        DomNode::Text(text) => visitor.visit_text(text),
+        DomNode::LineBreak(line_break) => visitor.visit_line_break(line_break),
+        DomNode::Mention(mention) => visitor.visit_mention(mention),
+        DomNode::Image(image) => visitor.visit_image(image),
+        DomNode::HorizontalRule(rule) => visitor.visit_horizontal_rule(rule),
+    }
+}
+
+/// Visit each child of `node` in order.
+pub fn walk_children<S, V>(visitor: &mut V, node: &ContainerNode<S>)
+where
+    S: UnicodeString,
+    V: DomVisitor<S> + ?Sized,
+{
+    for child in node.children() {
+        walk_node(visitor, child);
+    }
+}
+
+impl<S> Dom<S>
+where
+    S: UnicodeString,
+{
+    /// Walk the whole document with `visitor`, starting at the root.
+    pub fn accept<V>(&self, visitor: &mut V)
+    where
+        V: DomVisitor<S> + ?Sized,
+    {
+        walk_node(visitor, self.document_node());
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::{walk_children, DomVisitor};
+    use crate::dom::nodes::{
+        ContainerNode, LineBreakNode, MentionNode, TextNode,
+    };
+    use crate::tests::testutils_composer_model::{cm, TestString};
+    use crate::{InlineFormatType, ListType};
+
+    /// Writes the content as BBCode, as an example of an exporter.
+    #[derive(Default)]
+    struct BbCode(String);
+
+    impl BbCode {
+        fn tag(
+            &mut self,
+            tag: &str,
+            node: &ContainerNode<TestString>,
+            arg: Option<String>,
+        ) {
+            match arg {
+                Some(arg) => self.0.push_str(&format!("[{tag}={arg}]")),
+                None => self.0.push_str(&format!("[{tag}]")),
+            }
+            walk_children(self, node);
+            self.0.push_str(&format!("[/{tag}]"));
+        }
+    }
+
+    impl DomVisitor<TestString> for BbCode {
+        fn visit_paragraph(&mut self, node: &ContainerNode<TestString>) {
+            walk_children(self, node);
+            self.0.push('\n');
+        }
+
+        fn visit_quote(&mut self, node: &ContainerNode<TestString>) {
+            self.tag("quote", node, None);
+        }
+
+        fn visit_code_block(&mut self, node: &ContainerNode<TestString>) {
+            self.tag("code", node, None);
+        }
+
+        fn visit_list(
+            &mut self,
+            node: &ContainerNode<TestString>,
+            list_type: &ListType,
+        ) {
+            let arg =
+                matches!(list_type, ListType::Ordered).then(|| "1".into());
+            self.tag("list", node, arg);
+        }
+
+        fn visit_list_item(&mut self, node: &ContainerNode<TestString>) {
+            self.0.push_str("[*]");
+            walk_children(self, node);
+        }
+
+        fn visit_formatting(
+            &mut self,
+            node: &ContainerNode<TestString>,
+            format: &InlineFormatType,
+        ) {
+            match format {
+                InlineFormatType::Bold => self.tag("b", node, None),
+                InlineFormatType::Italic => self.tag("i", node, None),
+                _ => walk_children(self, node),
+            }
+        }
+
+        fn visit_link(
+            &mut self,
+            node: &ContainerNode<TestString>,
+            url: &TestString,
+        ) {
+            self.tag("url", node, Some(url.to_string()));
+        }
+
+        fn visit_text(&mut self, node: &TextNode<TestString>) {
+            self.0.push_str(&node.data().to_string());
+        }
+
+        fn visit_line_break(&mut self, _node: &LineBreakNode<TestString>) {
+            self.0.push('\n');
+        }
+
+        fn visit_mention(&mut self, node: &MentionNode<TestString>) {
+            self.0.push_str(&format!("@{}", node.display_text()));
+        }
+    }
+
+    fn bbcode(example: &str) -> String {
+        let mut visitor = BbCode::default();
+        cm(example).state.dom.accept(&mut visitor);
+        visitor.0
+    }
+
+    #[test]
+    fn an_exporter_only_handles_the_nodes_it_needs_to() {
+        assert_eq!(
+            bbcode("<p>a <b>b</b> <u>c</u></p><ol><li><i>d</i></li></ol>|"),
+            "a [b]b[/b] c\n[list=1][*][i]d[/i][/list]"
+        );
+        assert_eq!(
+            bbcode("<a href=\"https://matrix.org\">link|</a>"),
+            "[url=https://matrix.org]link[/url]"
+        );
+    }
+
+    #[test]
+    fn mentions_and_quotes_are_visited() {
+        assert_eq!(
+            bbcode(
+                "<blockquote><p>hi \
+                <a href=\"https://matrix.to/#/@alice:matrix.org\">Alice</a>|\
+                </p></blockquote>"
+            ),
+            "[quote]hi @Alice\n[/quote]"
+        );
+    }
+
+    /// Counts text nodes and the text in lists, as an example of an analysis.
+    #[derive(Default)]
+    struct Counter {
+        nodes: usize,
+        in_lists: usize,
+    }
+
+    impl DomVisitor<TestString> for Counter {
+        fn visit_list(
+            &mut self,
+            node: &ContainerNode<TestString>,
+            _list_type: &ListType,
+        ) {
+            self.in_lists += node.text_len();
+            walk_children(self, node);
+        }
+
+        fn visit_text(&mut self, _node: &TextNode<TestString>) {
+            self.nodes += 1;
+        }
+    }
+
+    #[test]
+    fn unhandled_containers_visit_their_children() {
+        let mut counter = Counter::default();
+        cm("<p><b><i>a</i></b></p><ul><li>bc|</li></ul>")
+            .state
+            .dom
+            .accept(&mut counter);
+        assert_eq!(counter.nodes, 2);
+        assert_eq!(counter.in_lists, 2);
+    }
+}
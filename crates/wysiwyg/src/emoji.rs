@@ -0,0 +1,262 @@
+// Copyright 2023 The Matrix.org Foundation C.I.C.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Normalising the emoji in inserted text, so that the same emoji is always
+//! stored as the same fully-qualified sequence. Platforms render these
+//! consistently, and grapheme boundaries never fall inside them.
+
+use std::borrow::Cow;
+
+const ZWJ: char = '\u{200D}';
+const VS15: char = '\u{FE0E}';
+const VS16: char = '\u{FE0F}';
+const KEYCAP: char = '\u{20E3}';
+
+/// Symbols in the BMP which may be shown as emoji.
+const BMP_EMOJI: &[(u32, u32)] = &[
+    (0x00A9, 0x00A9),
+    (0x00AE, 0x00AE),
+    (0x203C, 0x203C),
+    (0x2049, 0x2049),
+    (0x2122, 0x2122),
+    (0x2139, 0x2139),
+    (0x2194, 0x21AA),
+    (0x231A, 0x23FF),
+    (0x24C2, 0x24C2),
+    (0x25AA, 0x25FE),
+    (0x2600, 0x27BF),
+    (0x2934, 0x2935),
+    (0x2B05, 0x2B55),
+    (0x3030, 0x3030),
+    (0x303D, 0x303D),
+    (0x3297, 0x3297),
+    (0x3299, 0x3299),
+];
+
+/// Symbols in the BMP which are shown as emoji without VS16.
+const BMP_EMOJI_PRESENTATION: &[(u32, u32)] = &[
+    (0x231A, 0x231B),
+    (0x23E9, 0x23EC),
+    (0x23F0, 0x23F0),
+    (0x23F3, 0x23F3),
+    (0x25FD, 0x25FE),
+    (0x2614, 0x2615),
+    (0x2648, 0x2653),
+    (0x267F, 0x267F),
+    (0x2693, 0x2693),
+    (0x26A1, 0x26A1),
+    (0x26AA, 0x26AB),
+    (0x26BD, 0x26BE),
+    (0x26C4, 0x26C5),
+    (0x26CE, 0x26CE),
+    (0x26D4, 0x26D4),
+    (0x26EA, 0x26EA),
+    (0x26F2, 0x26F3),
+    (0x26F5, 0x26F5),
+    (0x26FA, 0x26FA),
+    (0x26FD, 0x26FD),
+    (0x2705, 0x2705),
+    (0x270A, 0x270B),
+    (0x2728, 0x2728),
+    (0x274C, 0x274C),
+    (0x274E, 0x274E),
+    (0x2753, 0x2755),
+    (0x2757, 0x2757),
+    (0x2795, 0x2797),
+    (0x27B0, 0x27B0),
+    (0x27BF, 0x27BF),
+    (0x2B1B, 0x2B1C),
+    (0x2B50, 0x2B50),
+    (0x2B55, 0x2B55),
+];
+
+/// Emoji outside the BMP which are shown as text without VS16.
+const SMP_TEXT_PRESENTATION: &[(u32, u32)] = &[
+    (0x1F170, 0x1F171),
+    (0x1F17E, 0x1F17F),
+    (0x1F202, 0x1F202),
+    (0x1F237, 0x1F237),
+    (0x1F321, 0x1F321),
+    (0x1F324, 0x1F32C),
+    (0x1F336, 0x1F336),
+    (0x1F37D, 0x1F37D),
+    (0x1F396, 0x1F397),
+    (0x1F399, 0x1F39B),
+    (0x1F39E, 0x1F39F),
+    (0x1F3CB, 0x1F3CE),
+    (0x1F3D4, 0x1F3DF),
+    (0x1F3F3, 0x1F3F3),
+    (0x1F3F5, 0x1F3F5),
+    (0x1F3F7, 0x1F3F7),
+    (0x1F43F, 0x1F43F),
+    (0x1F441, 0x1F441),
+    (0x1F4FD, 0x1F4FD),
+    (0x1F549, 0x1F54A),
+    (0x1F56F, 0x1F570),
+    (0x1F573, 0x1F579),
+    (0x1F587, 0x1F587),
+    (0x1F58A, 0x1F58D),
+    (0x1F590, 0x1F590),
+    (0x1F5A5, 0x1F5A5),
+    (0x1F5A8, 0x1F5A8),
+    (0x1F5B1, 0x1F5B2),
+    (0x1F5BC, 0x1F5BC),
+    (0x1F5C2, 0x1F5C4),
+    (0x1F5D1, 0x1F5D3),
+    (0x1F5DC, 0x1F5DE),
+    (0x1F5E1, 0x1F5E1),
+    (0x1F5E3, 0x1F5E3),
+    (0x1F5E8, 0x1F5E8),
+    (0x1F5EF, 0x1F5EF),
+    (0x1F5F3, 0x1F5F3),
+    (0x1F5FA, 0x1F5FA),
+    (0x1F6CB, 0x1F6CB),
+    (0x1F6CD, 0x1F6CF),
+    (0x1F6E0, 0x1F6E5),
+    (0x1F6E9, 0x1F6E9),
+    (0x1F6F0, 0x1F6F0),
+    (0x1F6F3, 0x1F6F3),
+];
+
+fn in_ranges(c: char, ranges: &[(u32, u32)]) -> bool {
+    let c = c as u32;
+    ranges
+        .iter()
+        .any(|(start, end)| (*start..=*end).contains(&c))
+}
+
+fn is_skin_tone(c: char) -> bool {
+    ('\u{1F3FB}'..='\u{1F3FF}').contains(&c)
+}
+
+fn is_emoji(c: char) -> bool {
+    in_ranges(c, BMP_EMOJI)
+        || (('\u{1F000}'..='\u{1FAFF}').contains(&c) && !is_skin_tone(c))
+}
+
+/// Whether `c` is an emoji which needs VS16 to be shown as one.
+fn has_text_presentation(c: char) -> bool {
+    if c as u32 <= 0xFFFF {
+        in_ranges(c, BMP_EMOJI) && !in_ranges(c, BMP_EMOJI_PRESENTATION)
+    } else {
+        in_ranges(c, SMP_TEXT_PRESENTATION)
+    }
+}
+
+/// Normalise the emoji sequences in `text`:
+/// * VS16 is added to keycaps, e.g. `1⃣` becomes `1️⃣`, and to emoji with a
+///   text presentation inside ZWJ sequences, e.g. `❤` in `👩‍❤‍👨`.
+/// * VS16 is removed before a skin tone, where it would split the emoji
+///   from its skin tone.
+/// * A ZWJ after an emoji which isn't followed by another emoji is removed.
+///   ZWJs after other characters are kept, as some scripts need them.
+pub(crate) fn normalize_emoji(text: &str) -> Cow<'_, str> {
+    if !text.contains([ZWJ, VS16, KEYCAP]) {
+        return Cow::Borrowed(text);
+    }
+    let chars: Vec<char> = text.chars().collect();
+    let mut normalized = String::with_capacity(text.len() + 4);
+    // The emoji the last character pushed belongs to, if any
+    let mut last_emoji: Option<char> = None;
+    for (i, &c) in chars.iter().enumerate() {
+        let next = chars.get(i + 1).copied();
+        match c {
+            VS16 if next.map_or(false, is_skin_tone) => continue,
+            ZWJ if last_emoji.is_some() => {
+                if next.map_or(false, is_emoji) {
+                    normalized.push(ZWJ);
+                }
+                continue;
+            }
+            '0'..='9' | '#' | '*' if next == Some(KEYCAP) => {
+                normalized.push(c);
+                normalized.push(VS16);
+                last_emoji = None;
+                continue;
+            }
+            _ => {}
+        }
+
+        let after_zwj = normalized.ends_with(ZWJ);
+        normalized.push(c);
+        if is_emoji(c) {
+            let joined = after_zwj
+                || (next == Some(ZWJ)
+                    && chars.get(i + 2).copied().map_or(false, is_emoji));
+            if joined
+                && has_text_presentation(c)
+                && !matches!(next, Some(VS15 | VS16))
+            {
+                normalized.push(VS16);
+            }
+            last_emoji = Some(c);
+        } else if !(c == VS16 || is_skin_tone(c)) {
+            last_emoji = None;
+        }
+    }
+    Cow::Owned(normalized)
+}
+
+#[cfg(test)]
+mod test {
+    use super::normalize_emoji;
+
+    #[test]
+    fn text_without_emoji_sequences_is_unchanged() {
+        assert_eq!(normalize_emoji("abc ❤ 👍"), "abc ❤ 👍");
+    }
+
+    #[test]
+    fn vs16_is_added_inside_zwj_sequences() {
+        // Woman, ZWJ, heart, ZWJ, man
+        assert_eq!(
+            normalize_emoji("👩\u{200D}❤\u{200D}👨"),
+            "👩\u{200D}❤\u{FE0F}\u{200D}👨"
+        );
+        // Rainbow flag
+        assert_eq!(normalize_emoji("🏳\u{200D}🌈"), "🏳\u{FE0F}\u{200D}🌈");
+    }
+
+    #[test]
+    fn fully_qualified_sequences_are_unchanged() {
+        let sequence = "👩\u{200D}❤\u{FE0F}\u{200D}👨";
+        assert_eq!(normalize_emoji(sequence), sequence);
+        let sequence = "🧑🏽\u{200D}💻";
+        assert_eq!(normalize_emoji(sequence), sequence);
+    }
+
+    #[test]
+    fn vs16_is_added_to_keycaps() {
+        assert_eq!(normalize_emoji("1\u{20E3}"), "1\u{FE0F}\u{20E3}");
+        assert_eq!(normalize_emoji("#\u{FE0F}\u{20E3}"), "#\u{FE0F}\u{20E3}");
+    }
+
+    #[test]
+    fn vs16_is_removed_before_a_skin_tone() {
+        assert_eq!(normalize_emoji("✌\u{FE0F}🏽"), "✌🏽");
+    }
+
+    #[test]
+    fn dangling_zwj_after_emoji_is_removed() {
+        assert_eq!(normalize_emoji("👍\u{200D} ok"), "👍 ok");
+        assert_eq!(normalize_emoji("👩\u{200D}\u{200D}💻"), "👩\u{200D}💻");
+    }
+
+    #[test]
+    fn zwj_between_letters_is_kept() {
+        // Devanagari uses ZWJ to choose the form of a conjunct
+        let text = "क्\u{200D}ष";
+        assert_eq!(normalize_emoji(text), text);
+    }
+}
@@ -12,6 +12,7 @@
 // See the License for the specific language governing permissions and
 // limitations under the License.
 
+use crate::composer_model::action_registry::action_for_format;
 use crate::{ComposerAction, UnicodeString};
 
 #[derive(Debug, Clone, Eq, PartialEq)]
@@ -21,9 +22,52 @@ pub enum InlineFormatType {
     StrikeThrough,
     Underline,
     InlineCode,
+    /// An extra inline tag (e.g. `kbd`) which the host allowed with
+    /// [crate::ComposerModel::set_custom_inline_tags].
+    Custom(String),
+    /// A foreground colour, e.g. `#ff0000`, written as a `span` with a
+    /// `data-mx-color` attribute. Set with
+    /// [crate::ComposerModel::set_text_color].
+    TextColor(String),
+    /// A background colour, e.g. `#ffff00`, written as a `span` with a
+    /// `data-mx-bg-color` attribute. Set with
+    /// [crate::ComposerModel::set_highlight].
+    Highlight(String),
+}
+
+/// The inline tags hosts may register with
+/// [crate::ComposerModel::set_custom_inline_tags]. Each holds formatted text
+/// and isn't one the editor gives a meaning of its own.
+pub(crate) const CUSTOM_INLINE_TAGS: [&str; 16] = [
+    "abbr", "bdi", "bdo", "cite", "data", "dfn", "ins", "kbd", "mark", "q",
+    "samp", "small", "sub", "sup", "time", "var",
+];
+
+/// The entry of [CUSTOM_INLINE_TAGS] matching `tag`, if there is one.
+pub(crate) fn allowed_custom_tag(tag: &str) -> Option<&'static str> {
+    CUSTOM_INLINE_TAGS.iter().copied().find(|t| *t == tag)
+}
+
+/// Whether `color` is a colour the editor writes to `data-mx-color` and
+/// `data-mx-bg-color`, i.e. `#rrggbb`.
+pub(crate) fn is_valid_color(color: &str) -> bool {
+    color.len() == 7
+        && color.starts_with('#')
+        && color[1..].chars().all(|c| c.is_ascii_hexdigit())
+}
+
+/// Whether the colour of `format`, if it is a colour, is valid.
+pub(crate) fn has_valid_color(format: &InlineFormatType) -> bool {
+    match format {
+        InlineFormatType::TextColor(color)
+        | InlineFormatType::Highlight(color) => is_valid_color(color),
+        _ => true,
+    }
 }
 
 impl InlineFormatType {
+    /// The tag this format is written as. A custom format with a tag that
+    /// can't be registered is never applied, and is written as a `span`.
     pub fn tag(&self) -> &'static str {
         match self {
             InlineFormatType::Bold => "strong",
@@ -31,18 +75,42 @@ impl InlineFormatType {
             InlineFormatType::StrikeThrough => "del",
             InlineFormatType::Underline => "u",
             InlineFormatType::InlineCode => "code",
+            InlineFormatType::Custom(tag) => {
+                allowed_custom_tag(tag).unwrap_or("span")
+            }
+            InlineFormatType::TextColor(_) | InlineFormatType::Highlight(_) => {
+                "span"
+            }
         }
     }
 
+    /// The action for this format: the one which toggles it, or
+    /// [ComposerAction::Highlight] for a highlight, which it removes.
+    ///
+    /// Panics for custom formats and text colours, which have no action.
+    #[deprecated(
+        since = "2.38.0",
+        note = "use toggle_action, as not every format has an action"
+    )]
     pub fn action(&self) -> ComposerAction {
         match self {
-            InlineFormatType::Bold => ComposerAction::Bold,
-            InlineFormatType::Italic => ComposerAction::Italic,
-            InlineFormatType::StrikeThrough => ComposerAction::StrikeThrough,
-            InlineFormatType::Underline => ComposerAction::Underline,
-            InlineFormatType::InlineCode => ComposerAction::InlineCode,
+            InlineFormatType::Highlight(_) => ComposerAction::Highlight,
+            format => format
+                .toggle_action()
+                .unwrap_or_else(|| panic!("{format:?} has no action")),
         }
     }
+
+    /// The action which toggles this format, if it has one. Custom formats
+    /// are toggled with [crate::ComposerModel::custom_format] and colours
+    /// are set with [crate::ComposerModel::set_text_color] and
+    /// [crate::ComposerModel::set_highlight] instead.
+    ///
+    /// None for a highlight: one of any colour reverses
+    /// [ComposerAction::Highlight], but isn't toggled by it.
+    pub fn toggle_action(&self) -> Option<ComposerAction> {
+        action_for_format(self)
+    }
 }
 
 impl<S: UnicodeString> From<S> for InlineFormatType {
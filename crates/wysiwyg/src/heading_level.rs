@@ -0,0 +1,94 @@
+// Copyright 2023 The Matrix.org Foundation C.I.C.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+/// The level of a heading, from `<h1>` (the most important) to `<h6>`.
+#[derive(Clone, Copy, Debug, Eq, PartialEq, Hash, PartialOrd, Ord)]
+pub enum HeadingLevel {
+    H1,
+    H2,
+    H3,
+    H4,
+    H5,
+    H6,
+}
+
+impl HeadingLevel {
+    /// All the levels, from H1 to H6.
+    pub const ALL: [HeadingLevel; 6] = [
+        HeadingLevel::H1,
+        HeadingLevel::H2,
+        HeadingLevel::H3,
+        HeadingLevel::H4,
+        HeadingLevel::H5,
+        HeadingLevel::H6,
+    ];
+
+    /// The level as a number from 1 to 6.
+    pub fn number(&self) -> u8 {
+        match self {
+            HeadingLevel::H1 => 1,
+            HeadingLevel::H2 => 2,
+            HeadingLevel::H3 => 3,
+            HeadingLevel::H4 => 4,
+            HeadingLevel::H5 => 5,
+            HeadingLevel::H6 => 6,
+        }
+    }
+
+    /// The level for a number from 1 to 6, or None for any other number.
+    pub fn from_number(number: u8) -> Option<Self> {
+        Self::ALL.get(usize::from(number).checked_sub(1)?).copied()
+    }
+
+    pub(crate) fn tag(&self) -> &'static str {
+        match self {
+            HeadingLevel::H1 => "h1",
+            HeadingLevel::H2 => "h2",
+            HeadingLevel::H3 => "h3",
+            HeadingLevel::H4 => "h4",
+            HeadingLevel::H5 => "h5",
+            HeadingLevel::H6 => "h6",
+        }
+    }
+
+    /// The level for a tag name like `h2`, ignoring case, or None if it
+    /// isn't a heading tag.
+    pub(crate) fn from_tag(tag: &str) -> Option<Self> {
+        Self::ALL
+            .into_iter()
+            .find(|level| level.tag().eq_ignore_ascii_case(tag))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::HeadingLevel;
+
+    #[test]
+    fn levels_round_trip_through_numbers_and_tags() {
+        for level in HeadingLevel::ALL {
+            assert_eq!(HeadingLevel::from_number(level.number()), Some(level));
+            assert_eq!(HeadingLevel::from_tag(level.tag()), Some(level));
+        }
+    }
+
+    #[test]
+    fn only_1_to_6_are_levels() {
+        assert_eq!(HeadingLevel::from_number(0), None);
+        assert_eq!(HeadingLevel::from_number(7), None);
+        assert_eq!(HeadingLevel::from_tag("H3"), Some(HeadingLevel::H3));
+        assert_eq!(HeadingLevel::from_tag("h7"), None);
+        assert_eq!(HeadingLevel::from_tag("hr"), None);
+    }
+}
@@ -13,52 +13,113 @@
 // limitations under the License.
 
 mod action_state;
+mod alignment;
+mod block_info;
 mod char;
+mod clipboard_content;
+mod command_info;
 mod composer_action;
+mod composer_error;
 mod composer_model;
 mod composer_state;
 mod composer_update;
 mod dom;
+mod emoji;
 mod format_type;
+mod granularity;
+mod heading_level;
 mod link_action;
+mod list_indentation;
 mod list_type;
+mod locale;
 mod location;
 mod mentions_state;
 mod menu_action;
 mod menu_state;
+mod metrics;
+mod outline_entry;
 mod pattern_key;
+mod quote_attribution;
+mod selection_stats;
+mod state_import_error;
 mod suggestion_pattern;
 mod tests;
 mod text_update;
 
+pub use crate::action_state::ActionInfo;
 pub use crate::action_state::ActionState;
+pub use crate::action_state::DisabledReason;
+pub use crate::alignment::Alignment;
+pub use crate::alignment::AlignmentAttribute;
+pub use crate::block_info::BlockInfo;
+pub use crate::clipboard_content::ClipboardContent;
+pub use crate::command_info::CommandArg;
+pub use crate::command_info::CommandArgType;
+pub use crate::command_info::CommandError;
+pub use crate::command_info::CommandInfo;
 pub use crate::composer_action::ComposerAction;
+pub use crate::composer_error::ComposerError;
+pub use crate::composer_error::ErrorListener;
+pub use crate::composer_error::UnreportedErrors;
+pub use crate::composer_model::delete_text::Direction;
 pub use crate::composer_model::ComposerModel;
 pub use crate::composer_state::ComposerState;
 pub use crate::composer_update::ComposerUpdate;
+pub use crate::dom::nodes::ContainerNode;
+pub use crate::dom::nodes::ContainerNodeKind;
 pub use crate::dom::nodes::DomNode;
+pub use crate::dom::nodes::HorizontalRuleNode;
+pub use crate::dom::nodes::ImageNode;
+pub use crate::dom::nodes::LineBreakNode;
+pub use crate::dom::nodes::MentionNode;
+pub use crate::dom::nodes::SplitAttributePolicy;
+pub use crate::dom::nodes::TextNode;
 pub use crate::dom::parser::parse;
+pub use crate::dom::parser::{
+    parse_with_options, parse_with_whitespace_policy, HtmlParseOptions,
+    WhitespacePolicy,
+};
+pub use crate::dom::Dom;
+pub use crate::dom::DomBuilder;
 pub use crate::dom::DomCreationError;
 pub use crate::dom::DomHandle;
 pub use crate::dom::HtmlParseError;
+pub use crate::dom::HtmlSourceMapEntry;
 pub use crate::dom::MarkdownParseError;
+pub use crate::dom::PlainTextOptions;
+#[cfg(feature = "rope")]
+pub use crate::dom::RopeString;
 pub use crate::dom::ToHtml;
 pub use crate::dom::ToRawText;
 pub use crate::dom::ToTree;
 pub use crate::dom::UnicodeString;
-pub use crate::dom::{MarkdownError, ToMarkdown};
+pub use crate::dom::{walk_children, walk_node, DomVisitor};
+pub use crate::dom::{CustomTagSerializer, CustomTagSerializers};
+pub use crate::dom::{MarkdownError, MarkdownOptions, ToMarkdown};
 pub use crate::format_type::InlineFormatType;
+pub use crate::granularity::Granularity;
+pub use crate::heading_level::HeadingLevel;
 pub use crate::link_action::LinkAction;
 pub use crate::link_action::LinkActionUpdate;
+pub use crate::list_indentation::ListIndentation;
 pub use crate::list_type::ListType;
+pub use crate::locale::Locale;
 pub use crate::location::Location;
 pub use crate::mentions_state::MentionsState;
 pub use crate::menu_action::MenuAction;
 pub use crate::menu_action::MenuActionSuggestion;
 pub use crate::menu_state::MenuState;
 pub use crate::menu_state::MenuStateUpdate;
+pub use crate::metrics::MetricsListener;
+pub use crate::metrics::OperationMetrics;
+pub use crate::metrics::OperationTimer;
+pub use crate::outline_entry::OutlineEntry;
 pub use crate::pattern_key::PatternKey;
+pub use crate::quote_attribution::QuoteAttribution;
+pub use crate::selection_stats::SelectionStats;
+pub use crate::state_import_error::StateImportError;
 pub use crate::suggestion_pattern::SuggestionPattern;
 pub use crate::text_update::ReplaceAll;
+pub use crate::text_update::ReplacementHtml;
 pub use crate::text_update::Selection;
 pub use crate::text_update::TextUpdate;
@@ -0,0 +1,24 @@
+// Copyright 2023 The Matrix.org Foundation C.I.C.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+/// Whether the selected list items can be indented or unindented, and how
+/// deeply nested they are, e.g. to show depth-aware list controls.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct ListIndentation {
+    pub can_indent: bool,
+    pub can_unindent: bool,
+    /// How many lists the selected list items are inside, so 1 for the
+    /// items of a list which isn't nested and 0 outside any list.
+    pub depth: usize,
+}
@@ -0,0 +1,198 @@
+// Copyright 2023 The Matrix.org Foundation C.I.C.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+/// The language and region the composer is used in, set with
+/// `ComposerModel::set_locale`. Affects where words end when deleting a
+/// word, how case is changed and which digits number list items.
+///
+/// The default locale has no language, and keeps the behaviour the
+/// composer had before locales were supported.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct Locale {
+    language: String,
+    region: String,
+    latin_digits: bool,
+}
+
+impl Locale {
+    /// Create a locale from a BCP 47 language tag, e.g. `en-GB` or
+    /// `ar-EG`. Only the language, the region and the `nu-latn` numbering
+    /// extension are used. Unknown languages behave like the default
+    /// locale.
+    pub fn from_tag(tag: &str) -> Self {
+        let tag = tag.to_ascii_lowercase().replace('_', "-");
+        let mut subtags = tag.split('-');
+        let language = subtags.next().unwrap_or_default().to_owned();
+        let region = subtags
+            .take_while(|s| s.len() != 1)
+            .find(|s| {
+                s.len() == 2 || (s.len() == 3 && s.parse::<u16>().is_ok())
+            })
+            .unwrap_or_default()
+            .to_owned();
+        let latin_digits = tag.contains("-nu-latn");
+        Self {
+            language,
+            region,
+            latin_digits,
+        }
+    }
+
+    /// The lowercase language subtag, e.g. `en`, or an empty string.
+    pub fn language(&self) -> &str {
+        &self.language
+    }
+
+    /// The lowercase region subtag, e.g. `gb`, or an empty string.
+    pub fn region(&self) -> &str {
+        &self.region
+    }
+
+    /// Whether `c` is part of a word when it comes between two letters or
+    /// digits, e.g. the apostrophe in "don't" in English. In French the
+    /// apostrophe in "l'homme" separates two words, so it is not.
+    pub fn joins_words(&self, c: char) -> bool {
+        match self.language.as_str() {
+            "en" | "de" | "nl" | "uk" | "be" => matches!(c, '\'' | '’'),
+            "ca" => c == '·',
+            _ => false,
+        }
+    }
+
+    /// Convert `text` to uppercase, e.g. turning `i` into `İ` in Turkish.
+    pub fn to_uppercase(&self, text: &str) -> String {
+        if self.has_dotted_i() {
+            text.chars()
+                .map(|c| match c {
+                    'i' => "İ".to_owned(),
+                    'ı' => "I".to_owned(),
+                    c => c.to_uppercase().collect(),
+                })
+                .collect()
+        } else {
+            text.to_uppercase()
+        }
+    }
+
+    /// Convert `text` to lowercase, e.g. turning `I` into `ı` in Turkish.
+    pub fn to_lowercase(&self, text: &str) -> String {
+        if self.has_dotted_i() {
+            text.chars()
+                .map(|c| match c {
+                    'I' => "ı".to_owned(),
+                    'İ' => "i".to_owned(),
+                    c => c.to_lowercase().collect(),
+                })
+                .collect()
+        } else {
+            text.to_lowercase()
+        }
+    }
+
+    /// Format `number` with the digits used in this locale, e.g. for the
+    /// marker of an ordered list item. Returns `٣` for 3 in `ar-EG`, and
+    /// `3` in `en` or `ar-MA`.
+    pub fn format_number(&self, number: usize) -> String {
+        let Some(zero) = self.zero_digit() else {
+            return number.to_string();
+        };
+        number
+            .to_string()
+            .chars()
+            .map(|d| {
+                let offset = d.to_digit(10).unwrap();
+                char::from_u32(zero as u32 + offset).unwrap()
+            })
+            .collect()
+    }
+
+    fn has_dotted_i(&self) -> bool {
+        matches!(self.language.as_str(), "tr" | "az")
+    }
+
+    /// The digit zero in this locale, if it doesn't use `0`-`9`.
+    fn zero_digit(&self) -> Option<char> {
+        if self.latin_digits {
+            return None;
+        }
+        match self.language.as_str() {
+            // The Maghreb uses Western Arabic digits
+            "ar" if matches!(
+                self.region.as_str(),
+                "ma" | "dz" | "tn" | "ly" | "eh"
+            ) =>
+            {
+                None
+            }
+            "ar" => Some('٠'),
+            "fa" | "ur" => Some('۰'),
+            "hi" | "mr" | "ne" => Some('०'),
+            "bn" => Some('০'),
+            _ => None,
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::Locale;
+
+    #[test]
+    fn tag_is_split_into_language_and_region() {
+        let locale = Locale::from_tag("en_GB");
+        assert_eq!(locale.language(), "en");
+        assert_eq!(locale.region(), "gb");
+    }
+
+    #[test]
+    fn script_is_skipped_when_finding_region() {
+        let locale = Locale::from_tag("sr-Latn-RS");
+        assert_eq!(locale.language(), "sr");
+        assert_eq!(locale.region(), "rs");
+    }
+
+    #[test]
+    fn default_locale_has_no_language() {
+        assert_eq!(Locale::default().language(), "");
+        assert!(!Locale::default().joins_words('\''));
+    }
+
+    #[test]
+    fn numbers_use_locale_digits() {
+        assert_eq!(Locale::from_tag("ar-EG").format_number(12), "١٢");
+        assert_eq!(Locale::from_tag("fa").format_number(3), "۳");
+        assert_eq!(Locale::from_tag("hi").format_number(10), "१०");
+    }
+
+    #[test]
+    fn numbers_use_latin_digits_where_they_are_preferred() {
+        assert_eq!(Locale::from_tag("en").format_number(12), "12");
+        assert_eq!(Locale::from_tag("ar-MA").format_number(12), "12");
+        assert_eq!(Locale::from_tag("ar-EG-u-nu-latn").format_number(12), "12");
+    }
+
+    #[test]
+    fn turkish_case_changes_keep_dots_on_i() {
+        let locale = Locale::from_tag("tr");
+        assert_eq!(locale.to_uppercase("istanbul"), "İSTANBUL");
+        assert_eq!(locale.to_lowercase("ISPARTA"), "ısparta");
+    }
+
+    #[test]
+    fn other_case_changes_use_unicode_rules() {
+        let locale = Locale::from_tag("en");
+        assert_eq!(locale.to_uppercase("istanbul"), "ISTANBUL");
+        assert_eq!(locale.to_lowercase("ISPARTA"), "isparta");
+    }
+}
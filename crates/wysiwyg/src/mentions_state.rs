@@ -14,7 +14,7 @@
 
 use std::collections::HashSet;
 
-#[derive(Default, Debug, PartialEq, Eq)]
+#[derive(Clone, Default, Debug, PartialEq, Eq)]
 pub struct MentionsState {
     pub user_ids: HashSet<String>,
     pub room_ids: HashSet<String>,
@@ -12,7 +12,7 @@
 // See the License for the specific language governing permissions and
 // limitations under the License.
 
-use crate::action_state::ActionState;
+use crate::action_state::{ActionState, DisabledReason};
 use crate::ComposerAction;
 use std::collections::HashMap;
 
@@ -25,4 +25,12 @@ pub enum MenuState {
 #[derive(Debug, Clone, PartialEq, Eq)]
 pub struct MenuStateUpdate {
     pub action_states: HashMap<ComposerAction, ActionState>,
+    /// Why each of the disabled actions in `action_states` is disabled.
+    pub disabled_reasons: HashMap<ComposerAction, DisabledReason>,
+    /// How many steps can be undone.
+    pub undo_depth: usize,
+    /// How many steps can be redone.
+    pub redo_depth: usize,
+    /// How many quotes the selection is inside.
+    pub quote_depth: usize,
 }
@@ -0,0 +1,278 @@
+// Copyright 2023 The Matrix.org Foundation C.I.C.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use std::cell::Cell;
+use std::sync::Arc;
+use std::time::Duration;
+
+use crate::{ComposerModel, UnicodeString};
+
+/// Told how long each operation on a [ComposerModel] took, e.g. so that
+/// clients can track latency in production. Set with
+/// [ComposerModel::set_metrics_listener].
+pub trait MetricsListener: Send + Sync {
+    /// Called after an operation finishes, with the name it was measured
+    /// under, e.g. `replace_text`.
+    fn on_operation(&self, operation: &str, duration: Duration);
+}
+
+impl<S> ComposerModel<S>
+where
+    S: UnicodeString,
+{
+    /// Report how long each operation measured with [Self::measure] takes
+    /// to `listener`, or stop reporting if it is None.
+    pub fn set_metrics_listener(
+        &mut self,
+        listener: Option<Arc<dyn MetricsListener>>,
+    ) {
+        self.metrics_listener = listener;
+    }
+
+    /// Start measuring an operation on the model, to be reported to the
+    /// metrics listener under `operation`. Stop the returned timer when the
+    /// operation finishes, and report the result once the model is no
+    /// longer locked or borrowed, so that the listener can use it too.
+    ///
+    /// Nothing is reported if there is no listener, or for operations a
+    /// listener runs while it is being told about another one.
+    pub fn measure(&self, operation: &'static str) -> OperationTimer {
+        let listener = if REPORTING.with(Cell::get) {
+            None
+        } else {
+            self.metrics_listener.clone()
+        };
+        OperationTimer {
+            listener,
+            operation,
+            stopwatch: Stopwatch::start(),
+        }
+    }
+}
+
+/// Measures one operation on a [ComposerModel] until it is stopped. See
+/// [ComposerModel::measure].
+#[must_use]
+pub struct OperationTimer {
+    listener: Option<Arc<dyn MetricsListener>>,
+    operation: &'static str,
+    stopwatch: Stopwatch,
+}
+
+impl OperationTimer {
+    /// Stop measuring, keeping how long the operation took until it is
+    /// reported.
+    pub fn stop(self) -> OperationMetrics {
+        OperationMetrics {
+            duration: self.stopwatch.elapsed(),
+            listener: self.listener,
+            operation: self.operation,
+        }
+    }
+}
+
+/// How long an operation on a [ComposerModel] took, waiting to be reported
+/// to the metrics listener.
+#[must_use]
+pub struct OperationMetrics {
+    listener: Option<Arc<dyn MetricsListener>>,
+    operation: &'static str,
+    duration: Duration,
+}
+
+impl OperationMetrics {
+    /// True if there is no listener to report to.
+    pub fn is_empty(&self) -> bool {
+        self.listener.is_none()
+    }
+
+    /// Tell the metrics listener how long the operation took.
+    pub fn report(self) {
+        if let Some(listener) = self.listener {
+            while_reporting(|| {
+                listener.on_operation(self.operation, self.duration)
+            });
+        }
+    }
+}
+
+thread_local! {
+    /// True while a listener is being told about an operation on this
+    /// thread, so that the operations it runs aren't reported in turn.
+    static REPORTING: Cell<bool> = const { Cell::new(false) };
+}
+
+/// Call `report`, which tells a listener about something, without
+/// measuring the operations the listener runs on a model meanwhile.
+pub(crate) fn while_reporting(report: impl FnOnce()) {
+    let was_reporting = REPORTING.with(|reporting| reporting.replace(true));
+    report();
+    REPORTING.with(|reporting| reporting.set(was_reporting));
+}
+
+cfg_if::cfg_if! {
+    if #[cfg(all(feature = "js", target_arch = "wasm32"))] {
+        use wasm_bindgen::prelude::wasm_bindgen;
+
+        #[wasm_bindgen]
+        extern "C" {
+            // std::time::Instant isn't available in the browser
+            #[wasm_bindgen(js_namespace = performance, js_name = now)]
+            fn performance_now() -> f64;
+        }
+
+        struct Stopwatch(f64);
+
+        impl Stopwatch {
+            fn start() -> Self {
+                Self(performance_now())
+            }
+
+            fn elapsed(&self) -> Duration {
+                let millis = (performance_now() - self.0).max(0.0);
+                Duration::from_secs_f64(millis / 1000.0)
+            }
+        }
+    } else {
+        struct Stopwatch(std::time::Instant);
+
+        impl Stopwatch {
+            fn start() -> Self {
+                Self(std::time::Instant::now())
+            }
+
+            fn elapsed(&self) -> Duration {
+                self.0.elapsed()
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::sync::{Arc, Mutex};
+    use std::time::Duration;
+
+    use super::MetricsListener;
+    use crate::tests::testutils_composer_model::{cm, tx, TestString};
+    use crate::tests::testutils_conversion::utf16;
+    use crate::ComposerModel;
+
+    #[derive(Default)]
+    struct Recorder(Mutex<Vec<String>>);
+
+    impl MetricsListener for Recorder {
+        fn on_operation(&self, operation: &str, _duration: Duration) {
+            self.0.lock().unwrap().push(operation.to_owned());
+        }
+    }
+
+    #[test]
+    fn measured_operations_are_reported_to_the_listener() {
+        let recorder = Arc::new(Recorder::default());
+        let mut model = cm("|");
+        model.set_metrics_listener(Some(recorder.clone()));
+        let timer = model.measure("replace_text");
+        model.replace_text(utf16("a"));
+        timer.stop().report();
+        let timer = model.measure("enter");
+        model.enter();
+        timer.stop().report();
+        assert_eq!(tx(&model), "<p>a</p><p>&nbsp;|</p>");
+        assert_eq!(*recorder.0.lock().unwrap(), vec!["replace_text", "enter"]);
+    }
+
+    #[test]
+    fn operations_are_not_reported_after_the_listener_is_removed() {
+        let recorder = Arc::new(Recorder::default());
+        let mut model = cm("|");
+        model.set_metrics_listener(Some(recorder.clone()));
+        let timer = model.measure("replace_text");
+        model.replace_text(utf16("a"));
+        timer.stop().report();
+        model.set_metrics_listener(None);
+        let timer = model.measure("replace_text");
+        model.replace_text(utf16("b"));
+        let metrics = timer.stop();
+        assert!(metrics.is_empty());
+        metrics.report();
+        assert_eq!(tx(&model), "ab|");
+        assert_eq!(recorder.0.lock().unwrap().len(), 1);
+    }
+
+    #[test]
+    fn unmeasured_operations_are_not_reported() {
+        let recorder = Arc::new(Recorder::default());
+        let mut model = cm("|");
+        model.set_metrics_listener(Some(recorder.clone()));
+        model.replace_text(utf16("a"));
+        assert!(recorder.0.lock().unwrap().is_empty());
+    }
+
+    #[test]
+    fn operations_are_not_reported_until_the_timer_is_stopped() {
+        let recorder = Arc::new(Recorder::default());
+        let mut model = cm("|");
+        model.set_metrics_listener(Some(recorder.clone()));
+        let timer = model.measure("replace_text");
+        model.replace_text(utf16("a"));
+        let metrics = timer.stop();
+        assert!(recorder.0.lock().unwrap().is_empty());
+        metrics.report();
+        assert_eq!(*recorder.0.lock().unwrap(), vec!["replace_text"]);
+    }
+
+    /// Reads the model it measures whenever it is told about an operation,
+    /// as a client might to log the state along with the timing.
+    struct ReadingRecorder {
+        model: Arc<Mutex<ComposerModel<TestString>>>,
+        reports: Mutex<Vec<String>>,
+    }
+
+    impl MetricsListener for ReadingRecorder {
+        fn on_operation(&self, operation: &str, _duration: Duration) {
+            let model = self.model.lock().unwrap();
+            let timer = model.measure("get_content_as_html");
+            let html = model.get_content_as_html();
+            timer.stop().report();
+            self.reports
+                .lock()
+                .unwrap()
+                .push(format!("{operation}: {html}"));
+        }
+    }
+
+    #[test]
+    fn the_listener_can_use_the_model_once_it_is_unlocked() {
+        let model = Arc::new(Mutex::new(cm("|")));
+        let recorder = Arc::new(ReadingRecorder {
+            model: model.clone(),
+            reports: Mutex::default(),
+        });
+        model
+            .lock()
+            .unwrap()
+            .set_metrics_listener(Some(recorder.clone()));
+
+        let mut locked = model.lock().unwrap();
+        let timer = locked.measure("replace_text");
+        locked.replace_text(utf16("a"));
+        let metrics = timer.stop();
+        drop(locked);
+        metrics.report();
+
+        // The listener's own read of the model isn't reported to it
+        assert_eq!(*recorder.reports.lock().unwrap(), vec!["replace_text: a"]);
+    }
+}
@@ -0,0 +1,29 @@
+// Copyright 2023 The Matrix.org Foundation C.I.C.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use crate::dom::UnicodeString;
+use crate::HeadingLevel;
+
+/// One of the headings of the content, with its text and where it is in
+/// the content, e.g. to show an outline of a long message to navigate it.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct OutlineEntry<S>
+where
+    S: UnicodeString,
+{
+    pub level: HeadingLevel,
+    pub text: S,
+    pub start: usize,
+    pub end: usize,
+}
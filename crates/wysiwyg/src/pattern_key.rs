@@ -27,6 +27,16 @@ impl PatternKey {
         matches!(self, Self::At | Self::Hash | Self::Slash)
     }
 
+    /// The character that triggers a static pattern.
+    pub(crate) fn trigger_char(&self) -> Option<char> {
+        match self {
+            Self::At => Some('\u{0040}'),
+            Self::Hash => Some('\u{0023}'),
+            Self::Slash => Some('\u{002F}'),
+            Self::Custom(_) => None,
+        }
+    }
+
     pub(crate) fn from_string_and_suggestions(
         string: String,
         custom_suggestion_patterns: &HashSet<String>,
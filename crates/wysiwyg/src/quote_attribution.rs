@@ -0,0 +1,63 @@
+// Copyright 2023 The Matrix.org Foundation C.I.C.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use crate::UnicodeString;
+
+const AUTHOR_ATTRIBUTE: &str = "data-mx-quote-author";
+const EVENT_LINK_ATTRIBUTE: &str = "data-mx-quote-event";
+
+/// Who wrote the text in a quote, and a link to the event it was quoted
+/// from, e.g. when quoting a message in a reply. Stored as
+/// `data-mx-quote-author` and `data-mx-quote-event` attributes on the
+/// `<blockquote>`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct QuoteAttribution {
+    pub author: String,
+    pub event_link: Option<String>,
+}
+
+impl QuoteAttribution {
+    /// The attribution in the attributes of a `<blockquote>`, if it has
+    /// a non-empty author.
+    pub(crate) fn from_attributes<'a>(
+        attributes: impl IntoIterator<Item = (&'a str, &'a str)>,
+    ) -> Option<Self> {
+        let mut author = None;
+        let mut event_link = None;
+        for (name, value) in attributes {
+            match name {
+                AUTHOR_ATTRIBUTE => author = Some(value.to_owned()),
+                EVENT_LINK_ATTRIBUTE => event_link = Some(value.to_owned()),
+                _ => {}
+            }
+        }
+        author.filter(|a| !a.is_empty()).map(|author| Self {
+            author,
+            event_link: event_link.filter(|l| !l.is_empty()),
+        })
+    }
+
+    /// The attributes storing this attribution on a `<blockquote>`.
+    pub(crate) fn to_attributes<S: UnicodeString>(&self) -> Vec<(S, S)> {
+        let mut attributes =
+            vec![(AUTHOR_ATTRIBUTE.into(), self.author.as_str().into())];
+        if let Some(event_link) = &self.event_link {
+            attributes.push((
+                EVENT_LINK_ATTRIBUTE.into(),
+                event_link.as_str().into(),
+            ));
+        }
+        attributes
+    }
+}
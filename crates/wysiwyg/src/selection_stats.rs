@@ -0,0 +1,27 @@
+// Copyright 2023 The Matrix.org Foundation C.I.C.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+/// What is selected, e.g. for a toolbar to show how much text is selected
+/// or to disable actions which only work inside one block.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct SelectionStats {
+    /// The number of graphemes selected.
+    pub character_count: usize,
+    /// The number of words selected, found using the Unicode word boundary
+    /// rules.
+    pub word_count: usize,
+    /// Whether the selection starts and ends in different blocks, e.g. two
+    /// paragraphs or list items.
+    pub spans_multiple_blocks: bool,
+}
@@ -0,0 +1,37 @@
+// Copyright 2023 The Matrix.org Foundation C.I.C.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use core::fmt;
+
+use crate::DomCreationError;
+
+/// Why `ComposerModel::import_state` couldn't import a state.
+#[derive(Debug, Eq, PartialEq)]
+pub enum StateImportError {
+    /// The bytes were not a state made by `ComposerModel::export_state`.
+    InvalidFormat(String),
+    /// The content of the state couldn't be parsed.
+    InvalidContent(DomCreationError),
+}
+
+impl fmt::Display for StateImportError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Self::InvalidFormat(message) => {
+                write!(f, "invalid state: {message}")
+            }
+            Self::InvalidContent(_) => write!(f, "invalid state content"),
+        }
+    }
+}
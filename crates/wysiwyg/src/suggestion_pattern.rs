@@ -14,10 +14,25 @@
 
 use crate::PatternKey;
 
-#[derive(Clone, Debug, PartialEq, Eq)]
+#[derive(Clone, Debug, Eq)]
 pub struct SuggestionPattern {
     pub key: PatternKey,
     pub text: String,
     pub start: usize,
     pub end: usize,
+    /// The revision of the update the suggestion was sent in. It can't
+    /// replace anything once the content has changed since then, because
+    /// `start` and `end` may no longer point at the pattern.
+    pub revision: u64,
+}
+
+/// Suggestions are compared by the pattern they found, whatever their
+/// revision.
+impl PartialEq for SuggestionPattern {
+    fn eq(&self, other: &Self) -> bool {
+        self.key == other.key
+            && self.text == other.text
+            && self.start == other.start
+            && self.end == other.end
+    }
 }
@@ -14,22 +14,47 @@
 
 #![cfg(test)]
 
+pub mod test_alignment;
+pub mod test_blocks;
+pub mod test_changed_range;
 pub mod test_characters;
+pub mod test_clipboard;
+pub mod test_code_block_whitespace;
+pub mod test_commands;
+pub mod test_content_hash;
+pub mod test_cursor_movement;
+pub mod test_custom_emoji;
+pub mod test_custom_tags;
+pub mod test_data_attributes;
 pub mod test_deleting;
 pub mod test_emoji_replacement;
 pub mod test_formatting;
 pub mod test_get_link_action;
+pub mod test_headings;
+pub mod test_highlight;
+pub mod test_horizontal_rule;
+pub mod test_images;
+pub mod test_linkify;
 pub mod test_links;
 pub mod test_lists;
 pub mod test_lists_with_blocks;
+pub mod test_maths;
 pub mod test_mentions;
 pub mod test_menu_action;
 pub mod test_menu_state;
+pub mod test_move_range;
 pub mod test_paragraphs;
+pub mod test_paste;
+pub mod test_recovery;
 pub mod test_remove_links;
+pub mod test_revisions;
 pub mod test_selection;
 pub mod test_set_content;
+pub mod test_state_export;
 pub mod test_suggestions;
+pub mod test_tab;
+pub mod test_text_color;
+pub mod test_to_fallback_text;
 pub mod test_to_markdown;
 pub mod test_to_message_html;
 pub mod test_to_plain_text;
@@ -39,3 +64,4 @@ pub mod test_undo_redo;
 pub mod testutils_composer_model;
 pub mod testutils_conversion;
 pub mod testutils_dom;
+pub mod testutils_snapshot;
@@ -0,0 +1,199 @@
+// Copyright 2023 The Matrix.org Foundation C.I.C.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+#![cfg(test)]
+
+use crate::tests::testutils_composer_model::{cm, tx};
+use crate::tests::testutils_conversion::utf16;
+use crate::{
+    Alignment, AlignmentAttribute, ComposerAction, HeadingLevel, ToHtml,
+};
+
+#[test]
+fn aligning_inline_content_wraps_it_in_a_paragraph() {
+    let mut model = cm("ab|c");
+    model.set_alignment(Some(Alignment::Center));
+    assert_eq!(tx(&model), "<p style=\"text-align: center\">ab|c</p>");
+}
+
+#[test]
+fn aligning_only_changes_the_block_with_the_cursor() {
+    let mut model = cm("<p>ab</p><h2>c|d</h2><p>ef</p>");
+    model.set_alignment(Some(Alignment::Right));
+    assert_eq!(
+        tx(&model),
+        "<p>ab</p><h2 style=\"text-align: right\">c|d</h2><p>ef</p>"
+    );
+}
+
+#[test]
+fn aligning_changes_every_selected_block() {
+    let mut model = cm("<p>a{b</p><p>c}|d</p><p>ef</p>");
+    model.set_alignment(Some(Alignment::Justify));
+    assert_eq!(
+        tx(&model),
+        "<p style=\"text-align: justify\">a{b</p>\
+        <p style=\"text-align: justify\">c}|d</p>\
+        <p>ef</p>"
+    );
+}
+
+#[test]
+fn removing_an_alignment() {
+    let mut model = cm("<p style=\"text-align: center\">a|b</p>");
+    model.set_alignment(None);
+    assert_eq!(tx(&model), "<p>a|b</p>");
+}
+
+#[test]
+fn removing_an_alignment_from_inline_content_does_nothing() {
+    let mut model = cm("a|b");
+    model.set_alignment(None);
+    assert_eq!(tx(&model), "a|b");
+}
+
+#[test]
+fn blocks_in_code_blocks_are_not_aligned() {
+    let mut model = cm("<pre><code>a|b</code></pre>");
+    model.set_alignment(Some(Alignment::Center));
+    assert_eq!(tx(&model), "<pre><code>a|b</code></pre>");
+}
+
+#[test]
+fn the_alignment_is_kept_when_the_heading_level_changes() {
+    let mut model = cm("<p style=\"text-align: right\">a|b</p>");
+    model.set_heading(Some(HeadingLevel::H1));
+    assert_eq!(tx(&model), "<h1 style=\"text-align: right\">a|b</h1>");
+}
+
+#[test]
+fn a_new_paragraph_keeps_the_alignment() {
+    let mut model = cm("<p style=\"text-align: center\">ab|</p>");
+    model.enter();
+    assert_eq!(
+        tx(&model),
+        "<p style=\"text-align: center\">ab</p>\
+        <p style=\"text-align: center\">&nbsp;|</p>"
+    );
+}
+
+#[test]
+fn both_halves_of_a_split_heading_keep_the_alignment() {
+    let mut model = cm("<h2 data-mx-align=\"right\">a|b</h2>");
+    model.enter();
+    assert_eq!(
+        tx(&model),
+        "<h2 data-mx-align=\"right\">a</h2><h2 data-mx-align=\"right\">|b</h2>"
+    );
+}
+
+#[test]
+fn aligning_can_be_undone() {
+    let mut model = cm("<p>a|b</p>");
+    model.set_alignment(Some(Alignment::Center));
+    model.undo();
+    assert_eq!(tx(&model), "<p>a|b</p>");
+}
+
+#[test]
+fn get_alignment_returns_the_alignment_at_the_cursor() {
+    let model = cm("<p>ab</p><p data-mx-align=\"right\">c|d</p>");
+    assert_eq!(model.get_alignment(), Some(Alignment::Right));
+    let model = cm("<p>a|b</p>");
+    assert_eq!(model.get_alignment(), None);
+}
+
+#[test]
+fn the_action_for_the_alignment_is_reversed() {
+    let mut model = cm("<p>a|b</p>");
+    model.set_alignment(Some(Alignment::Center));
+    assert!(model.action_is_reversed(ComposerAction::AlignCenter));
+    assert!(!model.action_is_reversed(ComposerAction::AlignLeft));
+}
+
+#[test]
+fn an_alignment_action_is_only_reversed_if_every_block_has_it() {
+    let model = cm("<p style=\"text-align: center\">a{b</p><p>c}|d</p>");
+    assert!(!model.action_is_reversed(ComposerAction::AlignCenter));
+}
+
+#[test]
+fn a_heading_can_reverse_the_heading_and_alignment_actions() {
+    let model = cm("<h3 style=\"text-align: left\">a|b</h3>");
+    assert!(model.action_is_reversed(ComposerAction::Heading));
+    assert!(model.action_is_reversed(ComposerAction::AlignLeft));
+}
+
+#[test]
+fn alignment_actions_are_disabled_in_code_blocks() {
+    let model = cm("<pre><code>a|b</code></pre>");
+    assert!(model.action_is_disabled(ComposerAction::AlignRight));
+}
+
+#[test]
+fn alignment_can_be_written_as_a_data_attribute() {
+    let mut model = cm("<p>a|b</p>");
+    model.set_alignment_attribute(AlignmentAttribute::Data);
+    model.set_alignment(Some(Alignment::Center));
+    assert_eq!(tx(&model), "<p data-mx-align=\"center\">a|b</p>");
+}
+
+#[test]
+fn changing_the_attribute_rewrites_aligned_blocks() {
+    let mut model =
+        cm("<p style=\"text-align: right\">ab</p><p>c|d</p><h1>e</h1>");
+    model.set_alignment_attribute(AlignmentAttribute::Data);
+    assert_eq!(
+        tx(&model),
+        "<p data-mx-align=\"right\">ab</p><p>c|d</p><h1>e</h1>"
+    );
+}
+
+#[test]
+fn parsed_alignments_keep_their_attribute() {
+    let mut model = cm("|");
+    model
+        .set_content_from_html(&utf16(
+            "<p style=\"color: red; text-align: center\">a</p>\
+            <h2 data-mx-align=\"justify\">b</h2>\
+            <p style=\"text-align: middle\">c</p>",
+        ))
+        .unwrap();
+    assert_eq!(
+        model.get_content_as_html(),
+        utf16(
+            "<p style=\"text-align: center\">a</p>\
+            <h2 data-mx-align=\"justify\">b</h2>\
+            <p>c</p>"
+        )
+    );
+}
+
+#[test]
+fn aligned_paragraphs_keep_their_tag_in_message_html() {
+    let model = cm("<p>a</p><p style=\"text-align: center\">b</p><p>c|</p>");
+    assert_eq!(
+        model.get_content_as_message_html(),
+        utf16("a<p style=\"text-align: center\">b</p>c")
+    );
+}
+
+#[test]
+fn aligned_headings_keep_their_alignment_in_message_html() {
+    let model = cm("<h1 data-mx-align=\"right\">a|</h1>");
+    assert_eq!(
+        model.state.dom.to_message_html(),
+        utf16("<h1 data-mx-align=\"right\">a</h1>")
+    );
+}
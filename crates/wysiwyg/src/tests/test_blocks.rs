@@ -0,0 +1,122 @@
+// Copyright 2023 The Matrix.org Foundation C.I.C.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use crate::tests::testutils_composer_model::cm;
+use crate::Location;
+
+fn blocks(model_html: &str) -> Vec<(usize, usize, String)> {
+    cm(model_html)
+        .get_blocks()
+        .into_iter()
+        .map(|b| (b.start, b.end, b.html.to_string()))
+        .collect()
+}
+
+#[test]
+fn empty_model_has_no_blocks() {
+    assert_eq!(blocks("|"), vec![]);
+}
+
+#[test]
+fn inline_content_is_one_block() {
+    assert_eq!(
+        blocks("a<strong>b</strong>c|"),
+        vec![(0, 3, "a<strong>b</strong>c".to_owned())]
+    );
+}
+
+#[test]
+fn each_paragraph_is_a_block() {
+    assert_eq!(
+        blocks("<p>abc</p><p>de|</p>"),
+        vec![(0, 3, "abc".to_owned()), (4, 6, "de".to_owned()),]
+    );
+}
+
+#[test]
+fn a_list_is_one_block() {
+    assert_eq!(
+        blocks("<p>a</p><ul><li>b</li><li>c|</li></ul><blockquote><p>d</p></blockquote>"),
+        vec![
+            (0, 1, "a".to_owned()),
+            (2, 5, "<ul><li>b</li><li>c</li></ul>".to_owned()),
+            (6, 7, "<blockquote>d</blockquote>".to_owned()),
+        ]
+    );
+}
+
+#[test]
+fn block_ranges_match_the_content() {
+    let model = cm("<p>abc</p><ol><li>de</li></ol><p>fgh|</p>");
+    let text = model.get_content_as_plain_text().to_string();
+    let chars: Vec<char> = text.chars().collect();
+    let block_texts: Vec<String> = model
+        .get_blocks()
+        .iter()
+        .map(|b| chars[b.start..b.end].iter().collect())
+        .collect();
+    assert_eq!(block_texts, vec!["abc", "de", "fgh"]);
+}
+
+#[test]
+fn block_html_is_message_html() {
+    let mut model = cm("|");
+    model.insert_mention(
+        "https://matrix.to/#/@alice:matrix.org".into(),
+        "Alice".into(),
+        vec![],
+    );
+    let html = model.get_blocks()[0].html.to_string();
+    assert_eq!(html, model.get_content_as_message_html().to_string());
+}
+
+#[test]
+fn editing_a_block_changes_only_its_revision() {
+    let mut model = cm("<p>abc</p><p>de|f</p><p>ghi</p>");
+    let before = model.get_block_revisions();
+    model.replace_text("X".into());
+    let after = model.get_block_revisions();
+    assert_eq!(after[0], before[0]);
+    assert_ne!(after[1], before[1]);
+    assert_eq!(after[2], before[2]);
+}
+
+#[test]
+fn adding_a_block_keeps_the_revisions_of_the_blocks_after_it() {
+    let mut model = cm("<p>ab|c</p><p>def</p>");
+    let before = model.get_block_revisions();
+    model.enter();
+    let after = model.get_block_revisions();
+    assert_eq!(after.len(), 3);
+    assert_ne!(after[0], before[0]);
+    assert_eq!(after[2], before[1]);
+}
+
+#[test]
+fn moving_the_selection_keeps_all_revisions() {
+    let mut model = cm("<p>abc</p><p>def|</p>");
+    let before = model.get_block_revisions();
+    model.select(Location::from(1), Location::from(1));
+    model.bold();
+    assert_eq!(model.get_block_revisions(), before);
+}
+
+#[test]
+fn block_info_includes_the_revision() {
+    let model = cm("<p>abc</p><p>def|</p>");
+    let revisions = model.get_block_revisions();
+    assert_eq!(model.get_block(1).unwrap().revision, revisions[1]);
+    assert_eq!(model.get_block(1).unwrap().html.to_string(), "def");
+    assert_eq!(model.get_block(2), None);
+}
@@ -0,0 +1,63 @@
+// Copyright 2023 The Matrix.org Foundation C.I.C.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use crate::tests::testutils_composer_model::cm;
+use crate::{ComposerUpdate, TextUpdate, UnicodeString};
+
+fn changed<S: UnicodeString>(update: ComposerUpdate<S>) -> (usize, usize) {
+    match update.text_update {
+        TextUpdate::ReplaceAll(r) => {
+            (r.changed_start.into(), r.changed_end.into())
+        }
+        _ => panic!("Expected a ReplaceAll update"),
+    }
+}
+
+#[test]
+fn typing_changes_the_typed_text() {
+    let mut model = cm("abc|def");
+    assert_eq!(changed(model.replace_text("XY".into())), (3, 5));
+}
+
+#[test]
+fn deleting_changes_nothing_after_the_deletion() {
+    let mut model = cm("ab{cd}|ef");
+    assert_eq!(changed(model.delete()), (2, 2));
+}
+
+#[test]
+fn formatting_changes_the_formatted_text() {
+    let mut model = cm("ab{cd}|ef");
+    assert_eq!(changed(model.bold()), (2, 4));
+}
+
+#[test]
+fn changes_in_later_paragraphs_are_found() {
+    let mut model = cm("<p>abc</p><p>de|f</p>");
+    assert_eq!(changed(model.replace_text("X".into())), (6, 7));
+}
+
+#[test]
+fn undo_changes_what_the_edit_changed() {
+    let mut model = cm("abc|");
+    model.replace_text("d".into());
+    assert_eq!(changed(model.undo()), (3, 3));
+    assert_eq!(changed(model.redo()), (3, 4));
+}
+
+#[test]
+fn nothing_changed_is_an_empty_range_at_the_cursor() {
+    let mut model = cm("ab|c");
+    assert_eq!(changed(model.replace_text("".into())), (2, 2));
+}
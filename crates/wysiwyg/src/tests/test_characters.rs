@@ -12,8 +12,8 @@
 // See the License for the specific language governing permissions and
 // limitations under the License.
 
+use crate::tests::testutils_composer_model::TestString;
 use crate::{ComposerModel, Location};
-use widestring::Utf16String;
 
 use crate::tests::testutils_composer_model::{cm, restore_whitespace, tx};
 use crate::tests::testutils_conversion::utf16;
@@ -77,6 +77,47 @@ fn typing_a_character_after_a_multi_codepoint_character() {
     assert_eq!(tx(&model), "\u{1F469}\u{1F3FF}\u{200D}\u{1F680}Z|");
 }
 
+#[test]
+fn typing_an_emoji_zwj_sequence_adds_missing_variation_selectors() {
+    let mut model = cm("a|");
+    model.replace_text(utf16("👩\u{200D}❤\u{200D}👨"));
+    assert_eq!(tx(&model), "a👩\u{200D}❤\u{FE0F}\u{200D}👨|");
+}
+
+#[test]
+fn backspacing_a_normalized_emoji_sequence_deletes_all_of_it() {
+    let mut model = cm("a|");
+    model.replace_text(utf16("🏳\u{200D}🌈"));
+    model.backspace();
+    assert_eq!(tx(&model), "a|");
+}
+
+#[test]
+fn inserting_an_emoji_applies_pending_formats() {
+    let mut model = cm("a|");
+    model.bold();
+    model.insert_emoji(utf16("🧑🏽\u{200D}💻"));
+    assert_eq!(tx(&model), "a<strong>🧑🏽\u{200D}💻|</strong>");
+}
+
+#[test]
+fn inserting_an_emoji_replaces_the_selection_in_one_undo_step() {
+    let mut model = cm("a{bc}|d");
+    model.insert_emoji(utf16("👍"));
+    assert_eq!(tx(&model), "a👍|d");
+    model.undo();
+    assert_eq!(tx(&model), "a{bc}|d");
+}
+
+#[test]
+fn inserting_more_than_one_grapheme_as_an_emoji_does_nothing() {
+    let mut model = cm("a|");
+    model.insert_emoji(utf16("👍👍"));
+    model.insert_emoji(utf16(""));
+    assert_eq!(tx(&model), "a|");
+    assert_eq!(model.undo_depth(), 0);
+}
+
 #[test]
 fn replacing_an_explicit_text_range_works() {
     let mut model = cm("0123456789|");
@@ -360,6 +401,6 @@ fn multiple_leading_and_trailing_whitespace_are_all_replaced_with_nbsp() {
     assert_eq!(tx(&model), "<p>&nbsp;&nbsp;text&nbsp;&nbsp;|</p>");
 }
 
-fn replace_text(model: &mut ComposerModel<Utf16String>, new_text: &str) {
+fn replace_text(model: &mut ComposerModel<TestString>, new_text: &str) {
     model.replace_text(utf16(new_text));
 }
@@ -0,0 +1,114 @@
+// Copyright 2023 The Matrix.org Foundation C.I.C.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use crate::tests::testutils_composer_model::{cm, tx};
+use crate::Location;
+
+fn clipboard(model_html: &str, start: usize, end: usize) -> (String, String) {
+    let model = cm(model_html);
+    let content =
+        model.get_clipboard_content(Location::from(start), Location::from(end));
+    (content.html.to_string(), content.plain_text.to_string())
+}
+
+#[test]
+fn copying_plain_text_gives_the_same_html_and_text() {
+    assert_eq!(
+        clipboard("abcdef|", 1, 4),
+        ("bcd".to_owned(), "bcd".to_owned())
+    );
+}
+
+#[test]
+fn copying_part_of_formatted_text_keeps_the_formatting() {
+    assert_eq!(
+        clipboard("a<strong>bcd</strong>ef|", 2, 5),
+        ("<strong>cd</strong>e".to_owned(), "cde".to_owned())
+    );
+}
+
+#[test]
+fn copying_a_backwards_range_works() {
+    assert_eq!(
+        clipboard("a<em>bc</em>|", 3, 1),
+        ("<em>bc</em>".to_owned(), "bc".to_owned())
+    );
+}
+
+#[test]
+fn copying_across_paragraphs_gives_lines_of_text() {
+    assert_eq!(
+        clipboard("<p>abc</p><p>def|</p>", 1, 6),
+        ("<p>bc</p><p>de</p>".to_owned(), "bc\nde".to_owned())
+    );
+}
+
+#[test]
+fn copying_list_items_keeps_the_list() {
+    assert_eq!(
+        clipboard("<ol><li>abc</li><li>def|</li></ol>", 0, 5),
+        (
+            "<ol><li>abc</li><li>d</li></ol>".to_owned(),
+            "abc\nd".to_owned()
+        )
+    );
+}
+
+#[test]
+fn copying_strips_zero_width_spaces() {
+    assert_eq!(
+        clipboard("a\u{200B}bc|", 0, 3),
+        ("ab".to_owned(), "ab".to_owned())
+    );
+}
+
+#[test]
+fn copying_an_empty_range_gives_nothing() {
+    assert_eq!(clipboard("abc|", 1, 1), (String::new(), String::new()));
+}
+
+#[test]
+fn copying_does_not_change_the_model() {
+    let model = cm("a<strong>b{cd}|</strong>ef");
+    model.get_clipboard_content(Location::from(0), Location::from(3));
+    assert_eq!(tx(&model), "a<strong>b{cd}|</strong>ef");
+}
+
+#[test]
+fn cutting_returns_the_selection_and_removes_it() {
+    let mut model = cm("a<strong>b{cd}|</strong>ef");
+    let (content, _) = model.cut_selection();
+    assert_eq!(content.html.to_string(), "<strong>cd</strong>");
+    assert_eq!(content.plain_text.to_string(), "cd");
+    assert_eq!(tx(&model), "a<strong>b|</strong>ef");
+}
+
+#[test]
+fn cutting_is_undone_in_one_step() {
+    let mut model = cm("abc|");
+    model.select(Location::from(0), Location::from(2));
+    model.cut_selection();
+    assert_eq!(model.undo_depth(), 1);
+    model.undo();
+    assert_eq!(tx(&model), "{ab}|c");
+}
+
+#[test]
+fn cutting_with_nothing_selected_does_nothing() {
+    let mut model = cm("ab|c");
+    let (content, _) = model.cut_selection();
+    assert_eq!(content.plain_text.to_string(), "");
+    assert_eq!(tx(&model), "ab|c");
+    assert_eq!(model.undo_depth(), 0);
+}
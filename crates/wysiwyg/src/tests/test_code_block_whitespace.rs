@@ -0,0 +1,127 @@
+// Copyright 2023 The Matrix.org Foundation C.I.C.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use crate::tests::testutils_composer_model::TestString;
+
+use crate::tests::testutils_composer_model::{cm, tx};
+use crate::ComposerModel;
+
+fn message_html_round_trip(html: &str) -> String {
+    let mut model: ComposerModel<TestString> = ComposerModel::new();
+    model.set_content_from_html(&utf16(html)).unwrap();
+    let first = model.get_content_as_message_html();
+
+    let mut model: ComposerModel<TestString> = ComposerModel::new();
+    model.set_content_from_html(&first).unwrap();
+    let second = model.get_content_as_message_html();
+    assert_eq!(first, second);
+    first.to_string()
+}
+
+fn utf16(s: &str) -> TestString {
+    TestString::from_str(s)
+}
+
+#[test]
+fn multiple_spaces_in_code_block_survive_round_trip() {
+    let html = "<pre><code>a    b  c</code></pre>";
+    assert_eq!(message_html_round_trip(html), html);
+}
+
+#[test]
+fn leading_and_trailing_spaces_in_code_block_survive_round_trip() {
+    let html = "<pre><code>  a  \n    b    </code></pre>";
+    assert_eq!(message_html_round_trip(html), html);
+}
+
+#[test]
+fn tabs_in_code_block_survive_round_trip() {
+    let html = "<pre><code>fn a() {\n\tif b {\n\t\tc();\n\t}\n}</code></pre>";
+    assert_eq!(message_html_round_trip(html), html);
+}
+
+#[test]
+fn blank_lines_in_code_block_survive_round_trip() {
+    let html = "<pre><code>a\n\n\nb</code></pre>";
+    assert_eq!(message_html_round_trip(html), html);
+}
+
+#[test]
+fn whitespace_only_lines_in_code_block_survive_round_trip() {
+    let html = "<pre><code>a\n   \n\t\nb</code></pre>";
+    assert_eq!(message_html_round_trip(html), html);
+}
+
+#[test]
+fn leading_empty_line_in_code_block_has_no_placeholder_in_message() {
+    let html = "<pre><code>\na</code></pre>";
+    assert_eq!(message_html_round_trip(html), html);
+}
+
+#[test]
+fn trailing_empty_line_in_code_block_has_no_placeholder_in_message() {
+    let html = "<pre><code>a\n</code></pre>";
+    assert_eq!(message_html_round_trip(html), html);
+}
+
+#[test]
+fn trailing_empty_line_in_code_block_keeps_placeholder_in_composer() {
+    let model = cm("<pre><code>a\n|</code></pre>");
+    assert_eq!(tx(&model), "<pre><code>a\n&nbsp;|</code></pre>");
+}
+
+#[test]
+fn typing_spaces_in_code_block_is_preserved_in_message() {
+    let mut model = cm("<pre><code>a|</code></pre>");
+    model.replace_text("   b  ".into());
+    assert_eq!(
+        model.get_content_as_message_html(),
+        "<pre><code>a   b  </code></pre>"
+    );
+}
+
+#[test]
+fn typing_tab_in_code_block_is_preserved_in_message() {
+    let mut model = cm("<pre><code>|a</code></pre>");
+    model.replace_text("\t".into());
+    assert_eq!(
+        model.get_content_as_message_html(),
+        "<pre><code>\ta</code></pre>"
+    );
+}
+
+#[test]
+fn new_lines_in_code_block_are_preserved_in_message() {
+    let mut model = cm("<pre><code>a|</code></pre>");
+    model.enter();
+    model.replace_text("  b".into());
+    model.enter();
+    assert_eq!(
+        model.get_content_as_message_html(),
+        "<pre><code>a\n  b\n</code></pre>"
+    );
+}
+
+#[test]
+fn code_block_whitespace_survives_markdown_round_trip() {
+    let html = "<pre><code>  a  \n\n\tb</code></pre>";
+    let mut model: ComposerModel<TestString> = ComposerModel::new();
+    model.set_content_from_html(&utf16(html)).unwrap();
+    let markdown = model.get_content_as_markdown();
+    assert_eq!(markdown, "```\n  a  \n\n\tb\n```\n");
+
+    let mut model: ComposerModel<TestString> = ComposerModel::new();
+    model.set_content_from_markdown(&markdown).unwrap();
+    assert_eq!(model.get_content_as_message_html(), html);
+}
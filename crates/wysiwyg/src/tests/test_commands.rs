@@ -0,0 +1,162 @@
+// Copyright 2023 The Matrix.org Foundation C.I.C.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use crate::tests::testutils_composer_model::{cm, tx};
+use crate::{ActionState, CommandError, ComposerAction, MenuState, TextUpdate};
+
+#[test]
+fn executing_a_command_without_args_runs_it() {
+    let mut model = cm("{abc}|");
+    model.execute("bold", "").unwrap();
+    assert_eq!(tx(&model), "<strong>{abc}|</strong>");
+}
+
+#[test]
+fn executing_a_command_with_args_passes_them() {
+    let mut model = cm("abc|");
+    model
+        .execute("select", r#"{"start": 1, "end": 2}"#)
+        .unwrap();
+    model.execute("replace_text", r#"{"text": "X"}"#).unwrap();
+    assert_eq!(tx(&model), "aX|c");
+}
+
+#[test]
+fn executing_set_link_passes_attributes() {
+    let mut model = cm("{abc}|");
+    model
+        .execute(
+            "set_link",
+            r#"{"url": "https://matrix.org", "attributes": {"data-x": "y"}}"#,
+        )
+        .unwrap();
+    assert_eq!(
+        tx(&model),
+        "<a data-x=\"y\" href=\"https://matrix.org\">{abc}|</a>"
+    );
+}
+
+#[test]
+fn executing_set_content_from_html_replaces_the_content() {
+    let mut model = cm("abc|");
+    model
+        .execute("set_content_from_html", r#"{"html": "<em>def</em>"}"#)
+        .unwrap();
+    assert_eq!(tx(&model), "<em>def|</em>");
+}
+
+#[test]
+fn executing_an_unknown_command_fails() {
+    let mut model = cm("abc|");
+    assert_eq!(
+        model.execute("explode", "").unwrap_err(),
+        CommandError::UnknownCommand("explode".to_owned())
+    );
+    assert_eq!(tx(&model), "abc|");
+}
+
+#[test]
+fn executing_with_missing_or_unexpected_args_fails() {
+    let mut model = cm("abc|");
+    assert!(matches!(
+        model.execute("replace_text", "{}"),
+        Err(CommandError::InvalidArguments(_))
+    ));
+    assert!(matches!(
+        model.execute("bold", r#"{"text": "X"}"#),
+        Err(CommandError::InvalidArguments(_))
+    ));
+    assert_eq!(tx(&model), "abc|");
+}
+
+#[test]
+fn executing_with_args_of_the_wrong_type_fails() {
+    let mut model = cm("abc|");
+    assert_eq!(
+        model
+            .execute("select", r#"{"start": "1", "end": 2}"#)
+            .unwrap_err(),
+        CommandError::InvalidArguments(
+            "select: start should be a location".to_owned()
+        )
+    );
+    assert!(matches!(
+        model.execute("replace_text", "not json"),
+        Err(CommandError::InvalidArguments(_))
+    ));
+}
+
+#[test]
+fn commands_are_listed_as_json() {
+    let model = cm("|");
+    let json = model.get_commands_as_json();
+    assert!(json.starts_with("[{"));
+    assert!(json.contains(
+        r#"{"args":[{"name":"text","required":true,"type":"string"}],"description":"Replace the selection with text","name":"replace_text"}"#
+    ));
+    assert_eq!(
+        model
+            .get_commands()
+            .iter()
+            .filter(|c| c.name == "bold")
+            .count(),
+        1
+    );
+}
+
+#[test]
+fn executing_a_batch_runs_every_command() {
+    let mut model = cm("abc def|");
+    let update = model
+        .execute_batch(&[("select", r#"{"start": 4, "end": 7}"#), ("bold", "")])
+        .unwrap();
+    assert_eq!(tx(&model), "abc <strong>{def}|</strong>");
+    let TextUpdate::ReplaceAll(replace_all) = update.text_update else {
+        panic!("Expected the batch to replace the content");
+    };
+    assert_eq!(replace_all.replacement_html, "abc <strong>def</strong>");
+}
+
+#[test]
+fn executing_a_batch_reports_the_menu_state_for_all_commands() {
+    let mut model = cm("abc|");
+    model.execute("bold", "").unwrap();
+    let update = model
+        .execute_batch(&[("bold", ""), ("italic", "")])
+        .unwrap();
+    let MenuState::Update(menu_state) = update.menu_state else {
+        panic!("Expected the menu state to be updated");
+    };
+    assert_eq!(
+        menu_state.action_states[&ComposerAction::Bold],
+        ActionState::Enabled
+    );
+    assert_eq!(
+        menu_state.action_states[&ComposerAction::Italic],
+        ActionState::Reversed
+    );
+}
+
+#[test]
+fn executing_a_batch_with_invalid_args_changes_nothing() {
+    let mut model = cm("abc|");
+    assert!(matches!(
+        model.execute_batch(&[
+            ("replace_text", r#"{"text": "X"}"#),
+            ("bold", "[]")
+        ]),
+        Err(CommandError::InvalidArguments(_))
+    ));
+    assert_eq!(tx(&model), "abc|");
+}
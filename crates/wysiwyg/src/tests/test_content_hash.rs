@@ -0,0 +1,66 @@
+// Copyright 2023 The Matrix.org Foundation C.I.C.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use crate::tests::testutils_composer_model::{cm, TestString};
+use crate::tests::testutils_conversion::utf16;
+use crate::ComposerModel;
+
+#[test]
+fn content_hash_changes_with_content_but_not_selection() {
+    let mut model = cm("<p>a|b</p>");
+    let hash = model.content_hash();
+    assert_eq!(cm("<p>{ab}|</p>").content_hash(), hash);
+    assert_ne!(cm("<p><em>a|b</em></p>").content_hash(), hash);
+    model.replace_text(utf16("c"));
+    assert_ne!(model.content_hash(), hash);
+    model.undo();
+    assert_eq!(model.content_hash(), hash);
+}
+
+#[test]
+fn content_hash_is_the_same_with_or_without_a_paragraph() {
+    let model = ComposerModel::<TestString>::from_html("a", 0, 0);
+    assert_eq!(
+        ComposerModel::<TestString>::from_html("<p>a</p>", 0, 0).content_hash(),
+        model.content_hash()
+    );
+    assert_ne!(
+        ComposerModel::<TestString>::from_html("<p>a</p><p>b</p>", 0, 0)
+            .content_hash(),
+        ComposerModel::<TestString>::from_html("<p>ab</p>", 0, 0)
+            .content_hash()
+    );
+    assert_ne!(
+        ComposerModel::<TestString>::from_html(
+            "<blockquote>a</blockquote>",
+            0,
+            0
+        )
+        .content_hash(),
+        model.content_hash()
+    );
+}
+
+#[test]
+fn content_is_empty_with_only_whitespace_and_empty_blocks() {
+    assert!(cm("|").is_content_empty());
+    assert!(cm("<p> |</p><p></p><br />").is_content_empty());
+    assert!(cm("<ul><li>&nbsp;|</li></ul>").is_content_empty());
+    assert!(!cm("<p> a|</p>").is_content_empty());
+    assert!(!cm(
+        "<p><a href=\"https://matrix.to/#/@alice:matrix.org\">Alice</a>|</p>"
+    )
+    .is_content_empty());
+    assert!(!cm("|<hr />").is_content_empty());
+}
@@ -0,0 +1,186 @@
+// Copyright 2023 The Matrix.org Foundation C.I.C.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+#![cfg(test)]
+
+use crate::tests::testutils_composer_model::{cm, tx};
+use crate::tests::testutils_dom::tn;
+use crate::{Direction, DomNode, Granularity, Location};
+
+#[test]
+fn moving_by_character_steps_over_whole_graphemes() {
+    let mut model = cm("a|🧑🏽\u{200D}💻b");
+    model.move_cursor(Direction::Forwards, Granularity::Character);
+    assert_eq!(tx(&model), "a🧑🏽\u{200D}💻|b");
+    model.move_cursor(Direction::Backwards, Granularity::Character);
+    model.move_cursor(Direction::Backwards, Granularity::Character);
+    assert_eq!(tx(&model), "|a🧑🏽\u{200D}💻b");
+}
+
+#[test]
+fn moving_by_character_steps_over_a_mention() {
+    let mut model =
+        cm("a|<a href=\"https://matrix.to/#/@alice:matrix.org\">Alice</a>b");
+    model.move_cursor(Direction::Forwards, Granularity::Character);
+    assert_eq!(
+        tx(&model),
+        "a<a data-mention-type=\"user\" href=\"https://matrix.to/#/@alice:matrix.org\" contenteditable=\"false\">Alice</a>|b"
+    );
+    model.move_cursor(Direction::Backwards, Granularity::Character);
+    assert_eq!(
+        tx(&model),
+        "a|<a data-mention-type=\"user\" href=\"https://matrix.to/#/@alice:matrix.org\" contenteditable=\"false\">Alice</a>b"
+    );
+}
+
+#[test]
+fn moving_by_character_steps_over_an_immutable_link() {
+    let mut model = cm(
+        "a|<a contenteditable=\"false\" href=\"https://matrix.org\">link</a>b",
+    );
+    model.move_cursor(Direction::Forwards, Granularity::Character);
+    assert_eq!(
+        tx(&model),
+        "a<a contenteditable=\"false\" href=\"https://matrix.org\">link|</a>b"
+    );
+}
+
+#[test]
+fn moving_by_character_crosses_formatting_and_blocks() {
+    let mut model = cm("<p>a<strong>b|</strong></p><p>c</p>");
+    model.move_cursor(Direction::Forwards, Granularity::Character);
+    assert_eq!(tx(&model), "<p>a<strong>b</strong></p><p>|c</p>");
+    model.move_cursor(Direction::Backwards, Granularity::Character);
+    model.move_cursor(Direction::Backwards, Granularity::Character);
+    assert_eq!(tx(&model), "<p>a|<strong>b</strong></p><p>c</p>");
+}
+
+#[test]
+fn moving_by_character_stops_at_the_ends() {
+    let mut model = cm("|ab");
+    model.move_cursor(Direction::Backwards, Granularity::Character);
+    assert_eq!(tx(&model), "|ab");
+    let mut model = cm("ab|");
+    model.move_cursor(Direction::Forwards, Granularity::Character);
+    assert_eq!(tx(&model), "ab|");
+}
+
+#[test]
+fn moving_by_character_collapses_a_selection() {
+    let mut model = cm("a{bc}|d");
+    model.move_cursor(Direction::Backwards, Granularity::Character);
+    assert_eq!(tx(&model), "a|bcd");
+    let mut model = cm("a|{bc}d");
+    model.move_cursor(Direction::Forwards, Granularity::Character);
+    assert_eq!(tx(&model), "abc|d");
+}
+
+#[test]
+fn moving_by_word_skips_spaces_then_a_word() {
+    let mut model = cm("|one, two-part <em>wo</em>rd");
+    model.move_cursor(Direction::Forwards, Granularity::Word);
+    assert_eq!(tx(&model), "one|, two-part <em>wo</em>rd");
+    model.move_cursor(Direction::Forwards, Granularity::Word);
+    assert_eq!(tx(&model), "one, two-part| <em>wo</em>rd");
+    model.move_cursor(Direction::Forwards, Granularity::Word);
+    assert_eq!(tx(&model), "one, two-part <em>wo</em>rd|");
+    model.move_cursor(Direction::Backwards, Granularity::Word);
+    assert_eq!(tx(&model), "one, two-part |<em>wo</em>rd");
+}
+
+#[test]
+fn moving_by_word_steps_over_one_mention_or_block_boundary() {
+    let mut model =
+        cm("a |<a href=\"https://matrix.to/#/@alice:matrix.org\">Alice</a> b");
+    model.move_cursor(Direction::Forwards, Granularity::Word);
+    assert_eq!(
+        tx(&model),
+        "a <a data-mention-type=\"user\" href=\"https://matrix.to/#/@alice:matrix.org\" contenteditable=\"false\">Alice</a>| b"
+    );
+    let mut model = cm("<p>abc</p><p>|def</p>");
+    model.move_cursor(Direction::Backwards, Granularity::Word);
+    assert_eq!(tx(&model), "<p>abc|</p><p>def</p>");
+    model.move_cursor(Direction::Backwards, Granularity::Word);
+    assert_eq!(tx(&model), "<p>|abc</p><p>def</p>");
+}
+
+#[test]
+fn moving_by_character_steps_over_characters_outside_the_bmp() {
+    let mut model = cm("😀a|");
+    model.move_cursor(Direction::Backwards, Granularity::Character);
+    assert_eq!(tx(&model), "😀|a");
+    model.move_cursor(Direction::Backwards, Granularity::Character);
+    assert_eq!(tx(&model), "|😀a");
+    model.move_cursor(Direction::Forwards, Granularity::Character);
+    assert_eq!(tx(&model), "😀|a");
+}
+
+#[test]
+fn moving_by_word_after_characters_outside_the_bmp() {
+    let mut model = cm("😀 |ab cd");
+    model.move_cursor(Direction::Forwards, Granularity::Word);
+    assert_eq!(tx(&model), "😀 ab| cd");
+    model.move_cursor(Direction::Forwards, Granularity::Word);
+    assert_eq!(tx(&model), "😀 ab cd|");
+    model.move_cursor(Direction::Backwards, Granularity::Word);
+    model.move_cursor(Direction::Backwards, Granularity::Word);
+    assert_eq!(tx(&model), "😀 |ab cd");
+}
+
+#[test]
+fn line_offsets_are_the_edges_of_the_block() {
+    let model = cm("<p>ab</p><p>c<strong>d|e</strong>f</p><p>gh</p>");
+    assert_eq!(
+        model.line_start_offset(Location::from(5)),
+        Location::from(3)
+    );
+    assert_eq!(model.line_end_offset(Location::from(5)), Location::from(7));
+}
+
+#[test]
+fn line_offsets_include_mentions_and_links() {
+    let model = cm(
+        "a<a href=\"https://matrix.to/#/@alice:matrix.org\">Alice</a> <a href=\"https://x.org\">link</a>|",
+    );
+    assert_eq!(
+        model.line_start_offset(Location::from(3)),
+        Location::from(0)
+    );
+    assert_eq!(model.line_end_offset(Location::from(3)), Location::from(7));
+}
+
+#[test]
+fn line_offsets_stop_at_line_breaks() {
+    let mut model = cm("|");
+    let dom = &mut model.state.dom;
+    dom.append_at_end_of_document(tn("ab"));
+    dom.append_at_end_of_document(DomNode::new_line_break());
+    dom.append_at_end_of_document(tn("cd"));
+    assert_eq!(
+        model.line_start_offset(Location::from(4)),
+        Location::from(3)
+    );
+    assert_eq!(model.line_end_offset(Location::from(1)), Location::from(2));
+    assert_eq!(model.line_end_offset(Location::from(3)), Location::from(5));
+}
+
+#[test]
+fn line_offsets_in_an_empty_block_are_the_position() {
+    let model = cm("<p>ab</p><p></p><p>|cd</p>");
+    assert_eq!(
+        model.line_start_offset(Location::from(3)),
+        Location::from(3)
+    );
+    assert_eq!(model.line_end_offset(Location::from(3)), Location::from(3));
+}
@@ -0,0 +1,103 @@
+// Copyright 2023 The Matrix.org Foundation C.I.C.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+#![cfg(test)]
+
+use crate::tests::testutils_composer_model::{cm, tx};
+use crate::tests::testutils_conversion::utf16;
+
+const PARTY: &str = "<img data-mx-emoticon src=\"mxc://a/b\" alt=\":party:\" \
+    title=\":party:\" height=\"32\" />";
+
+#[test]
+fn inserting_a_custom_emoji_puts_the_cursor_after_it() {
+    let mut model = cm("a|b");
+    model.insert_custom_emoji(utf16("party"), utf16("mxc://a/b"));
+    assert_eq!(tx(&model), format!("a{PARTY}|b"));
+    assert_eq!(model.state.start, 2);
+}
+
+#[test]
+fn custom_emoji_shortcode_keeps_its_colons() {
+    let mut model = cm("|");
+    model.insert_custom_emoji(utf16(":party:"), utf16("mxc://a/b"));
+    assert_eq!(tx(&model), format!("{PARTY}|"));
+}
+
+#[test]
+fn inserting_a_custom_emoji_replaces_the_selection() {
+    let mut model = cm("a{bc}|d");
+    model.insert_custom_emoji(utf16("party"), utf16("mxc://a/b"));
+    assert_eq!(tx(&model), format!("a{PARTY}|d"));
+}
+
+#[test]
+fn inserting_a_custom_emoji_in_inline_code_does_nothing() {
+    let mut model = cm("<code>a|b</code>");
+    model.insert_custom_emoji(utf16("party"), utf16("mxc://a/b"));
+    assert_eq!(tx(&model), "<code>a|b</code>");
+}
+
+#[test]
+fn custom_emoji_from_anywhere_but_mxc_is_not_inserted() {
+    let mut model = cm("a|");
+    model.insert_custom_emoji(utf16("party"), utf16("javascript:alert(1)"));
+    model.insert_custom_emoji(utf16("party"), utf16("data:image/png;base64,"));
+    assert_eq!(tx(&model), "a|");
+}
+
+#[test]
+fn custom_emoji_round_trips_through_html() {
+    let model = cm(&format!("a{PARTY}|"));
+    assert_eq!(tx(&model), format!("a{PARTY}|"));
+    assert_eq!(model.get_content_as_message_html(), format!("a{PARTY}"));
+}
+
+#[test]
+fn custom_emoji_is_deleted_as_one_character() {
+    let mut model = cm(&format!("a{PARTY}|b"));
+    model.backspace();
+    assert_eq!(tx(&model), "a|b");
+
+    let mut model = cm(&format!("a|{PARTY}b"));
+    model.delete();
+    assert_eq!(tx(&model), "a|b");
+}
+
+#[test]
+fn custom_emoji_is_selected_as_one_character() {
+    let mut model = cm(&format!("a|{PARTY}b"));
+    model.select(1.into(), 2.into());
+    assert_eq!(tx(&model), format!("a{{{PARTY}}}|b"));
+}
+
+#[test]
+fn cursor_moves_over_a_custom_emoji_in_one_step() {
+    let mut model = cm(&format!("a|{PARTY}b"));
+    model
+        .move_cursor(crate::Direction::Forwards, crate::Granularity::Character);
+    assert_eq!(tx(&model), format!("a{PARTY}|b"));
+}
+
+#[test]
+fn custom_emoji_is_its_shortcode_in_plain_text() {
+    let model = cm(&format!("a {PARTY}|"));
+    assert_eq!(model.get_content_as_plain_text(), "a :party:");
+}
+
+#[test]
+fn custom_emoji_is_written_as_html_in_markdown() {
+    let model = cm(&format!("a {PARTY}|"));
+    assert_eq!(model.get_content_as_markdown(), format!("a {PARTY}"));
+}
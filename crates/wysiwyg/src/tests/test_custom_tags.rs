@@ -0,0 +1,219 @@
+// Copyright 2023 The Matrix.org Foundation C.I.C.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use std::sync::Arc;
+
+use crate::tests::testutils_composer_model::TestString;
+
+use crate::tests::testutils_composer_model::{cm, tx};
+use crate::tests::testutils_conversion::utf16;
+use crate::{ComposerModel, CustomTagSerializer};
+
+/// Sends `kbd` as `code`, keeping its title as a `data-` attribute
+struct KbdAsCode;
+
+impl CustomTagSerializer for KbdAsCode {
+    fn open(&self, _tag: &str, attrs: &[(String, String)]) -> String {
+        match attrs.iter().find(|(name, _)| name == "title") {
+            Some((_, title)) => format!("<code data-title=\"{title}\">"),
+            None => "<code>".to_owned(),
+        }
+    }
+
+    fn close(&self, _tag: &str) -> String {
+        "</code>".to_owned()
+    }
+}
+
+fn model_with_tags(tags: &[&str]) -> ComposerModel<TestString> {
+    let mut model = ComposerModel::new();
+    model.set_custom_inline_tags(tags.iter().map(|t| t.to_string()).collect());
+    model
+}
+
+#[test]
+fn unregistered_tags_are_dropped_when_parsing() {
+    let mut model = model_with_tags(&[]);
+    model
+        .set_content_from_html(&utf16("Press <kbd>Ctrl</kbd>"))
+        .unwrap();
+    // Like any other unknown tag, it is dropped along with its contents
+    assert_eq!(model.get_content_as_html(), "Press\u{a0}");
+}
+
+#[test]
+fn registered_tags_are_kept_when_parsing() {
+    let mut model = model_with_tags(&["kbd", "abbr"]);
+    model
+        .set_content_from_html(&utf16(
+            "Press <kbd>Ctrl</kbd> in <abbr title=\"HyperText\" onclick=\"x\">HTML</abbr>",
+        ))
+        .unwrap();
+    assert_eq!(
+        model.get_content_as_html(),
+        "Press <kbd>Ctrl</kbd> in <abbr title=\"HyperText\">HTML</abbr>"
+    );
+}
+
+#[test]
+fn registered_tags_are_case_insensitive() {
+    let mut model = model_with_tags(&["KBD"]);
+    model.set_content_from_html(&utf16("<KBD>a</KBD>")).unwrap();
+    assert_eq!(model.get_content_as_html(), "<kbd>a</kbd>");
+}
+
+#[test]
+fn custom_tag_attributes_are_escaped() {
+    let mut model = model_with_tags(&["abbr"]);
+    model
+        .set_content_from_html(&utf16("<abbr title=\"a&quot;b\">c</abbr>"))
+        .unwrap();
+    assert_eq!(
+        model.get_content_as_html(),
+        "<abbr title=\"a&quot;b\">c</abbr>"
+    );
+}
+
+#[test]
+fn supported_and_unsafe_tags_cannot_be_registered() {
+    let model = model_with_tags(&["strong", "script", "a", "kbd", "k b"]);
+    assert_eq!(model.custom_inline_tags, vec!["kbd".to_owned()]);
+}
+
+#[test]
+fn only_allowlisted_tags_can_be_registered() {
+    let model = model_with_tags(&[
+        "textarea", "svg", "h1", "hr", "img", "span", "sub", "abbr",
+    ]);
+    assert_eq!(
+        model.custom_inline_tags,
+        vec!["sub".to_owned(), "abbr".to_owned()]
+    );
+}
+
+#[test]
+fn custom_format_wraps_selection() {
+    let mut model = cm("Press {Ctrl}|");
+    model.set_custom_inline_tags(vec!["kbd".to_owned()]);
+    model.custom_format("kbd");
+    assert_eq!(tx(&model), "Press <kbd>{Ctrl}|</kbd>");
+}
+
+#[test]
+fn custom_format_unwraps_formatted_selection() {
+    let mut model = model_with_tags(&["kbd"]);
+    model
+        .set_content_from_html(&utf16("<kbd>Ctrl</kbd>"))
+        .unwrap();
+    model.select(0.into(), 4.into());
+    model.custom_format("kbd");
+    assert_eq!(tx(&model), "{Ctrl}|");
+}
+
+#[test]
+fn custom_format_does_nothing_for_unregistered_tags() {
+    let mut model = cm("{Ctrl}|");
+    model.custom_format("kbd");
+    assert_eq!(tx(&model), "{Ctrl}|");
+}
+
+#[test]
+fn custom_format_with_cursor_applies_to_typed_text() {
+    let mut model = cm("Press |");
+    model.set_custom_inline_tags(vec!["kbd".to_owned()]);
+    model.custom_format("kbd");
+    model.replace_text(utf16("Esc"));
+    assert_eq!(tx(&model), "Press <kbd>Esc|</kbd>");
+}
+
+#[test]
+fn custom_format_toggled_twice_with_cursor_does_nothing() {
+    let mut model = cm("Press |");
+    model.set_custom_inline_tags(vec!["kbd".to_owned()]);
+    model.custom_format("kbd");
+    model.custom_format("kbd");
+    model.replace_text(utf16("Esc"));
+    assert_eq!(tx(&model), "Press Esc|");
+}
+
+#[test]
+fn custom_tags_are_kept_in_message_and_markdown_output() {
+    let mut model = model_with_tags(&["kbd"]);
+    model
+        .set_content_from_html(&utf16("Press <kbd>Ctrl</kbd>"))
+        .unwrap();
+    assert_eq!(model.get_content_as_message_html(), "Press <kbd>Ctrl</kbd>");
+    assert_eq!(
+        model.get_content_as_message_markdown(),
+        "Press <kbd>Ctrl</kbd>"
+    );
+    assert_eq!(model.get_content_as_plain_text(), "Press Ctrl");
+}
+
+#[test]
+fn custom_tag_serializer_writes_the_tag_in_message_html() {
+    let mut model = model_with_tags(&["kbd"]);
+    model.set_custom_tag_serializer("kbd", Arc::new(KbdAsCode));
+    model
+        .set_content_from_html(&utf16(
+            "Press <kbd title=\"control\">Ctrl <em>now</em></kbd>",
+        ))
+        .unwrap();
+    assert_eq!(
+        model.get_content_as_message_html(),
+        "Press <code data-title=\"control\">Ctrl <em>now</em></code>"
+    );
+}
+
+#[test]
+fn custom_tag_serializer_gets_escaped_attribute_values() {
+    let mut model = model_with_tags(&["kbd"]);
+    model.set_custom_tag_serializer("kbd", Arc::new(KbdAsCode));
+    model
+        .set_content_from_html(&utf16(
+            "<kbd title=\"&quot;&gt;<img src=x>\">Ctrl</kbd>",
+        ))
+        .unwrap();
+    assert_eq!(
+        model.get_content_as_message_html(),
+        "<code data-title=\"&quot;&gt;&lt;img src=x&gt;\">Ctrl</code>"
+    );
+}
+
+#[test]
+fn custom_tag_serializer_does_not_change_the_composer_html() {
+    let mut model = model_with_tags(&["kbd"]);
+    model.set_custom_tag_serializer("KBD", Arc::new(KbdAsCode));
+    model
+        .set_content_from_html(&utf16("Press <kbd>Ctrl</kbd>"))
+        .unwrap();
+    assert_eq!(model.get_content_as_html(), "Press <kbd>Ctrl</kbd>");
+    assert_eq!(
+        model.get_content_as_message_html(),
+        "Press <code>Ctrl</code>"
+    );
+}
+
+#[test]
+fn tags_without_a_serializer_are_written_as_usual() {
+    let mut model = model_with_tags(&["kbd", "abbr"]);
+    model.set_custom_tag_serializer("kbd", Arc::new(KbdAsCode));
+    model
+        .set_content_from_html(&utf16("<abbr title=\"a\">b</abbr>"))
+        .unwrap();
+    assert_eq!(
+        model.get_content_as_message_html(),
+        "<abbr title=\"a\">b</abbr>"
+    );
+}
@@ -0,0 +1,218 @@
+// Copyright 2023 The Matrix.org Foundation C.I.C.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use crate::tests::testutils_composer_model::TestString;
+
+use crate::tests::testutils_composer_model::tx;
+use crate::tests::testutils_conversion::utf16;
+use crate::ComposerModel;
+
+fn model_from_html(html: &str) -> ComposerModel<TestString> {
+    let mut model = ComposerModel::new();
+    model.set_content_from_html(&utf16(html)).unwrap();
+    model
+}
+
+#[test]
+fn data_attributes_on_links_survive_parsing() {
+    let model = model_from_html(
+        "<a href=\"https://matrix.org\" data-preview=\"card\">link</a>",
+    );
+    assert_eq!(
+        model.get_content_as_html(),
+        "<a data-preview=\"card\" href=\"https://matrix.org\">link</a>"
+    );
+    assert_eq!(
+        model.get_content_as_message_html(),
+        "<a data-preview=\"card\" href=\"https://matrix.org\">link</a>"
+    );
+}
+
+#[test]
+fn spans_with_data_attributes_survive_parsing() {
+    let model = model_from_html("a <span data-id=\"1\">span</span> b");
+    assert_eq!(
+        model.get_content_as_html(),
+        "a <span data-id=\"1\">span</span> b"
+    );
+    assert_eq!(
+        model.get_content_as_message_html(),
+        "a <span data-id=\"1\">span</span> b"
+    );
+    assert_eq!(
+        model.get_content_as_message_markdown(),
+        "a <span data-id=\"1\">span</span> b"
+    );
+}
+
+#[test]
+fn only_data_attributes_are_kept_on_spans() {
+    let model = model_from_html(
+        "<span data-id=\"1\" data-a_b=\"2\" onclick=\"x\">span</span>",
+    );
+    assert_eq!(
+        model.get_content_as_html(),
+        "<span data-id=\"1\">span</span>"
+    );
+}
+
+#[test]
+fn data_attribute_values_on_spans_are_escaped() {
+    let model = model_from_html("<span data-id='\"&lt;'>span</span>");
+    assert_eq!(
+        model.get_content_as_html(),
+        "<span data-id=\"&quot;&lt;\">span</span>"
+    );
+}
+
+#[test]
+fn formatting_inside_a_link_keeps_its_data_attributes() {
+    let mut model = model_from_html(
+        "<a href=\"https://matrix.org\" data-preview=\"card\">link text</a>",
+    );
+    model.select(2.into(), 6.into());
+    model.bold();
+    assert_eq!(
+        tx(&model),
+        "<a data-preview=\"card\" href=\"https://matrix.org\">li<strong>{nk t}|</strong>ext</a>"
+    );
+}
+
+#[test]
+fn formatting_across_a_span_boundary_keeps_its_data_attributes() {
+    let mut model = model_from_html("<span data-id=\"1\">span</span> text");
+    model.select(2.into(), 7.into());
+    model.italic();
+    assert_eq!(
+        tx(&model),
+        "<span data-id=\"1\">sp<em>{an</em></span><em>&nbsp;te}|</em>xt"
+    );
+}
+
+#[test]
+fn splitting_a_link_copies_its_data_attributes() {
+    let mut model = model_from_html(
+        "<a href=\"https://matrix.org\" data-preview=\"card\">link text</a>",
+    );
+    model.select(4.into(), 4.into());
+    model.enter();
+    assert_eq!(
+        tx(&model),
+        "<p><a data-preview=\"card\" href=\"https://matrix.org\">link</a></p>\
+         <p><a data-preview=\"card\" href=\"https://matrix.org\">|&nbsp;text</a></p>"
+    );
+}
+
+#[test]
+fn splitting_a_span_copies_its_data_attributes() {
+    let mut model = model_from_html("<span data-id=\"1\">span text</span>");
+    model.select(4.into(), 4.into());
+    model.enter();
+    assert_eq!(
+        tx(&model),
+        "<p><span data-id=\"1\">span</span></p>\
+         <p><span data-id=\"1\">|&nbsp;text</span></p>"
+    );
+}
+
+#[test]
+fn merging_split_spans_back_keeps_their_data_attributes() {
+    let mut model = model_from_html("<span data-id=\"1\">span text</span>");
+    model.select(4.into(), 4.into());
+    model.enter();
+    model.backspace();
+    assert_eq!(tx(&model), "<p><span data-id=\"1\">span| text</span></p>");
+}
+
+#[test]
+fn spans_with_different_data_attributes_are_not_merged() {
+    let mut model = model_from_html(
+        "<span data-id=\"1\">a</span>b<span data-id=\"2\">c</span>",
+    );
+    model.select(1.into(), 2.into());
+    model.delete();
+    assert_eq!(
+        tx(&model),
+        "<span data-id=\"1\">a|</span><span data-id=\"2\">c</span>"
+    );
+}
+
+#[test]
+fn editing_a_link_keeps_its_data_attributes() {
+    let mut model = model_from_html(
+        "<a href=\"https://matrix.org\" data-preview=\"card\">link</a>",
+    );
+    model.select(1.into(), 1.into());
+    model.set_link(utf16("https://element.io"), vec![]);
+    assert_eq!(
+        model.get_content_as_html(),
+        "<a data-preview=\"card\" href=\"https://element.io\">link</a>"
+    );
+}
+
+#[test]
+fn editing_a_link_can_replace_its_data_attributes() {
+    let mut model = model_from_html(
+        "<a href=\"https://matrix.org\" data-preview=\"card\">link</a>",
+    );
+    model.select(1.into(), 1.into());
+    model.set_link(
+        utf16("https://element.io"),
+        vec![(utf16("data-preview"), utf16("none"))],
+    );
+    assert_eq!(
+        model.get_content_as_html(),
+        "<a data-preview=\"none\" href=\"https://element.io\">link</a>"
+    );
+}
+
+#[test]
+fn splitting_a_spoiler_keeps_it_on_both_halves() {
+    let mut model =
+        model_from_html("<span data-mx-spoiler=\"plot\">big reveal</span>");
+    model.select(3.into(), 3.into());
+    model.enter();
+    assert_eq!(
+        tx(&model),
+        "<p><span data-mx-spoiler=\"plot\">big</span></p>\
+         <p><span data-mx-spoiler=\"plot\">|&nbsp;reveal</span></p>"
+    );
+}
+
+#[test]
+fn splitting_a_link_keeps_its_id_on_the_first_half_only() {
+    let mut model = model_from_html(
+        "<a id=\"top\" href=\"https://matrix.org\" data-preview=\"card\">link text</a>",
+    );
+    model.select(4.into(), 4.into());
+    model.enter();
+    assert_eq!(
+        tx(&model),
+        "<p><a id=\"top\" data-preview=\"card\" href=\"https://matrix.org\">link</a></p>\
+         <p><a data-preview=\"card\" href=\"https://matrix.org\">|&nbsp;text</a></p>"
+    );
+}
+
+#[test]
+fn moving_a_whole_link_keeps_its_id() {
+    let mut model = model_from_html(
+        "text <a id=\"top\" href=\"https://matrix.org\">link</a>",
+    );
+    model.select(5.into(), 5.into());
+    model.enter();
+    assert_eq!(
+        tx(&model),
+        "<p>text&nbsp;</p><p><a id=\"top\" href=\"https://matrix.org\">|link</a></p>"
+    );
+}
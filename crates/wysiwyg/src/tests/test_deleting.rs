@@ -245,6 +245,7 @@ fn test_backspace_emoji() {
 }
 
 #[test]
+#[cfg_attr(feature = "test-utf32", ignore = "uses UTF-16 offsets")]
 fn test_backspace_complex_emoji() {
     let mut model = cm("Test😮‍💨|😅");
     model.backspace();
@@ -486,6 +487,21 @@ fn plain_delete_word_removes_runs_of_non_word_characters_and_whitespace() {
     assert_eq!(restore_whitespace(&tx(&model)), "|  abc")
 }
 
+#[test]
+fn plain_backspace_word_after_characters_outside_the_bmp() {
+    let mut model = cm("😀 ab|");
+    model.backspace_word();
+    assert_eq!(restore_whitespace(&tx(&model)), "😀 |");
+    model.backspace_word();
+    assert_eq!(tx(&model), "|");
+}
+#[test]
+fn plain_delete_word_after_characters_outside_the_bmp() {
+    let mut model = cm("😀😀|ab cd");
+    model.delete_word();
+    assert_eq!(restore_whitespace(&tx(&model)), "😀😀| cd");
+}
+
 // Remove word tests including html
 #[test]
 fn html_backspace_word_at_beginning_does_nothing() {
@@ -634,12 +650,11 @@ fn html_delete_word_does_not_remove_past_linebreak_in_word() {
     assert_eq!(restore_whitespace(&tx(&model)), "<p> |</p><p>f </p>")
 }
 
-#[ignore] // FIXME
 #[test]
 fn html_backspace_word_at_linebreak_removes_linebreak() {
     let mut model = cm("abc <br/>|");
     model.backspace_word();
-    assert_eq!(restore_whitespace(&tx(&model)), "<p>abc </p><p> |</p>");
+    assert_eq!(restore_whitespace(&tx(&model)), "<p>abc |</p>");
 }
 #[test]
 fn html_delete_word_at_linebreak_removes_linebreak() {
@@ -648,7 +663,6 @@ fn html_delete_word_at_linebreak_removes_linebreak() {
     assert_eq!(restore_whitespace(&tx(&model)), "<p>| abc</p>");
 }
 
-#[ignore] // FIXME
 #[test]
 fn html_backspace_word_removes_past_linebreak_in_whitespace() {
     let mut model = cm("abc <br/> |");
@@ -705,6 +719,35 @@ fn html_delete_word_removes_through_a_tag() {
     assert_eq!(restore_whitespace(&tx(&model)), "|");
 }
 
+#[test]
+fn html_delete_word_through_tags_stops_at_punctuation() {
+    let mut model = cm("ab|c<b>de</b><i>f</i>, gh");
+    model.delete_word();
+    assert_eq!(tx(&model), "ab|, gh");
+}
+
+#[test]
+fn html_delete_word_through_tags_is_one_undo_step() {
+    let mut model = cm("|so<em>me emphasis</em>");
+    model.delete_word();
+    model.undo();
+    assert_eq!(tx(&model), "|so<em>me emphasis</em>");
+}
+
+#[test]
+fn html_delete_word_at_end_of_paragraph_joins_next_paragraph() {
+    let mut model = cm("<p>abc|</p><p>def ghi</p>");
+    model.delete_word();
+    assert_eq!(tx(&model), "<p>abc|def ghi</p>");
+}
+
+#[test]
+fn html_backspace_word_at_start_of_paragraph_joins_previous_paragraph() {
+    let mut model = cm("<p>abc</p><p>|def ghi</p>");
+    model.backspace_word();
+    assert_eq!(tx(&model), "<p>abc|def ghi</p>");
+}
+
 #[test]
 fn html_backspace_word_removes_between_tags() {
     let mut model = cm("<em>start spl</em><strong>it</strong>| end");
@@ -813,6 +856,8 @@ fn backspace_between_block_nodes() {
 fn backspace_between_nested_block_nodes() {
     let mut model = cm("<p>First</p><blockquote><p>|Second</p></blockquote>");
     model.backspace();
+    assert_eq!(tx(&model), "<p>First</p><p>|Second</p>");
+    model.backspace();
     assert_eq!(tx(&model), "<p>First|Second</p>");
 }
 
@@ -850,7 +895,7 @@ fn html_backspace_word_for_empty_list_item() {
     model.backspace_word();
     assert_eq!(
         restore_whitespace(&tx(&model)),
-        "<ol><li>1</li><li>|</li><li>123</li></ol>"
+        "<ol><li>1|</li><li>123</li></ol>"
     );
 }
 #[test]
@@ -1037,3 +1082,253 @@ fn backspacing_paragraphs_with_nbsp_at_start() {
     model.backspace();
     assert_eq!(tx(&model), "<p>|test</p>")
 }
+
+#[test]
+fn backspace_word_stops_at_apostrophe_without_locale() {
+    let mut model = cm("we don't|");
+    model.backspace_word();
+    assert_eq!(tx(&model), "we don'|");
+}
+
+#[test]
+fn backspace_word_removes_contraction_in_english() {
+    let mut model = cm("we don't|");
+    model.set_locale("en-GB");
+    model.backspace_word();
+    assert_eq!(tx(&model), "we&nbsp;|");
+}
+
+#[test]
+fn delete_word_removes_contraction_in_english() {
+    let mut model = cm("|don’t go");
+    model.set_locale("en");
+    model.delete_word();
+    assert_eq!(tx(&model), "|&nbsp;go");
+}
+
+#[test]
+fn backspace_word_stops_at_elision_in_french() {
+    let mut model = cm("l'homme|");
+    model.set_locale("fr");
+    model.backspace_word();
+    assert_eq!(tx(&model), "l'|");
+}
+
+#[test]
+fn backspace_word_keeps_trailing_apostrophe_separate_in_english() {
+    let mut model = cm("the dogs'|");
+    model.set_locale("en");
+    model.backspace_word();
+    assert_eq!(tx(&model), "the dogs|");
+}
+
+#[test]
+fn delete_at_end_of_paragraph_merges_next_paragraph() {
+    let mut model = cm("<p>ab|</p><p>cd</p>");
+    model.delete();
+    assert_eq!(tx(&model), "<p>ab|cd</p>");
+}
+
+#[test]
+fn delete_at_end_of_paragraph_merges_first_list_item() {
+    let mut model = cm("<p>ab|</p><ul><li>cd</li><li>ef</li></ul>");
+    model.delete();
+    assert_eq!(tx(&model), "<p>ab|cd</p><ul><li>ef</li></ul>");
+}
+
+#[test]
+fn delete_at_end_of_list_merges_next_paragraph() {
+    let mut model = cm("<ul><li>ab|</li></ul><p>cd</p>");
+    model.delete();
+    assert_eq!(tx(&model), "<ul><li>ab|cd</li></ul>");
+}
+
+#[test]
+fn delete_at_end_of_quote_merges_next_paragraph() {
+    let mut model = cm("<blockquote><p>ab|</p></blockquote><p>cd</p>");
+    model.delete();
+    assert_eq!(tx(&model), "<blockquote><p>ab|cd</p></blockquote>");
+}
+
+#[test]
+fn delete_at_end_of_code_block_merges_next_quote() {
+    let mut model =
+        cm("<pre><code>ab|</code></pre><blockquote><p>cd</p></blockquote>");
+    model.delete();
+    assert_eq!(tx(&model), "<pre><code>ab|cd</code></pre>");
+}
+
+#[test]
+fn delete_at_end_of_nested_list_merges_next_paragraph() {
+    let mut model =
+        cm("<ul><li><p>ab</p><ul><li>cd|</li></ul></li></ul><p>ef</p>");
+    model.delete();
+    assert_eq!(
+        tx(&model),
+        "<ul><li><p>ab</p><ul><li>cd|ef</li></ul></li></ul>"
+    );
+}
+
+#[test]
+fn delete_before_list_item_with_nested_list_lifts_nested_items() {
+    let mut model =
+        cm("<p>ab|</p><ul><li><p>cd</p><ul><li>ef</li></ul></li></ul>");
+    model.delete();
+    assert_eq!(tx(&model), "<p>ab|cd</p><ul><li>ef</li></ul>");
+    model.state.dom.explicitly_assert_invariants();
+}
+
+#[test]
+fn backspace_after_empty_list_item_lifts_nested_items() {
+    let mut model = cm(
+        "<ol><li>ab</li><li></li></ol><ul><li><p>|cd</p><ul><li>ef</li></ul></li></ul>",
+    );
+    model.backspace();
+    assert_eq!(
+        tx(&model),
+        "<ol><li>ab</li><li>|cd</li></ol><ul><li>ef</li></ul>"
+    );
+}
+
+#[test]
+fn delete_at_end_of_paragraph_removes_next_empty_paragraph() {
+    let mut model = cm("<p>ab|</p><p></p><p>cd</p>");
+    model.delete();
+    assert_eq!(tx(&model), "<p>ab|</p><p>cd</p>");
+}
+
+#[test]
+fn delete_in_empty_paragraph_merges_next_block() {
+    let mut model = cm("<p>|</p><blockquote><p>ab</p></blockquote>");
+    model.delete();
+    assert_eq!(tx(&model), "<p>|ab</p>");
+}
+
+#[test]
+fn delete_at_end_of_document_in_nested_blocks_does_nothing() {
+    let mut model = cm("<blockquote><ul><li>ab|</li></ul></blockquote>");
+    model.delete();
+    assert_eq!(tx(&model), "<blockquote><ul><li>ab|</li></ul></blockquote>");
+}
+
+#[test]
+fn backspace_at_start_of_list_in_quote_at_start_of_document_unwraps_it() {
+    let mut model = cm("<blockquote><ul><li>|ab</li></ul></blockquote>");
+    model.backspace();
+    assert_eq!(tx(&model), "<blockquote><p>|ab</p></blockquote>");
+}
+
+#[test]
+fn backspace_at_start_of_quote_in_list_at_start_of_document_removes_quote() {
+    let mut model = cm("<ul><li><blockquote><p>|ab</p></blockquote></li></ul>");
+    model.backspace();
+    assert_eq!(tx(&model), "<ul><li><p>|ab</p></li></ul>");
+    model.backspace();
+    assert_eq!(tx(&model), "<p>|ab</p>");
+}
+
+#[test]
+fn backspace_at_start_of_quote_lifts_first_paragraph_out() {
+    let mut model =
+        cm("<p>First</p><blockquote><p>|Second</p><p>Third</p></blockquote>");
+    model.backspace();
+    assert_eq!(
+        tx(&model),
+        "<p>First</p><p>|Second</p><blockquote><p>Third</p></blockquote>"
+    );
+    model.backspace();
+    assert_eq!(
+        tx(&model),
+        "<p>First|Second</p><blockquote><p>Third</p></blockquote>"
+    );
+}
+
+#[test]
+fn backspace_at_start_of_quote_with_one_paragraph_removes_it() {
+    let mut model = cm("<p>First</p><blockquote><p>|Second</p></blockquote>");
+    model.backspace();
+    assert_eq!(tx(&model), "<p>First</p><p>|Second</p>");
+}
+
+#[test]
+fn backspace_at_start_of_quote_at_start_of_document_removes_it() {
+    let mut model = cm("<blockquote><p>|ab</p></blockquote><p>cd</p>");
+    model.backspace();
+    assert_eq!(tx(&model), "<p>|ab</p><p>cd</p>");
+}
+
+#[test]
+fn backspace_at_start_of_quote_with_inline_content_wraps_it_in_a_paragraph() {
+    let mut model = cm("<p>a</p><blockquote>|ab</blockquote>");
+    model.backspace();
+    assert_eq!(tx(&model), "<p>a</p><p>|ab</p>");
+    model.backspace();
+    assert_eq!(tx(&model), "<p>a|ab</p>");
+}
+
+#[test]
+fn backspace_at_start_of_quote_with_inline_content_at_start_of_document() {
+    let mut model = cm("<blockquote>|ab</blockquote><p>c</p>");
+    model.backspace();
+    assert_eq!(tx(&model), "<p>|ab</p><p>c</p>");
+}
+
+#[test]
+fn backspace_at_start_of_nested_quote_lifts_one_level() {
+    let mut model = cm(
+        "<blockquote><blockquote><p>|ab</p><p>cd</p></blockquote></blockquote>",
+    );
+    model.backspace();
+    assert_eq!(
+        tx(&model),
+        "<blockquote><p>|ab</p><blockquote><p>cd</p></blockquote></blockquote>"
+    );
+}
+
+#[test]
+fn backspace_at_start_of_nested_list_at_start_of_document_unindents() {
+    let mut model = cm("<ul><li><ul><li>|ab</li></ul></li></ul>");
+    model.backspace();
+    assert_eq!(tx(&model), "<ul><li>|ab</li></ul>");
+}
+
+#[test]
+fn backspace_at_start_of_code_block_at_start_of_document_lifts_first_line() {
+    let mut model = cm("<pre><code>|ab\ncd</code></pre>");
+    model.backspace();
+    assert_eq!(tx(&model), "<p>|ab</p><pre><code>cd</code></pre>");
+    model.state.dom.explicitly_assert_invariants();
+}
+
+#[test]
+fn backspace_at_start_of_single_line_code_block_removes_it() {
+    let mut model = cm("<pre><code>|ab</code></pre>");
+    model.backspace();
+    assert_eq!(tx(&model), "<p>|ab</p>");
+}
+
+#[test]
+fn backspace_at_start_of_code_block_in_quote_lifts_first_line() {
+    let mut model =
+        cm("<blockquote><pre><code>|ab\ncd</code></pre></blockquote>");
+    model.backspace();
+    assert_eq!(
+        tx(&model),
+        "<blockquote><p>|ab</p><pre><code>cd</code></pre></blockquote>"
+    );
+}
+
+#[test]
+fn backspace_at_start_of_code_block_after_paragraph_merges_it() {
+    let mut model = cm("<p>ab</p><pre><code>|cd\nef</code></pre>");
+    model.backspace();
+    assert_eq!(tx(&model), "<p>ab|cd</p><pre><code>ef</code></pre>");
+}
+
+#[test]
+fn backspace_at_start_of_code_block_at_start_of_document_can_be_undone() {
+    let mut model = cm("<pre><code>|ab\ncd</code></pre>");
+    model.backspace();
+    model.undo();
+    assert_eq!(tx(&model), "<pre><code>|ab\ncd</code></pre>");
+}
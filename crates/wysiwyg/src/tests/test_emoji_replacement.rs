@@ -12,7 +12,7 @@
 // See the License for the specific language governing permissions and
 // limitations under the License.
 
-use widestring::Utf16String;
+use crate::tests::testutils_composer_model::TestString;
 
 use crate::{
     tests::testutils_composer_model::tx, ComposerModel, MenuAction, PatternKey,
@@ -20,7 +20,7 @@ use crate::{
 
 #[test]
 fn can_do_plain_text_to_empji_replacement() {
-    let mut model: ComposerModel<Utf16String> = ComposerModel::new();
+    let mut model: ComposerModel<TestString> = ComposerModel::new();
     model.set_custom_suggestion_patterns(vec![":)".into()]);
     let update = model.replace_text("Hey That's great! :)".into());
     let MenuAction::Suggestion(suggestion) = update.menu_action else {
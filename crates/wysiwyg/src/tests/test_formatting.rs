@@ -12,13 +12,13 @@
 // See the License for the specific language governing permissions and
 // limitations under the License.
 
+use crate::tests::testutils_composer_model::TestString;
 use crate::tests::testutils_composer_model::{cm, tx};
 use crate::tests::testutils_conversion::utf16;
-use widestring::Utf16String;
 
 use crate::InlineFormatType::Bold;
 use crate::Location;
-use crate::{ComposerModel, InlineFormatType};
+use crate::{ComposerModel, ComposerUpdate, InlineFormatType};
 
 #[test]
 fn selecting_and_bolding_multiple_times() {
@@ -234,7 +234,7 @@ fn unformatting_consecutive_same_formatting_nodes_with_nested_node() {
 
 #[test]
 fn format_empty_model_applies_formatting() {
-    let mut model = ComposerModel::<Utf16String>::new();
+    let mut model = ComposerModel::<TestString>::new();
     model.bold();
     assert!(model.state.toggled_format_types.contains(&Bold));
 }
@@ -364,3 +364,145 @@ fn formatting_in_an_empty_paragraph_applies_formatting() {
     model.replace_text("B".into());
     assert_eq!(tx(&model), "<p>A</p><p><strong>B|</strong></p>");
 }
+
+#[test]
+fn format_in_formats_range_without_moving_selection() {
+    let mut model = cm("aa|bbcc");
+    model.format_in(Location::from(2), Location::from(4), Bold);
+    assert_eq!(tx(&model), "aa|<strong>bb</strong>cc");
+}
+
+#[test]
+fn format_in_accepts_backwards_range() {
+    let mut model = cm("aabbcc|");
+    model.format_in(Location::from(4), Location::from(2), Bold);
+    assert_eq!(tx(&model), "aa<strong>bb</strong>cc|");
+}
+
+#[test]
+fn format_in_does_not_remove_existing_format() {
+    let mut model = cm("<strong>aabb</strong>cc|");
+    model.format_in(Location::from(0), Location::from(2), Bold);
+    assert_eq!(tx(&model), "<strong>aabb</strong>cc|");
+}
+
+#[test]
+fn format_in_extends_partially_formatted_range() {
+    let mut model = cm("<em>aa</em>bbcc|");
+    model.format_in(Location::from(0), Location::from(4), Bold);
+    assert_eq!(
+        tx(&model),
+        "<em><strong>aa</strong></em><strong>bb</strong>cc|"
+    );
+}
+
+#[test]
+fn format_in_several_ranges_can_be_undone_one_by_one() {
+    let mut model = cm("aa bb aa|");
+    model.format_in(Location::from(0), Location::from(2), Bold);
+    model.format_in(Location::from(6), Location::from(8), Bold);
+    assert_eq!(tx(&model), "<strong>aa</strong> bb <strong>aa|</strong>");
+    model.undo();
+    assert_eq!(tx(&model), "<strong>aa</strong> bb aa|");
+}
+
+#[test]
+fn format_in_empty_range_does_nothing() {
+    let mut model = cm("aabb|");
+    model.format_in(Location::from(1), Location::from(1), Bold);
+    assert_eq!(tx(&model), "aabb|");
+}
+
+#[test]
+fn format_in_inline_code() {
+    let mut model = cm("aa<strong>bb</strong>|");
+    model.format_in(
+        Location::from(1),
+        Location::from(3),
+        InlineFormatType::InlineCode,
+    );
+    assert_eq!(tx(&model), "a<code>ab</code><strong>b|</strong>");
+}
+
+#[test]
+fn format_in_ignores_custom_tags_which_are_not_allowed() {
+    let mut model = cm("aabb|");
+    let style = InlineFormatType::Custom("style".to_owned());
+    model.format_in(Location::from(0), Location::from(2), style);
+    assert_eq!(tx(&model), "aabb|");
+    model.set_custom_inline_tags(vec!["kbd".to_owned()]);
+    let kbd = InlineFormatType::Custom("kbd".to_owned());
+    model.format_in(Location::from(0), Location::from(2), kbd);
+    assert_eq!(tx(&model), "<kbd>aa</kbd>bb|");
+}
+
+#[test]
+fn format_in_ignores_invalid_colors() {
+    let mut model = cm("aabb|");
+    let color = InlineFormatType::TextColor("\" onclick=\"x".to_owned());
+    model.format_in(Location::from(0), Location::from(2), color);
+    assert_eq!(tx(&model), "aabb|");
+}
+
+#[test]
+fn remove_format_in_removes_format_from_range_only() {
+    let mut model = cm("<strong>aabbcc</strong>|");
+    model.remove_format_in(Location::from(2), Location::from(4), Bold);
+    assert_eq!(tx(&model), "<strong>aa</strong>bb<strong>cc|</strong>");
+}
+
+#[test]
+fn remove_format_in_unformatted_range_does_nothing() {
+    let mut model = cm("aabbcc|");
+    model.remove_format_in(Location::from(2), Location::from(4), Bold);
+    assert_eq!(tx(&model), "aabbcc|");
+}
+
+#[test]
+fn apply_formats_wraps_selection_in_all_formats() {
+    let mut model = cm("aa{bb}|cc");
+    model.apply_formats(&[Bold, InlineFormatType::Italic]);
+    assert_eq!(tx(&model), "aa<strong><em>{bb}|</em></strong>cc");
+}
+
+#[test]
+fn apply_formats_skips_formats_which_are_not_allowed() {
+    let mut model = cm("aa{bb}|cc");
+    model.apply_formats(&[
+        InlineFormatType::Custom("style".to_owned()),
+        Bold,
+        InlineFormatType::Highlight("yellow".to_owned()),
+    ]);
+    assert_eq!(tx(&model), "aa<strong>{bb}|</strong>cc");
+}
+
+#[test]
+fn apply_formats_is_a_single_undo_step() {
+    let mut model = cm("aa{bb}|cc");
+    model.apply_formats(&[Bold, InlineFormatType::Italic]);
+    model.undo();
+    assert_eq!(tx(&model), "aa{bb}|cc");
+}
+
+#[test]
+fn apply_formats_keeps_formats_already_applied() {
+    let mut model = cm("aa<strong>{bb}|</strong>cc");
+    model.apply_formats(&[Bold, InlineFormatType::Italic]);
+    assert_eq!(tx(&model), "aa<strong><em>{bb}|</em></strong>cc");
+}
+
+#[test]
+fn apply_formats_that_are_all_applied_does_nothing() {
+    let mut model = cm("aa<strong>{bb}|</strong>cc");
+    let update = model.apply_formats(&[Bold, Bold]);
+    assert_eq!(update, ComposerUpdate::keep_at(model.revision()));
+    assert_eq!(tx(&model), "aa<strong>{bb}|</strong>cc");
+}
+
+#[test]
+fn apply_formats_with_cursor_applies_them_to_typed_text() {
+    let mut model = cm("aa|");
+    model.apply_formats(&[Bold, InlineFormatType::Italic]);
+    model.replace_text(utf16("bb"));
+    assert_eq!(tx(&model), "aa<strong><em>bb|</em></strong>");
+}
@@ -309,3 +309,30 @@ fn get_link_action_on_multiple_link_with_last_is_mention() {
         LinkAction::Edit("https://rust-lang.org".into()),
     );
 }
+
+#[test]
+fn get_selected_link_urls_lists_each_different_link_once() {
+    let model = cm(indoc! {r#"
+        {<a href="https://element.io">a</a>
+        <a href="https://matrix.org">b</a>
+        <a href="https://element.io">c</a>}|
+    "#});
+    assert_eq!(
+        model.get_selected_link_urls(),
+        vec![utf16("https://element.io"), utf16("https://matrix.org")],
+    );
+}
+
+#[test]
+fn get_selected_link_urls_with_cursor_inside_a_link() {
+    let model = cm("<a href=\"https://element.io\">te|st</a>");
+    assert_eq!(
+        model.get_selected_link_urls(),
+        vec![utf16("https://element.io")],
+    );
+}
+
+#[test]
+fn get_selected_link_urls_without_links_is_empty() {
+    assert!(cm("{test}|").get_selected_link_urls().is_empty());
+}
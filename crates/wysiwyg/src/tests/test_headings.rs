@@ -0,0 +1,181 @@
+// Copyright 2023 The Matrix.org Foundation C.I.C.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+#![cfg(test)]
+
+use crate::tests::testutils_composer_model::{cm, tx};
+use crate::tests::testutils_conversion::utf16;
+use crate::{ComposerAction, HeadingLevel, OutlineEntry, ToHtml};
+
+#[test]
+fn setting_a_heading_on_inline_content_wraps_it() {
+    let mut model = cm("ab|c");
+    model.set_heading(Some(HeadingLevel::H2));
+    assert_eq!(tx(&model), "<h2>ab|c</h2>");
+}
+
+#[test]
+fn setting_a_heading_on_an_empty_model_adds_one() {
+    let mut model = cm("|");
+    model.set_heading(Some(HeadingLevel::H1));
+    assert_eq!(tx(&model), "<h1>&nbsp;|</h1>");
+    model.replace_text(utf16("Title"));
+    assert_eq!(tx(&model), "<h1>Title|</h1>");
+}
+
+#[test]
+fn setting_a_heading_only_changes_the_paragraph_with_the_cursor() {
+    let mut model = cm("<p>ab</p><p>c|d</p><p>ef</p>");
+    model.set_heading(Some(HeadingLevel::H3));
+    assert_eq!(tx(&model), "<p>ab</p><h3>c|d</h3><p>ef</p>");
+}
+
+#[test]
+fn setting_a_heading_changes_every_selected_paragraph() {
+    let mut model = cm("<p>a{b</p><p>cd</p><p>e}|f</p><p>gh</p>");
+    model.set_heading(Some(HeadingLevel::H4));
+    assert_eq!(tx(&model), "<h4>a{b</h4><h4>cd</h4><h4>e}|f</h4><p>gh</p>");
+}
+
+#[test]
+fn changing_the_level_of_a_heading() {
+    let mut model = cm("<h1>a|b</h1>");
+    model.set_heading(Some(HeadingLevel::H6));
+    assert_eq!(tx(&model), "<h6>a|b</h6>");
+    model.set_heading(None);
+    assert_eq!(tx(&model), "<p>a|b</p>");
+}
+
+#[test]
+fn toggling_a_heading_adds_then_removes_it() {
+    let mut model = cm("<p>a|b</p>");
+    model.heading(HeadingLevel::H2);
+    assert_eq!(tx(&model), "<h2>a|b</h2>");
+    model.heading(HeadingLevel::H3);
+    assert_eq!(tx(&model), "<h3>a|b</h3>");
+    model.heading(HeadingLevel::H3);
+    assert_eq!(tx(&model), "<p>a|b</p>");
+}
+
+#[test]
+fn setting_a_heading_can_be_undone() {
+    let mut model = cm("<p>a|b</p>");
+    model.heading(HeadingLevel::H1);
+    model.undo();
+    assert_eq!(tx(&model), "<p>a|b</p>");
+}
+
+#[test]
+fn setting_a_heading_leaves_code_blocks_alone() {
+    let mut model = cm("<pre><code>a|b</code></pre>");
+    model.set_heading(Some(HeadingLevel::H1));
+    assert_eq!(tx(&model), "<pre><code>a|b</code></pre>");
+}
+
+#[test]
+fn heading_action_is_reversed_inside_a_heading() {
+    let mut model = cm("<p>ab|</p>");
+    assert!(model.action_is_enabled(ComposerAction::Heading));
+    assert_eq!(model.get_heading_level(), None);
+    model.heading(HeadingLevel::H2);
+    assert!(model.action_is_reversed(ComposerAction::Heading));
+    assert_eq!(model.get_heading_level(), Some(HeadingLevel::H2));
+}
+
+#[test]
+fn heading_action_is_disabled_in_a_code_block() {
+    let model = cm("<pre><code>a|b</code></pre>");
+    assert!(model.action_is_disabled(ComposerAction::Heading));
+}
+
+#[test]
+fn enter_at_the_end_of_a_heading_starts_a_paragraph() {
+    let mut model = cm("<h1>Title|</h1>");
+    model.enter();
+    assert_eq!(tx(&model), "<h1>Title</h1><p>&nbsp;|</p>");
+    model.replace_text(utf16("body"));
+    assert_eq!(tx(&model), "<h1>Title</h1><p>body|</p>");
+}
+
+#[test]
+fn enter_in_the_middle_of_a_heading_splits_it() {
+    let mut model = cm("<h2>Ti|tle</h2>");
+    model.enter();
+    assert_eq!(tx(&model), "<h2>Ti</h2><h2>|tle</h2>");
+}
+
+#[test]
+fn enter_at_the_start_of_a_heading_adds_a_paragraph_before_it() {
+    let mut model = cm("<h2>|Title</h2>");
+    model.enter();
+    assert_eq!(tx(&model), "<p>&nbsp;</p><h2>|Title</h2>");
+}
+
+#[test]
+fn headings_are_parsed_from_html() {
+    let model = cm("<h1>a</h1><p>b</p><h5>c|</h5>");
+    assert_eq!(tx(&model), "<h1>a</h1><p>b</p><h5>c|</h5>");
+}
+
+#[test]
+fn line_breaks_in_a_heading_make_headings_of_the_same_level() {
+    let mut model = cm("|");
+    model
+        .set_content_from_html(&utf16("<h3>a<br />b</h3>"))
+        .unwrap();
+    assert_eq!(tx(&model), "<h3>a</h3><h3>b|</h3>");
+}
+
+#[test]
+fn headings_in_message_html() {
+    let model = cm("<h1>Title</h1><p>body|</p>");
+    assert_eq!(model.state.dom.to_message_html(), "<h1>Title</h1>body");
+}
+
+#[test]
+fn headings_round_trip_through_markdown() {
+    let mut model = cm("<h1>Title</h1><p>body</p><h3>Sub|</h3>");
+    let markdown = model.get_content_as_markdown();
+    assert_eq!(markdown, "# Title\nbody\n### Sub");
+
+    model.set_content_from_markdown(&markdown).unwrap();
+    assert_eq!(tx(&model), "<h1>Title</h1><p>body</p><h3>Sub|</h3>");
+}
+
+#[test]
+fn outline_lists_headings_in_order() {
+    let model = cm("<h1>Title</h1><p>body</p><blockquote><h3>S<em>ub</em>|</h3></blockquote>");
+    assert_eq!(
+        model.get_outline(),
+        vec![
+            OutlineEntry {
+                level: HeadingLevel::H1,
+                text: utf16("Title"),
+                start: 0,
+                end: 5,
+            },
+            OutlineEntry {
+                level: HeadingLevel::H3,
+                text: utf16("Sub"),
+                start: 11,
+                end: 14,
+            },
+        ]
+    );
+}
+
+#[test]
+fn outline_without_headings_is_empty() {
+    assert!(cm("<p>a</p><p>b|</p>").get_outline().is_empty());
+}
@@ -0,0 +1,176 @@
+// Copyright 2023 The Matrix.org Foundation C.I.C.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+#![cfg(test)]
+
+use crate::tests::testutils_composer_model::{cm, tx};
+use crate::tests::testutils_conversion::utf16;
+use crate::{ComposerAction, ComposerModel};
+
+#[test]
+fn setting_highlight_wraps_the_selection() {
+    let mut model = cm("a{bc}|d");
+    model.set_highlight("#ffff00");
+    assert_eq!(
+        tx(&model),
+        "a<span data-mx-bg-color=\"#ffff00\">{bc}|</span>d"
+    );
+}
+
+#[test]
+fn setting_highlight_replaces_another_highlight() {
+    let mut model = cm("<span data-mx-bg-color=\"#ffff00\">a{bc}|d</span>");
+    model.set_highlight("#00ffff");
+    assert_eq!(
+        tx(&model),
+        "<span data-mx-bg-color=\"#ffff00\">a</span>\
+         <span data-mx-bg-color=\"#00ffff\">{bc}|</span>\
+         <span data-mx-bg-color=\"#ffff00\">d</span>"
+    );
+}
+
+#[test]
+fn setting_highlight_keeps_text_color() {
+    let mut model = cm("<span data-mx-color=\"#ff0000\">a{bc}|d</span>");
+    model.set_highlight("#ffff00");
+    assert_eq!(
+        tx(&model),
+        "<span data-mx-color=\"#ff0000\">a\
+         <span data-mx-bg-color=\"#ffff00\">{bc}|</span>d</span>"
+    );
+}
+
+#[test]
+fn removing_highlight_unwraps_the_selection() {
+    let mut model = cm("<span data-mx-bg-color=\"#ffff00\">a{bc}|d</span>");
+    model.remove_highlight();
+    assert_eq!(
+        tx(&model),
+        "<span data-mx-bg-color=\"#ffff00\">a</span>{bc}|\
+         <span data-mx-bg-color=\"#ffff00\">d</span>"
+    );
+}
+
+#[test]
+fn removing_highlight_keeps_text_color() {
+    let mut model = cm("<span data-mx-color=\"#ff0000\">\
+         <span data-mx-bg-color=\"#ffff00\">{ab}|</span></span>");
+    model.remove_highlight();
+    assert_eq!(tx(&model), "<span data-mx-color=\"#ff0000\">{ab}|</span>");
+}
+
+#[test]
+fn highlight_set_at_the_cursor_applies_to_typed_text() {
+    let mut model = cm("a|");
+    model.set_highlight("#ffff00");
+    model.replace_text(utf16("b"));
+    assert_eq!(tx(&model), "a<span data-mx-bg-color=\"#ffff00\">b|</span>");
+}
+
+#[test]
+fn highlight_removed_at_the_cursor_is_not_applied_to_typed_text() {
+    let mut model = cm("<span data-mx-bg-color=\"#ffff00\">a|</span>");
+    model.remove_highlight();
+    model.replace_text(utf16("b"));
+    assert_eq!(tx(&model), "<span data-mx-bg-color=\"#ffff00\">a</span>b|");
+}
+
+#[test]
+fn setting_highlight_can_be_undone() {
+    let mut model = cm("a{bc}|d");
+    model.set_highlight("#ffff00");
+    model.undo();
+    assert_eq!(tx(&model), "a{bc}|d");
+}
+
+#[test]
+fn highlight_action_is_reversed_inside_a_highlight() {
+    let model = cm("<span data-mx-bg-color=\"#ffff00\">a|b</span>");
+    assert!(model.action_is_reversed(ComposerAction::Highlight));
+    let model = cm("a|b");
+    assert!(!model.action_is_reversed(ComposerAction::Highlight));
+}
+
+#[test]
+fn highlight_action_is_not_reversed_by_text_color() {
+    let model = cm("<span data-mx-color=\"#ff0000\">a|b</span>");
+    assert!(!model.action_is_reversed(ComposerAction::Highlight));
+}
+
+#[test]
+fn highlight_action_follows_highlights_set_at_the_cursor() {
+    let mut model = cm("a|");
+    model.set_highlight("#ffff00");
+    assert!(model.action_is_reversed(ComposerAction::Highlight));
+    model.remove_highlight();
+    assert!(!model.action_is_reversed(ComposerAction::Highlight));
+}
+
+#[test]
+fn changing_highlight_at_the_cursor_keeps_the_action_reversed() {
+    let mut model = cm("<span data-mx-bg-color=\"#ffff00\">a|</span>");
+    model.set_highlight("#00ffff");
+    assert!(model.action_is_reversed(ComposerAction::Highlight));
+    model.remove_highlight();
+    assert!(!model.action_is_reversed(ComposerAction::Highlight));
+}
+
+#[test]
+fn highlight_action_is_disabled_in_inline_code() {
+    let model = cm("<code>a|b</code>");
+    assert!(model.action_is_disabled(ComposerAction::Highlight));
+}
+
+#[test]
+fn spans_with_both_colors_are_parsed_as_nested_formats() {
+    let model = cm("<span data-mx-color=\"#ff0000\" \
+         data-mx-bg-color=\"#ffff00\">a|</span>");
+    assert_eq!(
+        tx(&model),
+        "<span data-mx-color=\"#ff0000\">\
+         <span data-mx-bg-color=\"#ffff00\">a|</span></span>"
+    );
+}
+
+#[test]
+fn highlight_round_trips_through_html() {
+    let mut model = ComposerModel::new();
+    model
+        .set_content_from_html(&utf16(
+            "a<span data-mx-bg-color=\"#ffff00\">b</span>",
+        ))
+        .unwrap();
+    assert_eq!(
+        model.get_content_as_message_html(),
+        "a<span data-mx-bg-color=\"#ffff00\">b</span>"
+    );
+}
+
+#[test]
+fn highlight_is_kept_as_html_in_markdown() {
+    let model = cm("a<span data-mx-bg-color=\"#ffff00\">b</span>|");
+    assert_eq!(
+        model.get_content_as_markdown(),
+        "a<span data-mx-bg-color=\"#ffff00\">b</span>"
+    );
+}
+
+#[test]
+fn setting_highlight_ignores_invalid_colors() {
+    let mut model = cm("{a}|");
+    for color in ["yellow", "#ff0", "#ffff00\" onclick=\"x"] {
+        model.set_highlight(color);
+        assert_eq!(tx(&model), "{a}|");
+    }
+}
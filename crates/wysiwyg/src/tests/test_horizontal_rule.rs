@@ -0,0 +1,154 @@
+// Copyright 2023 The Matrix.org Foundation C.I.C.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+#![cfg(test)]
+
+use crate::tests::testutils_composer_model::{cm, tx};
+use crate::tests::testutils_conversion::utf16;
+use crate::ComposerModel;
+
+#[test]
+fn inserting_a_horizontal_rule_into_an_empty_model() {
+    let mut model = cm("|");
+    model.insert_horizontal_rule();
+    assert_eq!(tx(&model), "<hr /><p>&nbsp;|</p>");
+}
+
+#[test]
+fn inserting_a_horizontal_rule_in_the_middle_of_a_paragraph_splits_it() {
+    let mut model = cm("<p>ab|cd</p>");
+    model.insert_horizontal_rule();
+    assert_eq!(tx(&model), "<p>ab</p><hr /><p>|cd</p>");
+}
+
+#[test]
+fn inserting_a_horizontal_rule_at_the_start_of_a_paragraph() {
+    let mut model = cm("<p>|abc</p>");
+    model.insert_horizontal_rule();
+    assert_eq!(tx(&model), "<hr /><p>|abc</p>");
+}
+
+#[test]
+fn inserting_a_horizontal_rule_at_the_end_adds_a_paragraph_after_it() {
+    let mut model = cm("<p>abc|</p>");
+    model.insert_horizontal_rule();
+    assert_eq!(tx(&model), "<p>abc</p><hr /><p>&nbsp;|</p>");
+}
+
+#[test]
+fn inserting_a_horizontal_rule_replaces_the_selection() {
+    let mut model = cm("<p>a{bc}|d</p>");
+    model.insert_horizontal_rule();
+    assert_eq!(tx(&model), "<p>a</p><hr /><p>|d</p>");
+}
+
+#[test]
+fn inserting_a_horizontal_rule_can_be_undone() {
+    let mut model = cm("<p>ab|cd</p>");
+    model.insert_horizontal_rule();
+    model.undo();
+    assert_eq!(tx(&model), "<p>ab|cd</p>");
+}
+
+#[test]
+fn typing_after_an_inserted_horizontal_rule() {
+    let mut model = cm("<p>abc|</p>");
+    model.insert_horizontal_rule();
+    model.replace_text(utf16("d"));
+    assert_eq!(tx(&model), "<p>abc</p><hr /><p>d|</p>");
+}
+
+#[test]
+fn horizontal_rule_is_parsed_as_a_block() {
+    let model = cm("<p>a</p><hr><p>b|</p>");
+    assert_eq!(tx(&model), "<p>a</p><hr /><p>b|</p>");
+    assert_eq!(model.state.start, 4);
+}
+
+#[test]
+fn horizontal_rule_takes_up_one_position() {
+    let mut model = cm("<p>a</p><hr /><p>b|</p>");
+    model.select(2.into(), 2.into());
+    assert_eq!(tx(&model), "<p>a</p>|<hr /><p>b</p>");
+}
+
+#[test]
+fn horizontal_rule_round_trips_through_markdown() {
+    let model = cm("<p>a</p><hr /><p>b|</p>");
+    assert_eq!(model.get_content_as_markdown(), "a\n\n---\n\nb");
+
+    let mut model = ComposerModel::new();
+    model
+        .set_content_from_markdown(&utf16("a\n\n---\n\nb"))
+        .unwrap();
+    assert_eq!(tx(&model), "<p>a</p><hr /><p>b|</p>");
+}
+
+#[test]
+fn horizontal_rule_in_plain_text() {
+    let model = cm("<p>a</p><hr /><p>b|</p>");
+    assert_eq!(model.get_content_as_plain_text(), "a\n---\nb\n");
+}
+
+#[test]
+fn backspace_at_the_start_of_the_block_after_a_rule_removes_it() {
+    let mut model = cm("<p>a</p><hr /><p>|b</p>");
+    model.backspace();
+    assert_eq!(tx(&model), "<p>a</p><p>|b</p>");
+}
+
+#[test]
+fn backspace_on_a_rule_removes_it() {
+    let mut model = cm("<p>a</p><hr /><p>b|</p>");
+    model.select(2.into(), 2.into());
+    model.backspace();
+    assert_eq!(tx(&model), "<p>a|</p><p>b</p>");
+}
+
+#[test]
+fn delete_at_the_end_of_the_block_before_a_rule_removes_it() {
+    let mut model = cm("<p>a|</p><hr /><p>b</p>");
+    model.delete();
+    assert_eq!(tx(&model), "<p>a|</p><p>b</p>");
+}
+
+#[test]
+fn deleting_a_selection_across_a_rule_removes_it() {
+    let mut model = cm("<p>a{b</p><hr /><p>c}|d</p>");
+    model.delete();
+    assert_eq!(tx(&model), "<p>a|d</p>");
+}
+
+#[test]
+fn enter_on_a_rule_adds_a_paragraph_after_it() {
+    let mut model = cm("<p>a</p><hr /><p>b|</p>");
+    model.select(2.into(), 2.into());
+    model.enter();
+    assert_eq!(tx(&model), "<p>a</p><hr /><p>&nbsp;|</p><p>b</p>");
+}
+
+#[test]
+fn typing_on_a_rule_goes_on_a_new_line_after_it() {
+    let mut model = cm("<p>a|</p><hr />");
+    model.select(2.into(), 2.into());
+    model.replace_text(utf16("b"));
+    assert_eq!(tx(&model), "<p>a</p><hr /><p>b|</p>");
+}
+
+#[test]
+fn enter_at_the_start_of_the_block_after_a_rule() {
+    let mut model = cm("<p>a</p><hr /><p>|b</p>");
+    model.enter();
+    assert_eq!(tx(&model), "<p>a</p><hr /><p>&nbsp;</p><p>|b</p>");
+}
@@ -0,0 +1,195 @@
+// Copyright 2023 The Matrix.org Foundation C.I.C.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+#![cfg(test)]
+
+use crate::tests::testutils_composer_model::{cm, tx};
+use crate::tests::testutils_conversion::utf16;
+use crate::ComposerModel;
+
+#[test]
+fn image_round_trips_through_example_format() {
+    let model = cm("a<img src=\"mxc://a/b\" alt=\"cat\" />|b");
+    assert_eq!(tx(&model), "a<img src=\"mxc://a/b\" alt=\"cat\" />|b");
+}
+
+#[test]
+fn image_can_be_selected() {
+    let model = cm("a{<img src=\"mxc://a/b\" alt=\"cat\" />}|b");
+    assert_eq!(model.state.start, 1);
+    assert_eq!(model.state.end, 2);
+    assert_eq!(tx(&model), "a{<img src=\"mxc://a/b\" alt=\"cat\" />}|b");
+}
+
+#[test]
+fn image_attributes_are_kept_when_parsing() {
+    let model =
+        cm("<img src=\"mxc://a/b\" alt=\"cat\" width=\"32\" height=\"16\" />|");
+    assert_eq!(
+        tx(&model),
+        "<img src=\"mxc://a/b\" alt=\"cat\" width=\"32\" height=\"16\" />|"
+    );
+    assert_eq!(model.to_tree(), "\n└>img \"cat\", mxc://a/b\n");
+}
+
+#[test]
+fn image_without_src_is_dropped_when_parsing() {
+    let model = cm("<img alt=\"cat\" />a|");
+    assert_eq!(tx(&model), "a|");
+}
+
+#[test]
+fn image_with_a_src_other_than_mxc_is_dropped_when_parsing() {
+    let model = cm(
+        "<img src=\"javascript:alert(1)\" data-mx-emoticon alt=\":x:\" />\
+        <img src=\"https://example.org/cat.png\" alt=\"cat\" />a|",
+    );
+    assert_eq!(tx(&model), "a|");
+}
+
+#[test]
+fn image_attributes_are_escaped_in_html() {
+    let mut model = cm("|");
+    model.insert_image("mxc://a/\"b\"".into(), "a <cat>".into(), None, None);
+    assert_eq!(
+        tx(&model),
+        "<img src=\"mxc://a/&quot;b&quot;\" alt=\"a &lt;cat&gt;\" />|"
+    );
+}
+
+#[test]
+fn image_is_kept_in_message_html() {
+    let model = cm("a<img src=\"mxc://a/b\" alt=\"cat\" />|");
+    assert_eq!(
+        model.get_content_as_message_html(),
+        "a<img src=\"mxc://a/b\" alt=\"cat\" />"
+    );
+}
+
+#[test]
+fn image_is_its_alt_text_in_plain_text() {
+    let model = cm("a <img src=\"mxc://a/b\" alt=\"cat\" />|");
+    assert_eq!(model.get_content_as_plain_text(), "a cat");
+}
+
+#[test]
+fn image_is_written_as_markdown_image() {
+    let model = cm("a <img src=\"mxc://a/b\" alt=\"cat\" />|");
+    assert_eq!(model.get_content_as_markdown(), "a ![cat](<mxc://a/b>)");
+}
+
+#[test]
+fn sized_image_is_written_as_html_in_markdown() {
+    let model = cm("<img src=\"mxc://a/b\" alt=\"cat\" width=\"32\" />|");
+    assert_eq!(
+        model.get_content_as_markdown(),
+        "<img src=\"mxc://a/b\" alt=\"cat\" width=\"32\" />"
+    );
+}
+
+#[test]
+fn markdown_image_is_parsed_into_an_image() {
+    let mut model = ComposerModel::new();
+    model
+        .set_content_from_markdown(&utf16("a ![cat](mxc://a/b)"))
+        .unwrap();
+    assert_eq!(tx(&model), "a <img src=\"mxc://a/b\" alt=\"cat\" />|");
+}
+
+#[test]
+fn inserting_an_image_puts_the_cursor_after_it() {
+    let mut model = cm("a|b");
+    model.insert_image("mxc://a/b".into(), "cat".into(), Some(32), None);
+    assert_eq!(
+        tx(&model),
+        "a<img src=\"mxc://a/b\" alt=\"cat\" width=\"32\" />|b"
+    );
+}
+
+#[test]
+fn inserting_an_image_replaces_the_selection() {
+    let mut model = cm("a{bc}|d");
+    model.insert_image("mxc://a/b".into(), "cat".into(), None, None);
+    assert_eq!(tx(&model), "a<img src=\"mxc://a/b\" alt=\"cat\" />|d");
+}
+
+#[test]
+fn inserting_an_image_into_formatting_keeps_it_inside() {
+    let mut model = cm("<strong>a|b</strong>");
+    model.insert_image("mxc://a/b".into(), "cat".into(), None, None);
+    assert_eq!(
+        tx(&model),
+        "<strong>a<img src=\"mxc://a/b\" alt=\"cat\" />|b</strong>"
+    );
+}
+
+#[test]
+fn inserting_an_image_in_inline_code_does_nothing() {
+    let mut model = cm("<code>a|b</code>");
+    model.insert_image("mxc://a/b".into(), "cat".into(), None, None);
+    assert_eq!(tx(&model), "<code>a|b</code>");
+}
+
+#[test]
+fn inserting_an_image_in_a_code_block_does_nothing() {
+    let mut model = cm("<pre><code>a|b</code></pre>");
+    model.insert_image("mxc://a/b".into(), "cat".into(), None, None);
+    assert_eq!(tx(&model), "<pre><code>a|b</code></pre>");
+}
+
+#[test]
+fn inserting_an_image_from_anywhere_but_mxc_does_nothing() {
+    let mut model = cm("a|");
+    model.insert_image("https://a/b.png".into(), "cat".into(), None, None);
+    model.insert_image("mxc://".into(), "cat".into(), None, None);
+    assert_eq!(tx(&model), "a|");
+}
+
+#[test]
+fn inserting_an_image_can_be_undone() {
+    let mut model = cm("a|");
+    model.insert_image("mxc://a/b".into(), "cat".into(), None, None);
+    model.undo();
+    assert_eq!(tx(&model), "a|");
+}
+
+#[test]
+fn backspace_removes_a_whole_image() {
+    let mut model = cm("a<img src=\"mxc://a/b\" alt=\"cat\" />|b");
+    model.backspace();
+    assert_eq!(tx(&model), "a|b");
+}
+
+#[test]
+fn delete_removes_a_whole_image() {
+    let mut model = cm("a|<img src=\"mxc://a/b\" alt=\"cat\" />b");
+    model.delete();
+    assert_eq!(tx(&model), "a|b");
+}
+
+#[test]
+fn typing_after_an_image_adds_text() {
+    let mut model = cm("<img src=\"mxc://a/b\" alt=\"cat\" />|");
+    model.replace_text("a".into());
+    assert_eq!(tx(&model), "<img src=\"mxc://a/b\" alt=\"cat\" />a|");
+}
+
+#[test]
+fn image_is_kept_in_a_paragraph() {
+    let model = cm("<p>a</p><p><img src=\"mxc://a/b\" alt=\"cat\" />|</p>");
+    assert_eq!(
+        tx(&model),
+        "<p>a</p><p><img src=\"mxc://a/b\" alt=\"cat\" />|</p>"
+    );
+}
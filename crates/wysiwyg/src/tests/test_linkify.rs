@@ -0,0 +1,269 @@
+// Copyright 2023 The Matrix.org Foundation C.I.C.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use crate::tests::testutils_composer_model::{cm, tx};
+
+#[test]
+fn linkify_url_in_plain_text() {
+    let mut model = cm("Go to https://matrix.org now|");
+    model.linkify_content();
+    assert_eq!(
+        tx(&model),
+        "Go to <a href=\"https://matrix.org\">https://matrix.org</a> now|"
+    );
+}
+
+#[test]
+fn linkify_url_starting_with_www_adds_scheme() {
+    let mut model = cm("www.matrix.org|");
+    model.linkify_content();
+    assert_eq!(
+        tx(&model),
+        "<a href=\"https://www.matrix.org\">www.matrix.org|</a>"
+    );
+}
+
+#[test]
+fn linkify_email_address() {
+    let mut model = cm("Mail test@matrix.org|");
+    model.linkify_content();
+    assert_eq!(
+        tx(&model),
+        "Mail <a href=\"mailto:test@matrix.org\">test@matrix.org|</a>"
+    );
+}
+
+#[test]
+fn linkify_does_not_include_surrounding_punctuation() {
+    let mut model = cm("See (https://matrix.org/docs). Thanks|");
+    model.linkify_content();
+    assert_eq!(
+        tx(&model),
+        "See (<a href=\"https://matrix.org/docs\">https://matrix.org/docs</a>). Thanks|"
+    );
+}
+
+#[test]
+fn linkify_multiple_urls_in_different_paragraphs() {
+    let mut model =
+        cm("<p>a https://matrix.org b</p><p>c www.element.io d|</p>");
+    model.linkify_content();
+    assert_eq!(
+        tx(&model),
+        "<p>a <a href=\"https://matrix.org\">https://matrix.org</a> b</p>\
+        <p>c <a href=\"https://www.element.io\">www.element.io</a> d|</p>"
+    );
+}
+
+#[test]
+fn linkify_url_inside_formatting() {
+    let mut model = cm("<strong>a https://matrix.org b|</strong>");
+    model.linkify_content();
+    assert_eq!(
+        tx(&model),
+        "<strong>a <a href=\"https://matrix.org\">https://matrix.org</a> b|</strong>"
+    );
+}
+
+#[test]
+fn linkify_skips_code_blocks() {
+    let mut model = cm("<pre><code>https://matrix.org|</code></pre>");
+    model.linkify_content();
+    assert_eq!(tx(&model), "<pre><code>https://matrix.org|</code></pre>");
+}
+
+#[test]
+fn linkify_skips_inline_code() {
+    let mut model = cm("<code>https://matrix.org</code> test@matrix.org|");
+    model.linkify_content();
+    assert_eq!(
+        tx(&model),
+        "<code>https://matrix.org</code> <a href=\"mailto:test@matrix.org\">test@matrix.org|</a>"
+    );
+}
+
+#[test]
+fn linkify_skips_existing_links() {
+    let mut model =
+        cm("<a href=\"https://element.io\">https://matrix.org</a> text|");
+    model.linkify_content();
+    assert_eq!(
+        tx(&model),
+        "<a href=\"https://element.io\">https://matrix.org</a> text|"
+    );
+}
+
+#[test]
+fn linkify_ignores_words_that_are_not_links() {
+    let mut model = cm("user@localhost http:// www. hello|");
+    model.linkify_content();
+    assert_eq!(tx(&model), "user@localhost http:// www. hello|");
+}
+
+#[test]
+fn linkify_can_be_undone() {
+    let mut model = cm("https://matrix.org|");
+    model.linkify_content();
+    model.undo();
+    assert_eq!(tx(&model), "https://matrix.org|");
+}
+
+#[test]
+fn linkify_with_nothing_to_link_does_not_add_undo_state() {
+    let mut model = cm("abc|");
+    model.linkify_content();
+    assert!(model.previous_states.is_empty());
+}
+
+#[test]
+fn linkify_skips_emails_when_disabled() {
+    let mut model = cm("test@matrix.org https://matrix.org|");
+    model.set_link_emails(false);
+    model.linkify_content();
+    assert_eq!(
+        tx(&model),
+        "test@matrix.org <a href=\"https://matrix.org\">https://matrix.org|</a>"
+    );
+}
+
+#[test]
+fn typing_space_after_url_does_not_link_by_default() {
+    let mut model = cm("https://matrix.org|");
+    model.replace_text(" ".into());
+    assert_eq!(tx(&model), "https://matrix.org&nbsp;|");
+}
+
+#[test]
+fn typing_space_after_url_links_it() {
+    let mut model = cm("Go to https://matrix.org|");
+    model.set_link_as_you_type(true);
+    model.replace_text(" ".into());
+    assert_eq!(
+        tx(&model),
+        "Go to <a href=\"https://matrix.org\">https://matrix.org</a>&nbsp;|"
+    );
+}
+
+#[test]
+fn typing_space_after_email_links_it() {
+    let mut model = cm("test@matrix.org|");
+    model.set_link_as_you_type(true);
+    model.replace_text(" ".into());
+    assert_eq!(
+        tx(&model),
+        "<a href=\"mailto:test@matrix.org\">test@matrix.org</a>&nbsp;|"
+    );
+}
+
+#[test]
+fn typing_space_after_email_does_not_link_it_when_disabled() {
+    let mut model = cm("test@matrix.org|");
+    model.set_link_as_you_type(true);
+    model.set_link_emails(false);
+    model.replace_text(" ".into());
+    assert_eq!(tx(&model), "test@matrix.org&nbsp;|");
+}
+
+#[test]
+fn typing_space_after_url_with_punctuation_links_only_the_url() {
+    let mut model = cm("(www.matrix.org).|");
+    model.set_link_as_you_type(true);
+    model.replace_text_in(" ".into(), 17, 17);
+    assert_eq!(
+        tx(&model),
+        "(<a href=\"https://www.matrix.org\">www.matrix.org</a>).&nbsp;|"
+    );
+}
+
+#[test]
+fn typing_space_after_other_word_does_not_link_earlier_url() {
+    let mut model = cm("https://matrix.org abc|");
+    model.set_link_as_you_type(true);
+    model.replace_text(" ".into());
+    assert_eq!(tx(&model), "https://matrix.org abc&nbsp;|");
+}
+
+#[test]
+fn typing_space_after_url_in_code_does_not_link_it() {
+    let mut model = cm("<code>https://matrix.org|</code>");
+    model.set_link_as_you_type(true);
+    model.replace_text(" ".into());
+    assert_eq!(tx(&model), "<code>https://matrix.org&nbsp;|</code>");
+}
+
+#[test]
+fn typing_letters_after_url_does_not_link_it() {
+    let mut model = cm("https://matrix.org|");
+    model.set_link_as_you_type(true);
+    model.replace_text("/a".into());
+    assert_eq!(tx(&model), "https://matrix.org/a|");
+}
+
+#[test]
+fn undo_after_link_as_you_type_only_removes_the_link() {
+    let mut model = cm("Go to https://matrix.org|");
+    model.set_link_as_you_type(true);
+    model.replace_text(" ".into());
+    model.undo();
+    assert_eq!(tx(&model), "Go to https://matrix.org&nbsp;|");
+    model.undo();
+    assert_eq!(tx(&model), "Go to https://matrix.org|");
+}
+
+#[test]
+fn redo_after_undoing_link_as_you_type_links_again() {
+    let mut model = cm("Go to https://matrix.org|");
+    model.set_link_as_you_type(true);
+    model.replace_text(" ".into());
+    model.undo();
+    model.redo();
+    assert_eq!(
+        tx(&model),
+        "Go to <a href=\"https://matrix.org\">https://matrix.org</a>&nbsp;|"
+    );
+}
+
+#[test]
+fn backspace_after_link_as_you_type_only_removes_the_link() {
+    let mut model = cm("Go to https://matrix.org|");
+    model.set_link_as_you_type(true);
+    model.replace_text(" ".into());
+    model.backspace();
+    assert_eq!(tx(&model), "Go to https://matrix.org&nbsp;|");
+    model.backspace();
+    assert_eq!(tx(&model), "Go to https://matrix.org|");
+}
+
+#[test]
+fn backspace_after_typing_more_does_not_remove_the_link() {
+    let mut model = cm("Go to https://matrix.org|");
+    model.set_link_as_you_type(true);
+    model.replace_text(" ".into());
+    model.replace_text("a".into());
+    model.backspace();
+    assert_eq!(
+        tx(&model),
+        "Go to <a href=\"https://matrix.org\">https://matrix.org</a>&nbsp;|"
+    );
+}
+
+#[test]
+fn undo_after_moving_the_cursor_undoes_the_typing_too() {
+    let mut model = cm("Go to https://matrix.org|");
+    model.set_link_as_you_type(true);
+    model.replace_text(" ".into());
+    model.select(0.into(), 0.into());
+    model.undo();
+    assert_eq!(tx(&model), "Go to https://matrix.org|");
+}
@@ -14,6 +14,7 @@
 
 use crate::tests::testutils_composer_model::{cm, tx};
 use crate::tests::testutils_conversion::utf16;
+use crate::Location;
 
 #[test]
 fn set_link_to_empty_selection_at_end_of_alink() {
@@ -943,3 +944,114 @@ fn set_links_in_list_then_add_list_item() {
         "<ul><li><a href=\"https://matrix.org\">test</a></li><li>|</li></ul>"
     );
 }
+
+#[test]
+fn typing_in_one_of_two_adjacent_links_to_the_same_url_merges_them() {
+    let mut model = cm(
+        "<a href=\"https://matrix.org\">ab</a><a href=\"https://matrix.org\">c|d</a>",
+    );
+    model.replace_text(utf16("z"));
+    assert_eq!(tx(&model), "<a href=\"https://matrix.org\">abcz|d</a>");
+}
+
+#[test]
+fn deleting_formatted_text_between_links_to_the_same_url_merges_them() {
+    let mut model = cm(
+        "<a href=\"https://matrix.org\">ab</a><b>c|</b><a href=\"https://matrix.org\">ef</a>",
+    );
+    model.backspace();
+    assert_eq!(tx(&model), "<a href=\"https://matrix.org\">ab|ef</a>");
+    model.replace_text(utf16("x"));
+    assert_eq!(tx(&model), "<a href=\"https://matrix.org\">abx|ef</a>");
+}
+
+#[test]
+fn deleting_text_between_formatted_links_to_the_same_url_merges_them() {
+    let mut model = cm(
+        "<b><a href=\"https://matrix.org\">ab</a></b>c|<b><a href=\"https://matrix.org\">ef</a></b>",
+    );
+    model.backspace();
+    assert_eq!(
+        tx(&model),
+        "<b><a href=\"https://matrix.org\">ab|ef</a></b>"
+    );
+}
+
+#[test]
+fn adjacent_links_to_different_urls_are_not_merged() {
+    let mut model = cm(
+        "<a href=\"https://matrix.org\">ab</a><a href=\"https://element.io\">c|d</a>",
+    );
+    model.replace_text(utf16("z"));
+    assert_eq!(
+        tx(&model),
+        "<a href=\"https://matrix.org\">ab</a><a href=\"https://element.io\">cz|d</a>"
+    );
+}
+
+#[test]
+fn editing_a_merged_link_changes_all_of_it() {
+    let mut model = cm(
+        "<a href=\"https://matrix.org\">ab</a>c|<a href=\"https://matrix.org\">ef</a>",
+    );
+    model.backspace();
+    model.set_link(utf16("https://element.io"), vec![]);
+    assert_eq!(tx(&model), "<a href=\"https://element.io\">ab|ef</a>");
+}
+
+#[test]
+fn typing_at_the_end_of_a_link_can_extend_it() {
+    let mut model = cm("<a href=\"https://matrix.org\">link|</a> text");
+    model.set_extend_links_at_edges(true);
+    model.replace_text(utf16("s"));
+    assert_eq!(tx(&model), "<a href=\"https://matrix.org\">links|</a> text");
+}
+
+#[test]
+fn typing_at_the_end_of_a_formatted_link_can_extend_it() {
+    let mut model =
+        cm("<b><a href=\"https://matrix.org\">li<i>nk|</i></a></b>");
+    model.set_extend_links_at_edges(true);
+    model.replace_text(utf16("s"));
+    assert_eq!(
+        tx(&model),
+        "<b><a href=\"https://matrix.org\">li<i>nks|</i></a></b>"
+    );
+}
+
+#[test]
+fn typing_at_the_start_of_a_block_starting_with_a_link_can_extend_it() {
+    let mut model =
+        cm("<p>a</p><p><a href=\"https://matrix.org\">|link</a></p>");
+    model.set_extend_links_at_edges(true);
+    model.replace_text(utf16("the "));
+    assert_eq!(
+        tx(&model),
+        "<p>a</p><p><a href=\"https://matrix.org\">the |link</a></p>"
+    );
+}
+
+#[test]
+fn typing_after_text_before_a_link_does_not_extend_it() {
+    let mut model = cm("a|<a href=\"https://matrix.org\">link</a>");
+    model.set_extend_links_at_edges(true);
+    model.replace_text(utf16("b"));
+    assert_eq!(tx(&model), "ab|<a href=\"https://matrix.org\">link</a>");
+}
+
+#[test]
+fn typing_over_a_selection_at_the_end_of_a_link_does_not_extend_it() {
+    let mut model = cm("<a href=\"https://matrix.org\">li{nk}|</a>");
+    model.set_extend_links_at_edges(true);
+    model.replace_text(utf16("Z"));
+    assert_eq!(tx(&model), "<a href=\"https://matrix.org\">li</a>Z|");
+}
+
+#[test]
+fn typing_at_the_edges_of_a_link_escapes_it_by_default() {
+    let mut model = cm("<a href=\"https://matrix.org\">|link</a>");
+    model.replace_text(utf16("a"));
+    model.select(Location::from(5), Location::from(5));
+    model.replace_text(utf16("b"));
+    assert_eq!(tx(&model), "a<a href=\"https://matrix.org\">link</a>b|");
+}
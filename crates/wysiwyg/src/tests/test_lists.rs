@@ -12,12 +12,12 @@
 // See the License for the specific language governing permissions and
 // limitations under the License.
 
-use widestring::Utf16String;
+use crate::tests::testutils_composer_model::TestString;
 
 use crate::tests::testutils_composer_model::{cm, tx};
 use crate::tests::testutils_conversion::utf16;
 
-use crate::ComposerModel;
+use crate::{ComposerModel, ListIndentation, ListType};
 
 #[test]
 fn creating_ordered_list_and_writing() {
@@ -326,7 +326,7 @@ fn creating_a_new_list_immediately_after_an_old_one_joins_them() {
     model.unordered_list();
     model.enter();
     model.enter();
-    model.replace_text(Utf16String::from_str("def"));
+    model.replace_text(TestString::from_str("def"));
     model.unordered_list();
     assert_eq!(tx(&model), "<ul><li>abc</li><li>def|</li></ul>");
 }
@@ -427,6 +427,38 @@ fn unindent_nested_lists_with_remnants_works() {
     )
 }
 
+#[test]
+fn list_indentation_outside_a_list_has_no_depth() {
+    let model = cm("abc|");
+    assert_eq!(model.get_list_indentation(), ListIndentation::default());
+}
+
+#[test]
+fn list_indentation_of_first_top_level_item_cannot_change() {
+    let model = cm("<ul><li>a|</li><li>b</li></ul>");
+    assert_eq!(
+        model.get_list_indentation(),
+        ListIndentation {
+            can_indent: false,
+            can_unindent: false,
+            depth: 1,
+        }
+    );
+}
+
+#[test]
+fn list_indentation_of_nested_item_counts_its_lists() {
+    let model = cm("<ul><li><p>a</p><ul><li>b</li><li>c|</li></ul></li></ul>");
+    assert_eq!(
+        model.get_list_indentation(),
+        ListIndentation {
+            can_indent: true,
+            can_unindent: true,
+            depth: 2,
+        }
+    );
+}
+
 #[test]
 fn replacing_text_with_newline_characters_inserts_list_items() {
     let mut model = cm("<ul><li>abc|</li></ul>");
@@ -603,6 +635,57 @@ fn backspacing_an_indented_list_item_with_siblings_doesnt_remove_parent_list_ite
     );
 }
 
-fn replace_text(model: &mut ComposerModel<Utf16String>, new_text: &str) {
+#[test]
+fn changing_the_type_of_a_nested_list_converts_the_whole_list() {
+    let mut model =
+        cm("<ul><li><p>a</p><ul><li>b|</li></ul></li><li>c</li></ul><p>d</p>");
+    model.ordered_list();
+    assert_eq!(
+        tx(&model),
+        "<ol><li><p>a</p><ol><li>b|</li></ol></li><li>c</li></ol><p>d</p>"
+    );
+}
+
+#[test]
+fn changing_the_type_of_a_list_converts_its_sublists() {
+    let mut model = cm("<ul><li><p>{a</p><ul><li>b}|</li></ul></li></ul>");
+    model.ordered_list();
+    assert_eq!(
+        tx(&model),
+        "<ol><li><p>{a</p><ol><li>b}|</li></ol></li></ol>"
+    );
+    model.undo();
+    assert_eq!(
+        tx(&model),
+        "<ul><li><p>{a</p><ul><li>b}|</li></ul></li></ul>"
+    );
+}
+
+#[test]
+fn toggling_the_type_of_a_nested_list_still_removes_the_item() {
+    let mut model = cm("<ul><li><p>a</p><ol><li>b|</li></ol></li></ul>");
+    model.ordered_list();
+    assert_eq!(tx(&model), "<ul><li><p>a</p><p>b|</p></li></ul>");
+}
+
+#[test]
+fn convert_list_type_keeps_the_list_when_it_has_the_type() {
+    let mut model = cm("<ol><li>a</li><li>b|</li></ol>");
+    model.convert_list_type(ListType::Ordered);
+    assert_eq!(tx(&model), "<ol><li>a</li><li>b|</li></ol>");
+    assert_eq!(model.undo_depth(), 0);
+    model.convert_list_type(ListType::Unordered);
+    assert_eq!(tx(&model), "<ul><li>a</li><li>b|</li></ul>");
+}
+
+#[test]
+fn convert_list_type_outside_a_list_does_nothing() {
+    let mut model = cm("<p>a|</p>");
+    model.convert_list_type(ListType::Ordered);
+    assert_eq!(tx(&model), "<p>a|</p>");
+    assert_eq!(model.undo_depth(), 0);
+}
+
+fn replace_text(model: &mut ComposerModel<TestString>, new_text: &str) {
     model.replace_text(utf16(new_text));
 }
@@ -13,6 +13,7 @@
 // limitations under the License.
 
 use crate::tests::testutils_composer_model::{cm, tx};
+use crate::tests::testutils_snapshot::assert_snapshot;
 
 // This file defines specific tests on how lists behave in combination to
 // some other block nodes such as quote and code blocks. At some point, some
@@ -83,3 +84,79 @@ fn create_list_with_selected_paragraph_quote_and_code_block() {
         "<ol><li>{quote</li><li>more quote</li><li>text</li><li>code</li><li>more code}|</li></ol>"
     )
 }
+
+#[test]
+fn create_list_inside_quote_keeps_the_paragraph_after_it() {
+    let mut model =
+        cm("<blockquote><p>{a</p><p>b</p><p>c}|</p><p>d</p></blockquote>");
+    model.ordered_list();
+    assert_snapshot!(model, @r#"
+        html:
+        <blockquote>
+          <ol>
+            <li>{a</li>
+            <li>b</li>
+            <li>c}|</li>
+          </ol>
+          <p>d</p>
+        </blockquote>
+        tree:
+        └>blockquote
+          ├>ol
+          │ ├>li
+          │ │ └>"a"
+          │ ├>li
+          │ │ └>"b"
+          │ └>li
+          │   └>"c"
+          └>p
+            └>"d"
+        selection: 0..5
+    "#);
+}
+
+#[test]
+fn add_code_block_to_nested_list_item() {
+    let mut model = cm("<ul><li><p>a</p><ul><li>b|</li></ul></li></ul>");
+    model.code_block();
+    model.enter();
+    assert_snapshot!(model, @r#"
+        html:
+        <ul>
+          <li>
+            <p>a</p>
+            <ul>
+              <li>
+                <pre><code>b\n&nbsp;|</code></pre>
+              </li>
+            </ul>
+          </li>
+        </ul>
+        tree:
+        └>ul
+          └>li
+            ├>p
+            │ └>"a"
+            └>ul
+              └>li
+                └>codeblock
+                  ├>p
+                  │ └>"b"
+                  └>p
+        selection: 4..4
+    "#);
+}
+
+#[test]
+#[should_panic(expected = "- selection: 0..0")]
+fn snapshot_mismatch_shows_the_differing_lines() {
+    let model = cm("a|");
+    assert_snapshot!(model, @r#"
+        html:
+        <p>a|</p>
+        tree:
+        └>p
+          └>"a"
+        selection: 0..0
+    "#);
+}
@@ -0,0 +1,187 @@
+// Copyright 2023 The Matrix.org Foundation C.I.C.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+#![cfg(test)]
+
+use crate::tests::testutils_composer_model::{cm, tx};
+use crate::tests::testutils_conversion::utf16;
+use crate::ComposerModel;
+use crate::{ToHtml, ToMarkdown};
+
+#[test]
+fn inserting_inline_math() {
+    let mut model = cm("a|");
+    model.insert_math(utf16("x^2"), false);
+    assert_eq!(
+        tx(&model),
+        "a<span data-mx-maths=\"x^2|\"><code>x^2</code></span>"
+    );
+}
+
+#[test]
+fn text_typed_after_inline_math_goes_after_it() {
+    let mut model = cm("a|");
+    model.insert_math(utf16("x^2"), false);
+    model.replace_text(utf16("b"));
+    assert_eq!(
+        tx(&model),
+        "a<span data-mx-maths=\"x^2\"><code>x^2</code></span>b|"
+    );
+}
+
+#[test]
+fn inserting_inline_math_replaces_the_selection() {
+    let mut model = cm("a{bc}|d");
+    model.insert_math(utf16("\\pi"), false);
+    model.replace_text(utf16("!"));
+    assert_eq!(
+        tx(&model),
+        "a<span data-mx-maths=\"\\pi\"><code>\\pi</code></span>!|d"
+    );
+}
+
+#[test]
+fn inserting_block_math_in_the_middle_of_a_paragraph_splits_it() {
+    let mut model = cm("<p>ab|cd</p>");
+    model.insert_math(utf16("E = mc^2"), true);
+    assert_eq!(
+        tx(&model),
+        "<p>ab</p><div data-mx-maths=\"E = mc^2\"><code>E = mc^2</code></div><p>|cd</p>"
+    );
+}
+
+#[test]
+fn inserting_block_math_at_the_end_adds_a_paragraph_after_it() {
+    let mut model = cm("<p>ab|</p>");
+    model.insert_math(utf16("x"), true);
+    assert_eq!(
+        tx(&model),
+        "<p>ab</p><div data-mx-maths=\"x\"><code>x</code></div><p>&nbsp;|</p>"
+    );
+}
+
+#[test]
+fn math_is_not_inserted_into_code_or_when_empty() {
+    let mut model = cm("<code>a|</code>");
+    model.insert_math(utf16("x"), false);
+    assert_eq!(tx(&model), "<code>a|</code>");
+
+    let mut model = cm("a|");
+    model.insert_math(utf16(""), false);
+    assert_eq!(tx(&model), "a|");
+}
+
+#[test]
+fn inserting_math_can_be_undone() {
+    let mut model = cm("a|");
+    model.insert_math(utf16("x"), false);
+    model.undo();
+    assert_eq!(tx(&model), "a|");
+}
+
+#[test]
+fn latex_special_characters_are_escaped_in_the_attribute() {
+    let mut model = cm("|");
+    model.insert_math(utf16("a < \"b\" & c"), false);
+    assert_eq!(
+        model.get_content_as_html(),
+        "<span data-mx-maths=\"a &lt; &quot;b&quot; &amp; c\"><code>a &lt; \"b\" &amp; c</code></span>"
+    );
+}
+
+#[test]
+fn math_round_trips_through_html() {
+    let html = "<p>a <span data-mx-maths=\"x^2\"><code>x^2</code></span> b</p>\
+        <div data-mx-maths=\"\\sum_i i\"><code>\\sum_i i</code></div>";
+    let mut model = ComposerModel::new();
+    model.set_content_from_html(&utf16(html)).unwrap();
+    assert_eq!(model.get_content_as_html(), html);
+}
+
+#[test]
+fn the_fallback_of_parsed_math_is_replaced_by_its_source() {
+    let mut model = ComposerModel::new();
+    model
+        .set_content_from_html(&utf16(
+            "<span data-mx-maths=\"x^2\"><code>x squared</code></span>",
+        ))
+        .unwrap();
+    assert_eq!(
+        model.get_content_as_html(),
+        "<span data-mx-maths=\"x^2\"><code>x^2</code></span>"
+    );
+}
+
+#[test]
+fn editing_the_source_of_math_changes_its_attribute() {
+    let mut model = cm("<span data-mx-maths=\"x^|2\"><code>x^2</code></span>");
+    model.backspace();
+    model.replace_text(utf16("_"));
+    assert_eq!(
+        model.state.dom.to_html(),
+        "<span data-mx-maths=\"x_2\"><code>x_2</code></span>"
+    );
+}
+
+#[test]
+fn math_is_written_as_html_in_markdown() {
+    let model = cm("a <span data-mx-maths=\"x\"><code>x</code></span>|");
+    assert_eq!(
+        model.state.dom.to_markdown().unwrap(),
+        "a <span data-mx-maths=\"x\"><code>x</code></span>"
+    );
+}
+
+#[test]
+fn math_is_its_source_in_plain_text() {
+    let model = cm("a <span data-mx-maths=\"x^2\"><code>x^2</code></span>|");
+    assert_eq!(model.get_content_as_plain_text(), "a x^2");
+}
+
+#[test]
+fn math_is_not_split_by_enter_or_a_line_break() {
+    let mut model = cm("a<span data-mx-maths=\"x|^2\"><code>x^2</code></span>");
+    model.enter();
+    model.insert_line_break();
+    assert_eq!(
+        tx(&model),
+        "a<span data-mx-maths=\"x|^2\"><code>x^2</code></span>"
+    );
+
+    let mut model = cm("<div data-mx-maths=\"x|^2\"><code>x^2</code></div>");
+    model.enter();
+    assert_eq!(
+        tx(&model),
+        "<div data-mx-maths=\"x|^2\"><code>x^2</code></div>"
+    );
+}
+
+#[test]
+fn formatting_leaves_math_alone() {
+    let mut model = cm("{a<span data-mx-maths=\"x\"><code>x</code></span>}|");
+    model.bold();
+    assert_eq!(
+        tx(&model),
+        "<strong>{a</strong><span data-mx-maths=\"x}|\"><code>x</code></span>"
+    );
+
+    let mut model =
+        cm("<span data-mx-maths=\"{x^}|2\"><code>x^2</code></span>");
+    model.bold();
+    model.inline_code();
+    assert_eq!(
+        tx(&model),
+        "<span data-mx-maths=\"{x^}|2\"><code>x^2</code></span>"
+    );
+}
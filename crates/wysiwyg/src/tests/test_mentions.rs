@@ -12,11 +12,11 @@
 // See the License for the specific language governing permissions and
 // limitations under the License.
 
-use widestring::Utf16String;
+use crate::tests::testutils_composer_model::TestString;
 
 use crate::{
     tests::testutils_composer_model::{cm, tx},
-    ComposerModel, MentionsState, MenuAction,
+    ComposerModel, Location, MentionsState, MenuAction,
 };
 /**
  * INSERTING WITH PARSING
@@ -227,7 +227,7 @@ fn formatting_node_replace_all() {
     );
     assert_eq!(
         tx(&model),
-        "<a data-mention-type=\"user\" href=\"https://matrix.to/#/@alice:matrix.org\" contenteditable=\"false\">Alice</a>&nbsp;|",
+        "<strong><a data-mention-type=\"user\" href=\"https://matrix.to/#/@alice:matrix.org\" contenteditable=\"false\">Alice</a>&nbsp;|</strong>",
     );
 }
 
@@ -261,6 +261,60 @@ fn formatting_node_replace_end() {
     );
 }
 
+#[test]
+fn nested_formatting_node_replace_all() {
+    let mut model = cm("<strong><em>|</em></strong>");
+    insert_mention_at_cursor(&mut model);
+    assert_eq!(
+        tx(&model),
+        "<strong><em><a data-mention-type=\"user\" href=\"https://matrix.to/#/@alice:matrix.org\" contenteditable=\"false\">Alice</a>&nbsp;|</em></strong>",
+    );
+}
+
+#[test]
+fn formatting_node_replace_all_with_text_around() {
+    let mut model = cm("Hey <em>@alic|</em> there");
+    insert_mention_at_suggestion(&mut model);
+    assert_eq!(
+        tx(&model),
+        "Hey <em><a data-mention-type=\"user\" href=\"https://matrix.to/#/@alice:matrix.org\" contenteditable=\"false\">Alice</a>|</em> there",
+    );
+}
+
+#[test]
+fn formatting_node_before_suggestion_is_not_extended() {
+    let mut model = cm("<em>Hey </em>@alic|");
+    insert_mention_at_suggestion(&mut model);
+    assert_eq!(
+        tx(&model),
+        "<em>Hey&nbsp;</em><a data-mention-type=\"user\" href=\"https://matrix.to/#/@alice:matrix.org\" contenteditable=\"false\">Alice</a>&nbsp;|",
+    );
+}
+
+#[test]
+fn formatting_node_after_suggestion_is_not_extended() {
+    let mut model = cm("@alic|<em> there</em>");
+    insert_mention_at_suggestion(&mut model);
+    assert_eq!(
+        tx(&model),
+        "<a data-mention-type=\"user\" href=\"https://matrix.to/#/@alice:matrix.org\" contenteditable=\"false\">Alice</a>|<em>&nbsp;there</em>",
+    );
+}
+
+#[test]
+fn at_room_formatting_node_replace_all() {
+    let mut model = cm("<em>|</em>");
+    let update = model.replace_text("@roo".into());
+    let MenuAction::Suggestion(suggestion) = update.menu_action else {
+        panic!("No suggestion pattern found")
+    };
+    model.insert_at_room_mention_at_suggestion(suggestion, vec![]);
+    assert_eq!(
+        tx(&model),
+        "<em><a data-mention-type=\"at-room\" href=\"#\" contenteditable=\"false\">@room</a>&nbsp;|</em>",
+    );
+}
+
 #[test]
 #[should_panic]
 fn formatting_node_inline_code() {
@@ -344,6 +398,16 @@ fn list_item_replace_end() {
     );
 }
 
+#[test]
+fn formatting_node_in_list_item_replace_all() {
+    let mut model = cm("<ol><li>hello</li><li><em>|</em></li></ol>");
+    insert_mention_at_cursor(&mut model);
+    assert_eq!(
+        tx(&model),
+        "<ol><li>hello</li><li><em><a data-mention-type=\"user\" href=\"https://matrix.to/#/@alice:matrix.org\" contenteditable=\"false\">Alice</a>&nbsp;|</em></li></ol>",
+    );
+}
+
 /**
  * CodeBlock
  */
@@ -711,10 +775,134 @@ fn get_mentions_state_for_multiple_mentions() {
     assert_eq!(model.get_mentions_state(), state)
 }
 
+#[test]
+fn get_mentions_state_follows_edits_and_undo() {
+    let mut model = cm("|");
+    model.insert_mention(
+        "https://matrix.to/#/!room:matrix.org".into(),
+        "Room".into(),
+        vec![],
+    );
+    model.insert_at_room_mention(vec![]);
+    let mut state = MentionsState::default();
+    state.room_ids.insert("!room:matrix.org".into());
+    state.has_at_room_mention = true;
+    assert_eq!(model.get_mentions_state(), state);
+
+    model.undo();
+    state.has_at_room_mention = false;
+    assert_eq!(model.get_mentions_state(), state);
+
+    model.undo();
+    assert_eq!(model.get_mentions_state(), MentionsState::default());
+}
+
+#[test]
+fn get_mentions_state_follows_edits_around_mentions() {
+    let mut model = cm("|");
+    model.insert_mention(
+        "https://matrix.to/#/@alice:matrix.org".into(),
+        "Alice".into(),
+        vec![],
+    );
+    model.replace_text(" and ".into());
+    model.insert_mention(
+        "https://matrix.to/#/@bob:matrix.org".into(),
+        "Bob".into(),
+        vec![],
+    );
+    model.select(Location::from(0), Location::from(0));
+    model.replace_text("Hi ".into());
+    let mut state = MentionsState::default();
+    state.user_ids.insert("@alice:matrix.org".into());
+    state.user_ids.insert("@bob:matrix.org".into());
+    assert_eq!(model.get_mentions_state(), state);
+
+    // Remove Alice, then check Bob is still found after the text moved
+    model.select(Location::from(4), Location::from(4));
+    model.backspace();
+    model.replace_text("x".into());
+    state.user_ids.remove("@alice:matrix.org");
+    assert_eq!(model.get_mentions_state(), state);
+
+    model.select(Location::from(11), Location::from(11));
+    model.backspace();
+    assert_eq!(model.get_mentions_state(), MentionsState::default());
+}
+
+/**
+ * KEEPING PUNCTUATION
+ */
+#[test]
+fn suggestion_punctuation_is_replaced_by_default() {
+    let mut model = cm("|");
+    insert_mention_for_typed_text(&mut model, "@alic,");
+    assert_eq!(
+        tx(&model),
+        "<a data-mention-type=\"user\" href=\"https://matrix.to/#/@alice:matrix.org\" contenteditable=\"false\">Alice</a>&nbsp;|",
+    );
+}
+
+#[test]
+fn suggestion_punctuation_is_kept_before_trailing_space() {
+    let mut model = cm("|");
+    model.set_keep_suggestion_punctuation(true);
+    insert_mention_for_typed_text(&mut model, "@alic,");
+    assert_eq!(
+        tx(&model),
+        "<a data-mention-type=\"user\" href=\"https://matrix.to/#/@alice:matrix.org\" contenteditable=\"false\">Alice</a>,&nbsp;|",
+    );
+}
+
+#[test]
+fn multiple_suggestion_punctuation_characters_are_kept() {
+    let mut model = cm("|");
+    model.set_keep_suggestion_punctuation(true);
+    insert_mention_for_typed_text(&mut model, "@alic?!");
+    assert_eq!(
+        tx(&model),
+        "<a data-mention-type=\"user\" href=\"https://matrix.to/#/@alice:matrix.org\" contenteditable=\"false\">Alice</a>?!&nbsp;|",
+    );
+}
+
+#[test]
+fn suggestion_punctuation_is_kept_without_space_before_more_text() {
+    let mut model = cm("@alic,| there");
+    model.set_keep_suggestion_punctuation(true);
+    let MenuAction::Suggestion(suggestion) = model.compute_menu_action() else {
+        panic!("No suggestion pattern found")
+    };
+    model.insert_mention_at_suggestion(
+        "https://matrix.to/#/@alice:matrix.org".into(),
+        "Alice".into(),
+        suggestion,
+        vec![],
+    );
+    assert_eq!(
+        tx(&model),
+        "<a data-mention-type=\"user\" href=\"https://matrix.to/#/@alice:matrix.org\" contenteditable=\"false\">Alice</a>,| there",
+    );
+}
+
+#[test]
+fn at_room_suggestion_punctuation_is_kept() {
+    let mut model = cm("|");
+    model.set_keep_suggestion_punctuation(true);
+    let update = model.replace_text("@roo.".into());
+    let MenuAction::Suggestion(suggestion) = update.menu_action else {
+        panic!("No suggestion pattern found")
+    };
+    model.insert_at_room_mention_at_suggestion(suggestion, vec![]);
+    assert_eq!(
+        tx(&model),
+        "<a data-mention-type=\"at-room\" href=\"#\" contenteditable=\"false\">@room</a>.&nbsp;|",
+    );
+}
+
 /**
  * HELPER FUNCTIONS
  */
-fn insert_mention_at_cursor(model: &mut ComposerModel<Utf16String>) {
+fn insert_mention_at_cursor(model: &mut ComposerModel<TestString>) {
     let update = model.replace_text("@alic".into());
     let MenuAction::Suggestion(suggestion) = update.menu_action else {
         panic!("No suggestion pattern found")
@@ -727,10 +915,38 @@ fn insert_mention_at_cursor(model: &mut ComposerModel<Utf16String>) {
     );
 }
 
-fn insert_mention_at_selection(model: &mut ComposerModel<Utf16String>) {
+fn insert_mention_at_suggestion(model: &mut ComposerModel<TestString>) {
+    let MenuAction::Suggestion(suggestion) = model.compute_menu_action() else {
+        panic!("No suggestion pattern found")
+    };
+    model.insert_mention_at_suggestion(
+        "https://matrix.to/#/@alice:matrix.org".into(),
+        "Alice".into(),
+        suggestion,
+        vec![],
+    );
+}
+
+fn insert_mention_at_selection(model: &mut ComposerModel<TestString>) {
     model.insert_mention(
         "https://matrix.to/#/@alice:matrix.org".into(),
         "Alice".into(),
         vec![],
     );
 }
+
+fn insert_mention_for_typed_text(
+    model: &mut ComposerModel<TestString>,
+    typed_text: &str,
+) {
+    let update = model.replace_text(typed_text.into());
+    let MenuAction::Suggestion(suggestion) = update.menu_action else {
+        panic!("No suggestion pattern found")
+    };
+    model.insert_mention_at_suggestion(
+        "https://matrix.to/#/@alice:matrix.org".into(),
+        "Alice".into(),
+        suggestion,
+        vec![],
+    );
+}
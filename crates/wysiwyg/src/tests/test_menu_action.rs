@@ -211,6 +211,109 @@ fn menu_action_retuns_keep_after_format_with_cursor() {
     assert_eq!(update.menu_action, MenuAction::Keep);
 }
 
+// Multi-word suggestion tests.
+#[test]
+fn multi_word_at_pattern_is_detected() {
+    let mut model = cm("@john smi|");
+    model.set_multi_word_suggestion_patterns(vec![At]);
+    assert_eq!(model.compute_menu_action(), sp(At, "john smi", 0, 9));
+}
+
+#[test]
+fn multi_word_at_pattern_is_not_detected_for_other_keys() {
+    let mut model = cm("#rich tex|");
+    model.set_multi_word_suggestion_patterns(vec![At]);
+    assert_eq!(model.compute_menu_action(), MenuAction::None);
+}
+
+#[test]
+fn multi_word_at_pattern_is_detected_after_text() {
+    let mut model = cm("Hey @john smi|");
+    model.set_multi_word_suggestion_patterns(vec![At]);
+    assert_eq!(model.compute_menu_action(), sp(At, "john smi", 4, 13));
+}
+
+#[test]
+fn multi_word_at_pattern_extends_to_end_of_current_word() {
+    let mut model = cm("@john sm|ith");
+    model.set_multi_word_suggestion_patterns(vec![At]);
+    assert_eq!(model.compute_menu_action(), sp(At, "john smith", 0, 11));
+}
+
+#[test]
+fn multi_word_at_pattern_uses_closest_trigger() {
+    let mut model = cm("@alice and @john smi|");
+    model.set_multi_word_suggestion_patterns(vec![At]);
+    assert_eq!(model.compute_menu_action(), sp(At, "john smi", 11, 20));
+}
+
+#[test]
+fn multi_word_slash_pattern_is_not_detected_if_not_at_the_beginning_of_dom() {
+    let mut model = cm("abc /invite ali|");
+    model.set_multi_word_suggestion_patterns(vec![Slash]);
+    assert_eq!(model.compute_menu_action(), MenuAction::None);
+}
+
+#[test]
+fn multi_word_pattern_end_is_updated_on_character_input() {
+    let mut model = cm("|");
+    model.set_multi_word_suggestion_patterns(vec![At]);
+    let update = model.replace_text("@john".into());
+    assert_eq!(update.menu_action, sp(At, "john", 0, 5));
+    let update = model.replace_text(" ".into());
+    assert_eq!(update.menu_action, sp(At, "john ", 0, 6));
+    let update = model.replace_text("s".into());
+    assert_eq!(update.menu_action, sp(At, "john s", 0, 7));
+}
+
+#[test]
+fn dismissed_multi_word_pattern_is_not_detected_while_typing() {
+    let mut model = cm("@john|");
+    model.set_multi_word_suggestion_patterns(vec![At]);
+    let update = model.dismiss_suggestion();
+    assert_eq!(update.menu_action, MenuAction::None);
+    let update = model.replace_text(" smith".into());
+    assert_eq!(update.menu_action, MenuAction::None);
+}
+
+#[test]
+fn new_pattern_is_detected_after_dismissing_one() {
+    let mut model = cm("@john|");
+    model.set_multi_word_suggestion_patterns(vec![At]);
+    model.dismiss_suggestion();
+    let update = model.replace_text(" @ali".into());
+    assert_eq!(update.menu_action, sp(At, "ali", 6, 10));
+}
+
+#[test]
+fn dismissed_pattern_is_detected_again_after_being_edited() {
+    let mut model = cm("@john|");
+    model.dismiss_suggestion();
+    model.backspace();
+    model.backspace();
+    let update = model.replace_text("e".into());
+    assert_eq!(update.menu_action, sp(At, "joe", 0, 4));
+}
+
+#[test]
+fn disabled_pattern_key_is_not_detected() {
+    let mut model = cm("/|");
+    model.set_suggestion_pattern_enabled(Slash, false);
+    let update = model.replace_text("inv".into());
+    assert_eq!(update.menu_action, MenuAction::None);
+    let update = model.replace_text(" @ali".into());
+    assert_eq!(update.menu_action, sp(At, "ali", 5, 9));
+}
+
+#[test]
+fn pattern_key_is_detected_again_once_enabled() {
+    let mut model = cm("#roo|");
+    model.set_suggestion_pattern_enabled(Hash, false);
+    assert_eq!(model.compute_menu_action(), MenuAction::None);
+    model.set_suggestion_pattern_enabled(Hash, true);
+    assert_eq!(model.compute_menu_action(), sp(Hash, "roo", 0, 4));
+}
+
 /// Short wrapper around [MenuAction::Suggestion(SuggestionPattern)].
 fn sp(k: PatternKey, t: &str, s: usize, e: usize) -> MenuAction {
     MenuAction::Suggestion(SuggestionPattern {
@@ -218,5 +321,6 @@ fn sp(k: PatternKey, t: &str, s: usize, e: usize) -> MenuAction {
         text: t.into(),
         start: s,
         end: e,
+        revision: 0,
     })
 }
@@ -12,12 +12,15 @@
 // See the License for the specific language governing permissions and
 // limitations under the License.
 
-use widestring::Utf16String;
+use crate::tests::testutils_composer_model::TestString;
 
 use crate::tests::testutils_composer_model::cm;
 use crate::tests::testutils_conversion::utf16;
 
-use crate::{ComposerAction, ComposerModel, Location};
+use crate::{
+    ActionState, ComposerAction, ComposerModel, DisabledReason, Location,
+    MenuState,
+};
 
 #[test]
 fn creating_and_deleting_lists_updates_reversed_actions() {
@@ -170,6 +173,45 @@ fn cursor_at_the_end_of_link_reverses_the_link_action() {
     assert!(model.action_is_reversed(ComposerAction::Link));
 }
 
+#[test]
+fn cursor_outside_link_enables_creating_a_link_with_text() {
+    let model = cm("text|");
+    assert!(model.action_is_enabled(ComposerAction::CreateLinkWithText));
+    assert!(model.action_is_disabled(ComposerAction::EditLink));
+}
+
+#[test]
+fn selecting_text_disables_creating_a_link_with_text_and_editing() {
+    let model = cm("{text}|");
+    assert!(model.action_is_enabled(ComposerAction::Link));
+    assert!(model.action_is_disabled(ComposerAction::CreateLinkWithText));
+    assert!(model.action_is_disabled(ComposerAction::EditLink));
+}
+
+#[test]
+fn cursor_inside_link_enables_editing_it() {
+    let model = cm("<a href=\"https://element.io\">li|nk</a>");
+    assert!(model.action_is_enabled(ComposerAction::EditLink));
+    assert!(model.action_is_disabled(ComposerAction::CreateLinkWithText));
+}
+
+#[test]
+fn moving_into_a_link_updates_the_link_actions_in_the_menu_state() {
+    let mut model = cm("text <a href=\"https://element.io\">link</a>|");
+    let update = model.select(Location::from(2), Location::from(2));
+    let MenuState::Update(menu_state) = update.menu_state else {
+        panic!("Expected a menu state update");
+    };
+    assert_eq!(
+        menu_state.action_states[&ComposerAction::CreateLinkWithText],
+        ActionState::Enabled
+    );
+    assert_eq!(
+        menu_state.action_states[&ComposerAction::EditLink],
+        ActionState::Disabled
+    );
+}
+
 #[test]
 fn formatting_is_disabled_when_selection_is_inside_inline_code_node() {
     let model = cm("<code>Some inl|ine code</code>");
@@ -224,6 +266,8 @@ fn clicking_code_block_disables_expected_formatting_functions() {
     assert!(model.action_is_disabled(ComposerAction::UnorderedList));
     assert!(model.action_is_disabled(ComposerAction::Quote));
     assert!(model.action_is_disabled(ComposerAction::Link));
+    assert!(model.action_is_disabled(ComposerAction::CreateLinkWithText));
+    assert!(model.action_is_disabled(ComposerAction::EditLink));
 }
 
 #[test]
@@ -289,8 +333,138 @@ fn empty_list_item_with_formatting_computes_expected_menu_state() {
     assert!(model.action_is_reversed(ComposerAction::Italic));
 }
 
+#[test]
+fn disabled_undo_and_redo_report_empty_history() {
+    let model = cm("|");
+    assert_eq!(
+        model.disabled_reasons().get(&ComposerAction::Undo),
+        Some(&DisabledReason::HistoryEmpty)
+    );
+    assert_eq!(
+        model.disabled_reasons().get(&ComposerAction::Redo),
+        Some(&DisabledReason::HistoryEmpty)
+    );
+}
+
+#[test]
+fn enabled_actions_have_no_disabled_reason() {
+    let model = cm("|");
+    assert_eq!(model.disabled_reasons().get(&ComposerAction::Bold), None);
+}
+
+#[test]
+fn formatting_in_inline_code_reports_inline_code() {
+    let model = cm("<code>Some inl|ine code</code>");
+    assert_eq!(
+        model.disabled_reasons().get(&ComposerAction::Bold),
+        Some(&DisabledReason::InInlineCode)
+    );
+    assert_eq!(
+        model.disabled_reasons().get(&ComposerAction::EditLink),
+        Some(&DisabledReason::InInlineCode)
+    );
+}
+
+#[test]
+fn block_actions_in_code_block_report_code_block() {
+    let model = cm("<pre><code>Some |code</code></pre>");
+    assert_eq!(
+        model.disabled_reasons().get(&ComposerAction::Quote),
+        Some(&DisabledReason::InCodeBlock)
+    );
+}
+
+#[test]
+fn link_actions_on_immutable_link_report_immutable_link() {
+    let model = cm(
+        "<a contenteditable=\"false\" href=\"https://matrix.org\">te|st</a>",
+    );
+    assert_eq!(
+        model.disabled_reasons().get(&ComposerAction::Link),
+        Some(&DisabledReason::ImmutableLink)
+    );
+    assert_eq!(
+        model.disabled_reasons().get(&ComposerAction::EditLink),
+        Some(&DisabledReason::ImmutableLink)
+    );
+}
+
+#[test]
+fn editing_a_link_outside_links_is_not_applicable() {
+    let model = cm("text|");
+    assert_eq!(
+        model.disabled_reasons().get(&ComposerAction::EditLink),
+        Some(&DisabledReason::NotApplicable)
+    );
+}
+
+#[test]
+fn indenting_outside_lists_reports_it_cannot_be_indented() {
+    let model = cm("text|");
+    assert_eq!(
+        model.disabled_reasons().get(&ComposerAction::Indent),
+        Some(&DisabledReason::CannotIndent)
+    );
+    assert_eq!(
+        model.disabled_reasons().get(&ComposerAction::Unindent),
+        Some(&DisabledReason::CannotUnindent)
+    );
+}
+
+#[test]
+fn menu_state_update_contains_disabled_reasons() {
+    let mut model = cm("te|xt <code>code</code>");
+    let update = model.select(Location::from(7), Location::from(7));
+    let MenuState::Update(menu_state) = update.menu_state else {
+        panic!("Expected a menu state update");
+    };
+    assert_eq!(&menu_state.disabled_reasons, model.disabled_reasons());
+    assert_eq!(
+        menu_state.disabled_reasons.get(&ComposerAction::Bold),
+        Some(&DisabledReason::InInlineCode)
+    );
+}
+
+#[test]
+fn typing_a_url_reports_the_menu_state_once_it_is_linked() {
+    let mut model = ComposerModel::new();
+    model.set_link_as_you_type(true);
+    let update = model.replace_text(utf16("https://matrix.org "));
+    let MenuState::Update(menu_state) = update.menu_state else {
+        panic!("Expected a menu state update");
+    };
+    assert_eq!(
+        menu_state.action_states[&ComposerAction::Undo],
+        ActionState::Enabled
+    );
+    assert_eq!(menu_state.undo_depth, 1);
+}
+
+#[test]
+fn menu_state_follows_edits_made_after_an_undo() {
+    let mut model = cm("{a}|");
+    model.bold();
+    assert!(model.action_is_reversed(ComposerAction::Bold));
+    model.undo();
+    assert!(!model.action_is_reversed(ComposerAction::Bold));
+    model.italic();
+    assert!(model.action_is_reversed(ComposerAction::Italic));
+    assert!(!model.action_is_reversed(ComposerAction::Bold));
+}
+
+#[test]
+fn selecting_back_and_forth_reports_the_menu_state_each_time() {
+    let mut model = cm("<strong>a</strong> b|");
+    let update = model.select(Location::from(1), Location::from(1));
+    assert!(matches!(update.menu_state, MenuState::Update(_)));
+    let update = model.select(Location::from(3), Location::from(3));
+    assert!(matches!(update.menu_state, MenuState::Update(_)));
+    let update = model.select(Location::from(1), Location::from(1));
+    assert!(matches!(update.menu_state, MenuState::Update(_)));
+}
+
 fn assert_formatting_actions_and_links_are_disabled(
-    model: &ComposerModel<Utf16String>,
+    model: &ComposerModel<TestString>,
 ) {
     assert!(model.action_is_disabled(ComposerAction::Bold));
     assert!(model.action_is_disabled(ComposerAction::Italic));
@@ -299,6 +473,6 @@ fn assert_formatting_actions_and_links_are_disabled(
     assert!(model.action_is_disabled(ComposerAction::Link));
 }
 
-fn replace_text(model: &mut ComposerModel<Utf16String>, new_text: &str) {
+fn replace_text(model: &mut ComposerModel<TestString>, new_text: &str) {
     model.replace_text(utf16(new_text));
 }
@@ -0,0 +1,119 @@
+// Copyright 2023 The Matrix.org Foundation C.I.C.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use crate::tests::testutils_composer_model::{cm, tx};
+use crate::{ComposerUpdate, Location};
+
+fn move_range(
+    model_html: &str,
+    start: usize,
+    end: usize,
+    dest: usize,
+) -> String {
+    let mut model = cm(model_html);
+    model.move_range(
+        Location::from(start),
+        Location::from(end),
+        Location::from(dest),
+    );
+    tx(&model)
+}
+
+#[test]
+fn moving_text_forwards() {
+    assert_eq!(move_range("abcdef|", 0, 2, 4), "cd{ab}|ef");
+}
+
+#[test]
+fn moving_text_backwards() {
+    assert_eq!(move_range("abcdef|", 4, 6, 1), "a{ef}|bcd");
+}
+
+#[test]
+fn moving_formatted_text_keeps_the_formatting() {
+    assert_eq!(
+        move_range("a<strong>bc</strong>def|", 1, 3, 5),
+        "ade<strong>{bc}|</strong>f"
+    );
+}
+
+#[test]
+fn moving_part_of_formatted_text_keeps_the_formatting() {
+    assert_eq!(
+        move_range("<em>abc</em>def|", 1, 2, 5),
+        "<em>ac</em>de<em>{b}|</em>f"
+    );
+}
+
+#[test]
+fn moved_text_does_not_pick_up_formatting_before_it() {
+    assert_eq!(
+        move_range("ab<strong>cd</strong>ef|", 0, 2, 4),
+        "<strong>cd</strong>{ab}|ef"
+    );
+}
+
+#[test]
+fn moving_a_mention_keeps_it() {
+    let mut model = cm("|");
+    model.replace_text("ab ".into());
+    model.insert_mention(
+        "https://matrix.to/#/@alice:matrix.org".into(),
+        "Alice".into(),
+        vec![],
+    );
+    model.replace_text("cd".into());
+    model.move_range(Location::from(3), Location::from(4), Location::from(0));
+    assert_eq!(
+        model.get_mentions_state().user_ids,
+        ["@alice:matrix.org".to_owned()].into()
+    );
+    assert_eq!(model.get_content_as_plain_text(), "Aliceab  cd");
+}
+
+#[test]
+fn moving_is_undone_in_one_step() {
+    let mut model = cm("abcdef|");
+    model.move_range(Location::from(0), Location::from(2), Location::from(4));
+    model.undo();
+    assert_eq!(tx(&model), "abcdef|");
+}
+
+#[test]
+fn moving_into_itself_does_nothing() {
+    let mut model = cm("abcdef|");
+    let update = model.move_range(
+        Location::from(1),
+        Location::from(4),
+        Location::from(2),
+    );
+    assert_eq!(update, ComposerUpdate::keep_at(model.revision()));
+    assert_eq!(tx(&model), "abcdef|");
+}
+
+#[test]
+fn moving_across_blocks_does_nothing() {
+    assert_eq!(
+        move_range("<p>abc</p><p>def|</p>", 1, 5, 7),
+        "<p>abc</p><p>def|</p>"
+    );
+}
+
+#[test]
+fn moving_into_another_paragraph() {
+    assert_eq!(
+        move_range("<p>abc</p><p>def|</p>", 0, 1, 6),
+        "<p>bc</p><p>de{a}|f</p>"
+    );
+}
@@ -12,11 +12,11 @@
 // See the License for the specific language governing permissions and
 // limitations under the License.
 
-use widestring::Utf16String;
+use crate::tests::testutils_composer_model::TestString;
 
 use crate::{
     tests::testutils_composer_model::{cm, tx},
-    ComposerModel,
+    ComposerModel, Location,
 };
 
 use super::testutils_conversion::utf16;
@@ -32,7 +32,7 @@ fn pressing_enter_with_a_brand_new_model() {
 #[test]
 #[allow(deprecated)]
 fn adding_line_break_after_replacing_with_empty_html() {
-    let mut model = ComposerModel::<Utf16String>::new();
+    let mut model = ComposerModel::<TestString>::new();
     model.set_content_from_html(&utf16("")).unwrap();
     model.add_line_break();
     assert_eq!(tx(&model), "<br />|");
@@ -147,7 +147,7 @@ fn delete_deletes_br() {
 fn type_after_adding_line_break() {
     let mut model = cm("a|");
     model.add_line_break();
-    model.replace_text(Utf16String::from_str("b"));
+    model.replace_text(TestString::from_str("b"));
     assert_eq!(tx(&model), "a<br />b|");
 }
 
@@ -535,3 +535,125 @@ fn several_new_lines_inside_block_node_then_text() {
     model.replace_text_in("o".into(), 4, 4);
     assert_eq!(tx(&model), "<blockquote><p>Ok</p></blockquote><p>o|</p>");
 }
+
+#[test]
+fn enter_after_clearing_the_model_adds_paragraphs() {
+    let mut model = cm("abc|");
+    model.clear();
+    model.enter();
+    assert_eq!(tx(&model), "<p>&nbsp;</p><p>&nbsp;|</p>");
+}
+
+#[test]
+fn enter_after_setting_html_with_an_empty_list_does_not_panic() {
+    let mut model = cm("|");
+    model.set_content_from_html(&utf16("<ol></ol>")).unwrap();
+    model.enter();
+    assert_eq!(tx(&model), "<p>&nbsp;|</p>");
+}
+
+#[test]
+fn enter_in_an_empty_list_without_exiting_adds_list_items() {
+    let mut model = cm("|");
+    model.set_exit_list_on_empty_line(false);
+    model.set_content_from_html(&utf16("<ol></ol>")).unwrap();
+    model.enter();
+    assert_eq!(tx(&model), "<ol><li></li><li>|</li></ol>");
+}
+
+#[test]
+fn typing_after_setting_html_with_an_empty_list_adds_a_list_item() {
+    let mut model = cm("|");
+    model.set_content_from_html(&utf16("<ol></ol>")).unwrap();
+    model.replace_text(utf16("ab"));
+    assert_eq!(tx(&model), "<ol><li>ab|</li></ol>");
+    model.enter();
+    assert_eq!(tx(&model), "<ol><li>ab</li><li>|</li></ol>");
+}
+
+#[test]
+fn double_enter_in_list_can_add_list_items_instead_of_exiting() {
+    let mut model = cm("<ul><li>a|</li></ul>");
+    model.set_exit_list_on_empty_line(false);
+    model.enter();
+    model.enter();
+    model.replace_text(utf16("b"));
+    assert_eq!(tx(&model), "<ul><li>a</li><li></li><li>b|</li></ul>");
+}
+
+#[test]
+fn double_enter_in_quote_can_add_lines_instead_of_exiting() {
+    let mut model = cm("<blockquote><p>a|</p></blockquote>");
+    model.set_exit_quote_on_empty_line(false);
+    model.enter();
+    model.enter();
+    model.replace_text(utf16("b"));
+    assert_eq!(
+        tx(&model),
+        "<blockquote><p>a</p><p>&nbsp;</p><p>b|</p></blockquote>"
+    );
+}
+
+#[test]
+fn double_enter_in_code_block_can_add_lines_instead_of_exiting() {
+    let mut model = cm("<pre><code>a|</code></pre>");
+    model.set_exit_code_block_on_empty_line(false);
+    model.enter();
+    model.enter();
+    model.replace_text(utf16("b"));
+    assert_eq!(tx(&model), "<pre><code>a\n\nb|</code></pre>");
+}
+
+#[test]
+fn exiting_on_empty_line_is_configured_per_block_type() {
+    let mut model = cm("<blockquote><p>a</p><p>|</p></blockquote>");
+    model.set_exit_code_block_on_empty_line(false);
+    model.set_exit_list_on_empty_line(false);
+    model.enter();
+    assert_eq!(
+        tx(&model),
+        "<blockquote><p>a</p></blockquote><p>&nbsp;|</p>"
+    );
+}
+
+#[test]
+fn new_empty_blocks_have_no_placeholder_text() {
+    let mut model = cm("|");
+    model.quote();
+    assert_eq!(tx(&model), "<blockquote><p>&nbsp;|</p></blockquote>");
+    assert_eq!(model.state.dom.text_len(), 0);
+
+    let mut model = cm("|");
+    model.ordered_list();
+    assert_eq!(tx(&model), "<ol><li>|</li></ol>");
+    assert_eq!(model.state.dom.text_len(), 0);
+
+    let mut model = cm("|");
+    model.code_block();
+    assert_eq!(tx(&model), "<pre><code>&nbsp;|</code></pre>");
+    assert_eq!(model.state.dom.text_len(), 0);
+}
+
+#[test]
+fn typing_into_a_new_empty_quote_needs_no_selection_adjustment() {
+    let mut model = cm("|");
+    model.quote();
+    model.replace_text(utf16("a"));
+    assert_eq!(tx(&model), "<blockquote><p>a|</p></blockquote>");
+    assert_eq!(
+        model.get_selection(),
+        (Location::from(1), Location::from(1))
+    );
+}
+
+#[test]
+fn empty_line_in_a_quote_is_serialized_without_placeholder_text() {
+    let mut model = cm("a|");
+    model.quote();
+    model.enter();
+    assert_eq!(
+        model.get_selection(),
+        (Location::from(2), Location::from(2))
+    );
+    assert!(!model.get_content_as_html().to_string().contains('\u{200B}'));
+}
@@ -0,0 +1,223 @@
+// Copyright 2023 The Matrix.org Foundation C.I.C.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use crate::tests::testutils_composer_model::{cm, tx};
+use crate::tests::testutils_conversion::utf16;
+
+#[test]
+fn inserting_plain_text_without_formatting_inserts_it() {
+    let mut model = cm("a|");
+    model.insert_text_without_formatting(utf16("bc"));
+    assert_eq!(tx(&model), "abc|");
+}
+
+#[test]
+fn inserting_html_without_formatting_drops_the_tags() {
+    let mut model = cm("a|");
+    model.insert_text_without_formatting(utf16(
+        "<strong>b</strong><a href=\"https://matrix.org\">c</a>",
+    ));
+    assert_eq!(tx(&model), "abc|");
+}
+
+#[test]
+fn inserting_html_without_formatting_replaces_the_selection() {
+    let mut model = cm("a{bc}|d");
+    model.insert_text_without_formatting(utf16("<em>x</em>"));
+    assert_eq!(tx(&model), "ax|d");
+}
+
+#[test]
+fn inserting_html_without_formatting_decodes_entities() {
+    let mut model = cm("|");
+    model.insert_text_without_formatting(utf16("<b>a &amp; b</b>"));
+    assert_eq!(model.get_content_as_plain_text(), "a & b");
+}
+
+#[test]
+fn inserting_html_without_formatting_keeps_paragraphs_as_lines() {
+    let mut model = cm("|");
+    model.insert_text_without_formatting(utf16("<p>a</p><p>b</p>"));
+    assert_eq!(tx(&model), "<p>a</p><p>b|</p>");
+}
+
+#[test]
+fn inserting_text_that_only_looks_like_html_keeps_it() {
+    let mut model = cm("|");
+    model.insert_text_without_formatting(utf16("x<y and y>z"));
+    assert_eq!(model.get_content_as_plain_text(), "x<y and y>z");
+}
+
+#[test]
+fn inserting_without_formatting_uses_the_formatting_at_the_cursor() {
+    let mut model = cm("<strong>a|</strong>");
+    model.insert_text_without_formatting(utf16("<em>b</em>"));
+    assert_eq!(tx(&model), "<strong>ab|</strong>");
+}
+
+#[test]
+fn inserting_without_formatting_is_one_undo_step() {
+    let mut model = cm("a|");
+    model.insert_text_without_formatting(utf16("<p>b</p><p>c</p>"));
+    model.undo();
+    assert_eq!(tx(&model), "a|");
+}
+
+#[test]
+fn pasting_lines_into_a_list_item_makes_sibling_list_items() {
+    let mut model = cm("<ol><li>a|b</li><li>cd</li></ol>");
+    model.replace_text(utf16("x\ny"));
+    assert_eq!(tx(&model), "<ol><li>ax</li><li>y|b</li><li>cd</li></ol>");
+}
+
+#[test]
+fn pasting_lines_into_a_list_item_skips_blank_lines() {
+    let mut model = cm("<ol><li>a|b</li><li>cd</li></ol>");
+    model.replace_text(utf16("x\n\ny"));
+    assert_eq!(tx(&model), "<ol><li>ax</li><li>y|b</li><li>cd</li></ol>");
+}
+
+#[test]
+fn pasting_lines_with_a_trailing_newline_into_a_list_item() {
+    let mut model = cm("<ul><li>a|</li></ul>");
+    model.replace_text(utf16("x\ny\n\n"));
+    assert_eq!(tx(&model), "<ul><li>ax</li><li>y</li><li>|</li></ul>");
+}
+
+#[test]
+fn pasting_lines_over_a_selection_in_a_list() {
+    let mut model = cm("<ul><li>a{b</li><li>c}|d</li></ul>");
+    model.replace_text(utf16("x\ny"));
+    assert_eq!(tx(&model), "<ul><li>ax</li><li>y|d</li></ul>");
+}
+
+#[test]
+fn pasting_html_paragraphs_without_formatting_into_a_list_item() {
+    let mut model = cm("<ul><li>a|</li></ul>");
+    model.insert_text_without_formatting(utf16("<p>b</p><p>c</p>"));
+    assert_eq!(tx(&model), "<ul><li>ab</li><li>c|</li></ul>");
+}
+
+#[test]
+fn pasting_lines_into_a_list_item_can_keep_them_as_paragraphs() {
+    let mut model = cm("<ol><li>a|b</li><li>cd</li></ol>");
+    model.set_split_lines_into_list_items(false);
+    model.replace_text(utf16("x\n\ny"));
+    assert_eq!(
+        tx(&model),
+        "<ol><li><p>ax</p><p>y|b</p></li><li>cd</li></ol>"
+    );
+}
+
+#[test]
+fn pasting_lines_as_paragraphs_keeps_a_nested_list_in_the_list_item() {
+    let mut model = cm("<ul><li>a|<ul><li>b</li></ul></li></ul>");
+    model.set_split_lines_into_list_items(false);
+    model.replace_text(utf16("x\ny"));
+    assert_eq!(
+        tx(&model),
+        "<ul><li><p>ax</p><p>y|</p><ul><li>b</li></ul></li></ul>"
+    );
+}
+
+#[test]
+fn pasting_lines_as_paragraphs_is_one_undo_step() {
+    let mut model = cm("<ul><li>a|</li></ul>");
+    model.set_split_lines_into_list_items(false);
+    model.replace_text(utf16("x\ny"));
+    model.undo();
+    assert_eq!(tx(&model), "<ul><li>a|</li></ul>");
+}
+
+#[test]
+fn pasting_only_newlines_into_a_list_item_adds_one_list_item() {
+    let mut model = cm("<ul><li>a|b</li></ul>");
+    model.replace_text(utf16("\n\n"));
+    assert_eq!(tx(&model), "<ul><li>a</li><li>|b</li></ul>");
+}
+
+#[test]
+fn pasting_bullets_makes_an_unordered_list() {
+    let mut model = cm("|");
+    model.set_convert_pasted_bullets_to_lists(true);
+    model.replace_text(utf16("- a\n* b"));
+    assert_eq!(tx(&model), "<ul><li>a</li><li>b|</li></ul>");
+}
+
+#[test]
+fn pasting_numbered_lines_makes_an_ordered_list() {
+    let mut model = cm("|");
+    model.set_convert_pasted_bullets_to_lists(true);
+    model.replace_text(utf16("1. a\n2. b\n10. c"));
+    assert_eq!(tx(&model), "<ol><li>a</li><li>b</li><li>c|</li></ol>");
+}
+
+#[test]
+fn pasting_bullets_keeps_other_lines_as_paragraphs() {
+    let mut model = cm("|");
+    model.set_convert_pasted_bullets_to_lists(true);
+    model.replace_text(utf16("Notes:\n- a\n- b\n1. c\nEnd"));
+    assert_eq!(
+        tx(&model),
+        "<p>Notes:</p><ul><li>a</li><li>b</li></ul>\
+         <ol><li>c</li></ol><p>End|</p>"
+    );
+}
+
+#[test]
+fn pasting_bullets_after_text_keeps_the_first_bullet() {
+    let mut model = cm("x|");
+    model.set_convert_pasted_bullets_to_lists(true);
+    model.replace_text(utf16("- a\n- b"));
+    assert_eq!(tx(&model), "<p>x- a</p><ul><li>b|</li></ul>");
+}
+
+#[test]
+fn pasting_bullets_does_nothing_special_when_disabled() {
+    let mut model = cm("|");
+    model.replace_text(utf16("- a\n- b"));
+    assert_eq!(tx(&model), "<p>- a</p><p>- b|</p>");
+}
+
+#[test]
+fn pasting_bullets_into_a_code_block_keeps_them() {
+    let mut model = cm("<pre><code>|</code></pre>");
+    model.set_convert_pasted_bullets_to_lists(true);
+    model.replace_text(utf16("- a\n- b"));
+    assert_eq!(tx(&model), "<pre><code>- a\n- b|</code></pre>");
+}
+
+#[test]
+fn pasting_bullets_is_one_undo_step() {
+    let mut model = cm("|");
+    model.set_convert_pasted_bullets_to_lists(true);
+    model.replace_text(utf16("- a\n- b"));
+    model.undo();
+    assert_eq!(tx(&model), "|");
+}
+
+#[test]
+fn pasting_lines_after_characters_outside_the_bmp() {
+    let mut model = cm("😀😀|");
+    model.replace_text(utf16("x\ny"));
+    assert_eq!(tx(&model), "<p>😀😀x</p><p>y|</p>");
+}
+
+#[test]
+fn pasting_bullets_after_characters_outside_the_bmp() {
+    let mut model = cm("😀😀|");
+    model.set_convert_pasted_bullets_to_lists(true);
+    model.replace_text(utf16("- a\n- b"));
+    assert_eq!(tx(&model), "<p>😀😀- a</p><ul><li>b|</li></ul>");
+}
@@ -0,0 +1,134 @@
+// Copyright 2023 The Matrix.org Foundation C.I.C.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+#![cfg(test)]
+
+use std::sync::{Arc, Mutex};
+
+use crate::tests::testutils_composer_model::{cm, tx};
+use crate::tests::testutils_conversion::utf16;
+use crate::{ComposerError, ComposerUpdate, DomHandle, DomNode, ErrorListener};
+
+#[test]
+fn recovering_consistent_content_does_nothing() {
+    let mut model = cm("<p>a{b}|c</p>");
+    assert_eq!(model.recover(), ComposerUpdate::keep_at(model.revision()));
+    assert_eq!(tx(&model), "<p>a{b}|c</p>");
+}
+
+#[test]
+fn recovering_joins_adjacent_text_nodes() {
+    let mut model = cm("<p>ab|</p>");
+    append_to(&mut model, &[0], DomNode::new_text(utf16("c")));
+    assert!(model.state.dom.invariant_violation().is_some());
+
+    model.recover();
+
+    assert_eq!(tx(&model), "<p>ab|c</p>");
+    assert!(model.state.dom.invariant_violation().is_none());
+}
+
+#[test]
+fn recovering_removes_empty_text_nodes() {
+    let mut model = cm("<p><b>a|</b></p>");
+    append_to(&mut model, &[0], DomNode::new_text(utf16("")));
+
+    model.recover();
+
+    assert_eq!(tx(&model), "<p><b>a|</b></p>");
+}
+
+#[test]
+fn recovering_wraps_inline_nodes_next_to_blocks_in_paragraphs() {
+    let mut model = cm("<p>a|</p>");
+    append_to(&mut model, &[], DomNode::new_text(utf16("b")));
+
+    model.recover();
+
+    assert_eq!(tx(&model), "<p>a|</p><p>b</p>");
+}
+
+#[test]
+fn recovering_keeps_editing_working() {
+    let mut model = cm("<p>ab|</p>");
+    append_to(&mut model, &[0], DomNode::new_text(utf16("c")));
+    model.recover();
+    model.replace_text(utf16("X"));
+    model.enter();
+    assert_eq!(tx(&model), "<p>abX</p><p>|c</p>");
+}
+
+#[test]
+fn the_violation_is_reported_to_the_error_listener() {
+    let recorder = Arc::new(Recorder::default());
+    let mut model = cm("<p>ab|</p>");
+    model.set_error_listener(Some(recorder.clone()));
+    append_to(&mut model, &[0], DomNode::new_text(utf16("c")));
+
+    model.recover();
+    model.take_unreported_errors().report();
+
+    let errors = recorder.0.lock().unwrap();
+    assert_eq!(errors.len(), 1);
+    assert!(matches!(
+        &errors[0],
+        ComposerError::InvariantViolation(v) if v.starts_with("Adjacent text nodes")
+    ));
+}
+
+#[test]
+fn the_reported_violation_does_not_include_the_content() {
+    let recorder = Arc::new(Recorder::default());
+    let mut model = cm("<p>secret|</p>");
+    model.set_error_listener(Some(recorder.clone()));
+    append_to(&mut model, &[0], DomNode::new_text(utf16("words")));
+
+    model.recover();
+    model.take_unreported_errors().report();
+
+    let errors = recorder.0.lock().unwrap();
+    assert_eq!(
+        errors[0],
+        ComposerError::InvariantViolation(
+            "Adjacent text nodes found! handle: [0, 0]".to_owned()
+        )
+    );
+}
+
+#[derive(Default)]
+struct Recorder(Mutex<Vec<ComposerError>>);
+
+impl ErrorListener for Recorder {
+    fn on_error(&self, error: &ComposerError) {
+        self.0.lock().unwrap().push(error.clone());
+    }
+}
+
+/// Break the model's content on purpose by adding `node` to the end of the
+/// container at `path`, bypassing the usual clean up.
+fn append_to(
+    model: &mut crate::ComposerModel<
+        crate::tests::testutils_composer_model::TestString,
+    >,
+    path: &[usize],
+    node: DomNode<crate::tests::testutils_composer_model::TestString>,
+) {
+    let handle = DomHandle::from_raw(path.to_vec());
+    let DomNode::Container(container) =
+        model.state.dom.lookup_node_mut(&handle)
+    else {
+        panic!("Not a container");
+    };
+    container.append_child(node);
+}
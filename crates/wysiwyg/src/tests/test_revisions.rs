@@ -0,0 +1,88 @@
+// Copyright 2023 The Matrix.org Foundation C.I.C.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+#![cfg(test)]
+
+use crate::tests::testutils_composer_model::{cm, TestString};
+use crate::tests::testutils_conversion::utf16;
+use crate::{ComposerModel, Location};
+
+#[test]
+fn a_new_model_is_at_revision_zero() {
+    let model = ComposerModel::<TestString>::new();
+    assert_eq!(model.revision(), 0);
+}
+
+#[test]
+fn every_update_carries_the_next_revision() {
+    let mut model = cm("|");
+    let first = model.replace_text(utf16("a"));
+    let second = model.select(Location::from(0), Location::from(1));
+    let third = model.bold();
+
+    assert_eq!(first.revision, 1);
+    assert_eq!(second.revision, 2);
+    assert_eq!(third.revision, 3);
+    assert_eq!(model.revision(), 3);
+}
+
+#[test]
+fn toggling_a_format_at_the_cursor_moves_the_revision_on() {
+    let mut model = cm("a|");
+    let update = model.italic();
+    assert_eq!(update.revision, 1);
+}
+
+#[test]
+fn updates_which_change_nothing_carry_the_current_revision() {
+    let mut model = cm("a|");
+    model.replace_text(utf16("b"));
+    let update = model.redo();
+    assert_eq!(update.revision, 1);
+    assert_eq!(model.revision(), 1);
+}
+
+#[test]
+fn undo_and_redo_keep_moving_the_revision_on() {
+    let mut model = cm("|");
+    model.replace_text(utf16("a"));
+    model.replace_text(utf16("b"));
+    assert_eq!(model.undo().revision, 3);
+    assert_eq!(model.undo().revision, 4);
+    assert_eq!(model.redo().revision, 5);
+}
+
+#[test]
+fn importing_state_keeps_moving_the_revision_on() {
+    let mut source = cm("|");
+    source.replace_text(utf16("abc"));
+    let exported = source.export_state();
+
+    let mut model = cm("|");
+    model.replace_text(utf16("x"));
+    model.replace_text(utf16("y"));
+    let update = model.import_state(&exported).unwrap();
+
+    assert_eq!(update.revision, 3);
+}
+
+#[test]
+fn states_with_different_revisions_but_the_same_content_are_equal() {
+    let mut model = cm("a|");
+    let before = model.state.clone();
+    model.select(Location::from(0), Location::from(0));
+    model.select(Location::from(1), Location::from(1));
+    assert_ne!(before.revision, model.state.revision);
+    assert_eq!(before, model.state);
+}
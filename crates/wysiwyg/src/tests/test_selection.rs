@@ -13,8 +13,9 @@
 // limitations under the License.
 
 use crate::tests::testutils_composer_model::{cm, tx};
+use crate::tests::testutils_conversion::utf16;
 
-use crate::{Location, TextUpdate};
+use crate::{Location, SelectionStats, TextUpdate};
 
 #[test]
 fn selecting_ascii_characters() {
@@ -47,6 +48,7 @@ fn selecting_single_utf16_code_unit_characters() {
 }
 
 #[test]
+#[cfg_attr(feature = "test-utf32", ignore = "uses UTF-16 offsets")]
 fn selecting_multiple_utf16_code_unit_characters() {
     let mut model = cm("\u{1F4A9}\u{1F4A9}\u{1F4A9}|");
 
@@ -61,6 +63,7 @@ fn selecting_multiple_utf16_code_unit_characters() {
 }
 
 #[test]
+#[cfg_attr(feature = "test-utf32", ignore = "uses UTF-16 offsets")]
 fn selecting_complex_characters() {
     let mut model = cm("aaa\u{03A9}bbb\u{1F469}\u{1F3FF}\u{200D}\u{1F680}ccc|");
 
@@ -108,3 +111,129 @@ fn selecting_creates_a_selection_update() {
         panic!("TextUpdate should be a selection")
     }
 }
+
+#[test]
+fn selection_as_markdown_keeps_formatting() {
+    let model = cm("ab<strong>{cd</strong>e}|f");
+    assert_eq!(model.get_selection_as_markdown().to_string(), "__cd__e");
+}
+
+#[test]
+fn selection_as_markdown_keeps_lists() {
+    let model = cm("<ul><li>{abc</li><li>de}|f</li></ul>");
+    assert_eq!(model.get_selection_as_markdown().to_string(), "* abc\n* de");
+}
+
+#[test]
+fn selection_as_plain_text() {
+    let model = cm("<p>a{bc</p><p>de}|f</p>");
+    assert_eq!(model.get_selection_as_plain_text().to_string(), "bc\nde");
+}
+
+#[test]
+fn selection_as_markdown_is_empty_without_a_selection() {
+    let model = cm("ab|c");
+    assert_eq!(model.get_selection_as_markdown().to_string(), "");
+    assert_eq!(model.get_selection_as_plain_text().to_string(), "");
+}
+
+fn word_range(model_html: &str, offset: usize) -> (usize, usize) {
+    let (start, end) = cm(model_html).word_range_at(Location::from(offset));
+    (start.into(), end.into())
+}
+
+#[test]
+fn word_range_is_the_word_at_the_offset() {
+    assert_eq!(word_range("one two three|", 5), (4, 7));
+}
+
+#[test]
+fn word_range_at_the_end_of_the_text_is_the_last_word() {
+    assert_eq!(word_range("one two|", 7), (4, 7));
+}
+
+#[test]
+fn word_range_includes_hyphens_inside_words() {
+    assert_eq!(word_range("a well-known fact|", 3), (2, 12));
+}
+
+#[test]
+fn word_range_does_not_include_trailing_punctuation() {
+    assert_eq!(word_range("hello, world|", 2), (0, 5));
+}
+
+#[test]
+fn word_range_on_whitespace_is_the_whitespace() {
+    assert_eq!(word_range("one   two|", 4), (3, 6));
+}
+
+#[test]
+fn word_range_includes_apostrophes_in_english() {
+    let mut model = cm("I don't know|");
+    model.set_locale("en");
+    let (start, end) = model.word_range_at(Location::from(3));
+    assert_eq!((start, end), (Location::from(2), Location::from(7)));
+}
+
+#[test]
+fn word_range_is_the_whole_url() {
+    assert_eq!(word_range("see https://matrix.org/docs. ok|", 10), (4, 27));
+}
+
+#[test]
+fn word_range_is_the_whole_link() {
+    assert_eq!(
+        word_range("a <a href=\"https://x.org\">two words</a> b|", 3),
+        (2, 11)
+    );
+}
+
+#[test]
+fn word_range_is_the_whole_mention() {
+    let mut model = cm("|");
+    model.replace_text("ab ".into());
+    model.insert_mention(
+        "https://matrix.to/#/@alice:matrix.org".into(),
+        "Alice".into(),
+        vec![],
+    );
+    assert_eq!(
+        model.word_range_at(Location::from(3)),
+        (Location::from(3), Location::from(4))
+    );
+}
+
+#[test]
+fn word_range_spans_surrogate_pairs() {
+    let word_len = utf16("𝐀𝐁").len();
+    assert_eq!(word_range("a 𝐀𝐁 b|", 2), (2, 2 + word_len));
+}
+
+#[test]
+fn selection_stats_count_graphemes_and_words() {
+    let model = cm("a{b <strong>cd</strong> 👍🏽 e}|f");
+    assert_eq!(
+        model.selection_stats(),
+        SelectionStats {
+            character_count: 8,
+            word_count: 3,
+            spans_multiple_blocks: false,
+        }
+    );
+}
+
+#[test]
+fn selection_stats_are_empty_for_a_cursor() {
+    let model = cm("ab|c");
+    assert_eq!(model.selection_stats(), SelectionStats::default());
+}
+
+#[test]
+fn selection_stats_notice_a_selection_in_two_blocks() {
+    let model = cm("<p>a{b</p><p>c}|d</p>");
+    assert!(model.selection_stats().spans_multiple_blocks);
+    let model = cm("<ul><li>{ab</li><li>}|cd</li></ul>");
+    assert!(model.selection_stats().spans_multiple_blocks);
+    let model = cm("<p>ab</p><p>{cd}|</p>");
+    assert!(!model.selection_stats().spans_multiple_blocks);
+}
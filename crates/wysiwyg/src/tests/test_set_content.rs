@@ -12,13 +12,13 @@
 // See the License for the specific language governing permissions and
 // limitations under the License.
 
+use crate::tests::testutils_composer_model::TestString;
 use indoc::indoc;
-use widestring::Utf16String;
 
 use crate::{
     dom::DomCreationError,
     tests::{testutils_composer_model::tx, testutils_conversion::utf16},
-    HtmlParseError,
+    HtmlParseError, WhitespacePolicy,
 };
 
 use super::testutils_composer_model::cm;
@@ -38,12 +38,28 @@ fn set_content_from_html_invalid() {
         .unwrap_err();
     assert_eq!(
         error,
-        DomCreationError::HtmlParseError(HtmlParseError::new(vec![
-            "Unexpected open tag at end of body".into()
-        ]))
+        DomCreationError::HtmlParseError(HtmlParseError {
+            parse_errors: vec!["Unexpected open tag at end of body".into()],
+            recovered_html: Some(
+                "<strong>hello<strong></strong></strong>".into()
+            ),
+        })
     );
 }
 
+#[test]
+fn recovered_html_from_invalid_html_can_be_set() {
+    let mut model = cm("|");
+    let Err(DomCreationError::HtmlParseError(error)) =
+        model.set_content_from_html(&utf16("<em>a</strong>b"))
+    else {
+        panic!("Expected an HTML parse error");
+    };
+    let recovered = error.recovered_html.unwrap();
+    model.set_content_from_html(&utf16(&recovered)).unwrap();
+    assert_eq!(model.get_content_as_html(), recovered);
+}
+
 #[test]
 fn set_content_from_html_containing_newlines() {
     let mut model = cm("|");
@@ -169,7 +185,7 @@ fn set_content_from_html_multiple_br() {
 fn clear() {
     let mut model = cm("|");
     model
-        .set_content_from_html(&Utf16String::from("content"))
+        .set_content_from_html(&TestString::from("content"))
         .unwrap();
     model.clear();
     assert_eq!(tx(&model), "|");
@@ -211,6 +227,75 @@ fn set_content_from_markdown_codeblock_with_newlines() {
     assert_eq!(tx(&model), "<pre><code>I am a code block|</code></pre>");
 }
 
+#[test]
+fn set_content_from_markdown_keeps_pipe_tables_as_text() {
+    // There are no table nodes yet, so tables must survive as plain text
+    // rather than losing their structure.
+    let table = "| a | b |\n|---|---|\n| 1 | 2 |";
+    let mut model = cm("|");
+    model.set_content_from_markdown(&utf16(table)).unwrap();
+    assert_eq!(
+        tx(&model),
+        "<p>\\| a \\| b \\|</p><p>\\|---\\|---\\|</p><p>\\| 1 \\| 2 \\||</p>"
+    );
+    assert_eq!(model.get_content_as_markdown(), utf16(table));
+}
+
+#[test]
+fn set_content_from_markdown_codeblock_with_language() {
+    let mut model = cm("|");
+    model
+        .set_content_from_markdown(&utf16("```rust\nlet a = 1;\n```"))
+        .unwrap();
+    assert_eq!(
+        tx(&model),
+        "<pre><code class=\"language-rust\">let a = 1;|</code></pre>"
+    );
+    assert_eq!(
+        model.get_content_as_markdown(),
+        utf16("```rust\nlet a = 1;\n```\n")
+    );
+}
+
+#[test]
+fn set_content_from_html_codeblock_with_language() {
+    let mut model = cm("|");
+    model
+        .set_content_from_html(&utf16(
+            "<pre><code class=\"language-python\">print(1)</code></pre>",
+        ))
+        .unwrap();
+    assert_eq!(
+        tx(&model),
+        "<pre><code class=\"language-python\">print(1)|</code></pre>"
+    );
+}
+
+#[test]
+fn set_content_from_html_codeblock_ignores_other_classes() {
+    let mut model = cm("|");
+    model
+        .set_content_from_html(&utf16(
+            "<pre><code class=\"highlighted\">print(1)</code></pre>",
+        ))
+        .unwrap();
+    assert_eq!(tx(&model), "<pre><code>print(1)|</code></pre>");
+}
+
+#[test]
+fn codeblock_language_is_kept_when_editing() {
+    let mut model = cm("|");
+    model
+        .set_content_from_markdown(&utf16("```rust\nlet a = 1;\n```"))
+        .unwrap();
+    model.enter();
+    model.replace_text(utf16("let b = 2;"));
+    assert_eq!(
+        model.get_content_as_markdown(),
+        utf16("```rust\nlet a = 1;\nlet b = 2;\n```\n")
+    );
+}
+
 #[test]
 fn set_content_from_markdown_codeblock_with_newlines_in_the_middle() {
     let mut model = cm("|");
@@ -237,3 +322,72 @@ fn set_content_from_markdown_one_new_line() {
         .unwrap();
     assert_eq!(tx(&model), "<p>test</p><p>test|</p>");
 }
+
+#[test]
+fn set_content_from_html_preserves_spaces_by_default() {
+    let mut model = cm("|");
+    model
+        .set_content_from_html(&utf16("<p>a   b\n    c</p>"))
+        .unwrap();
+    assert_eq!(tx(&model), "<p>a&nbsp;&nbsp; b c|</p>");
+}
+
+#[test]
+fn set_content_from_html_with_collapsed_whitespace() {
+    let mut model = cm("|");
+    model
+        .set_content_from_html_with_whitespace_policy(
+            &utf16("<p>a   b\n    c\u{A0}\u{A0}d</p>"),
+            WhitespacePolicy::Collapse,
+        )
+        .unwrap();
+    assert_eq!(tx(&model), "<p>a b c&nbsp;&nbsp;d|</p>");
+}
+
+#[test]
+fn set_content_from_html_with_pre_whitespace() {
+    let mut model = cm("|");
+    model
+        .set_content_from_html_with_whitespace_policy(
+            &utf16("<p>a   b\n    c</p>\n<p>d</p>"),
+            WhitespacePolicy::Pre,
+        )
+        .unwrap();
+    assert_eq!(
+        tx(&model),
+        "<p>a&nbsp;&nbsp; b</p><p>&nbsp;&nbsp;&nbsp;&nbsp;c</p><p>d|</p>"
+    );
+}
+
+#[test]
+fn set_content_from_html_with_pre_whitespace_keeps_mentions() {
+    let mut model = cm("|");
+    model
+        .set_content_from_html_with_whitespace_policy(
+            &utf16("hi @room\nbye"),
+            WhitespacePolicy::Pre,
+        )
+        .unwrap();
+    assert_eq!(
+        tx(&model),
+        "<p>hi <a data-mention-type=\"at-room\" href=\"#\" contenteditable=\"false\">@room</a></p><p>bye|</p>"
+    );
+}
+
+#[test]
+fn whitespace_policy_does_not_change_code_blocks() {
+    for policy in [
+        WhitespacePolicy::Preserve,
+        WhitespacePolicy::Collapse,
+        WhitespacePolicy::Pre,
+    ] {
+        let mut model = cm("|");
+        model
+            .set_content_from_html_with_whitespace_policy(
+                &utf16("<pre><code>a  b\n  c</code></pre>"),
+                policy,
+            )
+            .unwrap();
+        assert_eq!(tx(&model), "<pre><code>a  b\n  c|</code></pre>");
+    }
+}
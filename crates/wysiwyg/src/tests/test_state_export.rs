@@ -0,0 +1,138 @@
+// Copyright 2023 The Matrix.org Foundation C.I.C.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use serde_json::json;
+
+use crate::tests::testutils_composer_model::{cm, tx};
+use crate::tests::testutils_conversion::utf16;
+use crate::{ComposerModel, Location, StateImportError};
+
+#[test]
+fn importing_an_exported_state_restores_content_and_selection() {
+    let model = cm("<p>a<strong>{bc}|</strong></p><ul><li>d</li></ul>");
+    let mut imported = ComposerModel::new();
+    imported.import_state(&model.export_state()).unwrap();
+    assert_eq!(tx(&imported), tx(&model));
+}
+
+#[test]
+fn importing_an_exported_state_keeps_the_undo_history() {
+    let mut model = cm("abc|");
+    model.replace_text(utf16("d"));
+    model.replace_text(utf16("e"));
+    model.undo();
+    let mut imported = ComposerModel::new();
+    imported.import_state(&model.export_state()).unwrap();
+    assert_eq!(tx(&imported), "abcd|");
+    imported.undo();
+    assert_eq!(tx(&imported), "abc|");
+    imported.redo();
+    imported.redo();
+    assert_eq!(tx(&imported), "abcde|");
+}
+
+#[test]
+fn importing_an_exported_state_keeps_toggled_formats() {
+    let mut model = cm("abc|");
+    model.bold();
+    let mut imported = ComposerModel::new();
+    imported.import_state(&model.export_state()).unwrap();
+    imported.replace_text(utf16("d"));
+    assert_eq!(tx(&imported), "abc<strong>d|</strong>");
+}
+
+#[test]
+fn importing_an_exported_state_keeps_a_toggled_text_color() {
+    let mut model = cm("abc|");
+    model.set_text_color("#ff0000");
+    let mut imported = ComposerModel::new();
+    imported.import_state(&model.export_state()).unwrap();
+    imported.replace_text(utf16("d"));
+    assert_eq!(
+        tx(&imported),
+        "abc<span data-mx-color=\"#ff0000\">d|</span>"
+    );
+}
+
+#[test]
+fn importing_an_exported_state_keeps_a_toggled_highlight() {
+    let mut model = cm("abc|");
+    model.set_highlight("#ffff00");
+    let mut imported = ComposerModel::new();
+    imported.import_state(&model.export_state()).unwrap();
+    imported.replace_text(utf16("d"));
+    assert_eq!(
+        tx(&imported),
+        "abc<span data-mx-bg-color=\"#ffff00\">d|</span>"
+    );
+}
+
+#[test]
+fn importing_invalid_bytes_changes_nothing() {
+    let mut model = cm("abc|");
+    assert!(matches!(
+        model.import_state(b"not a state"),
+        Err(StateImportError::InvalidFormat(_))
+    ));
+    assert!(matches!(
+        model.import_state(br#"{"version": 99}"#),
+        Err(StateImportError::InvalidFormat(_))
+    ));
+    assert_eq!(tx(&model), "abc|");
+}
+
+#[test]
+fn importing_drops_toggled_custom_formats_which_are_not_allowed() {
+    let mut model = cm("|");
+    model
+        .import_state(&state("abc", 3, 3, &["strong", "blink"]))
+        .unwrap();
+    model.replace_text(utf16("d"));
+    assert_eq!(tx(&model), "abc<strong>d|</strong>");
+}
+
+#[test]
+fn importing_drops_toggled_colors_which_are_not_valid() {
+    let mut model = cm("|");
+    model
+        .import_state(&state("abc", 3, 3, &["data-mx-color:red\"><b>"]))
+        .unwrap();
+    model.replace_text(utf16("d"));
+    assert_eq!(tx(&model), "abcd|");
+}
+
+#[test]
+fn importing_moves_a_selection_past_the_end_of_the_content_to_the_end() {
+    let mut model = cm("|");
+    model.import_state(&state("abc", 2, 99, &[])).unwrap();
+    assert_eq!(model.state.end, Location::from(3));
+    assert_eq!(tx(&model), "ab{c}|");
+}
+
+/// An exported state with no undo history, written by hand.
+fn state(html: &str, start: usize, end: usize, formats: &[&str]) -> Vec<u8> {
+    json!({
+        "version": 1,
+        "state": {
+            "html": html,
+            "start": start,
+            "end": end,
+            "toggled_formats": formats,
+        },
+        "previous_states": [],
+        "next_states": [],
+    })
+    .to_string()
+    .into_bytes()
+}
@@ -12,7 +12,7 @@
 // See the License for the specific language governing permissions and
 // limitations under the License.
 
-use crate::MenuAction;
+use crate::{ComposerUpdate, Location, MenuAction};
 
 use super::testutils_composer_model::{cm, tx};
 
@@ -26,3 +26,71 @@ fn test_replace_text_suggestion() {
     model.replace_text_suggestion("/invite".into(), suggestion, true);
     assert_eq!(tx(&model), "/invite&nbsp;|");
 }
+
+#[test]
+fn test_replace_text_suggestion_keeps_punctuation() {
+    let mut model = cm("|");
+    model.set_keep_suggestion_punctuation(true);
+    let update = model.replace_text("#ric!".into());
+    let MenuAction::Suggestion(suggestion) = update.menu_action else {
+        panic!("No suggestion pattern found")
+    };
+    model.replace_text_suggestion("#rich".into(), suggestion, true);
+    assert_eq!(tx(&model), "#rich!&nbsp;|");
+}
+
+#[test]
+fn replace_text_suggestion_does_nothing_after_the_content_changed() {
+    let mut model = cm("|");
+    let update = model.replace_text("/inv".into());
+    let MenuAction::Suggestion(suggestion) = update.menu_action else {
+        panic!("No suggestion pattern found")
+    };
+    model.select(Location::from(0), Location::from(0));
+    model.replace_text("a ".into());
+    let update =
+        model.replace_text_suggestion("/invite".into(), suggestion, true);
+    assert_eq!(update, ComposerUpdate::keep_at(model.revision()));
+    assert_eq!(tx(&model), "a |/inv");
+}
+
+#[test]
+fn replace_text_suggestion_works_after_the_selection_changed() {
+    let mut model = cm("|");
+    let update = model.replace_text("/inv".into());
+    let MenuAction::Suggestion(suggestion) = update.menu_action else {
+        panic!("No suggestion pattern found")
+    };
+    model.select(Location::from(2), Location::from(2));
+    model.replace_text_suggestion("/invite".into(), suggestion, true);
+    assert_eq!(tx(&model), "/invite&nbsp;|");
+}
+
+#[test]
+fn mention_at_suggestion_is_not_inserted_after_the_content_changed() {
+    let mut model = cm("|");
+    let update = model.replace_text("@alic".into());
+    let MenuAction::Suggestion(suggestion) = update.menu_action else {
+        panic!("No suggestion pattern found")
+    };
+    model.select(Location::from(0), Location::from(0));
+    model.replace_text("hi ".into());
+    model.insert_mention_at_suggestion(
+        "https://matrix.to/#/@alice:matrix.org".into(),
+        "Alice".into(),
+        suggestion.clone(),
+        vec![],
+    );
+    model.insert_at_room_mention_at_suggestion(suggestion, vec![]);
+    assert_eq!(tx(&model), "hi |@alic");
+}
+
+#[test]
+fn suggestion_is_sent_with_the_revision_of_its_update() {
+    let mut model = cm("|");
+    let update = model.replace_text("@alic".into());
+    let MenuAction::Suggestion(suggestion) = update.menu_action else {
+        panic!("No suggestion pattern found")
+    };
+    assert_eq!(suggestion.revision, update.revision);
+}
@@ -0,0 +1,131 @@
+// Copyright 2023 The Matrix.org Foundation C.I.C.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use crate::tests::testutils_composer_model::{cm, tx};
+
+#[test]
+fn tab_indents_a_list_item() {
+    let mut model = cm("<ul><li>a</li><li>b|</li></ul>");
+    model.handle_tab();
+    assert_eq!(tx(&model), "<ul><li><p>a</p><ul><li>b|</li></ul></li></ul>");
+}
+
+#[test]
+fn shift_tab_unindents_a_list_item() {
+    let mut model = cm("<ul><li><p>a</p><ul><li>b|</li></ul></li></ul>");
+    model.handle_shift_tab();
+    assert_eq!(tx(&model), "<ul><li>a</li><li>b|</li></ul>");
+}
+
+#[test]
+fn tab_in_the_first_list_item_does_nothing() {
+    let mut model = cm("<ul><li>a|</li></ul>");
+    model.handle_tab();
+    assert_eq!(tx(&model), "<ul><li>a|</li></ul>");
+}
+
+#[test]
+fn tab_outside_lists_and_code_blocks_does_nothing() {
+    let mut model = cm("<p>a|b</p><blockquote><p>c</p></blockquote>");
+    model.handle_tab();
+    model.handle_shift_tab();
+    assert_eq!(tx(&model), "<p>a|b</p><blockquote><p>c</p></blockquote>");
+    assert!(model.previous_states.is_empty());
+}
+
+#[test]
+fn tab_in_a_code_block_inserts_a_tab() {
+    let mut model = cm("<pre><code>a|b</code></pre>");
+    model.handle_tab();
+    assert_eq!(tx(&model), "<pre><code>a\t|b</code></pre>");
+}
+
+#[test]
+fn tab_in_a_code_block_replaces_a_selection_within_a_line() {
+    let mut model = cm("<pre><code>x\na{b}|c</code></pre>");
+    model.handle_tab();
+    assert_eq!(tx(&model), "<pre><code>x\na\t|c</code></pre>");
+}
+
+#[test]
+fn tab_with_a_selection_in_a_code_block_indents_each_line() {
+    let mut model = cm("<pre><code>a{b\ncd\ne}|f\ngh</code></pre>");
+    model.handle_tab();
+    assert_eq!(
+        tx(&model),
+        "<pre><code>\ta{b\n\tcd\n\te}|f\ngh</code></pre>"
+    );
+}
+
+#[test]
+fn tab_indents_empty_and_formatted_lines_in_a_code_block() {
+    let mut model = cm("<pre><code>{a\n\n<b>b}|</b></code></pre>");
+    model.handle_tab();
+    assert_eq!(tx(&model), "<pre><code>\t{a\n\t\n\t<b>b}|</b></code></pre>");
+}
+
+#[test]
+fn shift_tab_in_a_code_block_removes_a_tab() {
+    let mut model = cm("<pre><code>\ta|b</code></pre>");
+    model.handle_shift_tab();
+    assert_eq!(tx(&model), "<pre><code>a|b</code></pre>");
+}
+
+#[test]
+fn shift_tab_in_a_code_block_removes_up_to_four_spaces() {
+    let mut model = cm("<pre><code>      a|</code></pre>");
+    model.handle_shift_tab();
+    assert_eq!(tx(&model), "<pre><code>  a|</code></pre>");
+}
+
+#[test]
+fn shift_tab_at_the_start_of_an_indented_line_keeps_the_cursor_there() {
+    let mut model = cm("<pre><code>x\n\t|a</code></pre>");
+    model.handle_shift_tab();
+    assert_eq!(tx(&model), "<pre><code>x\n|a</code></pre>");
+}
+
+#[test]
+fn shift_tab_with_a_selection_in_a_code_block_unindents_each_line() {
+    let mut model = cm("<pre><code>\ta{b\ncd\n\te}|f</code></pre>");
+    model.handle_shift_tab();
+    assert_eq!(tx(&model), "<pre><code>a{b\ncd\ne}|f</code></pre>");
+}
+
+#[test]
+fn shift_tab_on_an_unindented_code_line_does_nothing() {
+    let mut model = cm("<pre><code>a|b</code></pre>");
+    model.handle_shift_tab();
+    assert_eq!(tx(&model), "<pre><code>a|b</code></pre>");
+    assert!(model.previous_states.is_empty());
+}
+
+#[test]
+fn tab_in_a_code_block_in_a_list_item_indents_the_code() {
+    let mut model =
+        cm("<ul><li>a</li><li><pre><code>b|</code></pre></li></ul>");
+    model.handle_tab();
+    assert_eq!(
+        tx(&model),
+        "<ul><li>a</li><li><pre><code>b\t|</code></pre></li></ul>"
+    );
+}
+
+#[test]
+fn indenting_code_lines_is_one_undo_step() {
+    let mut model = cm("<pre><code>{a\nb}|</code></pre>");
+    model.handle_tab();
+    model.undo();
+    assert_eq!(tx(&model), "<pre><code>{a\nb}|</code></pre>");
+}
@@ -0,0 +1,174 @@
+// Copyright 2023 The Matrix.org Foundation C.I.C.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+#![cfg(test)]
+
+use crate::tests::testutils_composer_model::{cm, tx};
+use crate::tests::testutils_conversion::utf16;
+use crate::ComposerModel;
+
+#[test]
+fn setting_text_color_wraps_the_selection() {
+    let mut model = cm("a{bc}|d");
+    model.set_text_color("#ff0000");
+    assert_eq!(tx(&model), "a<span data-mx-color=\"#ff0000\">{bc}|</span>d");
+}
+
+#[test]
+fn setting_text_color_replaces_another_color() {
+    let mut model = cm("<span data-mx-color=\"#ff0000\">a{bc}|d</span>");
+    model.set_text_color("#00ff00");
+    assert_eq!(
+        tx(&model),
+        "<span data-mx-color=\"#ff0000\">a</span>\
+         <span data-mx-color=\"#00ff00\">{bc}|</span>\
+         <span data-mx-color=\"#ff0000\">d</span>"
+    );
+}
+
+#[test]
+fn setting_the_same_text_color_keeps_it() {
+    let mut model = cm("<span data-mx-color=\"#ff0000\">a{bc}|d</span>");
+    model.set_text_color("#ff0000");
+    assert_eq!(tx(&model), "<span data-mx-color=\"#ff0000\">a{bc}|d</span>");
+}
+
+#[test]
+fn setting_text_color_over_two_colors_replaces_both() {
+    let mut model = cm("<span data-mx-color=\"#ff0000\">{a</span>\
+         <span data-mx-color=\"#00ff00\">b}|</span>");
+    model.set_text_color("#0000ff");
+    assert_eq!(tx(&model), "<span data-mx-color=\"#0000ff\">{ab}|</span>");
+}
+
+#[test]
+fn setting_text_color_keeps_other_formatting() {
+    let mut model = cm("<strong>a{b</strong>c}|");
+    model.set_text_color("#ff0000");
+    assert_eq!(
+        tx(&model),
+        "<strong>a<span data-mx-color=\"#ff0000\">{b</span></strong>\
+         <span data-mx-color=\"#ff0000\">c}|</span>"
+    );
+}
+
+#[test]
+fn removing_text_color_unwraps_the_selection() {
+    let mut model = cm("<span data-mx-color=\"#ff0000\">a{bc}|d</span>");
+    model.remove_text_color();
+    assert_eq!(
+        tx(&model),
+        "<span data-mx-color=\"#ff0000\">a</span>{bc}|\
+         <span data-mx-color=\"#ff0000\">d</span>"
+    );
+}
+
+#[test]
+fn removing_text_color_without_color_does_nothing() {
+    let mut model = cm("a{bc}|d");
+    model.remove_text_color();
+    assert_eq!(tx(&model), "a{bc}|d");
+}
+
+#[test]
+fn text_color_set_at_the_cursor_applies_to_typed_text() {
+    let mut model = cm("a|");
+    model.set_text_color("#ff0000");
+    model.replace_text(utf16("b"));
+    assert_eq!(tx(&model), "a<span data-mx-color=\"#ff0000\">b|</span>");
+}
+
+#[test]
+fn text_color_changed_at_the_cursor_applies_to_typed_text() {
+    let mut model = cm("<span data-mx-color=\"#ff0000\">a|</span>");
+    model.set_text_color("#00ff00");
+    model.replace_text(utf16("b"));
+    assert_eq!(
+        tx(&model),
+        "<span data-mx-color=\"#ff0000\">a</span>\
+         <span data-mx-color=\"#00ff00\">b|</span>"
+    );
+}
+
+#[test]
+fn text_color_removed_at_the_cursor_is_not_applied_to_typed_text() {
+    let mut model = cm("<span data-mx-color=\"#ff0000\">a|</span>");
+    model.remove_text_color();
+    model.replace_text(utf16("b"));
+    assert_eq!(tx(&model), "<span data-mx-color=\"#ff0000\">a</span>b|");
+}
+
+#[test]
+fn setting_text_color_can_be_undone() {
+    let mut model = cm("a{bc}|d");
+    model.set_text_color("#ff0000");
+    model.undo();
+    assert_eq!(tx(&model), "a{bc}|d");
+}
+
+#[test]
+fn font_tags_are_parsed_as_text_color() {
+    let model = cm("<font data-mx-color=\"#ff0000\">a</font>\
+         <font color=\"#00ff00\">b</font>|");
+    assert_eq!(
+        tx(&model),
+        "<span data-mx-color=\"#ff0000\">a</span>\
+         <span data-mx-color=\"#00ff00\">b|</span>"
+    );
+}
+
+#[test]
+fn text_color_round_trips_through_html() {
+    let mut model = ComposerModel::new();
+    model
+        .set_content_from_html(&utf16(
+            "a<span data-mx-color=\"#ff0000\">b</span>",
+        ))
+        .unwrap();
+    assert_eq!(
+        model.get_content_as_message_html(),
+        "a<span data-mx-color=\"#ff0000\">b</span>"
+    );
+}
+
+#[test]
+fn text_color_is_kept_as_html_in_markdown() {
+    let model = cm("a<span data-mx-color=\"#ff0000\">b</span>|");
+    assert_eq!(
+        model.get_content_as_markdown(),
+        "a<span data-mx-color=\"#ff0000\">b</span>"
+    );
+}
+
+#[test]
+fn setting_text_color_ignores_invalid_colors() {
+    let mut model = cm("{a}|");
+    for color in ["\"red\"", "red", "#ff00", "#ff00000", "#gg0000"] {
+        model.set_text_color(color);
+        assert_eq!(tx(&model), "{a}|");
+    }
+}
+
+#[test]
+fn invalid_text_colors_are_dropped_when_parsing() {
+    let mut model = ComposerModel::new();
+    model
+        .set_content_from_html(&utf16(
+            "a<span data-mx-color=\"&quot;red&quot;\">b</span>\
+             <font color=\"red\">c</font>",
+        ))
+        .unwrap();
+    // Like other spans and fonts the editor doesn't support
+    assert_eq!(model.get_content_as_message_html(), "a");
+}
@@ -0,0 +1,87 @@
+// Copyright 2023 The Matrix.org Foundation C.I.C.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use crate::tests::testutils_composer_model::TestString;
+
+use crate::ComposerModel;
+
+#[test]
+fn text() {
+    assert_to_fallback("abc def", "abc def");
+}
+
+#[test]
+fn text_is_not_escaped() {
+    assert_to_fallback("# 1. a_b *c*", "# 1. a_b *c*");
+}
+
+#[test]
+fn formatting() {
+    assert_to_fallback("<strong>bold</strong>", "*bold*");
+    assert_to_fallback("<em>italic</em>", "_italic_");
+    assert_to_fallback("<del>gone</del>", "~~gone~~");
+    assert_to_fallback("<u>underlined</u>", "underlined");
+    assert_to_fallback("<code>a ` b</code>", "`a ` b`");
+    assert_to_fallback(
+        "<em><strong>both</strong></em> plain",
+        "_*both*_ plain",
+    );
+}
+
+#[test]
+fn links() {
+    assert_to_fallback(
+        r#"<a href="https://matrix.org">Matrix</a>"#,
+        "Matrix (https://matrix.org)",
+    );
+    assert_to_fallback(
+        r#"<a href="https://matrix.org">https://matrix.org</a>"#,
+        "https://matrix.org",
+    );
+    assert_to_fallback(
+        r#"<a href="https://www.matrix.org">www.matrix.org</a>"#,
+        "www.matrix.org",
+    );
+    assert_to_fallback(
+        r#"<a href="mailto:test@matrix.org">test@matrix.org</a>"#,
+        "test@matrix.org",
+    );
+}
+
+#[test]
+fn mentions_use_display_names() {
+    assert_to_fallback(
+        r#"<a href="https://matrix.to/#/@alice:matrix.org">Alice</a> hi"#,
+        "Alice hi",
+    );
+    assert_to_fallback("@room hi", "@room hi");
+}
+
+#[test]
+fn blocks() {
+    assert_to_fallback(
+        "<blockquote><p>quote</p></blockquote><p>reply</p>",
+        "> quote\n\nreply",
+    );
+    assert_to_fallback("<ul><li>a</li><li>b</li></ul>", "* a\n* b");
+    assert_to_fallback(
+        "<pre><code>let a = *b;</code></pre>",
+        "```\nlet a = *b;\n```\n",
+    );
+}
+
+fn assert_to_fallback(html: &str, expected: &str) {
+    let model: ComposerModel<TestString> = ComposerModel::from_html(html, 0, 0);
+    assert_eq!(model.get_content_as_fallback_text(), expected);
+}
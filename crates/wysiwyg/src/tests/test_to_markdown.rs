@@ -12,11 +12,12 @@
 // See the License for the specific language governing permissions and
 // limitations under the License.
 
+use crate::tests::testutils_composer_model::TestString;
 use crate::{
     dom::parser::markdown::MarkdownHTMLParser,
-    tests::testutils_composer_model::tx, ComposerModel, ToMarkdown,
+    tests::testutils_composer_model::tx, ComposerModel, MarkdownOptions,
+    ToMarkdown,
 };
-use widestring::Utf16String;
 
 use super::testutils_composer_model::cm;
 
@@ -28,16 +29,60 @@ fn text() {
     assert_to_message_md("abc   def", "abc   def");
 }
 
-// Markdown output contains unescaped special characters but this is ok.
-// Athough the plain text content of a Matrix message _may_ contain markdown,
-// the format is not specified. It is more important for the message to be
-// human readable than valid or machine readable markdown.
+// Characters that would change the meaning of the Markdown are escaped, so
+// that e.g. a plain text body doesn't unexpectedly gain emphasis or lists.
 #[test]
 fn text_with_ascii_punctuation() {
-    assert_to_md_no_roundtrip(r"<em>**b**</em>", r"***b***");
+    assert_to_md_no_roundtrip(r"<em>**b**</em>", r"*\*\*b\*\**");
     assert_to_md_no_roundtrip(
+        r##"!&quot;#$%&amp;'()*+,-./:;&lt;=&gt;?@[\]^_`{|}~"##,
+        r##"!"#$%&'()\*+,-./:;\<=>?@\[\\\]^\_\`{|}\~"##,
+    );
+}
+
+#[test]
+fn text_with_ascii_punctuation_without_escaping() {
+    assert_to_md_with_options_no_roundtrip(
         r##"!&quot;#$%&amp;'()*+,-./:;&lt;=&gt;?@[\]^_`{|}~"##,
         r##"!"#$%&'()*+,-./:;<=>?@[\]^_`{|}~"##,
+        MarkdownOptions::NO_ESCAPING,
+    );
+}
+
+#[test]
+fn text_with_all_punctuation_escaped() {
+    assert_to_md_with_options_no_roundtrip(
+        "a-b (c) 1.",
+        r"a\-b \(c\) 1\.",
+        MarkdownOptions::ESCAPE_ALL_PUNCTUATION,
+    );
+}
+
+#[test]
+fn text_looking_like_blocks_is_escaped_at_line_start() {
+    assert_to_message_md("# not a heading", r"\# not a heading");
+    assert_to_message_md("- not a list", r"\- not a list");
+    assert_to_message_md("+ not a list", r"\+ not a list");
+    assert_to_message_md("12. not a list", r"12\. not a list");
+    assert_to_message_md("&gt; not a quote", r"\> not a quote");
+    assert_to_md_no_roundtrip("<p>a</p><p>- b</p>", "a\n\\- b");
+    assert_to_md_no_roundtrip(
+        "<blockquote><p># a</p></blockquote>",
+        "> \\# a\n",
+    );
+}
+
+#[test]
+fn text_looking_like_blocks_is_not_escaped_mid_line() {
+    assert_to_message_md("a # b - c 1. d &gt; e", "a # b - c 1. d > e");
+    assert_to_md_no_roundtrip("<em>a</em># b", "*a*# b");
+}
+
+#[test]
+fn text_looking_like_entities_is_escaped() {
+    assert_to_message_md(
+        "&amp;amp; &amp;#123; a &amp; b",
+        r"\&amp; \&#123; a & b",
     );
 }
 
@@ -169,6 +214,14 @@ fn text_with_code_block() {
     );
 }
 
+#[test]
+fn text_with_code_block_with_language() {
+    assert_to_message_md(
+        "<pre><code class=\"language-rust\">let a = 1;</code></pre>",
+        "```rust\nlet a = 1;\n```\n",
+    );
+}
+
 #[test]
 fn link() {
     assert_to_message_md(r#"<a href="url">abc</a>"#, "[abc](<url>)");
@@ -282,6 +335,19 @@ fn assert_to_md_no_roundtrip(html: &str, expected_markdown: &str) {
     assert_eq!(markdown, expected_markdown);
 }
 
+fn assert_to_md_with_options_no_roundtrip(
+    html: &str,
+    expected_markdown: &str,
+    options: MarkdownOptions,
+) {
+    let markdown: TestString = ComposerModel::from_html(html, 0, 0)
+        .state
+        .dom
+        .to_message_markdown_with_options(options)
+        .unwrap();
+    assert_eq!(markdown, expected_markdown);
+}
+
 fn assert_to_message_md(html: &str, expected_markdown: &str) {
     let markdown = to_message_markdown(html);
     assert_eq!(markdown, expected_markdown);
@@ -307,7 +373,7 @@ fn assert_to_composer_md(html: &str, expected_markdown: &str) {
     assert_eq!(html, expected_html);
 }
 
-fn to_message_markdown(html: &str) -> Utf16String {
+fn to_message_markdown(html: &str) -> TestString {
     let markdown = ComposerModel::from_html(html, 0, 0)
         .state
         .dom
@@ -317,7 +383,7 @@ fn to_message_markdown(html: &str) -> Utf16String {
     markdown.unwrap()
 }
 
-fn to_composer_markdown(html: &str) -> Utf16String {
+fn to_composer_markdown(html: &str) -> TestString {
     let markdown = ComposerModel::from_html(html, 0, 0).state.dom.to_markdown();
     assert!(markdown.is_ok());
 
@@ -83,3 +83,45 @@ fn only_outputs_href_inner_text_for_at_room_mention() {
     let message_output = model.get_content_as_message_html();
     assert_eq!(message_output, "@room\u{a0}");
 }
+
+#[test]
+fn safe_link_attributes_replace_target_and_rel_of_links() {
+    let mut model = cm(
+        "<a target=\"_top\" rel=\"opener\" href=\"https://element.io\">a</a> \
+        <a href=\"https://matrix.org\">b|</a>",
+    );
+    model.set_safe_link_attributes(true);
+    assert_eq!(
+        model.get_content_as_message_html(),
+        "<a href=\"https://element.io\" target=\"_blank\" rel=\"noreferrer noopener\">a</a> \
+        <a href=\"https://matrix.org\" target=\"_blank\" rel=\"noreferrer noopener\">b</a>"
+    );
+    // Only the message HTML is changed
+    assert_eq!(
+        tx(&model),
+        "<a target=\"_top\" rel=\"opener\" href=\"https://element.io\">a</a> \
+        <a href=\"https://matrix.org\">b|</a>"
+    );
+}
+
+#[test]
+fn safe_link_attributes_escape_the_other_attributes() {
+    let mut model = cm(
+        "<a href=\"https://matrix.org\" title=\"&quot;x&quot; &amp; y\">a|</a>",
+    );
+    model.set_safe_link_attributes(true);
+    assert_eq!(
+        model.get_content_as_message_html(),
+        "<a title=\"&quot;x&quot; &amp; y\" href=\"https://matrix.org\" \
+        target=\"_blank\" rel=\"noreferrer noopener\">a</a>"
+    );
+}
+
+#[test]
+fn safe_link_attributes_are_disabled_by_default() {
+    let model = cm("<a href=\"https://element.io\">a|</a>");
+    assert_eq!(
+        model.get_content_as_message_html(),
+        "<a href=\"https://element.io\">a</a>"
+    );
+}
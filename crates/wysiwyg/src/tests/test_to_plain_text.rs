@@ -12,9 +12,9 @@
 // See the License for the specific language governing permissions and
 // limitations under the License.
 
-use crate::{dom::to_plain_text::ToPlainText, ComposerModel};
+use crate::tests::testutils_composer_model::TestString;
+use crate::{dom::to_plain_text::ToPlainText, ComposerModel, PlainTextOptions};
 use indoc::indoc;
-use widestring::Utf16String;
 
 #[test]
 fn text() {
@@ -251,12 +251,88 @@ fn blocks() {
     );
 }
 
+#[test]
+fn preview_strips_formatting_and_resolves_mentions() {
+    assert_preview(
+        r#"<p><b>Hi</b> <a href="https://matrix.to/#/@alice:matrix.org">Alice</a></p><ul><li>one</li></ul>"#,
+        100,
+        "Hi Alice one",
+    );
+}
+
+#[test]
+fn preview_collapses_whitespace() {
+    assert_preview("<p>a&nbsp;&nbsp; b</p><p>&nbsp;</p><p>c</p>", 100, "a b c");
+}
+
+#[test]
+fn preview_shorter_than_limit_is_not_cut() {
+    assert_preview("abcde", 5, "abcde");
+}
+
+#[test]
+fn preview_longer_than_limit_ends_with_ellipsis() {
+    assert_preview("abcdef", 5, "abcd…");
+    assert_preview("abc def", 5, "abc…");
+    assert_preview("abcdef", 1, "…");
+    assert_preview("abcdef", 0, "");
+}
+
+#[test]
+fn preview_is_cut_on_grapheme_boundaries() {
+    // A family emoji is several code points joined into one grapheme
+    assert_preview("👨‍👩‍👧👨‍👩‍👧👨‍👩‍👧", 2, "👨‍👩‍👧…");
+    assert_preview("e\u{301}e\u{301}e\u{301}", 3, "e\u{301}e\u{301}e\u{301}");
+}
+
+#[test]
+fn plain_text_options_collapse_and_trim_whitespace() {
+    let model: ComposerModel<TestString> =
+        ComposerModel::from_html("<p> abc </p><p>def  ghi</p>", 0, 0);
+    let text = |collapse_whitespace, trim| {
+        model
+            .get_content_as_plain_text_with_options(PlainTextOptions {
+                collapse_whitespace,
+                trim,
+            })
+            .to_string()
+    };
+    assert_eq!(text(false, false), model.get_content_as_plain_text());
+    assert_eq!(text(true, false), " abc def ghi ");
+    assert_eq!(text(false, true), "abc \ndef  ghi");
+    assert_eq!(text(true, true), "abc def ghi");
+}
+
+#[test]
+fn character_count_counts_graphemes() {
+    let model: ComposerModel<TestString> =
+        ComposerModel::from_html("a<strong>b👨‍👩‍👧</strong>e\u{301}", 0, 0);
+    assert_eq!(model.get_character_count(), 4);
+}
+
+#[test]
+fn word_count_ignores_formatting_and_punctuation() {
+    let model: ComposerModel<TestString> = ComposerModel::from_html(
+        "<p>Hello, <em>wide</em> world!</p><ul><li>don't stop</li></ul>",
+        0,
+        0,
+    );
+    assert_eq!(model.get_word_count(), 5);
+    let empty: ComposerModel<TestString> = ComposerModel::from_html("", 0, 0);
+    assert_eq!(empty.get_word_count(), 0);
+}
+
+fn assert_preview(html: &str, max_chars: usize, expected: &str) {
+    let model: ComposerModel<TestString> = ComposerModel::from_html(html, 0, 0);
+    assert_eq!(model.get_preview(max_chars), expected);
+}
+
 fn assert_to_plain(html: &str, expected_plain_text: &str) {
     let plain_text = to_plain_text(html);
     assert_eq!(plain_text, expected_plain_text);
 }
 
-fn to_plain_text(html: &str) -> Utf16String {
+fn to_plain_text(html: &str) -> TestString {
     ComposerModel::from_html(html, 0, 0)
         .state
         .dom
@@ -12,7 +12,7 @@
 // See the License for the specific language governing permissions and
 // limitations under the License.
 
-use widestring::Utf16String;
+use crate::tests::testutils_composer_model::TestString;
 
 use crate::tests::testutils_composer_model::cm;
 use crate::ToRawText;
@@ -60,6 +60,6 @@ fn tags_are_stripped_from_raw_text() {
     assert_eq!(raw("|emptynodes<b><i></i></b>"), "emptynodes");
 }
 
-fn raw(s: &str) -> Utf16String {
+fn raw(s: &str) -> TestString {
     cm(s).state.dom.to_raw_text()
 }
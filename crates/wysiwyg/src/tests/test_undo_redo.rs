@@ -15,7 +15,8 @@
 use crate::tests::testutils_composer_model::{cm, tx};
 
 use crate::dom::nodes::{DomNode, TextNode};
-use crate::{InlineFormatType, Location};
+use crate::InlineFormatType::{Bold, Italic};
+use crate::{ComposerUpdate, InlineFormatType, Location, MenuState};
 
 use crate::tests::testutils_conversion::utf16;
 
@@ -189,3 +190,107 @@ fn replacing_text_with_newlines_only_adds_one_to_undo_stack() {
     model.undo();
     assert_eq!(tx(&model), "abc|");
 }
+
+#[test]
+fn undo_and_redo_depths_count_history_steps() {
+    let mut model = cm("|");
+    assert_eq!((model.undo_depth(), model.redo_depth()), (0, 0));
+    model.replace_text(utf16("a"));
+    model.replace_text(utf16("b"));
+    assert_eq!((model.undo_depth(), model.redo_depth()), (2, 0));
+    model.undo();
+    assert_eq!((model.undo_depth(), model.redo_depth()), (1, 1));
+    model.replace_text(utf16("c"));
+    assert_eq!((model.undo_depth(), model.redo_depth()), (2, 0));
+}
+
+#[test]
+fn menu_state_update_contains_history_depths() {
+    let mut model = cm("|");
+    model.replace_text(utf16("a"));
+    model.replace_text(utf16("b"));
+    let update = model.undo();
+    let MenuState::Update(menu_state) = update.menu_state else {
+        panic!("Expected a menu state update");
+    };
+    assert_eq!((menu_state.undo_depth, menu_state.redo_depth), (1, 1));
+}
+
+#[test]
+fn menu_state_is_updated_when_only_history_depth_changes() {
+    let mut model = cm("|");
+    model.replace_text(utf16("a"));
+    let update = model.replace_text(utf16("b"));
+    let MenuState::Update(menu_state) = update.menu_state else {
+        panic!("Expected a menu state update");
+    };
+    assert_eq!(menu_state.undo_depth, 2);
+}
+
+#[test]
+fn undo_last_formatting_keeps_text_typed_afterwards() {
+    let mut model = cm("aa{bb}|");
+    model.bold();
+    model.select(Location::from(4), Location::from(4));
+    model.replace_text(utf16("cc"));
+    model.undo_last_formatting();
+    assert_eq!(tx(&model), "aabb<strong>cc|</strong>");
+}
+
+#[test]
+fn undo_last_formatting_keeps_text_typed_before_the_formatted_text() {
+    let mut model = cm("aa{bb}|");
+    model.bold();
+    model.select(Location::from(0), Location::from(0));
+    model.replace_text(utf16("cc"));
+    model.undo_last_formatting();
+    assert_eq!(tx(&model), "cc|aabb");
+}
+
+#[test]
+fn undo_last_formatting_only_reverts_the_latest_formatting() {
+    let mut model = cm("aabb|");
+    model.format_in(Location::from(0), Location::from(2), Bold);
+    model.format_in(Location::from(2), Location::from(4), Italic);
+    model.replace_text(utf16("c"));
+    model.undo_last_formatting();
+    assert_eq!(tx(&model), "<strong>aa</strong>bb<em>c|</em>");
+}
+
+#[test]
+fn undo_last_formatting_restores_removed_formatting() {
+    let mut model = cm("<strong>aabb</strong>|");
+    model.remove_format_in(Location::from(0), Location::from(2), Bold);
+    model.replace_text(utf16("c"));
+    model.undo_last_formatting();
+    assert_eq!(tx(&model), "<strong>aabbc|</strong>");
+}
+
+#[test]
+fn undo_last_formatting_can_itself_be_undone() {
+    let mut model = cm("aa{bb}|");
+    model.bold();
+    model.undo_last_formatting();
+    assert_eq!(tx(&model), "aa{bb}|");
+    model.undo();
+    assert_eq!(tx(&model), "aa<strong>{bb}|</strong>");
+}
+
+#[test]
+fn undo_last_formatting_does_nothing_if_formatted_text_was_edited() {
+    let mut model = cm("aa{bb}|");
+    model.bold();
+    model.select(Location::from(3), Location::from(3));
+    model.replace_text(utf16("c"));
+    model.undo_last_formatting();
+    assert_eq!(tx(&model), "aa<strong>bc|b</strong>");
+}
+
+#[test]
+fn undo_last_formatting_does_nothing_without_formatting_changes() {
+    let mut model = cm("|");
+    model.replace_text(utf16("aa"));
+    let update = model.undo_last_formatting();
+    assert_eq!(update, ComposerUpdate::keep_at(model.revision()));
+    assert_eq!(tx(&model), "aa|");
+}
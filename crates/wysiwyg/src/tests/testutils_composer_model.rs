@@ -12,17 +12,31 @@
 // See the License for the specific language governing permissions and
 // limitations under the License.
 
+#[cfg(not(any(feature = "test-utf32", feature = "test-rope")))]
 use widestring::Utf16String;
+#[cfg(feature = "test-utf32")]
+use widestring::Utf32String;
 
 use crate::{ComposerModel, Location};
 
+/// The string type the composer model tests run with. The platforms use
+/// UTF-16, but building with the `test-utf32` feature runs the tests over
+/// `Utf32String` instead, to catch bugs that depend on the encoding, and
+/// `test-rope` runs them over [crate::RopeString].
+#[cfg(not(any(feature = "test-utf32", feature = "test-rope")))]
+pub type TestString = Utf16String;
+#[cfg(feature = "test-utf32")]
+pub type TestString = Utf32String;
+#[cfg(all(feature = "test-rope", not(feature = "test-utf32")))]
+pub type TestString = crate::RopeString;
+
 /// Short wrapper around [ComposerModel::from_example_format].
-pub fn cm(text: &str) -> ComposerModel<Utf16String> {
-    ComposerModel::<Utf16String>::from_example_format(text)
+pub fn cm(text: &str) -> ComposerModel<TestString> {
+    ComposerModel::<TestString>::from_example_format(text)
 }
 
 /// Short wrapper around [ComposerModel::to_example_format].
-pub fn tx(model: &ComposerModel<Utf16String>) -> String {
+pub fn tx(model: &ComposerModel<TestString>) -> String {
     model.to_example_format()
 }
 
@@ -35,6 +49,6 @@ pub(crate) fn restore_whitespace(text: &str) -> String {
     text.replace("&nbsp;", " ").replace('\u{A0}', " ")
 }
 
-pub(crate) fn restore_whitespace_u16(text: &Utf16String) -> Utf16String {
-    Utf16String::from(restore_whitespace(&text.to_string()))
+pub(crate) fn restore_whitespace_u16(text: &TestString) -> TestString {
+    TestString::from(restore_whitespace(&text.to_string()))
 }
@@ -12,8 +12,8 @@
 // See the License for the specific language governing permissions and
 // limitations under the License.
 
-use widestring::Utf16String;
+use crate::tests::testutils_composer_model::TestString;
 
-pub fn utf16(s: &str) -> Utf16String {
-    Utf16String::from_str(s)
+pub fn utf16(s: &str) -> TestString {
+    TestString::from(s)
 }
@@ -12,7 +12,7 @@
 // See the License for the specific language governing permissions and
 // limitations under the License.
 
-use widestring::Utf16String;
+use crate::tests::testutils_composer_model::TestString;
 
 use crate::dom::nodes::DomNode;
 use crate::dom::Dom;
@@ -21,14 +21,14 @@ use crate::DomHandle;
 use crate::tests::testutils_conversion::utf16;
 
 pub fn dom<'a>(
-    children: impl IntoIterator<Item = &'a DomNode<Utf16String>>,
-) -> Dom<Utf16String> {
+    children: impl IntoIterator<Item = &'a DomNode<TestString>>,
+) -> Dom<TestString> {
     Dom::new(clone_children(children))
 }
 
 pub fn a<'a>(
-    children: impl IntoIterator<Item = &'a DomNode<Utf16String>>,
-) -> DomNode<Utf16String> {
+    children: impl IntoIterator<Item = &'a DomNode<TestString>>,
+) -> DomNode<TestString> {
     DomNode::new_link(
         utf16("https://element.io"),
         clone_children(children),
@@ -37,30 +37,30 @@ pub fn a<'a>(
 }
 
 pub fn b<'a>(
-    children: impl IntoIterator<Item = &'a DomNode<Utf16String>>,
-) -> DomNode<Utf16String> {
+    children: impl IntoIterator<Item = &'a DomNode<TestString>>,
+) -> DomNode<TestString> {
     DomNode::new_formatting_from_tag(utf16("b"), clone_children(children))
 }
 
 pub fn i<'a>(
-    children: impl IntoIterator<Item = &'a DomNode<Utf16String>>,
-) -> DomNode<Utf16String> {
+    children: impl IntoIterator<Item = &'a DomNode<TestString>>,
+) -> DomNode<TestString> {
     DomNode::new_formatting_from_tag(utf16("i"), clone_children(children))
 }
 
 pub fn i_c<'a>(
-    children: impl IntoIterator<Item = &'a DomNode<Utf16String>>,
-) -> DomNode<Utf16String> {
+    children: impl IntoIterator<Item = &'a DomNode<TestString>>,
+) -> DomNode<TestString> {
     DomNode::new_formatting_from_tag(utf16("code"), clone_children(children))
 }
 
 fn clone_children<'a>(
-    children: impl IntoIterator<Item = &'a DomNode<Utf16String>>,
-) -> Vec<DomNode<Utf16String>> {
+    children: impl IntoIterator<Item = &'a DomNode<TestString>>,
+) -> Vec<DomNode<TestString>> {
     children.into_iter().cloned().collect()
 }
 
-pub fn tn(data: &str) -> DomNode<Utf16String> {
+pub fn tn(data: &str) -> DomNode<TestString> {
     DomNode::new_text(utf16(data))
 }
 
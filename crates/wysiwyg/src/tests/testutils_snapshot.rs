@@ -0,0 +1,152 @@
+// Copyright 2023 The Matrix.org Foundation C.I.C.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Snapshots of a model for tests where one line of HTML is hard to read,
+//! e.g. deeply nested lists and quotes. A snapshot shows the HTML with one
+//! block per line, the Dom tree and the selection:
+//!
+//! ```text
+//! html:
+//! <blockquote>
+//!   <p>a|</p>
+//! </blockquote>
+//! tree:
+//! └>blockquote
+//!   └>p
+//!     └>"a"
+//! selection: 1..1
+//! ```
+
+use crate::tests::testutils_composer_model::{tx, TestString};
+use crate::ComposerModel;
+
+/// Tags which start a new line in the HTML part of a snapshot.
+const BLOCK_TAGS: [&str; 6] = ["blockquote", "li", "ol", "p", "pre", "ul"];
+
+/// A snapshot of `model`, as described at the top of this file.
+pub(crate) fn snapshot(model: &ComposerModel<TestString>) -> String {
+    let start: usize = model.state.start.into();
+    let end: usize = model.state.end.into();
+    format!(
+        "html:\n{}\ntree:\n{}\nselection: {}..{}",
+        pretty_html(&tx(model)),
+        model.to_tree().to_string().trim(),
+        start,
+        end,
+    )
+}
+
+/// `html` with each block tag on a line of its own, indented by how deeply
+/// it is nested. Blocks holding inline content stay on one line, with any
+/// newlines in them, e.g. in code blocks, written as `\n`.
+fn pretty_html(html: &str) -> String {
+    let html = html.replace('\n', "\\n");
+    let mut lines: Vec<String> = Vec::new();
+    let mut depth = 0;
+    let mut after_block_end = true;
+    let mut rest = html.as_str();
+    while !rest.is_empty() {
+        let (token, tail) = match rest.find('<') {
+            Some(0) => {
+                let end = rest.find('>').map_or(rest.len(), |i| i + 1);
+                rest.split_at(end)
+            }
+            Some(i) => rest.split_at(i),
+            None => (rest, ""),
+        };
+        rest = tail;
+        let name = token
+            .trim_start_matches('<')
+            .trim_end_matches('>')
+            .split_whitespace()
+            .next()
+            .unwrap_or_default();
+        if let Some(name) = name.strip_prefix('/') {
+            if BLOCK_TAGS.contains(&name) {
+                depth -= 1;
+                if after_block_end {
+                    lines.push(format!("{}{token}", "  ".repeat(depth)));
+                } else if let Some(line) = lines.last_mut() {
+                    line.push_str(token);
+                }
+                after_block_end = true;
+                continue;
+            }
+        } else if token.starts_with('<') && BLOCK_TAGS.contains(&name) {
+            lines.push(format!("{}{token}", "  ".repeat(depth)));
+            depth += 1;
+            after_block_end = false;
+            continue;
+        }
+        match lines.last_mut() {
+            Some(line) if !after_block_end => line.push_str(token),
+            _ => {
+                lines.push(format!("{}{token}", "  ".repeat(depth)));
+                after_block_end = false;
+            }
+        }
+    }
+    lines.join("\n")
+}
+
+/// The lines of `expected` and `actual` with `-` before the expected ones
+/// and `+` before the actual ones where they differ.
+pub(crate) fn snapshot_diff(expected: &str, actual: &str) -> String {
+    let expected: Vec<&str> = expected.lines().collect();
+    let actual: Vec<&str> = actual.lines().collect();
+    let mut diff = Vec::new();
+    for i in 0..expected.len().max(actual.len()) {
+        match (expected.get(i), actual.get(i)) {
+            (Some(e), Some(a)) if e == a => diff.push(format!("  {e}")),
+            (e, a) => {
+                diff.extend(e.map(|e| format!("- {e}")));
+                diff.extend(a.map(|a| format!("+ {a}")));
+            }
+        }
+    }
+    diff.join("\n")
+}
+
+/// Assert that the [snapshot] of a model matches an inline one, with its
+/// common indentation removed, like `insta`'s inline snapshots:
+///
+/// ```ignore
+/// assert_snapshot!(model, @r#"
+///     html:
+///     <p>a|</p>
+///     tree:
+///     └>p
+///       └>"a"
+///     selection: 1..1
+/// "#);
+/// ```
+///
+/// On failure, the lines that differ are shown with `-` and `+` before them.
+macro_rules! assert_snapshot {
+    ($model:expr, @$expected:literal) => {{
+        let actual = $crate::tests::testutils_snapshot::snapshot(&$model);
+        let expected = indoc::indoc!($expected);
+        if actual.trim() != expected.trim() {
+            panic!(
+                "Snapshot does not match:\n{}\n\nActual snapshot:\n{}",
+                $crate::tests::testutils_snapshot::snapshot_diff(
+                    expected.trim(),
+                    actual.trim(),
+                ),
+                actual,
+            );
+        }
+    }};
+}
+pub(crate) use assert_snapshot;
@@ -12,7 +12,13 @@
 // See the License for the specific language governing permissions and
 // limitations under the License.
 
-use crate::{dom::UnicodeString, Location};
+use std::fmt::{self, Debug, Display};
+use std::sync::Arc;
+
+use once_cell::sync::OnceCell;
+
+use crate::dom::Dom;
+use crate::{dom::UnicodeString, Location, ToHtml};
 
 #[derive(Debug, Clone, PartialEq, Eq)]
 pub enum TextUpdate<S>
@@ -29,9 +35,13 @@ pub struct ReplaceAll<S>
 where
     S: UnicodeString,
 {
-    pub replacement_html: S,
+    pub replacement_html: ReplacementHtml<S>,
     pub start: Location,
     pub end: Location,
+    /// Where the content that changed since the last update starts and
+    /// ends, in the new content. Equal when nothing changed.
+    pub changed_start: Location,
+    pub changed_end: Location,
 }
 
 #[derive(Debug, Clone, PartialEq, Eq)]
@@ -39,3 +49,128 @@ pub struct Selection {
     pub start: Location,
     pub end: Location,
 }
+
+/// The new content of a [ReplaceAll] update. It keeps a snapshot of the
+/// Dom, which shares its nodes with the model's until either is changed,
+/// and only serializes it to HTML the first time the HTML is read, so
+/// consumers which only look at the tree never pay for serializing it.
+#[derive(Clone)]
+pub struct ReplacementHtml<S>
+where
+    S: UnicodeString,
+{
+    /// The content to serialize, or None if the HTML was given up front
+    dom: Option<Dom<S>>,
+    html: Arc<OnceCell<S>>,
+}
+
+impl<S> ReplacementHtml<S>
+where
+    S: UnicodeString,
+{
+    pub(crate) fn new(dom: Dom<S>) -> Self {
+        Self {
+            dom: Some(dom),
+            html: Arc::new(OnceCell::new()),
+        }
+    }
+
+    /// Content which was already serialized to `html`.
+    pub fn from_html(html: S) -> Self {
+        Self {
+            dom: None,
+            html: Arc::new(OnceCell::with_value(html)),
+        }
+    }
+
+    /// The new content as a tree, unless it was given as HTML.
+    pub fn dom(&self) -> Option<&Dom<S>> {
+        self.dom.as_ref()
+    }
+
+    /// The new content as HTML, serialized on the first call.
+    pub fn html(&self) -> &S {
+        self.html.get_or_init(|| {
+            self.dom
+                .as_ref()
+                .map(|dom| dom.to_html())
+                .unwrap_or_default()
+        })
+    }
+
+    pub fn into_html(self) -> S {
+        self.html().clone()
+    }
+}
+
+impl<S> PartialEq for ReplacementHtml<S>
+where
+    S: UnicodeString,
+{
+    fn eq(&self, other: &Self) -> bool {
+        self.html() == other.html()
+    }
+}
+
+impl<S> Eq for ReplacementHtml<S> where S: UnicodeString {}
+
+impl<S> PartialEq<&str> for ReplacementHtml<S>
+where
+    S: UnicodeString,
+{
+    fn eq(&self, other: &&str) -> bool {
+        self.html().to_string() == *other
+    }
+}
+
+impl<S> Debug for ReplacementHtml<S>
+where
+    S: UnicodeString,
+{
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        Debug::fmt(&self.html().to_string(), f)
+    }
+}
+
+impl<S> Display for ReplacementHtml<S>
+where
+    S: UnicodeString,
+{
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.html())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::tests::testutils_composer_model::cm;
+    use crate::tests::testutils_conversion::utf16;
+    use crate::TextUpdate;
+
+    #[test]
+    fn replacement_html_is_only_serialized_when_read() {
+        let mut model = cm("a|");
+        let update = model.replace_text(utf16("<b"));
+        let TextUpdate::ReplaceAll(replace_all) = update.text_update else {
+            panic!("Expected a ReplaceAll update");
+        };
+        let html = &replace_all.replacement_html;
+        assert!(html.html.get().is_none());
+        assert_eq!(html.dom().unwrap().text_len(), 3);
+        assert!(html.html.get().is_none());
+
+        assert_eq!(html.html().to_string(), "a&lt;b");
+        assert!(html.html.get().is_some());
+    }
+
+    #[test]
+    fn replacement_html_is_the_content_at_the_time_of_the_update() {
+        let mut model = cm("a|");
+        let update = model.replace_text(utf16("b"));
+        model.replace_text(utf16("c"));
+        let TextUpdate::ReplaceAll(replace_all) = update.text_update else {
+            panic!("Expected a ReplaceAll update");
+        };
+        assert_eq!(replace_all.replacement_html, "ab");
+    }
+}
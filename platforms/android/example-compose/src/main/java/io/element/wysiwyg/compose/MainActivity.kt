@@ -201,7 +201,9 @@ class MainActivity : ComponentActivity() {
                                         InlineFormat.InlineCode
                                     )
 
-                                    ComposerAction.LINK -> linkDialogAction = state.linkAction
+                                    ComposerAction.LINK,
+                                    ComposerAction.CREATE_LINK_WITH_TEXT,
+                                    ComposerAction.EDIT_LINK -> linkDialogAction = state.linkAction
 
                                     ComposerAction.UNDO -> state.undo()
                                     ComposerAction.REDO -> state.redo()
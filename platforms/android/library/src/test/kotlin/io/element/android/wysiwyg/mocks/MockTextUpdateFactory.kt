@@ -8,9 +8,13 @@ object MockTextUpdateFactory {
         html: String = "",
         start: Int = 0,
         end: Int = 0,
+        changedStart: Int = 0,
+        changedEnd: Int = 0,
     ) = TextUpdate.ReplaceAll(
         replacementHtml = html.toUShortList(),
         startUtf16Codeunit = start.toUInt(),
-        endUtf16Codeunit = end.toUInt()
+        endUtf16Codeunit = end.toUInt(),
+        changedStartUtf16Codeunit = changedStart.toUInt(),
+        changedEndUtf16Codeunit = changedEnd.toUInt()
     )
 }
@@ -67,7 +67,13 @@ internal class EditorViewModelTest {
 
         private val composerStateUpdate = MockComposerUpdateFactory.create(
             textUpdate = MockTextUpdateFactory.createReplaceAll(updatedParagraph, 2, 3),
-            menuState = MenuState.Update(actionStates = actionStates),
+            menuState = MenuState.Update(
+                actionStates = actionStates,
+                disabledReasons = emptyMap(),
+                undoDepth = 0u,
+                redoDepth = 0u,
+                quoteDepth = 0u,
+            ),
         )
         private val replaceTextResult = ComposerResult.ReplaceText(updatedParagraph, 2..3)
     }
@@ -297,7 +303,7 @@ internal class EditorViewModelTest {
         val name = "jonny"
         val url = "https://matrix.to/#/@test:matrix.org"
         val suggestionPattern =
-            SuggestionPattern(PatternKey.At, text = "jonny", 0.toUInt(), 5.toUInt())
+            SuggestionPattern(PatternKey.At, text = "jonny", 0.toUInt(), 5.toUInt(), 0.toULong())
         composer.givenReplaceTextResult(MockComposerUpdateFactory.create(
             menuAction = MenuAction.Suggestion(suggestionPattern)
         ))
@@ -315,7 +321,7 @@ internal class EditorViewModelTest {
     @Test
     fun `when process insert @room mention at suggestion action, it returns a text update`() {
         val suggestionPattern =
-            SuggestionPattern(PatternKey.At, text = "room", 0.toUInt(), 4.toUInt())
+            SuggestionPattern(PatternKey.At, text = "room", 0.toUInt(), 4.toUInt(), 0.toULong())
         composer.givenReplaceTextResult(MockComposerUpdateFactory.create(
             menuAction = MenuAction.Suggestion(suggestionPattern)
         ))
@@ -378,7 +378,7 @@ public extension WysiwygComposerViewModel {
         // this adjusts an iOS behaviour that extends a link when typing after it
         // which does not reflect the model state.
         switch update.menuState() {
-        case let .update(newState):
+        case let .update(newState, _, _, _, _):
             if newState[.link] != actionStates[.link] {
                 applyUpdate(update, skipTextViewUpdate: true)
                 applyAtributedContent()
@@ -563,7 +563,9 @@ private extension WysiwygComposerViewModel {
         switch update.textUpdate() {
         case let .replaceAll(replacementHtml: codeUnits,
                              startUtf16Codeunit: start,
-                             endUtf16Codeunit: end):
+                             endUtf16Codeunit: end,
+                             changedStartUtf16Codeunit: _,
+                             changedEndUtf16Codeunit: _):
             applyReplaceAll(codeUnits: codeUnits, start: start, end: end)
             // Note: this makes replaceAll act like .keep on cases where we expect the text
             // view to be properly updated by the system.
@@ -583,7 +585,7 @@ private extension WysiwygComposerViewModel {
         }
 
         switch update.menuState() {
-        case let .update(actionStates: actionStates):
+        case let .update(actionStates: actionStates, disabledReasons: _, undoDepth: _, redoDepth: _, quoteDepth: _):
             self.actionStates = actionStates
         default:
             break
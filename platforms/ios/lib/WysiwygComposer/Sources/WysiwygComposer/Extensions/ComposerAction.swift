@@ -19,7 +19,7 @@ extension ComposerAction {
     /// next character stroke when triggered on an empty selection.
     var requiresReapplyFormattingOnEmptySelection: Bool {
         switch self {
-        case .bold, .italic, .strikeThrough, .underline, .inlineCode, .link, .undo, .redo:
+        case .bold, .italic, .strikeThrough, .underline, .inlineCode, .link, .createLinkWithText, .editLink, .undo, .redo:
             return false
         case .orderedList, .unorderedList, .indent, .unindent, .codeBlock, .quote:
             return true
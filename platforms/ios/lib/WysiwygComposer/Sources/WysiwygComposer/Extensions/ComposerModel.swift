@@ -49,7 +49,7 @@ extension ComposerModel {
             update = try codeBlock()
         case .quote:
             update = try quote()
-        case .link:
+        case .link, .createLinkWithText, .editLink:
             fatalError()
         }
 
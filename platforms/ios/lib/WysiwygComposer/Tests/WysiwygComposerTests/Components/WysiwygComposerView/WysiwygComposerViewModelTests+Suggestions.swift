@@ -21,12 +21,12 @@ import XCTest
 extension WysiwygComposerViewModelTests {
     func testAtSuggestionsArePublished() {
         let expectation = expectSuggestionPattern(
-            expectedPattern: SuggestionPattern(key: .at, text: "ali", start: 0, end: 4)
+            expectedPattern: SuggestionPattern(key: .at, text: "ali", start: 0, end: 4, revision: 0)
         )
         _ = viewModel.replaceText(range: .zero, replacementText: "@ali")
         waitExpectation(expectation: expectation, timeout: 2.0)
         let expectation2 = expectSuggestionPattern(
-            expectedPattern: SuggestionPattern(key: .at, text: "alice", start: 0, end: 6)
+            expectedPattern: SuggestionPattern(key: .at, text: "alice", start: 0, end: 6, revision: 0)
         )
         _ = viewModel.replaceText(range: .init(location: 4, length: 0), replacementText: "ce")
         waitExpectation(expectation: expectation2, timeout: 2.0)
@@ -34,7 +34,7 @@ extension WysiwygComposerViewModelTests {
 
     func testHashSuggestionsArePublished() {
         let expectation = expectSuggestionPattern(
-            expectedPattern: SuggestionPattern(key: .hash, text: "room", start: 0, end: 5)
+            expectedPattern: SuggestionPattern(key: .hash, text: "room", start: 0, end: 5, revision: 0)
         )
         _ = viewModel.replaceText(range: .zero, replacementText: "#room")
         waitExpectation(expectation: expectation, timeout: 2.0)
@@ -42,7 +42,7 @@ extension WysiwygComposerViewModelTests {
 
     func testSlashSuggestionArePublished() {
         let expectation = expectSuggestionPattern(
-            expectedPattern: SuggestionPattern(key: .slash, text: "inv", start: 0, end: 4)
+            expectedPattern: SuggestionPattern(key: .slash, text: "inv", start: 0, end: 4, revision: 0)
         )
         _ = viewModel.replaceText(range: .zero, replacementText: "/inv")
         waitExpectation(expectation: expectation, timeout: 2.0)
@@ -182,6 +182,8 @@ private extension WysiwygComposerViewModelTests {
                                  description: String = "Await suggestion pattern") -> WysiwygTestExpectation {
         let expectSuggestionPattern = expectation(description: description)
         let cancellable = viewModel.$suggestionPattern
+            // Compare the patterns whatever update they were sent in.
+            .map { $0.map { SuggestionPattern(key: $0.key, text: $0.text, start: $0.start, end: $0.end, revision: 0) } }
             // Ignore on subscribe publish.
             .removeDuplicates()
             .dropFirst()